@@ -28,7 +28,7 @@ use ark_std::{
     start_timer,
     end_timer,
 };
-use num_traits::Zero;
+use num_traits::{One, Zero};
 use zeroize::Zeroize;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
@@ -106,6 +106,18 @@ pub trait PairingEngine: Sized + 'static + Copy + Debug + Sync + Send + Eq + Par
         Self::final_exponentiation(&Self::miller_loop(i)).unwrap()
     }
 
+    /// Whether the product of pairings of `pairs` is one, i.e., whether a pairing equation holds.
+    ///
+    /// An engine over secret-shared points can answer this without opening the points.
+    #[must_use]
+    fn product_of_pairings_is_one(pairs: &[(Self::G1Affine, Self::G2Prepared)]) -> bool {
+        let prepared: Vec<_> = pairs
+            .iter()
+            .map(|(p, q)| ((*p).into(), q.clone()))
+            .collect();
+        Self::product_of_pairings(&prepared).is_one()
+    }
+
     /// Performs multiple pairing operations
     #[must_use]
     fn pairing<G1, G2>(p: G1, q: G2) -> Self::Fqk
@@ -127,6 +127,26 @@ pub trait Field:
 {
     type BasePrimeField: PrimeField;
 
+    /// An unnormalized form of `Self`, for long runs of additions and of scalings by `Self`
+    /// (inner products, FFT butterflies); converting back to `Self` normalizes it.
+    ///
+    /// Fields of secret-shared values use this to add public constants to a share once per run,
+    /// rather than once per operation. For other fields, it is `Self`.
+    type Acc: Copy
+        + Send
+        + Sync
+        + Debug
+        + Zero
+        + Add<Output = Self::Acc>
+        + Sub<Output = Self::Acc>
+        + AddAssign
+        + SubAssign
+        + AddAssign<Self>
+        + MulAssign<Self>
+        + core::iter::Sum
+        + From<Self>
+        + Into<Self> = Self;
+
     /// Returns the characteristic of the field,
     /// in little-endian representation.
     fn characteristic<'a>() -> &'a [u64] {
@@ -225,6 +245,31 @@ pub trait Field:
         }
     }
 
+    /// Replace each non-zero `x` in `selfs` with `coeff / x`; see [batch_inversion_and_mul].
+    /// By Montgomery's trick, unless a field can invert many elements together more cheaply.
+    fn batch_inversion_and_mul_in_place(selfs: &mut [Self], coeff: &Self) {
+        montgomery_batch_inversion_and_mul(selfs, coeff);
+    }
+
+    /// The inner product of `xs` and `ys`, which must have the same length.
+    fn dot(xs: &[Self], ys: &[Self]) -> Self {
+        assert_eq!(xs.len(), ys.len());
+        xs.iter().zip(ys).map(|(x, y)| *x * y).sum()
+    }
+
+    /// The product of a sparse matrix and `z`. Each row lists its non-zero entries as
+    /// `(coefficient, column)`.
+    fn sparse_mat_vec(rows: &[Vec<(Self, usize)>], z: &[Self]) -> Vec<Self> {
+        rows.iter()
+            .map(|row| row.iter().map(|(c, i)| *c * z[*i]).sum())
+            .collect()
+    }
+
+    /// Each of `selfs` to the power `exp` (little-endian limbs, as for [Field::pow]).
+    fn batch_pow(selfs: &[Self], exp: &[u64]) -> Vec<Self> {
+        selfs.iter().map(|x| x.pow(exp)).collect()
+    }
+
     /// Replace `[x1, x2, ... , xn]` with `[x1, x1*x2, ... , x1*x2*...*xn]`
     fn partial_products_in_place(selfs: &mut [Self]) {
         for i in 1..selfs.len() {
@@ -617,15 +662,60 @@ pub fn batch_inversion<F: Field>(v: &mut [F]) {
     batch_inversion_and_mul(v, &F::one());
 }
 
-#[cfg(not(feature = "parallel"))]
 // Given a vector of field elements {v_i}, compute the vector {coeff * v_i^(-1)}
 pub fn batch_inversion_and_mul<F: Field>(v: &mut [F], coeff: &F) {
+    F::batch_inversion_and_mul_in_place(v, coeff);
+}
+
+/// Inversions requested one at a time (in a loop, or across the steps of a phase), and computed
+/// together, by [Field::batch_inversion_and_mul_in_place]. For secret-shared elements, that is
+/// one masked opening for all of them, rather than one for each.
+#[derive(Clone, Debug, Default)]
+pub struct InversionQueue<F: Field> {
+    values: Vec<F>,
+}
+
+impl<F: Field> InversionQueue<F> {
+    pub fn new() -> Self {
+        Self { values: Vec::new() }
+    }
+
+    /// Queue `x`; its inverse is at the index returned, in what [InversionQueue::invert] returns.
+    pub fn push(&mut self, x: F) -> usize {
+        self.values.push(x);
+        self.values.len() - 1
+    }
+
+    /// Queue each of `xs`; their inverses are at the indices returned.
+    pub fn extend(&mut self, xs: impl IntoIterator<Item = F>) -> core::ops::Range<usize> {
+        let start = self.values.len();
+        self.values.extend(xs);
+        start..self.values.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The inverses of everything queued, in order; zeros stay zero.
+    pub fn invert(self) -> Vec<F> {
+        let mut values = self.values;
+        batch_inversion(&mut values);
+        values
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+fn montgomery_batch_inversion_and_mul<F: Field>(v: &mut [F], coeff: &F) {
     serial_batch_inversion_and_mul(v, coeff);
 }
 
 #[cfg(feature = "parallel")]
-// Given a vector of field elements {v_i}, compute the vector {coeff * v_i^(-1)}
-pub fn batch_inversion_and_mul<F: Field>(v: &mut [F], coeff: &F) {
+fn montgomery_batch_inversion_and_mul<F: Field>(v: &mut [F], coeff: &F) {
     // Divide the vector v evenly between all available cores
     let min_elements_per_thread = 1;
     let num_cpus_available = rayon::current_num_threads();
@@ -724,6 +814,16 @@ mod no_std_tests {
                 rand_multiplier
             );
         }
+
+        let mut queue = InversionQueue::new();
+        let first = queue.push(random_coeffs[0]);
+        let zero = queue.push(Fr::zero());
+        let rest = queue.extend(random_coeffs[1..].iter().cloned());
+        assert_eq!(queue.len(), vec_size + 2);
+        let inverses = queue.invert();
+        assert_eq!(inverses[first], random_coeffs_inv[0]);
+        assert!(inverses[zero].is_zero());
+        assert_eq!(&inverses[rest], &random_coeffs_inv[1..]);
     }
 
     #[test]
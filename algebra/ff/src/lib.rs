@@ -3,6 +3,7 @@
 #![allow(clippy::op_ref, clippy::suspicious_op_assign_impl)]
 #![cfg_attr(not(feature = "asm"), forbid(unsafe_code))]
 #![cfg_attr(use_asm, feature(llvm_asm))]
+#![feature(associated_type_defaults)]
 #![cfg_attr(feature = "asm", deny(unsafe_code))]
 
 #[macro_use]
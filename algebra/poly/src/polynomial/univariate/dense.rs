@@ -356,7 +356,7 @@ impl<'a, 'b, F: Field> AddAssign<(F, &'a DensePolynomial<F>)> for DensePolynomia
             // Add the necessary number of zero coefficients.
             self.coeffs.resize(other.coeffs.len(), F::zero());
         }
-        if other.is_shared() && f.is_shared() {
+        if f.is_shared() && other.is_shared() {
             let mut fs = vec![f; other.len()];
             F::batch_product_in_place(&mut fs[..], &other.coeffs[..]);
             self.coeffs
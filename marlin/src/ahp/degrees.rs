@@ -0,0 +1,73 @@
+//! Degree bounds that the prover claims for its polynomials, checked in debug builds, and
+//! whenever they are [track]ed.
+//!
+//! Over shares, [Polynomial::degree] only counts coefficients, since it cannot tell a shared
+//! zero from any other share. So when the MPC prover makes a polynomial of too high a degree, no
+//! assertion fires, and the bug only surfaces as a proof that does not verify. Instead, the
+//! prover [claim]s a bound for each polynomial it sends. Public polynomials are checked on the
+//! spot. Shared ones are kept, if [track] was called, and [check_tracked] reveals them and
+//! checks them all, for tests to call after proving.
+use crate::{String, Vec};
+use ark_ff::Field;
+use ark_poly::univariate::DensePolynomial;
+use ark_std::{boxed::Box, format};
+use mpc_trait::MpcWire;
+
+use std::cell::RefCell;
+
+type Claim = Box<dyn FnOnce() -> Result<(), String>>;
+
+thread_local! {
+    /// Claims about shared polynomials, if they are being tracked.
+    static TRACKED: RefCell<Option<Vec<Claim>>> = RefCell::new(None);
+}
+
+/// Keep the claims about shared polynomials from now on (in release builds too), for
+/// [check_tracked].
+pub fn track() {
+    TRACKED.with(|t| *t.borrow_mut() = Some(Vec::new()));
+}
+
+/// Reveal the shared polynomials claimed about since [track], and check their degrees. Returns
+/// how many were checked, or the first that exceeds its bound. Afterwards, claims are not kept.
+///
+/// All parties must call this together, as it opens every polynomial.
+pub fn check_tracked() -> Result<usize, String> {
+    let claims = TRACKED.with(|t| t.borrow_mut().take()).unwrap_or_default();
+    let n = claims.len();
+    for claim in claims {
+        claim()?;
+    }
+    Ok(n)
+}
+
+/// Claim that `p`, called `label`, has degree at most `bound`.
+pub(crate) fn claim<F: Field>(label: &str, p: &DensePolynomial<F>, bound: usize) {
+    let tracking = TRACKED.with(|t| t.borrow().is_some());
+    if !tracking && !cfg!(debug_assertions) {
+        return;
+    }
+    if !p.is_shared() {
+        check(label, p.clone(), bound).unwrap();
+        return;
+    }
+    TRACKED.with(|t| {
+        if let Some(claims) = t.borrow_mut().as_mut() {
+            let (label, p) = (String::from(label), p.clone());
+            claims.push(Box::new(move || check(&label, p, bound)));
+        }
+    });
+}
+
+fn check<F: Field>(label: &str, mut p: DensePolynomial<F>, bound: usize) -> Result<(), String> {
+    if p.is_shared() {
+        p.publicize();
+    }
+    match p.coeffs.iter().rposition(|c| !c.is_zero()) {
+        Some(degree) if degree > bound => Err(format!(
+            "{} has degree {}, but claims at most {}",
+            label, degree, bound
+        )),
+        _ => Ok(()),
+    }
+}
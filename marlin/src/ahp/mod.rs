@@ -10,6 +10,8 @@ use ark_std::{borrow::Borrow, cfg_iter_mut, format, marker::PhantomData, vec};
 use rayon::prelude::*;
 
 pub mod constraint_systems;
+/// Degree bounds that the AHP prover claims, checked in debug builds.
+pub mod degrees;
 /// Describes data structures and the algorithms used by the AHP indexer.
 pub mod indexer;
 /// Describes data structures and the algorithms used by the AHP prover.
@@ -148,8 +150,8 @@ impl<F: PrimeField> AHPForR1CS<F> {
         let t = LinearCombination::new("t", vec![(F::one(), "t")]);
 
         let r_alpha_at_beta = domain_h.eval_unnormalized_bivariate_lagrange_poly(alpha, beta);
-        let v_H_at_alpha = domain_h.evaluate_vanishing_polynomial(alpha);
-        let v_H_at_beta = domain_h.evaluate_vanishing_polynomial(beta);
+        let v_H = batch_evaluate_vanishing_polynomial(&domain_h, &[alpha, beta]);
+        let (v_H_at_alpha, v_H_at_beta) = (v_H[0], v_H[1]);
         let v_X_at_beta = x_domain.evaluate_vanishing_polynomial(beta);
 
         let z_b_at_beta = evals.get_lc_eval(&z_b, beta)?;
@@ -179,11 +181,10 @@ impl<F: PrimeField> AHPForR1CS<F> {
                 (-beta * g_1_at_beta, LCTerm::One),
             ],
         );
-        #[cfg(debug_assertions)]
-        {
+        if mpc_net::self_checks() {
           let mut e = evals.get_lc_eval(&outer_sumcheck, beta)?;
           e.publicize();
-          debug_assert!(e.is_zero(), "Evaluation of lc is\n{}\n, not zero", e);
+          assert!(e.is_zero(), "Evaluation of lc is\n{}\n, not zero", e);
         }
 
         linear_combinations.push(z_b);
@@ -250,11 +251,11 @@ impl<F: PrimeField> AHPForR1CS<F> {
 
         a.label = "inner_sumcheck".into();
         let inner_sumcheck = a;
-        debug_assert!({
+        if mpc_net::self_checks() {
           let mut e = evals.get_lc_eval(&inner_sumcheck, gamma)?;
           e.publicize();
-          e.is_zero()
-        });
+          assert!(e.is_zero());
+        }
 
         linear_combinations.push(g_2);
         linear_combinations.push(a_denom);
@@ -333,6 +334,18 @@ impl From<SynthesisError> for Error {
     }
 }
 
+/// The vanishing polynomial of `domain` at each of `points`, which are raised to its size
+/// together (see [Field::batch_pow]), saving rounds when they are shared.
+pub(crate) fn batch_evaluate_vanishing_polynomial<F: PrimeField>(
+    domain: &GeneralEvaluationDomain<F>,
+    points: &[F],
+) -> Vec<F> {
+    F::batch_pow(points, &[domain.size() as u64])
+        .into_iter()
+        .map(|x| x - F::one())
+        .collect()
+}
+
 /// The derivative of the vanishing polynomial
 pub trait UnnormalizedBivariateLagrangePoly<F: ark_ff::FftField> {
     /// Evaluate the polynomial
@@ -6,6 +6,7 @@ use crate::ahp::*;
 
 use crate::ahp::constraint_systems::{
     make_matrices_square_for_prover, pad_input_for_indexer_and_prover, unformat_public_input,
+    MatrixArithmetization,
 };
 use crate::{ToString, Vec};
 use ark_ff::{Field, PrimeField};
@@ -256,26 +257,19 @@ impl<F: PrimeField> AHPForR1CS<F> {
         }
 
         // Perform matrix multiplications
-        let inner_prod_fn = |row: &[(F, usize)]| {
-            let mut acc = F::zero();
-            for &(ref coeff, i) in row {
-                let tmp = if i < num_input_variables {
-                    formatted_input_assignment[i]
-                } else {
-                    witness_assignment[i - num_input_variables]
-                };
-
-                acc += &(if coeff.is_one() { tmp } else { tmp * coeff });
-            }
-            acc
-        };
+        // Via `Field::sparse_mat_vec`, which MPC fields evaluate locally for public matrices.
+        let z: Vec<F> = formatted_input_assignment
+            .iter()
+            .chain(&witness_assignment)
+            .cloned()
+            .collect();
 
         let eval_z_a_time = start_timer!(|| "Evaluating z_A");
-        let z_a = index.a.iter().map(|row| inner_prod_fn(row)).collect();
+        let z_a = F::sparse_mat_vec(&index.a, &z);
         end_timer!(eval_z_a_time);
 
         let eval_z_b_time = start_timer!(|| "Evaluating z_B");
-        let z_b = index.b.iter().map(|row| inner_prod_fn(row)).collect();
+        let z_b = F::sparse_mat_vec(&index.b, &z);
         end_timer!(eval_z_b_time);
 
         let zk_bound = 1; // One query is sufficient for our desired soundness
@@ -378,10 +372,11 @@ impl<F: PrimeField> AHPForR1CS<F> {
 
         let msg = ProverMsg::EmptyMessage;
 
-        //assert!(w_poly.degree() < domain_h.size() - domain_x.size() + zk_bound);
-        //assert!(z_a_poly.degree() < domain_h.size() + zk_bound);
-        //assert!(z_b_poly.degree() < domain_h.size() + zk_bound);
-        //assert!(mask_poly.degree() <= 3 * domain_h.size() + 2 * zk_bound - 3);
+        let w_bound = domain_h.size() - domain_x.size() + zk_bound - 1;
+        degrees::claim("w", &w_poly, w_bound);
+        degrees::claim("z_a", &z_a_poly, domain_h.size() + zk_bound - 1);
+        degrees::claim("z_b", &z_b_poly, domain_h.size() + zk_bound - 1);
+        degrees::claim("mask_poly", &mask_poly, mask_poly_degree);
 
         let w = LabeledPolynomial::new("w".to_string(), w_poly, None, Some(1));
         let z_a = LabeledPolynomial::new("z_a".to_string(), z_a_poly, None, Some(1));
@@ -553,6 +548,9 @@ impl<F: PrimeField> AHPForR1CS<F> {
 
         assert!(g_1.degree() <= domain_h.size() - 2);
         assert!(h_1.degree() <= 2 * domain_h.size() + 2 * zk_bound - 2);
+        degrees::claim("t", &t_poly, domain_h.size() - 1);
+        degrees::claim("g_1", &g_1, domain_h.size() - 2);
+        degrees::claim("h_1", &h_1, 2 * domain_h.size() + 2 * zk_bound - 2);
 
         let oracles = ProverSecondOracles {
             t: LabeledPolynomial::new("t".into(), t_poly, None, None),
@@ -609,8 +607,8 @@ impl<F: PrimeField> AHPForR1CS<F> {
 
         let beta = ver_message.beta;
 
-        let v_H_at_alpha = domain_h.evaluate_vanishing_polynomial(alpha);
-        let v_H_at_beta = domain_h.evaluate_vanishing_polynomial(beta);
+        let v_H = batch_evaluate_vanishing_polynomial(&domain_h, &[alpha, beta]);
+        let (v_H_at_alpha, v_H_at_beta) = (v_H[0], v_H[1]);
 
         let (a_star, b_star, c_star) = (
             &index.a_star_arith,
@@ -620,18 +618,18 @@ impl<F: PrimeField> AHPForR1CS<F> {
 
         let f_evals_time = start_timer!(|| "Computing f evals on K");
         let mut f_vals_on_K = Vec::with_capacity(domain_k.size());
-        let mut inverses_a = Vec::with_capacity(domain_k.size());
-        let mut inverses_b = Vec::with_capacity(domain_k.size());
-        let mut inverses_c = Vec::with_capacity(domain_k.size());
-
-        for i in 0..domain_k.size() {
-            inverses_a.push((beta - a_star.evals_on_K.row[i]) * (alpha - a_star.evals_on_K.col[i]));
-            inverses_b.push((beta - b_star.evals_on_K.row[i]) * (alpha - b_star.evals_on_K.col[i]));
-            inverses_c.push((beta - c_star.evals_on_K.row[i]) * (alpha - c_star.evals_on_K.col[i]));
-        }
-        ark_ff::batch_inversion(&mut inverses_a);
-        ark_ff::batch_inversion(&mut inverses_b);
-        ark_ff::batch_inversion(&mut inverses_c);
+        // All three matrices' denominators are inverted together (over shares, in one batch).
+        let denominators = |m: &'a MatrixArithmetization<F>| {
+            (0..domain_k.size())
+                .map(move |i| (beta - m.evals_on_K.row[i]) * (alpha - m.evals_on_K.col[i]))
+        };
+        let mut queue = ark_ff::InversionQueue::new();
+        let range_a = queue.extend(denominators(a_star));
+        let range_b = queue.extend(denominators(b_star));
+        let range_c = queue.extend(denominators(c_star));
+        let inverses = queue.invert();
+        let (inverses_a, inverses_b, inverses_c) =
+            (&inverses[range_a], &inverses[range_b], &inverses[range_c]);
 
         for i in 0..domain_k.size() {
             let t = eta_a * a_star.evals_on_K.val[i] * inverses_a[i]
@@ -709,6 +707,8 @@ impl<F: PrimeField> AHPForR1CS<F> {
         let msg = ProverMsg::EmptyMessage;
 
         assert!(g_2.degree() <= domain_k.size() - 2);
+        degrees::claim("g_2", &g_2, domain_k.size() - 2);
+        degrees::claim("h_2", &h_2, 3 * domain_k.size() - 3);
         let oracles = ProverThirdOracles {
             g_2: LabeledPolynomial::new("g_2".to_string(), g_2, Some(domain_k.size() - 2), None),
             h_2: LabeledPolynomial::new("h_2".to_string(), h_2, None, None),
@@ -1,6 +1,6 @@
 use crate::ahp::indexer::*;
 use crate::ahp::prover::ProverMsg;
-use crate::Vec;
+use crate::{String, Vec};
 use ark_ff::PrimeField;
 use ark_poly::univariate::DensePolynomial;
 use ark_poly_commit::{BatchLCProof, PolynomialCommitment};
@@ -125,6 +125,26 @@ impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>> Proof<F, PC
         }
     }
 
+    /// The serialized size in bytes of each component of the proof, in the order they are
+    /// serialized: each round's commitments, the evaluations, the prover's messages, and the
+    /// polynomial commitment's batch proof and evaluations.
+    ///
+    /// These depend only on the index, so proofs from the same index have the same sizes, however
+    /// they were produced.
+    pub fn component_sizes(&self) -> Vec<(String, usize)> {
+        let mut sizes: Vec<(String, usize)> = self
+            .commitments
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (format!("round {} commitments", i + 1), c.serialized_size()))
+            .collect();
+        sizes.push(("evaluations".into(), self.evaluations.serialized_size()));
+        sizes.push(("prover messages".into(), self.prover_messages.serialized_size()));
+        sizes.push(("pc proof".into(), self.pc_proof.proof.serialized_size()));
+        sizes.push(("pc evaluations".into(), self.pc_proof.evals.serialized_size()));
+        sizes
+    }
+
     /// Prints information about the size of the proof.
     pub fn print_size_info(&self) {
         use ark_poly_commit::{PCCommitment, PCProof};
@@ -25,7 +25,6 @@ use ark_poly_commit::{LabeledCommitment, PCUniversalParams, PolynomialCommitment
 use ark_relations::r1cs::ConstraintSynthesizer;
 use ark_std::rand::RngCore;
 use mpc_trait::MpcWire;
-use digest::Digest;
 
 use ark_std::{
     collections::BTreeMap,
@@ -45,7 +44,11 @@ macro_rules! eprintln {
 /// Implements a Fiat-Shamir based Rng that allows one to incrementally update
 /// the seed based on new messages in the proof transcript.
 pub mod rng;
-use rng::FiatShamirRng;
+use rng::{FiatShamirHash, FiatShamirTranscript};
+
+/// A Poseidon sponge, usable as the Fiat-Shamir hash in place of a byte-oriented one.
+pub mod sponge;
+pub use sponge::PoseidonSponge;
 
 mod error;
 pub use error::*;
@@ -64,13 +67,19 @@ pub mod reveal;
 mod test;
 
 /// The compiled argument system.
-pub struct Marlin<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: Digest>(
+pub struct Marlin<
+    F: PrimeField,
+    PC: PolynomialCommitment<F, DensePolynomial<F>>,
+    D: FiatShamirHash,
+>(
     #[doc(hidden)] PhantomData<F>,
     #[doc(hidden)] PhantomData<PC>,
     #[doc(hidden)] PhantomData<D>,
 );
 
-impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: Digest> Marlin<F, PC, D> {
+impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: FiatShamirHash>
+    Marlin<F, PC, D>
+{
     /// The personalization string for this protocol. Used to personalize the
     /// Fiat-Shamir rng.
     pub const PROTOCOL_NAME: &'static [u8] = b"MARLIN-2019";
@@ -161,7 +170,7 @@ impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: Digest>
         let prover_init_state = AHPForR1CS::prover_init(&index_pk.index, c)?;
         let public_input = prover_init_state.public_input();
         println!("{:?}", public_input);
-        let mut fs_rng = FiatShamirRng::<D>::from_seed(
+        let mut fs_rng = D::Transcript::from_seed(
             &to_bytes![&Self::PROTOCOL_NAME, &index_pk.index_vk, &public_input].unwrap(),
         );
 
@@ -348,7 +357,7 @@ impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: Digest>
             unpadded_input
         };
 
-        let mut fs_rng = FiatShamirRng::<D>::from_seed(
+        let mut fs_rng = D::Transcript::from_seed(
             &to_bytes![&Self::PROTOCOL_NAME, &index_vk, &public_input].unwrap(),
         );
 
@@ -5,9 +5,11 @@
 use ark_ff::Field;
 use ark_ec::PairingEngine;
 use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::ipa_pc::InnerProductArgPC;
 use ark_poly_commit::marlin_pc::MarlinKZG10;
 use ark_poly_commit::reveal as pc_reveal;
 use blake2::Blake2s;
+use digest::Digest;
 use mpc_algebra::*;
 use Marlin;
 
@@ -264,3 +266,64 @@ impl<E: PairingEngine, S: PairingShare<E>> Reveal
     >;
     struct_reveal_simp_impl!(IndexProverKey; index_vk, index_comm_rands, index, committer_key);
 }
+
+type MpcIpaPC<E, S, D> = InnerProductArgPC<
+    MpcG1Affine<E, S>,
+    D,
+    DensePolynomial<<MpcPairingEngine<E, S> as PairingEngine>::Fr>,
+>;
+type IpaPC<E, D> =
+    InnerProductArgPC<<E as PairingEngine>::G1Affine, D, DensePolynomial<<E as PairingEngine>::Fr>>;
+
+impl<E: PairingEngine, S: PairingShare<E>, D: Digest> Reveal
+    for Proof<<MpcPairingEngine<E, S> as PairingEngine>::Fr, MpcIpaPC<E, S, D>>
+{
+    type Base = Proof<<E as PairingEngine>::Fr, IpaPC<E, D>>;
+    struct_reveal_simp_impl!(Proof; commitments, evaluations, prover_messages, pc_proof);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>, D: Digest> Reveal
+    for IndexVerifierKey<<MpcPairingEngine<E, S> as PairingEngine>::Fr, MpcIpaPC<E, S, D>>
+{
+    type Base = IndexVerifierKey<<E as PairingEngine>::Fr, IpaPC<E, D>>;
+    struct_reveal_simp_impl!(IndexVerifierKey; index_comms, verifier_key, index_info);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>, D: Digest> Reveal
+    for IndexProverKey<<MpcPairingEngine<E, S> as PairingEngine>::Fr, MpcIpaPC<E, S, D>>
+{
+    type Base = IndexProverKey<<E as PairingEngine>::Fr, IpaPC<E, D>>;
+    struct_reveal_simp_impl!(IndexProverKey; index_vk, index_comm_rands, index, committer_key);
+}
+
+impl<F: PrimeField, PC: PolynomialCommitment<F, DensePolynomial<F>>, D: FiatShamirHash>
+    Marlin<F, PC, D>
+where
+    IndexVerifierKey<F, PC>: Reveal,
+    <IndexVerifierKey<F, PC> as Reveal>::Base: Clone,
+{
+    /// Index a circuit over MPC types whose description is itself shared: its constraints'
+    /// coefficients may be shared field elements, though which variables each constraint touches
+    /// is public. When the circuit is public, indexing it locally and lifting the keys with
+    /// [Reveal::from_public] is faster, and gives the same keys.
+    ///
+    /// The indexer's arithmetic is linear in the coefficients, so it runs locally at each party,
+    /// but the prover then multiplies shared coefficients by the shared witness. The verifier key
+    /// (the index commitments, which the prover hashes and every verifier needs) is revealed, and
+    /// returned as a local one. Those commitments do not hide the circuit: they let anyone check a
+    /// guess of it.
+    pub fn index_shared<C: ConstraintSynthesizer<F>>(
+        srs: &UniversalSRS<F, PC>,
+        c: C,
+    ) -> Result<
+        (IndexProverKey<F, PC>, <IndexVerifierKey<F, PC> as Reveal>::Base),
+        Error<PC::Error>,
+    > {
+        let (mut index_pk, index_vk) = Self::index(srs, c)?;
+        let reveal_time = start_timer!(|| "Reveal index commitments");
+        let index_vk = index_vk.reveal();
+        index_pk.index_vk = Reveal::from_public(index_vk.clone());
+        end_timer!(reveal_time);
+        Ok((index_pk, index_vk))
+    }
+}
@@ -5,6 +5,27 @@ use ark_std::rand::{RngCore, SeedableRng};
 use digest::{generic_array::GenericArray, Digest};
 use rand_chacha::ChaChaRng;
 
+/// The Fiat-Shamir transform's transcript: it absorbs the prover's messages and, as an `RngCore`,
+/// squeezes out the verifier's challenges.
+pub trait FiatShamirTranscript: RngCore {
+    /// A transcript that starts with `seed`.
+    fn from_seed<'a, T: 'a + ToBytes>(seed: &'a T) -> Self;
+
+    /// Add `msg` to the transcript: every challenge after depends on it.
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, msg: &'a T);
+}
+
+/// What Marlin is parametrized by to pick its transcript: any [Digest], which drives a
+/// [FiatShamirRng], or a sponge, which is its own transcript.
+pub trait FiatShamirHash {
+    /// The transcript.
+    type Transcript: FiatShamirTranscript;
+}
+
+impl<D: Digest> FiatShamirHash for D {
+    type Transcript = FiatShamirRng<D>;
+}
+
 /// A `SeedableRng` that refreshes its seed by hashing together the previous seed
 /// and the new seed material.
 // TODO: later: re-evaluate decision about ChaChaRng
@@ -66,3 +87,13 @@ impl<D: Digest> FiatShamirRng<D> {
         self.r = ChaChaRng::from_seed(seed);
     }
 }
+
+impl<D: Digest> FiatShamirTranscript for FiatShamirRng<D> {
+    fn from_seed<'a, T: 'a + ToBytes>(seed: &'a T) -> Self {
+        Self::from_seed(seed)
+    }
+
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, msg: &'a T) {
+        self.absorb(msg)
+    }
+}
@@ -0,0 +1,382 @@
+use crate::rng::{FiatShamirHash, FiatShamirTranscript};
+use crate::Vec;
+use ark_ff::{BigInteger, FpParameters, PrimeField, ToBytes};
+use ark_std::rand::RngCore;
+
+/// Field elements in the sponge's state.
+const WIDTH: usize = 3;
+/// Field elements absorbed or squeezed per permutation.
+const RATE: usize = 2;
+
+/// The vetted instances of width 3, at 128 bits of security, as `(alpha, full rounds, partial
+/// rounds)`: `x^5` is the Poseidon paper's (and circomlib's) over ~255-bit fields, and `x^17` is
+/// arkworks' default for rate 2, which fields where `x^5` is no permutation (such as BLS12-377's)
+/// use.
+const INSTANCES: [(u64, usize, usize); 2] = [(5, 8, 57), (17, 8, 31)];
+
+/// The constants of a Poseidon permutation over `F`.
+///
+/// The round constants and the MDS matrix are those the reference implementation's Grain LFSR
+/// derives for the instance, so they agree with other implementations of it.
+#[derive(Clone, Debug)]
+pub struct PoseidonParameters<F: PrimeField> {
+    /// The S-box is `x -> x^alpha`.
+    pub alpha: u64,
+    /// Rounds with an S-box on every element, half before the partial rounds and half after.
+    pub full_rounds: usize,
+    /// Rounds with an S-box on the first element only.
+    pub partial_rounds: usize,
+    /// `WIDTH` constants per round.
+    pub round_constants: Vec<F>,
+    /// The MDS matrix of the linear layer.
+    pub mds: Vec<Vec<F>>,
+}
+
+impl<F: PrimeField> PoseidonParameters<F> {
+    /// The parameters for `F`, of the first vetted instance whose S-box permutes it.
+    pub fn new() -> Self {
+        // x^alpha permutes F iff gcd(alpha, p - 1) = 1.
+        let mut p_minus_one = F::Params::MODULUS;
+        p_minus_one.sub_noborrow(&F::BigInt::from(1));
+        let (alpha, full_rounds, partial_rounds) = INSTANCES
+            .iter()
+            .cloned()
+            .find(|&(a, _, _)| !divides(a, &p_minus_one))
+            .expect("no vetted Poseidon instance for this field");
+
+        let mut lfsr = GrainLfsr::new(F::size_in_bits(), full_rounds, partial_rounds);
+        let round_constants = (0..(full_rounds + partial_rounds) * WIDTH)
+            .map(|_| lfsr.field_element_below_modulus())
+            .collect();
+        // A Cauchy matrix 1 / (x_i + y_j) is MDS.
+        let xs: Vec<F> = (0..WIDTH).map(|_| lfsr.field_element_mod_order()).collect();
+        let ys: Vec<F> = (0..WIDTH).map(|_| lfsr.field_element_mod_order()).collect();
+        let mds = xs
+            .iter()
+            .map(|x| {
+                ys.iter()
+                    .map(|y| (*x + y).inverse().expect("degenerate MDS matrix"))
+                    .collect()
+            })
+            .collect();
+        Self {
+            alpha,
+            full_rounds,
+            partial_rounds,
+            round_constants,
+            mds,
+        }
+    }
+
+    /// Apply the permutation to `state`.
+    pub fn permute(&self, state: &mut [F; WIDTH]) {
+        let half = self.full_rounds / 2;
+        for (r, constants) in self.round_constants.chunks(WIDTH).enumerate() {
+            for (s, c) in state.iter_mut().zip(constants) {
+                *s += c;
+            }
+            if r < half || r >= half + self.partial_rounds {
+                for s in state.iter_mut() {
+                    *s = s.pow([self.alpha]);
+                }
+            } else {
+                state[0] = state[0].pow([self.alpha]);
+            }
+            let old = *state;
+            for (s, row) in state.iter_mut().zip(&self.mds) {
+                *s = row.iter().zip(&old).map(|(m, x)| *m * x).sum();
+            }
+        }
+    }
+}
+
+impl<F: PrimeField> Default for PoseidonParameters<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the small integer `a` divides the big integer `n`.
+fn divides<B: BigInteger>(a: u64, n: &B) -> bool {
+    // Horner's rule on the limbs, most significant first.
+    let rem = n
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |rem, limb| ((rem << 64) | *limb as u128) % a as u128);
+    rem == 0
+}
+
+/// The self-shrinking Grain LFSR from which the Poseidon reference implementation
+/// (`generate_parameters_grain.sage`) draws an instance's constants.
+struct GrainLfsr {
+    bits: [bool; 80],
+    /// The position of the oldest bit in `bits`.
+    head: usize,
+    field_bits: usize,
+}
+
+impl GrainLfsr {
+    fn new(field_bits: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let mut bits = [true; 80];
+        let mut at = 0;
+        let mut push = |value: usize, width: usize| {
+            for i in (0..width).rev() {
+                bits[at] = (value >> i) & 1 == 1;
+                at += 1;
+            }
+        };
+        // A prime field, an x^alpha S-box, the field size, the width, and the rounds; then ones.
+        push(1, 2);
+        push(0, 4);
+        push(field_bits, 12);
+        push(WIDTH, 12);
+        push(full_rounds, 10);
+        push(partial_rounds, 10);
+        let mut lfsr = Self {
+            bits,
+            head: 0,
+            field_bits,
+        };
+        for _ in 0..160 {
+            lfsr.clock();
+        }
+        lfsr
+    }
+
+    fn clock(&mut self) -> bool {
+        let tap = |i: usize| self.bits[(self.head + i) % 80];
+        let bit = tap(62) ^ tap(51) ^ tap(38) ^ tap(23) ^ tap(13) ^ tap(0);
+        self.bits[self.head] = bit;
+        self.head = (self.head + 1) % 80;
+        bit
+    }
+
+    /// The next output bit: of each pair of clocked bits, the second if the first is set.
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.clock();
+            let bit = self.clock();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    /// The next `field_bits` bits, most significant first.
+    fn next_bits(&mut self) -> Vec<bool> {
+        (0..self.field_bits).map(|_| self.next_bit()).collect()
+    }
+
+    /// A round constant: the first draw below the modulus.
+    fn field_element_below_modulus<F: PrimeField>(&mut self) -> F {
+        loop {
+            if let Some(x) = F::from_repr(F::BigInt::from_bits_be(&self.next_bits())) {
+                return x;
+            }
+        }
+    }
+
+    /// An MDS matrix seed: a draw, reduced modulo the modulus.
+    fn field_element_mod_order<F: PrimeField>(&mut self) -> F {
+        let mut repr = F::BigInt::from_bits_be(&self.next_bits());
+        // A draw is below 2^field_bits, so below twice the modulus.
+        if F::from_repr(repr).is_none() {
+            repr.sub_noborrow(&F::Params::MODULUS);
+        }
+        F::from_repr(repr).unwrap()
+    }
+}
+
+/// A Poseidon duplex sponge over `F`, as Marlin's Fiat-Shamir transcript (it is the
+/// [FiatShamirHash] whose transcript is itself).
+///
+/// Messages are packed into field elements, prefixed by their length in bytes, and absorbed into
+/// the sponge's state; challenges are squeezed out of the state. Hashing the transcript in a
+/// prime field makes it cheap to check in a circuit over that field, e.g., to verify a proof
+/// recursively.
+#[derive(Clone)]
+pub struct PoseidonSponge<F: PrimeField> {
+    params: PoseidonParameters<F>,
+    state: [F; WIDTH],
+    /// The next rate element to absorb into, or squeeze.
+    position: usize,
+    squeezing: bool,
+    /// Squeezed bytes not yet handed out.
+    pending: Vec<u8>,
+}
+
+impl<F: PrimeField> PoseidonSponge<F> {
+    /// Whole bytes that fit in a field element below the modulus.
+    fn bytes_per_element() -> usize {
+        (F::size_in_bits() - 1) / 8
+    }
+
+    /// Absorb `elements` into the rate part of the state.
+    pub fn absorb_elements(&mut self, elements: &[F]) {
+        if self.squeezing {
+            self.squeezing = false;
+            self.position = 0;
+        }
+        for x in elements {
+            if self.position == RATE {
+                self.params.permute(&mut self.state);
+                self.position = 0;
+            }
+            self.state[WIDTH - RATE + self.position] += x;
+            self.position += 1;
+        }
+    }
+
+    /// Squeeze `n` field elements out of the state.
+    pub fn squeeze_elements(&mut self, n: usize) -> Vec<F> {
+        if !self.squeezing {
+            self.squeezing = true;
+            self.position = RATE;
+        }
+        (0..n)
+            .map(|_| {
+                if self.position == RATE {
+                    self.params.permute(&mut self.state);
+                    self.position = 0;
+                }
+                self.position += 1;
+                self.state[WIDTH - RATE + self.position - 1]
+            })
+            .collect()
+    }
+
+    fn absorb_bytes(&mut self, bytes: &[u8]) {
+        let mut elements = vec![F::from(bytes.len() as u64)];
+        elements.extend(
+            bytes
+                .chunks(Self::bytes_per_element())
+                .map(F::from_le_bytes_mod_order),
+        );
+        self.absorb_elements(&elements);
+        // Challenges squeezed before this message must not be handed out after it.
+        self.pending.clear();
+    }
+}
+
+impl<F: PrimeField> FiatShamirTranscript for PoseidonSponge<F> {
+    fn from_seed<'a, T: 'a + ToBytes>(seed: &'a T) -> Self {
+        let mut sponge = Self {
+            params: PoseidonParameters::new(),
+            state: [F::zero(); WIDTH],
+            position: 0,
+            squeezing: false,
+            pending: Vec::new(),
+        };
+        sponge.absorb(seed);
+        sponge
+    }
+
+    fn absorb<'a, T: 'a + ToBytes>(&mut self, msg: &'a T) {
+        let mut bytes = Vec::new();
+        msg.write(&mut bytes).expect("failed to convert to bytes");
+        self.absorb_bytes(&bytes);
+    }
+}
+
+impl<F: PrimeField> FiatShamirHash for PoseidonSponge<F> {
+    type Transcript = Self;
+}
+
+impl<F: PrimeField> RngCore for PoseidonSponge<F> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        // The low bytes of a uniform field element are (very nearly) uniform.
+        let chunk = Self::bytes_per_element();
+        while self.pending.len() < dest.len() {
+            let x = self.squeeze_elements(1)[0];
+            self.pending
+                .extend_from_slice(&x.into_repr().to_bytes_le()[..chunk]);
+        }
+        dest.copy_from_slice(&self.pending[..dest.len()]);
+        self.pending.drain(..dest.len());
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ark_std::rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ff::{One, Zero};
+
+    fn fr(hex: &str) -> Fr {
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        Fr::from_be_bytes_mod_order(&bytes)
+    }
+
+    /// The reference implementation's test vector for `poseidonperm_x5_254_3`, whose first output
+    /// is circomlib's `poseidon([1, 2])`.
+    #[test]
+    fn bn254_known_answer() {
+        let params = PoseidonParameters::<Fr>::new();
+        assert_eq!(
+            (params.alpha, params.full_rounds, params.partial_rounds),
+            (5, 8, 57)
+        );
+        assert_eq!(
+            params.round_constants[0],
+            fr("0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e")
+        );
+        let mut state = [Fr::zero(), Fr::one(), Fr::from(2u8)];
+        params.permute(&mut state);
+        assert_eq!(
+            state,
+            [
+                fr("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"),
+                fr("0fca49b798923ab0239de1c9e7a4a9a2210312b6a2f616d18b5a87f9b628ae29"),
+                fr("0e7ae82e40091e63cbd4f16a6d16310b3729d4b6e138fcf54110e2867045a30c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bls12_377_uses_x17() {
+        let params = PoseidonParameters::<ark_bls12_377::Fr>::new();
+        assert_eq!(
+            (params.alpha, params.full_rounds, params.partial_rounds),
+            (17, 8, 31)
+        );
+    }
+
+    #[test]
+    fn challenges_depend_on_every_message() {
+        let challenge = |msgs: &[u64]| {
+            let mut sponge = PoseidonSponge::<Fr>::from_seed(&0u64);
+            for m in msgs {
+                sponge.absorb(m);
+            }
+            sponge.next_u64()
+        };
+        assert_eq!(challenge(&[1, 2]), challenge(&[1, 2]));
+        assert_ne!(challenge(&[1, 2]), challenge(&[2, 1]));
+        assert_ne!(challenge(&[1]), challenge(&[1, 0]));
+
+        let mut sponge = PoseidonSponge::<Fr>::from_seed(&0u64);
+        let (a, b) = (sponge.next_u64(), sponge.next_u64());
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,128 @@
+//! The linear part of the Groth16 H-polynomial computation, over `MpcField` and over
+//! `MpcFieldAcc`.
+//!
+//! Each constraint row is an inner product of public coefficients with an assignment whose first
+//! entry (the constant `1`) is public and whose others are shared, as in a real witness map. The
+//! rows, padded with zeros, are then interpolated and evaluated on a coset. Over `MpcField`,
+//! every public term added to a shared one is a share shift; over `MpcFieldAcc`, each result is
+//! shifted once. The FFTs over `MpcFieldAcc` avoid the shifts of the zero padding, but carry a
+//! public part through every butterfly, which can cost more than it saves: compare both before
+//! using it for FFTs.
+//!
+//! For benchmark, run:
+//!     cargo bench --bench acc
+//! which runs a 2-party SPDZ computation over loopback (and prints each party's timings).
+use ark_bls12_377::Fr;
+use ark_ff::{UniformRand, Zero};
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use mpc_algebra::{MpcField, MpcFieldAcc, Reveal, SpdzFieldShare};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+use rand::Rng;
+use std::time::{Duration, Instant};
+
+type S = SpdzFieldShare<Fr>;
+type F = MpcField<Fr, S>;
+
+/// Not a power of two, so the FFT inputs are padded with (public) zeros.
+const NUM_CONSTRAINTS: usize = 3 << 12;
+const NUM_VARIABLES: usize = 1 << 12;
+const TERMS_PER_ROW: usize = 4;
+const NUM_REPETITIONS: usize = 5;
+
+/// One row per constraint: a multiple of the constant `1`, and some other variables.
+fn rows<R: Rng>(rng: &mut R) -> Vec<Vec<(F, usize)>> {
+    (0..NUM_CONSTRAINTS)
+        .map(|_| {
+            let mut row = vec![(F::from_public(Fr::rand(rng)), 0)];
+            row.extend((1..TERMS_PER_ROW).map(|_| {
+                (
+                    F::from_public(Fr::rand(rng)),
+                    rng.gen_range(1, NUM_VARIABLES),
+                )
+            }));
+            row
+        })
+        .collect()
+}
+
+fn products_field(rows: &[Vec<(F, usize)>], assignment: &[F]) -> Vec<F> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .fold(F::zero(), |sum, (c, i)| sum + assignment[*i] * c)
+        })
+        .collect()
+}
+
+fn products_acc(rows: &[Vec<(F, usize)>], assignment: &[F]) -> Vec<F> {
+    rows.iter()
+        .map(|row| {
+            F::from(
+                row.iter()
+                    .map(|(c, i)| MpcFieldAcc::from(assignment[*i] * c))
+                    .sum::<MpcFieldAcc<Fr, S>>(),
+            )
+        })
+        .collect()
+}
+
+fn ffts_field(evals: &[F], domain: &Radix2EvaluationDomain<F>) -> Vec<F> {
+    let mut evals = evals.to_vec();
+    evals.resize(domain.size(), F::zero());
+    domain.ifft_in_place(&mut evals);
+    domain.coset_fft_in_place(&mut evals);
+    evals
+}
+
+fn ffts_acc(evals: &[F], domain: &Radix2EvaluationDomain<F>) -> Vec<F> {
+    let mut evals: Vec<MpcFieldAcc<Fr, S>> = evals.iter().map(|e| (*e).into()).collect();
+    evals.resize(domain.size(), MpcFieldAcc::zero());
+    domain.ifft_in_place(&mut evals);
+    domain.coset_fft_in_place(&mut evals);
+    evals.into_iter().map(F::from).collect()
+}
+
+fn time(name: &str, f: impl Fn() -> Vec<F>) -> Vec<F> {
+    let mut total = Duration::default();
+    let mut out = Vec::new();
+    for _ in 0..NUM_REPETITIONS {
+        let start = Instant::now();
+        out = f();
+        total += start.elapsed();
+    }
+    println!(
+        "party {}: {} takes {:?} on average",
+        MpcMultiNet::party_id(),
+        name,
+        total / NUM_REPETITIONS as u32
+    );
+    out
+}
+
+fn bench() {
+    F::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let rows = rows(rng);
+    let mut assignment = vec![F::from_public(Fr::from(1u64))];
+    assignment.extend(F::king_share_batch(
+        (1..NUM_VARIABLES).map(|_| Fr::rand(rng)).collect(),
+        rng,
+    ));
+    let domain = Radix2EvaluationDomain::<F>::new(rows.len()).unwrap();
+
+    let evals = time("inner products over MpcField", || {
+        products_field(&rows, &assignment)
+    });
+    let by_acc = time("inner products over MpcFieldAcc", || {
+        products_acc(&rows, &assignment)
+    });
+    assert_eq!(evals.clone().reveal(), by_acc.reveal());
+
+    let by_field = time("FFTs over MpcField", || ffts_field(&evals, &domain));
+    let by_acc = time("FFTs over MpcFieldAcc", || ffts_acc(&evals, &domain));
+    assert_eq!(by_field.reveal(), by_acc.reveal());
+}
+
+fn main() {
+    run_parties("acc", 2, bench);
+}
@@ -0,0 +1,88 @@
+//! MSMs over shared scalars and public bases, as in the Groth16 prover: `ark-ec`'s MSM, run once
+//! on a SPDZ share's values and once on its MACs, against [PippengerMsm], which takes one pass
+//! over the bases for both; and the whole MSM on lifted types, which dispatches on whether each
+//! scalar is shared before it gets to either.
+//!
+//! For benchmark, run:
+//!     cargo bench --bench msm
+//! which runs a 2-party SPDZ computation over loopback (and prints each party's timings). The
+//! parties take turns to time each MSM, so that they do not compete for the machine's cores.
+use ark_bls12_377::{Bls12_377, Fr, G1Affine, G1Projective};
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::UniformRand;
+use mpc_algebra::{
+    msm::{AffineMsm, PippengerMsm},
+    GroupShare, MpcField, MpcPairingEngine, Reveal, SpdzFieldShare, SpdzGroupShare,
+    SpdzPairingShare,
+};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+use std::time::{Duration, Instant};
+
+type E = MpcPairingEngine<Bls12_377, SpdzPairingShare<Bls12_377>>;
+type F = MpcField<Fr, SpdzFieldShare<Fr>>;
+
+const SIZES: [usize; 3] = [1 << 10, 1 << 13, 1 << 16];
+const NUM_REPETITIONS: usize = 3;
+
+fn time<T>(name: &str, n: usize, f: impl Fn() -> T) -> T {
+    let mut out = None;
+    for turn in 0..MpcMultiNet::n_parties() {
+        if turn == MpcMultiNet::party_id() {
+            let mut total = Duration::default();
+            for _ in 0..NUM_REPETITIONS {
+                let start = Instant::now();
+                out = Some(f());
+                total += start.elapsed();
+            }
+            println!(
+                "party {}: {} of size {} takes {:?} on average",
+                turn,
+                name,
+                n,
+                total / NUM_REPETITIONS as u32
+            );
+        }
+        // The others wait their turn.
+        MpcMultiNet::broadcast_bytes(&[]);
+    }
+    out.unwrap()
+}
+
+fn bench() {
+    F::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    for &n in &SIZES {
+        let bases: Vec<G1Affine> = G1Projective::batch_normalization_into_affine(
+            &(0..n).map(|_| G1Projective::rand(rng)).collect::<Vec<_>>(),
+        );
+        let scalars = F::king_share_batch((0..n).map(|_| Fr::rand(rng)).collect(), rng);
+        let shares: Vec<SpdzFieldShare<Fr>> = scalars
+            .iter()
+            .map(|s| match s {
+                MpcField::Shared(s) => *s,
+                MpcField::Public(_) => unreachable!(),
+            })
+            .collect();
+
+        let by_ark = time("ark's MSM, on values and MACs", n, || {
+            SpdzGroupShare::<G1Affine, AffineMsm<G1Affine>>::multi_scale_pub_group(&bases, &shares)
+        });
+        let by_pippenger = time("Pippenger, on both in one pass", n, || {
+            SpdzGroupShare::<G1Affine, PippengerMsm<G1Affine>>::multi_scale_pub_group(
+                &bases, &shares,
+            )
+        });
+        let lifted: Vec<<E as PairingEngine>::G1Affine> =
+            bases.iter().map(|b| Reveal::from_public(*b)).collect();
+        let by_lifted = time("MSM on lifted types", n, || {
+            <E as PairingEngine>::G1Affine::multi_scalar_mul(&lifted, &scalars)
+        });
+        let expected = by_ark.reveal();
+        assert_eq!(by_pippenger.reveal(), expected);
+        assert_eq!(by_lifted.reveal().into_affine(), expected);
+    }
+}
+
+fn main() {
+    run_parties("msm", 2, bench);
+}
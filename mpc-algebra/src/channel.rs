@@ -2,11 +2,14 @@ use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use digest::Digest;
 use rand::RngCore;
 use sha2::Sha256;
-use std::cell::Cell;
+use log::{debug, warn};
+use std::cell::{Cell, RefCell};
+use std::time::{Duration, Instant};
 
 use mpc_net::two as net_two;
 
-use mpc_net::MpcNet;
+use mpc_net::stream::{Receiver, Sender};
+use mpc_net::{MpcMultiNet, MpcNet};
 
 pub trait MpcSerNet: MpcNet {
     #[inline]
@@ -32,6 +35,70 @@ pub trait MpcSerNet: MpcNet {
         })
     }
 
+    /// [MpcSerNet::broadcast], with `None` for parties that drop out (see
+    /// [MpcNet::broadcast_bytes_surviving]).
+    #[inline]
+    fn broadcast_surviving<T: CanonicalDeserialize + CanonicalSerialize>(
+        out: &T,
+    ) -> Vec<Option<T>> {
+        let mut bytes_out = Vec::new();
+        out.serialize(&mut bytes_out).unwrap();
+        Self::broadcast_bytes_surviving(&bytes_out)
+            .into_iter()
+            .map(|b| b.map(|b| T::deserialize(&b[..]).unwrap()))
+            .collect()
+    }
+
+    /// [MpcSerNet::send_to_king], with `None` for parties that drop out.
+    #[inline]
+    fn send_to_king_surviving<T: CanonicalDeserialize + CanonicalSerialize>(
+        out: &T,
+    ) -> Option<Vec<Option<T>>> {
+        let mut bytes_out = Vec::new();
+        out.serialize(&mut bytes_out).unwrap();
+        Self::send_bytes_to_king_surviving(&bytes_out).map(|bytes_in| {
+            bytes_in
+                .into_iter()
+                .map(|b| b.map(|b| T::deserialize(&b[..]).unwrap()))
+                .collect()
+        })
+    }
+
+    /// [MpcSerNet::send_to_king], to `party`.
+    #[inline]
+    fn send_to<T: CanonicalDeserialize + CanonicalSerialize>(
+        party: usize,
+        out: &T,
+    ) -> Option<Vec<T>> {
+        let mut bytes_out = Vec::new();
+        out.serialize(&mut bytes_out).unwrap();
+        Self::send_bytes_to(party, &bytes_out).map(|bytes_in| {
+            bytes_in
+                .into_iter()
+                .map(|b| T::deserialize(&b[..]).unwrap())
+                .collect()
+        })
+    }
+
+    /// Open to `party` alone: everyone sends it their `share`, and it gets `combine` of all
+    /// shares. It then acknowledges completion to the others, who get `None`.
+    #[inline]
+    fn open_to<T: CanonicalDeserialize + CanonicalSerialize, R>(
+        party: usize,
+        share: &T,
+        combine: impl FnOnce(Vec<T>) -> R,
+    ) -> Option<R> {
+        let value = Self::send_to(party, share).map(combine);
+        let acks = value.as_ref().map(|_| vec![OPEN_ACK.to_vec(); Self::n_parties()]);
+        let ack = Self::recv_bytes_from(party, acks);
+        assert_eq!(
+            ack, OPEN_ACK,
+            "Protocol abort: party {} did not acknowledge an opening",
+            party
+        );
+        value
+    }
+
     #[inline]
     fn recv_from_king<T: CanonicalDeserialize + CanonicalSerialize>(out: Option<Vec<T>>) -> T {
         let bytes_in = Self::recv_bytes_from_king(out.map(|outs| {
@@ -53,19 +120,17 @@ pub trait MpcSerNet: MpcNet {
         let ser_len = bytes_out.len();
         bytes_out.resize(ser_len + COMMIT_RAND_BYTES, 0);
         rand::thread_rng().fill_bytes(&mut bytes_out[ser_len..]);
-        let commitment = CommitHash::new().chain(&bytes_out).finalize();
+        let session = Self::session_id();
+        let self_id = Self::party_id();
+        let commitment = commit(&session, self_id, &bytes_out);
         // exchange commitments
-        let all_commits = Self::broadcast_bytes(&commitment[..]);
+        let all_commits = Self::broadcast_bytes(&commitment);
         // exchange (data || randomness)
         let all_data = Self::broadcast_bytes(&bytes_out);
-        let self_id = Self::party_id();
         for i in 0..all_commits.len() {
             if i != self_id {
                 // check other commitment
-                assert_eq!(
-                    &all_commits[i][..],
-                    &CommitHash::new().chain(&all_data[i]).finalize()[..]
-                );
+                assert_eq!(all_commits[i], commit(&session, i, &all_data[i]));
             }
         }
         all_data
@@ -79,6 +144,92 @@ pub trait MpcSerNet: MpcNet {
         let king_response = Self::send_to_king(x).map(f);
         Self::recv_from_king(king_response)
     }
+
+    /// [MpcSerNet::broadcast] for a slice, returning every party's slice.
+    ///
+    /// The byte buffers are pooled across calls, so big batches (e.g., openings) don't allocate
+    /// them anew each time.
+    #[inline]
+    fn broadcast_slice<T: CanonicalDeserialize + CanonicalSerialize>(out: &[T]) -> Vec<Vec<T>> {
+        BUFFERS.with(|bufs| {
+            let (bytes_out, bytes_in) = &mut *bufs.borrow_mut();
+            serialize_into(out, bytes_out);
+            Self::broadcast_bytes_into(bytes_out, bytes_in);
+            bytes_in
+                .iter()
+                .map(|b| {
+                    let mut v = Vec::new();
+                    deserialize_from(b, out.len(), &mut v);
+                    v
+                })
+                .collect()
+        })
+    }
+
+    /// Combine all parties' `out` with `combine`, which must be associative, up a tree (see
+    /// [MpcNet::reduce_bytes]), giving everyone the result.
+    fn reduce<T: CanonicalDeserialize + CanonicalSerialize>(
+        out: &T,
+        combine: impl Fn(T, T) -> T,
+    ) -> T {
+        let mut bytes_out = Vec::new();
+        out.serialize(&mut bytes_out).unwrap();
+        let bytes_in = Self::reduce_bytes(&bytes_out, |a, b| {
+            let t = combine(
+                T::deserialize(&a[..]).unwrap(),
+                T::deserialize(&b[..]).unwrap(),
+            );
+            let mut bytes = Vec::new();
+            t.serialize(&mut bytes).unwrap();
+            bytes
+        });
+        T::deserialize(&bytes_in[..]).unwrap()
+    }
+
+    /// Send `out` to every other party, who call [MpcSerNet::stream_from], serializing it
+    /// straight into the network in chunks (see [mpc_net::stream]) rather than into memory.
+    ///
+    /// `progress` gets the number of bytes sent so far, after each chunk. Returns the total.
+    fn stream_to_all<T: CanonicalSerialize>(out: &T, progress: impl FnMut(usize)) -> usize {
+        let mut sender = Sender::<Self, _>::new(progress);
+        out.serialize(&mut sender).unwrap();
+        sender.finish()
+    }
+
+    /// Receive what `party` sends with [MpcSerNet::stream_to_all], deserializing it as it
+    /// arrives.
+    ///
+    /// `progress` gets the number of bytes received so far, after each chunk.
+    fn stream_from<T: CanonicalDeserialize>(party: usize, progress: impl FnMut(usize)) -> T {
+        let mut receiver = Receiver::<Self, _>::new(party, progress);
+        let t = T::deserialize(&mut receiver).unwrap();
+        receiver.finish();
+        t
+    }
+}
+
+/// What the recipient of [MpcSerNet::open_to] sends everyone else once it has the value.
+const OPEN_ACK: &[u8] = b"opened";
+
+thread_local! {
+    /// Send and receive buffers for [MpcSerNet::broadcast_slice].
+    static BUFFERS: RefCell<(Vec<u8>, Vec<Vec<u8>>)> = RefCell::new(Default::default());
+}
+
+/// Serialize `items` back to back (with no length prefix) into `buf`, replacing its contents.
+pub fn serialize_into<T: CanonicalSerialize>(items: &[T], buf: &mut Vec<u8>) {
+    buf.clear();
+    for t in items {
+        t.serialize(&mut *buf).unwrap();
+    }
+}
+
+/// Deserialize `n` items written by [serialize_into], appending them to `out`.
+pub fn deserialize_from<T: CanonicalDeserialize>(mut buf: &[u8], n: usize, out: &mut Vec<T>) {
+    out.reserve(n);
+    for _ in 0..n {
+        out.push(T::deserialize(&mut buf).unwrap());
+    }
 }
 
 impl<N: MpcNet> MpcSerNet for N {}
@@ -91,6 +242,19 @@ const COMMIT_RAND_BYTES: usize = 32;
 /// The hash function to use for the commitment
 type CommitHash = Sha256;
 
+/// `party`'s commitment to `bytes` (its value and randomness) in session `session`.
+///
+/// Binding the session and the committer means that a party can neither reuse a commitment (or
+/// opening) from another session, nor echo another party's commitment as its own.
+fn commit(session: &[u8], party: usize, bytes: &[u8]) -> Vec<u8> {
+    CommitHash::new()
+        .chain(session)
+        .chain(&(party as u64).to_le_bytes())
+        .chain(bytes)
+        .finalize()
+        .to_vec()
+}
+
 #[inline]
 pub fn exchange<F: CanonicalSerialize + CanonicalDeserialize>(f: &F) -> F {
     let mut bytes_out = Vec::new();
@@ -124,6 +288,39 @@ pub fn atomic_exchange<F: CanonicalSerialize + CanonicalDeserialize>(f: &F) -> F
     F::deserialize(&other_bytes[..ser_len]).unwrap()
 }
 
+/// How long a party may wait at a [barrier] before it logs that it waited for stragglers.
+const STRAGGLER_WAIT: Duration = Duration::from_millis(500);
+
+/// Wait until every party reaches the barrier `label`.
+///
+/// Put at a phase boundary, this starts the phase (and its timers) at once at every party, and a
+/// party that lags shows up as a wait at this labeled barrier, in the others' logs, rather than as
+/// a stall inside the phase. Parties at different barriers abort the protocol. Without a
+/// network (a local run), there is nothing to wait for.
+pub fn barrier(label: &str) {
+    if !MpcMultiNet::is_init() {
+        return;
+    }
+    let ours = Sha256::digest(label.as_bytes());
+    debug!("Barrier `{}`: waiting", label);
+    let start = Instant::now();
+    let theirs = MpcMultiNet::broadcast_bytes(&ours);
+    let waited = start.elapsed();
+    for (party, digest) in theirs.iter().enumerate() {
+        assert!(
+            digest[..] == ours[..],
+            "Protocol abort: party {} is not at barrier `{}`",
+            party,
+            label
+        );
+    }
+    if waited >= STRAGGLER_WAIT {
+        warn!("Barrier `{}`: waited {:?} for the other parties", label, waited);
+    } else {
+        debug!("Barrier `{}`: passed after {:?}", label, waited);
+    }
+}
+
 #[inline]
 pub fn can_cheat() -> bool {
     ALLOW_CHEATING.get()
@@ -142,3 +339,21 @@ pub fn without_cheating<O, F: FnOnce() -> O>(f: F) -> O {
     set_cheating_allowed(allowed);
     r
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_ff::UniformRand;
+
+    #[test]
+    fn slice_round_trip() {
+        let rng = &mut ark_std::test_rng();
+        let xs: Vec<Fr> = (0..10).map(|_| Fr::rand(rng)).collect();
+        let mut buf = vec![1, 2, 3];
+        serialize_into(&xs, &mut buf);
+        let mut ys = vec![Fr::rand(rng)];
+        deserialize_from(&buf, xs.len(), &mut ys);
+        assert_eq!(&ys[1..], &xs[..]);
+    }
+}
@@ -1,5 +1,5 @@
 use digest::Digest;
-use rand::Rng;
+use rand::{Rng, RngCore};
 use sha2;
 
 use ark_ff::prelude::*;
@@ -8,6 +8,7 @@ use mpc_trait::MpcWire;
 
 use crate::{channel, Reveal};
 use mpc_net::two as net_two;
+use mpc_net::{MpcMultiNet, MpcNet};
 use crate::wire::field::MpcField;
 use crate::share::field::FieldShare;
 
@@ -31,7 +32,7 @@ impl<Fr: PrimeField, S: FieldShare<Fr>>  ComField for MpcField<Fr, S> {
         Vec<(Vec<u8>, Vec<u8>)>,
     );
     fn public_rand<R: Rng>(r: &mut R) -> Self {
-        Self::from_public(Fr::rand(r))
+        Self::public_rand(r)
     }
     fn commit(vs: &[Self]) -> (Self::Key, Self::Commitment) {
         let mut tree = Vec::new();
@@ -40,7 +41,10 @@ impl<Fr: PrimeField, S: FieldShare<Fr>>  ComField for MpcField<Fr, S> {
             .enumerate()
             .map(|(_i, v)| {
                 let mut bytes_out = Vec::new();
-                v.unwrap_as_public().serialize(&mut bytes_out).unwrap();
+                // Each party commits to its own share; only the root is exchanged.
+                unsafe { v.unwrap_as_public() }
+                    .serialize(&mut bytes_out)
+                    .unwrap();
                 let o = sha2::Sha256::digest(&bytes_out[..]).as_slice().to_owned();
                 o
             })
@@ -66,7 +70,8 @@ impl<Fr: PrimeField, S: FieldShare<Fr>>  ComField for MpcField<Fr, S> {
         }
     }
     fn open_at(inputs: &[Self], tree: &Self::Key, mut i: usize) -> (Self, Self::OpeningProof) {
-        let self_f = inputs[i].unwrap_as_public();
+        // Opening reveals the value, so our share is sent on purpose.
+        let self_f = unsafe { inputs[i].unwrap_as_public() };
         let other_f = channel::exchange(&self_f);
         let mut siblings = Vec::new();
         for level in 0..tree.len() {
@@ -93,7 +98,7 @@ impl<Fr: PrimeField, S: FieldShare<Fr>>  ComField for MpcField<Fr, S> {
         )
     }
     fn check_opening(c: &Self::Commitment, p: Self::OpeningProof, i: usize, v: Self) -> bool {
-        if p.0 + p.1 != v.reveal() {
+        if !crate::ct::eq(&(p.0 + p.1), &v.reveal()) {
             return false;
         }
         let mut hash0 = Vec::new();
@@ -119,6 +124,69 @@ impl<Fr: PrimeField, S: FieldShare<Fr>>  ComField for MpcField<Fr, S> {
             hash0 = h0.finalize().as_slice().to_owned();
             hash1 = h1.finalize().as_slice().to_owned();
         }
-        &(hash1, hash0) == c
+        crate::ct::bytes_eq(&hash1, &c.0) & crate::ct::bytes_eq(&hash0, &c.1)
+    }
+}
+
+/// Hash commitments to every party's shares of a witness, exchanged before proving.
+///
+/// Every party commits before seeing anyone else's commitment, so no party can pick its shares
+/// after the fact. Each party also keeps the opening of its own commitment, with which it can
+/// later show exactly which shares it proved with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WitnessCommitments {
+    /// Party `i` committed to `commitments[i]`.
+    pub commitments: Vec<Vec<u8>>,
+    /// The randomness in this party's commitment.
+    pub opening: Vec<u8>,
+}
+
+impl WitnessCommitments {
+    /// Commit to this party's (additive) shares of `witness`, and exchange commitments with all parties.
+    pub fn exchange<F: Field, S: FieldShare<F>>(witness: &[MpcField<F, S>]) -> Self {
+        let mut opening = vec![0u8; 32];
+        rand::thread_rng().fill_bytes(&mut opening);
+        let commitment = Self::commit(witness, &opening);
+        let commitments = MpcMultiNet::broadcast_bytes(&commitment);
+        Self {
+            commitments,
+            opening,
+        }
+    }
+
+    /// Does `opening` open `party`'s commitment to `witness` (as that party's shares)?
+    pub fn check<F: Field, S: FieldShare<F>>(
+        &self,
+        party: usize,
+        witness: &[MpcField<F, S>],
+        opening: &[u8],
+    ) -> bool {
+        let ours = Self::commit(witness, opening);
+        self.commitments
+            .get(party)
+            .map_or(false, |c| crate::ct::bytes_eq(c, &ours))
+    }
+
+    fn commit<F: Field, S: FieldShare<F>>(witness: &[MpcField<F, S>], opening: &[u8]) -> Vec<u8> {
+        let mut h = sha2::Sha256::default();
+        h.update(opening);
+        let mut bytes = Vec::new();
+        for w in witness {
+            bytes.clear();
+            // A hiding commitment to our share; it is only opened to check this party.
+            unsafe { w.unwrap_as_public() }.serialize(&mut bytes).unwrap();
+            h.update(&bytes);
+        }
+        h.finalize().to_vec()
+    }
+}
+
+impl std::fmt::Display for WitnessCommitments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, c) in self.commitments.iter().enumerate() {
+            write!(f, "{}party {}: ", if i > 0 { ", " } else { "" }, i)?;
+            c.iter().try_for_each(|b| write!(f, "{:02x}", b))?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,51 @@
+//! Constant-time comparisons, for the checks that parties make on each other's values.
+//!
+//! A comparison that returns at the first difference takes longer the more of a value matches
+//! what was expected, and a party (or an observer) timing it could learn how close a forged or
+//! corrupted value came. These compare whole canonical serializations, with [subtle], however
+//! early they differ; only the lengths, which the types fix, may leak.
+use ark_ff::Zero;
+use ark_serialize::CanonicalSerialize;
+use subtle::ConstantTimeEq;
+
+/// Whether `a` and `b` are the same bytes, in time that depends only on their lengths.
+pub fn bytes_eq(a: &[u8], b: &[u8]) -> bool {
+    a.ct_eq(b).into()
+}
+
+/// Whether `a` and `b` serialize to the same bytes, compared as by [bytes_eq].
+pub fn eq<T: CanonicalSerialize>(a: &T, b: &T) -> bool {
+    bytes_eq(&bytes(a), &bytes(b))
+}
+
+/// Whether `t` serializes as zero does, compared as by [bytes_eq].
+pub fn is_zero<T: CanonicalSerialize + Zero>(t: &T) -> bool {
+    eq(t, &T::zero())
+}
+
+fn bytes<T: CanonicalSerialize>(t: &T) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(t.serialized_size());
+    t.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::{Fr, G1Projective};
+    use ark_ec::ProjectiveCurve;
+
+    #[test]
+    fn compares_like_eq() {
+        assert!(bytes_eq(b"abc", b"abc"));
+        assert!(!bytes_eq(b"abc", b"abd"));
+        assert!(!bytes_eq(b"abc", b"ab"));
+        let (a, b) = (Fr::from(3u8), Fr::from(4u8));
+        assert!(eq(&a, &a) && !eq(&a, &b));
+        assert!(is_zero(&(a - a)) && !is_zero(&a));
+        // Projective points serialize as affine ones, so equal points compare equal.
+        let g = G1Projective::prime_subgroup_generator();
+        assert!(eq(&(g + g), &g.double()));
+        assert!(is_zero(&(g - g)));
+    }
+}
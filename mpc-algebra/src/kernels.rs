@@ -0,0 +1,207 @@
+//! Where the heavy share-local kernels run: on the CPU, or on an accelerator.
+//!
+//! An additive share's MSM or FFT is the same computation on the share's value as on a plain
+//! value, with no communication, so each party can hand it to whatever hardware it has. Once
+//! batching has cut the communication, these kernels are most of a party's time.
+//!
+//! A [Backend] registers kernels for the types it supports, as plain functions; [install] makes
+//! them the ones [msm] and [fft_in_place] use, at this party. Types with no kernel (and calls that
+//! a kernel declines, by returning `None` or `false`) run on the CPU, so results never depend on
+//! the backend. No accelerator backend ships with this crate: one that wraps a CUDA or Metal
+//! library lives with that library, and the binary that links it installs it.
+use crate::msm::pippenger;
+use ark_ec::AffineCurve;
+use ark_ff::{FftField, PrimeField};
+use ark_poly::EvaluationDomain;
+use lazy_static::lazy_static;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// `sum_i scalars[j][i] * bases[i]` for each `j`, as [pippenger] computes it; `None` to decline.
+pub type MsmKernel<G> = fn(
+    &[G],
+    &[&[<<G as AffineCurve>::ScalarField as PrimeField>::BigInt]],
+) -> Option<Vec<<G as AffineCurve>::Projective>>;
+
+/// The FFT of `values` (of power-of-two length) over the subgroup generated by `omega`, in
+/// place, in natural order: `values[i]` becomes `sum_j values[j] * omega^(i * j)`. `false` to
+/// decline, leaving `values` as they were.
+pub type FftKernel<F> = fn(&mut [F], F) -> bool;
+
+/// The kernels of a [Backend], by the type they are for.
+#[derive(Default)]
+pub struct Kernels {
+    name: Option<&'static str>,
+    msm: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    fft: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl Kernels {
+    pub fn set_msm<G: AffineCurve>(&mut self, kernel: MsmKernel<G>) {
+        self.msm.insert(TypeId::of::<G>(), Box::new(kernel));
+    }
+
+    pub fn set_fft<F: FftField>(&mut self, kernel: FftKernel<F>) {
+        self.fft.insert(TypeId::of::<F>(), Box::new(kernel));
+    }
+
+    fn msm<G: AffineCurve>(&self) -> Option<MsmKernel<G>> {
+        let kernel = self.msm.get(&TypeId::of::<G>())?;
+        kernel.downcast_ref::<MsmKernel<G>>().cloned()
+    }
+
+    fn fft<F: FftField>(&self) -> Option<FftKernel<F>> {
+        let kernel = self.fft.get(&TypeId::of::<F>())?;
+        kernel.downcast_ref::<FftKernel<F>>().cloned()
+    }
+}
+
+/// Hardware that runs some of the kernels.
+pub trait Backend {
+    fn name(&self) -> &'static str;
+    /// Register a kernel for each type this backend supports.
+    fn register(&self, kernels: &mut Kernels);
+}
+
+lazy_static! {
+    static ref KERNELS: RwLock<Kernels> = RwLock::new(Kernels::default());
+}
+
+/// Run kernels on `backend` from now on, rather than on the one installed before, if any.
+pub fn install(backend: &dyn Backend) {
+    let mut kernels = Kernels {
+        name: Some(backend.name()),
+        ..Kernels::default()
+    };
+    backend.register(&mut kernels);
+    *KERNELS.write().unwrap() = kernels;
+}
+
+/// Run every kernel on the CPU again.
+pub fn uninstall() {
+    *KERNELS.write().unwrap() = Kernels::default();
+}
+
+/// The installed backend's name, or `"cpu"`.
+pub fn backend() -> &'static str {
+    KERNELS.read().unwrap().name.unwrap_or("cpu")
+}
+
+/// [pippenger], on the installed backend if it has a kernel for `G`.
+pub fn msm<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[&[<G::ScalarField as PrimeField>::BigInt]],
+) -> Vec<G::Projective> {
+    let kernel = KERNELS.read().unwrap().msm::<G>();
+    kernel
+        .and_then(|k| k(bases, scalars))
+        .unwrap_or_else(|| pippenger(bases, scalars))
+}
+
+/// `domain.fft_in_place(values)`, on the installed backend if it has a kernel for `F` and
+/// `domain` is a power of two in size.
+pub fn fft_in_place<F: FftField, D: EvaluationDomain<F>>(domain: &D, values: &mut Vec<F>) {
+    if !fft_on_backend(domain, values, domain.element(1)) {
+        domain.fft_in_place(values)
+    }
+}
+
+/// `domain.ifft_in_place(values)`, as [fft_in_place] runs it.
+pub fn ifft_in_place<F: FftField, D: EvaluationDomain<F>>(domain: &D, values: &mut Vec<F>) {
+    let omega_inv = domain.element(domain.size() - 1);
+    if fft_on_backend(domain, values, omega_inv) {
+        let size_inv = domain.size_as_field_element().inverse().unwrap();
+        values.iter_mut().for_each(|v| *v *= size_inv);
+    } else {
+        domain.ifft_in_place(values)
+    }
+}
+
+fn fft_on_backend<F: FftField, D: EvaluationDomain<F>>(
+    domain: &D,
+    values: &mut Vec<F>,
+    omega: F,
+) -> bool {
+    let n = domain.size();
+    let kernel = match KERNELS.read().unwrap().fft::<F>() {
+        Some(k) if n.is_power_of_two() && n > 1 => k,
+        _ => return false,
+    };
+    values.resize(n, F::zero());
+    kernel(values, omega)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::{Fr, G1Affine, G1Projective};
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::{Field, UniformRand};
+    use ark_poly::Radix2EvaluationDomain;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Other tests may run MSMs or FFTs while this backend is installed, so its kernels are exact;
+    /// they count their calls.
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+
+    fn counting_msm(
+        bases: &[G1Affine],
+        scalars: &[&[<Fr as PrimeField>::BigInt]],
+    ) -> Option<Vec<G1Projective>> {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        Some(pippenger(bases, scalars))
+    }
+
+    /// The FFT by its definition.
+    fn counting_fft(values: &mut [Fr], omega: Fr) -> bool {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        let n = values.len() as u64;
+        let out: Vec<Fr> = (0..n)
+            .map(|i| {
+                let w = omega.pow([i]);
+                (0..n).map(|j| values[j as usize] * w.pow([j])).sum()
+            })
+            .collect();
+        values.copy_from_slice(&out);
+        true
+    }
+
+    impl Backend for Counting {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+        fn register(&self, kernels: &mut Kernels) {
+            kernels.set_msm::<G1Affine>(counting_msm);
+            kernels.set_fft::<Fr>(counting_fft);
+        }
+    }
+
+    #[test]
+    fn kernels_run_on_the_backend() {
+        let rng = &mut ark_std::test_rng();
+        let bases: Vec<G1Affine> = (0..10)
+            .map(|_| G1Projective::rand(rng).into_affine())
+            .collect();
+        let scalars: Vec<_> = (0..10).map(|_| Fr::rand(rng).into_repr()).collect();
+        let domain = Radix2EvaluationDomain::<Fr>::new(8).unwrap();
+        let coeffs: Vec<Fr> = (0..8).map(|_| Fr::rand(rng)).collect();
+        let (mut evals, mut back) = (coeffs.clone(), domain.fft(&coeffs));
+
+        install(&Counting);
+        assert_eq!(backend(), "counting");
+        let before = CALLS.load(Ordering::SeqCst);
+        let on_backend = msm(&bases, &[&scalars]);
+        fft_in_place(&domain, &mut evals);
+        ifft_in_place(&domain, &mut back);
+        assert!(CALLS.load(Ordering::SeqCst) - before >= 3);
+        uninstall();
+        assert_eq!(backend(), "cpu");
+
+        assert_eq!(on_backend, pippenger(&bases, &[&scalars]));
+        assert_eq!(evals, domain.fft(&coeffs));
+        assert_eq!(back, coeffs);
+    }
+}
@@ -5,8 +5,14 @@ pub mod reveal;
 pub use reveal::*;
 pub mod channel;
 pub mod com;
+pub mod ct;
 pub mod group;
+pub mod kernels;
+pub mod mp_spdz;
+pub mod preprocessing;
+pub mod security;
 pub mod share;
+pub mod shuffle;
 pub use share::*;
 pub mod wire;
 pub use wire::*;
@@ -0,0 +1,211 @@
+//! Shares computed by [MP-SPDZ](https://github.com/data61/MP-SPDZ), for proving here about a
+//! witness computed there.
+//!
+//! An MP-SPDZ program run with a SPDZ-family protocol over the same prime (`-P`, the modulus of
+//! the proof system's scalar field) writes its outputs with `sint.write_to_file`, and each party's
+//! share file (`Persistence/Transactions-P<i>.data`) holds:
+//!
+//! * a header: a little-endian `u64` length, then that many bytes naming the share type and field;
+//! * then per value, this party's share of it and its share of the value's MAC, each in
+//!   Montgomery form, little-endian ([Layout::MONTGOMERY_LE]).
+//!
+//! Its share of the MAC key is in `Player-Data/<n>-p-<bits>/Player-MAC-Keys-p-P<i>`: the number of
+//! parties, then the key share, in decimal.
+//!
+//! [import] checks the values against their MP-SPDZ MACs, and then re-shares them under this
+//! crate's MAC. The check opens a random combination of the values, so the program must write one
+//! more random value (`sint.get_random()`) after the witness, to mask it; it is used up.
+use ark_ff::PrimeField;
+use digest::Digest;
+use sha2::Sha256;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::channel::MpcSerNet;
+use crate::share::raw::Layout;
+use crate::{MpcField, Reveal, SpdzFieldShare};
+use mpc_net::{MpcMultiNet as Net, MpcNet};
+
+/// One party's MP-SPDZ share file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShareFile<F> {
+    /// The header's description of the shares.
+    pub signature: Vec<u8>,
+    /// Per value, this party's share of it and of its MAC.
+    pub shares: Vec<(F, F)>,
+}
+
+impl<F: PrimeField> ShareFile<F> {
+    pub fn read<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut len = [0u8; 8];
+        reader.read_exact(&mut len)?;
+        let mut signature = vec![0u8; u64::from_le_bytes(len) as usize];
+        reader.read_exact(&mut signature)?;
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        let record = 2 * Layout::width::<F>();
+        if body.len() % record != 0 {
+            return Err(invalid(format!(
+                "{} bytes of shares, in records of {}: is MP-SPDZ's prime this field's?",
+                body.len(),
+                record
+            )));
+        }
+        let layout = Layout::MONTGOMERY_LE;
+        let shares = body
+            .chunks(record)
+            .map(|r| {
+                let (val, mac) = r.split_at(record / 2);
+                Ok((layout.from_bytes(val)?, layout.from_bytes(mac)?))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self { signature, shares })
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+}
+
+/// Reads this party's share of MP-SPDZ's MAC key, checking the number of parties.
+pub fn read_mac_key<F: PrimeField, R: Read>(reader: R) -> io::Result<F> {
+    let mut tokens = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        tokens.extend(line?.split_whitespace().map(String::from));
+    }
+    match &tokens[..] {
+        [n, key] => {
+            let n: usize = n.parse().map_err(|_| invalid("bad number of parties"))?;
+            if n != Net::n_parties() {
+                return Err(invalid(format!("a key for {} parties", n)));
+            }
+            key.parse().map_err(|_| invalid("bad key share"))
+        }
+        _ => Err(invalid("expected the number of parties and a key share")),
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// The values in `file`, but for the last (the mask), as this crate's SPDZ shares, after checking
+/// them against their MP-SPDZ MACs under this party's key share `key`.
+///
+/// Every party calls this at once, with its own file, of as many values. The shares are re-MACed
+/// as [Reveal::from_add_shared] does, so cheating must still be allowed. Panics if the MAC check
+/// fails, as any opening does.
+pub fn import<F: PrimeField>(file: &ShareFile<F>, key: F) -> Vec<MpcField<F, SpdzFieldShare<F>>> {
+    let (mask, values) = file.shares.split_last().expect("no mask in the share file");
+    // The coefficients are fixed only once every party's shares are.
+    let mut hash = Sha256::new();
+    for (val, mac) in &file.shares {
+        hash.update(&Layout::CANONICAL_LE.to_bytes(val));
+        hash.update(&Layout::CANONICAL_LE.to_bytes(mac));
+    }
+    let digests: Vec<Vec<u8>> = Net::atomic_broadcast(&hash.finalize().to_vec());
+    let seed = digests
+        .iter()
+        .fold(Sha256::new(), |h, d| h.chain(d))
+        .finalize();
+    let (mut y, mut m) = *mask;
+    for (i, (val, mac)) in values.iter().enumerate() {
+        let rho = F::from_le_bytes_mod_order(
+            &Sha256::new()
+                .chain(&seed)
+                .chain(&(i as u64).to_le_bytes())
+                .finalize(),
+        );
+        y += rho * val;
+        m += rho * mac;
+    }
+    let y: F = Net::broadcast(&y).into_iter().sum();
+    let sigma: F = Net::atomic_broadcast(&(key * y - m)).into_iter().sum();
+    assert!(
+        crate::ct::is_zero(&sigma),
+        "Protocol abort: MP-SPDZ MAC check failed"
+    );
+    values
+        .iter()
+        .map(|(val, _)| MpcField::Shared(Reveal::from_add_shared(*val)))
+        .collect()
+}
+
+/// Like [import], from the files at `shares` and `mac_key`.
+pub fn import_files<F: PrimeField>(
+    shares: impl AsRef<Path>,
+    mac_key: impl AsRef<Path>,
+) -> io::Result<Vec<MpcField<F, SpdzFieldShare<F>>>> {
+    let key = read_mac_key(File::open(mac_key)?)?;
+    Ok(import(&ShareFile::open(shares)?, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_ff::{UniformRand, Zero};
+    use mpc_net::testing::{run_parties, run_parties_checked};
+    use std::time::Duration;
+
+    /// What MP-SPDZ would write for this party, sharing `xs` under key `alpha` (shared as
+    /// `alpha_shares`), from shared randomness.
+    fn share_file(xs: &[Fr], alpha_shares: &[Fr]) -> Vec<u8> {
+        let (me, n) = (Net::party_id(), Net::n_parties());
+        let rng = &mut ark_std::test_rng();
+        let alpha: Fr = alpha_shares.iter().sum();
+        let mut bytes = Vec::new();
+        let signature = b"SPDZ gfp";
+        bytes.extend(&(signature.len() as u64).to_le_bytes());
+        bytes.extend(signature);
+        for x in xs {
+            let mut shares: Vec<(Fr, Fr)> =
+                (1..n).map(|_| (Fr::rand(rng), Fr::rand(rng))).collect();
+            let (v, m) = shares
+                .iter()
+                .fold((Fr::zero(), Fr::zero()), |(v, m), s| (v + s.0, m + s.1));
+            shares.push((*x - v, alpha * x - m));
+            let layout = Layout::MONTGOMERY_LE;
+            bytes.extend(layout.to_bytes(&shares[me].0));
+            bytes.extend(layout.to_bytes(&shares[me].1));
+        }
+        bytes
+    }
+
+    #[test]
+    fn imports_mp_spdz_shares() {
+        run_parties("mp_spdz::tests::imports_mp_spdz_shares", 2, || {
+            let xs: Vec<Fr> = (1..6u64).map(Fr::from).collect();
+            let alpha = [Fr::from(11u64), Fr::from(31u64)];
+            let file = ShareFile::read(&share_file(&xs, &alpha)[..]).unwrap();
+            assert_eq!(file.signature, b"SPDZ gfp");
+            let key_file = format!("2\n{}\n", [11, 31][Net::party_id()]);
+            let key: Fr = read_mac_key(key_file.as_bytes()).unwrap();
+            let values = import(&file, key);
+            assert_eq!(values.reveal(), xs[..xs.len() - 1].to_vec());
+        });
+    }
+
+    #[test]
+    fn rejects_bad_macs() {
+        run_parties_checked(
+            "mp_spdz::tests::rejects_bad_macs",
+            2,
+            Duration::from_secs(20),
+            |_| (),
+            || {
+                let xs: Vec<Fr> = (1..6u64).map(Fr::from).collect();
+                let alpha = [Fr::from(11u64), Fr::from(31u64)];
+                let mut file = ShareFile::read(&share_file(&xs, &alpha)[..]).unwrap();
+                file.shares[0].0 += Fr::from(1u64);
+                import(&file, alpha[Net::party_id()]);
+            },
+            |exits| {
+                for e in &exits {
+                    assert!(e.stderr.contains("MP-SPDZ MAC check failed"), "{:?}", exits);
+                }
+            },
+        );
+    }
+}
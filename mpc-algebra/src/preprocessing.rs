@@ -0,0 +1,930 @@
+//! Circuit-agnostic preprocessing material.
+//!
+//! Beaver triples, inverse pairs, and random bits do not depend on the circuit being proven, so
+//! they can be dealt once into a *randomness pool* and drawn down by many proving sessions.
+//! Group triples, for products of shared scalars and shared group elements, are made from field
+//! triples (see [SessionGroupTripleSource]).
+//!
+//! Each party keeps its own pool directory. The king decides which range of records a session
+//! consumes and tells the other parties, so all parties always read matching records.
+//! Reservations take an exclusive lock on the pool's cursor file, which records every claimed
+//! range (sessions may learn theirs out of order), so several proving processes can share one
+//! pool concurrently, and every reservation is appended to a ledger, which gives per-session
+//! accounting.
+//!
+//! A pool can also be dealt *seed-compressed* (see [RandomnessPool::deal_compressed]): then every
+//! party but the last holds only a short seed, from which it expands its shares on demand, and
+//! the last holds one correction per record. Sessions draw from either kind of pool alike. This
+//! only compresses what the trusted dealer ships; it is not silent preprocessing (a PCG, from
+//! which parties would expand correlated randomness that no dealer knows).
+use lazy_static::lazy_static;
+use log::debug;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use ark_ec::group::Group;
+use ark_ff::prelude::*;
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use derivative::Derivative;
+use mpc_net::{MpcMultiNet as Net, MpcNet};
+
+use sha2::{Digest, Sha256};
+
+use crate::channel::MpcSerNet;
+use crate::share::field::FieldShare;
+use crate::share::group::GroupShare;
+use crate::security::KING_DEALT;
+use crate::share::BeaverSource;
+use crate::wire::field::DummyFieldTripleSource;
+
+/// Magic bytes at the start of every pool data file.
+const POOL_MAGIC: &[u8; 8] = b"MPCPOOL1";
+
+/// Default number of records a session reserves at once.
+pub const DEFAULT_CHUNK: usize = 1 << 12;
+
+/// A kind of circuit-agnostic correlated randomness.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Material {
+    /// Shares of `(a, b, ab)`.
+    Triple,
+    /// Shares of `(r, 1/r)`.
+    InvPair,
+    /// A share of a uniform `b` in `{0, 1}`.
+    Bit,
+}
+
+impl Material {
+    pub const ALL: [Material; 3] = [Material::Triple, Material::InvPair, Material::Bit];
+
+    /// How many field elements make up one record.
+    pub fn arity(&self) -> usize {
+        match self {
+            Material::Triple => 3,
+            Material::InvPair => 2,
+            Material::Bit => 1,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Material::Triple => "triples",
+            Material::InvPair => "inv_pairs",
+            Material::Bit => "bits",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|m| m.name() == s)
+    }
+
+    /// In a seed-compressed pool, the element of a record which the last party does not expand, but is
+    /// given, to make the record correct.
+    fn corrected(&self) -> usize {
+        match self {
+            Material::Triple => 2,
+            Material::InvPair => 1,
+            Material::Bit => 0,
+        }
+    }
+
+    fn plain<F: Field, R: Rng>(&self, rng: &mut R) -> Vec<F> {
+        match self {
+            Material::Triple => {
+                let a = F::rand(rng);
+                let b = F::rand(rng);
+                vec![a, b, a * b]
+            }
+            Material::InvPair => {
+                let r = loop {
+                    let r = F::rand(rng);
+                    if !r.is_zero() {
+                        break r;
+                    }
+                };
+                vec![r, r.inverse().unwrap()]
+            }
+            Material::Bit => vec![if rng.gen::<bool>() { F::one() } else { F::zero() }],
+        }
+    }
+}
+
+impl Display for Material {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn other_err<E: Display>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e.to_string())
+}
+
+/// Header of a pool data file.
+struct PoolHeader {
+    field: String,
+    elem_size: u64,
+    records: u64,
+}
+
+impl PoolHeader {
+    fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(POOL_MAGIC)?;
+        w.write_all(&(self.field.len() as u64).to_le_bytes())?;
+        w.write_all(self.field.as_bytes())?;
+        w.write_all(&self.elem_size.to_le_bytes())?;
+        w.write_all(&self.records.to_le_bytes())
+    }
+
+    fn read<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != POOL_MAGIC {
+            return Err(other_err("not a randomness pool file"));
+        }
+        let mut word = [0u8; 8];
+        r.read_exact(&mut word)?;
+        let mut field = vec![0u8; u64::from_le_bytes(word) as usize];
+        r.read_exact(&mut field)?;
+        r.read_exact(&mut word)?;
+        let elem_size = u64::from_le_bytes(word);
+        r.read_exact(&mut word)?;
+        let records = u64::from_le_bytes(word);
+        Ok(Self {
+            field: String::from_utf8(field).map_err(other_err)?,
+            elem_size,
+            records,
+        })
+    }
+
+    fn len(&self) -> u64 {
+        8 + 8 + self.field.len() as u64 + 8 + 8
+    }
+}
+
+/// A party's seed in a seed-compressed pool.
+struct Seed {
+    n_parties: u64,
+    bytes: [u8; 32],
+}
+
+impl Seed {
+    fn write<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(&self.n_parties.to_le_bytes())?;
+        w.write_all(&self.bytes)
+    }
+
+    fn read<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut word = [0u8; 8];
+        r.read_exact(&mut word)?;
+        let mut bytes = [0u8; 32];
+        r.read_exact(&mut bytes)?;
+        Ok(Self {
+            n_parties: u64::from_le_bytes(word),
+            bytes,
+        })
+    }
+
+    /// This party's (uncorrected) share of record `i` of `m`: uniform, and independent of every
+    /// other record, so records expand in any order.
+    fn expand<F: Field>(&self, m: Material, i: u64) -> Vec<F> {
+        let record_seed = Sha256::new()
+            .chain(&self.bytes)
+            .chain(m.name().as_bytes())
+            .chain(&i.to_le_bytes())
+            .finalize();
+        let mut rng_seed = <StdRng as SeedableRng>::Seed::default();
+        rng_seed.copy_from_slice(&record_seed);
+        let rng = &mut StdRng::from_seed(rng_seed);
+        (0..m.arity()).map(|_| F::rand(rng)).collect()
+    }
+
+    /// How many field elements per record the party stores explicitly.
+    fn stored(&self, party: usize) -> usize {
+        if party as u64 + 1 == self.n_parties {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// One party's view of a shared pool of preprocessing material.
+#[derive(Clone, Debug)]
+pub struct RandomnessPool {
+    dir: PathBuf,
+    party: usize,
+}
+
+impl RandomnessPool {
+    /// Deal `n` records of every kind of material, additively shared between `n_parties`, into
+    /// `dir`.
+    ///
+    /// This is a trusted dealer: fine for benchmarking, not for deployment.
+    pub fn deal<F: Field, R: Rng>(
+        dir: &Path,
+        n_parties: usize,
+        n: usize,
+        rng: &mut R,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let header = PoolHeader {
+            field: std::any::type_name::<F>().to_owned(),
+            elem_size: F::zero().serialized_size() as u64,
+            records: n as u64,
+        };
+        for m in &Material::ALL {
+            let mut files = (0..n_parties)
+                .map(|p| {
+                    let mut f = BufWriter::new(File::create(Self::data_path(dir, p, *m))?);
+                    header.write(&mut f)?;
+                    Ok(f)
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            for _ in 0..n {
+                for v in m.plain::<F, R>(rng) {
+                    let mut sum = F::zero();
+                    for (p, f) in files.iter_mut().enumerate() {
+                        let share = if p + 1 == n_parties { v - sum } else { F::rand(rng) };
+                        sum += share;
+                        share.serialize(&mut *f).map_err(other_err)?;
+                    }
+                }
+            }
+            for f in &mut files {
+                f.flush()?;
+            }
+        }
+        for p in 0..n_parties {
+            let pool = Self {
+                dir: dir.to_owned(),
+                party: p,
+            };
+            pool.write_cursors(&mut File::create(pool.cursor_path())?, &BTreeMap::new())?;
+            File::create(pool.ledger_path())?;
+        }
+        Ok(())
+    }
+
+    /// Like [RandomnessPool::deal], but parties `0..n_parties - 1` get just a seed each, and
+    /// expand their shares from it as they read them. The last party's shares are expanded from a
+    /// seed too, except for one element per record (e.g., its share of `ab` in a triple), which
+    /// is dealt to it in full, making the record correct.
+    ///
+    /// So the dealer ships a few dozen bytes to all parties but one, and a third (for triples) of
+    /// the usual material to that one. If the dealer is itself the last party, only the seeds
+    /// ever leave it. Either way it is still a trusted dealer.
+    pub fn deal_compressed<F: Field, R: Rng>(
+        dir: &Path,
+        n_parties: usize,
+        n: usize,
+        rng: &mut R,
+    ) -> io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let header = PoolHeader {
+            field: std::any::type_name::<F>().to_owned(),
+            elem_size: F::zero().serialized_size() as u64,
+            records: n as u64,
+        };
+        let seeds: Vec<Seed> = (0..n_parties)
+            .map(|_| Seed {
+                n_parties: n_parties as u64,
+                bytes: rng.gen(),
+            })
+            .collect();
+        for (p, seed) in seeds.iter().enumerate() {
+            seed.write(File::create(Self::seed_path(dir, p))?)?;
+        }
+        for m in &Material::ALL {
+            let mut files = (0..n_parties)
+                .map(|p| {
+                    let mut f = BufWriter::new(File::create(Self::data_path(dir, p, *m))?);
+                    header.write(&mut f)?;
+                    Ok(f)
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            let last = files.last_mut().unwrap();
+            for i in 0..n as u64 {
+                let shares: Vec<Vec<F>> = seeds.iter().map(|s| s.expand(*m, i)).collect();
+                let sum = |j: usize| shares.iter().map(|s| s[j]).sum::<F>();
+                let others = sum(m.corrected()) - shares[n_parties - 1][m.corrected()];
+                let value = match m {
+                    Material::Triple => sum(0) * sum(1),
+                    Material::InvPair => sum(0)
+                        .inverse()
+                        .ok_or_else(|| other_err("seeds expand to a zero inverse pair"))?,
+                    Material::Bit => m.plain::<F, R>(rng)[0],
+                };
+                (value - others).serialize(&mut *last).map_err(other_err)?;
+            }
+            for f in &mut files {
+                f.flush()?;
+            }
+        }
+        for p in 0..n_parties {
+            let pool = Self {
+                dir: dir.to_owned(),
+                party: p,
+            };
+            pool.write_cursors(&mut File::create(pool.cursor_path())?, &BTreeMap::new())?;
+            File::create(pool.ledger_path())?;
+        }
+        Ok(())
+    }
+
+    /// Open the pool for party `party` in `dir`.
+    pub fn open(dir: &Path, party: usize) -> io::Result<Self> {
+        let pool = Self {
+            dir: dir.to_owned(),
+            party,
+        };
+        for m in &Material::ALL {
+            pool.header(*m)?;
+        }
+        File::open(pool.cursor_path())?;
+        Ok(pool)
+    }
+
+    fn data_path(dir: &Path, party: usize, m: Material) -> PathBuf {
+        dir.join(format!("{}.{}", m.name(), party))
+    }
+
+    fn seed_path(dir: &Path, party: usize) -> PathBuf {
+        dir.join(format!("seed.{}", party))
+    }
+
+    /// This party's seed, if the pool is seed-compressed.
+    fn seed(&self) -> io::Result<Option<Seed>> {
+        match File::open(Self::seed_path(&self.dir, self.party)) {
+            Ok(f) => Seed::read(f).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn cursor_path(&self) -> PathBuf {
+        self.dir.join(format!("cursor.{}", self.party))
+    }
+
+    fn ledger_path(&self) -> PathBuf {
+        self.dir.join(format!("ledger.{}", self.party))
+    }
+
+    fn header(&self, m: Material) -> io::Result<PoolHeader> {
+        PoolHeader::read(File::open(Self::data_path(&self.dir, self.party, m))?)
+    }
+
+    /// The name of the field this pool was dealt over.
+    pub fn field_name(&self) -> io::Result<String> {
+        Ok(self.header(Material::Triple)?.field)
+    }
+
+    /// The records of each kind that have been claimed, as sorted, disjoint ranges.
+    ///
+    /// Each line names a kind, then its ranges as `start-end`. (A bare number `n`, as older pools
+    /// wrote, is the range `0-n`.)
+    fn read_cursors(&self, f: &mut File) -> io::Result<BTreeMap<Material, Vec<Range<u64>>>> {
+        let mut s = String::new();
+        f.seek(SeekFrom::Start(0))?;
+        f.read_to_string(&mut s)?;
+        let parse = |n: &str| n.parse::<u64>().map_err(other_err);
+        let mut cursors = BTreeMap::new();
+        for line in s.lines() {
+            let mut words = line.split_whitespace();
+            let name = match words.next() {
+                Some(name) => name,
+                None => continue,
+            };
+            let m = Material::from_name(name).ok_or_else(|| other_err("bad cursor file"))?;
+            let mut claimed = Vec::new();
+            for word in words {
+                let range = match word.split_once('-') {
+                    Some((start, end)) => parse(start)?..parse(end)?,
+                    None => 0..parse(word)?,
+                };
+                add_claim(&mut claimed, range);
+            }
+            cursors.insert(m, claimed);
+        }
+        Ok(cursors)
+    }
+
+    fn write_cursors(
+        &self,
+        f: &mut File,
+        cursors: &BTreeMap<Material, Vec<Range<u64>>>,
+    ) -> io::Result<()> {
+        let mut s = String::new();
+        for m in &Material::ALL {
+            s.push_str(m.name());
+            for r in cursors.get(m).map_or(&[][..], |c| &c[..]) {
+                s.push_str(&format!(" {}-{}", r.start, r.end));
+            }
+            s.push('\n');
+        }
+        f.set_len(0)?;
+        f.seek(SeekFrom::Start(0))?;
+        f.write_all(s.as_bytes())?;
+        f.sync_data()
+    }
+
+    /// Run `f` on the claimed records of `m` while holding the pool's exclusive lock, claim the
+    /// range it returns, then log that to the ledger under `session`.
+    fn locked_update(
+        &self,
+        session: &str,
+        m: Material,
+        f: impl FnOnce(&[Range<u64>]) -> io::Result<Range<u64>>,
+    ) -> io::Result<Range<u64>> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(self.cursor_path())?;
+        file.lock()?;
+        let mut cursors = self.read_cursors(&mut file)?;
+        let claimed = cursors.entry(m).or_default();
+        let range = f(claimed)?;
+        if range.end > self.header(m)?.records {
+            return Err(other_err(format!(
+                "randomness pool exhausted: {} {} requested, {} dealt",
+                range.end,
+                m,
+                self.header(m)?.records
+            )));
+        }
+        add_claim(claimed, range.clone());
+        self.write_cursors(&mut file, &cursors)?;
+        let mut ledger = OpenOptions::new().append(true).open(self.ledger_path())?;
+        writeln!(ledger, "{} {} {} {}", session, m.name(), range.start, range.end - range.start)?;
+        debug!("Pool: {} reserved {} {:?}", session, m, range);
+        Ok(range)
+    }
+
+    /// Reserve the next `n` unused records of `m` for `session`.
+    pub fn reserve(&self, session: &str, m: Material, n: usize) -> io::Result<Range<u64>> {
+        self.locked_update(session, m, |claimed| {
+            let next = claimed.last().map_or(0, |r| r.end);
+            Ok(next..next + n as u64)
+        })
+    }
+
+    /// Record that `session` consumes `range` of `m`, as chosen by another party.
+    ///
+    /// Ranges may be claimed in any order (concurrent sessions may learn theirs out of order),
+    /// but not twice.
+    pub fn claim(&self, session: &str, m: Material, range: Range<u64>) -> io::Result<()> {
+        self.locked_update(session, m, |claimed| {
+            match claimed
+                .iter()
+                .find(|r| r.start < range.end && range.start < r.end)
+            {
+                Some(r) => Err(other_err(format!(
+                    "{} {:?} were already consumed (claimed {:?})",
+                    m, range, r
+                ))),
+                None => Ok(range),
+            }
+        })
+        .map(|_| ())
+    }
+
+    /// Read the field elements of records `range` of `m`.
+    pub fn read<F: Field>(&self, m: Material, range: Range<u64>) -> io::Result<Vec<F>> {
+        let mut f = File::open(Self::data_path(&self.dir, self.party, m))?;
+        let header = PoolHeader::read(&mut f)?;
+        if header.field != std::any::type_name::<F>() {
+            return Err(other_err(format!(
+                "randomness pool holds {}, not {}",
+                header.field,
+                std::any::type_name::<F>()
+            )));
+        }
+        let seed = self.seed()?;
+        let stored = match &seed {
+            Some(seed) => seed.stored(self.party),
+            None => m.arity(),
+        };
+        let record_size = header.elem_size * stored as u64;
+        f.seek(SeekFrom::Start(header.len() + range.start * record_size))?;
+        let mut r = BufReader::new(f);
+        let elems = (0..(range.end - range.start) as usize * stored)
+            .map(|_| F::deserialize(&mut r).map_err(other_err))
+            .collect::<io::Result<Vec<F>>>()?;
+        let seed = match seed {
+            Some(seed) => seed,
+            None => return Ok(elems),
+        };
+        let mut corrections = elems.into_iter();
+        let mut out = Vec::with_capacity((range.end - range.start) as usize * m.arity());
+        for i in range {
+            let mut record = seed.expand::<F>(m, i);
+            if stored == 1 {
+                record[m.corrected()] = corrections.next().unwrap();
+            }
+            out.extend(record);
+        }
+        Ok(out)
+    }
+
+    /// How many records of `m` have not been reserved yet.
+    pub fn remaining(&self, m: Material) -> io::Result<u64> {
+        let mut file = File::open(self.cursor_path())?;
+        let claimed = self.read_cursors(&mut file)?.remove(&m).unwrap_or_default();
+        let used: u64 = claimed.iter().map(|r| r.end - r.start).sum();
+        Ok(self.header(m)?.records - used)
+    }
+
+    /// Total consumption per session, per kind of material.
+    pub fn usage(&self) -> io::Result<BTreeMap<String, BTreeMap<Material, u64>>> {
+        let mut usage: BTreeMap<String, BTreeMap<Material, u64>> = BTreeMap::new();
+        for line in BufReader::new(File::open(self.ledger_path())?).lines() {
+            let line = line?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            if words.len() != 4 {
+                return Err(other_err(format!("bad ledger line: {}", line)));
+            }
+            let m = Material::from_name(words[1]).ok_or_else(|| other_err("bad ledger line"))?;
+            let n: u64 = words[3].parse().map_err(other_err)?;
+            *usage
+                .entry(words[0].to_owned())
+                .or_default()
+                .entry(m)
+                .or_default() += n;
+        }
+        Ok(usage)
+    }
+}
+
+/// Add `range` to `claimed`, sorted, disjoint ranges, merging it with those it touches.
+fn add_claim(claimed: &mut Vec<Range<u64>>, range: Range<u64>) {
+    if range.start >= range.end {
+        return;
+    }
+    let i = claimed.partition_point(|r| r.end < range.start);
+    let j = claimed.partition_point(|r| r.start <= range.end);
+    let merged = if i < j {
+        claimed[i].start.min(range.start)..claimed[j - 1].end.max(range.end)
+    } else {
+        range
+    };
+    claimed.splice(i..j, std::iter::once(merged));
+}
+
+/// A proving session's stream of material from a [RandomnessPool].
+///
+/// Records are reserved from the pool in chunks. All parties must draw the same material in the
+/// same order (which they do, since they run the same code), because each refill is a round in
+/// which the king announces the reserved range.
+pub struct PoolSession {
+    pool: RandomnessPool,
+    session: String,
+    chunk: usize,
+    field: String,
+    buffers: BTreeMap<Material, (Vec<u8>, usize)>,
+    used: BTreeMap<Material, u64>,
+}
+
+impl PoolSession {
+    pub fn new(pool: RandomnessPool, session: &str, chunk: usize) -> io::Result<Self> {
+        Ok(Self {
+            field: pool.field_name()?,
+            pool,
+            session: session.to_owned(),
+            chunk,
+            buffers: BTreeMap::new(),
+            used: BTreeMap::new(),
+        })
+    }
+
+    /// Does this session serve material over `F`?
+    pub fn serves<F: Field>(&self) -> bool {
+        self.field == std::any::type_name::<F>()
+    }
+
+    /// How many records of each kind this session has consumed.
+    pub fn used(&self) -> &BTreeMap<Material, u64> {
+        &self.used
+    }
+
+    fn refill<F: Field>(&mut self, m: Material) -> io::Result<()> {
+        let n = self.chunk;
+        let start = if Net::am_king() {
+            Some(self.pool.reserve(&self.session, m, n)?.start)
+        } else {
+            None
+        };
+        let start: u64 = Net::recv_from_king(start.map(|s| vec![s; Net::n_parties()]));
+        let range = start..start + n as u64;
+        if !Net::am_king() {
+            self.pool.claim(&self.session, m, range.clone())?;
+        }
+        let mut bytes = Vec::new();
+        for v in self.pool.read::<F>(m, range)? {
+            v.serialize(&mut bytes).map_err(other_err)?;
+        }
+        self.buffers.insert(m, (bytes, 0));
+        Ok(())
+    }
+
+    /// Take the next record of `m`.
+    pub fn take<F: Field>(&mut self, m: Material) -> Vec<F> {
+        let record_size = F::zero().serialized_size() * m.arity();
+        let exhausted = match self.buffers.get(&m) {
+            Some((bytes, offset)) => offset + record_size > bytes.len(),
+            None => true,
+        };
+        if exhausted {
+            self.refill::<F>(m)
+                .unwrap_or_else(|e| panic!("Could not draw {} from the pool: {}", m, e));
+        }
+        let (bytes, offset) = self.buffers.get_mut(&m).unwrap();
+        let mut r = &bytes[*offset..*offset + record_size];
+        *offset += record_size;
+        *self.used.entry(m).or_default() += 1;
+        (0..m.arity())
+            .map(|_| F::deserialize(&mut r).unwrap())
+            .collect()
+    }
+}
+
+lazy_static! {
+    static ref SESSION: Mutex<Option<PoolSession>> = Mutex::new(None);
+}
+
+/// Make `session` the source of preprocessing material for this process.
+pub fn install(session: PoolSession) {
+    let mut s = SESSION.lock().unwrap();
+    assert!(s.is_none(), "A pool session is already installed");
+    *s = Some(session);
+}
+
+/// Stop drawing from the installed session, returning it.
+pub fn uninstall() -> Option<PoolSession> {
+    SESSION.lock().unwrap().take()
+}
+
+/// Is a pool session installed?
+pub fn is_installed() -> bool {
+    SESSION.lock().unwrap().is_some()
+}
+
+/// Take the next record of `m` over `F` from the installed session, if it serves `F`.
+pub fn take_installed<F: Field>(m: Material) -> Option<Vec<F>> {
+    let mut s = SESSION.lock().unwrap();
+    match s.as_mut() {
+        Some(session) if session.serves::<F>() => Some(session.take::<F>(m)),
+        _ => None,
+    }
+}
+
+/// `n` shared uniformly random bits, from the installed session if it serves `T`.
+///
+/// Without one, the king deals them, knowing their values; like [DummyFieldTripleSource], this is
+/// only suitable for benchmarking.
+pub fn random_bits<T: Field, S: FieldShare<T>>(n: usize) -> Vec<S> {
+    mpc_net::metrics::inc(BITS_METRIC, "Shared random bits consumed.", n as u64);
+    let pooled: Option<Vec<T>> = (0..n)
+        .map(|_| take_installed::<T>(Material::Bit).map(|v| v[0]))
+        .collect();
+    match pooled {
+        Some(bits) => bits.into_iter().map(S::from_add_shared).collect(),
+        None => {
+            KING_DEALT.take();
+            let rng = &mut rand::thread_rng();
+            let bits = (0..n)
+                .map(|_| if rng.gen::<bool>() { T::one() } else { T::zero() })
+                .collect();
+            S::king_share_batch(bits, rng)
+        }
+    }
+}
+
+const TRIPLES_METRIC: &str = "mpc_triples_consumed_total";
+const INV_PAIRS_METRIC: &str = "mpc_inverse_pairs_consumed_total";
+const BITS_METRIC: &str = "mpc_random_bits_consumed_total";
+
+/// Beaver source which draws from the installed [PoolSession], falling back to
+/// [DummyFieldTripleSource] when no session for this field is installed.
+#[derive(Derivative)]
+#[derivative(Default(bound = ""), Clone(bound = ""), Copy(bound = ""))]
+pub struct SessionTripleSource<T, S> {
+    _scalar: PhantomData<T>,
+    _share: PhantomData<S>,
+}
+
+impl<T: Field, S: FieldShare<T>> BeaverSource<S, S, S> for SessionTripleSource<T, S> {
+    #[inline]
+    fn triple(&mut self) -> (S, S, S) {
+        mpc_net::metrics::inc(TRIPLES_METRIC, "Beaver triples consumed.", 1);
+        match take_installed::<T>(Material::Triple) {
+            Some(v) => (
+                S::from_add_shared(v[0]),
+                S::from_add_shared(v[1]),
+                S::from_add_shared(v[2]),
+            ),
+            None => DummyFieldTripleSource::<T, S>::default().triple(),
+        }
+    }
+    #[inline]
+    fn inv_pair(&mut self) -> (S, S) {
+        mpc_net::metrics::inc(INV_PAIRS_METRIC, "Inverse pairs consumed.", 1);
+        match take_installed::<T>(Material::InvPair) {
+            Some(v) => (S::from_add_shared(v[0]), S::from_add_shared(v[1])),
+            None => DummyFieldTripleSource::<T, S>::default().inv_pair(),
+        }
+    }
+}
+
+/// Seeds the public base of group triples; any fixed seed does.
+const GROUP_TRIPLE_BASE_SEED: u64 = 0x6d70_632d_6261_7365;
+
+/// Beaver source of group triples `(aP, b, abP)`, for a shared group element times a shared
+/// scalar (see [GroupShare::scale]).
+///
+/// They are made locally from field triples `(a, b, ab)`, drawn from [SessionTripleSource], and a
+/// fixed public base `P`: since `a` is uniform and secret, so is `aP`. They are as good as the
+/// field triples they come from.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Copy(bound = ""))]
+pub struct SessionGroupTripleSource<G: Group, S: GroupShare<G>> {
+    base: G,
+    field: SessionTripleSource<G::ScalarField, S::FieldShare>,
+}
+
+impl<G: Group, S: GroupShare<G>> Default for SessionGroupTripleSource<G, S> {
+    fn default() -> Self {
+        Self {
+            base: G::rand(&mut StdRng::seed_from_u64(GROUP_TRIPLE_BASE_SEED)),
+            field: SessionTripleSource::default(),
+        }
+    }
+}
+
+impl<G: Group, S: GroupShare<G>> BeaverSource<S, S::FieldShare, S>
+    for SessionGroupTripleSource<G, S>
+{
+    #[inline]
+    fn triple(&mut self) -> (S, S::FieldShare, S) {
+        let (a, b, ab) = self.field.triple();
+        (
+            S::scale_pub_group(self.base, &a),
+            b,
+            S::scale_pub_group(self.base, &ab),
+        )
+    }
+    #[inline]
+    fn inv_pair(&mut self) -> (S::FieldShare, S::FieldShare) {
+        self.field.inv_pair()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_serialize::CanonicalSerialize;
+
+    fn tmp_pool_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("mpc-pool-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn dealt_shares_reconstruct() {
+        let dir = tmp_pool_dir("reconstruct");
+        let rng = &mut ark_std::test_rng();
+        RandomnessPool::deal::<Fr, _>(&dir, 2, 8, rng).unwrap();
+        let p0 = RandomnessPool::open(&dir, 0).unwrap();
+        let p1 = RandomnessPool::open(&dir, 1).unwrap();
+        let t0 = p0.read::<Fr>(Material::Triple, 0..8).unwrap();
+        let t1 = p1.read::<Fr>(Material::Triple, 0..8).unwrap();
+        for (a, b) in t0.chunks(3).zip(t1.chunks(3)) {
+            assert_eq!((a[0] + b[0]) * (a[1] + b[1]), a[2] + b[2]);
+        }
+        let i0 = p0.read::<Fr>(Material::InvPair, 3..5).unwrap();
+        let i1 = p1.read::<Fr>(Material::InvPair, 3..5).unwrap();
+        for (a, b) in i0.chunks(2).zip(i1.chunks(2)) {
+            assert_eq!((a[0] + b[0]) * (a[1] + b[1]), Fr::one());
+        }
+        for (a, b) in p0
+            .read::<Fr>(Material::Bit, 0..8)
+            .unwrap()
+            .into_iter()
+            .zip(p1.read::<Fr>(Material::Bit, 0..8).unwrap())
+        {
+            let bit = a + b;
+            assert!(bit.is_zero() || bit.is_one());
+        }
+        assert!(p0.read::<ark_bls12_377::Fq>(Material::Bit, 0..1).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn compressed_shares_reconstruct() {
+        let dir = tmp_pool_dir("compressed");
+        let rng = &mut ark_std::test_rng();
+        RandomnessPool::deal_compressed::<Fr, _>(&dir, 3, 8, rng).unwrap();
+        let pools: Vec<_> = (0..3)
+            .map(|p| RandomnessPool::open(&dir, p).unwrap())
+            .collect();
+        // Expanding a range on its own gives the same shares as expanding it as part of another.
+        let read = |m, range: Range<u64>| -> Vec<Vec<Fr>> {
+            pools.iter().map(|p| p.read(m, range.clone()).unwrap()).collect()
+        };
+        assert_eq!(read(Material::Triple, 2..5)[1], read(Material::Triple, 0..8)[1][6..15]);
+        let sum = |shares: Vec<Vec<Fr>>| -> Vec<Fr> {
+            (0..shares[0].len())
+                .map(|j| shares.iter().map(|s| s[j]).sum())
+                .collect()
+        };
+        for t in sum(read(Material::Triple, 0..8)).chunks(3) {
+            assert_eq!(t[0] * t[1], t[2]);
+        }
+        for i in sum(read(Material::InvPair, 3..6)).chunks(2) {
+            assert_eq!(i[0] * i[1], Fr::one());
+        }
+        for b in sum(read(Material::Bit, 0..8)) {
+            assert!(b.is_zero() || b.is_one());
+        }
+        // All but the last party store nothing but a seed.
+        let stored = |p: usize| {
+            std::fs::metadata(RandomnessPool::data_path(&dir, p, Material::Triple))
+                .unwrap()
+                .len()
+        };
+        assert_eq!(stored(0), stored(1));
+        assert_eq!(stored(2) - stored(0), 8 * Fr::zero().serialized_size() as u64);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reservations_are_disjoint_and_accounted() {
+        let dir = tmp_pool_dir("accounting");
+        let rng = &mut ark_std::test_rng();
+        RandomnessPool::deal::<Fr, _>(&dir, 2, 10, rng).unwrap();
+        let king = RandomnessPool::open(&dir, 0).unwrap();
+        let other = RandomnessPool::open(&dir, 1).unwrap();
+        let a = king.reserve("a", Material::Triple, 4).unwrap();
+        let b = king.reserve("b", Material::Triple, 3).unwrap();
+        assert_eq!(a, 0..4);
+        assert_eq!(b, 4..7);
+        other.claim("a", Material::Triple, a.clone()).unwrap();
+        other.claim("b", Material::Triple, b).unwrap();
+        assert!(other.claim("c", Material::Triple, a).is_err());
+        assert!(king.reserve("c", Material::Triple, 4).is_err());
+        assert_eq!(king.remaining(Material::Triple).unwrap(), 3);
+        assert_eq!(king.remaining(Material::Bit).unwrap(), 10);
+        let usage = king.usage().unwrap();
+        assert_eq!(usage["a"][&Material::Triple], 4);
+        assert_eq!(usage["b"][&Material::Triple], 3);
+        assert!(!usage.contains_key("c"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn reservations_can_be_claimed_out_of_order() {
+        let dir = tmp_pool_dir("out-of-order");
+        let rng = &mut ark_std::test_rng();
+        RandomnessPool::deal::<Fr, _>(&dir, 2, 10, rng).unwrap();
+        let king = RandomnessPool::open(&dir, 0).unwrap();
+        let other = RandomnessPool::open(&dir, 1).unwrap();
+        let a = king.reserve("a", Material::Triple, 3).unwrap();
+        let b = king.reserve("b", Material::Triple, 3).unwrap();
+        let c = king.reserve("c", Material::Triple, 3).unwrap();
+        // Sessions b and c learn their ranges before a does.
+        other.claim("c", Material::Triple, c.clone()).unwrap();
+        other.claim("b", Material::Triple, b).unwrap();
+        assert_eq!(other.remaining(Material::Triple).unwrap(), 4);
+        other.claim("a", Material::Triple, a).unwrap();
+        assert_eq!(other.remaining(Material::Triple).unwrap(), 1);
+        // Overlapping any claimed range is still refused.
+        assert!(other.claim("d", Material::Triple, 8..10).is_err());
+        assert_eq!(other.reserve("d", Material::Triple, 1).unwrap(), 9..10);
+        let mut file = File::open(other.cursor_path()).unwrap();
+        let cursors = other.read_cursors(&mut file).unwrap();
+        assert_eq!(cursors[&Material::Triple], vec![0..10]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn claims_merge() {
+        let mut claimed = Vec::new();
+        add_claim(&mut claimed, 6..8);
+        add_claim(&mut claimed, 0..2);
+        add_claim(&mut claimed, 3..4);
+        assert_eq!(claimed, vec![0..2, 3..4, 6..8]);
+        add_claim(&mut claimed, 2..3);
+        assert_eq!(claimed, vec![0..4, 6..8]);
+        add_claim(&mut claimed, 5..9);
+        assert_eq!(claimed, vec![0..4, 5..9]);
+    }
+}
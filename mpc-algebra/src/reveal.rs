@@ -1,6 +1,48 @@
 #![macro_use]
 use ark_std::{collections::BTreeMap, marker::PhantomData, rc::Rc};
+use mpc_net::{MpcMultiNet, MpcNet};
 use rand::Rng;
+use std::fmt::{self, Display, Formatter};
+
+/// A shared value, where a public one was expected (see [Reveal::try_unwrap_as_public]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotPublic {
+    pub type_name: &'static str,
+}
+
+impl NotPublic {
+    pub fn of<T>() -> Self {
+        Self {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+}
+
+impl Display for NotPublic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected a public {}, but it is shared", self.type_name)
+    }
+}
+
+impl std::error::Error for NotPublic {}
+
+/// `Some(x)` for `party`, and `None` for the others: what [Reveal::reveal_to] gives for public
+/// data.
+pub fn public_to<T>(party: usize, x: T) -> Option<T> {
+    if MpcMultiNet::party_id() == party {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+/// The counter of values the king secret-shares (see [Reveal::king_share]).
+pub const INPUTS_METRIC: &str = "mpc_inputs_shared_total";
+
+/// Count `n` values as secret-shared by the king; the wire types call this from their sharings.
+pub fn count_inputs(n: usize) {
+    mpc_net::metrics::inc(INPUTS_METRIC, "Values secret-shared by the king.", n as u64);
+}
 
 /// A type should implement [Reveal] if it represents the MPC abstraction of some base type.
 ///
@@ -17,6 +59,11 @@ pub trait Reveal: Sized {
 
     /// Reveal shared data, yielding plain data.
     fn reveal(self) -> Self::Base;
+    /// Reveal shared data to `party` alone, which gets the plain data; the other parties only
+    /// learn that the opening completed, and get `None`.
+    fn reveal_to(self, _party: usize) -> Option<Self::Base> {
+        unimplemented!("No reveal to one party for {}", std::any::type_name::<Self>())
+    }
     /// Construct a share of the sum of the `b` over all machines in the protocol.
     fn from_add_shared(b: Self::Base) -> Self;
     /// Lift public data (same in all machines) into shared data.
@@ -24,9 +71,21 @@ pub trait Reveal: Sized {
     /// If this share type has some underlying value of the base type, grabs it.
     ///
     /// The semantics of this are highly dependent on the sharing system.
-    fn unwrap_as_public(self) -> Self::Base {
+    ///
+    /// # Safety
+    ///
+    /// This is memory safe, but not share safe: for shared data, it yields this party's share as
+    /// if it were the value, and publishing that leaks the share. Only call it to compute locally
+    /// on a share; use [Reveal::try_unwrap_as_public] to get a value that should be public.
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         unimplemented!("No unwrap as public for {}", std::any::type_name::<Self>())
     }
+    /// The underlying value, if it is public, or [NotPublic] if it is shared.
+    ///
+    /// Types that are always shared need not implement this.
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        Err(NotPublic::of::<Self>())
+    }
     /// Have the king share their `b` value, and send shares to all parties.
     fn king_share<R: Rng>(_b: Self::Base, _rng: &mut R) -> Self {
         unimplemented!("No king share for {}", std::any::type_name::<Self>())
@@ -49,6 +108,10 @@ impl Reveal for usize {
         self
     }
 
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        public_to(party, self)
+    }
+
     fn from_add_shared(b: Self::Base) -> Self {
         b
     }
@@ -57,10 +120,14 @@ impl Reveal for usize {
         b
     }
 
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         self
     }
 
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        Ok(self)
+    }
+
     fn king_share<R: Rng>(b: Self::Base, _rng: &mut R) -> Self {
         b
     }
@@ -73,6 +140,10 @@ impl<T: Reveal> Reveal for PhantomData<T> {
         PhantomData::default()
     }
 
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        public_to(party, PhantomData::default())
+    }
+
     fn from_add_shared(_b: Self::Base) -> Self {
         PhantomData::default()
     }
@@ -80,9 +151,12 @@ impl<T: Reveal> Reveal for PhantomData<T> {
     fn from_public(_b: Self::Base) -> Self {
         PhantomData::default()
     }
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         PhantomData::default()
     }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        Ok(PhantomData::default())
+    }
     fn king_share<R: Rng>(_b: Self::Base, _rng: &mut R) -> Self {
         PhantomData::default()
     }
@@ -101,22 +175,23 @@ impl<T: Reveal> Reveal for Vec<T> {
     fn reveal(self) -> Self::Base {
         self.into_iter().map(|x| x.reveal()).collect()
     }
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        // Every element takes part in its opening, so collect them all before combining.
+        let opened: Vec<Option<T::Base>> = self.into_iter().map(|x| x.reveal_to(party)).collect();
+        opened.into_iter().collect()
+    }
     fn from_public(other: Self::Base) -> Self {
-        other
-            .into_iter()
-            .map(|x| <T as Reveal>::from_public(x))
-            .collect()
+        map_releasing(other, <T as Reveal>::from_public)
     }
     fn from_add_shared(other: Self::Base) -> Self {
-        other
-            .into_iter()
-            .map(|x| <T as Reveal>::from_add_shared(x))
-            .collect()
+        map_releasing(other, <T as Reveal>::from_add_shared)
     }
-    fn unwrap_as_public(self) -> Self::Base {
-        self
-            .into_iter()
-            .map(|x| <T as Reveal>::unwrap_as_public(x))
+    unsafe fn unwrap_as_public(self) -> Self::Base {
+        map_releasing(self, |x| <T as Reveal>::unwrap_as_public(x))
+    }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        self.into_iter()
+            .map(|x| <T as Reveal>::try_unwrap_as_public(x))
             .collect()
     }
     fn king_share<R: Rng>(b: Self::Base, rng: &mut R) -> Self {
@@ -132,6 +207,27 @@ impl<T: Reveal> Reveal for Vec<T> {
     }
 }
 
+/// How many elements [map_releasing] converts between shrinking its input.
+const RELEASE_EVERY: usize = 1 << 16;
+
+/// `v.into_iter().map(f).collect()`, but giving back `v`'s memory as it goes.
+///
+/// Collecting holds all of `v` until the output is complete, so lifting a large key (a tree of
+/// vectors) would need the old and the new key in memory at once. Instead, elements are taken from
+/// the back, and `v` shrinks as it empties; the output is only touched as it fills, so the peak is
+/// about the larger of the two.
+fn map_releasing<A, B>(mut v: Vec<A>, mut f: impl FnMut(A) -> B) -> Vec<B> {
+    let mut out = Vec::with_capacity(v.len());
+    while let Some(x) = v.pop() {
+        out.push(f(x));
+        if v.len() % RELEASE_EVERY == 0 {
+            v.shrink_to_fit();
+        }
+    }
+    out.reverse();
+    out
+}
+
 impl<K: Reveal + Ord, V: Reveal> Reveal for BTreeMap<K, V>
 where
     K::Base: Ord,
@@ -140,6 +236,11 @@ where
     fn reveal(self) -> Self::Base {
         self.into_iter().map(|x| x.reveal()).collect()
     }
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        let opened: Vec<Option<(K::Base, V::Base)>> =
+            self.into_iter().map(|x| x.reveal_to(party)).collect();
+        opened.into_iter().collect()
+    }
     fn from_public(other: Self::Base) -> Self {
         other.into_iter().map(|x| Reveal::from_public(x)).collect()
     }
@@ -149,12 +250,17 @@ where
             .map(|x| Reveal::from_add_shared(x))
             .collect()
     }
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         self
             .into_iter()
             .map(|x| Reveal::unwrap_as_public(x))
             .collect()
     }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        self.into_iter()
+            .map(|x| Reveal::try_unwrap_as_public(x))
+            .collect()
+    }
 
     fn init_protocol() {
         K::init_protocol();
@@ -172,16 +278,25 @@ impl<T: Reveal> Reveal for Option<T> {
     fn reveal(self) -> Self::Base {
         self.map(|x| x.reveal())
     }
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        match self {
+            Some(x) => x.reveal_to(party).map(Some),
+            None => public_to(party, None),
+        }
+    }
     fn from_public(other: Self::Base) -> Self {
         other.map(|x| <T as Reveal>::from_public(x))
     }
     fn from_add_shared(other: Self::Base) -> Self {
         other.map(|x| <T as Reveal>::from_add_shared(x))
     }
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         self
             .map(|x| Reveal::unwrap_as_public(x))
     }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        self.map(|x| Reveal::try_unwrap_as_public(x)).transpose()
+    }
     fn init_protocol() {
         T::init_protocol()
     }
@@ -198,15 +313,21 @@ where
     fn reveal(self) -> Self::Base {
         Rc::new((*self).clone().reveal())
     }
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        (*self).clone().reveal_to(party).map(Rc::new)
+    }
     fn from_public(other: Self::Base) -> Self {
         Rc::new(Reveal::from_public((*other).clone()))
     }
     fn from_add_shared(other: Self::Base) -> Self {
         Rc::new(Reveal::from_add_shared((*other).clone()))
     }
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         Rc::new((*self).clone().unwrap_as_public())
     }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        Ok(Rc::new((*self).clone().try_unwrap_as_public()?))
+    }
     fn init_protocol() {
         T::init_protocol()
     }
@@ -220,6 +341,10 @@ impl<A: Reveal, B: Reveal> Reveal for (A, B) {
     fn reveal(self) -> Self::Base {
         (self.0.reveal(), self.1.reveal())
     }
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        let (a, b) = (self.0.reveal_to(party), self.1.reveal_to(party));
+        Some((a?, b?))
+    }
     fn from_public(other: Self::Base) -> Self {
         (
             <A as Reveal>::from_public(other.0),
@@ -232,9 +357,12 @@ impl<A: Reveal, B: Reveal> Reveal for (A, B) {
             <B as Reveal>::from_add_shared(other.1),
         )
     }
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         (self.0.unwrap_as_public(), self.1.unwrap_as_public())
     }
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        Ok((self.0.try_unwrap_as_public()?, self.1.try_unwrap_as_public()?))
+    }
     fn init_protocol() {
         A::init_protocol();
         B::init_protocol();
@@ -256,6 +384,20 @@ macro_rules! struct_reveal_impl {
                 )*
             }
         }
+        fn reveal_to(self, party: usize) -> Option<Self::Base> {
+            // Open every field before giving up on any, so that all parties take part in each.
+            $(
+                let $x = self.$x.reveal_to(party);
+            )*
+            let r = {
+                $con {
+                    $(
+                        $x: $x?,
+                    )*
+                }
+            };
+            Some(r)
+        }
         fn from_public(other: Self::Base) -> Self {
             $con {
                 $(
@@ -270,13 +412,30 @@ macro_rules! struct_reveal_impl {
                 )*
             }
         }
-        fn unwrap_as_public(self) -> Self::Base {
+        unsafe fn unwrap_as_public(self) -> Self::Base {
             $con {
                 $(
                     $x: self.$x.unwrap_as_public(),
                 )*
             }
         }
+        fn try_unwrap_as_public(self) -> Result<Self::Base, $crate::NotPublic> {
+            let r = {
+                $con {
+                    $(
+                        $x: self.$x.try_unwrap_as_public()?,
+                    )*
+                }
+            };
+            Ok(r)
+        }
+        fn king_share<R: ark_std::rand::Rng>(other: Self::Base, rng: &mut R) -> Self {
+            $con {
+                $(
+                    $x: Reveal::king_share(other.$x, rng),
+                )*
+            }
+        }
     }
 }
 
@@ -290,6 +449,20 @@ macro_rules! struct_reveal_simp_impl {
                 )*
             }
         }
+        fn reveal_to(self, party: usize) -> Option<Self::Base> {
+            // Open every field before giving up on any, so that all parties take part in each.
+            $(
+                let $x = self.$x.reveal_to(party);
+            )*
+            let r = {
+                $con {
+                    $(
+                        $x: $x?,
+                    )*
+                }
+            };
+            Some(r)
+        }
         fn from_public(other: Self::Base) -> Self {
             $con {
                 $(
@@ -304,13 +477,30 @@ macro_rules! struct_reveal_simp_impl {
                 )*
             }
         }
-        fn unwrap_as_public(self) -> Self::Base {
+        unsafe fn unwrap_as_public(self) -> Self::Base {
             $con {
                 $(
                     $x: self.$x.unwrap_as_public(),
                 )*
             }
         }
+        fn try_unwrap_as_public(self) -> Result<Self::Base, $crate::NotPublic> {
+            let r = {
+                $con {
+                    $(
+                        $x: self.$x.try_unwrap_as_public()?,
+                    )*
+                }
+            };
+            Ok(r)
+        }
+        fn king_share<R: ark_std::rand::Rng>(other: Self::Base, rng: &mut R) -> Self {
+            $con {
+                $(
+                    $x: Reveal::king_share(other.$x, rng),
+                )*
+            }
+        }
     }
 }
 
@@ -320,3 +510,32 @@ macro_rules! dbg_disp {
         println!("{}: {}", std::stringify!($e), &$e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::honest_but_curious::MpcField;
+    use ark_bls12_377::Fr;
+
+    #[test]
+    fn shared_values_are_not_public() {
+        let public = MpcField::<Fr>::from_public(Fr::from(3u64));
+        let shared = MpcField::<Fr>::from_add_shared(Fr::from(3u64));
+        assert_eq!(public.try_unwrap_as_public(), Ok(Fr::from(3u64)));
+        assert!(shared.try_unwrap_as_public().is_err());
+        assert!(vec![public, shared].try_unwrap_as_public().is_err());
+        assert_eq!(
+            (public, Some(public)).try_unwrap_as_public(),
+            Ok((Fr::from(3u64), Some(Fr::from(3u64))))
+        );
+    }
+
+    #[test]
+    fn lifting_keeps_order() {
+        let xs: Vec<Vec<Fr>> = (0..3)
+            .map(|i| (0..RELEASE_EVERY as u64 + i).map(Fr::from).collect())
+            .collect();
+        let lifted = Vec::<Vec<MpcField<Fr>>>::from_public(xs.clone());
+        assert_eq!(unsafe { lifted.unwrap_as_public() }, xs);
+    }
+}
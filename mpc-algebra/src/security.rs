@@ -0,0 +1,88 @@
+//! Whether a run must be secure, or may take the shortcuts that benchmarks do.
+//!
+//! Benchmarks stand in for parts of the protocols that they do not measure: Beaver triples that
+//! are fixed rather than preprocessed, a SPDZ MAC key that every party knows, randomness from a
+//! fixed seed. Each such [Shortcut] checks the [SecurityMode] when taken: in
+//! [SecurityMode::Secure], it is an error; in [SecurityMode::Benchmark] (the default), it is
+//! allowed, with a warning the first time.
+use log::warn;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecurityMode {
+    /// Refuse every insecure shortcut.
+    Secure,
+    /// Allow insecure shortcuts, warning of each.
+    Benchmark,
+}
+
+static SECURE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_security_mode(mode: SecurityMode) {
+    SECURE.store(mode == SecurityMode::Secure, Ordering::SeqCst);
+}
+
+pub fn security_mode() -> SecurityMode {
+    if SECURE.load(Ordering::SeqCst) {
+        SecurityMode::Secure
+    } else {
+        SecurityMode::Benchmark
+    }
+}
+
+/// Beaver triples (of fields, groups or pairings) that every party knows.
+pub static DUMMY_TRIPLES: Shortcut = Shortcut::new("dummy Beaver triples, known to every party");
+/// Preprocessing (random bits, boolean triples, daBits) that the king deals, knowing its values.
+pub static KING_DEALT: Shortcut = Shortcut::new("preprocessing dealt by the king");
+/// The SPDZ MAC key, which is fixed, and which every party knows.
+pub static FIXED_MAC_KEY: Shortcut = Shortcut::new("a fixed SPDZ MAC key, known to every party");
+
+/// An insecure stand-in, declared as a `static`.
+pub struct Shortcut {
+    what: &'static str,
+    warned: AtomicBool,
+}
+
+impl Shortcut {
+    pub const fn new(what: &'static str) -> Self {
+        Self {
+            what,
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    /// Check that this shortcut may be taken: an error in secure mode.
+    pub fn allow(&self) -> Result<(), InsecureError> {
+        if SECURE.load(Ordering::Relaxed) {
+            return Err(InsecureError(self.what));
+        }
+        if !self.warned.swap(true, Ordering::Relaxed) {
+            warn!(
+                "INSECURE: using {}; this is for benchmarks only, and is refused in secure mode",
+                self.what
+            );
+        }
+        Ok(())
+    }
+
+    /// [Shortcut::allow], on paths that cannot fail otherwise: panics in secure mode.
+    #[track_caller]
+    pub fn take(&self) {
+        if let Err(e) = self.allow() {
+            panic!("{}", e);
+        }
+    }
+}
+
+/// A shortcut was taken in secure mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsecureError(pub &'static str);
+
+impl Display for InsecureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is insecure, and refused in secure mode", self.0)
+    }
+}
+
+impl std::error::Error for InsecureError {}
@@ -23,10 +23,12 @@ use mpc_net::{MpcNet, MpcMultiNet as Net};
 use crate::channel::MpcSerNet;
 
 use super::field::{
-    DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare, SparsePolynomial,
+    count_field_openings, DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare,
+    SparsePolynomial, SUM_TREE_MIN_PARTIES,
 };
-use super::group::GroupShare;
+use super::group::{count_group_openings, GroupShare};
 use super::pairing::{AffProjShare, PairingShare};
+use super::raw::{Layout, RawShare};
 use super::BeaverSource;
 use crate::msm::*;
 use crate::Reveal;
@@ -83,8 +85,13 @@ impl<F: Field> Reveal for AdditiveFieldShare<F> {
     type Base = F;
 
     fn reveal(self) -> F {
+        count_field_openings(1);
         Net::broadcast(&self.val).into_iter().sum()
     }
+    fn reveal_to(self, party: usize) -> Option<F> {
+        count_field_openings(1);
+        Net::open_to(party, &self.val, |vals| vals.into_iter().sum())
+    }
     fn from_public(f: F) -> Self {
         Self {
             val: if Net::am_king() { f } else { F::zero() },
@@ -93,11 +100,11 @@ impl<F: Field> Reveal for AdditiveFieldShare<F> {
     fn from_add_shared(f: F) -> Self {
         Self { val: f }
     }
-    fn unwrap_as_public(self) -> F {
+    unsafe fn unwrap_as_public(self) -> F {
         self.val
     }
     fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
-        let mut r: Vec<F> = (0..(Net::n_parties()-1)).map(|_| F::rand(rng)).collect();
+        let mut r: Vec<F> = (0..(Net::n_parties()-1)).map(|_| F::pub_rand(rng)).collect();
         let sum_r: F = r.iter().sum();
         r.push(f - sum_r);
         Self::from_add_shared(Net::recv_from_king( if Net::am_king() { Some(r) } else { None }))
@@ -106,7 +113,7 @@ impl<F: Field> Reveal for AdditiveFieldShare<F> {
         let mut rs: Vec<Vec<Self::Base>> =
             (0..(Net::n_parties()-1)).map(|_| {
             (0..f.len()).map(|_| {
-                F::rand(rng)
+                F::pub_rand(rng)
             }).collect()
         }).collect();
         let final_shares: Vec<Self::Base> = (0..rs[0].len()).map(|i| {
@@ -117,12 +124,33 @@ impl<F: Field> Reveal for AdditiveFieldShare<F> {
     }
 }
 
+/// Just the share of the value.
+impl<F: PrimeField> RawShare<F> for AdditiveFieldShare<F> {
+    fn write_raw<W: Write>(&self, layout: Layout, writer: W) -> io::Result<()> {
+        layout.write(&self.val, writer)
+    }
+    fn read_raw<R: Read>(layout: Layout, reader: R) -> io::Result<Self> {
+        Ok(Self {
+            val: layout.read(reader)?,
+        })
+    }
+}
+
 impl<F: Field> FieldShare<F> for AdditiveFieldShare<F> {
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<F> {
         let self_vec: Vec<F> = selfs.into_iter().map(|s| s.val).collect();
-        let all_vals = Net::broadcast(&self_vec);
+        count_field_openings(self_vec.len());
+        let all_vals = Net::broadcast_slice(&self_vec);
         (0..self_vec.len()).map(|i| all_vals.iter().map(|v| &v[i]).sum()).collect()
     }
+    fn sum_and_open(selfs: impl IntoIterator<Item = Self>) -> F {
+        let sum: F = selfs.into_iter().map(|s| s.val).sum();
+        if Net::n_parties() < SUM_TREE_MIN_PARTIES {
+            return Self::from_add_shared(sum).open();
+        }
+        count_field_openings(1);
+        Net::reduce(&sum, |a, b| a + b)
+    }
     fn add(&mut self, other: &Self) -> &mut Self {
         self.val += &other.val;
         self
@@ -176,8 +204,13 @@ impl<G: Group, M> Reveal for AdditiveGroupShare<G, M> {
     type Base = G;
 
     fn reveal(self) -> G {
+        count_group_openings(1);
         Net::broadcast(&self.val).into_iter().sum()
     }
+    fn reveal_to(self, party: usize) -> Option<G> {
+        count_group_openings(1);
+        Net::open_to(party, &self.val, |vals| vals.into_iter().sum())
+    }
     fn from_public(f: G) -> Self {
         Self {
             val: if Net::am_king() { f } else { G::zero() },
@@ -190,7 +223,7 @@ impl<G: Group, M> Reveal for AdditiveGroupShare<G, M> {
             _phants: PhantomData::default(),
         }
     }
-    fn unwrap_as_public(self) -> G {
+    unsafe fn unwrap_as_public(self) -> G {
         self.val
     }
     fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
@@ -219,7 +252,8 @@ impl<G: Group, M: Msm<G, G::ScalarField>> GroupShare<G> for AdditiveGroupShare<G
 
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<G> {
         let self_vec: Vec<G> = selfs.into_iter().map(|s| s.val).collect();
-        let all_vals = Net::broadcast(&self_vec);
+        count_group_openings(self_vec.len());
+        let all_vals = Net::broadcast_slice(&self_vec);
         (0..self_vec.len()).map(|i| all_vals.iter().map(|v| &v[i]).sum()).collect()
     }
 
@@ -414,6 +448,9 @@ impl<F: Field> Reveal for MulFieldShare<F> {
     fn reveal(self) -> F {
         Net::broadcast(&self.val).into_iter().product()
     }
+    fn reveal_to(self, party: usize) -> Option<F> {
+        Net::open_to(party, &self.val, |vals| vals.into_iter().product())
+    }
     fn from_public(f: F) -> Self {
         Self {
             val: if Net::am_king() { f } else { F::one() },
@@ -422,7 +459,7 @@ impl<F: Field> Reveal for MulFieldShare<F> {
     fn from_add_shared(f: F) -> Self {
         Self { val: f }
     }
-    fn unwrap_as_public(self) -> F {
+    unsafe fn unwrap_as_public(self) -> F {
         self.val
     }
 }
@@ -433,7 +470,7 @@ impl<F: Field> FieldShare<F> for MulFieldShare<F> {
     }
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<F> {
         let self_vec: Vec<F> = selfs.into_iter().map(|s| s.val).collect();
-        let all_vals = Net::broadcast(&self_vec);
+        let all_vals = Net::broadcast_slice(&self_vec);
         (0..self_vec.len()).map(|i| all_vals.iter().map(|v| &v[i]).product()).collect()
     }
 
@@ -503,7 +540,7 @@ macro_rules! groups_share {
 
         impl<E: PairingEngine> AffProjShare<E::Fr, E::$affine, E::$proj> for $struct_name<E> {
             type FrShare = AdditiveFieldShare<E::Fr>;
-            type AffineShare = AdditiveGroupShare<E::$affine, crate::msm::AffineMsm<E::$affine>>;
+            type AffineShare = AdditiveGroupShare<E::$affine, crate::msm::PippengerMsm<E::$affine>>;
             type ProjectiveShare =
                 AdditiveGroupShare<E::$proj, crate::msm::ProjectiveMsm<E::$proj>>;
 
@@ -558,8 +595,8 @@ impl<E: PairingEngine> PairingShare<E> for AdditivePairingShare<E> {
     type FqeShare = AdditiveExtFieldShare<E::Fqe>;
     // Not a typo. We want a multiplicative subgroup.
     type FqkShare = MulExtFieldShare<E::Fqk>;
-    type G1AffineShare = AdditiveGroupShare<E::G1Affine, crate::msm::AffineMsm<E::G1Affine>>;
-    type G2AffineShare = AdditiveGroupShare<E::G2Affine, crate::msm::AffineMsm<E::G2Affine>>;
+    type G1AffineShare = AdditiveGroupShare<E::G1Affine, crate::msm::PippengerMsm<E::G1Affine>>;
+    type G2AffineShare = AdditiveGroupShare<E::G2Affine, crate::msm::PippengerMsm<E::G2Affine>>;
     type G1ProjectiveShare =
         AdditiveGroupShare<E::G1Projective, crate::msm::ProjectiveMsm<E::G1Projective>>;
     type G2ProjectiveShare =
@@ -0,0 +1,278 @@
+//! Boolean (GF(2)) shares, for bitwise computation such as hashing and comparison.
+//!
+//! A [BitShare] is one party's XOR share of a bit. XOR, and anything else linear over GF(2), is
+//! local; AND takes a boolean Beaver triple and one round, in which the parties open both masked
+//! operands. The opened bits are packed eight to a byte, so a batch of `n` ANDs sends about
+//! `n / 4` bytes to each party.
+//!
+//! [a2b] and [b2a] convert between these and arithmetic shares of a prime field, using shared
+//! bits known in both domains ("daBits"). So witness generation can move to the boolean domain for
+//! bitwise work, and back before synthesis.
+//!
+//! Boolean shares carry no MACs, so these protocols are only secure against honest-but-curious
+//! parties, whatever the arithmetic share type. Triples and daBits are dealt by the king, knowing
+//! their values; like [crate::DummyFieldTripleSource], this is only suitable for benchmarking.
+use ark_ff::{BigInteger, PrimeField};
+use rand::Rng;
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::{BitXor, BitXorAssign, Not};
+
+use mpc_net::{MpcMultiNet as Net, MpcNet};
+
+use super::field::FieldShare;
+use crate::security::KING_DEALT;
+use crate::wire::field::BIT_DECOMPOSITION_SECURITY;
+
+/// The counter of boolean triples consumed by [BitShare::and_batch].
+pub const BOOLEAN_TRIPLES_METRIC: &str = "mpc_boolean_triples_consumed_total";
+/// The counter of daBits consumed by [a2b] and [b2a].
+pub const DABITS_METRIC: &str = "mpc_dabits_consumed_total";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct BitShare {
+    pub val: bool,
+}
+
+impl Display for BitShare {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.val as u8)
+    }
+}
+
+impl BitShare {
+    /// A public bit, held by the king.
+    pub fn from_public(b: bool) -> Self {
+        Self {
+            val: b && Net::am_king(),
+        }
+    }
+
+    pub fn from_xor_shared(b: bool) -> Self {
+        Self { val: b }
+    }
+
+    /// Secret-share the king's bits `bs`; other parties' arguments are ignored, but must be the
+    /// same length.
+    pub fn king_share_batch<R: Rng>(bs: Vec<bool>, rng: &mut R) -> Vec<Self> {
+        let n = bs.len();
+        // Every party draws the masks, as for field shares, so that their rngs stay in step.
+        let mut rs: Vec<Vec<bool>> = (0..Net::n_parties() - 1)
+            .map(|_| (0..n).map(|_| rng.gen()).collect())
+            .collect();
+        let last = (0..n)
+            .map(|i| rs.iter().fold(bs[i], |acc, r| acc ^ r[i]))
+            .collect();
+        rs.push(last);
+        let shares = if Net::am_king() {
+            Some(rs.iter().map(|r| pack(r)).collect())
+        } else {
+            None
+        };
+        unpack(&Net::recv_bytes_from_king(shares), n)
+            .into_iter()
+            .map(Self::from_xor_shared)
+            .collect()
+    }
+
+    /// `self ^ b`, for a public `b`.
+    pub fn xor_public(self, b: bool) -> Self {
+        self ^ Self::from_public(b)
+    }
+
+    /// `self & b`, for a public `b`.
+    pub fn and_public(self, b: bool) -> Self {
+        Self { val: self.val & b }
+    }
+
+    /// `xs[i] & ys[i]`, all in one round.
+    pub fn and_batch(xs: &[Self], ys: &[Self]) -> Vec<Self> {
+        assert_eq!(xs.len(), ys.len());
+        let n = xs.len();
+        let (a, b, c) = boolean_triples(n);
+        let masked: Vec<Self> = xs
+            .iter()
+            .zip(&a)
+            .map(|(x, a)| *x ^ *a)
+            .chain(ys.iter().zip(&b).map(|(y, b)| *y ^ *b))
+            .collect();
+        let opened = Self::open_batch(&masked);
+        let (d, e) = opened.split_at(n);
+        (0..n)
+            .map(|i| {
+                c[i] ^ b[i].and_public(d[i])
+                    ^ a[i].and_public(e[i])
+                    ^ Self::from_public(d[i] & e[i])
+            })
+            .collect()
+    }
+
+    pub fn and(self, other: Self) -> Self {
+        Self::and_batch(&[self], &[other])[0]
+    }
+
+    pub fn open_batch(bits: &[Self]) -> Vec<bool> {
+        let vals: Vec<bool> = bits.iter().map(|b| b.val).collect();
+        let all = Net::broadcast_bytes(&pack(&vals));
+        let mut out = vec![false; bits.len()];
+        for bytes in all {
+            for (o, b) in out.iter_mut().zip(unpack(&bytes, bits.len())) {
+                *o ^= b;
+            }
+        }
+        out
+    }
+
+    pub fn open(self) -> bool {
+        Self::open_batch(&[self])[0]
+    }
+}
+
+impl BitXor for BitShare {
+    type Output = Self;
+    fn bitxor(self, other: Self) -> Self {
+        Self {
+            val: self.val ^ other.val,
+        }
+    }
+}
+
+impl BitXorAssign for BitShare {
+    fn bitxor_assign(&mut self, other: Self) {
+        self.val ^= other.val;
+    }
+}
+
+impl Not for BitShare {
+    type Output = Self;
+    fn not(self) -> Self {
+        self.xor_public(true)
+    }
+}
+
+/// The bits of each of `xs`, least-significant first, which must all be less than `2^k`.
+///
+/// Like [crate::MpcField::bit_decomposition], each `x` is masked by a random `r` and `c = x + r`
+/// opened; the low `k` bits of `r` are daBits, so the bits of `c - r` come from a boolean
+/// ripple-borrow subtractor, at one AND (and one round, for the whole batch) per bit.
+pub fn a2b<F: PrimeField, S: FieldShare<F>>(xs: &[S], k: usize) -> Vec<Vec<BitShare>> {
+    let m = k + BIT_DECOMPOSITION_SECURITY;
+    assert!(
+        (m as u32) < F::size_in_bits() as u32 - 1,
+        "cannot decompose {} bits with {} bits of masking",
+        k,
+        BIT_DECOMPOSITION_SECURITY
+    );
+    let n = xs.len();
+    let (r_bits, r_low) = dabits::<F, S>(n * k);
+    let r_high = crate::preprocessing::random_bits::<F, S>(n * BIT_DECOMPOSITION_SECURITY);
+    let two = F::from(2u8);
+    let masked: Vec<S> = (0..n)
+        .map(|j| {
+            let low = &r_low[j * k..(j + 1) * k];
+            let high =
+                &r_high[j * BIT_DECOMPOSITION_SECURITY..(j + 1) * BIT_DECOMPOSITION_SECURITY];
+            let mut mask = S::from_public(F::zero());
+            for r in low.iter().chain(high).rev() {
+                mask.scale(&two).add(r);
+            }
+            *mask.add(&xs[j])
+        })
+        .collect();
+    let c: Vec<Vec<bool>> = S::batch_open(masked)
+        .into_iter()
+        .map(|c| c.into_repr().to_bits_le())
+        .collect();
+    let r = |j: usize, i: usize| r_bits[j * k + i];
+    let mut out = vec![Vec::new(); n];
+    let mut borrow = vec![BitShare::default(); n];
+    for i in 0..k {
+        for j in 0..n {
+            out[j].push(r(j, i).xor_public(c[j][i]) ^ borrow[j]);
+        }
+        if i + 1 == k {
+            break;
+        }
+        let rs: Vec<BitShare> = (0..n).map(|j| r(j, i)).collect();
+        let both = BitShare::and_batch(&rs, &borrow);
+        // Borrow iff (!c_i && r_i) or (!(c_i ^ r_i) && borrow): if c_i, that is r_i && borrow,
+        // and otherwise r_i || borrow.
+        for j in 0..n {
+            borrow[j] = if c[j][i] {
+                both[j]
+            } else {
+                rs[j] ^ borrow[j] ^ both[j]
+            };
+        }
+    }
+    out
+}
+
+/// Arithmetic shares of `bits`.
+///
+/// Each bit `b` is masked by a daBit `r`, and `c = b ^ r` opened; then `b = c + r - 2cr`, which is
+/// linear in the arithmetic share of `r`.
+pub fn b2a<F: PrimeField, S: FieldShare<F>>(bits: &[BitShare]) -> Vec<S> {
+    let (r_bits, r) = dabits::<F, S>(bits.len());
+    let masked: Vec<BitShare> = bits.iter().zip(&r_bits).map(|(b, r)| *b ^ *r).collect();
+    BitShare::open_batch(&masked)
+        .into_iter()
+        .zip(r)
+        .map(|(c, mut r)| {
+            if c {
+                // 1 + r - 2r = 1 - r
+                *r.neg().shift(&F::one())
+            } else {
+                r
+            }
+        })
+        .collect()
+}
+
+/// `n` boolean triples `(a, b, a & b)`, dealt by the king.
+fn boolean_triples(n: usize) -> (Vec<BitShare>, Vec<BitShare>, Vec<BitShare>) {
+    mpc_net::metrics::inc(
+        BOOLEAN_TRIPLES_METRIC,
+        "Boolean triples consumed.",
+        n as u64,
+    );
+    KING_DEALT.take();
+    let rng = &mut rand::thread_rng();
+    let a: Vec<bool> = (0..n).map(|_| rng.gen()).collect();
+    let b: Vec<bool> = (0..n).map(|_| rng.gen()).collect();
+    let c = a.iter().zip(&b).map(|(a, b)| a & b).collect();
+    (
+        BitShare::king_share_batch(a, rng),
+        BitShare::king_share_batch(b, rng),
+        BitShare::king_share_batch(c, rng),
+    )
+}
+
+/// `n` random bits, shared both as [BitShare]s and as `S`s, dealt by the king.
+fn dabits<F: PrimeField, S: FieldShare<F>>(n: usize) -> (Vec<BitShare>, Vec<S>) {
+    mpc_net::metrics::inc(
+        DABITS_METRIC,
+        "Bits shared in both domains consumed.",
+        n as u64,
+    );
+    KING_DEALT.take();
+    let rng = &mut rand::thread_rng();
+    let bits: Vec<bool> = (0..n).map(|_| rng.gen()).collect();
+    let arith = bits.iter().map(|b| F::from(*b as u8)).collect();
+    (
+        BitShare::king_share_batch(bits, rng),
+        S::king_share_batch(arith, rng),
+    )
+}
+
+fn pack(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; (bits.len() + 7) / 8];
+    for (i, b) in bits.iter().enumerate() {
+        bytes[i / 8] |= (*b as u8) << (i % 8);
+    }
+    bytes
+}
+
+fn unpack(bytes: &[u8], n: usize) -> Vec<bool> {
+    (0..n).map(|i| bytes[i / 8] >> (i % 8) & 1 == 1).collect()
+}
@@ -13,6 +13,20 @@ use std::hash::Hash;
 use super::BeaverSource;
 use crate::Reveal;
 
+/// The counter of shared field elements opened.
+pub const FIELD_OPENINGS_METRIC: &str = "mpc_field_elements_opened_total";
+
+/// Count `n` shared field elements as opened; each [FieldShare] implementation calls this from
+/// its openings.
+pub fn count_field_openings(n: usize) {
+    mpc_net::metrics::inc(FIELD_OPENINGS_METRIC, "Shared field elements opened.", n as u64);
+}
+
+/// With at least this many parties, [FieldShare::sum_and_open] sums additive shares up a tree
+/// (see [mpc_net::MpcNet::reduce_bytes]) rather than broadcasting them: more rounds, but each
+/// party handles a few messages rather than one from every other party.
+pub const SUM_TREE_MIN_PARTIES: usize = 4;
+
 pub trait FieldShare<F: Field>:
     Clone
     + Copy
@@ -38,13 +52,25 @@ pub trait FieldShare<F: Field>:
     }
 
     fn map_homo<FF: Field, SS: FieldShare<FF>, Fun: Fn(F) -> FF>(self, f: Fun) -> SS {
-        SS::from_add_shared(f(self.unwrap_as_public()))
+        // A homomorphism of our share is our share of the image.
+        SS::from_add_shared(f(unsafe { self.unwrap_as_public() }))
     }
 
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<F> {
         selfs.into_iter().map(|s| s.open()).collect()
     }
 
+    /// Open the sum of `selfs`, summing the shares locally so that only one value is opened.
+    fn sum_and_open(selfs: impl IntoIterator<Item = Self>) -> F {
+        selfs
+            .into_iter()
+            .fold(Self::from_public(F::zero()), |mut acc, s| {
+                acc.add(&s);
+                acc
+            })
+            .open()
+    }
+
     fn add(&mut self, other: &Self) -> &mut Self;
 
     fn sub(&mut self, other: &Self) -> &mut Self {
@@ -81,8 +107,7 @@ pub trait FieldShare<F: Field>:
         };
         let mut result = z;
         result.sub(y.scale(&sx)).sub(x.scale(&oy)).shift(&(sx * oy));
-        #[cfg(debug_assertions)]
-        {
+        if mpc_net::self_checks() {
             let a = s.reveal();
             let b = o.reveal();
             let r = result.reveal();
@@ -126,6 +151,38 @@ pub trait FieldShare<F: Field>:
             .collect()
     }
 
+    /// The inner product of `xs` and `ys`, consuming one triple per term but opening all the
+    /// masked values in a single round (rather than the two of [FieldShare::batch_mul]).
+    fn dot<S: BeaverSource<Self, Self, Self>>(
+        xs: Vec<Self>,
+        ys: Vec<Self>,
+        source: &mut S,
+    ) -> Self {
+        assert_eq!(xs.len(), ys.len());
+        let n = xs.len();
+        let (as_, bs, cs) = source.triples(n);
+        // As in mul: sum_i z_i - open(x_i + a_i)b_i - open(y_i + b_i)a_i + open(..)open(..)
+        let masked = xs
+            .into_iter()
+            .zip(as_.iter())
+            .chain(ys.into_iter().zip(bs.iter()))
+            .map(|(mut s, mask)| {
+                s.add(mask);
+                s
+            });
+        let opened = Self::batch_open(masked);
+        let (ds, es) = opened.split_at(n);
+        let mut cross = F::zero();
+        let mut result = Self::from_public(F::zero());
+        for i in 0..n {
+            result.add(&cs[i]);
+            let (mut a, mut b) = (as_[i], bs[i]);
+            result.sub(b.scale(&ds[i])).sub(a.scale(&es[i]));
+            cross += ds[i] * es[i];
+        }
+        *result.shift(&cross)
+    }
+
     fn inv<S: BeaverSource<Self, Self, Self>>(self, source: &mut S) -> Self {
         let (x, mut y) = source.inv_pair();
         let xa = x.mul(self, source).open().inverse().unwrap();
@@ -14,6 +14,15 @@ use super::field::FieldShare;
 use super::BeaverSource;
 use crate::Reveal;
 
+/// The counter of shared group elements opened, e.g., to audit which values a prover reveals.
+pub const GROUP_OPENINGS_METRIC: &str = "mpc_group_elements_opened_total";
+
+/// Count `n` shared group elements as opened; each [GroupShare] implementation calls this from
+/// its openings.
+pub fn count_group_openings(n: usize) {
+    mpc_net::metrics::inc(GROUP_OPENINGS_METRIC, "Shared group elements opened.", n as u64);
+}
+
 /// Secret sharing scheme which support affine functions of secrets.
 pub trait GroupShare<G: Group>:
     Clone
@@ -41,7 +50,8 @@ pub trait GroupShare<G: Group>:
     }
 
     fn map_homo<G2: Group, S2: GroupShare<G2>, Fun: Fn(G) -> G2>(self, f: Fun) -> S2 {
-        S2::from_add_shared(f(self.unwrap_as_public()))
+        // A homomorphism of our share is our share of the image.
+        S2::from_add_shared(f(unsafe { self.unwrap_as_public() }))
     }
 
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<G> {
@@ -92,8 +102,7 @@ pub trait GroupShare<G: Group>:
         out.sub(x.scale_pub_scalar(&oy));
         sx *= oy;
         out.shift(&sx);
-        #[cfg(debug_assertions)]
-        {
+        if mpc_net::self_checks() {
             let a = s.reveal();
             let b = o.reveal();
             let mut acp = a.clone();
@@ -108,6 +117,55 @@ pub trait GroupShare<G: Group>:
         out
     }
 
+    /// Compute \sum_i (s_i * g_i)
+    /// where the s_i are public and the g_i are shared.
+    fn multi_scale_pub_scalar(bases: &[Self], scalars: &[G::ScalarField]) -> Self {
+        bases
+            .iter()
+            .zip(scalars.iter())
+            .fold(Self::from_public(G::zero()), |mut acc, (g, s)| {
+                let mut g = *g;
+                acc.add(g.scale_pub_scalar(s));
+                acc
+            })
+    }
+
+    /// Compute \sum_i (s_i * g_i)
+    /// where both the s_i and the g_i are shared.
+    ///
+    /// Like [GroupShare::scale], but opening all the masked operands in one batch.
+    fn multi_scale<S: BeaverSource<Self, Self::FieldShare, Self>>(
+        bases: &[Self],
+        scalars: &[Self::FieldShare],
+        source: &mut S,
+    ) -> Self {
+        let timer = start_timer!(|| "SS multi-scalar multiplication");
+        let triples: Vec<_> = bases.iter().map(|_| source.triple()).collect();
+        let sx = Self::batch_open(bases.iter().zip(&triples).map(|(s, (x, _, _))| {
+            let mut t = *s;
+            *t.add(x)
+        }));
+        let oy = Self::FieldShare::batch_open(scalars.iter().zip(&triples).map(|(o, (_, y, _))| {
+            let mut t = *o;
+            *t.add(y)
+        }));
+        let out = triples.into_iter().zip(sx).zip(oy).fold(
+            Self::from_public(G::zero()),
+            |mut acc, (((mut x, y, z), mut sx), oy)| {
+                // z - sx * y - x * oy + sx * oy, as in [GroupShare::scale]
+                let mut term = z;
+                term.sub(&Self::scale_pub_group(sx, &y));
+                term.sub(x.scale_pub_scalar(&oy));
+                sx *= oy;
+                term.shift(&sx);
+                acc.add(&term);
+                acc
+            },
+        );
+        end_timer!(timer);
+        out
+    }
+
     /// Compute \sum_i (s_i * g_i)
     /// where the s_i are shared and the g_i are public.
     fn multi_scale_pub_group(bases: &[G], scalars: &[Self::FieldShare]) -> Self {
@@ -52,11 +52,13 @@ use log::debug;
 use rand::Rng;
 
 use super::field::{
-    DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare, SparsePolynomial,
+    count_field_openings, DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare,
+    SparsePolynomial,
 };
 use super::BeaverSource;
 use crate::msm::Msm;
 use crate::share::pairing::{AffProjShare, PairingShare};
+use crate::share::raw::{Layout, RawShare};
 use crate::Reveal;
 
 lazy_static! {
@@ -104,6 +106,50 @@ pub fn domain<F: FftField>() -> &'static MixedRadixEvaluationDomain<F> {
     }).downcast_ref().unwrap()
 }
 
+/// The secret of a degree-`d` sharing, from the shares of the parties that remain (`None` marks a
+/// party that dropped out), by interpolating at zero.
+///
+/// This needs `d + 1` shares; any more are checked against the polynomial those determine.
+fn reconstruct_surviving<F: FftField, T>(
+    shares: &[Option<T>],
+    d: usize,
+    scale: impl Fn(T, F) -> T,
+) -> T
+where
+    T: Copy + Zero + Eq + Debug + std::ops::Add<Output = T>,
+{
+    let domain = domain::<F>();
+    let (points, shares): (Vec<F>, Vec<T>) = shares
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| s.map(|s| (domain.element(i), s)))
+        .unzip();
+    assert!(
+        shares.len() > d,
+        "Protocol abort: {} shares remain, but a degree {} sharing needs {}",
+        shares.len(),
+        d,
+        d + 1
+    );
+    let interpolate = |at: F| {
+        (0..=d).fold(T::zero(), |acc, i| {
+            let l = (0..=d)
+                .filter(|&j| j != i)
+                .fold(F::one(), |l, j| l * (at - points[j]) / (points[i] - points[j]));
+            acc + scale(shares[i], l)
+        })
+    };
+    for i in d + 1..shares.len() {
+        assert_eq!(
+            interpolate(points[i]),
+            shares[i],
+            "Surviving shares are not of degree <= {}",
+            d
+        );
+    }
+    interpolate(F::zero())
+}
+
 pub mod field {
     use super::*;
 
@@ -180,15 +226,21 @@ pub mod field {
         type Base = F;
 
         fn reveal(self) -> F {
+            count_field_openings(1);
             open(&self)
         }
+        fn reveal_to(self, party: usize) -> Option<F> {
+            count_field_openings(1);
+            check_accumulated_field_products::<F>();
+            Net::open_to(party, &self.val, |shares| open_degree_vec(shares, self.degree))
+        }
         fn from_public(f: F) -> Self {
             Self { val: f, degree: 0 }
         }
         fn from_add_shared(_f: F) -> Self {
             unimplemented!()
         }
-        fn unwrap_as_public(self) -> F {
+        unsafe fn unwrap_as_public(self) -> F {
             self.val
         }
         fn king_share<R: Rng>(f: Self::Base, _rng: &mut R) -> Self {
@@ -257,6 +309,20 @@ pub mod field {
         }
     }
 
+    /// Just the share of the value: its evaluation at this party's point. Shares read are
+    /// taken to be of degree `t`, as fresh ones are.
+    impl<F: PrimeField> RawShare<F> for GszFieldShare<F> {
+        fn write_raw<W: Write>(&self, layout: Layout, writer: W) -> io::Result<()> {
+            layout.write(&self.val, writer)
+        }
+        fn read_raw<R: Read>(layout: Layout, reader: R) -> io::Result<Self> {
+            Ok(Self {
+                val: layout.read(reader)?,
+                degree: t(),
+            })
+        }
+    }
+
     impl<F: FftField> FieldShare<F> for GszFieldShare<F> {
         fn add(&mut self, other: &Self) -> &mut Self {
             self.val += other.val;
@@ -286,8 +352,21 @@ pub mod field {
         fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<F> {
             let (self_vec, mut deg_vec): (Vec<F>, Vec<usize>) =
                 selfs.into_iter().map(|s| (s.val, s.degree)).unzip();
+            count_field_openings(self_vec.len());
             let timer = start_timer!(|| format!("Batch open: {}", self_vec.len()));
-            let mut all_vals = Net::broadcast(&self_vec);
+            let all_vals = Net::broadcast_surviving(&self_vec);
+            if all_vals.iter().any(Option::is_none) {
+                let out = (0..self_vec.len())
+                    .map(|i| {
+                        let vals: Vec<Option<F>> =
+                            all_vals.iter().map(|v| v.as_ref().map(|v| v[i])).collect();
+                        reconstruct_surviving(&vals, deg_vec[i], |s, l: F| s * l)
+                    })
+                    .collect();
+                end_timer!(timer);
+                return out;
+            }
+            let mut all_vals: Vec<Vec<F>> = all_vals.into_iter().map(Option::unwrap).collect();
             let mut out = Vec::new();
             while all_vals[0].len() > 0 {
                 let vals: Vec<F> = all_vals.iter_mut().map(|v| v.pop().unwrap()).collect();
@@ -314,6 +393,21 @@ pub mod field {
             batch_mult(xs, &ys, true)
         }
 
+        /// One batched multiplication, so that each product is still checked.
+        fn dot<S: BeaverSource<Self, Self, Self>>(
+            xs: Vec<Self>,
+            ys: Vec<Self>,
+            _source: &mut S,
+        ) -> Self {
+            let mut sum = Self::from_public(F::zero());
+            if !xs.is_empty() {
+                for p in batch_mult(xs, &ys, true) {
+                    sum.add(&p);
+                }
+            }
+            sum
+        }
+
         fn inv<S: super::BeaverSource<Self, Self, Self>>(self, _source: &mut S) -> Self {
             let mut r = rand::<F>();
             let self_r = self.mul(r, _source);
@@ -431,10 +525,18 @@ pub mod field {
     }
 
     /// Open a t-share.
+    ///
+    /// Parties may drop out, as long as enough shares remain.
     pub fn open<F: FftField>(s: &GszFieldShare<F>) -> F {
         check_accumulated_field_products::<F>();
-        let shares = Net::broadcast(&s.val);
-        open_degree_vec(shares, s.degree)
+        open_surviving(Net::broadcast_surviving(&s.val), s.degree)
+    }
+
+    fn open_surviving<F: FftField>(shares: Vec<Option<F>>, d: usize) -> F {
+        match shares.iter().cloned().collect::<Option<Vec<F>>>() {
+            Some(shares) => open_degree_vec(shares, d),
+            None => reconstruct_surviving(&shares, d, |s, l: F| s * l),
+        }
     }
 
     fn open_degree_vec<F: FftField>(mut shares: Vec<F>, d: usize) -> F {
@@ -442,7 +544,7 @@ pub mod field {
         let domain = domain::<F>();
         //end_timer!(build_domain_timer);
         //let ifft_timer = start_timer!(|| "ifft");
-        domain.ifft_in_place(&mut shares);
+        crate::kernels::ifft_in_place(domain, &mut shares);
         //end_timer!(ifft_timer);
         //let eval_timer = start_timer!(|| "polyeval");
         let p = ark_poly::univariate::DensePolynomial::from_coefficients_vec(shares);
@@ -470,9 +572,9 @@ pub mod field {
         new_degree: usize,
         f: Func,
     ) -> GszFieldShare<F> {
-        let king_answer = Net::send_to_king(&share.val).map(|shares| {
+        let king_answer = Net::send_to_king_surviving(&share.val).map(|shares| {
             let n = shares.len();
-            let value = open_degree_vec(shares, share.degree);
+            let value = open_surviving(shares, share.degree);
             let output = f(value);
             // TODO: randomize
             vec![output; n]
@@ -497,19 +599,22 @@ pub mod field {
         f: Func,
     ) -> Vec<GszFieldShare<F>> {
         let values: Vec<F> = shares.iter().map(|s| s.val).collect();
-        let king_answer = Net::send_to_king(&values).map(|all_shares| {
+        let king_answer = Net::send_to_king_surviving(&values).map(|all_shares| {
             let kc_timer = start_timer!(|| format!("King computation"));
             let n = all_shares.len();
             let mut outputs = vec![Vec::new(); n];
-            for i in 0..all_shares[0].len() {
-                let these_shares: Vec<F> = all_shares.iter().map(|s| s[i]).collect();
-                let value = open_degree_vec(these_shares, shares[i].degree);
+            for i in 0..values.len() {
+                let these_shares: Vec<Option<F>> = all_shares
+                    .iter()
+                    .map(|s| s.as_ref().map(|s| s[i]))
+                    .collect();
+                let value = open_surviving(these_shares, shares[i].degree);
                 let output = f(value);
                 // TODO: randomize
                 outputs.iter_mut().for_each(|o| o.push(output));
             }
             assert_eq!(outputs.len(), all_shares.len());
-            assert_eq!(outputs[0].len(), all_shares[0].len());
+            assert_eq!(outputs[0].len(), values.len());
             end_timer!(kc_timer);
             outputs
         });
@@ -783,7 +888,7 @@ pub mod field {
         let x = open(&x_blind);
         let y = open(&y_blind);
         let z = open(&ip_blind);
-        assert_eq!(x * &y, z);
+        assert!(crate::ct::eq(&(x * &y), &z), "Protocol abort: product check failed");
     }
 
     pub fn ip_compute<F: FftField>(
@@ -817,7 +922,7 @@ pub mod field {
 pub use field::GszFieldShare;
 
 pub mod group {
-    use super::super::group::GroupShare;
+    use super::super::group::{count_group_openings, GroupShare};
     use super::*;
     use ark_ec::group::Group;
     use std::marker::PhantomData;
@@ -901,8 +1006,14 @@ pub mod group {
 
         fn reveal(self) -> G {
             M::pre_reveal_check();
+            count_group_openings(1);
             open(&self)
         }
+        fn reveal_to(self, party: usize) -> Option<G> {
+            M::pre_reveal_check();
+            count_group_openings(1);
+            Net::open_to(party, &self.val, |shares| open_degree_vec(shares, self.degree))
+        }
         fn from_public(f: G) -> Self {
             Self {
                 val: f,
@@ -913,7 +1024,7 @@ pub mod group {
         fn from_add_shared(_f: G) -> Self {
             unimplemented!("from_add_shared")
         }
-        fn unwrap_as_public(self) -> G {
+        unsafe fn unwrap_as_public(self) -> G {
             self.val
         }
         fn king_share<R: Rng>(f: Self::Base, _rng: &mut R) -> Self {
@@ -1040,9 +1151,17 @@ pub mod group {
     );
 
     /// Open a t-share.
+    ///
+    /// Parties may drop out, as long as enough shares remain.
     pub fn open<G: Group, M: Send + 'static>(s: &GszGroupShare<G, M>) -> G {
-        let shares = Net::broadcast(&s.val);
-        open_degree_vec(shares, s.degree)
+        open_surviving(Net::broadcast_surviving(&s.val), s.degree)
+    }
+
+    fn open_surviving<G: Group>(shares: Vec<Option<G>>, d: usize) -> G {
+        match shares.iter().cloned().collect::<Option<Vec<G>>>() {
+            Some(shares) => open_degree_vec(shares, d),
+            None => reconstruct_surviving(&shares, d, |s: G, l: G::ScalarField| s.mul(&l)),
+        }
     }
 
     fn open_degree_vec<G: Group>(shares: Vec<G>, d: usize) -> G {
@@ -1092,9 +1211,9 @@ pub mod group {
         new_degree: usize,
         f: Func,
     ) -> GszGroupShare<G, M> {
-        let king_answer = Net::send_to_king(&share.val).map(|shares| {
+        let king_answer = Net::send_to_king_surviving(&share.val).map(|shares| {
             let n = shares.len();
-            let value = open_degree_vec(shares, share.degree);
+            let value = open_surviving(shares, share.degree);
             let output = f(value);
             // TODO: randomize
             vec![output; n]
@@ -1325,7 +1444,7 @@ pub mod group {
         let x = field::open(&x_blind);
         let y = open(&y_blind);
         let z = open(&ip_blind);
-        assert_eq!(y.mul(&x), z);
+        assert!(crate::ct::eq(&y.mul(&x), &z), "Protocol abort: product check failed");
     }
 
     /// Convert a hadamard check into an IP check
@@ -1558,7 +1677,7 @@ pub mod mul_field {
         fn from_add_shared(_f: F) -> Self {
             unimplemented!()
         }
-        fn unwrap_as_public(self) -> F {
+        unsafe fn unwrap_as_public(self) -> F {
             self.val
         }
     }
@@ -11,6 +11,10 @@ pub mod spdz;
 pub use spdz::*;
 pub mod gsz20;
 pub use gsz20::*;
+pub mod bit;
+pub use bit::BitShare;
+pub mod raw;
+pub use raw::{Layout, RawShare};
 
 use std::marker::PhantomData;
 use derivative::Derivative;
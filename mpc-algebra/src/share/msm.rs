@@ -1,10 +1,15 @@
 use derivative::Derivative;
 use ark_ec::{AffineCurve, ProjectiveCurve, group::Group};
+use ark_ff::{FpParameters, PrimeField, Zero};
 use std::marker::PhantomData;
 
 /// Multi-scalar multiplications
 pub trait Msm<G, S>: Send + Sync + 'static {
     fn msm(bases: &[G], scalars: &[S]) -> G;
+    /// One MSM per vector in `scalars`, all over `bases` (e.g., a SPDZ share's value and MAC).
+    fn msm_many(bases: &[G], scalars: &[Vec<S>]) -> Vec<G> {
+        scalars.iter().map(|s| Self::msm(bases, s)).collect()
+    }
     fn pre_reveal_check() {}
 }
 
@@ -46,3 +51,137 @@ impl<G: ProjectiveCurve> Msm<G, G::ScalarField> for ProjectiveMsm<G> {
         <G::Affine as AffineCurve>::multi_scalar_mul(&bases, scalars)
     }
 }
+
+/// [pippenger], for additive shares of scalars: a share's value is a field element like any
+/// other, so the MSM runs on its representation, with no per-element dispatch on whether it is
+/// shared or public. Several MSMs over the same bases (e.g., a SPDZ share and its MAC) take one
+/// pass over the bases. It runs on the installed [crate::kernels::Backend], if any.
+#[derive(Debug, Derivative)]
+#[derivative(Default(bound = ""), Clone(bound = ""), Copy(bound = ""))]
+pub struct PippengerMsm<G: AffineCurve>(pub PhantomData<G>);
+
+impl<G: AffineCurve> Msm<G, G::ScalarField> for PippengerMsm<G> {
+    fn msm(bases: &[G], scalars: &[G::ScalarField]) -> G {
+        Self::msm_many(bases, &[scalars.to_vec()]).pop().unwrap()
+    }
+
+    fn msm_many(bases: &[G], scalars: &[Vec<G::ScalarField>]) -> Vec<G> {
+        let reprs: Vec<Vec<_>> = scalars
+            .iter()
+            .map(|s| s.iter().map(|s| s.into_repr()).collect())
+            .collect();
+        let reprs: Vec<&[_]> = reprs.iter().map(|r| &r[..]).collect();
+        crate::kernels::msm(bases, &reprs)
+            .into_iter()
+            .map(|r| r.into_affine())
+            .collect()
+    }
+}
+
+/// `sum_i scalars[j][i] * bases[i]` for each `j`, by Pippenger's bucket method.
+///
+/// Scalars are cut into `c`-bit windows, with signed digits in `[-2^(c-1), 2^(c-1)]`, so that
+/// `c`-bit windows need only `2^(c-1)` buckets: in each window, each base is added to (or
+/// subtracted from) the bucket of its scalar's digit, and the buckets are summed, weighted by
+/// their digits, with a running sum. Against `ark-ec`'s MSM, this takes windows one bit wider for
+/// the same number of buckets, and so fewer of them.
+pub fn pippenger<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[&[<G::ScalarField as PrimeField>::BigInt]],
+) -> Vec<G::Projective> {
+    // As in `ark-ec`, extra bases or scalars are ignored.
+    let n = scalars.iter().map(|s| s.len()).fold(bases.len(), usize::min);
+    let bases = &bases[..n];
+    let c = window_bits(bases.len());
+    let num_bits = <G::ScalarField as PrimeField>::Params::MODULUS_BITS as usize;
+    // One more window, for the carry out of the top one.
+    let num_windows = num_bits / c + 1;
+    // Per vector of scalars, the digits of window `w` are at `w * bases.len()..`.
+    let digits: Vec<Vec<i32>> = scalars
+        .iter()
+        .map(|s| {
+            let per_scalar: Vec<Vec<i32>> = s[..n]
+                .iter()
+                .map(|s| signed_digits(s.as_ref(), c, num_windows))
+                .collect();
+            (0..num_windows)
+                .flat_map(|w| per_scalar.iter().map(move |d| d[w]))
+                .collect()
+        })
+        .collect();
+    let window_sums: Vec<Vec<G::Projective>> = (0..num_windows)
+        .map(|w| {
+            let mut buckets = vec![vec![G::Projective::zero(); 1 << (c - 1)]; scalars.len()];
+            let offset = w * bases.len();
+            for (i, base) in bases.iter().enumerate() {
+                for (buckets, digits) in buckets.iter_mut().zip(&digits) {
+                    let d = digits[offset + i];
+                    if d > 0 {
+                        buckets[d as usize - 1].add_assign_mixed(base);
+                    } else if d < 0 {
+                        buckets[(-d) as usize - 1].add_assign_mixed(&-*base);
+                    }
+                }
+            }
+            buckets
+                .into_iter()
+                .map(|buckets| {
+                    // sum_d d * buckets[d - 1], as the sum of the running sums from the top.
+                    let mut running = G::Projective::zero();
+                    let mut sum = G::Projective::zero();
+                    for b in buckets.into_iter().rev() {
+                        running += b;
+                        sum += running;
+                    }
+                    sum
+                })
+                .collect()
+        })
+        .collect();
+    (0..scalars.len())
+        .map(|j| {
+            // Horner's rule, from the top window down.
+            window_sums.iter().rev().fold(G::Projective::zero(), |mut acc, sums| {
+                for _ in 0..c {
+                    ProjectiveCurve::double_in_place(&mut acc);
+                }
+                acc + sums[j]
+            })
+        })
+        .collect()
+}
+
+/// The window size for an MSM of `n` terms: one bit wider than `ark-ec`'s, for as many buckets.
+fn window_bits(n: usize) -> usize {
+    if n < 32 {
+        4
+    } else {
+        (ark_std::log2(n) * 69 / 100) as usize + 3
+    }
+}
+
+/// The `num_windows` signed `c`-bit digits of the little-endian `limbs`, lowest first.
+fn signed_digits(limbs: &[u64], c: usize, num_windows: usize) -> Vec<i32> {
+    let half = 1i32 << (c - 1);
+    let mut carry = 0;
+    (0..num_windows)
+        .map(|w| {
+            let d = digit(limbs, w * c, c) as i32 + carry;
+            carry = (d > half) as i32;
+            d - (carry << c)
+        })
+        .collect()
+}
+
+/// Bits `start..start + c` of the little-endian `limbs` (zero past their end).
+fn digit(limbs: &[u64], start: usize, c: usize) -> usize {
+    let (limb, shift) = (start / 64, start % 64);
+    if limb >= limbs.len() {
+        return 0;
+    }
+    let mut d = limbs[limb] >> shift;
+    if shift + c > 64 && limb + 1 < limbs.len() {
+        d |= limbs[limb + 1] << (64 - shift);
+    }
+    (d & ((1 << c) - 1)) as usize
+}
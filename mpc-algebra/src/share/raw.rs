@@ -0,0 +1,222 @@
+//! Raw shares, in byte layouts that other MPC frameworks use, for handing shares between them and
+//! the provers here.
+//!
+//! A [Layout] fixes how each field element is written: as its canonical integer or in Montgomery
+//! form (the integer times `R = 2^(64 * limbs)`, mod the modulus), and little- or big-endian.
+//! Either way an element takes [Layout::width] bytes, the size of the field's integer type; what
+//! each share writes besides its value is documented on its [RawShare] impl.
+//!
+//! These move shares only. Whatever a share is checked against must agree with this crate's: a
+//! SPDZ MAC made under another framework's MAC key fails the MAC check when the value is opened,
+//! so shares from elsewhere should come in as additive shares, and be re-MACed here.
+use ark_ff::{BigInteger, FpParameters, FromBytes, PrimeField};
+use std::io::{self, Read, Write};
+
+/// How a field element is written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Form {
+    /// The element's integer, less than the modulus.
+    Canonical,
+    /// The element's integer times `R`, mod the modulus: how most libraries (this one, MP-SPDZ)
+    /// hold elements in memory.
+    Montgomery,
+}
+
+/// The order of an element's bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+/// The layout of a raw field element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub form: Form,
+    pub endian: Endian,
+}
+
+impl Layout {
+    /// As [ark_ff::ToBytes] writes elements: canonical, little-endian.
+    pub const CANONICAL_LE: Self = Self {
+        form: Form::Canonical,
+        endian: Endian::Little,
+    };
+    /// Canonical, big-endian: as integers are usually printed, and as GMP exports them.
+    pub const CANONICAL_BE: Self = Self {
+        form: Form::Canonical,
+        endian: Endian::Big,
+    };
+    /// Montgomery form, little-endian: as MP-SPDZ's binary share files store prime-field shares.
+    pub const MONTGOMERY_LE: Self = Self {
+        form: Form::Montgomery,
+        endian: Endian::Little,
+    };
+
+    /// How many bytes an element of `F` takes.
+    pub fn width<F: PrimeField>() -> usize {
+        F::BigInt::NUM_LIMBS * 8
+    }
+
+    /// `f`'s bytes in this layout.
+    pub fn to_bytes<F: PrimeField>(&self, f: &F) -> Vec<u8> {
+        let int = match self.form {
+            Form::Canonical => f.into_repr(),
+            Form::Montgomery => (*f * r::<F>()).into_repr(),
+        };
+        match self.endian {
+            Endian::Little => int.to_bytes_le(),
+            Endian::Big => int.to_bytes_be(),
+        }
+    }
+
+    /// The element whose bytes in this layout are `bytes`, which must be [Self::width] long and
+    /// (in either form) an integer less than the modulus.
+    pub fn from_bytes<F: PrimeField>(&self, bytes: &[u8]) -> io::Result<F> {
+        if bytes.len() != Self::width::<F>() {
+            return Err(invalid(format!(
+                "{} bytes for an element of {}",
+                bytes.len(),
+                Self::width::<F>()
+            )));
+        }
+        let mut le = bytes.to_vec();
+        if self.endian == Endian::Big {
+            le.reverse();
+        }
+        let int = F::BigInt::read(&le[..])?;
+        let f = F::from_repr(int).ok_or_else(|| invalid("not less than the modulus"))?;
+        Ok(match self.form {
+            Form::Canonical => f,
+            Form::Montgomery => f * r::<F>().inverse().unwrap(),
+        })
+    }
+
+    /// Writes `f` in this layout.
+    pub fn write<F: PrimeField, W: Write>(&self, f: &F, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes(f))
+    }
+
+    /// Reads an element in this layout.
+    pub fn read<F: PrimeField, R: Read>(&self, mut reader: R) -> io::Result<F> {
+        let mut bytes = vec![0u8; Self::width::<F>()];
+        reader.read_exact(&mut bytes)?;
+        self.from_bytes(&bytes)
+    }
+}
+
+/// `R`, as an element.
+fn r<F: PrimeField>() -> F {
+    F::from_repr(<F::Params as FpParameters>::R).unwrap()
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// A field share that can be written and read raw, in a [Layout].
+///
+/// A share is this party's only, so exporting or importing one involves no communication; every
+/// party handles its own, in the same order as the others.
+pub trait RawShare<F: PrimeField>: Sized {
+    fn write_raw<W: Write>(&self, layout: Layout, writer: W) -> io::Result<()>;
+    fn read_raw<R: Read>(layout: Layout, reader: R) -> io::Result<Self>;
+
+    /// Writes each of `shares`, back to back.
+    fn write_raw_batch<W: Write>(shares: &[Self], layout: Layout, mut writer: W) -> io::Result<()> {
+        shares
+            .iter()
+            .try_for_each(|s| s.write_raw(layout, &mut writer))
+    }
+
+    /// Reads `n` shares, back to back.
+    fn read_raw_batch<R: Read>(n: usize, layout: Layout, mut reader: R) -> io::Result<Vec<Self>> {
+        (0..n)
+            .map(|_| Self::read_raw(layout, &mut reader))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{malicious_majority::MpcField, AdditiveFieldShare, Reveal};
+    use ark_bls12_377::Fr;
+    use ark_ff::{One, UniformRand};
+    use mpc_net::testing::run_parties;
+
+    const LAYOUTS: [Layout; 4] = [
+        Layout::CANONICAL_LE,
+        Layout::CANONICAL_BE,
+        Layout::MONTGOMERY_LE,
+        Layout {
+            form: Form::Montgomery,
+            endian: Endian::Big,
+        },
+    ];
+
+    #[test]
+    fn layouts_round_trip() {
+        let rng = &mut ark_std::test_rng();
+        let fs: Vec<Fr> = (0..8).map(|_| Fr::rand(rng)).collect();
+        for layout in &LAYOUTS {
+            for f in &fs {
+                let bytes = layout.to_bytes(f);
+                assert_eq!(bytes.len(), Layout::width::<Fr>());
+                assert_eq!(layout.from_bytes::<Fr>(&bytes).unwrap(), *f);
+            }
+            let shares: Vec<_> = fs.iter().map(|&val| AdditiveFieldShare { val }).collect();
+            let mut bytes = Vec::new();
+            RawShare::write_raw_batch(&shares, *layout, &mut bytes).unwrap();
+            let back = AdditiveFieldShare::read_raw_batch(fs.len(), *layout, &bytes[..]).unwrap();
+            assert_eq!(back, shares);
+        }
+    }
+
+    #[test]
+    fn layouts_match_the_integers() {
+        let mut one = vec![0u8; Layout::width::<Fr>()];
+        one[0] = 1;
+        assert_eq!(Layout::CANONICAL_LE.to_bytes(&Fr::one()), one);
+        one.reverse();
+        assert_eq!(Layout::CANONICAL_BE.to_bytes(&Fr::one()), one);
+        let r = <<Fr as PrimeField>::Params as FpParameters>::R;
+        assert_eq!(Layout::MONTGOMERY_LE.to_bytes(&Fr::one()), r.to_bytes_le());
+        // Ark's own element bytes are the canonical little-endian ones.
+        let f = Fr::from(1234567u64);
+        assert_eq!(
+            ark_ff::to_bytes![f].unwrap(),
+            Layout::CANONICAL_LE.to_bytes(&f)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_elements() {
+        let modulus = <<Fr as PrimeField>::Params as FpParameters>::MODULUS;
+        for layout in &LAYOUTS {
+            let bytes = match layout.endian {
+                Endian::Little => modulus.to_bytes_le(),
+                Endian::Big => modulus.to_bytes_be(),
+            };
+            assert!(layout.from_bytes::<Fr>(&bytes).is_err());
+            assert!(layout.from_bytes::<Fr>(&bytes[1..]).is_err());
+        }
+    }
+
+    #[test]
+    fn spdz_shares_round_trip() {
+        run_parties("share::raw::tests::spdz_shares_round_trip", 2, || {
+            let rng = &mut ark_std::test_rng();
+            let xs: Vec<MpcField<Fr>> = (0..4)
+                .map(|i| MpcField::joint_rand(rng) * MpcField::from(i as u64))
+                .chain(Some(MpcField::from(7u64)))
+                .collect();
+            let mut bytes = Vec::new();
+            MpcField::write_raw_shares(&xs, Layout::MONTGOMERY_LE, &mut bytes).unwrap();
+            assert_eq!(bytes.len(), xs.len() * 2 * Layout::width::<Fr>());
+            let back = MpcField::<Fr>::read_raw_shares(xs.len(), Layout::MONTGOMERY_LE, &bytes[..]);
+            // Opening checks the MACs, which came through too.
+            assert_eq!(back.unwrap().reveal(), xs.reveal());
+        });
+    }
+}
@@ -20,15 +20,21 @@ use mpc_net::{MpcNet, MpcMultiNet as Net};
 use crate::channel::{can_cheat, MpcSerNet};
 
 use super::add::{AdditiveFieldShare, AdditiveGroupShare, MulFieldShare};
-use super::field::{DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare};
-use super::group::GroupShare;
+use super::field::{
+    count_field_openings, DenseOrSparsePolynomial, DensePolynomial, ExtFieldShare, FieldShare,
+    SUM_TREE_MIN_PARTIES,
+};
+use super::group::{count_group_openings, GroupShare};
 use super::msm::*;
 use super::pairing::{AffProjShare, PairingShare};
+use super::raw::{Layout, RawShare};
 use super::{BeaverSource, PanicBeaverSource};
+use crate::security::FIXED_MAC_KEY;
 use crate::Reveal;
 
 #[inline]
 pub fn mac_share<F: Field>() -> F {
+    FIXED_MAC_KEY.take();
     if Net::am_king() {
         F::one()
     } else {
@@ -40,6 +46,7 @@ pub fn mac_share<F: Field>() -> F {
 /// A huge cheat. Useful for importing shares.
 pub fn mac<F: Field>() -> F {
     if can_cheat() {
+        FIXED_MAC_KEY.take();
         F::one()
     } else {
         panic!("Attempted to grab the MAC secret while cheating was not allowed")
@@ -116,19 +123,40 @@ macro_rules! impl_basics_spdz {
 }
 impl_basics_spdz!(SpdzFieldShare, Field);
 
+/// Check our share `mac` of the MAC on the opened value `x`, with the others (_Pragmatic MPC_
+/// 6.6.2).
+fn check_mac<F: Field>(x: F, mac: F) {
+    let dx_t: F = mac_share::<F>() * x - mac;
+    let all_dx_ts: Vec<F> = Net::atomic_broadcast(&dx_t);
+    let sum: F = all_dx_ts.iter().sum();
+    assert!(crate::ct::is_zero(&sum), "Protocol abort: MAC check failed");
+}
+
 impl<F: Field> Reveal for SpdzFieldShare<F> {
     type Base = F;
 
     fn reveal(self) -> F {
+        count_field_openings(1);
         let vals: Vec<F> = Net::broadcast(&self.sh.val);
-        // _Pragmatic MPC_ 6.6.2
         let x: F = vals.iter().sum();
-        let dx_t: F = mac_share::<F>() * x - self.mac.val;
-        let all_dx_ts: Vec<F> = Net::atomic_broadcast(&dx_t);
-        let sum: F = all_dx_ts.iter().sum();
-        assert!(sum.is_zero());
+        check_mac(x, self.mac.val);
         x
     }
+    /// Opens the value plus a mask that only `party` knows, so that the MAC check is the usual
+    /// one; the others learn only the masked value.
+    fn reveal_to(mut self, party: usize) -> Option<F> {
+        let me = Net::party_id() == party;
+        let mask = if me { F::pub_rand(&mut rand::thread_rng()) } else { F::zero() };
+        let m = Self::from_add_shared(mask);
+        self.sh.val += m.sh.val;
+        self.mac.val += m.mac.val;
+        let masked = self.reveal();
+        if me {
+            Some(masked - mask)
+        } else {
+            None
+        }
+    }
     fn from_public(f: F) -> Self {
         Self {
             sh: Reveal::from_public(f),
@@ -141,8 +169,12 @@ impl<F: Field> Reveal for SpdzFieldShare<F> {
             mac: Reveal::from_add_shared(f * mac::<F>()),
         }
     }
+    /// This party's additive share of the value (without its MAC).
+    unsafe fn unwrap_as_public(self) -> F {
+        self.sh.unwrap_as_public()
+    }
     fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
-        let mut r: Vec<F> = (0..(Net::n_parties()-1)).map(|_| F::rand(rng)).collect();
+        let mut r: Vec<F> = (0..(Net::n_parties()-1)).map(|_| F::pub_rand(rng)).collect();
         let sum_r: F = r.iter().sum();
         r.push(f - sum_r);
         Self::from_add_shared(Net::recv_from_king( if Net::am_king() { Some(r) } else { None }))
@@ -151,7 +183,7 @@ impl<F: Field> Reveal for SpdzFieldShare<F> {
         let mut rs: Vec<Vec<Self::Base>> =
             (0..(Net::n_parties()-1)).map(|_| {
             (0..f.len()).map(|_| {
-                F::rand(rng)
+                F::pub_rand(rng)
             }).collect()
         }).collect();
         let final_shares: Vec<Self::Base> = (0..rs[0].len()).map(|i| {
@@ -162,12 +194,28 @@ impl<F: Field> Reveal for SpdzFieldShare<F> {
     }
 }
 
+/// The share of the value, then the share of its MAC, which must be under this crate's MAC key
+/// (see [mac_share]).
+impl<F: PrimeField> RawShare<F> for SpdzFieldShare<F> {
+    fn write_raw<W: Write>(&self, layout: Layout, mut writer: W) -> io::Result<()> {
+        self.sh.write_raw(layout, &mut writer)?;
+        self.mac.write_raw(layout, writer)
+    }
+    fn read_raw<R: Read>(layout: Layout, mut reader: R) -> io::Result<Self> {
+        Ok(Self {
+            sh: RawShare::read_raw(layout, &mut reader)?,
+            mac: RawShare::read_raw(layout, reader)?,
+        })
+    }
+}
+
 impl<F: Field> FieldShare<F> for SpdzFieldShare<F> {
     fn batch_open(selfs: impl IntoIterator<Item = Self>) -> Vec<F> {
         let (s_vals, macs): (Vec<F>, Vec<F>) =
             selfs.into_iter().map(|s| (s.sh.val, s.mac.val)).unzip();
         let n = s_vals.len();
-        let all_vals = Net::broadcast(&s_vals);
+        count_field_openings(n);
+        let all_vals = Net::broadcast_slice(&s_vals);
         let vals: Vec<F> =
             (0..n).map(|i| all_vals.iter().map(|v| &v[i]).sum()).collect();
         let dx_ts: Vec<F> =
@@ -179,10 +227,27 @@ impl<F: Field> FieldShare<F> for SpdzFieldShare<F> {
         let all_dx_ts: Vec<Vec<F>> = Net::atomic_broadcast(&dx_ts);
         for i in 0..n {
             let sum: F = all_dx_ts.iter().map(|dx_ts| &dx_ts[i]).sum();
-            assert!(sum.is_zero());
+            assert!(crate::ct::is_zero(&sum), "Protocol abort: MAC check failed");
         }
         vals
     }
+    /// Sums the value shares up a tree with enough parties, as [AdditiveFieldShare] does; the
+    /// MAC check is as for any opening.
+    fn sum_and_open(selfs: impl IntoIterator<Item = Self>) -> F {
+        let sum = selfs
+            .into_iter()
+            .fold(Self::from_add_shared(F::zero()), |mut acc, s| {
+                acc.add(&s);
+                acc
+            });
+        if Net::n_parties() < SUM_TREE_MIN_PARTIES {
+            return sum.open();
+        }
+        count_field_openings(1);
+        let x = Net::reduce(&sum.sh.val, |a, b| a + b);
+        check_mac(x, sum.mac.val);
+        x
+    }
     fn add(&mut self, other: &Self) -> &mut Self {
         self.sh.add(&other.sh);
         self.mac.add(&other.mac);
@@ -260,6 +325,7 @@ impl<G: Group, M> Reveal for SpdzGroupShare<G, M> {
     type Base = G;
 
     fn reveal(self) -> G {
+        count_group_openings(1);
         let vals: Vec<G> = Net::broadcast(&self.sh.val);
         // _Pragmatic MPC_ 6.6.2
         let x: G = vals.iter().sum();
@@ -270,9 +336,23 @@ impl<G: Group, M> Reveal for SpdzGroupShare<G, M> {
         };
         let all_dx_ts: Vec<G> = Net::atomic_broadcast(&dx_t);
         let sum: G = all_dx_ts.iter().sum();
-        assert!(sum.is_zero());
+        assert!(crate::ct::is_zero(&sum), "Protocol abort: MAC check failed");
         x
     }
+    /// As for [SpdzFieldShare::reveal_to], with a random group element as the mask.
+    fn reveal_to(mut self, party: usize) -> Option<G> {
+        let me = Net::party_id() == party;
+        let mask = if me { G::rand(&mut rand::thread_rng()) } else { G::zero() };
+        let m = Self::from_add_shared(mask);
+        self.sh.val += m.sh.val;
+        self.mac.val += m.mac.val;
+        let masked = self.reveal();
+        if me {
+            Some(masked - mask)
+        } else {
+            None
+        }
+    }
     fn from_public(f: G) -> Self {
         Self {
             sh: Reveal::from_public(f),
@@ -386,7 +466,8 @@ impl<G: Group, M: Msm<G, G::ScalarField>> GroupShare<G> for SpdzGroupShare<G, M>
         let (s_vals, macs): (Vec<G>, Vec<G>) =
             selfs.into_iter().map(|s| (s.sh.val, s.mac.val)).unzip();
         let n = s_vals.len();
-        let all_vals = Net::broadcast(&s_vals);
+        count_group_openings(n);
+        let all_vals = Net::broadcast_slice(&s_vals);
         let vals: Vec<G> =
             (0..n).map(|i| all_vals.iter().map(|v| &v[i]).sum()).collect();
         let dx_ts: Vec<G> =
@@ -398,7 +479,7 @@ impl<G: Group, M: Msm<G, G::ScalarField>> GroupShare<G> for SpdzGroupShare<G, M>
         let all_dx_ts: Vec<Vec<G>> = Net::atomic_broadcast(&dx_ts);
         for i in 0..n {
             let sum: G = all_dx_ts.iter().map(|dx_ts| &dx_ts[i]).sum();
-            assert!(sum.is_zero());
+            assert!(crate::ct::is_zero(&sum), "Protocol abort: MAC check failed");
         }
         vals
     }
@@ -439,9 +520,10 @@ impl<G: Group, M: Msm<G, G::ScalarField>> GroupShare<G> for SpdzGroupShare<G, M>
 
     fn multi_scale_pub_group(bases: &[G], scalars: &[Self::FieldShare]) -> Self {
         let shares: Vec<G::ScalarField> = scalars.into_iter().map(|s| s.sh.val.clone()).collect();
-        let macs: Vec<G::ScalarField> = scalars.into_iter().map(|s| s.sh.val.clone()).collect();
-        let sh = AdditiveGroupShare::from_add_shared(M::msm(bases, &shares));
-        let mac = AdditiveGroupShare::from_add_shared(M::msm(bases, &macs));
+        let macs: Vec<G::ScalarField> = scalars.into_iter().map(|s| s.mac.val.clone()).collect();
+        let mut results = M::msm_many(bases, &[shares, macs]);
+        let mac = AdditiveGroupShare::from_add_shared(results.pop().unwrap());
+        let sh = AdditiveGroupShare::from_add_shared(results.pop().unwrap());
         Self { sh, mac }
     }
 }
@@ -578,7 +660,7 @@ macro_rules! groups_share {
 
         impl<E: PairingEngine> AffProjShare<E::Fr, E::$affine, E::$proj> for $struct_name<E> {
             type FrShare = SpdzFieldShare<E::Fr>;
-            type AffineShare = SpdzGroupShare<E::$affine, AffineMsm<E::$affine>>;
+            type AffineShare = SpdzGroupShare<E::$affine, PippengerMsm<E::$affine>>;
             type ProjectiveShare = SpdzGroupShare<E::$proj, ProjectiveMsm<E::$proj>>;
 
             fn sh_aff_to_proj(g: Self::AffineShare) -> Self::ProjectiveShare {
@@ -640,8 +722,8 @@ impl<E: PairingEngine> PairingShare<E> for SpdzPairingShare<E> {
     type FqeShare = SpdzExtFieldShare<E::Fqe>;
     // Not a typo. We want a multiplicative subgroup.
     type FqkShare = SpdzMulExtFieldShare<E::Fqk, E::Fr>;
-    type G1AffineShare = SpdzGroupShare<E::G1Affine, AffineMsm<E::G1Affine>>;
-    type G2AffineShare = SpdzGroupShare<E::G2Affine, AffineMsm<E::G2Affine>>;
+    type G1AffineShare = SpdzGroupShare<E::G1Affine, PippengerMsm<E::G1Affine>>;
+    type G2AffineShare = SpdzGroupShare<E::G2Affine, PippengerMsm<E::G2Affine>>;
     type G1ProjectiveShare =
         SpdzGroupShare<E::G1Projective, ProjectiveMsm<E::G1Projective>>;
     type G2ProjectiveShare =
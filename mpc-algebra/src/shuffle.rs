@@ -0,0 +1,252 @@
+//! Shuffling a shared list: applying a permutation that no party knows.
+//!
+//! A [Benes] network on `2^k` wires is `2k - 1` layers of switches, each of which passes its two
+//! wires straight through or crosses them, and which together can route any permutation of the
+//! wires. Its structure is public: what a permutation fixes is only which switches cross. A party
+//! that knows a permutation finds those settings ([Benes::route]); shared, they apply the
+//! permutation to shared values without revealing it, at one multiplication per switch: a switch
+//! with setting `b` on `(x, y)` outputs `(x + d, y - d)`, for `d = b * (y - x)`. The switches of a
+//! layer are independent, so this is a round per layer.
+//!
+//! In [MpcField::shuffle], each party in turn applies a random permutation of its own choosing,
+//! so the list ends up permuted by their composition, which is uniformly random, and unknown to
+//! any coalition that leaves out one of the parties. The settings are shared as the party's alone
+//! (as [MpcField::local_rand] shares a value), and nothing checks that they are bits: a party can
+//! set a switch to another value, and so mix its two wires' values rather than permuting them.
+//! A circuit over the settings and the products `d` ([Pass]) proves that the output is a
+//! permutation of the input, by checking that every setting is a bit.
+use ark_ff::{Field, PrimeField};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::share::field::FieldShare;
+use crate::wire::field::MpcField;
+use mpc_net::{MpcMultiNet as Net, MpcNet};
+
+/// A Beneš network with at least as many wires as the lists it permutes; the wires beyond those
+/// are padding, which every permutation it routes leaves in place.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Benes {
+    len: usize,
+    /// The wires that each switch of each layer connects, by position in the list.
+    layers: Vec<Vec<(usize, usize)>>,
+}
+
+impl Benes {
+    /// The network for lists of length `len`, on the next power of two (at least two) wires.
+    pub fn new(len: usize) -> Self {
+        let identity: Vec<usize> = (0..len).collect();
+        let layers = route(&identity)
+            .into_iter()
+            .map(|layer| layer.into_iter().map(|(a, b, _)| (a, b)).collect())
+            .collect();
+        Self { len, layers }
+    }
+
+    /// The length of the lists permuted.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn layers(&self) -> &[Vec<(usize, usize)>] {
+        &self.layers
+    }
+
+    /// The number of switches, in all layers.
+    pub fn switches(&self) -> usize {
+        self.layers.iter().map(Vec::len).sum()
+    }
+
+    /// The settings of the switches (layer by layer, in the order of [Benes::layers]) that send
+    /// the value at position `i` to position `perm[i]`; `perm` must be a permutation of
+    /// `0..len`.
+    pub fn route(&self, perm: &[usize]) -> Vec<bool> {
+        assert_eq!(perm.len(), self.len, "a permutation of the wrong length");
+        let mut seen = vec![false; perm.len()];
+        for &p in perm {
+            assert!(p < perm.len() && !seen[p], "not a permutation");
+            seen[p] = true;
+        }
+        route(perm)
+            .into_iter()
+            .flatten()
+            .map(|(_, _, cross)| cross)
+            .collect()
+    }
+
+    /// Apply the network, with switch settings `bits` (each 0 or 1, in the order of
+    /// [Benes::route]), to `values`.
+    ///
+    /// Returns the permuted values, and each switch's product `d = b * (y - x)`. A layer's
+    /// products are computed together, with [Field::batch_product_in_place], so over shares,
+    /// the values must all be shared, or all public, and so must the settings.
+    pub fn apply<F: Field>(&self, values: &[F], bits: &[F]) -> (Vec<F>, Vec<F>) {
+        assert_eq!(values.len(), self.len, "a list of the wrong length");
+        assert_eq!(
+            bits.len(),
+            self.switches(),
+            "the wrong number of switch settings"
+        );
+        // Padding that is shared if the values are, so that no layer mixes the two.
+        let zero = values.first().map_or(F::zero(), |v| *v - v);
+        let mut wires = values.to_vec();
+        wires.resize(self.width(), zero);
+        let mut products = Vec::with_capacity(bits.len());
+        let mut bits = bits;
+        for layer in &self.layers {
+            let (ours, rest) = bits.split_at(layer.len());
+            bits = rest;
+            let mut d: Vec<F> = layer.iter().map(|&(a, b)| wires[b] - wires[a]).collect();
+            F::batch_product_in_place(&mut d, ours);
+            for (&(a, b), d) in layer.iter().zip(&d) {
+                wires[a] += d;
+                wires[b] -= d;
+            }
+            products.extend(d);
+        }
+        wires.truncate(self.len);
+        (wires, products)
+    }
+
+    fn width(&self) -> usize {
+        2 * self.layers.first().map_or(1, Vec::len)
+    }
+}
+
+/// The switches, by layer, of a network that routes `perm` (extended to a power of two by
+/// fixing the padding): the wires each connects, and whether it crosses them.
+fn route(perm: &[usize]) -> Vec<Vec<(usize, usize, bool)>> {
+    let width = perm.len().max(2).next_power_of_two();
+    let sigma: Vec<usize> = (0..width)
+        .map(|i| perm.get(i).copied().unwrap_or(i))
+        .collect();
+    let positions: Vec<usize> = (0..width).collect();
+    let depth = 2 * width.trailing_zeros() as usize - 1;
+    let mut layers = vec![Vec::new(); depth];
+    route_at(&positions, &sigma, 0, &mut layers);
+    layers
+}
+
+/// Route `sigma` through the subnetwork on wires `positions`, whose first layer is `layer`.
+///
+/// The subnetwork's input (and output) `i` is at `positions[i]`. Its first layer switches inputs
+/// `2j` and `2j + 1`, leaving the one bound for the upper half at `positions[2j]`, and the other
+/// at `positions[2j + 1]`; so the upper half is on the even positions, and the lower on the odd.
+/// Its last layer switches the halves' outputs `j` into its outputs `2j` and `2j + 1`.
+fn route_at(
+    positions: &[usize],
+    sigma: &[usize],
+    layer: usize,
+    layers: &mut [Vec<(usize, usize, bool)>],
+) {
+    let m = positions.len();
+    if m == 2 {
+        layers[layer].push((positions[0], positions[1], sigma[0] == 1));
+        return;
+    }
+    let mut inverse = vec![0; m];
+    for (i, &o) in sigma.iter().enumerate() {
+        inverse[o] = i;
+    }
+    // The looping algorithm: the inputs of a switch go to different halves, and so do the
+    // sources of a pair of outputs; following those constraints around each cycle settles it.
+    let mut lower: Vec<Option<bool>> = vec![None; m];
+    for start in (0..m).step_by(2) {
+        if lower[start].is_some() {
+            continue;
+        }
+        let mut i = start;
+        while lower[i].is_none() {
+            lower[i] = Some(false);
+            lower[i ^ 1] = Some(true);
+            // The output paired with `i ^ 1`'s must come through the upper half.
+            i = inverse[sigma[i ^ 1] ^ 1];
+        }
+    }
+    let lower: Vec<bool> = lower.into_iter().map(Option::unwrap).collect();
+
+    let half = m / 2;
+    let (mut upper_sigma, mut lower_sigma) = (vec![0; half], vec![0; half]);
+    for i in 0..m {
+        let sub = if lower[i] {
+            &mut lower_sigma
+        } else {
+            &mut upper_sigma
+        };
+        sub[i / 2] = sigma[i] / 2;
+    }
+    for j in 0..half {
+        layers[layer].push((positions[2 * j], positions[2 * j + 1], lower[2 * j]));
+    }
+    let evens: Vec<usize> = positions.iter().step_by(2).copied().collect();
+    let odds: Vec<usize> = positions.iter().skip(1).step_by(2).copied().collect();
+    route_at(&evens, &upper_sigma, layer + 1, layers);
+    route_at(&odds, &lower_sigma, layer + 1, layers);
+    let last = layer + 2 * m.trailing_zeros() as usize - 2;
+    for k in 0..half {
+        let from_lower = lower[inverse[2 * k]];
+        layers[last].push((positions[2 * k], positions[2 * k + 1], from_lower));
+    }
+}
+
+/// One party's pass of a shuffle: its (shared) switch settings, their products, and the list
+/// they output.
+#[derive(Clone, Debug)]
+pub struct Pass<F> {
+    pub owner: usize,
+    pub bits: Vec<F>,
+    pub products: Vec<F>,
+    pub outputs: Vec<F>,
+}
+
+impl<F: PrimeField, S: FieldShare<F>> MpcField<F, S> {
+    /// `values` in an order that no party knows (unless all collude); see [crate::shuffle].
+    ///
+    /// Each party draws its permutation from `rng`, which must be its own: parties that pass the
+    /// same seeded `rng` draw the same permutations. For additive sharings only, and
+    /// `2 * ceil(log2(len)) - 1` rounds of multiplications per party.
+    pub fn shuffle<R: Rng + ?Sized>(values: &[Self], rng: &mut R) -> Vec<Self> {
+        let owners: Vec<usize> = (0..Net::n_parties()).collect();
+        match Self::shuffle_passes(values, &owners, rng).pop() {
+            Some(pass) => pass.outputs,
+            None => values.to_vec(),
+        }
+    }
+
+    /// Shuffle `values` with a pass by each of `owners`, in order, keeping what each computed.
+    pub fn shuffle_passes<R: Rng + ?Sized>(
+        values: &[Self],
+        owners: &[usize],
+        rng: &mut R,
+    ) -> Vec<Pass<Self>> {
+        let network = Benes::new(values.len());
+        let mut list: Vec<Self> = values.iter().map(|v| v.into_shared()).collect();
+        let mut passes = Vec::with_capacity(owners.len());
+        for &owner in owners {
+            let settings = if Net::party_id() == owner {
+                let mut perm: Vec<usize> = (0..values.len()).collect();
+                perm.shuffle(rng);
+                network.route(&perm)
+            } else {
+                vec![false; network.switches()]
+            };
+            let bits: Vec<Self> = settings
+                .into_iter()
+                .map(|b| Self::Shared(S::from_add_shared(F::from(b))))
+                .collect();
+            let (outputs, products) = network.apply(&list, &bits);
+            list = outputs.clone();
+            passes.push(Pass {
+                owner,
+                bits,
+                products,
+                outputs,
+            });
+        }
+        passes
+    }
+}
@@ -5,7 +5,7 @@ use zeroize::Zeroize;
 
 use ark_ff::bytes::{FromBytes, ToBytes};
 use ark_ff::prelude::*;
-use ark_ff::{poly_stub, FftField};
+use ark_ff::{poly_stub, BitIteratorLE, FftField};
 use ark_serialize::{
     CanonicalDeserialize, CanonicalDeserializeWithFlags, CanonicalSerialize,
     CanonicalSerializeWithFlags, Flags, SerializationError,
@@ -19,8 +19,11 @@ use std::marker::PhantomData;
 use std::ops::*;
 
 use super::super::share::field::FieldShare;
-use super::super::share::BeaverSource;
-use crate::Reveal;
+use super::super::share::raw::{Layout, RawShare};
+use super::super::share::{BeaverSource, BitShare};
+use crate::preprocessing::SessionTripleSource;
+use crate::security::DUMMY_TRIPLES;
+use crate::{count_inputs, public_to, NotPublic, Reveal};
 use mpc_net::{MpcNet, MpcMultiNet as Net};
 
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -41,6 +44,7 @@ pub struct DummyFieldTripleSource<T, S> {
 impl<T: Field, S: FieldShare<T>> BeaverSource<S, S, S> for DummyFieldTripleSource<T, S> {
     #[inline]
     fn triple(&mut self) -> (S, S, S) {
+        DUMMY_TRIPLES.take();
         (
             S::from_add_shared(if Net::am_king() {
                 T::one()
@@ -61,6 +65,7 @@ impl<T: Field, S: FieldShare<T>> BeaverSource<S, S, S> for DummyFieldTripleSourc
     }
     #[inline]
     fn inv_pair(&mut self) -> (S, S) {
+        DUMMY_TRIPLES.take();
         (
             S::from_add_shared(if Net::am_king() {
                 T::one()
@@ -77,15 +82,26 @@ impl<T: Field, S: FieldShare<T>> BeaverSource<S, S, S> for DummyFieldTripleSourc
 }
 
 impl<T: Field, S: FieldShare<T>> MpcField<T, S> {
+    /// A shared value's inverse takes a masked opening of its own; to invert many, queue them
+    /// ([ark_ff::InversionQueue]) or pass them to [ark_ff::batch_inversion], to share one.
     #[inline]
     pub fn inv(self) -> Option<Self> {
         match self {
             Self::Public(x) => x.inverse().map(MpcField::Public),
             Self::Shared(x) => Some(MpcField::Shared(
-                x.inv(&mut DummyFieldTripleSource::default()),
+                x.inv(&mut SessionTripleSource::default()),
             )),
         }
     }
+    /// The same value, as a (trivial) share if it is public.
+    ///
+    /// Useful for witnesses that happen to be public, since provers expect all of them shared.
+    pub fn into_shared(self) -> Self {
+        match self {
+            Self::Public(x) => Self::Shared(S::from_public(x)),
+            s => s,
+        }
+    }
     pub fn all_public_or_shared(v: impl IntoIterator<Item = Self>) -> Result<Vec<T>, Vec<S>> {
         let mut out_a = Vec::new();
         let mut out_b = Vec::new();
@@ -103,7 +119,250 @@ impl<T: Field, S: FieldShare<T>> MpcField<T, S> {
             Err(out_b)
         }
     }
+    /// The sum of `v`, opened with a single opening (see [FieldShare::sum_and_open]) rather
+    /// than one per element; public elements are added in without communication.
+    pub fn sum_and_open(v: impl IntoIterator<Item = Self>) -> T {
+        let mut public = T::zero();
+        let mut shared = Vec::new();
+        for x in v {
+            match x {
+                Self::Public(x) => public += x,
+                Self::Shared(x) => shared.push(x),
+            }
+        }
+        if shared.is_empty() {
+            public
+        } else {
+            public + S::sum_and_open(shared)
+        }
+    }
+}
+
+impl<F: PrimeField, S: FieldShare<F> + RawShare<F>> MpcField<F, S> {
+    /// Writes this party's share of each of `xs` in `layout` (see [crate::share::raw]). A public
+    /// value is written as the share that [Reveal::from_public] gives it.
+    pub fn write_raw_shares<W: Write>(xs: &[Self], layout: Layout, writer: W) -> io::Result<()> {
+        let shares: Vec<S> = xs
+            .iter()
+            .map(|x| match x {
+                Self::Public(x) => S::from_public(*x),
+                Self::Shared(s) => *s,
+            })
+            .collect();
+        S::write_raw_batch(&shares, layout, writer)
+    }
+
+    /// Reads this party's shares of `n` values in `layout`, as [Self::write_raw_shares] writes
+    /// them.
+    pub fn read_raw_shares<R: Read>(n: usize, layout: Layout, reader: R) -> io::Result<Vec<Self>> {
+        Ok(S::read_raw_batch(n, layout, reader)?
+            .into_iter()
+            .map(Self::Shared)
+            .collect())
+    }
 }
+
+/// Statistical security parameter (in bits) for masking in [MpcField::bit_decomposition].
+pub const BIT_DECOMPOSITION_SECURITY: usize = 40;
+
+impl<F: PrimeField, S: FieldShare<F>> MpcField<F, S> {
+    /// The `k` low bits of `self`, least-significant first, which must be less than `2^k`.
+    ///
+    /// For a shared value, the parties mask it with random shared bits `r`, open `c = self + r`,
+    /// and compute the bits of `c - r` with a ripple-borrow subtractor, taking one multiplication
+    /// (and one round) per bit. If `self` is not less than `2^k`, the result is garbage, and any
+    /// proof that it decomposes `self` will fail.
+    pub fn bit_decomposition(self, k: usize) -> Vec<Self> {
+        match self {
+            Self::Public(x) => {
+                let bits = x.into_repr().to_bits_le();
+                (0..k)
+                    .map(|i| Self::from(bits.get(i).cloned().unwrap_or(false)))
+                    .collect()
+            }
+            Self::Shared(_) => {
+                let m = k + BIT_DECOMPOSITION_SECURITY;
+                assert!(
+                    (m as u32) < F::size_in_bits() as u32 - 1,
+                    "cannot decompose {} bits with {} bits of masking",
+                    k,
+                    BIT_DECOMPOSITION_SECURITY
+                );
+                let r: Vec<Self> = crate::preprocessing::random_bits::<F, S>(m)
+                    .into_iter()
+                    .map(Self::Shared)
+                    .collect();
+                let two = Self::from(2u8);
+                let mask = r.iter().rev().fold(Self::zero(), |acc, b| acc * two + b);
+                let c = (self + mask).reveal().into_repr().to_bits_le();
+                let mut borrow = Self::zero();
+                let mut out = Vec::with_capacity(k);
+                for (i, r_i) in r.into_iter().take(k).enumerate() {
+                    let c_i = Self::from(c[i]);
+                    // u = c_i xor r_i, linear since c_i is public
+                    let u = c_i + r_i - two * c_i * r_i;
+                    let u_borrow = u * borrow;
+                    out.push(u + borrow - two * u_borrow);
+                    // Borrow iff (!c_i && r_i) or (!u && borrow); these are exclusive.
+                    borrow = (Self::one() - c_i) * r_i + borrow - u_borrow;
+                }
+                out
+            }
+        }
+    }
+
+    /// `1` if `self >= other` and `0` otherwise, where both are less than `2^k`.
+    ///
+    /// This is the top bit of `self - other + 2^k`, so it costs one [Self::bit_decomposition] of
+    /// `k + 1` bits.
+    pub fn is_ge(self, other: Self, k: usize) -> Self {
+        let shift = (0..k).fold(Self::one(), |acc, _| acc.double());
+        (self - other + shift).bit_decomposition(k + 1).pop().unwrap()
+    }
+
+    /// `self` to the power whose bits (least-significant first) are `exp_bits`.
+    ///
+    /// The bits are typically shared, e.g., from [Self::bit_decomposition] of a shared exponent.
+    /// Square-and-multiply selects `self^(2^i)` or `1` by each bit, so each bit costs up to three
+    /// multiplications (and rounds): a squaring, a selection, and an accumulation.
+    pub fn pow_shared(self, exp_bits: &[Self]) -> Self {
+        let one = Self::one();
+        let mut base = self;
+        let mut acc = one;
+        for (i, b) in exp_bits.iter().enumerate() {
+            acc *= one + *b * (base - one);
+            if i + 1 < exp_bits.len() {
+                base = base.square();
+            }
+        }
+        acc
+    }
+
+    /// Each of `values` to the public power `exp` (little-endian limbs, as for [Field::pow]).
+    ///
+    /// Public values are raised locally. The shared ones share one square-and-multiply chain: at
+    /// each bit of `exp`, their squarings and their multiplications into the accumulators are
+    /// one batch, so the whole batch takes a round per bit, rather than two per bit and value.
+    pub fn batch_pow_public(values: &[Self], exp: &[u64]) -> Vec<Self> {
+        let bits: Vec<bool> = BitIteratorLE::without_trailing_zeros(exp).collect();
+        let mut bases: Vec<S> = values
+            .iter()
+            .filter_map(|x| match x {
+                Self::Shared(s) => Some(*s),
+                Self::Public(_) => None,
+            })
+            .collect();
+        // `None` while the accumulators are still one.
+        let mut accs: Option<Vec<S>> = None;
+        if !bases.is_empty() {
+            for (i, bit) in bits.iter().enumerate() {
+                let last = i + 1 == bits.len();
+                let (mut xs, mut ys) = (Vec::new(), Vec::new());
+                let multiply = *bit && accs.is_some();
+                if multiply {
+                    xs.extend(accs.take().unwrap());
+                    ys.extend_from_slice(&bases);
+                } else if *bit {
+                    accs = Some(bases.clone());
+                }
+                if !last {
+                    xs.extend_from_slice(&bases);
+                    ys.extend_from_slice(&bases);
+                }
+                if xs.is_empty() {
+                    continue;
+                }
+                let mut products = S::batch_mul(xs, ys, &mut SessionTripleSource::default());
+                if !last {
+                    bases = products.split_off(products.len() - bases.len());
+                }
+                if multiply {
+                    accs = Some(products);
+                }
+            }
+        }
+        let mut shared = accs.into_iter().flatten();
+        values
+            .iter()
+            .map(|x| match x {
+                Self::Public(x) => Self::Public(x.pow(exp)),
+                Self::Shared(_) => shared.next().map_or(Self::one(), Self::Shared),
+            })
+            .collect()
+    }
+
+    /// The `k` low bits of `self` as boolean shares, least-significant first; `self` must be less
+    /// than `2^k`. See [crate::share::bit::a2b], which converts many values together.
+    pub fn to_bit_shares(self, k: usize) -> Vec<BitShare> {
+        match self {
+            Self::Public(x) => {
+                let bits = x.into_repr().to_bits_le();
+                (0..k)
+                    .map(|i| BitShare::from_public(bits.get(i).cloned().unwrap_or(false)))
+                    .collect()
+            }
+            Self::Shared(s) => crate::share::bit::a2b::<F, S>(&[s], k).pop().unwrap(),
+        }
+    }
+
+    /// Shared field elements, each `0` or `1`, for boolean shares `bits`, in one round.
+    pub fn from_bit_shares(bits: &[BitShare]) -> Vec<Self> {
+        crate::share::bit::b2a::<F, S>(bits)
+            .into_iter()
+            .map(Self::Shared)
+            .collect()
+    }
+
+    /// The indicator vector of `self` among `0..n`: a `1` at position `self`, and `0`s
+    /// elsewhere. `self` must be less than `n`.
+    ///
+    /// For a shared index, the vector is built up one bit of the index at a time: each of the
+    /// `k = ceil(log2(n))` bits from [Self::bit_decomposition] doubles the vector, splitting each
+    /// entry `e` into `e * (1 - b)` and `e * b`. That is a round of multiplications per bit,
+    /// `2^k` multiplications in all, and no openings beyond the decomposition's.
+    pub fn one_hot(self, n: usize) -> Vec<Self> {
+        let k = (0..).find(|&k| 1usize << k >= n).unwrap();
+        let mut hot = vec![Self::one()];
+        // With one entry, there is nothing to decompose.
+        let bits = if k == 0 { Vec::new() } else { self.bit_decomposition(k) };
+        for (i, b) in bits.into_iter().enumerate() {
+            let high: Vec<Self> = if i == 0 {
+                vec![b]
+            } else {
+                let mut high = hot.clone();
+                Self::batch_product_in_place(&mut high, &vec![b; hot.len()]);
+                high
+            };
+            for (e, h) in hot.iter_mut().zip(&high) {
+                *e -= h;
+            }
+            hot.extend(high);
+        }
+        hot.truncate(n);
+        hot
+    }
+
+    /// `table[self]`, shared if `self` is, without revealing `self`; which must be less than
+    /// `table.len()`.
+    ///
+    /// This is the [Self::one_hot] vector of `self`, dotted with the (public) table, so beyond
+    /// the indicator vector, it is free.
+    pub fn lookup(self, table: &[F]) -> Self {
+        match self {
+            Self::Public(i) => {
+                let i = i.into_repr().as_ref()[0] as usize;
+                Self::Public(table[i])
+            }
+            Self::Shared(_) => self
+                .one_hot(table.len())
+                .into_iter()
+                .zip(table)
+                .map(|(e, t)| e * Self::Public(*t))
+                .sum(),
+        }
+    }
+}
+
 impl<'a, T: Field, S: FieldShare<T>> MulAssign<&'a MpcField<T, S>> for MpcField<T, S> {
     #[inline]
     fn mul_assign(&mut self, other: &Self) {
@@ -125,7 +384,7 @@ impl<'a, T: Field, S: FieldShare<T>> MulAssign<&'a MpcField<T, S>> for MpcField<
                     x.scale(y);
                 }
                 MpcField::Shared(y) => {
-                    let t = x.mul(*y, &mut DummyFieldTripleSource::default());
+                    let t = x.mul(*y, &mut SessionTripleSource::default());
                     *self = MpcField::Shared(t);
                 }
             },
@@ -139,15 +398,43 @@ impl<T: Field, S: FieldShare<T>> One for MpcField<T, S> {
     }
 }
 impl<T: Field, S: FieldShare<T>> Product for MpcField<T, S> {
-    #[inline]
+    /// The public factors are multiplied apart, and scale the product of the shared ones, which
+    /// are multiplied pairwise, a level of the tree per round. A public zero makes the product
+    /// public, with no shared multiplications at all.
     fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.fold(Self::one(), Mul::mul)
+        let mut public = T::one();
+        let mut shared = Vec::new();
+        for x in iter {
+            match x {
+                MpcField::Public(x) => public *= &x,
+                MpcField::Shared(x) => shared.push(x),
+            }
+        }
+        if public.is_zero() || shared.is_empty() {
+            return MpcField::Public(public);
+        }
+        while shared.len() > 1 {
+            let odd = if shared.len() % 2 == 1 {
+                shared.pop()
+            } else {
+                None
+            };
+            let (lefts, rights): (Vec<S>, Vec<S>) = shared
+                .chunks(2)
+                .map(|pair| (pair[0], pair[1]))
+                .unzip();
+            shared = S::batch_mul(lefts, rights, &mut SessionTripleSource::default());
+            shared.extend(odd);
+        }
+        let mut product = shared.pop().unwrap();
+        product.scale(&public);
+        MpcField::Shared(product)
     }
 }
 impl<'a, T: Field, S: FieldShare<T> + 'a> Product<&'a MpcField<T, S>> for MpcField<T, S> {
     #[inline]
     fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
-        iter.fold(Self::one(), |x, y| x.mul(y.clone()))
+        iter.cloned().product()
     }
 }
 
@@ -162,7 +449,7 @@ impl<'a, T: Field, S: FieldShare<T>> DivAssign<&'a MpcField<T, S>> for MpcField<
                     *x /= y;
                 }
                 MpcField::Shared(y) => {
-                    let mut t = y.inv(&mut DummyFieldTripleSource::default());
+                    let mut t = y.inv(&mut SessionTripleSource::default());
                     t.scale(&x);
                     *self = MpcField::Shared(t);
                 }
@@ -172,7 +459,7 @@ impl<'a, T: Field, S: FieldShare<T>> DivAssign<&'a MpcField<T, S>> for MpcField<
                     x.scale(&y.inverse().unwrap());
                 }
                 MpcField::Shared(y) => {
-                    let src = &mut DummyFieldTripleSource::default();
+                    let src = &mut SessionTripleSource::default();
                     *x = x.div(*y, src);
                 }
             },
@@ -226,15 +513,9 @@ impl<T: Field, S: FieldShare<T>> MpcWire for MpcField<T, S> {
             }
             _ => {}
         }
-        debug_assert!({
-            let self_val = if let MpcField::Public(s) = self {
-                s.clone()
-            } else {
-                unreachable!()
-            };
-            super::macros::check_eq(self_val.clone());
-            true
-        })
+        if let MpcField::Public(s) = self {
+            super::macros::check_eq(s.clone());
+        }
     }
     #[inline]
     fn is_shared(&self) -> bool {
@@ -257,6 +538,13 @@ impl<T: Field, S: FieldShare<T>> Reveal for MpcField<T, S> {
         result
     }
     #[inline]
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        match self {
+            Self::Shared(s) => s.reveal_to(party),
+            Self::Public(s) => public_to(party, s),
+        }
+    }
+    #[inline]
     fn from_public(b: Self::Base) -> Self {
         MpcField::Public(b)
     }
@@ -265,18 +553,27 @@ impl<T: Field, S: FieldShare<T>> Reveal for MpcField<T, S> {
         MpcField::Shared(S::from_add_shared(b))
     }
     #[inline]
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         match self {
             Self::Shared(s) => s.unwrap_as_public(),
             Self::Public(s) => s,
         }
     }
     #[inline]
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        match self {
+            Self::Shared(_) => Err(NotPublic::of::<Self>()),
+            Self::Public(s) => Ok(s),
+        }
+    }
+    #[inline]
     fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
+        count_inputs(1);
         Self::Shared(S::king_share(f, rng))
     }
     #[inline]
     fn king_share_batch<R: Rng>(f: Vec<Self::Base>, rng: &mut R) -> Vec<Self> {
+        count_inputs(f.len());
         S::king_share_batch(f, rng).into_iter().map(Self::Shared).collect()
     }
     fn init_protocol() {
@@ -302,8 +599,150 @@ impl<T: PrimeField, S: FieldShare<T>> std::str::FromStr for MpcField<T, S> {
     }
 }
 
+/// An [MpcField] in the middle of a run of additions and public scalings (see
+/// [Field::Acc](ark_ff::Field::Acc)).
+///
+/// It keeps the public and the shared part of the value apart, so adding a public value to a
+/// shared one costs no [FieldShare::shift] (which, e.g., for SPDZ also updates the MAC). The
+/// public part is shifted in once, by converting back to an [MpcField].
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Copy(bound = ""), Debug(bound = ""))]
+pub struct MpcFieldAcc<F: Field, S: FieldShare<F>> {
+    public: F,
+    shared: Option<S>,
+}
+
+impl<F: Field, S: FieldShare<F>> From<MpcField<F, S>> for MpcFieldAcc<F, S> {
+    #[inline]
+    fn from(x: MpcField<F, S>) -> Self {
+        match x {
+            MpcField::Public(x) => Self {
+                public: x,
+                shared: None,
+            },
+            MpcField::Shared(x) => Self {
+                public: F::zero(),
+                shared: Some(x),
+            },
+        }
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> From<MpcFieldAcc<F, S>> for MpcField<F, S> {
+    #[inline]
+    fn from(x: MpcFieldAcc<F, S>) -> Self {
+        match x.shared {
+            None => MpcField::Public(x.public),
+            Some(mut s) => {
+                if !x.public.is_zero() {
+                    s.shift(&x.public);
+                }
+                MpcField::Shared(s)
+            }
+        }
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> Zero for MpcFieldAcc<F, S> {
+    #[inline]
+    fn zero() -> Self {
+        Self {
+            public: F::zero(),
+            shared: None,
+        }
+    }
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.shared.is_none() && self.public.is_zero()
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> AddAssign for MpcFieldAcc<F, S> {
+    #[inline]
+    fn add_assign(&mut self, other: Self) {
+        self.public += other.public;
+        match (&mut self.shared, other.shared) {
+            (Some(x), Some(y)) => {
+                x.add(&y);
+            }
+            (None, y) => self.shared = y,
+            (Some(_), None) => {}
+        }
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> SubAssign for MpcFieldAcc<F, S> {
+    #[inline]
+    fn sub_assign(&mut self, other: Self) {
+        self.public -= other.public;
+        match (&mut self.shared, other.shared) {
+            (Some(x), Some(y)) => {
+                x.sub(&y);
+            }
+            (None, Some(mut y)) => {
+                y.neg();
+                self.shared = Some(y);
+            }
+            (_, None) => {}
+        }
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> AddAssign<MpcField<F, S>> for MpcFieldAcc<F, S> {
+    #[inline]
+    fn add_assign(&mut self, other: MpcField<F, S>) {
+        *self += Self::from(other);
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> MulAssign<MpcField<F, S>> for MpcFieldAcc<F, S> {
+    /// Scale by a public value.
+    ///
+    /// Panics if `other` is shared: that would be a multiplication, not a linear operation.
+    #[inline]
+    fn mul_assign(&mut self, other: MpcField<F, S>) {
+        match other {
+            MpcField::Public(y) => {
+                if !self.public.is_zero() {
+                    self.public *= y;
+                }
+                if let Some(x) = &mut self.shared {
+                    x.scale(&y);
+                }
+            }
+            MpcField::Shared(_) => panic!("MpcFieldAcc can only be scaled by public values"),
+        }
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> Add for MpcFieldAcc<F, S> {
+    type Output = Self;
+    #[inline]
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> Sub for MpcFieldAcc<F, S> {
+    type Output = Self;
+    #[inline]
+    fn sub(mut self, other: Self) -> Self {
+        self -= other;
+        self
+    }
+}
+
+impl<F: Field, S: FieldShare<F>> Sum for MpcFieldAcc<F, S> {
+    #[inline]
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
 impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
     type BasePrimeField = Self;
+    type Acc = MpcFieldAcc<F, S>;
     #[inline]
     fn extension_degree() -> u64 {
         unimplemented!("extension_degree")
@@ -326,9 +765,11 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
         *self *= Self::Public(F::from(2u8));
         self
     }
+    /// The bytes are public (a hash, say), so the element is too; for a random shared element,
+    /// see [MpcField::joint_rand].
     #[inline]
     fn from_random_bytes_with_flags<Fl: Flags>(b: &[u8]) -> Option<(Self, Fl)> {
-        F::from_random_bytes_with_flags(b).map(|(val, f)| (Self::Shared(S::from_public(val)), f))
+        F::from_random_bytes_with_flags(b).map(|(val, f)| (Self::Public(val), f))
     }
     #[inline]
     fn square(&self) -> Self {
@@ -381,7 +822,7 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
                     Self::Public(_) => unreachable!(),
                 })
                 .collect();
-            let nshares = S::batch_mul(sshares, oshares, &mut DummyFieldTripleSource::default());
+            let nshares = S::batch_mul(sshares, oshares, &mut SessionTripleSource::default());
             for (self_, new) in selfs.iter_mut().zip(nshares.into_iter()) {
                 *self_ = Self::Shared(new);
             }
@@ -391,6 +832,95 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
             }
         }
     }
+    /// Products of shared pairs are taken together, in one round; the rest are local.
+    fn dot(xs: &[Self], ys: &[Self]) -> Self {
+        assert_eq!(xs.len(), ys.len());
+        let mut acc = MpcFieldAcc::zero();
+        let (mut sxs, mut sys) = (Vec::new(), Vec::new());
+        for (x, y) in xs.iter().zip(ys) {
+            match (x, y) {
+                (Self::Shared(x), Self::Shared(y)) => {
+                    sxs.push(*x);
+                    sys.push(*y);
+                }
+                _ => acc += *x * y,
+            }
+        }
+        if !sxs.is_empty() {
+            acc += Self::Shared(S::dot(sxs, sys, &mut SessionTripleSource::default()));
+        }
+        acc.into()
+    }
+    /// Public coefficients are applied locally, without building any rows; the products of shared
+    /// coefficients and shared entries, from all rows, are taken together in one round.
+    fn sparse_mat_vec(rows: &[Vec<(Self, usize)>], z: &[Self]) -> Vec<Self> {
+        let mut accs = vec![MpcFieldAcc::zero(); rows.len()];
+        let (mut owners, mut sxs, mut sys) = (Vec::new(), Vec::new(), Vec::new());
+        for (r, row) in rows.iter().enumerate() {
+            for (c, i) in row {
+                match (c, &z[*i]) {
+                    (Self::Shared(c), Self::Shared(v)) => {
+                        owners.push(r);
+                        sxs.push(*c);
+                        sys.push(*v);
+                    }
+                    (c, v) => accs[r] += *c * v,
+                }
+            }
+        }
+        if !sxs.is_empty() {
+            let prods = S::batch_mul(sxs, sys, &mut SessionTripleSource::default());
+            for (r, p) in owners.into_iter().zip(prods) {
+                accs[r] += Self::Shared(p);
+            }
+        }
+        accs.into_iter().map(Self::from).collect()
+    }
+    /// Public elements are inverted locally, by Montgomery's trick, and shared ones together, by
+    /// masking and opening them all at once ([FieldShare::batch_inv]), so that the rounds do not
+    /// grow with their number. Shared elements must be non-zero. A shared `coeff` takes a round
+    /// of multiplications more.
+    fn batch_inversion_and_mul_in_place(selfs: &mut [Self], coeff: &Self) {
+        let (mut public, mut shared) = (Vec::new(), Vec::new());
+        for x in selfs.iter() {
+            match x {
+                Self::Public(x) => public.push(*x),
+                Self::Shared(x) => shared.push(*x),
+            }
+        }
+        ark_ff::batch_inversion(&mut public);
+        if !shared.is_empty() {
+            shared = S::batch_inv(shared, &mut SessionTripleSource::default());
+        }
+        let (mut public, mut shared) = (public.into_iter(), shared.into_iter());
+        for x in selfs.iter_mut() {
+            *x = match x {
+                Self::Public(_) => Self::Public(public.next().unwrap()),
+                Self::Shared(_) => Self::Shared(shared.next().unwrap()),
+            };
+        }
+        match coeff {
+            Self::Public(c) if c.is_one() => {}
+            Self::Public(_) => selfs.iter_mut().for_each(|x| *x *= coeff),
+            Self::Shared(c) => {
+                let (mut owners, mut shares) = (Vec::new(), Vec::new());
+                for (i, x) in selfs.iter_mut().enumerate() {
+                    match x {
+                        Self::Shared(s) => {
+                            owners.push(i);
+                            shares.push(*s);
+                        }
+                        Self::Public(_) => *x *= coeff,
+                    }
+                }
+                let cs = vec![*c; shares.len()];
+                let products = S::batch_mul(shares, cs, &mut SessionTripleSource::default());
+                for (i, p) in owners.into_iter().zip(products) {
+                    selfs[i] = Self::Shared(p);
+                }
+            }
+        }
+    }
     fn batch_division_in_place(selfs: &mut [Self], others: &[Self]) {
         let selfs_shared = selfs[0].is_shared();
         let others_shared = others[0].is_shared();
@@ -417,12 +947,27 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
                     Self::Public(_) => unreachable!(),
                 })
                 .collect();
-            let nshares = S::batch_div(sshares, oshares, &mut DummyFieldTripleSource::default());
+            let nshares = S::batch_div(sshares, oshares, &mut SessionTripleSource::default());
             for (self_, new) in selfs.iter_mut().zip(nshares.into_iter()) {
                 *self_ = Self::Shared(new);
             }
+        } else if others_shared {
+            // Invert the shared divisors together, in one round.
+            let oshares = others
+                .iter()
+                .map(|s| match s {
+                    Self::Shared(s) => s.clone(),
+                    Self::Public(_) => unreachable!(),
+                })
+                .collect();
+            let inverses = S::batch_inv(oshares, &mut SessionTripleSource::default());
+            for (a, b) in selfs.iter_mut().zip(inverses.into_iter()) {
+                *a *= Self::Shared(b);
+            }
         } else {
-            for (a, b) in ark_std::cfg_iter_mut!(selfs).zip(others.iter()) {
+            let mut inverses = others.to_vec();
+            ark_ff::batch_inversion(&mut inverses);
+            for (a, b) in ark_std::cfg_iter_mut!(selfs).zip(inverses.iter()) {
                 *a *= b;
             }
         }
@@ -442,7 +987,7 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
                 })
                 .collect();
             for (self_, new) in selfs.iter_mut().zip(
-                S::partial_products(sshares, &mut DummyFieldTripleSource::default()).into_iter(),
+                S::partial_products(sshares, &mut SessionTripleSource::default()).into_iter(),
             ) {
                 *self_ = Self::Shared(new);
             }
@@ -453,6 +998,9 @@ impl<F: PrimeField, S: FieldShare<F>> Field for MpcField<F, S> {
             }
         }
     }
+    fn batch_pow(selfs: &[Self], exp: &[u64]) -> Vec<Self> {
+        Self::batch_pow_public(selfs, exp)
+    }
     fn has_univariate_div_qr() -> bool {
         true
     }
@@ -22,7 +22,9 @@ use super::super::share::group::GroupShare;
 use super::super::share::BeaverSource;
 use super::field::MpcField;
 use mpc_net::{MpcNet, MpcMultiNet as Net};
-use crate::Reveal;
+use crate::preprocessing::SessionGroupTripleSource;
+use crate::security::DUMMY_TRIPLES;
+use crate::{count_inputs, public_to, NotPublic, Reveal};
 
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MpcGroup<G: Group, S: GroupShare<G>> {
@@ -44,6 +46,7 @@ impl<T: Group, S: GroupShare<T>> BeaverSource<S, S::FieldShare, S>
 {
     #[inline]
     fn triple(&mut self) -> (S, S::FieldShare, S) {
+        DUMMY_TRIPLES.take();
         (
             S::from_add_shared(T::zero()),
             <S::FieldShare as Reveal>::from_add_shared(if Net::am_king() {
@@ -56,6 +59,7 @@ impl<T: Group, S: GroupShare<T>> BeaverSource<S, S::FieldShare, S>
     }
     #[inline]
     fn inv_pair(&mut self) -> (S::FieldShare, S::FieldShare) {
+        DUMMY_TRIPLES.take();
         (
             <S::FieldShare as Reveal>::from_add_shared(if Net::am_king() {
                 T::ScalarField::one()
@@ -83,15 +87,9 @@ impl<T: Group, S: GroupShare<T>> MpcWire for MpcGroup<T, S> {
             }
             _ => {}
         }
-        debug_assert!({
-            let self_val = if let MpcGroup::Public(s) = self {
-                s.clone()
-            } else {
-                unreachable!()
-            };
-            super::macros::check_eq(self_val);
-            true
-        })
+        if let MpcGroup::Public(s) = self {
+            super::macros::check_eq(s.clone());
+        }
     }
     #[inline]
     fn is_shared(&self) -> bool {
@@ -114,6 +112,13 @@ impl<T: Group, S: GroupShare<T>> Reveal for MpcGroup<T, S> {
         result
     }
     #[inline]
+    fn reveal_to(self, party: usize) -> Option<Self::Base> {
+        match self {
+            Self::Shared(s) => s.reveal_to(party),
+            Self::Public(s) => public_to(party, s),
+        }
+    }
+    #[inline]
     fn from_public(b: Self::Base) -> Self {
         Self::Public(b)
     }
@@ -122,18 +127,27 @@ impl<T: Group, S: GroupShare<T>> Reveal for MpcGroup<T, S> {
         Self::Shared(S::from_add_shared(b))
     }
     #[inline]
-    fn unwrap_as_public(self) -> Self::Base {
+    unsafe fn unwrap_as_public(self) -> Self::Base {
         match self {
             Self::Shared(s) => s.unwrap_as_public(),
             Self::Public(s) => s,
         }
     }
     #[inline]
+    fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+        match self {
+            Self::Shared(_) => Err(NotPublic::of::<Self>()),
+            Self::Public(s) => Ok(s),
+        }
+    }
+    #[inline]
     fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
+        count_inputs(1);
         Self::Shared(S::king_share(f, rng))
     }
     #[inline]
     fn king_share_batch<R: Rng>(f: Vec<Self::Base>, rng: &mut R) -> Vec<Self> {
+        count_inputs(f.len());
         S::king_share_batch(f, rng).into_iter().map(Self::Shared).collect()
     }
     fn init_protocol() {
@@ -193,7 +207,7 @@ impl<'a, T: Group, S: GroupShare<T>> MulAssign<&'a MpcField<T::ScalarField, S::F
                     x.scale_pub_scalar(y);
                 }
                 MpcField::Shared(y) => {
-                    let t = x.scale(*y, &mut DummyGroupTripleSource::default());
+                    let t = x.scale(*y, &mut SessionGroupTripleSource::default());
                     *x = t;
                 }
             },
@@ -205,7 +219,12 @@ impl<T: Group, S: GroupShare<T>> Group for MpcGroup<T, S> {
     type ScalarField = MpcField<T::ScalarField, S::FieldShare>;
 }
 impl<T: Group, S: GroupShare<T>> MpcGroup<T, S> {
-    pub fn unwrap_as_public_or_add_shared(self) -> T {
+    /// The public value, or this party's share.
+    ///
+    /// # Safety
+    ///
+    /// As for [Reveal::unwrap_as_public].
+    pub unsafe fn unwrap_as_public_or_add_shared(self) -> T {
         match self {
             Self::Public(p) => p,
             Self::Shared(p) => p.unwrap_as_public(),
@@ -2,19 +2,175 @@
 
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 
+use blake2::{Blake2s, Digest};
+use lazy_static::lazy_static;
+use sha3::Sha3_256;
+
 use crate::channel::{self, MpcSerNet};
 use mpc_net::MpcNet;
 
-use std::fmt::Display;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The digest that consistency checks compare, for values too large to send as they are, and for
+/// deferred checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckDigest {
+    Blake2s,
+    Sha3_256,
+}
+
+impl CheckDigest {
+    pub fn digest(self, bytes: &[u8]) -> Vec<u8> {
+        let mut h = Hasher::new(self);
+        h.update(bytes);
+        h.finalize()
+    }
+}
+
+impl FromStr for CheckDigest {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "blake2s" => Ok(CheckDigest::Blake2s),
+            "sha3" | "sha3-256" => Ok(CheckDigest::Sha3_256),
+            _ => Err(format!("unknown digest {:?}; expected blake2s or sha3-256", s)),
+        }
+    }
+}
+
+impl Display for CheckDigest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckDigest::Blake2s => write!(f, "blake2s"),
+            CheckDigest::Sha3_256 => write!(f, "sha3-256"),
+        }
+    }
+}
+
+enum Hasher {
+    Blake2s(Blake2s),
+    Sha3_256(Sha3_256),
+}
+
+impl Hasher {
+    fn new(digest: CheckDigest) -> Self {
+        match digest {
+            CheckDigest::Blake2s => Hasher::Blake2s(Blake2s::new()),
+            CheckDigest::Sha3_256 => Hasher::Sha3_256(Sha3_256::new()),
+        }
+    }
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Blake2s(h) => h.update(bytes),
+            Hasher::Sha3_256(h) => h.update(bytes),
+        }
+    }
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Blake2s(h) => h.finalize().to_vec(),
+            Hasher::Sha3_256(h) => h.finalize().to_vec(),
+        }
+    }
+}
+
+lazy_static! {
+    /// Digests of deferred checks, per phase, in the order the phases began.
+    static ref DEFERRED: Mutex<Option<Vec<(String, Hasher)>>> = Mutex::new(None);
+    static ref DIGEST: Mutex<CheckDigest> = Mutex::new(CheckDigest::Blake2s);
+}
+
+/// Values that serialize to more bytes than this are checked by their digest.
+static RAW_LIMIT: AtomicUsize = AtomicUsize::new(64);
+
+/// Choose the digest that consistency checks compare (Blake2s by default). All parties must
+/// choose the same.
+pub fn set_check_digest(digest: CheckDigest) {
+    *DIGEST.lock().unwrap() = digest;
+}
+
+/// Choose the size, in bytes, above which [check_eq] compares a value's digest rather than the
+/// value (64 by default). Sending a small value costs no more than its digest, and a failed check
+/// can then show the other party's value; a large one (a `G2` element, a pairing output) costs
+/// several times more. All parties must choose the same.
+pub fn set_raw_check_limit(bytes: usize) {
+    RAW_LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Choose whether [check_eq] compares values immediately (the default), or accumulates a digest
+/// per phase (the innermost open MPC timer section) to be compared by [finish_deferred_checks].
+/// Deferred checks are kept whether or not [mpc_net::self_checks] are on.
+pub fn set_deferred_checks(deferred: bool) {
+    *DEFERRED.lock().unwrap() = if deferred { Some(Vec::new()) } else { None };
+}
+
+/// Compare the digests of all deferred checks with the other parties, in one round.
+///
+/// Returns the number of phases checked, or the first phase in which the parties disagreed.
+/// Afterwards, checks are eager again.
+pub fn finish_deferred_checks() -> Result<usize, String> {
+    let phases = DEFERRED.lock().unwrap().take().unwrap_or_default();
+    let (names, digests): (Vec<String>, Vec<Vec<u8>>) = phases
+        .into_iter()
+        .map(|(name, h)| (name, h.finalize()))
+        .unzip();
+    let others = if mpc_net::two::is_init() {
+        vec![channel::exchange(&digests)]
+    } else {
+        // Parties that dropped out have nothing to compare.
+        mpc_net::MpcMultiNet::broadcast_surviving(&digests)
+            .into_iter()
+            .flatten()
+            .collect()
+    };
+    for other in others {
+        let agrees = |i: usize| other.get(i).map_or(false, |o| crate::ct::bytes_eq(o, &digests[i]));
+        if let Some(i) = (0..names.len()).find(|&i| !agrees(i)) {
+            return Err(names[i].clone());
+        }
+        if other.len() != digests.len() {
+            return Err("(after the last phase)".to_owned());
+        }
+    }
+    Ok(names.len())
+}
+
+/// Fold `t` into the digest of the current phase, if checks are deferred.
+fn defer<T: CanonicalSerialize>(t: &T) -> bool {
+    let mut deferred = DEFERRED.lock().unwrap();
+    match deferred.as_mut() {
+        Some(phases) => {
+            let phase = mpc_net::timer::current_path();
+            if phases.last().map(|(p, _)| p != &phase).unwrap_or(true) {
+                phases.push((phase, Hasher::new(*DIGEST.lock().unwrap())));
+            }
+            let mut bytes = Vec::new();
+            t.serialize(&mut bytes).unwrap();
+            phases.last_mut().unwrap().1.update(&bytes);
+            true
+        }
+        None => false,
+    }
+}
 
 #[track_caller]
-/// Checks that both sides of the channel have the same value.
+/// Checks that both sides of the channel have the same value, if [mpc_net::self_checks] are on.
+///
+/// A value larger than the limit (see [set_raw_check_limit]) is checked by its digest. See
+/// [set_deferred_checks] for batching these checks.
 pub fn check_eq<T: CanonicalSerialize + CanonicalDeserialize + Clone + Eq + Display>(t: T) {
-    debug_assert!({
+    if defer(&t) || !mpc_net::self_checks() {
+        return;
+    }
+    assert!({
         use log::debug;
-        if mpc_net::two::is_init() {
+        if t.serialized_size() > RAW_LIMIT.load(Ordering::Relaxed) {
+            check_digest_eq(&t)
+        } else if mpc_net::two::is_init() {
             let other = channel::exchange(&t);
-            if t == other {
+            if crate::ct::eq(&t, &other) {
                 debug!("Consistency check passed");
                 true
             } else {
@@ -23,10 +179,14 @@ pub fn check_eq<T: CanonicalSerialize + CanonicalDeserialize + Clone + Eq + Disp
             }
         } else {
             debug!("Consistency check");
-            let others = mpc_net::MpcMultiNet::broadcast(&t);
+            let others = mpc_net::MpcMultiNet::broadcast_surviving(&t);
             let mut result = true;
             for (i, other_t) in others.iter().enumerate() {
-                if &t != other_t {
+                let other_t = match other_t {
+                    Some(o) => o,
+                    None => continue,
+                };
+                if !crate::ct::eq(&t, other_t) {
                     println!("\nConsistency check failed\nI (party {}) have {}\nvs\n  (party {}) has  {}", mpc_net::MpcMultiNet::party_id(), t, i, other_t);
                     result = false;
                     break;
@@ -37,6 +197,33 @@ pub fn check_eq<T: CanonicalSerialize + CanonicalDeserialize + Clone + Eq + Disp
     })
 }
 
+/// Check `t` by its digest: only ours can be shown if the check fails.
+fn check_digest_eq<T: CanonicalSerialize + Display>(t: &T) -> bool {
+    let digest = *DIGEST.lock().unwrap();
+    let mut bytes = Vec::new();
+    t.serialize(&mut bytes).unwrap();
+    let ours = digest.digest(&bytes);
+    let others: Vec<(String, Vec<u8>)> = if mpc_net::two::is_init() {
+        vec![("the other party".to_owned(), channel::exchange(&ours))]
+    } else {
+        mpc_net::MpcMultiNet::broadcast_surviving(&ours)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.map(|d| (format!("party {}", i), d)))
+            .collect()
+    };
+    match others.iter().find(|(_, d)| !crate::ct::bytes_eq(d, &ours)) {
+        None => true,
+        Some((who, _)) => {
+            println!(
+                "\nConsistency check failed\nI have {}\nwhose {} digest differs from {}'s",
+                t, digest, who
+            );
+            false
+        }
+    }
+}
+
 macro_rules! impl_basics_2 {
     ($share:ident, $bound:ident, $wrap:ident) => {
         impl<T: $bound, S: $share<T>> $wrap<T, S> {
@@ -52,6 +239,34 @@ macro_rules! impl_basics_2 {
             pub fn from_public(t: T) -> Self {
                 Self::new(t, false)
             }
+            /// A public random value, the same at every party: all must pass `rng` in the same
+            /// state (seeded in common, or from a beacon).
+            #[inline]
+            pub fn public_rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
+                Self::Public(<T as PubUniformRand>::pub_rand(rng))
+            }
+            /// A shared random value that `owner` alone knows: it draws the value from `rng` and
+            /// holds it as its share, and the others hold zero (so only `owner` draws). No
+            /// communication; for additive sharings only.
+            #[inline]
+            pub fn local_rand<R: Rng + ?Sized>(owner: usize, rng: &mut R) -> Self {
+                let ours = if Net::party_id() == owner {
+                    <T as UniformRand>::rand(rng)
+                } else {
+                    T::zero()
+                };
+                Self::Shared(<S as Reveal>::from_add_shared(ours))
+            }
+            /// A shared random value that every party contributes to: each draws its share from
+            /// its own `rng`, without communicating. It is secret as long as one party's `rng` is,
+            /// and no more: parties that pass the same seeded `rng` all hold the same share, and
+            /// so all know the value. Pass public coins to [Self::public_rand] instead.
+            ///
+            /// This is what [UniformRand::rand] gives, and so what lifted provers blind with.
+            #[inline]
+            pub fn joint_rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
+                Self::Shared(<S as UniformRand>::rand(rng))
+            }
             #[inline]
             pub fn map<TT: $bound, SS: $share<TT>, FT: Fn(T) -> TT, FS: Fn(S) -> SS>(
                 self,
@@ -113,9 +328,11 @@ macro_rules! impl_basics_2 {
                 unimplemented!("serialized_size_with_flags")
             }
         }
+        // Only public values go over the wire this way (shares are sent as `S`), so they read
+        // back as public. This is what lets a share of a `$wrap` (nesting) be opened.
         impl<T: $bound, S: $share<T>> CanonicalDeserialize for $wrap<T, S> {
-            fn deserialize<R: Read>(_reader: R) -> Result<Self, SerializationError> {
-                unimplemented!("deserialize")
+            fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+                T::deserialize(reader).map(Self::Public)
             }
         }
         impl<T: $bound, S: $share<T>> CanonicalDeserializeWithFlags for $wrap<T, S> {
@@ -127,12 +344,12 @@ macro_rules! impl_basics_2 {
         }
         impl<T: $bound, S: $share<T>> UniformRand for $wrap<T, S> {
             fn rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
-                Self::Shared(<S as UniformRand>::rand(rng))
+                Self::joint_rand(rng)
             }
         }
         impl<T: $bound, S: $share<T>> PubUniformRand for $wrap<T, S> {
             fn pub_rand<R: Rng + ?Sized>(rng: &mut R) -> Self {
-                Self::Public(<T as PubUniformRand>::pub_rand(rng))
+                Self::public_rand(rng)
             }
         }
         //impl<T: $bound, S: $share<T>> Add for $wrap<T, S> {
@@ -175,15 +392,35 @@ macro_rules! impl_basics_2 {
             }
         }
         impl<T: $bound, S: $share<T>> Sum for $wrap<T, S> {
+            /// The public terms are summed apart, and shift the sum of the shared ones once.
             #[inline]
             fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
-                iter.fold(Self::zero(), Add::add)
+                let mut public = T::zero();
+                let mut shared: Option<S> = None;
+                for x in iter {
+                    match x {
+                        $wrap::Public(x) => public += &x,
+                        $wrap::Shared(x) => match &mut shared {
+                            Some(s) => {
+                                s.add(&x);
+                            }
+                            None => shared = Some(x),
+                        },
+                    }
+                }
+                match shared {
+                    Some(mut s) => {
+                        s.shift(&public);
+                        $wrap::Shared(s)
+                    }
+                    None => $wrap::Public(public),
+                }
             }
         }
         impl<'a, T: $bound, S: $share<T> + 'a> Sum<&'a $wrap<T, S>> for $wrap<T, S> {
             #[inline]
             fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
-                iter.fold(Self::zero(), |x, y| x.add(y.clone()))
+                iter.cloned().sum()
             }
         }
         impl<T: $bound, S: $share<T>> Neg for $wrap<T, S> {
@@ -28,7 +28,9 @@ use super::super::share::pairing::{AffProjShare, PairingShare};
 use super::super::share::BeaverSource;
 use super::field::MpcField;
 use super::group::MpcGroup;
-use crate::Reveal;
+use crate::preprocessing::SessionGroupTripleSource;
+use crate::security::DUMMY_TRIPLES;
+use crate::{public_to, NotPublic, Reveal};
 
 #[derive(Derivative)]
 #[derivative(Default(bound = ""), Clone(bound = ""), Copy(bound = ""))]
@@ -48,6 +50,7 @@ impl<E: PairingEngine, S: PairingShare<E>>
         MpcG2Projective<E, S>,
         MpcExtField<E::Fqk, S::FqkShare>,
     ) {
+        DUMMY_TRIPLES.take();
         let g1 = E::G1Projective::zero();
         let g2 = E::G2Projective::zero();
         (
@@ -179,14 +182,41 @@ impl<E: PairingEngine, PS: PairingShare<E>> PairingEngine for MpcPairingEngine<E
     }
 
     /// Computes a product of pairings.
+    ///
+    /// Prepared points are public, so this is local.
     #[must_use]
-    fn product_of_pairings<'a, I>(_i: I) -> Self::Fqk
+    fn product_of_pairings<'a, I>(i: I) -> Self::Fqk
     where
         I: IntoIterator<Item = &'a (Self::G1Prepared, Self::G2Prepared)>,
     {
-        // TODO: MPC!
-        // <Bls12_377 as PairingEngine>::product_of_pairings(i)
-        unimplemented!("pairing product")
+        let pairs: Vec<(E::G1Prepared, E::G2Prepared)> = i
+            .into_iter()
+            .map(|(p, q)| (p.val.clone(), q.val.clone()))
+            .collect();
+        MpcExtField::wrap(MpcField::Public(E::product_of_pairings(&pairs)))
+    }
+
+    /// Whether the pairing equation holds, opening nothing else when some G1 points are shared.
+    ///
+    /// The G1 points are all scaled by one shared, uniformly random `s`, and then opened. This
+    /// raises the product to the power `s`, which is one exactly when the product is. If the
+    /// equation holds, the opened points are uniformly random subject to it, so they reveal only
+    /// that it holds. (If it does not, they still reveal no point, but not only a bit either.)
+    fn product_of_pairings_is_one(pairs: &[(Self::G1Affine, Self::G2Prepared)]) -> bool {
+        let g1s: Vec<MpcGroup<E::G1Affine, PS::G1AffineShare>> =
+            pairs.iter().map(|(p, _)| p.val).collect();
+        let g1s: Vec<E::G1Affine> = if g1s.iter().any(|p| p.is_shared()) {
+            let s = MpcField::<E::Fr, PS::FrShare>::rand(&mut rand::thread_rng());
+            g1s.into_iter().map(|p| p * s).collect::<Vec<_>>().reveal()
+        } else {
+            g1s.reveal()
+        };
+        let prepared: Vec<(E::G1Prepared, E::G2Prepared)> = g1s
+            .into_iter()
+            .zip(pairs)
+            .map(|(p, (_, q))| (p.into(), q.val.clone()))
+            .collect();
+        E::product_of_pairings(&prepared).is_one()
     }
 
     /// Performs multiple pairing operations
@@ -210,17 +240,14 @@ impl<E: PairingEngine, PS: PairingShare<E>> PairingEngine for MpcPairingEngine<E
             let yb = (b + y).reveal();
             let xayb: MpcExtField<E::Fqk, PS::FqkShare> =
                 MpcExtField::wrap(MpcField::Public(E::pairing(xa, yb)));
+            // Pairing our shares of the triple with public points gives our shares of the
+            // products, so the shares stay local.
+            let (x, y) = unsafe { (x.unwrap_as_public(), y.unwrap_as_public()) };
             let xay: MpcExtField<E::Fqk, PS::FqkShare> = MpcExtField::wrap(MpcField::Shared(
-                <PS::FqkShare as ExtFieldShare<E::Fqk>>::Ext::from_add_shared(E::pairing(
-                    xa,
-                    y.unwrap_as_public(),
-                )),
+                <PS::FqkShare as ExtFieldShare<E::Fqk>>::Ext::from_add_shared(E::pairing(xa, y)),
             ));
             let xyb: MpcExtField<E::Fqk, PS::FqkShare> = MpcExtField::wrap(MpcField::Shared(
-                <PS::FqkShare as ExtFieldShare<E::Fqk>>::Ext::from_add_shared(E::pairing(
-                    x.unwrap_as_public(),
-                    yb,
-                )),
+                <PS::FqkShare as ExtFieldShare<E::Fqk>>::Ext::from_add_shared(E::pairing(x, yb)),
             ));
             z / xay / xyb * xayb
         } else {
@@ -267,9 +294,10 @@ macro_rules! impl_pairing_mpc_wrapper {
                 self.val.serialized_size_with_flags::<F>()
             }
         }
+        // Like the group's own: the value reads back as public.
         impl<E: $bound1, PS: $bound2<E>> CanonicalDeserialize for $wrap<E, PS> {
-            fn deserialize<R: Read>(_reader: R) -> Result<Self, SerializationError> {
-                unimplemented!("deserialize")
+            fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+                CanonicalDeserialize::deserialize(reader).map(|val| Self { val })
             }
         }
         impl<E: $bound1, PS: $bound2<E>> CanonicalDeserializeWithFlags for $wrap<E, PS> {
@@ -424,6 +452,10 @@ macro_rules! impl_ext_field_wrapper {
                 self.val.reveal()
             }
             #[inline]
+            fn reveal_to(self, party: usize) -> Option<Self::Base> {
+                self.val.reveal_to(party)
+            }
+            #[inline]
             fn from_public(t: E) -> Self {
                 Self::wrap($wrapped::from_public(t))
             }
@@ -432,10 +464,14 @@ macro_rules! impl_ext_field_wrapper {
                 Self::wrap($wrapped::from_add_shared(t))
             }
             #[inline]
-            fn unwrap_as_public(self) -> Self::Base {
+            unsafe fn unwrap_as_public(self) -> Self::Base {
                 self.val.unwrap_as_public()
             }
             #[inline]
+            fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+                self.val.try_unwrap_as_public()
+            }
+            #[inline]
             fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
                 Self::wrap($wrapped::king_share(f, rng))
             }
@@ -478,7 +514,7 @@ macro_rules! impl_ext_field_wrapper {
                 self
             }
             fn from_random_bytes_with_flags<Fl: Flags>(b: &[u8]) -> Option<(Self, Fl)> {
-                F::from_random_bytes_with_flags(b).map(|(val, f)| (Self::new(val, true), f))
+                F::from_random_bytes_with_flags(b).map(|(val, f)| (Self::new(val, false), f))
             }
             #[inline]
             fn square(&self) -> Self {
@@ -564,6 +600,10 @@ macro_rules! impl_pairing_curve_wrapper {
                 self.val.reveal()
             }
             #[inline]
+            fn reveal_to(self, party: usize) -> Option<Self::Base> {
+                self.val.reveal_to(party)
+            }
+            #[inline]
             fn from_public(t: Self::Base) -> Self {
                 Self {
                     val: $wrapped::from_public(t),
@@ -576,10 +616,14 @@ macro_rules! impl_pairing_curve_wrapper {
                 }
             }
             #[inline]
-            fn unwrap_as_public(self) -> Self::Base {
+            unsafe fn unwrap_as_public(self) -> Self::Base {
                 self.val.unwrap_as_public()
             }
             #[inline]
+            fn try_unwrap_as_public(self) -> Result<Self::Base, NotPublic> {
+                self.val.try_unwrap_as_public()
+            }
+            #[inline]
             fn king_share<R: Rng>(f: Self::Base, rng: &mut R) -> Self {
                 Self {
                     val: $wrapped::king_share(f, rng),
@@ -688,8 +732,11 @@ macro_rules! impl_aff_proj {
         }
 
         impl<E: PairingEngine, PS: PairingShare<E>> From<$w_aff<E, PS>> for $w_prep<E, PS> {
-            fn from(_o: $w_aff<E, PS>) -> Self {
-                unimplemented!("Prepared curves")
+            fn from(o: $w_aff<E, PS>) -> Self {
+                match o.val {
+                    MpcGroup::Public(g) => Self::from_public(g.into()),
+                    MpcGroup::Shared(_) => panic!("Cannot prepare a shared curve point"),
+                }
             }
         }
 
@@ -706,6 +753,10 @@ macro_rules! impl_aff_proj {
                 self.val
             }
             #[inline]
+            fn reveal_to(self, party: usize) -> Option<E::$prep> {
+                public_to(party, self.val)
+            }
+            #[inline]
             fn from_public(g: E::$prep) -> Self {
                 Self {
                     val: g,
@@ -718,6 +769,48 @@ macro_rules! impl_aff_proj {
             }
         }
 
+        impl<E: PairingEngine, PS: PairingShare<E>> $w_aff<E, PS> {
+            /// [AffineCurve::multi_scalar_mul] when some bases are shared (e.g., they come from a
+            /// shared proving key). Public scalars make this local; shared ones cost one round.
+            fn multi_scalar_mul_shared_bases(
+                bases: &[Self],
+                scalars: &[MpcField<E::Fr, PS::FrShare>],
+            ) -> $w_pro<E, PS> {
+                let t = start_timer!(|| "MSM with shared bases");
+                let bases: Vec<PS::$share_aff> = bases
+                    .iter()
+                    .map(|b| match b.val {
+                        MpcGroup::Shared(s) => s,
+                        MpcGroup::Public(g) => <PS::$share_aff as Reveal>::from_public(g),
+                    })
+                    .collect();
+                let r = if scalars.iter().any(|s| s.is_shared()) {
+                    let scalars: Vec<PS::FrShare> = scalars
+                        .iter()
+                        .map(|s| match s.into_shared() {
+                            MpcField::Shared(s) => s,
+                            MpcField::Public(_) => unreachable!(),
+                        })
+                        .collect();
+                    GroupShare::multi_scale(
+                        &bases,
+                        &scalars,
+                        &mut SessionGroupTripleSource::default(),
+                    )
+                } else {
+                    let scalars: Vec<E::Fr> = scalars
+                        .iter()
+                        .map(|s| s.try_unwrap_as_public().unwrap())
+                        .collect();
+                    GroupShare::multi_scale_pub_scalar(&bases, &scalars)
+                };
+                end_timer!(t);
+                $w_pro {
+                    val: MpcGroup::Shared(PS::$g_name::sh_aff_to_proj(r)),
+                }
+            }
+        }
+
         impl<E: PairingEngine, PS: PairingShare<E>> AffineCurve for $w_aff<E, PS> {
             type ScalarField = MpcField<E::Fr, PS::FrShare>;
             const COFACTOR: &'static [u64] = E::$aff::COFACTOR;
@@ -744,10 +837,27 @@ macro_rules! impl_aff_proj {
                 todo!("AffineCurve::mul_by_cofactor_inv")
             }
             fn multi_scalar_mul(bases: &[Self], scalars: &[Self::ScalarField]) -> Self::Projective {
+                if bases.iter().any(|b| b.is_shared()) {
+                    return Self::multi_scalar_mul_shared_bases(bases, scalars);
+                }
                 let b = {
-                    assert!(bases.iter().all(|b| !b.is_shared()));
                     let scalars_shared = scalars.first().map(|s| s.is_shared()).unwrap_or(true);
-                    assert!(scalars.iter().all(|b| scalars_shared == b.is_shared()));
+                    if scalars.iter().any(|b| scalars_shared != b.is_shared()) {
+                        // Mixed scalars (e.g. a shared polynomial padded with public zeros):
+                        // handle the public and shared terms separately.
+                        let split = |shared: bool| -> (Vec<Self>, Vec<Self::ScalarField>) {
+                            bases
+                                .iter()
+                                .zip(scalars)
+                                .filter(|(_, s)| s.is_shared() == shared)
+                                .map(|(b, s)| (b.clone(), s.clone()))
+                                .unzip()
+                        };
+                        let (pub_bases, pub_scalars) = split(false);
+                        let (sh_bases, sh_scalars) = split(true);
+                        return Self::multi_scalar_mul(&pub_bases, &pub_scalars)
+                            + Self::multi_scalar_mul(&sh_bases, &sh_scalars);
+                    }
                     let bases =
                         MpcGroup::all_public_or_shared(bases.into_iter().map(|i| i.val.clone()))
                             .unwrap();
@@ -0,0 +1,41 @@
+//! Parties meet at barriers, and abort if they are at different ones.
+use mpc_algebra::channel::barrier;
+use mpc_net::testing::{run_parties, run_parties_checked};
+use mpc_net::{MpcMultiNet, MpcNet};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+#[test]
+fn parties_leave_a_barrier_together() {
+    run_parties("parties_leave_a_barrier_together", 3, || {
+        barrier("start");
+        let start = Instant::now();
+        // Party 2 lags; the others wait for it.
+        if MpcMultiNet::party_id() == 2 {
+            sleep(Duration::from_millis(300));
+        }
+        barrier("lagging");
+        assert!(start.elapsed() >= Duration::from_millis(250));
+        barrier("in step");
+    });
+}
+
+#[test]
+fn parties_at_different_barriers_abort() {
+    run_parties_checked(
+        "parties_at_different_barriers_abort",
+        2,
+        Duration::from_secs(20),
+        |_| (),
+        || barrier(&format!("phase {}", MpcMultiNet::party_id())),
+        |exits| {
+            for e in &exits {
+                assert!(
+                    !e.hung && e.stderr.contains("is not at barrier `phase"),
+                    "{:?}",
+                    exits
+                );
+            }
+        },
+    );
+}
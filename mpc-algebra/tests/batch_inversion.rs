@@ -0,0 +1,65 @@
+//! Inverting many shared values together.
+use ark_bls12_377::Fr;
+use ark_ff::{batch_inversion, batch_inversion_and_mul, Field, InversionQueue, UniformRand};
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::testing::{measure, run_parties};
+
+fn check_batch_inversion<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let xs: Vec<Fr> = (0..12).map(|_| Fr::rand(rng)).collect();
+    let expected: Vec<Fr> = xs.iter().map(|x| x.inverse().unwrap()).collect();
+    // Shared and public, interleaved, and a public zero, which stays zero.
+    let mut values: Vec<MpcField<Fr, S>> = MpcField::king_share_batch(xs[..8].to_vec(), rng);
+    values.insert(1, MpcField::from_public(xs[8]));
+    values.insert(4, MpcField::from_public(Fr::from(0u8)));
+    values.extend(xs[9..].iter().map(|x| MpcField::from_public(*x)));
+    let mut inverses = values.clone();
+    let (_, many) = measure(|| batch_inversion(&mut inverses));
+    let mut want = expected[..8].to_vec();
+    want.insert(1, expected[8]);
+    want.insert(4, Fr::from(0u8));
+    want.extend_from_slice(&expected[9..]);
+    assert_eq!(inverses.clone().reveal(), want);
+
+    // One shared value takes as many rounds as eight.
+    let mut one = values[..1].to_vec();
+    let (_, single) = measure(|| batch_inversion(&mut one));
+    assert_eq!(many.rounds, single.rounds);
+    assert!(single.rounds > 0);
+
+    // Scaled by a shared coefficient.
+    let c = MpcField::<Fr, S>::king_share(Fr::from(3u8), rng);
+    let mut scaled = values.clone();
+    batch_inversion_and_mul(&mut scaled, &c);
+    let three = Fr::from(3u8);
+    let want_scaled: Vec<Fr> = want.iter().map(|w| *w * three).collect();
+    assert_eq!(scaled.reveal(), want_scaled);
+
+    // Requested one at a time.
+    let mut queue = InversionQueue::new();
+    let first = queue.push(values[0]);
+    let rest = queue.extend(values[2..4].iter().cloned());
+    let (inverted, queued) = measure(|| queue.invert());
+    assert_eq!(queued.rounds, single.rounds);
+    assert_eq!(inverted[first].reveal(), expected[0]);
+    assert_eq!(inverted[rest].to_vec().reveal(), expected[1..3].to_vec());
+}
+
+#[test]
+fn hbc_batch_inversion() {
+    run_parties(
+        "hbc_batch_inversion",
+        2,
+        check_batch_inversion::<AdditiveFieldShare<Fr>>,
+    );
+}
+
+#[test]
+fn spdz_batch_inversion() {
+    run_parties(
+        "spdz_batch_inversion",
+        2,
+        check_batch_inversion::<SpdzFieldShare<Fr>>,
+    );
+}
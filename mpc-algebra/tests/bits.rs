@@ -0,0 +1,72 @@
+//! Boolean shares: AND via boolean triples, and conversions to and from arithmetic shares.
+use ark_bls12_377::Fr;
+use mpc_algebra::{AdditiveFieldShare, BitShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::testing::run_parties;
+use mpc_trait::MpcWire;
+use rand::Rng;
+
+fn check_bits<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+
+    // Truth tables for xor, and, and not, with all four cases in one batch.
+    let xs = BitShare::king_share_batch(vec![false, false, true, true], rng);
+    let ys = BitShare::king_share_batch(vec![false, true, false, true], rng);
+    let xor: Vec<BitShare> = xs.iter().zip(&ys).map(|(x, y)| *x ^ *y).collect();
+    let not: Vec<BitShare> = xs.iter().map(|x| !*x).collect();
+    assert_eq!(BitShare::open_batch(&xor), vec![false, true, true, false]);
+    assert_eq!(
+        BitShare::open_batch(&BitShare::and_batch(&xs, &ys)),
+        vec![false, false, false, true]
+    );
+    assert_eq!(BitShare::open_batch(&not), vec![true, true, false, false]);
+    assert!(!xs[3].and(ys[3]).xor_public(true).and_public(true).open());
+
+    // Round trips between the domains.
+    let vals: Vec<u64> = (0..5).map(|_| rng.gen::<u32>() as u64).collect();
+    let xs: Vec<MpcField<Fr, S>> = vals
+        .iter()
+        .map(|v| MpcField::king_share(Fr::from(*v), rng))
+        .collect();
+    for (x, v) in xs.iter().zip(&vals) {
+        let bits = x.to_bit_shares(32);
+        let expected: Vec<bool> = (0..32).map(|i| v >> i & 1 == 1).collect();
+        assert_eq!(BitShare::open_batch(&bits), expected);
+        let back = MpcField::<Fr, S>::from_bit_shares(&bits);
+        assert!(back.iter().all(|b| b.is_shared()));
+        let back: Vec<Fr> = back.reveal();
+        let expected: Vec<Fr> = expected.into_iter().map(Fr::from).collect();
+        assert_eq!(back, expected);
+    }
+    let public = MpcField::<Fr, S>::from_public(Fr::from(6u8)).to_bit_shares(3);
+    assert_eq!(BitShare::open_batch(&public), vec![false, true, true]);
+
+    // A whole batch converts in k rounds, not k per value.
+    let shares: Vec<S> = xs
+        .iter()
+        .map(|x| match x {
+            MpcField::Shared(s) => *s,
+            MpcField::Public(_) => unreachable!(),
+        })
+        .collect();
+    let batch = mpc_algebra::share::bit::a2b::<Fr, S>(&shares, 32);
+    for (bits, x) in batch.iter().zip(&xs) {
+        let sum = MpcField::<Fr, S>::from_bit_shares(bits)
+            .into_iter()
+            .rev()
+            .fold(MpcField::from_public(Fr::from(0u8)), |acc, b| {
+                acc * MpcField::from_public(Fr::from(2u8)) + b
+            });
+        assert_eq!(sum.reveal(), x.reveal());
+    }
+}
+
+#[test]
+fn hbc_bits() {
+    run_parties("hbc_bits", 3, check_bits::<AdditiveFieldShare<Fr>>);
+}
+
+#[test]
+fn spdz_bits() {
+    run_parties("spdz_bits", 2, check_bits::<SpdzFieldShare<Fr>>);
+}
@@ -0,0 +1,82 @@
+//! Consistency checks of large values compare digests, rather than the values.
+use ark_bls12_377::{Fr, G2Projective as G2};
+use ark_ff::UniformRand;
+use mpc_algebra::honest_but_curious::MpcGroup;
+use mpc_algebra::macros::{
+    check_eq, finish_deferred_checks, set_check_digest, set_deferred_checks, set_raw_check_limit,
+    CheckDigest,
+};
+use mpc_algebra::Reveal;
+use mpc_net::testing::run_parties;
+use mpc_net::{end_mpc_timer, start_mpc_timer, MpcMultiNet, MpcNet};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[test]
+fn sha3_known_answers() {
+    let sha3 = |m: &[u8]| hex(&CheckDigest::Sha3_256.digest(m));
+    assert_eq!(
+        sha3(b""),
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+    );
+    assert_eq!(
+        sha3(b"abc"),
+        "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532"
+    );
+    // The padding in the last byte of a block, and in a block of its own.
+    assert_eq!(
+        sha3(&[b'a'; 135]),
+        "8094bb53c44cfb1e67b7c30447f9a1c33696d2463ecc1d9c92538913392843c9"
+    );
+    assert_eq!(
+        sha3(&[b'a'; 136]),
+        "3fc5559f14db8e453a0a3091edbd2bc25e11528d81c66fa570a4efdcc2695ee1"
+    );
+    assert_eq!("sha3-256".parse(), Ok(CheckDigest::Sha3_256));
+    assert!("md5".parse::<CheckDigest>().is_err());
+}
+
+#[test]
+fn large_values_are_checked_by_digest() {
+    run_parties("large_values_are_checked_by_digest", 3, || {
+        // As they are in debug builds.
+        mpc_net::set_self_checks(true);
+        let rng = &mut ark_std::test_rng();
+        let g = G2::rand(rng);
+        let x = MpcGroup::<G2>::king_share(g, rng);
+        let sent = |x: MpcGroup<G2>| {
+            MpcMultiNet::reset_stats();
+            assert_eq!(x.reveal(), g);
+            MpcMultiNet::stats().bytes_sent
+        };
+        set_raw_check_limit(usize::MAX);
+        let raw = sent(x.clone());
+        set_raw_check_limit(64);
+        let digested = sent(x.clone());
+        set_check_digest(CheckDigest::Sha3_256);
+        assert_eq!(sent(x), digested);
+        // A G2 element's check sends 32 bytes, rather than the element, to each peer.
+        assert!(digested < raw, "{} bytes digested, {} raw", digested, raw);
+    });
+}
+
+#[test]
+fn deferred_checks_run_without_self_checks() {
+    run_parties("deferred_checks_run_without_self_checks", 2, || {
+        // As they are in release builds.
+        mpc_net::set_self_checks(false);
+        set_deferred_checks(true);
+        let t = start_mpc_timer!(|| "agree");
+        check_eq(Fr::from(1u8));
+        end_mpc_timer!(t);
+        assert_eq!(finish_deferred_checks(), Ok(1));
+
+        set_deferred_checks(true);
+        let t = start_mpc_timer!(|| "disagree");
+        check_eq(Fr::from(MpcMultiNet::party_id() as u64));
+        end_mpc_timer!(t);
+        assert_eq!(finish_deferred_checks(), Err("disagree".to_owned()));
+    });
+}
@@ -0,0 +1,27 @@
+//! Threshold operation: honest-majority parties carry on when some drop out.
+use ark_bls12_377::Fr;
+use ark_ff::UniformRand;
+use mpc_algebra::{share::gsz20::field::GszFieldShare, MpcField, Reveal};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+
+type F = MpcField<Fr, GszFieldShare<Fr>>;
+
+#[test]
+fn gsz_survives_dropout() {
+    // With four parties, shares have degree one and products degree two, so any three suffice.
+    run_parties("gsz_survives_dropout", 4, || {
+        if MpcMultiNet::party_id() == 3 {
+            return;
+        }
+        MpcMultiNet::set_max_dropouts(1);
+        F::init_protocol();
+        let rng = &mut ark_std::test_rng();
+        let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+        let x = F::king_share(a, rng);
+        let y = F::king_share(b, rng);
+        assert_eq!((x + y).reveal(), a + b);
+        assert_eq!((x * y).reveal(), a * b);
+        assert_eq!(vec![x, y, x * y].reveal(), vec![a, b, a * b]);
+        assert_eq!(MpcMultiNet::stats().dropouts, vec![3]);
+    });
+}
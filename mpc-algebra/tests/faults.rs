@@ -0,0 +1,41 @@
+//! Tampering with shares in transit: SPDZ's MAC check catches it.
+use ark_bls12_377::Fr;
+use ark_ff::UniformRand;
+use mpc_algebra::{MpcField, Reveal, SpdzFieldShare};
+use mpc_net::faults::{self, Fault};
+use mpc_net::testing::run_parties_checked;
+use mpc_net::{MpcMultiNet, MpcNet};
+use std::time::Duration;
+
+type F = MpcField<Fr, SpdzFieldShare<Fr>>;
+
+#[test]
+fn spdz_detects_corrupted_share() {
+    let body = || {
+        F::init_protocol();
+        let rng = &mut ark_std::test_rng();
+        let x = F::king_share(Fr::rand(rng), rng);
+        // Party 1's share of `x`, as it is opened, is off by one.
+        if MpcMultiNet::party_id() == 1 {
+            faults::inject(0, Fault::Corrupt);
+        }
+        x.reveal();
+    };
+    let deadline = Duration::from_secs(20);
+    run_parties_checked(
+        "spdz_detects_corrupted_share",
+        2,
+        deadline,
+        |_| (),
+        body,
+        |exits| {
+            for e in &exits {
+                assert!(
+                    !e.hung && e.stderr.contains("MAC check failed"),
+                    "{:?}",
+                    exits
+                );
+            }
+        },
+    );
+}
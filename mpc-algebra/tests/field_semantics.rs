@@ -0,0 +1,134 @@
+//! Property tests: every `MpcField` operation, once revealed, agrees with the plain field.
+//!
+//! Each test runs as a small MPC. The test binary re-runs itself once per party (selecting just
+//! that test), and the parties talk over loopback. Since proptest's runner is seeded
+//! deterministically, all parties draw the same cases, and each case mixes public and shared
+//! operands.
+use ark_bls12_377::Fr;
+use ark_ff::{Field, PrimeField, Zero};
+use mpc_trait::MpcWire;
+use mpc_algebra::{
+    share::gsz20::field::GszFieldShare, AdditiveFieldShare, FieldShare, MpcField, MpcFieldAcc,
+    Reveal, SpdzFieldShare,
+};
+use proptest::prelude::*;
+use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+use mpc_net::testing::run_parties;
+
+/// A field element, and whether its `MpcField` operand is shared.
+fn operand() -> impl Strategy<Value = (Fr, bool)> {
+    (any::<[u8; 32]>(), any::<bool>()).prop_map(|(b, s)| (Fr::from_le_bytes_mod_order(&b), s))
+}
+
+fn lift<S: FieldShare<Fr>>((x, shared): (Fr, bool)) -> MpcField<Fr, S> {
+    if shared {
+        MpcField::king_share(x, &mut ark_std::test_rng())
+    } else {
+        MpcField::from_public(x)
+    }
+}
+
+fn check_ops<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let config = Config {
+        cases: 16,
+        max_shrink_iters: 0,
+        ..Config::default()
+    };
+    let rng = TestRng::deterministic_rng(RngAlgorithm::ChaCha);
+    let strategy = (
+        operand(),
+        operand(),
+        prop::collection::vec(operand(), 0..5),
+        any::<[u8; 48]>(),
+        (any::<u8>(), any::<bool>()),
+    );
+    TestRunner::new_with_rng(config, rng)
+        .run(&strategy, |(a, b, xs, bytes, (e, e_shared))| {
+            let (x, y) = (lift::<S>(a), lift::<S>(b));
+            prop_assert_eq!((x + y).reveal(), a.0 + b.0);
+            prop_assert_eq!((x - y).reveal(), a.0 - b.0);
+            prop_assert_eq!((x * y).reveal(), a.0 * b.0);
+            prop_assert_eq!((-x).reveal(), -a.0);
+            prop_assert_eq!(x.double().reveal(), a.0.double());
+            prop_assert_eq!(x.square().reveal(), a.0.square());
+            if !b.0.is_zero() {
+                prop_assert_eq!((x / y).reveal(), a.0 / b.0);
+                prop_assert_eq!(y.inverse().map(Reveal::reveal), b.0.inverse());
+                let mut quotients = vec![x; 3];
+                MpcField::batch_division_in_place(&mut quotients, &[y; 3]);
+                for q in quotients {
+                    prop_assert_eq!(q.reveal(), a.0 / b.0);
+                }
+            }
+
+            let mpc_xs: Vec<MpcField<Fr, S>> = xs.iter().cloned().map(lift).collect();
+            let plain_xs: Vec<Fr> = xs.iter().map(|x| x.0).collect();
+            prop_assert_eq!(
+                mpc_xs.iter().sum::<MpcField<Fr, S>>().reveal(),
+                plain_xs.iter().sum::<Fr>()
+            );
+            prop_assert_eq!(
+                mpc_xs.iter().product::<MpcField<Fr, S>>().reveal(),
+                plain_xs.iter().product::<Fr>()
+            );
+            // Reversed, so that shared operands meet both shared and public ones.
+            let rev_xs: Vec<_> = mpc_xs.iter().rev().cloned().collect();
+            prop_assert_eq!(
+                MpcField::dot(&mpc_xs, &rev_xs).reveal(),
+                plain_xs
+                    .iter()
+                    .zip(plain_xs.iter().rev())
+                    .map(|(x, y)| *x * y)
+                    .sum::<Fr>()
+            );
+            // The matrix [xs 0; 0 y], times (reversed xs, x).
+            let n = xs.len();
+            let rows = vec![
+                mpc_xs.iter().cloned().zip(0..n).collect(),
+                vec![(y, n)],
+            ];
+            let mpc_z: Vec<_> = rev_xs.iter().cloned().chain(Some(x)).collect();
+            let z = MpcField::sparse_mat_vec(&rows, &mpc_z);
+            prop_assert_eq!(z.len(), 2);
+            prop_assert_eq!(z[0].reveal(), MpcField::dot(&mpc_xs, &rev_xs).reveal());
+            prop_assert_eq!(z[1].reveal(), a.0 * b.0);
+            let mut acc: MpcFieldAcc<Fr, S> = mpc_xs.iter().map(|x| MpcFieldAcc::from(*x)).sum();
+            acc -= y.into();
+            acc *= MpcField::from_public(a.0);
+            prop_assert_eq!(
+                MpcField::from(acc).reveal(),
+                (plain_xs.iter().sum::<Fr>() - b.0) * a.0
+            );
+
+            let from_bytes = MpcField::<Fr, S>::from_random_bytes(&bytes);
+            prop_assert!(from_bytes.as_ref().map_or(true, |x| !x.is_shared()));
+            prop_assert_eq!(
+                from_bytes.map(Reveal::reveal),
+                Fr::from_random_bytes(&bytes)
+            );
+
+            let e_bits: Vec<MpcField<Fr, S>> = (0..8)
+                .map(|i| lift((Fr::from((e >> i) & 1), e_shared)))
+                .collect();
+            prop_assert_eq!(x.pow_shared(&e_bits).reveal(), a.0.pow([e as u64]));
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn hbc_field_ops() {
+    run_parties("hbc_field_ops", 2, check_ops::<AdditiveFieldShare<Fr>>);
+}
+
+#[test]
+fn spdz_field_ops() {
+    run_parties("spdz_field_ops", 2, check_ops::<SpdzFieldShare<Fr>>);
+}
+
+#[test]
+fn gsz_field_ops() {
+    run_parties("gsz_field_ops", 3, check_ops::<GszFieldShare<Fr>>);
+}
@@ -0,0 +1,63 @@
+//! Group Beaver triples, and the shared-point-times-shared-scalar products they feed.
+use ark_bls12_377::{Fr, G1Projective};
+use ark_ec::ProjectiveCurve;
+use ark_ff::{PrimeField, UniformRand, Zero};
+use mpc_algebra::{
+    preprocessing::SessionGroupTripleSource,
+    share::gsz20::{field::GszFieldShare, group::GszGroupShare},
+    share::msm::NaiveMsm,
+    AdditiveGroupShare, BeaverSource, GroupShare, MpcField, MpcGroup, Reveal, SpdzGroupShare,
+};
+
+use mpc_net::testing::run_parties;
+use mpc_trait::MpcWire;
+
+type G = G1Projective;
+
+fn check_group_triples<S: GroupShare<G>>() {
+    MpcField::<Fr, S::FieldShare>::init_protocol();
+    let mut source = SessionGroupTripleSource::<G, S>::default();
+    for _ in 0..4 {
+        let (x, y, z) = source.triple();
+        let (x, y, z) = (x.reveal(), y.reveal(), z.reveal());
+        assert!(!x.is_zero());
+        assert_eq!(x.mul(y.into_repr()), z);
+    }
+    check_shared_scale::<S>();
+}
+
+fn check_shared_scale<S: GroupShare<G>>() {
+    let rng = &mut ark_std::test_rng();
+    let (p, s) = (G::rand(rng), Fr::rand(rng));
+    let mut shared = MpcGroup::<G, S>::king_share(p, rng);
+    shared *= MpcField::<Fr, S::FieldShare>::king_share(s, rng);
+    assert!(shared.is_shared());
+    assert_eq!(shared.reveal(), p.mul(s.into_repr()));
+}
+
+#[test]
+fn hbc_group_triples() {
+    run_parties(
+        "hbc_group_triples",
+        2,
+        check_group_triples::<AdditiveGroupShare<G, NaiveMsm<G>>>,
+    );
+}
+
+#[test]
+fn spdz_group_triples() {
+    run_parties(
+        "spdz_group_triples",
+        2,
+        check_group_triples::<SpdzGroupShare<G, NaiveMsm<G>>>,
+    );
+}
+
+/// GSZ multiplies without triples, so only the product is checked.
+#[test]
+fn gsz_shared_scale() {
+    run_parties("gsz_shared_scale", 3, || {
+        MpcField::<Fr, GszFieldShare<Fr>>::init_protocol();
+        check_shared_scale::<GszGroupShare<G, NaiveMsm<G>>>();
+    });
+}
@@ -0,0 +1,38 @@
+//! Looking up a shared index in a public table.
+use ark_bls12_377::Fr;
+use ark_ff::UniformRand;
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::testing::run_parties;
+use mpc_trait::MpcWire;
+
+fn check_lookup<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    // A power of two, and not.
+    for &n in &[1usize, 8, 13] {
+        let table: Vec<Fr> = (0..n).map(|_| Fr::rand(rng)).collect();
+        for i in 0..n {
+            let index = MpcField::<Fr, S>::king_share(Fr::from(i as u64), rng);
+            let hot = index.one_hot(n);
+            assert_eq!(hot.len(), n);
+            let expected: Vec<Fr> = (0..n).map(|j| Fr::from((i == j) as u8)).collect();
+            assert_eq!(hot.reveal(), expected);
+
+            let out = index.lookup(&table);
+            assert!(out.is_shared() || n == 1);
+            assert_eq!(out.reveal(), table[i]);
+            let public = MpcField::<Fr, S>::from_public(Fr::from(i as u64));
+            assert_eq!(public.lookup(&table), MpcField::from_public(table[i]));
+        }
+    }
+}
+
+#[test]
+fn hbc_lookup() {
+    run_parties("hbc_lookup", 2, check_lookup::<AdditiveFieldShare<Fr>>);
+}
+
+#[test]
+fn spdz_lookup() {
+    run_parties("spdz_lookup", 2, check_lookup::<SpdzFieldShare<Fr>>);
+}
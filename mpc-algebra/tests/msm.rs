@@ -0,0 +1,78 @@
+//! Pippenger's MSM, against `ark-ec`'s, locally and over shared scalars.
+use ark_bls12_377::{Bls12_377, Fr, G1Affine, G2Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{Field, UniformRand, Zero};
+use mpc_algebra::{
+    msm::{Msm, PippengerMsm},
+    AdditivePairingShare, MpcPairingEngine, PairingShare, Reveal, SpdzPairingShare,
+};
+use mpc_net::testing::run_parties;
+
+fn check_local<G: AffineCurve>() {
+    let rng = &mut ark_std::test_rng();
+    for &n in &[0, 1, 5, 31, 32, 100] {
+        let bases: Vec<G> = (0..n)
+            .map(|_| G::Projective::rand(rng).into_affine())
+            .collect();
+        let mut scalars: Vec<G::ScalarField> = (0..n).map(|_| G::ScalarField::rand(rng)).collect();
+        // Digits of zero and of the largest scalar.
+        if n > 1 {
+            scalars[0] = G::ScalarField::zero();
+            scalars[1] = -G::ScalarField::from(1u8);
+        }
+        let doubled: Vec<G::ScalarField> = scalars.iter().map(|s| s.double()).collect();
+        assert_eq!(
+            PippengerMsm::msm(&bases, &scalars),
+            G::multi_scalar_mul(&bases, &scalars).into_affine()
+        );
+        let both = PippengerMsm::msm_many(&bases, &[scalars.clone(), doubled.clone()]);
+        assert_eq!(both[1], G::multi_scalar_mul(&bases, &doubled).into_affine());
+        assert_eq!(both[0], PippengerMsm::msm(&bases, &scalars));
+        // Extra bases are ignored, as by ark's.
+        assert_eq!(
+            PippengerMsm::msm(&bases, &scalars[..n / 2]),
+            G::multi_scalar_mul(&bases, &scalars[..n / 2]).into_affine()
+        );
+    }
+}
+
+#[test]
+fn pippenger_matches_ark() {
+    check_local::<G1Affine>();
+    check_local::<G2Affine>();
+}
+
+fn check_shared<S: PairingShare<Bls12_377>>() {
+    type E<S> = MpcPairingEngine<Bls12_377, S>;
+    let rng = &mut ark_std::test_rng();
+    let bases: Vec<G1Affine> = (0..40)
+        .map(|_| ark_bls12_377::G1Projective::rand(rng).into_affine())
+        .collect();
+    let scalars: Vec<Fr> = (0..40).map(|_| Fr::rand(rng)).collect();
+    let shared = Reveal::king_share_batch(scalars.clone(), rng);
+    let lifted: Vec<<E<S> as ark_ec::PairingEngine>::G1Affine> =
+        bases.iter().map(|b| Reveal::from_public(*b)).collect();
+    let result = <E<S> as ark_ec::PairingEngine>::G1Affine::multi_scalar_mul(&lifted, &shared);
+    assert_eq!(
+        result.reveal(),
+        G1Affine::multi_scalar_mul(&bases, &scalars)
+    );
+}
+
+#[test]
+fn hbc_pippenger() {
+    run_parties(
+        "hbc_pippenger",
+        3,
+        check_shared::<AdditivePairingShare<Bls12_377>>,
+    );
+}
+
+#[test]
+fn spdz_pippenger() {
+    run_parties(
+        "spdz_pippenger",
+        2,
+        check_shared::<SpdzPairingShare<Bls12_377>>,
+    );
+}
@@ -0,0 +1,39 @@
+//! Nested shares: an `MpcField` over an `MpcField`.
+//!
+//! The outer protocol treats the inner field as its base field, so its shares, openings and
+//! triples are inner values. Here each outer party is a group of one, so inner values stay
+//! public, and revealing twice gives back the plain value.
+use ark_bls12_377::Fr;
+use ark_ff::{FftField, Field, PrimeField};
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::testing::run_parties;
+use mpc_trait::MpcWire;
+
+type Inner = MpcField<Fr, AdditiveFieldShare<Fr>>;
+
+/// Nested values can go wherever a field can.
+fn is_field<F: PrimeField + FftField>() {}
+
+fn check_nested<S: FieldShare<Inner>>() {
+    is_field::<MpcField<Inner, S>>();
+    MpcField::<Inner, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let mut lift = |x: u8| MpcField::<Inner, S>::king_share(Inner::from_public(Fr::from(x)), rng);
+    let (x, y) = (lift(3), lift(5));
+    let z = x * y + x;
+    assert!(z.is_shared());
+    assert_eq!(z.reveal().reveal(), Fr::from(18u8));
+    let z_inv = z.inverse().unwrap().reveal();
+    assert!(!z_inv.is_shared());
+    assert_eq!(z_inv.reveal(), Fr::from(18u8).inverse().unwrap());
+}
+
+#[test]
+fn hbc_over_hbc() {
+    run_parties("hbc_over_hbc", 2, check_nested::<AdditiveFieldShare<Inner>>);
+}
+
+#[test]
+fn spdz_over_hbc() {
+    run_parties("spdz_over_hbc", 2, check_nested::<SpdzFieldShare<Inner>>);
+}
@@ -0,0 +1,40 @@
+//! Counting protocol operations, in the stats and in each timed section.
+use ark_bls12_377::Fr;
+use mpc_algebra::{
+    preprocessing::random_bits, share::field::FIELD_OPENINGS_METRIC, AdditiveFieldShare, MpcField,
+    Reveal, INPUTS_METRIC,
+};
+use mpc_net::{end_mpc_timer, start_mpc_timer, testing::run_parties, timer, MpcMultiNet, MpcNet};
+
+type F = MpcField<Fr, AdditiveFieldShare<Fr>>;
+
+#[test]
+fn hbc_op_counts() {
+    run_parties("hbc_op_counts", 2, || {
+        let rng = &mut ark_std::test_rng();
+        MpcMultiNet::reset_stats();
+        let xs = F::king_share_batch(vec![Fr::from(2u8), Fr::from(3u8)], rng);
+        let t = start_mpc_timer!(|| "multiply");
+        let product = (xs[0] * xs[1]).reveal();
+        let _bits = random_bits::<Fr, AdditiveFieldShare<Fr>>(4);
+        end_mpc_timer!(t);
+        assert_eq!(product, Fr::from(6u8));
+
+        let counters = MpcMultiNet::stats().counters;
+        assert_eq!(counters[INPUTS_METRIC], 2);
+        assert_eq!(counters["mpc_triples_consumed_total"], 1);
+        assert_eq!(counters["mpc_random_bits_consumed_total"], 4);
+        // The masked operands of the product, and the product itself; self-checks (on in debug
+        // builds) also check the product by opening its operands and result.
+        let openings = if mpc_net::self_checks() { 6 } else { 3 };
+        assert_eq!(counters[FIELD_OPENINGS_METRIC], openings);
+
+        let phase = timer::phases()
+            .into_iter()
+            .find(|p| p.path == "multiply")
+            .unwrap();
+        assert!(!phase.counters.contains_key(INPUTS_METRIC));
+        assert_eq!(phase.counters[FIELD_OPENINGS_METRIC], openings);
+        assert!(timer::summary().contains(&format!("field_elements_opened={}", openings)));
+    });
+}
@@ -0,0 +1,45 @@
+//! Raising shared values to a public power, together.
+use ark_bls12_377::Fr;
+use ark_ff::{Field, UniformRand};
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::assert_comm;
+use mpc_net::testing::{measure, run_parties};
+
+fn check_batch_pow<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let xs: Vec<Fr> = (0..6).map(|_| Fr::rand(rng)).collect();
+    let mut values = MpcField::<Fr, S>::king_share_batch(xs[..4].to_vec(), rng);
+    values.extend(xs[4..].iter().map(|x| MpcField::from_public(*x)));
+    for exp in &[
+        vec![0u64],
+        vec![1],
+        vec![2],
+        vec![13],
+        vec![1 << 20],
+        vec![5, 3],
+    ] {
+        let expected: Vec<Fr> = xs.iter().map(|x| x.pow(exp)).collect();
+        let (powers, batch) = measure(|| MpcField::batch_pow(&values, exp));
+        assert_eq!(powers.reveal(), expected);
+        // The shared values go through the chain together, in as many rounds as one alone.
+        let (_, one) = measure(|| MpcField::batch_pow(&values[..1], exp));
+        assert_eq!(batch.rounds, one.rounds);
+    }
+    let squares = assert_comm!(rounds == 0, { MpcField::batch_pow(&values[4..], &[2]) });
+    assert_eq!(squares.reveal(), vec![xs[4].square(), xs[5].square()]);
+}
+
+#[test]
+fn hbc_batch_pow() {
+    run_parties(
+        "hbc_batch_pow",
+        2,
+        check_batch_pow::<AdditiveFieldShare<Fr>>,
+    );
+}
+
+#[test]
+fn spdz_batch_pow() {
+    run_parties("spdz_batch_pow", 2, check_batch_pow::<SpdzFieldShare<Fr>>);
+}
@@ -0,0 +1,60 @@
+//! Openings to a single party.
+use ark_bls12_377::{Fr, G1Projective as G1};
+use ark_ff::UniformRand;
+use mpc_algebra::{
+    share::gsz20::field::GszFieldShare, AdditiveFieldShare, FieldShare, MpcField, Reveal,
+    SpdzFieldShare,
+};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+
+const RECIPIENT: usize = 1;
+
+fn check_reveal_to<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+    let x = MpcField::<Fr, S>::king_share(a, rng);
+    let y = MpcField::<Fr, S>::king_share(b, rng);
+    let public = MpcField::<Fr, S>::from_public(b);
+    let me = MpcMultiNet::party_id() == RECIPIENT;
+    assert_eq!(x.reveal_to(RECIPIENT), if me { Some(a) } else { None });
+    assert_eq!(
+        vec![x * y, public].reveal_to(RECIPIENT),
+        if me { Some(vec![a * b, b]) } else { None }
+    );
+    // The parties are still in step.
+    assert_eq!(y.reveal(), b);
+}
+
+#[test]
+fn hbc_reveal_to() {
+    run_parties("hbc_reveal_to", 3, || {
+        check_reveal_to::<AdditiveFieldShare<Fr>>();
+        // Others only get the recipient's acknowledgement, not shares.
+        MpcMultiNet::reset_stats();
+        let g = G1::rand(&mut ark_std::test_rng());
+        let x = mpc_algebra::honest_but_curious::MpcGroup::<G1>::king_share(
+            g,
+            &mut ark_std::test_rng(),
+        );
+        MpcMultiNet::reset_stats();
+        let opened = x.reveal_to(RECIPIENT);
+        let recvd = MpcMultiNet::stats().bytes_recv;
+        if MpcMultiNet::party_id() == RECIPIENT {
+            assert_eq!(opened, Some(g));
+        } else {
+            assert_eq!(opened, None);
+            assert!(recvd < 16, "received {} bytes", recvd);
+        }
+    });
+}
+
+#[test]
+fn spdz_reveal_to() {
+    run_parties("spdz_reveal_to", 3, check_reveal_to::<SpdzFieldShare<Fr>>);
+}
+
+#[test]
+fn gsz_reveal_to() {
+    run_parties("gsz_reveal_to", 3, check_reveal_to::<GszFieldShare<Fr>>);
+}
@@ -0,0 +1,43 @@
+//! Random elements: public, locally shared, and jointly shared.
+use ark_bls12_377::Fr;
+use ark_ff::{Field, UniformRand, Zero};
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use ark_std::test_rng;
+use mpc_algebra::{AdditiveFieldShare, MpcField, Reveal};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+use mpc_trait::MpcWire;
+
+type MFr = MpcField<Fr, AdditiveFieldShare<Fr>>;
+
+#[test]
+fn sampling_modes() {
+    run_parties("sampling_modes", 2, || {
+        // A common rng, as public coins are drawn from.
+        let (rng, mut copy) = (&mut test_rng(), test_rng());
+        let public = MFr::public_rand(rng);
+        assert!(!public.is_shared());
+        assert_eq!(public.reveal(), Fr::rand(&mut copy));
+
+        // The value is the owner's draw, which the others skip.
+        let local = MFr::local_rand(1, rng);
+        assert!(local.is_shared());
+        assert_eq!(local.reveal(), Fr::rand(&mut copy));
+        let owner = MpcMultiNet::party_id() == 1;
+        assert_eq!(Fr::rand(rng) == Fr::rand(&mut copy), owner);
+
+        // From a common rng, each party holds the same share, so all know the value.
+        let (rng, mut copy) = (&mut test_rng(), test_rng());
+        let joint = MFr::joint_rand(rng);
+        assert!(joint.is_shared());
+        assert_eq!(joint.reveal(), Fr::rand(&mut copy).double());
+
+        // From per-party rngs, it is the sum of the parties' draws.
+        let me = MpcMultiNet::party_id() as u64;
+        let joint = MFr::joint_rand(&mut StdRng::seed_from_u64(me));
+        let sum = (0..2).fold(Fr::zero(), |acc, i| {
+            acc + Fr::rand(&mut StdRng::seed_from_u64(i))
+        });
+        assert_eq!(joint.reveal(), sum);
+        assert_eq!(MFr::rand(&mut StdRng::seed_from_u64(me)).reveal(), sum);
+    });
+}
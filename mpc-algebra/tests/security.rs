@@ -0,0 +1,28 @@
+//! Security modes: insecure shortcuts are refused in secure mode, and allowed in benchmark mode.
+use ark_bls12_377::Fr;
+use mpc_algebra::security::{
+    security_mode, set_security_mode, SecurityMode, DUMMY_TRIPLES, FIXED_MAC_KEY,
+};
+use mpc_algebra::share::spdz::mac_share;
+use mpc_algebra::{AdditiveFieldShare, BeaverSource, DummyFieldTripleSource};
+use std::panic::catch_unwind;
+
+fn dummy_triple() {
+    DummyFieldTripleSource::<Fr, AdditiveFieldShare<Fr>>::default().triple();
+}
+
+#[test]
+fn secure_mode_refuses_shortcuts() {
+    assert_eq!(security_mode(), SecurityMode::Benchmark);
+    assert!(DUMMY_TRIPLES.allow().is_ok());
+
+    set_security_mode(SecurityMode::Secure);
+    let err = DUMMY_TRIPLES.allow().unwrap_err();
+    assert!(err.to_string().contains("dummy Beaver triples"));
+    assert!(FIXED_MAC_KEY.allow().is_err());
+    assert!(catch_unwind(dummy_triple).is_err());
+    assert!(catch_unwind(mac_share::<Fr>).is_err());
+
+    set_security_mode(SecurityMode::Benchmark);
+    assert!(catch_unwind(mac_share::<Fr>).is_ok());
+}
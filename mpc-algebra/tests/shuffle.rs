@@ -0,0 +1,80 @@
+//! Shuffling a shared list through Beneš networks.
+use ark_bls12_377::Fr;
+use ark_ff::{One, UniformRand, Zero};
+use ark_std::rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use mpc_algebra::shuffle::Benes;
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::testing::{measure, run_parties};
+use mpc_net::{MpcMultiNet, MpcNet};
+
+#[test]
+fn routes_every_permutation() {
+    let rng = &mut ark_std::test_rng();
+    for len in 0..=17 {
+        let network = Benes::new(len);
+        let width = len.max(2).next_power_of_two();
+        let log = width.trailing_zeros() as usize;
+        assert_eq!(network.layers().len(), 2 * log - 1);
+        assert!(network.layers().iter().all(|l| l.len() == width / 2));
+        let values: Vec<Fr> = (0..len).map(|_| Fr::rand(rng)).collect();
+        for _ in 0..20 {
+            let mut perm: Vec<usize> = (0..len).collect();
+            perm.shuffle(rng);
+            let bits: Vec<Fr> = network.route(&perm).into_iter().map(Fr::from).collect();
+            let (out, products) = network.apply(&values, &bits);
+            assert_eq!(products.len(), network.switches());
+            for i in 0..len {
+                assert_eq!(out[perm[i]], values[i], "len {}, perm {:?}", len, perm);
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "not a permutation")]
+fn refuses_non_permutations() {
+    Benes::new(3).route(&[0, 2, 2]);
+}
+
+fn check_shuffle<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let me = MpcMultiNet::party_id();
+    let n = MpcMultiNet::n_parties();
+    // Each party's own permutations.
+    let rng = &mut StdRng::seed_from_u64(me as u64);
+    let xs: Vec<Fr> = (0..11u64).map(Fr::from).collect();
+    let values = MpcField::<Fr, S>::king_share_batch(xs.clone(), rng);
+    let (shuffled, cost) = measure(|| MpcField::shuffle(&values, rng));
+    let mut opened = shuffled.reveal();
+    assert_ne!(opened, xs);
+    opened.sort();
+    assert_eq!(opened, xs);
+    // As many rounds per layer (of 7, on 16 wires), for each party, as a single switch takes.
+    let (_, switch) = measure(|| MpcField::shuffle_passes(&values[..2], &[0], rng));
+    assert_eq!(cost.rounds, 7 * n * switch.rounds);
+
+    let owners: Vec<usize> = (0..n).rev().collect();
+    let passes = MpcField::<Fr, S>::shuffle_passes(&values, &owners, rng);
+    assert_eq!(passes.len(), n);
+    for pass in passes {
+        assert_eq!(pass.products.len(), pass.bits.len());
+        let bits = pass.bits.reveal();
+        assert!(bits.iter().all(|b| b.is_zero() || b.is_one()));
+    }
+
+    let public: Vec<MpcField<Fr, S>> = xs.iter().map(|x| MpcField::from_public(*x)).collect();
+    let mut opened = MpcField::shuffle(&public, rng).reveal();
+    opened.sort();
+    assert_eq!(opened, xs);
+    assert!(MpcField::<Fr, S>::shuffle(&[], rng).is_empty());
+}
+
+#[test]
+fn hbc_shuffle() {
+    run_parties("hbc_shuffle", 3, check_shuffle::<AdditiveFieldShare<Fr>>);
+}
+
+#[test]
+fn spdz_shuffle() {
+    run_parties("spdz_shuffle", 2, check_shuffle::<SpdzFieldShare<Fr>>);
+}
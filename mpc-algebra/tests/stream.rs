@@ -0,0 +1,26 @@
+//! Streaming a serialized object bigger than a chunk to the other parties.
+use ark_bls12_377::Fr;
+use ark_ff::UniformRand;
+use mpc_algebra::channel::MpcSerNet;
+use mpc_net::{stream::CHUNK, testing::run_parties, MpcMultiNet, MpcNet};
+
+const SENDER: usize = 1;
+
+#[test]
+fn stream_serialized() {
+    run_parties("stream_serialized", 3, || {
+        let rng = &mut ark_std::test_rng();
+        let xs: Vec<Fr> = (0..CHUNK / 10).map(|_| Fr::rand(rng)).collect();
+        let mut last = 0;
+        let total = if MpcMultiNet::party_id() == SENDER {
+            MpcMultiNet::stream_to_all(&xs, |n| last = n)
+        } else {
+            let ys: Vec<Fr> = MpcMultiNet::stream_from(SENDER, |n| last = n);
+            assert!(ys == xs);
+            last
+        };
+        // A length prefix and 32 bytes per element.
+        assert_eq!(total, 8 + xs.len() * 32);
+        assert_eq!(last, total);
+    });
+}
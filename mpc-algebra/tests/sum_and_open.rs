@@ -0,0 +1,84 @@
+//! Opening the sum of many shared values at once.
+use ark_bls12_377::Fr;
+use ark_ff::{UniformRand, Zero};
+use mpc_algebra::{
+    share::field::FIELD_OPENINGS_METRIC, share::gsz20::field::GszFieldShare, AdditiveFieldShare,
+    FieldShare, MpcField, Reveal, SpdzFieldShare,
+};
+use mpc_net::{assert_comm, metrics, testing::run_parties, MpcMultiNet, MpcNet};
+
+fn check_sum_and_open<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let xs: Vec<Fr> = (0..10).map(|_| Fr::rand(rng)).collect();
+    let mut shared = MpcField::<Fr, S>::king_share_batch(xs.clone(), rng);
+    shared.push(MpcField::from_public(Fr::from(7u8)));
+    let expected = xs.iter().sum::<Fr>() + Fr::from(7u8);
+
+    let openings = || {
+        metrics::counters()
+            .get(FIELD_OPENINGS_METRIC)
+            .copied()
+            .unwrap_or(0)
+    };
+    let before = openings();
+    assert_eq!(MpcField::sum_and_open(shared), expected);
+    assert_eq!(openings() - before, 1);
+
+    let public = vec![MpcField::<Fr, S>::from_public(Fr::from(2u8)); 3];
+    assert_eq!(
+        assert_comm!(rounds == 0, { MpcField::sum_and_open(public) }),
+        Fr::from(6u8)
+    );
+    assert!(MpcField::<Fr, S>::sum_and_open(Vec::new()).is_zero());
+}
+
+#[test]
+fn hbc_sum_and_open() {
+    run_parties(
+        "hbc_sum_and_open",
+        2,
+        check_sum_and_open::<AdditiveFieldShare<Fr>>,
+    );
+}
+
+#[test]
+fn spdz_sum_and_open() {
+    run_parties(
+        "spdz_sum_and_open",
+        2,
+        check_sum_and_open::<SpdzFieldShare<Fr>>,
+    );
+}
+
+#[test]
+fn gsz_sum_and_open() {
+    run_parties(
+        "gsz_sum_and_open",
+        3,
+        check_sum_and_open::<GszFieldShare<Fr>>,
+    );
+}
+
+/// With enough parties, the shares go up a tree: each party sends or receives one message per
+/// level, and then gets the sum from the king.
+#[test]
+fn hbc_sum_tree() {
+    run_parties("hbc_sum_tree", 5, || {
+        check_sum_and_open::<AdditiveFieldShare<Fr>>();
+        let rng = &mut ark_std::test_rng();
+        let x = MpcField::<Fr, AdditiveFieldShare<Fr>>::king_share(Fr::from(3u8), rng);
+        let levels = if MpcMultiNet::party_id() % 2 == 1 {
+            1
+        } else {
+            3
+        };
+        let sum = assert_comm!(rounds <= levels + 1, { MpcField::sum_and_open(vec![x; 4]) });
+        assert_eq!(sum, Fr::from(12u8));
+    });
+}
+
+#[test]
+fn spdz_sum_tree() {
+    run_parties("spdz_sum_tree", 5, check_sum_and_open::<SpdzFieldShare<Fr>>);
+}
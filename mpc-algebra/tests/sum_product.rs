@@ -0,0 +1,63 @@
+//! Sums and products over iterators that mix public and shared values.
+use ark_bls12_377::Fr;
+use ark_ff::{UniformRand, Zero};
+use mpc_algebra::{AdditiveFieldShare, FieldShare, MpcField, Reveal, SpdzFieldShare};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+use mpc_trait::MpcWire;
+
+fn triples() -> u64 {
+    MpcMultiNet::stats()
+        .counters
+        .get("mpc_triples_consumed_total")
+        .copied()
+        .unwrap_or(0)
+}
+
+fn check_sum_product<S: FieldShare<Fr>>() {
+    MpcField::<Fr, S>::init_protocol();
+    let rng = &mut ark_std::test_rng();
+    let shared: Vec<Fr> = (0..7).map(|_| Fr::rand(rng)).collect();
+    let public: Vec<Fr> = (0..3).map(|_| Fr::rand(rng)).collect();
+    let mut xs = MpcField::<Fr, S>::king_share_batch(shared.clone(), rng);
+    for (i, p) in public.iter().enumerate() {
+        xs.insert(2 * i, MpcField::from_public(*p));
+    }
+    let all = || shared.iter().chain(&public);
+
+    let sum: MpcField<Fr, S> = xs.iter().sum();
+    assert!(sum.is_shared());
+    assert_eq!(sum.reveal(), all().sum::<Fr>());
+    let public_sum: MpcField<Fr, S> = xs.iter().filter(|x| !x.is_shared()).sum();
+    assert_eq!(public_sum, MpcField::from_public(public.iter().sum()));
+
+    // One triple per shared factor but the first; the public ones only scale.
+    let before = triples();
+    let product: MpcField<Fr, S> = xs.iter().product();
+    assert_eq!(triples() - before, shared.len() as u64 - 1);
+    assert_eq!(product.reveal(), all().product::<Fr>());
+
+    // A public zero needs no shared multiplications at all.
+    xs.push(MpcField::from_public(Fr::zero()));
+    let before = triples();
+    let zero: MpcField<Fr, S> = xs.into_iter().product();
+    assert_eq!(triples(), before);
+    assert_eq!(zero, MpcField::from_public(Fr::zero()));
+}
+
+#[test]
+fn hbc_sum_product() {
+    run_parties(
+        "hbc_sum_product",
+        2,
+        check_sum_product::<AdditiveFieldShare<Fr>>,
+    );
+}
+
+#[test]
+fn spdz_sum_product() {
+    run_parties(
+        "spdz_sum_product",
+        2,
+        check_sum_product::<SpdzFieldShare<Fr>>,
+    );
+}
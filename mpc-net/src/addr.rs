@@ -0,0 +1,196 @@
+//! Parties' addresses: names or IP addresses (v4 or v6), with a port.
+//!
+//! A hosts-file entry is `HOST:PORT`, where `HOST` is a DNS name, an IPv4 address, or an IPv6
+//! address in brackets (`[::1]:8000`). A name is resolved when connecting, not when the file is
+//! read, and each address it resolves to (IPv6 or IPv4) is tried in turn, so a dual-stack or
+//! multi-homed host is reached on whichever of its addresses is reachable.
+//!
+//! The address in the hosts file is the one a party advertises: where its peers connect to it.
+//! By default, it listens there too. Behind NAT, the advertised address is not one of the host's
+//! own; a party then listens on the advertised port on all interfaces instead. To listen on a
+//! particular interface, set it with [crate::MpcMultiNet::set_bind_addr].
+use log::warn;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+
+/// A name or IP address, and a port.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct HostPort {
+    /// A DNS name or an IP address; IPv6 addresses are kept without their brackets.
+    pub host: String,
+    pub port: u16,
+}
+
+impl HostPort {
+    /// The same host, at `port`.
+    pub fn with_port(&self, port: u16) -> Self {
+        Self {
+            host: self.host.clone(),
+            port,
+        }
+    }
+
+    /// Every address the host resolves to, in the resolver's order.
+    pub fn resolve(&self) -> io::Result<Vec<SocketAddr>> {
+        let addrs: Vec<SocketAddr> = (self.host.as_str(), self.port).to_socket_addrs()?.collect();
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} has no addresses", self.host),
+            ));
+        }
+        Ok(addrs)
+    }
+}
+
+impl FromStr for HostPort {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (host, port) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("expected HOST:PORT, found {}", s))?;
+        let port = port
+            .parse()
+            .map_err(|e| format!("bad port in {}: {}", s, e))?;
+        let host = match host.strip_prefix('[') {
+            Some(v6) => v6
+                .strip_suffix(']')
+                .filter(|v6| v6.parse::<Ipv6Addr>().is_ok())
+                .ok_or_else(|| format!("bad IPv6 address in {}", s))?,
+            // An unbracketed colon would make the port ambiguous.
+            None if host.is_empty() || host.contains(':') => {
+                return Err(format!("bad host in {} (bracket IPv6 addresses)", s))
+            }
+            None => host,
+        };
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+        })
+    }
+}
+
+impl Display for HostPort {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+impl From<SocketAddr> for HostPort {
+    fn from(addr: SocketAddr) -> Self {
+        Self {
+            host: addr.ip().to_string(),
+            port: addr.port(),
+        }
+    }
+}
+
+/// Listen for peers of a party that advertises `advertised`.
+///
+/// With `bind`, listen there, on the advertised port if `bind`'s is 0. Otherwise, listen at the
+/// first of the advertised addresses that is this host's; if none is (as behind NAT), listen on
+/// the advertised port on all interfaces.
+pub fn listen(advertised: &HostPort, bind: Option<SocketAddr>) -> io::Result<TcpListener> {
+    if let Some(mut bind) = bind {
+        if bind.port() == 0 {
+            bind.set_port(advertised.port);
+        }
+        return TcpListener::bind(bind);
+    }
+    let addrs = advertised.resolve()?;
+    let mut last = None;
+    for addr in &addrs {
+        match TcpListener::bind(addr) {
+            Ok(l) => return Ok(l),
+            Err(e) if e.kind() == io::ErrorKind::AddrNotAvailable => last = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+    let any: IpAddr = match addrs[0] {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+    warn!(
+        "{} is not an address of this host ({}); listening on all interfaces",
+        advertised,
+        last.unwrap()
+    );
+    TcpListener::bind(SocketAddr::new(any, advertised.port))
+}
+
+/// Connect to the first of `addr`'s addresses that accepts, or fail with the last one's error.
+pub fn connect(addr: &HostPort) -> io::Result<TcpStream> {
+    let mut last = None;
+    for a in addr.resolve()? {
+        match TcpStream::connect(a) {
+            Ok(s) => return Ok(s),
+            Err(e) => last = Some(e),
+        }
+    }
+    Err(last.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_and_both_families() {
+        for s in &["127.0.0.1:8000", "[::1]:8000", "localhost:0", "[fe80::1]:1"] {
+            let a: HostPort = s.parse().unwrap();
+            assert_eq!(&a.to_string(), s);
+        }
+        assert_eq!("[::1]:8000".parse::<HostPort>().unwrap().host, "::1");
+        for s in &[
+            "::1:8000",
+            "[::1:8000",
+            "[nope]:1",
+            "host",
+            ":1",
+            "host:port",
+        ] {
+            assert!(s.parse::<HostPort>().is_err(), "{} parsed", s);
+        }
+        let v6: SocketAddr = "[::1]:9".parse().unwrap();
+        assert_eq!(HostPort::from(v6), "[::1]:9".parse().unwrap());
+    }
+
+    #[test]
+    fn listens_and_connects_over_ipv6() {
+        let loopback: HostPort = "[::1]:0".parse().unwrap();
+        let listener = match listen(&loopback, None) {
+            Ok(l) => l,
+            // Some hosts (and containers) have no IPv6.
+            Err(_) => return,
+        };
+        let port = listener.local_addr().unwrap().port();
+        connect(&loopback.with_port(port)).unwrap();
+        listener.accept().unwrap();
+    }
+
+    #[test]
+    fn falls_back_to_all_interfaces_behind_nat() {
+        // A documentation address: routable in form, but never this host's.
+        let public: HostPort = "192.0.2.1:0".parse().unwrap();
+        let listener = listen(&public, None).unwrap();
+        let local = listener.local_addr().unwrap();
+        assert!(local.ip().is_unspecified());
+        connect(
+            &"127.0.0.1:0"
+                .parse::<HostPort>()
+                .unwrap()
+                .with_port(local.port()),
+        )
+        .unwrap();
+
+        let bind = "127.0.0.1:0".parse().unwrap();
+        let listener = listen(&public, Some(bind)).unwrap();
+        assert!(listener.local_addr().unwrap().ip().is_loopback());
+    }
+}
@@ -0,0 +1,89 @@
+//! Faults injected into this party's outgoing messages, for testing how protocols fail.
+//!
+//! A test [inject]s a fault into the `nth` message that this party sends from then on, to any
+//! peer, on any connection or lane. Dropped or delayed messages leave the receiver waiting, so
+//! such tests should also set a [timeout](crate::MpcMultiNet::set_timeout), and run the parties
+//! with [run_parties_checked](crate::testing::run_parties_checked), which expects failures.
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The exit code of a party that [Fault::Crash]ed.
+pub const CRASH_EXIT: i32 = 86;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The message is lost: it is not sent, but counts as sent.
+    Drop,
+    /// The low bit of the message's first byte (not counting any length prefix) is flipped,
+    /// after the message is tagged (if messages are authenticated). For a message of field
+    /// elements, this changes the first by one.
+    Corrupt,
+    /// The message is sent after a pause.
+    Delay(Duration),
+    /// The party crashes instead of sending the message: its process exits with [CRASH_EXIT],
+    /// closing its connections.
+    Crash,
+}
+
+lazy_static! {
+    /// Pending faults, with how many messages are to be sent before each.
+    static ref FAULTS: Mutex<Vec<(u64, Fault)>> = Mutex::new(Vec::new());
+}
+
+/// Whether any faults are pending, so that sends need not lock [FAULTS] otherwise.
+static ARMED: AtomicBool = AtomicBool::new(false);
+
+/// Inject `fault` into the `nth` (from zero) message this party sends from now on.
+pub fn inject(nth: u64, fault: Fault) {
+    FAULTS.lock().unwrap().push((nth, fault));
+    ARMED.store(true, Ordering::SeqCst);
+}
+
+/// Cancel all pending faults.
+pub fn clear() {
+    FAULTS.lock().unwrap().clear();
+    ARMED.store(false, Ordering::SeqCst);
+}
+
+/// What to send in place of a message.
+pub(crate) enum Outgoing {
+    Intact,
+    Dropped,
+    Corrupted(Vec<Vec<u8>>),
+}
+
+/// Count a message, made of `parts`, that this party is about to send, and apply the fault
+/// injected into it, if any. Delays and crashes happen here.
+pub(crate) fn outgoing(parts: &[&[u8]]) -> Outgoing {
+    if !ARMED.load(Ordering::SeqCst) {
+        return Outgoing::Intact;
+    }
+    let fault = {
+        let mut faults = FAULTS.lock().unwrap();
+        let due = faults.iter().position(|(left, _)| *left == 0);
+        let fault = due.map(|i| faults.remove(i).1);
+        for (left, _) in faults.iter_mut() {
+            *left -= 1;
+        }
+        ARMED.store(!faults.is_empty(), Ordering::SeqCst);
+        fault
+    };
+    match fault {
+        None => Outgoing::Intact,
+        Some(Fault::Drop) => Outgoing::Dropped,
+        Some(Fault::Corrupt) => {
+            let mut parts: Vec<Vec<u8>> = parts.iter().map(|p| p.to_vec()).collect();
+            if let Some(p) = parts.iter_mut().rev().find(|p| !p.is_empty()) {
+                p[0] ^= 1;
+            }
+            Outgoing::Corrupted(parts)
+        }
+        Some(Fault::Delay(d)) => {
+            std::thread::sleep(d);
+            Outgoing::Intact
+        }
+        Some(Fault::Crash) => std::process::exit(CRASH_EXIT),
+    }
+}
@@ -0,0 +1,322 @@
+//! Checking that all parties were started with the same public parameters.
+//!
+//! Parties configured differently (another curve, proof system, circuit, ...) do not notice by
+//! themselves: they exchange messages of unexpected sizes and hang, or compute garbage. Right
+//! after connecting, [check_params] compares digests of every named parameter, and reports the
+//! first that differs.
+//!
+//! That needs the parties to agree on the wire format in the first place. So before anything
+//! else, each connection starts with a [Hello]: the protocol version, and the [Features] in use.
+//! Most features must match; [negotiated](Features::NEGOTIATED) ones are used on a connection
+//! only if both ends offer them.
+//!
+//! After the hello, the two ends swap random nonces; every party's goes into the session id
+//! ([crate::MpcNet::session_id]), and with authentication on, into the keys messages are tagged
+//! with.
+use blake2::{Blake2s, Digest};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::Duration;
+
+use super::MpcNet;
+
+const DIGEST_LEN: usize = 32;
+
+/// Version of the wire format. Bump it whenever messages change shape.
+pub const PROTOCOL_VERSION: u16 = 2;
+
+/// Starts every [Hello], so that a peer that sends none (e.g., an older build) is recognized.
+const MAGIC: &[u8; 4] = b"MPCN";
+
+const HELLO_LEN: usize = 10;
+
+/// How long to wait for a peer's [Hello].
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Application features, set with [set_app_features].
+static APP_FEATURES: AtomicU16 = AtomicU16::new(0);
+
+/// A bitmap of options all parties must agree on.
+///
+/// The low 16 bits are options of this crate that change the wire format; the high 16 are the
+/// application's (see [set_app_features]).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Features(pub u32);
+
+impl Features {
+    /// Messages carry authentication tags.
+    pub const AUTHENTICATED: Features = Features(1);
+    /// Large messages may be compressed.
+    pub const COMPRESSED: Features = Features(1 << 1);
+    /// Features a connection uses only if both ends offer them, rather than requiring a match.
+    pub const NEGOTIATED: Features = Features::COMPRESSED;
+
+    pub fn contains(self, other: Features) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// The application's part.
+    pub fn app(self) -> u16 {
+        (self.0 >> 16) as u16
+    }
+}
+
+impl std::ops::BitOr for Features {
+    type Output = Features;
+    fn bitor(self, other: Features) -> Features {
+        Features(self.0 | other.0)
+    }
+}
+
+impl Display for Features {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut names = Vec::new();
+        if self.contains(Features::AUTHENTICATED) {
+            names.push("authenticated".to_string());
+        }
+        if self.contains(Features::COMPRESSED) {
+            names.push("compressed".to_string());
+        }
+        let unknown = self.0 & 0xffff & !(Features::AUTHENTICATED | Features::COMPRESSED).0;
+        if unknown != 0 {
+            names.push(format!("unknown {:#x}", unknown));
+        }
+        names.push(format!("app {:#x}", self.app()));
+        write!(f, "{{{}}}", names.join(", "))
+    }
+}
+
+/// Declare options of the application (e.g., which secret sharing it uses) that all parties must
+/// agree on. Call before connecting.
+pub fn set_app_features(bits: u16) {
+    APP_FEATURES.store(bits, Ordering::SeqCst);
+}
+
+/// What a party announces on each connection, before anything else.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hello {
+    pub version: u16,
+    pub features: Features,
+}
+
+impl Hello {
+    /// Our hello, with this crate's `features` and the application's.
+    pub fn ours(features: Features) -> Self {
+        let app = (APP_FEATURES.load(Ordering::SeqCst) as u32) << 16;
+        Hello {
+            version: PROTOCOL_VERSION,
+            features: Features(features.0 & 0xffff | app),
+        }
+    }
+
+    fn to_bytes(self) -> [u8; HELLO_LEN] {
+        let mut b = [0u8; HELLO_LEN];
+        b[..4].copy_from_slice(MAGIC);
+        b[4..6].copy_from_slice(&self.version.to_le_bytes());
+        b[6..].copy_from_slice(&self.features.0.to_le_bytes());
+        b
+    }
+
+    fn from_bytes(b: &[u8; HELLO_LEN]) -> Option<Self> {
+        let mut version = [0u8; 2];
+        let mut features = [0u8; 4];
+        version.copy_from_slice(&b[4..6]);
+        features.copy_from_slice(&b[6..]);
+        if &b[..4] == MAGIC {
+            Some(Hello {
+                version: u16::from_le_bytes(version),
+                features: Features(u32::from_le_bytes(features)),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for Hello {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "version {} with features {}",
+            self.version, self.features
+        )
+    }
+}
+
+/// A peer that does not speak our version of the protocol, with our features.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HelloMismatch {
+    pub party: usize,
+    pub ours: Hello,
+    /// The peer's hello, or `None` if it sent none in time.
+    pub theirs: Option<Hello>,
+}
+
+impl Display for HelloMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.theirs {
+            Some(h) => write!(
+                f,
+                "party {} runs protocol {}, but we run {}",
+                self.party, h, self.ours
+            ),
+            None => write!(
+                f,
+                "party {} sent no protocol hello (is it an older build?); we run {}",
+                self.party, self.ours
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HelloMismatch {}
+
+/// Send `ours` to `party` over `stream`, and check that it sends a compatible hello back: the
+/// same, up to [negotiated](Features::NEGOTIATED) features. Returns the peer's hello.
+pub fn exchange_hello(
+    stream: &mut TcpStream,
+    party: usize,
+    ours: Hello,
+) -> io::Result<Result<Hello, HelloMismatch>> {
+    stream.write_all(&ours.to_bytes())?;
+    let mut theirs = [0u8; HELLO_LEN];
+    stream.set_read_timeout(Some(HELLO_TIMEOUT))?;
+    let read = stream.read_exact(&mut theirs);
+    stream.set_read_timeout(None)?;
+    let theirs = match read {
+        Ok(()) => Hello::from_bytes(&theirs),
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    let required = |h: Hello| (h.version, h.features.0 & !Features::NEGOTIATED.0);
+    Ok(match theirs {
+        Some(theirs) if required(theirs) == required(ours) => Ok(theirs),
+        _ => Err(HelloMismatch {
+            party,
+            ours,
+            theirs,
+        }),
+    })
+}
+
+
+/// A parameter on which some party disagrees with us.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParamMismatch {
+    pub party: usize,
+    /// The parameter, or `None` if the parties do not even have the same list of parameters.
+    pub param: Option<String>,
+    /// Our value of the parameter.
+    pub ours: Option<String>,
+}
+
+impl Display for ParamMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.param, &self.ours) {
+            (Some(p), Some(v)) => write!(
+                f,
+                "party {} has a different {} (ours is {:?})",
+                self.party, p, v
+            ),
+            _ => write!(f, "party {} has a different set of parameters", self.party),
+        }
+    }
+}
+
+impl std::error::Error for ParamMismatch {}
+
+fn digest(name: &str, value: &str) -> Vec<u8> {
+    Blake2s::new()
+        .chain(&(name.len() as u64).to_le_bytes())
+        .chain(name)
+        .chain(value)
+        .finalize()
+        .to_vec()
+}
+
+/// Check that every party has the same `params` (name and value pairs), in the same order.
+///
+/// The common case costs one round, of fixed size; naming the differing parameter takes another.
+pub fn check_params<N: MpcNet>(params: &[(&str, String)]) -> Result<(), ParamMismatch> {
+    let digests: Vec<Vec<u8>> = params.iter().map(|(n, v)| digest(n, v)).collect();
+    let mut summary = (params.len() as u64).to_le_bytes().to_vec();
+    summary.extend(Blake2s::digest(&digests.concat()));
+    let summaries = N::broadcast_bytes(&summary);
+    let party = match summaries.iter().position(|s| s != &summary) {
+        None => return Ok(()),
+        Some(party) => party,
+    };
+    // Everyone sees the same summaries, so everyone gets here; if all have the same number of
+    // parameters, all take part in comparing them one by one.
+    let lengths_agree = summaries.iter().all(|s| s[..8] == summary[..8]);
+    let whole = ParamMismatch {
+        party,
+        param: None,
+        ours: None,
+    };
+    if !lengths_agree {
+        return Err(whole);
+    }
+    let all_digests = N::broadcast_bytes(&digests.concat());
+    for (i, (name, value)) in params.iter().enumerate() {
+        let slot = i * DIGEST_LEN..(i + 1) * DIGEST_LEN;
+        if let Some(party) = all_digests
+            .iter()
+            .position(|d| d[slot.clone()] != digests[i][..])
+        {
+            return Err(ParamMismatch {
+                party,
+                param: Some(name.to_string()),
+                ours: Some(value.clone()),
+            });
+        }
+    }
+    Err(whole)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// Exchange `ours` with a peer that sends `theirs` bytes.
+    fn exchange(ours: Hello, theirs: [u8; HELLO_LEN]) -> Result<Hello, HelloMismatch> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = std::thread::spawn(move || {
+            let mut s = TcpStream::connect(addr).unwrap();
+            s.write_all(&theirs).unwrap();
+            s.read_exact(&mut [0u8; HELLO_LEN]).unwrap();
+        });
+        let (mut stream, _) = listener.accept().unwrap();
+        let r = exchange_hello(&mut stream, 1, ours).unwrap();
+        peer.join().unwrap();
+        r
+    }
+
+    #[test]
+    fn hellos_must_match() {
+        let plain = Hello::ours(Features::default());
+        let authed = Hello::ours(Features::AUTHENTICATED);
+        assert_eq!(exchange(plain, plain.to_bytes()), Ok(plain));
+        let compressed = Hello::ours(Features::COMPRESSED);
+        assert_eq!(exchange(plain, compressed.to_bytes()), Ok(compressed));
+        let e = exchange(plain, authed.to_bytes()).unwrap_err();
+        assert_eq!(e.theirs, Some(authed));
+        assert!(e.to_string().contains("{authenticated, app 0x0}"));
+        let old = Hello {
+            version: PROTOCOL_VERSION - 1,
+            ..plain
+        };
+        assert_eq!(
+            exchange(plain, old.to_bytes()).unwrap_err().theirs,
+            Some(old)
+        );
+        let e = exchange(plain, [0u8; HELLO_LEN]).unwrap_err();
+        assert_eq!(e.theirs, None);
+    }
+}
@@ -0,0 +1,161 @@
+//! A histogram of message sizes, one per communication round, in constant space.
+//!
+//! Totals of bytes and rounds do not tell a phase that waits on many small messages (where
+//! batching rounds helps) from one that moves a few large ones (where compressing or shrinking
+//! them does). The sizes of a phase's messages do: [SizeHistogram] keeps them in power-of-two
+//! buckets, so that recording one is a single increment however long the run, and quantiles are
+//! known to within a factor of two.
+use std::fmt;
+
+/// Buckets for sizes `0..=1`, `2`, `3..=4`, ..., `2^63 + 1..=2^64`.
+const BUCKETS: usize = 65;
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct SizeHistogram {
+    /// `counts[i]` sizes are at most `2^i`, and (for `i > 0`) above `2^(i - 1)`.
+    counts: [u64; BUCKETS],
+    sum: u64,
+    max: usize,
+}
+
+impl Default for SizeHistogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; BUCKETS],
+            sum: 0,
+            max: 0,
+        }
+    }
+}
+
+fn bucket(size: usize) -> usize {
+    (usize::BITS - size.saturating_sub(1).leading_zeros()) as usize
+}
+
+impl SizeHistogram {
+    /// Record a message of `size` bytes.
+    pub fn record(&mut self, size: usize) {
+        self.counts[bucket(size)] += 1;
+        self.sum += size as u64;
+        self.max = self.max.max(size);
+    }
+
+    /// Messages recorded.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The bytes in all messages recorded.
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    /// The largest message recorded, or 0.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// An upper bound on the `q`-quantile (`0 < q <= 1`) of the sizes recorded, at most twice it
+    /// (and never above [SizeHistogram::max]); 0 if none were.
+    pub fn quantile(&self, q: f64) -> usize {
+        let rank = (q * self.count() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, c) in self.counts.iter().enumerate() {
+            seen += c;
+            if seen >= rank {
+                return 1usize
+                    .checked_shl(i as u32)
+                    .unwrap_or(usize::MAX)
+                    .min(self.max);
+            }
+        }
+        0
+    }
+
+    /// The messages recorded since this histogram was `before`. Their largest is only known to
+    /// within its bucket, so [SizeHistogram::max] is an upper bound, as the quantiles are.
+    pub fn since(&self, before: &SizeHistogram) -> SizeHistogram {
+        let mut counts = [0; BUCKETS];
+        for (i, c) in counts.iter_mut().enumerate() {
+            *c = self.counts[i].saturating_sub(before.counts[i]);
+        }
+        let max = match counts.iter().rposition(|&c| c > 0) {
+            Some(i) => self
+                .max
+                .min(1usize.checked_shl(i as u32).unwrap_or(usize::MAX)),
+            None => 0,
+        };
+        SizeHistogram {
+            counts,
+            sum: self.sum.saturating_sub(before.sum),
+            max,
+        }
+    }
+
+    /// Add the messages recorded in `other`.
+    pub fn merge(&mut self, other: &SizeHistogram) {
+        for (c, o) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *c += o;
+        }
+        self.sum += other.sum;
+        self.max = self.max.max(other.max);
+    }
+
+    /// Messages recorded of at most `2^i` bytes, for each `i` up to the largest message's
+    /// bucket: the cumulative buckets of a Prometheus histogram.
+    pub fn cumulative(&self) -> Vec<(usize, u64)> {
+        let mut seen = 0;
+        (0..=bucket(self.max))
+            .map(|i| {
+                seen += self.counts[i];
+                (1usize.checked_shl(i as u32).unwrap_or(usize::MAX), seen)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for SizeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SizeHistogram")
+            .field("rounds", &self.count())
+            .field("p50", &self.quantile(0.5))
+            .field("p95", &self.quantile(0.95))
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantiles_within_a_factor_of_two() {
+        let mut h = SizeHistogram::default();
+        assert_eq!((h.count(), h.quantile(0.5), h.max()), (0, 0, 0));
+        // 90 rounds of 32-byte messages, and 10 of 1000-byte ones.
+        (0..90).for_each(|_| h.record(32));
+        (0..10).for_each(|_| h.record(1000));
+        assert_eq!((h.count(), h.sum()), (100, 90 * 32 + 10 * 1000));
+        assert_eq!(h.quantile(0.5), 32);
+        assert_eq!(h.quantile(0.9), 32);
+        assert_eq!(h.quantile(0.95), 1000);
+        assert_eq!(h.max(), 1000);
+        assert_eq!(h.cumulative().last(), Some(&(1024, 100)));
+        assert_eq!(h.cumulative()[5], (32, 90));
+
+        h.record(33);
+        assert_eq!(h.quantile(0.95), 1000);
+        assert!(h.quantile(1.0) >= 1000);
+        assert_eq!((bucket(0), bucket(1), bucket(2), bucket(3)), (0, 0, 1, 2));
+        assert_eq!(bucket(usize::MAX), 64);
+
+        let before = h.clone();
+        h.record(8);
+        h.record(100);
+        let mut since = h.since(&before);
+        assert_eq!((since.count(), since.sum(), since.max()), (2, 108, 128));
+        since.merge(&before);
+        assert_eq!((since.count(), since.max()), (h.count(), 1000));
+    }
+}
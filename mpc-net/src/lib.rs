@@ -1,9 +1,45 @@
+pub mod addr;
+pub mod faults;
+pub mod handshake;
+pub mod histogram;
+pub mod memory;
+pub mod metrics;
 pub mod multi;
+pub mod rendezvous;
+pub mod stream;
+pub mod testing;
+pub mod timer;
 pub mod two;
 
 pub use two::MpcTwoNet;
 pub use multi::MpcMultiNet;
 
+/// Run computation on `n` threads of rayon's global pool, rather than one per core; call before
+/// connecting, which already runs on the pool. This covers FFTs and multi-scalar multiplications (in builds with the
+/// `parallel` features), and messages to peers too, unless [MpcMultiNet::set_net_threads] gives
+/// them threads of their own.
+pub fn set_compute_threads(n: usize) {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(n)
+        .build_global()
+        .unwrap_or_else(|e| panic!("Could not start {} compute threads: {}", n, e));
+}
+
+static SELF_CHECKS: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(cfg!(debug_assertions));
+
+/// Whether the protocol checks itself as it goes, by opening extra values: that the parties agree
+/// on each value they open, and that each product opens to the product of its operands. On by
+/// default in debug builds only, since the checks communicate.
+pub fn self_checks() -> bool {
+    SELF_CHECKS.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// Turn [self_checks] on or off, returning whether they were on. All parties must choose the same.
+pub fn set_self_checks(on: bool) -> bool {
+    SELF_CHECKS.swap(on, std::sync::atomic::Ordering::Relaxed)
+}
+
 #[derive(Clone, Debug)]
 pub struct Stats {
     pub bytes_sent: usize,
@@ -11,6 +47,20 @@ pub struct Stats {
     pub broadcasts: usize,
     pub to_king: usize,
     pub from_king: usize,
+    /// Messages sent to or received from a single peer (see [MpcNet::send_bytes_to_peer]).
+    pub peer_msgs: usize,
+    /// Parties that dropped out; if any did, the run was degraded.
+    pub dropouts: Vec<usize>,
+    /// Bytes (of those sent) that went out compressed.
+    pub compressed_raw: usize,
+    /// What those bytes compressed to.
+    pub compressed_sent: usize,
+    /// How much each [metrics] counter (e.g., of Beaver triples consumed, or of values opened)
+    /// grew, for those that did.
+    pub counters: metrics::Counts,
+    /// The size of the message in each round, in bytes: the one this party sent or received
+    /// (without length prefixes).
+    pub round_sizes: histogram::SizeHistogram,
 }
 
 impl std::default::Default for Stats {
@@ -21,6 +71,12 @@ impl std::default::Default for Stats {
             broadcasts: 0,
             to_king: 0,
             from_king: 0,
+            peer_msgs: 0,
+            dropouts: Vec::new(),
+            compressed_raw: 0,
+            compressed_sent: 0,
+            counters: metrics::Counts::new(),
+            round_sizes: histogram::SizeHistogram::default(),
         }
     }
 }
@@ -45,17 +101,80 @@ pub trait MpcNet {
     fn is_init() -> bool;
     /// Uninitialize the network layer, closing all connections.
     fn deinit();
+    /// An id of the current session: the same at every party, and fresh each time the parties
+    /// connect. Hashing it into a commitment or MAC ties that to this session, so it cannot be
+    /// replayed in another. Networks without sessions return zeros.
+    #[inline]
+    fn session_id() -> [u8; 32] {
+        [0; 32]
+    }
     /// Set statistics to zero.
     fn reset_stats();
     /// Get statistics.
     fn stats() -> Stats;
     /// All parties send bytes to each other.
     fn broadcast_bytes(bytes: &[u8]) -> Vec<Vec<u8>>;
+    /// Like [MpcNet::broadcast_bytes], but receives into `bufs` (one per party), reusing their
+    /// allocations.
+    #[inline]
+    fn broadcast_bytes_into(bytes: &[u8], bufs: &mut Vec<Vec<u8>>) {
+        *bufs = Self::broadcast_bytes(bytes);
+    }
+    /// Like [MpcNet::broadcast_bytes], but parties may drop out, up to a configured limit; their
+    /// entries are `None`.
+    ///
+    /// Only rounds whose result can be recovered from the survivors should use this.
+    #[inline]
+    fn broadcast_bytes_surviving(bytes: &[u8]) -> Vec<Option<Vec<u8>>> {
+        Self::broadcast_bytes(bytes).into_iter().map(Some).collect()
+    }
     /// All parties send bytes to the king.
     fn send_bytes_to_king(bytes: &[u8]) -> Option<Vec<Vec<u8>>>;
+    /// Like [MpcNet::send_bytes_to_king], but parties other than the king may drop out, as in
+    /// [MpcNet::broadcast_bytes_surviving].
+    #[inline]
+    fn send_bytes_to_king_surviving(bytes: &[u8]) -> Option<Vec<Option<Vec<u8>>>> {
+        Self::send_bytes_to_king(bytes).map(|v| v.into_iter().map(Some).collect())
+    }
     /// All parties recv bytes from the king.
     /// Provide bytes iff you're the king!
     fn recv_bytes_from_king(bytes: Option<Vec<Vec<u8>>>) -> Vec<u8>;
+    /// All parties send bytes to `party`, as [MpcNet::send_bytes_to_king] does to the king.
+    fn send_bytes_to(party: usize, bytes: &[u8]) -> Option<Vec<Vec<u8>>>;
+    /// All parties recv bytes from `party`, as [MpcNet::recv_bytes_from_king] does from the king.
+    /// Provide bytes iff you're `party`!
+    fn recv_bytes_from(party: usize, bytes: Option<Vec<Vec<u8>>>) -> Vec<u8>;
+    /// Send bytes to `party` alone, who must receive them with [MpcNet::recv_bytes_from_peer].
+    /// Unlike the calls above, only these two parties take part.
+    fn send_bytes_to_peer(party: usize, bytes: &[u8]);
+    /// Receive the bytes `party` sent with [MpcNet::send_bytes_to_peer].
+    fn recv_bytes_from_peer(party: usize) -> Vec<u8>;
+
+    /// Combine all parties' bytes with `combine`, which must be associative, and give everyone
+    /// the result.
+    ///
+    /// The bytes go up a binary tree to the king, who sends back the result: that is
+    /// `ceil(log2(n))` rounds of single messages, then one from the king, rather than the king
+    /// receiving from every party at once.
+    fn reduce_bytes(bytes: &[u8], combine: impl Fn(Vec<u8>, Vec<u8>) -> Vec<u8>) -> Vec<u8> {
+        let (n, me) = (Self::n_parties(), Self::party_id());
+        let mut acc = bytes.to_vec();
+        let mut step = 1;
+        while step < n {
+            if me % (2 * step) == step {
+                Self::send_bytes_to_peer(me - step, &acc);
+                break;
+            } else if me + step < n {
+                acc = combine(acc, Self::recv_bytes_from_peer(me + step));
+            }
+            step *= 2;
+        }
+        Self::recv_bytes_from_king(if Self::am_king() {
+            Some(vec![acc; n])
+        } else {
+            None
+        })
+    }
 
     /// Everyone sends bytes to the king, who recieves those bytes, runs a computation on them, and
     /// redistributes the resulting bytes.
@@ -0,0 +1,97 @@
+//! Memory accounting for timed sections.
+//!
+//! A binary that installs [CountingAlloc] as its `#[global_allocator]` gets exact live and peak
+//! heap usage; [MpcTimer](crate::timer::MpcTimer) then records the peak within each section.
+//! Otherwise, sections fall back to sampling the resident set size when they end.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// The system allocator, counting live and peak bytes.
+pub struct CountingAlloc;
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc(layout);
+        if !p.is_null() {
+            grow(layout.size());
+        }
+        p
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let p = System.alloc_zeroed(layout);
+        if !p.is_null() {
+            grow(layout.size());
+        }
+        p
+    }
+    unsafe fn dealloc(&self, p: *mut u8, layout: Layout) {
+        System.dealloc(p, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+    unsafe fn realloc(&self, p: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let q = System.realloc(p, layout, new_size);
+        if !q.is_null() {
+            LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+            grow(new_size);
+        }
+        q
+    }
+}
+
+fn grow(n: usize) {
+    let live = LIVE.fetch_add(n, Ordering::Relaxed) + n;
+    PEAK.fetch_max(live, Ordering::Relaxed);
+    COUNTING.store(true, Ordering::Relaxed);
+}
+
+/// Live heap bytes, if [CountingAlloc] is installed.
+pub fn live() -> Option<usize> {
+    if COUNTING.load(Ordering::Relaxed) {
+        Some(LIVE.load(Ordering::Relaxed))
+    } else {
+        None
+    }
+}
+
+/// The resident set size, where the platform reports it.
+pub fn rss() -> Option<usize> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: usize = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+/// Start watching for a new peak, returning the enclosing one to pass to [end_peak].
+pub(crate) fn begin_peak() -> usize {
+    PEAK.swap(LIVE.load(Ordering::Relaxed), Ordering::Relaxed)
+}
+
+/// The peak since the matching [begin_peak], after which the enclosing peak is restored.
+///
+/// Without [CountingAlloc], this is just the current resident set size (or zero).
+pub(crate) fn end_peak(outer: usize) -> usize {
+    if COUNTING.load(Ordering::Relaxed) {
+        PEAK.fetch_max(outer, Ordering::Relaxed)
+    } else {
+        rss().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_peaks() {
+        let outer = begin_peak();
+        grow(100);
+        let inner = begin_peak();
+        grow(50);
+        LIVE.fetch_sub(150, Ordering::Relaxed);
+        assert_eq!(end_peak(inner), 150);
+        assert_eq!(end_peak(outer), 150);
+    }
+}
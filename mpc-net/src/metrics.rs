@@ -0,0 +1,227 @@
+//! Metrics in the Prometheus text format, optionally served over HTTP.
+//!
+//! [render] reports the network [Stats](crate::Stats) of [MpcMultiNet], each finished
+//! [timer](crate::timer) section (as a `phase` label), and any counters bumped with [inc] (e.g.,
+//! by the preprocessing layer, or by a proving service per proof). [serve] answers every HTTP
+//! request with [render], so that a party can be scraped while it runs.
+use lazy_static::lazy_static;
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::{timer, MpcMultiNet, MpcNet};
+
+/// Counter values, by name.
+pub type Counts = BTreeMap<&'static str, u64>;
+
+lazy_static! {
+    /// Counters by name, with their help text.
+    static ref COUNTERS: Mutex<BTreeMap<&'static str, (&'static str, u64)>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// Add `by` to the counter `name`, creating it (described by `help`) if need be.
+pub fn inc(name: &'static str, help: &'static str, by: u64) {
+    COUNTERS.lock().unwrap().entry(name).or_insert((help, 0)).1 += by;
+}
+
+/// The current value of counter `name`.
+pub fn get(name: &str) -> u64 {
+    COUNTERS.lock().unwrap().get(name).map_or(0, |c| c.1)
+}
+
+/// The current values of all counters.
+pub fn counters() -> Counts {
+    COUNTERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, (_, value))| (*name, *value))
+        .collect()
+}
+
+/// How much each counter grew from `before` to `after`, leaving out those that did not.
+pub fn diff(after: &Counts, before: &Counts) -> Counts {
+    after
+        .iter()
+        .map(|(name, v)| (*name, v.saturating_sub(*before.get(name).unwrap_or(&0))))
+        .filter(|(_, v)| *v > 0)
+        .collect()
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, String)]) {
+    writeln!(out, "# HELP {} {}", name, help).unwrap();
+    writeln!(out, "# TYPE {} {}", name, kind).unwrap();
+    for (labels, value) in samples {
+        writeln!(out, "{}{} {}", name, labels, value).unwrap();
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn phase_label(path: &str) -> String {
+    format!("{{phase=\"{}\"}}", escape(path))
+}
+
+/// All metrics, in the Prometheus text exposition format.
+pub fn render() -> String {
+    let mut out = String::new();
+    let plain = |v: usize| vec![(String::new(), v.to_string())];
+    {
+        let s = MpcMultiNet::stats();
+        let totals = [
+            ("mpc_bytes_sent_total", "counter", "Bytes sent.", s.bytes_sent),
+            ("mpc_bytes_received_total", "counter", "Bytes received.", s.bytes_recv),
+            (
+                "mpc_rounds_total",
+                "counter",
+                "Communication rounds.",
+                s.broadcasts + s.to_king + s.from_king + s.peer_msgs,
+            ),
+            ("mpc_dropouts", "gauge", "Parties that dropped out.", s.dropouts.len()),
+            (
+                "mpc_compressed_raw_bytes_total",
+                "counter",
+                "Bytes sent compressed, before compression.",
+                s.compressed_raw,
+            ),
+            (
+                "mpc_compressed_sent_bytes_total",
+                "counter",
+                "Bytes sent compressed, after compression.",
+                s.compressed_sent,
+            ),
+        ];
+        for (name, kind, help, v) in totals.iter() {
+            metric(&mut out, name, kind, help, &plain(*v));
+        }
+        let h = &s.round_sizes;
+        let mut samples: Vec<_> = h
+            .cumulative()
+            .into_iter()
+            .map(|(le, n)| (format!("_bucket{{le=\"{}\"}}", le), n.to_string()))
+            .collect();
+        samples.push(("_bucket{le=\"+Inf\"}".into(), h.count().to_string()));
+        samples.push(("_sum".into(), h.sum().to_string()));
+        samples.push(("_count".into(), h.count().to_string()));
+        let help = "The size of the message in each round, in bytes.";
+        metric(&mut out, "mpc_round_message_bytes", "histogram", help, &samples);
+    }
+    for (name, (help, value)) in COUNTERS.lock().unwrap().iter() {
+        metric(&mut out, name, "counter", help, &plain(*value as usize));
+    }
+    let phases = timer::phases();
+    let per_phase: [(&str, &str, fn(&timer::Phase) -> String); 4] = [
+        ("mpc_phase_seconds_total", "Wall time in each timed section.", |p| {
+            p.time.as_secs_f64().to_string()
+        }),
+        ("mpc_phase_calls_total", "Runs of each timed section.", |p| {
+            p.calls.to_string()
+        }),
+        ("mpc_phase_bytes_total", "Bytes communicated in each timed section.", |p| {
+            p.bytes.to_string()
+        }),
+        ("mpc_phase_rounds_total", "Rounds in each timed section.", |p| {
+            p.rounds.to_string()
+        }),
+    ];
+    if !phases.is_empty() {
+        for (name, help, value) in per_phase.iter() {
+            let samples: Vec<_> = phases
+                .iter()
+                .map(|p| (phase_label(&p.path), value(p)))
+                .collect();
+            metric(&mut out, name, "counter", help, &samples);
+        }
+        let samples: Vec<_> = phases
+            .iter()
+            .flat_map(|p| {
+                p.counters.iter().map(move |(counter, v)| {
+                    let path = escape(&p.path);
+                    let labels = format!("{{phase=\"{}\",counter=\"{}\"}}", path, counter);
+                    (labels, v.to_string())
+                })
+            })
+            .collect();
+        if !samples.is_empty() {
+            let help = "Growth of each counter within each timed section.";
+            metric(&mut out, "mpc_phase_counter_total", "counter", help, &samples);
+        }
+    }
+    out
+}
+
+/// How long [serve] waits on a client, which holds up the clients after it.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Serve [render] over HTTP at `addr`, from a background thread; returns the bound address.
+pub fn serve(addr: SocketAddr) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let bound = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            // Clients are served in turn, so a silent one must not stall the rest.
+            let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+            let _ = stream.set_write_timeout(Some(CLIENT_TIMEOUT));
+            // Any request gets the metrics; only read enough of it to be polite.
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let body = render();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\
+                 Connection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        }
+    });
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_render() {
+        inc("test_things_total", "Things.", 2);
+        inc("test_things_total", "Things.", 3);
+        assert_eq!(get("test_things_total"), 5);
+        let out = render();
+        assert!(out.contains("# TYPE test_things_total counter\ntest_things_total 5\n"));
+    }
+
+    #[test]
+    fn counter_growth() {
+        let before: Counts = vec![("a", 1), ("b", 2)].into_iter().collect();
+        let after: Counts = vec![("a", 1), ("b", 5), ("c", 1)].into_iter().collect();
+        let grown: Counts = vec![("b", 3), ("c", 1)].into_iter().collect();
+        assert_eq!(diff(&after, &before), grown);
+    }
+
+    #[test]
+    fn silent_clients_do_not_stall_scrapes() {
+        use std::net::TcpStream;
+        let addr = serve("127.0.0.1:0".parse().unwrap()).unwrap();
+        let _silent = TcpStream::connect(addr).unwrap();
+        let mut scrape = TcpStream::connect(addr).unwrap();
+        scrape.set_read_timeout(Some(CLIENT_TIMEOUT * 10)).unwrap();
+        scrape.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        scrape.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+    }
+}
@@ -1,18 +1,38 @@
+use blake2::crypto_mac::{Mac, NewMac};
+use blake2::Blake2s;
 use lazy_static::lazy_static;
-use log::debug;
+use log::{debug, warn};
 use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
-use std::sync::Mutex;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use ark_std::{end_timer, start_timer};
 
-use super::{MpcNet, Stats};
+use super::addr::{self, HostPort};
+use super::faults::{self, Outgoing};
+use super::handshake::{self, Features, Hello};
+use super::{metrics, rendezvous, timer, MpcNet, Stats};
 
 #[macro_use]
 lazy_static! {
     static ref CONNECTIONS: Mutex<Connections> = Mutex::new(Connections::default());
+    /// The [metrics] counters when the stats were last reset; [Stats::counters] counts from there.
+    static ref COUNTERS_AT_RESET: Mutex<metrics::Counts> = Mutex::new(metrics::Counts::new());
+}
+
+thread_local! {
+    /// The lanes this thread's traffic is multiplexed over, and its own lane, inside
+    /// [MpcMultiNet::run_lanes].
+    static LANE: RefCell<Option<(Arc<Lanes>, u32)>> = RefCell::new(None);
 }
 
 /// Macro for locking the FieldChannel singleton in the current scope.
@@ -22,11 +42,51 @@ macro_rules! get_ch {
     };
 }
 
+/// A party's message-authentication key.
+type Key = [u8; 32];
+
+/// Length of the tag appended to each authenticated message.
+const TAG_LEN: usize = 32;
+
+/// A session's id (see [MpcNet::session_id]), and the random value each party contributes to it.
+type SessionId = [u8; 32];
+type Nonce = [u8; 32];
+
+/// Messages shorter than this are not worth compressing.
+const COMPRESS_MIN: usize = 4096;
+
+/// Marks the length of a compressed message.
+const COMPRESSED: u64 = 1 << 63;
+
+/// The largest message of unknown length accepted from a peer, unless set otherwise (see
+/// [MpcMultiNet::set_max_message]).
+const MAX_MESSAGE: usize = 1 << 30;
+
+/// Bytes sent compressed, before and after compression. Peers send concurrently, so these are
+/// kept apart from [Stats].
+static COMPRESSED_RAW: AtomicUsize = AtomicUsize::new(0);
+static COMPRESSED_SENT: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug)]
 struct Peer {
     id: usize,
-    addr: SocketAddr,
+    /// Where this peer is reached (see [crate::addr]).
+    addr: HostPort,
     stream: Option<TcpStream>,
+    /// The key this party tags its messages with, if authentication is on.
+    key: Option<Key>,
+    /// This party's contribution to the current session's id, once connected.
+    nonce: Option<Nonce>,
+    /// The key this party tags its messages with in the current session, derived from `key`.
+    session_key: Option<Key>,
+    /// Messages sent to this peer.
+    sent: u64,
+    /// Messages received from this peer.
+    recvd: u64,
+    /// Whether this peer has dropped out; it is not contacted again.
+    dropped: bool,
+    /// Whether large messages to and from this peer are compressed.
+    compress: bool,
 }
 
 #[derive(Default, Debug)]
@@ -34,20 +94,314 @@ struct Connections {
     id: usize,
     peers: Vec<Peer>,
     stats: Stats,
+    /// How many peers may drop out before the protocol aborts.
+    max_dropouts: usize,
+    /// This party's listener, if it was bound before connecting (see [Connections::rendezvous]).
+    listener: Option<TcpListener>,
+    /// Where to listen, if not at this party's own address (see [addr::listen]).
+    bind: Option<SocketAddr>,
+    /// Whether to offer compression to peers.
+    compress: bool,
+    /// The largest message of unknown length to accept from a peer, if not [MAX_MESSAGE].
+    max_message: Option<usize>,
+    /// How long to wait for a peer's message before taking it to have dropped out.
+    timeout: Option<Duration>,
+    /// When the protocol must be done by, after which waiting for any peer aborts it.
+    deadline: Option<Instant>,
+    /// The threads that exchange messages with peers, if not rayon's global pool.
+    net_pool: Option<Arc<ThreadPool>>,
+    /// The current session's id, once connected.
+    session: Option<SessionId>,
 }
 
 impl std::default::Default for Peer {
     fn default() -> Self {
         Self {
             id: 0,
-            addr: "127.0.0.1:8000".parse().unwrap(),
+            addr: HostPort {
+                host: "127.0.0.1".to_owned(),
+                port: 8000,
+            },
             stream: None,
+            key: None,
+            nonce: None,
+            session_key: None,
+            sent: 0,
+            recvd: 0,
+            dropped: false,
+            compress: false,
+        }
+    }
+}
+
+fn parse_key(s: &str) -> Key {
+    let bytes = s.as_bytes();
+    if bytes.len() != 2 * TAG_LEN || !s.chars().all(|c| c.is_ascii_hexdigit()) {
+        panic!("bad key (expected {} hex digits): {}", 2 * TAG_LEN, s);
+    }
+    let mut key = [0u8; 32];
+    for (i, k) in key.iter_mut().enumerate() {
+        *k = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    key
+}
+
+/// A fresh session's id: a digest of the parties' `nonces`, in order.
+fn session_id(nonces: &[Nonce]) -> SessionId {
+    use blake2::Digest;
+    let mut transcript = b"mpc-net session".to_vec();
+    transcript.extend_from_slice(&handshake::PROTOCOL_VERSION.to_le_bytes());
+    transcript.extend_from_slice(&(nonces.len() as u64).to_le_bytes());
+    for n in nonces {
+        transcript.extend_from_slice(n);
+    }
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&Blake2s::digest(&transcript));
+    id
+}
+
+/// The key that a party with long-term key `key` tags its messages with in session `session`.
+///
+/// Each session has keys of its own, so a message recorded in one session fails authentication in
+/// any other, even at the same place in the protocol.
+fn session_key(key: &Key, session: &SessionId) -> Key {
+    let mut mac = Blake2s::new_varkey(key).unwrap();
+    mac.update(b"mpc-net session key");
+    mac.update(session);
+    let mut k = [0u8; 32];
+    k.copy_from_slice(&mac.finalize().into_bytes());
+    k
+}
+
+/// MAC over a message from `from` to `to`, the `seq`-th on that link.
+///
+/// Binding the endpoints and sequence number prevents reflection, reordering and replay within a
+/// session; the key, which is the session's (see [session_key]), prevents replay across them.
+fn tag(key: &Key, from: usize, to: usize, seq: u64, parts: &[&[u8]]) -> Blake2s {
+    let mut mac = Blake2s::new_varkey(key).unwrap();
+    mac.update(&(from as u64).to_le_bytes());
+    mac.update(&(to as u64).to_le_bytes());
+    mac.update(&seq.to_le_bytes());
+    for p in parts {
+        mac.update(p);
+    }
+    mac
+}
+
+/// The concatenation of `parts`, compressed, if that is worthwhile.
+fn compress(parts: &[&[u8]]) -> Option<Vec<u8>> {
+    let len: usize = parts.iter().map(|p| p.len()).sum();
+    if len < COMPRESS_MIN {
+        return None;
+    }
+    let compressed = lz4_flex::compress_prepend_size(&parts.concat());
+    if compressed.len() >= len {
+        return None;
+    }
+    COMPRESSED_RAW.fetch_add(len, Ordering::Relaxed);
+    COMPRESSED_SENT.fetch_add(compressed.len(), Ordering::Relaxed);
+    Some(compressed)
+}
+
+/// Decompress `bytes` into `out`, which the message must fill exactly.
+fn decompress(bytes: &[u8], out: &mut [u8]) -> io::Result<()> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let (size, block) =
+        lz4_flex::block::uncompressed_size(bytes).map_err(|e| invalid(e.to_string()))?;
+    if size != out.len() {
+        return Err(invalid(format!(
+            "compressed message of {} bytes, expected {}",
+            size,
+            out.len()
+        )));
+    }
+    match lz4_flex::block::decompress_into(block, out) {
+        Ok(n) if n == out.len() => Ok(()),
+        Ok(n) => Err(invalid(format!(
+            "compressed message of {} bytes, expected {}",
+            n,
+            out.len()
+        ))),
+        Err(e) => Err(invalid(e.to_string())),
+    }
+}
+
+/// The uncompressed length that a compressed message claims.
+fn decompressed_len(bytes: &[u8]) -> io::Result<usize> {
+    lz4_flex::block::uncompressed_size(bytes)
+        .map(|(size, _)| size)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+/// Refuse a message of `len` bytes if it is longer than `max`.
+fn check_len(len: u64, max: usize) -> io::Result<usize> {
+    if len > max as u64 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message of {} bytes, more than the {} allowed", len, max),
+        ));
+    }
+    Ok(len as usize)
+}
+
+impl Peer {
+    /// Send a message to this peer, tagged with `key` if authentication is on.
+    ///
+    /// With compression on, the message is preceded by its compressed length, or by zero if it
+    /// is sent as is.
+    fn send(&mut self, own_id: usize, key: Option<&Key>, parts: &[&[u8]]) -> io::Result<()> {
+        let corrupted;
+        let sent_parts: Vec<&[u8]> = match faults::outgoing(parts) {
+            Outgoing::Intact => parts.to_vec(),
+            Outgoing::Dropped => {
+                self.sent += 1;
+                return Ok(());
+            }
+            Outgoing::Corrupted(c) => {
+                corrupted = c;
+                corrupted.iter().map(|p| &p[..]).collect()
+            }
+        };
+        let stream = self.stream.as_mut().unwrap();
+        let compressed = if self.compress { compress(&sent_parts) } else { None };
+        match compressed {
+            Some(c) => {
+                stream.write_all(&(c.len() as u64 | COMPRESSED).to_le_bytes())?;
+                stream.write_all(&c)?;
+            }
+            None => {
+                if self.compress {
+                    stream.write_all(&0u64.to_le_bytes())?;
+                }
+                for p in &sent_parts {
+                    stream.write_all(p)?;
+                }
+            }
+        }
+        if let Some(key) = key {
+            let t = tag(key, own_id, self.id, self.sent, parts);
+            stream.write_all(&t.finalize().into_bytes())?;
+        }
+        self.sent += 1;
+        Ok(())
+    }
+    /// With compression on, read the header of a message of at most `max` bytes from this peer,
+    /// and the message, still compressed, if it was compressed. `None` means that the message
+    /// follows as is.
+    ///
+    /// Nothing here is authenticated yet, so a compressed length that could not come from such a
+    /// message is refused before anything is allocated for it.
+    fn read_compressed(&mut self, max: usize) -> io::Result<Option<Vec<u8>>> {
+        if !self.compress {
+            return Ok(None);
+        }
+        let stream = self.stream.as_mut().unwrap();
+        let mut header = [0u8; 8];
+        stream.read_exact(&mut header)?;
+        let header = u64::from_le_bytes(header);
+        if header & COMPRESSED == 0 {
+            return Ok(None);
+        }
+        let len = check_len(
+            header & !COMPRESSED,
+            lz4_flex::block::get_maximum_output_size(max),
+        )?;
+        let mut compressed = vec![0u8; len];
+        stream.read_exact(&mut compressed)?;
+        Ok(Some(compressed))
+    }
+    /// Receive a message from this peer, filling `bufs`.
+    fn recv(&mut self, own_id: usize, bufs: &mut [&mut [u8]]) -> io::Result<()> {
+        let len = bufs.iter().map(|b| b.len()).sum::<usize>();
+        match self.read_compressed(len)? {
+            Some(compressed) => match bufs {
+                [b] => decompress(&compressed, b)?,
+                _ => {
+                    let mut bytes = vec![0u8; len];
+                    decompress(&compressed, &mut bytes)?;
+                    let mut rest = &bytes[..];
+                    for b in bufs.iter_mut() {
+                        let (head, tail) = rest.split_at(b.len());
+                        b.copy_from_slice(head);
+                        rest = tail;
+                    }
+                }
+            },
+            None => {
+                let stream = self.stream.as_mut().unwrap();
+                for b in bufs.iter_mut() {
+                    stream.read_exact(b)?;
+                }
+            }
+        }
+        let parts: Vec<&[u8]> = bufs.iter().map(|b| &b[..]).collect();
+        self.check(own_id, &parts)
+    }
+    /// Receive a message of unknown length, at most `max` bytes, from this peer, sent as its
+    /// length and then its bytes.
+    fn recv_sized(&mut self, own_id: usize, max: usize) -> io::Result<Vec<u8>> {
+        let (size, bytes) = match self.read_compressed(8 + max)? {
+            Some(compressed) => {
+                let len = decompressed_len(&compressed)?;
+                if len < 8 {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                let mut size = vec![0u8; check_len(len as u64, 8 + max)?];
+                decompress(&compressed, &mut size)?;
+                let bytes = size.split_off(8);
+                (size, bytes)
+            }
+            None => {
+                let stream = self.stream.as_mut().unwrap();
+                let mut size = vec![0u8; 8];
+                stream.read_exact(&mut size)?;
+                let mut m = [0u8; 8];
+                m.copy_from_slice(&size);
+                let mut bytes = vec![0u8; check_len(u64::from_le_bytes(m), max)?];
+                stream.read_exact(&mut bytes)?;
+                (size, bytes)
+            }
+        };
+        self.check(own_id, &[&size, &bytes])?;
+        Ok(bytes)
+    }
+    /// Read and check the tag on the message just received from this peer.
+    ///
+    /// If authentication is on and the tag does not verify, the protocol aborts.
+    fn check(&mut self, own_id: usize, parts: &[&[u8]]) -> io::Result<()> {
+        if let Some(key) = self.session_key.as_ref() {
+            let mut t = [0u8; TAG_LEN];
+            self.stream.as_mut().unwrap().read_exact(&mut t)?;
+            if tag(key, self.id, own_id, self.recvd, parts)
+                .verify(&t)
+                .is_err()
+            {
+                panic!(
+                    "Protocol abort: message {} from party {} failed authentication",
+                    self.recvd, self.id
+                );
+            }
+        }
+        self.recvd += 1;
+        Ok(())
+    }
+    /// Fail fast if this peer has already dropped out.
+    fn live(&self) -> io::Result<()> {
+        if self.dropped {
+            Err(io::ErrorKind::NotConnected.into())
+        } else {
+            Ok(())
         }
     }
 }
 
 impl Connections {
     /// Given a path and the `id` of oneself, initialize the structure
+    ///
+    /// Each line holds a party's `HOST:PORT` (see [crate::addr]), optionally followed by that
+    /// party's 64-hex-digit message-authentication key. If any party has a key, all must, and
+    /// every message is then tagged by its sender and checked by its receiver, under keys derived
+    /// afresh for each session.
     fn init_from_path(&mut self, path: &str, id: usize) {
         let f = BufReader::new(File::open(path).expect("host configuration path"));
         let mut peer_id = 0;
@@ -55,33 +409,86 @@ impl Connections {
             let line = line.unwrap();
             let trimmed = line.trim();
             if trimmed.len() > 0 {
-                let addr: SocketAddr = trimmed
+                let mut fields = trimmed.split_whitespace();
+                let addr_str = fields.next().unwrap();
+                let addr: HostPort = addr_str
                     .parse()
-                    .unwrap_or_else(|e| panic!("bad socket address: {}:\n{}", trimmed, e));
+                    .unwrap_or_else(|e| panic!("bad host address: {}:\n{}", addr_str, e));
                 let peer = Peer {
                     id: peer_id,
                     addr,
-                    stream: None,
+                    key: fields.next().map(parse_key),
+                    ..Peer::default()
                 };
                 self.peers.push(peer);
                 peer_id += 1;
             }
         }
         assert!(id < self.peers.len());
+        let n_keys = self.peers.iter().filter(|p| p.key.is_some()).count();
+        assert!(
+            n_keys == 0 || n_keys == self.peers.len(),
+            "either all parties or none must have keys"
+        );
         self.id = id;
     }
+    /// Fill in the addresses of parties with port 0 from the rendezvous file at `path`, first
+    /// listening on a free port and registering it there, if this party has port 0.
+    fn rendezvous(&mut self, path: &Path) {
+        let timer = start_timer!(|| "Rendezvous");
+        // The king only connects, so it needs no port.
+        if self.id > 0 && self.peers[self.id].addr.port == 0 {
+            let listener = self.listen();
+            // Peers reach us at our own address, on whichever port we got.
+            let addr = self.peers[self.id]
+                .addr
+                .with_port(listener.local_addr().unwrap().port());
+            debug!("Listening at {}", addr);
+            rendezvous::publish(path, self.id, addr.clone());
+            self.peers[self.id].addr = addr;
+            self.listener = Some(listener);
+        }
+        // Only parties after this one are contacted by it.
+        let wanted: Vec<usize> = self.peers[self.id + 1..]
+            .iter()
+            .filter(|p| p.addr.port == 0)
+            .map(|p| p.id)
+            .collect();
+        let found = rendezvous::wait_for(path, &wanted);
+        for id in wanted {
+            self.peers[id].addr = found[&id].clone();
+        }
+        end_timer!(timer);
+    }
+    /// Listen at this party's address, or at the bind address, if one is set.
+    fn listen(&self) -> TcpListener {
+        let own = &self.peers[self.id].addr;
+        addr::listen(own, self.bind).unwrap_or_else(|e| match self.bind {
+            Some(bind) => panic!("Could not listen at {}: {}", bind, e),
+            None => panic!("Could not listen at {}: {}", own, e),
+        })
+    }
     fn connect_to_all(&mut self) {
         let timer = start_timer!(|| "Connecting");
         let n = self.peers.len();
+        let mut nonce = [0u8; 32];
+        getrandom::getrandom(&mut nonce).expect("no randomness for the session nonce");
+        self.peers[self.id].nonce = Some(nonce);
+        // Bind once: re-binding per peer could drop a connection queued on the old listener.
+        let listener = if self.id > 0 {
+            Some(self.listener.take().unwrap_or_else(|| self.listen()))
+        } else {
+            None
+        };
         for from_id in 0..n {
             for to_id in (from_id + 1)..n {
                 debug!("{} to {}", from_id, to_id);
                 if self.id == from_id {
-                    let to_addr = self.peers[to_id].addr;
-                    debug!("Contacting {}", to_id);
-                    let stream = loop {
-                        let mut ms_waited = 0;
-                        match TcpStream::connect(to_addr) {
+                    let to_addr = self.peers[to_id].addr.clone();
+                    debug!("Contacting {} at {}", to_id, to_addr);
+                    let mut ms_waited = 0;
+                    let mut stream = loop {
+                        match addr::connect(&to_addr) {
                             Ok(s) => break s,
                             Err(e) => match e.kind() {
                                 std::io::ErrorKind::ConnectionRefused
@@ -100,13 +507,12 @@ impl Connections {
                             },
                         }
                     };
-                    stream.set_nodelay(true).unwrap();
+                    self.hello(&mut stream, to_id);
                     self.peers[to_id].stream = Some(stream);
                 } else if self.id == to_id {
                     debug!("Awaiting {}", from_id);
-                    let listener = TcpListener::bind(self.peers[self.id].addr).unwrap();
-                    let (stream, _addr) = listener.accept().unwrap();
-                    stream.set_nodelay(true).unwrap();
+                    let (mut stream, _addr) = listener.as_ref().unwrap().accept().unwrap();
+                    self.hello(&mut stream, from_id);
                     self.peers[from_id].stream = Some(stream);
                 }
             }
@@ -129,9 +535,10 @@ impl Connections {
                 }
             }
         }
+        self.start_session();
         // Do a round with the king, to be sure everyone is ready
-        let from_all = self.send_to_king(&[self.id as u8]);
-        self.recv_from_king(from_all);
+        let from_all = self.send_to(0, &[self.id as u8]);
+        self.recv_from(0, from_all);
         for id in 0..n {
             if id != self.id {
                 assert!(self.peers[id].stream.is_some());
@@ -139,116 +546,786 @@ impl Connections {
         }
         end_timer!(timer);
     }
-    fn am_king(&self) -> bool {
-        self.id == 0
+    /// Set up a new connection to `peer`, checking that it speaks our protocol, deciding whether
+    /// to compress on it, and exchanging session nonces.
+    fn hello(&mut self, stream: &mut TcpStream, peer: usize) {
+        stream.set_nodelay(true).unwrap();
+        let mut features = Features::default();
+        if self.peers[self.id].key.is_some() {
+            features = features | Features::AUTHENTICATED;
+        }
+        if self.compress {
+            features = features | Features::COMPRESSED;
+        }
+        let hello = handshake::exchange_hello(stream, peer, Hello::ours(features)).unwrap();
+        let compress = match hello {
+            Ok(theirs) => self.compress && theirs.features.contains(Features::COMPRESSED),
+            Err(e) => panic!("{}", e),
+        };
+        stream.write_all(&self.peers[self.id].nonce.unwrap()).unwrap();
+        let mut nonce = [0u8; 32];
+        stream.read_exact(&mut nonce).unwrap();
+        self.peers[peer].nonce = Some(nonce);
+        self.peers[peer].compress = compress;
+        stream.set_read_timeout(self.timeout).unwrap();
+    }
+    /// Start a session, once every party's nonce is in: fix its id, and derive the keys that
+    /// messages are tagged with in it.
+    ///
+    /// A peer that lies about its nonce, or a network that alters one, leaves the two ends of a
+    /// link with different ids, and their first message fails authentication.
+    fn start_session(&mut self) {
+        let nonces: Vec<Nonce> = self.peers.iter().map(|p| p.nonce.unwrap()).collect();
+        let session = session_id(&nonces);
+        for p in &mut self.peers {
+            p.session_key = p.key.as_ref().map(|k| session_key(k, &session));
+        }
+        self.session = Some(session);
     }
     fn broadcast(&mut self, bytes_out: &[u8]) -> Vec<Vec<u8>> {
+        let mut bytes_in = Vec::new();
+        self.broadcast_into(bytes_out, &mut bytes_in);
+        bytes_in
+    }
+    fn broadcast_into(&mut self, bytes_out: &[u8], bytes_in: &mut Vec<Vec<u8>>) {
+        self.exchange(bytes_out, bytes_in, false);
+    }
+    fn broadcast_surviving(&mut self, bytes_out: &[u8]) -> Vec<Option<Vec<u8>>> {
+        let mut bytes_in = Vec::new();
+        let live = self.exchange(bytes_out, &mut bytes_in, true);
+        bytes_in
+            .into_iter()
+            .zip(live)
+            .map(|(b, live)| if live { Some(b) } else { None })
+            .collect()
+    }
+    /// Broadcast, returning which parties' messages arrived.
+    ///
+    /// If `tolerant`, up to `max_dropouts` parties may drop out; otherwise, all must take part.
+    fn exchange(
+        &mut self,
+        bytes_out: &[u8],
+        bytes_in: &mut Vec<Vec<u8>>,
+        tolerant: bool,
+    ) -> Vec<bool> {
         let timer = start_timer!(|| format!("Broadcast {}", bytes_out.len()));
         let m = bytes_out.len();
         let own_id = self.id;
+        let own_key = self.peers[own_id].session_key;
         self.stats.bytes_sent += (self.peers.len() - 1) * m;
         self.stats.bytes_recv += (self.peers.len() - 1) * m;
         self.stats.broadcasts += 1;
-        let r = self
-            .peers
-            .par_iter_mut()
-            .enumerate()
-            .map(|(id, peer)| {
-                let mut bytes_in = vec![0u8; m];
-                if id < own_id {
-                    let stream = peer.stream.as_mut().unwrap();
-                    stream.read_exact(&mut bytes_in[..]).unwrap();
-                    stream.write_all(bytes_out).unwrap();
-                } else if id == own_id {
-                    bytes_in.copy_from_slice(bytes_out);
-                } else {
-                    let stream = peer.stream.as_mut().unwrap();
-                    stream.write_all(bytes_out).unwrap();
-                    stream.read_exact(&mut bytes_in[..]).unwrap();
-                };
-                bytes_in
-            })
-            .collect();
+        self.stats.round_sizes.record(m);
+        self.arm();
+        bytes_in.resize_with(self.peers.len(), Vec::new);
+        let peers = &mut self.peers;
+        let results: Vec<io::Result<()>> = on_net_pool(&self.net_pool, || {
+            peers
+                .par_iter_mut()
+                .zip(bytes_in.par_iter_mut())
+                .enumerate()
+                .map(|(id, (peer, bytes_in))| {
+                    bytes_in.resize(m, 0);
+                    if id < own_id {
+                        peer.live()?;
+                        peer.recv(own_id, &mut [&mut bytes_in[..]])?;
+                        peer.send(own_id, own_key.as_ref(), &[bytes_out])
+                    } else if id == own_id {
+                        bytes_in.copy_from_slice(bytes_out);
+                        Ok(())
+                    } else {
+                        peer.live()?;
+                        peer.send(own_id, own_key.as_ref(), &[bytes_out])?;
+                        peer.recv(own_id, &mut [&mut bytes_in[..]])
+                    }
+                })
+                .collect()
+        });
+        let live = self.settle(results, tolerant);
+        for (b, live) in bytes_in.iter_mut().zip(&live) {
+            if !live {
+                b.clear();
+            }
+        }
         end_timer!(timer);
-        r
+        live
+    }
+    /// Mark the peers whose I/O failed as dropped out, and return which peers are live.
+    ///
+    /// Aborts if a peer drops out of a round that is not `tolerant`, or if too many have.
+    fn settle(&mut self, results: Vec<io::Result<()>>, tolerant: bool) -> Vec<bool> {
+        let whereabouts = self.whereabouts();
+        for (peer, r) in self.peers.iter_mut().zip(&results) {
+            if let Err(e) = r {
+                if !peer.dropped {
+                    warn!(
+                        "Party {} dropped out {}: {}",
+                        peer.id,
+                        whereabouts,
+                        describe(e)
+                    );
+                    peer.dropped = true;
+                    peer.stream = None;
+                }
+            }
+        }
+        let dropped = self.dropouts();
+        if !dropped.is_empty() && !tolerant {
+            self.abort(format!(
+                "parties {:?} dropped out {}, and this round needs everyone",
+                dropped, whereabouts
+            ));
+        }
+        if dropped.len() > self.max_dropouts {
+            self.abort(format!(
+                "parties {:?} dropped out {}, but at most {} may",
+                dropped, whereabouts, self.max_dropouts
+            ));
+        }
+        results.iter().map(Result::is_ok).collect()
+    }
+    /// Which round this is (counting from one, since the stats were reset), and in which timed
+    /// phase, for reporting where a protocol stalled.
+    fn whereabouts(&self) -> String {
+        let s = &self.stats;
+        let round = s.broadcasts + s.to_king + s.from_king + s.peer_msgs;
+        format!("in round {} of phase `{}`", round, timer::current_path())
+    }
+    /// Before a round: with a deadline, wait for peers no longer than what is left of it, and
+    /// abort if nothing is.
+    fn arm(&mut self) {
+        let deadline = match self.deadline {
+            Some(d) => d,
+            None => return,
+        };
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left == Duration::from_secs(0) {
+            let whereabouts = self.whereabouts();
+            self.abort(format!("the deadline passed {}", whereabouts));
+        }
+        let wait = Some(self.timeout.map_or(left, |t| t.min(left)));
+        for stream in self.peers.iter().filter_map(|p| p.stream.as_ref()) {
+            let _ = stream.set_read_timeout(wait);
+            let _ = stream.set_write_timeout(wait);
+        }
     }
-    fn send_to_king(&mut self, bytes_out: &[u8]) -> Option<Vec<Vec<u8>>> {
-        let timer = start_timer!(|| format!("To king {}", bytes_out.len()));
+    /// Abort the protocol: shut down every connection, so that peers fail at once rather than
+    /// wait for us, and panic with `why`.
+    fn abort(&mut self, why: String) -> ! {
+        for stream in self.peers.iter().filter_map(|p| p.stream.as_ref()) {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+        panic!("Protocol abort: {}", why)
+    }
+    /// Abort because `party`, which this round cannot do without, failed with `e`.
+    fn lost(&mut self, party: usize, e: io::Error) -> ! {
+        let whereabouts = self.whereabouts();
+        self.abort(format!(
+            "party {} dropped out {}: {}",
+            party,
+            whereabouts,
+            describe(&e)
+        ))
+    }
+    fn dropouts(&self) -> Vec<usize> {
+        self.peers.iter().filter(|p| p.dropped).map(|p| p.id).collect()
+    }
+    fn send_to(&mut self, party: usize, bytes_out: &[u8]) -> Option<Vec<Vec<u8>>> {
+        self.to_party(party, bytes_out, false)
+            .map(|bytes_in| bytes_in.into_iter().map(Option::unwrap).collect())
+    }
+    /// Send to `party`, who gets each party's message, or `None` for parties that dropped out.
+    ///
+    /// If `tolerant`, up to `max_dropouts` parties may drop out; `party` itself may not.
+    fn to_party(
+        &mut self,
+        party: usize,
+        bytes_out: &[u8],
+        tolerant: bool,
+    ) -> Option<Vec<Option<Vec<u8>>>> {
+        let timer = start_timer!(|| format!("To party {} {}", party, bytes_out.len()));
         let m = bytes_out.len();
         let own_id = self.id;
         self.stats.to_king += 1;
-        let r = if self.am_king() {
+        self.stats.round_sizes.record(m);
+        self.arm();
+        let r = if own_id == party {
             self.stats.bytes_recv += (self.peers.len() - 1) * m;
-            Some(
-                self.peers
+            let peers = &mut self.peers;
+            let (bytes_in, results): (Vec<_>, Vec<_>) = on_net_pool(&self.net_pool, || {
+                peers
                     .par_iter_mut()
                     .enumerate()
                     .map(|(id, peer)| {
                         let mut bytes_in = vec![0u8; m];
-                        if id == own_id {
+                        let r = if id == own_id {
                             bytes_in.copy_from_slice(bytes_out);
+                            Ok(())
                         } else {
-                            let stream = peer.stream.as_mut().unwrap();
-                            stream.read_exact(&mut bytes_in[..]).unwrap();
+                            peer.live()
+                                .and_then(|()| peer.recv(own_id, &mut [&mut bytes_in[..]]))
                         };
-                        bytes_in
+                        (bytes_in, r)
                     })
+                    .unzip()
+            });
+            let live = self.settle(results, tolerant);
+            Some(
+                bytes_in
+                    .into_iter()
+                    .zip(live)
+                    .map(|(b, live)| if live { Some(b) } else { None })
                     .collect(),
             )
         } else {
             self.stats.bytes_sent += m;
-            self.peers[0]
-                .stream
-                .as_mut()
-                .unwrap()
-                .write_all(bytes_out)
-                .unwrap();
+            let own_key = self.peers[own_id].session_key;
+            if let Err(e) = self.peers[party].send(own_id, own_key.as_ref(), &[bytes_out]) {
+                self.lost(party, e);
+            }
             None
         };
         end_timer!(timer);
         r
     }
-    fn recv_from_king(&mut self, bytes_out: Option<Vec<Vec<u8>>>) -> Vec<u8> {
+    fn recv_from(&mut self, party: usize, bytes_out: Option<Vec<Vec<u8>>>) -> Vec<u8> {
         let own_id = self.id;
         self.stats.from_king += 1;
-        if self.am_king() {
+        self.arm();
+        if own_id == party {
             let bytes_out = bytes_out.unwrap();
-            let m = bytes_out[0].len();
-            let timer = start_timer!(|| format!("From king {}", m));
+            let m = bytes_out[party].len();
+            let timer = start_timer!(|| format!("From party {} {}", party, m));
             let bytes_size = (m as u64).to_le_bytes();
+            let own_key = self.peers[own_id].session_key;
             self.stats.bytes_sent += (self.peers.len() - 1) * (m + 8);
-            self.peers
-                .par_iter_mut()
-                .enumerate()
-                .filter(|p| p.0 != own_id)
-                .for_each(|(id, peer)| {
-                    let stream = peer.stream.as_mut().unwrap();
-                    assert_eq!(bytes_out[id].len(), m);
-                    stream.write_all(&bytes_size).unwrap();
-                    stream.write_all(&bytes_out[id]).unwrap();
-                });
+            self.stats.round_sizes.record(m);
+            let peers = &mut self.peers;
+            let results = on_net_pool(&self.net_pool, || {
+                peers
+                    .par_iter_mut()
+                    .enumerate()
+                    .map(|(id, peer)| {
+                        if id == own_id {
+                            return Ok(());
+                        }
+                        peer.live()?;
+                        assert_eq!(bytes_out[id].len(), m);
+                        peer.send(own_id, own_key.as_ref(), &[&bytes_size, &bytes_out[id]])
+                    })
+                    .collect()
+            });
+            // Parties that drop out miss the king's answer; those that remain are unaffected.
+            self.settle(results, true);
             end_timer!(timer);
             bytes_out[own_id].clone()
         } else {
-            let stream = self.peers[0].stream.as_mut().unwrap();
-            let mut bytes_size = [0u8; 8];
-            stream.read_exact(&mut bytes_size).unwrap();
-            let m = u64::from_le_bytes(bytes_size) as usize;
-            self.stats.bytes_recv += m;
-            let mut bytes_in = vec![0u8; m];
-            stream.read_exact(&mut bytes_in).unwrap();
+            let max_message = self.max_message.unwrap_or(MAX_MESSAGE);
+            let bytes_in = match self.peers[party].recv_sized(own_id, max_message) {
+                Ok(bytes_in) => bytes_in,
+                Err(e) => self.lost(party, e),
+            };
+            self.stats.bytes_recv += bytes_in.len();
+            self.stats.round_sizes.record(bytes_in.len());
             bytes_in
         }
     }
+    fn send_to_peer(&mut self, party: usize, bytes_out: &[u8]) {
+        let own_id = self.id;
+        let own_key = self.peers[own_id].session_key;
+        self.stats.peer_msgs += 1;
+        self.arm();
+        self.stats.bytes_sent += bytes_out.len() + 8;
+        self.stats.round_sizes.record(bytes_out.len());
+        let size = (bytes_out.len() as u64).to_le_bytes();
+        if let Err(e) = self.peers[party].send(own_id, own_key.as_ref(), &[&size, bytes_out]) {
+            self.lost(party, e);
+        }
+    }
+    fn recv_from_peer(&mut self, party: usize) -> Vec<u8> {
+        let own_id = self.id;
+        self.stats.peer_msgs += 1;
+        self.arm();
+        let max_message = self.max_message.unwrap_or(MAX_MESSAGE);
+        let bytes_in = match self.peers[party].recv_sized(own_id, max_message) {
+            Ok(bytes_in) => bytes_in,
+            Err(e) => self.lost(party, e),
+        };
+        self.stats.bytes_recv += bytes_in.len();
+        self.stats.round_sizes.record(bytes_in.len());
+        bytes_in
+    }
     fn uninit(&mut self) {
         for p in &mut self.peers {
             p.stream = None;
+            p.sent = 0;
+            p.recvd = 0;
+            p.dropped = false;
+            p.compress = false;
+            p.nonce = None;
+            p.session_key = None;
+        }
+        self.session = None;
+        self.listener = None;
+        self.deadline = None;
+    }
+}
+
+/// An I/O error, saying so plainly if it is that the peer sent nothing in time.
+fn describe(e: &io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => "timed out".to_owned(),
+        _ => e.to_string(),
+    }
+}
+
+/// Run `f`, which does I/O with the peers in parallel, on `pool` if there is one, and otherwise
+/// on rayon's global pool.
+fn on_net_pool<T: Send>(pool: &Option<Arc<ThreadPool>>, f: impl FnOnce() -> T + Send) -> T {
+    match pool {
+        Some(pool) => pool.install(f),
+        None => f(),
+    }
+}
+
+/// Marks the last frame a party sends in [MpcMultiNet::run_lanes].
+const CLOSE_LANES: u32 = u32::MAX;
+
+/// Messages (and their tags) received on one lane from one peer, in order.
+#[derive(Default)]
+struct InboxState {
+    msgs: VecDeque<(Vec<u8>, Vec<u8>)>,
+    /// Messages taken so far, for checking tags.
+    taken: u64,
+    /// Whether the connection failed; nothing more will arrive.
+    broken: bool,
+}
+
+#[derive(Default)]
+struct Inbox {
+    state: Mutex<InboxState>,
+    ready: Condvar,
+}
+
+/// Concurrent lanes of traffic, multiplexed over the parties' connections.
+///
+/// Each message is framed with its lane and length. Per peer, a reader thread sorts incoming
+/// frames into per-lane inboxes, so that a lane waiting on a message never blocks the others.
+/// Messages are tagged as on the plain connections, with a sequence number per lane.
+struct Lanes {
+    id: usize,
+    /// Per peer: the stream to write frames to, and how many we sent on each lane.
+    writers: Vec<Option<Mutex<(TcpStream, Vec<u64>)>>>,
+    /// Per peer, per lane.
+    inboxes: Vec<Vec<Inbox>>,
+    keys: Vec<Option<Key>>,
+    /// Per peer, whether large frames are compressed.
+    compress: Vec<bool>,
+    /// The longest frame to accept.
+    max_message: usize,
+}
+
+impl Lanes {
+    fn send(&self, to: usize, lane: u32, bytes: &[u8]) {
+        let mut w = self.writers[to].as_ref().unwrap().lock().unwrap();
+        let (stream, sent) = &mut *w;
+        let lane_bytes = lane.to_le_bytes();
+        let parts: [&[u8]; 2] = [&lane_bytes, bytes];
+        let corrupted;
+        let bytes = match faults::outgoing(&[bytes]) {
+            Outgoing::Intact => bytes,
+            Outgoing::Dropped => {
+                sent[lane as usize] += 1;
+                return;
+            }
+            Outgoing::Corrupted(c) => {
+                corrupted = c;
+                &corrupted[0][..]
+            }
+        };
+        let compressed = if self.compress[to] {
+            compress(&[bytes])
+        } else {
+            None
+        };
+        let (len, body) = match &compressed {
+            Some(c) => (c.len() as u64 | COMPRESSED, &c[..]),
+            None => (bytes.len() as u64, bytes),
+        };
+        let mut frame = Vec::with_capacity(12 + body.len() + TAG_LEN);
+        frame.extend_from_slice(&lane_bytes);
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(body);
+        if let Some(key) = &self.keys[self.id] {
+            let seq = sent[lane as usize];
+            frame.extend_from_slice(&tag(key, self.id, to, seq, &parts).finalize().into_bytes());
+        }
+        sent[lane as usize] += 1;
+        stream
+            .write_all(&frame)
+            .unwrap_or_else(|e| panic!("Protocol abort: lost party {}: {}", to, e));
+    }
+    fn recv(&self, from: usize, lane: u32) -> Vec<u8> {
+        let inbox = &self.inboxes[from][lane as usize];
+        let mut state = inbox.state.lock().unwrap();
+        let (bytes, t) = loop {
+            if let Some(m) = state.msgs.pop_front() {
+                break m;
+            }
+            assert!(!state.broken, "Protocol abort: lost party {}", from);
+            state = inbox.ready.wait(state).unwrap();
+        };
+        if let Some(key) = &self.keys[from] {
+            let lane_bytes = lane.to_le_bytes();
+            if tag(key, from, self.id, state.taken, &[&lane_bytes, &bytes])
+                .verify(&t)
+                .is_err()
+            {
+                panic!(
+                    "Protocol abort: message {} on lane {} from party {} failed authentication",
+                    state.taken, lane, from
+                );
+            }
+        }
+        state.taken += 1;
+        bytes
+    }
+    fn peers(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.writers.len()).filter(move |&i| i != self.id)
+    }
+    fn broadcast(&self, lane: u32, bytes: &[u8]) -> Vec<Vec<u8>> {
+        let n = self.writers.len();
+        {
+            let mut ch = get_ch!();
+            ch.stats.bytes_sent += (n - 1) * bytes.len();
+            ch.stats.bytes_recv += (n - 1) * bytes.len();
+            ch.stats.broadcasts += 1;
+            ch.stats.round_sizes.record(bytes.len());
+        }
+        for to in self.peers() {
+            self.send(to, lane, bytes);
+        }
+        (0..n)
+            .map(|from| {
+                if from == self.id {
+                    bytes.to_vec()
+                } else {
+                    self.recv(from, lane)
+                }
+            })
+            .collect()
+    }
+    fn send_to(&self, lane: u32, party: usize, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let n = self.writers.len();
+        let king = self.id == party;
+        {
+            let mut ch = get_ch!();
+            ch.stats.to_king += 1;
+            ch.stats.round_sizes.record(bytes.len());
+            if king {
+                ch.stats.bytes_recv += (n - 1) * bytes.len();
+            } else {
+                ch.stats.bytes_sent += bytes.len();
+            }
+        }
+        if king {
+            Some(
+                (0..n)
+                    .map(|from| {
+                        if from == party {
+                            bytes.to_vec()
+                        } else {
+                            self.recv(from, lane)
+                        }
+                    })
+                    .collect(),
+            )
+        } else {
+            self.send(party, lane, bytes);
+            None
+        }
+    }
+    fn recv_from(&self, lane: u32, party: usize, bytes_out: Option<Vec<Vec<u8>>>) -> Vec<u8> {
+        get_ch!().stats.from_king += 1;
+        match bytes_out {
+            Some(bytes_out) => {
+                {
+                    let mut ch = get_ch!();
+                    let sent: Vec<usize> = self.peers().map(|to| bytes_out[to].len()).collect();
+                    ch.stats.bytes_sent += sent.iter().sum::<usize>();
+                    ch.stats.round_sizes.record(sent.into_iter().max().unwrap_or(0));
+                }
+                for to in self.peers() {
+                    self.send(to, lane, &bytes_out[to]);
+                }
+                bytes_out[party].clone()
+            }
+            None => {
+                let bytes = self.recv(party, lane);
+                let mut ch = get_ch!();
+                ch.stats.bytes_recv += bytes.len();
+                ch.stats.round_sizes.record(bytes.len());
+                bytes
+            }
+        }
+    }
+    fn send_to_peer(&self, lane: u32, party: usize, bytes: &[u8]) {
+        {
+            let mut ch = get_ch!();
+            ch.stats.peer_msgs += 1;
+            ch.stats.bytes_sent += bytes.len();
+            ch.stats.round_sizes.record(bytes.len());
+        }
+        self.send(party, lane, bytes);
+    }
+    fn recv_from_peer(&self, lane: u32, party: usize) -> Vec<u8> {
+        let bytes = self.recv(party, lane);
+        let mut ch = get_ch!();
+        ch.stats.peer_msgs += 1;
+        ch.stats.bytes_recv += bytes.len();
+        ch.stats.round_sizes.record(bytes.len());
+        bytes
+    }
+    /// Sort `from`'s frames into inboxes, until it closes its lanes (or the connection fails).
+    fn read_frames(&self, from: usize, mut stream: TcpStream) {
+        let tag_len = if self.keys[from].is_some() { TAG_LEN } else { 0 };
+        let mut read_frame = || -> io::Result<Option<(u32, Vec<u8>, Vec<u8>)>> {
+            let mut header = [0u8; 12];
+            stream.read_exact(&mut header)?;
+            let mut lane = [0u8; 4];
+            lane.copy_from_slice(&header[..4]);
+            let lane = u32::from_le_bytes(lane);
+            if lane == CLOSE_LANES {
+                return Ok(None);
+            }
+            if lane as usize >= self.inboxes[from].len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown lane {}", lane),
+                ));
+            }
+            let mut len = [0u8; 8];
+            len.copy_from_slice(&header[4..]);
+            let len = u64::from_le_bytes(len);
+            // Nothing here is authenticated yet, so no length is taken on trust.
+            let bytes = if len & COMPRESSED != 0 {
+                let max = lz4_flex::block::get_maximum_output_size(self.max_message);
+                let mut compressed = vec![0u8; check_len(len & !COMPRESSED, max)?];
+                stream.read_exact(&mut compressed)?;
+                let len = decompressed_len(&compressed)?;
+                let mut bytes = vec![0u8; check_len(len as u64, self.max_message)?];
+                decompress(&compressed, &mut bytes)?;
+                bytes
+            } else {
+                let mut bytes = vec![0u8; check_len(len, self.max_message)?];
+                stream.read_exact(&mut bytes)?;
+                bytes
+            };
+            let mut t = vec![0u8; tag_len];
+            stream.read_exact(&mut t)?;
+            Ok(Some((lane, bytes, t)))
+        };
+        loop {
+            match read_frame() {
+                Ok(Some((lane, bytes, t))) => {
+                    let inbox = &self.inboxes[from][lane as usize];
+                    inbox.state.lock().unwrap().msgs.push_back((bytes, t));
+                    inbox.ready.notify_one();
+                }
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Lost party {} while running lanes: {}", from, e);
+                    for inbox in &self.inboxes[from] {
+                        inbox.state.lock().unwrap().broken = true;
+                        inbox.ready.notify_all();
+                    }
+                    return;
+                }
+            }
+        }
+    }
+    fn close(&self) {
+        let mut frame = CLOSE_LANES.to_le_bytes().to_vec();
+        frame.extend_from_slice(&0u64.to_le_bytes());
+        for to in self.peers() {
+            let mut w = self.writers[to].as_ref().unwrap().lock().unwrap();
+            // A peer that is gone has nothing left to read.
+            let _ = w.0.write_all(&frame);
         }
     }
 }
 
+impl Connections {
+    /// Multiplex `k` lanes over the connections, spawning a reader thread per peer.
+    fn open_lanes(&self, k: usize) -> (Arc<Lanes>, Vec<JoinHandle<()>>) {
+        assert!(
+            self.dropouts().is_empty(),
+            "lanes need every party, but {:?} dropped out",
+            self.dropouts()
+        );
+        let clone = |p: &Peer| p.stream.as_ref().map(|s| s.try_clone().unwrap());
+        let lanes = Arc::new(Lanes {
+            id: self.id,
+            writers: self
+                .peers
+                .iter()
+                .map(|p| clone(p).map(|s| Mutex::new((s, vec![0; k]))))
+                .collect(),
+            inboxes: self
+                .peers
+                .iter()
+                .map(|_| (0..k).map(|_| Inbox::default()).collect())
+                .collect(),
+            keys: self.peers.iter().map(|p| p.session_key).collect(),
+            compress: self.peers.iter().map(|p| p.compress).collect(),
+            max_message: self.max_message.unwrap_or(MAX_MESSAGE),
+        });
+        let readers = self
+            .peers
+            .iter()
+            .filter(|p| p.id != self.id)
+            .map(|p| {
+                let (lanes, stream, from) = (lanes.clone(), clone(p).unwrap(), p.id);
+                std::thread::spawn(move || lanes.read_frames(from, stream))
+            })
+            .collect();
+        (lanes, readers)
+    }
+}
+
+/// Run `f` with this thread's lane, if it is in one.
+fn on_lane<T>(f: impl FnOnce(&Lanes, u32) -> T) -> Option<T> {
+    LANE.with(|l| l.borrow().as_ref().map(|(lanes, lane)| f(lanes, *lane)))
+}
+
 pub struct MpcMultiNet;
 
+impl MpcMultiNet {
+    /// Let up to `n` parties drop out of rounds that can do without them (see
+    /// [MpcNet::broadcast_bytes_surviving]), rather than aborting.
+    ///
+    /// Parties that drop out are listed in [Stats::dropouts].
+    ///
+    /// The king is not covered: rounds through it (such as GSZ20's degree reductions) cannot do
+    /// without it, since the others would need another round to agree on what it sent each of
+    /// them. If it drops out, they abort, naming it, rather than wait.
+    pub fn set_max_dropouts(n: usize) {
+        get_ch!().max_dropouts = n;
+    }
+
+    /// Offer to compress large messages; call before connecting. Compression is used with the
+    /// peers that offer it too, and accounted in [Stats::compressed_raw] and
+    /// [Stats::compressed_sent].
+    pub fn set_compression(on: bool) {
+        get_ch!().compress = on;
+    }
+
+    /// Refuse messages of unknown length (such as the king's answers, messages between peers,
+    /// and every message on [lanes](MpcMultiNet::run_lanes)) longer than `bytes` (by default,
+    /// 1 GiB), taking the peer that sent one to have dropped out, rather than allocating for it;
+    /// call before connecting.
+    pub fn set_max_message(bytes: usize) {
+        get_ch!().max_message = Some(bytes);
+    }
+
+    /// Listen for peers at `addr` (or, with `None`, at this party's own address; see
+    /// [crate::addr]), rather than at the address they connect to; call before connecting. A port
+    /// of 0 means this party's own port.
+    ///
+    /// This is for hosts behind NAT, or with several interfaces: e.g., `0.0.0.0:0` or `[::]:0`
+    /// listens on every interface.
+    pub fn set_bind_addr(addr: Option<SocketAddr>) {
+        get_ch!().bind = addr;
+    }
+
+    /// Take a peer that sends nothing for `timeout` to have dropped out (which aborts the
+    /// protocol, unless the round tolerates dropouts), rather than waiting for it forever; call
+    /// before connecting. By default, there is no timeout.
+    ///
+    /// The timeout covers any wait for a message, including while the peer computes.
+    pub fn set_timeout(timeout: Option<Duration>) {
+        get_ch!().timeout = timeout;
+    }
+
+    /// Abort the protocol if it is still running `deadline` from now (or, with `None`, never):
+    /// no wait for a peer lasts beyond it. Call after connecting.
+    ///
+    /// Aborts, like those of a [timeout](MpcMultiNet::set_timeout), name the round and the
+    /// timed phase that stalled, and shut down every connection, so that peers abort too.
+    pub fn set_deadline(deadline: Option<Duration>) {
+        let mut ch = get_ch!();
+        ch.deadline = deadline.map(|d| Instant::now() + d);
+        if ch.deadline.is_none() {
+            let timeout = ch.timeout;
+            for stream in ch.peers.iter().filter_map(|p| p.stream.as_ref()) {
+                let _ = stream.set_read_timeout(timeout);
+                let _ = stream.set_write_timeout(None);
+            }
+        }
+    }
+
+    /// Exchange messages with peers on a pool of `n` threads of its own, rather than on rayon's
+    /// global pool, which computation shares (see [crate::set_compute_threads]). With `None`, go
+    /// back to the global pool.
+    ///
+    /// Each thread blocks on one peer at a time, so fewer than `n_parties() - 1` threads
+    /// serialize some of a round's I/O.
+    pub fn set_net_threads(n: Option<usize>) {
+        get_ch!().net_pool = n.map(|n| {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(n)
+                .thread_name(|i| format!("mpc-net-{}", i))
+                .build()
+                .unwrap_or_else(|e| panic!("Could not start {} network threads: {}", n, e));
+            Arc::new(pool)
+        });
+    }
+
+    /// Like [MpcNet::init_from_file], but parties whose hosts-file entry has port 0 listen on
+    /// any free port, and find each other through the rendezvous file at `rendezvous` (see
+    /// [crate::rendezvous]).
+    pub fn init_with_rendezvous(path: &str, party_id: usize, rendezvous: &str) {
+        let mut ch = get_ch!();
+        ch.init_from_path(path, party_id);
+        ch.rendezvous(Path::new(rendezvous));
+        ch.connect_to_all();
+    }
+
+    /// Run `f(0)`, ..., `f(k - 1)` concurrently, each on a thread of its own, and return their
+    /// results in order.
+    ///
+    /// All parties must call this together, with the same `k`. Each call of `f` is a lane: its
+    /// messages go to the same lane at the other parties, multiplexed over the existing
+    /// connections. Lanes must not drop out, and do not tolerate dropouts.
+    pub fn run_lanes<T: Send>(k: usize, f: impl Fn(usize) -> T + Sync) -> Vec<T> {
+        assert!(
+            on_lane(|_, _| ()).is_none(),
+            "lanes cannot be nested"
+        );
+        let (lanes, readers) = get_ch!().open_lanes(k);
+        let results = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..k)
+                .map(|lane| {
+                    let (lanes, f) = (lanes.clone(), &f);
+                    scope.spawn(move || {
+                        LANE.with(|l| *l.borrow_mut() = Some((lanes, lane as u32)));
+                        f(lane)
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|w| w.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+                .collect()
+        });
+        lanes.close();
+        for r in readers {
+            r.join().unwrap();
+        }
+        results
+    }
+}
+
 impl MpcNet for MpcMultiNet {
     #[inline]
     fn party_id() -> usize {
@@ -260,20 +1337,27 @@ impl MpcNet for MpcMultiNet {
         get_ch!().peers.len()
     }
 
+    /// Before connecting, there is no session, and this is zeros.
+    #[inline]
+    fn session_id() -> [u8; 32] {
+        get_ch!().session.unwrap_or([0; 32])
+    }
+
     #[inline]
     fn init_from_file(path: &str, party_id: usize) {
         let mut ch = get_ch!();
         ch.init_from_path(path, party_id);
+        assert!(
+            ch.peers.iter().all(|p| p.id == 0 || p.addr.port > 0),
+            "port 0 needs a rendezvous file (see MpcMultiNet::init_with_rendezvous)"
+        );
         ch.connect_to_all();
     }
 
     #[inline]
     fn is_init() -> bool {
-        get_ch!()
-            .peers
-            .first()
-            .map(|p| p.stream.is_some())
-            .unwrap_or(false)
+        // Our own entry never has a stream, so look at every peer's (the king's first is its own).
+        get_ch!().peers.iter().any(|p| p.stream.is_some())
     }
 
     #[inline]
@@ -284,25 +1368,184 @@ impl MpcNet for MpcMultiNet {
     #[inline]
     fn reset_stats() {
         get_ch!().stats = Stats::default();
+        *COUNTERS_AT_RESET.lock().unwrap() = metrics::counters();
+        COMPRESSED_RAW.store(0, Ordering::Relaxed);
+        COMPRESSED_SENT.store(0, Ordering::Relaxed);
     }
 
     #[inline]
     fn stats() -> crate::Stats {
-        get_ch!().stats.clone()
+        let ch = get_ch!();
+        Stats {
+            dropouts: ch.dropouts(),
+            compressed_raw: COMPRESSED_RAW.load(Ordering::Relaxed),
+            compressed_sent: COMPRESSED_SENT.load(Ordering::Relaxed),
+            counters: metrics::diff(&metrics::counters(), &COUNTERS_AT_RESET.lock().unwrap()),
+            ..ch.stats.clone()
+        }
     }
 
     #[inline]
     fn broadcast_bytes(bytes: &[u8]) -> Vec<Vec<u8>> {
-        get_ch!().broadcast(bytes)
+        on_lane(|l, lane| l.broadcast(lane, bytes)).unwrap_or_else(|| get_ch!().broadcast(bytes))
+    }
+
+    #[inline]
+    fn broadcast_bytes_into(bytes: &[u8], bufs: &mut Vec<Vec<u8>>) {
+        match on_lane(|l, lane| l.broadcast(lane, bytes)) {
+            Some(r) => *bufs = r,
+            None => get_ch!().broadcast_into(bytes, bufs),
+        }
+    }
+
+    #[inline]
+    fn broadcast_bytes_surviving(bytes: &[u8]) -> Vec<Option<Vec<u8>>> {
+        on_lane(|l, lane| l.broadcast(lane, bytes).into_iter().map(Some).collect())
+            .unwrap_or_else(|| get_ch!().broadcast_surviving(bytes))
     }
 
     #[inline]
     fn send_bytes_to_king(bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
-        get_ch!().send_to_king(bytes)
+        Self::send_bytes_to(0, bytes)
+    }
+
+    #[inline]
+    fn send_bytes_to_king_surviving(bytes: &[u8]) -> Option<Vec<Option<Vec<u8>>>> {
+        on_lane(|l, lane| {
+            l.send_to(lane, 0, bytes)
+                .map(|r| r.into_iter().map(Some).collect())
+        })
+        .unwrap_or_else(|| get_ch!().to_party(0, bytes, true))
     }
 
     #[inline]
     fn recv_bytes_from_king(bytes: Option<Vec<Vec<u8>>>) -> Vec<u8> {
-        get_ch!().recv_from_king(bytes)
+        Self::recv_bytes_from(0, bytes)
+    }
+
+    #[inline]
+    fn send_bytes_to(party: usize, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+        on_lane(|l, lane| l.send_to(lane, party, bytes))
+            .unwrap_or_else(|| get_ch!().send_to(party, bytes))
+    }
+
+    #[inline]
+    fn recv_bytes_from(party: usize, bytes: Option<Vec<Vec<u8>>>) -> Vec<u8> {
+        match LANE.with(|l| l.borrow().clone()) {
+            Some((lanes, lane)) => lanes.recv_from(lane, party, bytes),
+            None => get_ch!().recv_from(party, bytes),
+        }
+    }
+
+    #[inline]
+    fn send_bytes_to_peer(party: usize, bytes: &[u8]) {
+        on_lane(|l, lane| l.send_to_peer(lane, party, bytes))
+            .unwrap_or_else(|| get_ch!().send_to_peer(party, bytes))
+    }
+
+    #[inline]
+    fn recv_bytes_from_peer(party: usize) -> Vec<u8> {
+        on_lane(|l, lane| l.recv_from_peer(lane, party))
+            .unwrap_or_else(|| get_ch!().recv_from_peer(party))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tags_do_not_carry_across_sessions() {
+        let key = [7u8; 32];
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let first = session_id(&[a, b]);
+        assert_eq!(first, session_id(&[a, b]));
+        assert_ne!(first, session_id(&[b, a]));
+        let second = session_id(&[a, c]);
+        assert_ne!(first, second);
+
+        let msg: &[&[u8]] = &[b"a share"];
+        let t = tag(&session_key(&key, &first), 0, 1, 0, msg)
+            .finalize()
+            .into_bytes();
+        assert!(tag(&session_key(&key, &first), 0, 1, 0, msg)
+            .verify(&t)
+            .is_ok());
+        // The same message, at the same place in a later session, is rejected.
+        assert!(tag(&session_key(&key, &second), 0, 1, 0, msg)
+            .verify(&t)
+            .is_err());
+    }
+
+    /// A peer reached over a local connection, and the other end of it.
+    fn local_peer() -> (Peer, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ours = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (theirs, _) = listener.accept().unwrap();
+        let peer = Peer {
+            id: 1,
+            stream: Some(ours),
+            compress: true,
+            ..Peer::default()
+        };
+        (peer, theirs)
+    }
+
+    #[test]
+    fn compressed_lengths_are_checked() {
+        let msg = vec![5u8; 1 << 14];
+        let c = compress(&[&msg]).unwrap();
+        let mut out = vec![0u8; msg.len()];
+        decompress(&c, &mut out).unwrap();
+        assert_eq!(out, msg);
+        assert!(decompress(&c, &mut out[1..]).is_err());
+
+        // A compressed length no message of the expected size could have is refused unread.
+        let (mut peer, mut theirs) = local_peer();
+        let len = lz4_flex::block::get_maximum_output_size(msg.len()) as u64 + 1;
+        theirs.write_all(&(len | COMPRESSED).to_le_bytes()).unwrap();
+        assert!(peer.recv(0, &mut [&mut out[..]]).is_err());
+
+        // So is a message of unknown length that claims to be longer than allowed.
+        let (mut peer, mut theirs) = local_peer();
+        theirs
+            .write_all(&(c.len() as u64 | COMPRESSED).to_le_bytes())
+            .unwrap();
+        theirs.write_all(&c).unwrap();
+        assert!(peer.recv_sized(0, 1 << 10).is_err());
+        let (mut peer, mut theirs) = local_peer();
+        theirs.write_all(&0u64.to_le_bytes()).unwrap();
+        theirs.write_all(&u64::MAX.to_le_bytes()).unwrap();
+        assert!(peer.recv_sized(0, 1 << 10).is_err());
+    }
+
+    #[test]
+    fn bad_frames_break_the_inboxes() {
+        let lanes = Lanes {
+            id: 0,
+            writers: vec![None, None],
+            inboxes: (0..2)
+                .map(|_| (0..2).map(|_| Inbox::default()).collect())
+                .collect(),
+            keys: vec![None, None],
+            compress: vec![false, false],
+            max_message: 1 << 10,
+        };
+        let frame = |lane: u32, len: u64| [&lane.to_le_bytes()[..], &len.to_le_bytes()].concat();
+        // A frame on a lane that was not opened, and one longer than allowed.
+        for bad in [frame(2, 1), frame(1, u64::MAX >> 1)] {
+            let (peer, mut theirs) = local_peer();
+            theirs.write_all(&frame(0, 1)).unwrap();
+            theirs.write_all(&[7]).unwrap();
+            theirs.write_all(&bad).unwrap();
+            lanes.read_frames(1, peer.stream.unwrap());
+            for inbox in &lanes.inboxes[1] {
+                assert!(inbox.state.lock().unwrap().broken);
+            }
+        }
+        assert_eq!(
+            lanes.inboxes[1][0].state.lock().unwrap().msgs.pop_front(),
+            Some((vec![7], vec![]))
+        );
     }
 }
@@ -0,0 +1,67 @@
+//! Discovering parties' addresses through a shared file.
+//!
+//! A party whose hosts-file entry has port 0 listens on any free port, then appends `ID HOST:PORT`
+//! to the rendezvous file, where its peers look it up. The file must not hold entries from an
+//! earlier run: use a fresh path per run, or remove the file before launching any party.
+use crate::addr::HostPort;
+use log::debug;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long to wait for every party to register.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Register party `id` as listening at `addr`.
+pub fn publish(path: &Path, id: usize, addr: HostPort) {
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .unwrap_or_else(|e| panic!("rendezvous file {}: {}", path.display(), e));
+    // One small appending write, so concurrent registrations do not interleave.
+    f.write_all(format!("{} {}\n", id, addr).as_bytes())
+        .unwrap();
+}
+
+/// Wait until all of `ids` have registered, and return their addresses.
+pub fn wait_for(path: &Path, ids: &[usize]) -> HashMap<usize, HostPort> {
+    let start = Instant::now();
+    loop {
+        let found = read(path);
+        if ids.iter().all(|id| found.contains_key(id)) {
+            return found;
+        }
+        if start.elapsed() > TIMEOUT {
+            let missing: Vec<_> = ids.iter().filter(|id| !found.contains_key(id)).collect();
+            panic!("parties {:?} did not register in {:?}", missing, TIMEOUT);
+        }
+        debug!("Waiting for parties to register");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// The complete entries in the file so far.
+fn read(path: &Path) -> HashMap<usize, HostPort> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return HashMap::new(),
+        Err(e) => panic!("rendezvous file {}: {}", path.display(), e),
+    };
+    // A line without its newline may still be being written.
+    let complete = &contents[..contents.rfind('\n').map_or(0, |i| i + 1)];
+    complete
+        .lines()
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let id = fields.next().and_then(|f| f.parse().ok());
+            let addr = fields.next().and_then(|f| f.parse().ok());
+            match (id, addr) {
+                (Some(id), Some(addr)) => (id, addr),
+                _ => panic!("bad rendezvous entry: {}", line),
+            }
+        })
+        .collect()
+}
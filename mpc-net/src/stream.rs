@@ -0,0 +1,127 @@
+//! Streaming a large message from one party to all others, a chunk at a time.
+//!
+//! The sending party writes into a [Sender], and every other party reads from a [Receiver]; each
+//! chunk is one [MpcNet::recv_bytes_from] round, and an empty chunk ends the stream. Neither side
+//! buffers more than a chunk (per peer), so objects bigger than memory (e.g., a serialized SRS)
+//! can be passed straight from a file or into one.
+use super::MpcNet;
+
+use std::io::{self, Read, Write};
+use std::marker::PhantomData;
+
+/// The size of a chunk.
+pub const CHUNK: usize = 1 << 20;
+
+/// Writes to every other party, who must be reading from a [Receiver] for this party.
+///
+/// `progress` gets the number of bytes sent so far, after each chunk.
+pub struct Sender<N: MpcNet + ?Sized, P: FnMut(usize)> {
+    buf: Vec<u8>,
+    sent: usize,
+    progress: P,
+    _net: PhantomData<N>,
+}
+
+impl<N: MpcNet + ?Sized, P: FnMut(usize)> Sender<N, P> {
+    pub fn new(progress: P) -> Self {
+        Self {
+            buf: Vec::with_capacity(CHUNK),
+            sent: 0,
+            progress,
+            _net: PhantomData,
+        }
+    }
+
+    fn send_chunk(&mut self, chunk: &[u8]) {
+        N::recv_bytes_from(N::party_id(), Some(vec![chunk.to_vec(); N::n_parties()]));
+        if !chunk.is_empty() {
+            self.sent += chunk.len();
+            (self.progress)(self.sent);
+        }
+    }
+
+    /// Send what is buffered, and end the stream. Returns the number of bytes sent.
+    pub fn finish(mut self) -> usize {
+        let rest = std::mem::take(&mut self.buf);
+        if !rest.is_empty() {
+            self.send_chunk(&rest);
+        }
+        self.send_chunk(&[]);
+        self.sent
+    }
+}
+
+impl<N: MpcNet + ?Sized, P: FnMut(usize)> Write for Sender<N, P> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let n = bytes.len().min(CHUNK - self.buf.len());
+        self.buf.extend_from_slice(&bytes[..n]);
+        if self.buf.len() == CHUNK {
+            let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK));
+            self.send_chunk(&chunk);
+        }
+        Ok(n)
+    }
+
+    /// Chunks are sent as they fill; a partial one is only sent by [Sender::finish].
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads what `party` writes into its [Sender].
+///
+/// `progress` gets the number of bytes received so far, after each chunk.
+pub struct Receiver<N: MpcNet + ?Sized, P: FnMut(usize)> {
+    party: usize,
+    chunk: Vec<u8>,
+    pos: usize,
+    received: usize,
+    done: bool,
+    progress: P,
+    _net: PhantomData<N>,
+}
+
+impl<N: MpcNet + ?Sized, P: FnMut(usize)> Receiver<N, P> {
+    pub fn new(party: usize, progress: P) -> Self {
+        assert_ne!(party, N::party_id(), "a party cannot stream to itself");
+        Self {
+            party,
+            chunk: Vec::new(),
+            pos: 0,
+            received: 0,
+            done: false,
+            progress,
+            _net: PhantomData,
+        }
+    }
+
+    /// Read the rest of the stream, which must be empty. Returns the number of bytes received.
+    pub fn finish(mut self) -> usize {
+        let mut rest = Vec::new();
+        self.read_to_end(&mut rest).unwrap();
+        assert!(
+            rest.is_empty(),
+            "{} bytes left unread in stream",
+            rest.len()
+        );
+        self.received
+    }
+}
+
+impl<N: MpcNet + ?Sized, P: FnMut(usize)> Read for Receiver<N, P> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() && !self.done {
+            self.chunk = N::recv_bytes_from(self.party, None);
+            self.pos = 0;
+            self.done = self.chunk.is_empty();
+            if !self.done {
+                self.received += self.chunk.len();
+                (self.progress)(self.received);
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,244 @@
+//! Running a test as a small MPC over loopback.
+//!
+//! The test binary re-runs itself once per party (selecting just that test). Within a party,
+//! [assert_comm](crate::assert_comm) bounds the communication an operation uses.
+use super::{MpcMultiNet, MpcNet};
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const PARTY_VAR: &str = "MPC_TEST_PARTY";
+const HOSTS_VAR: &str = "MPC_TEST_HOSTS";
+const RENDEZVOUS_VAR: &str = "MPC_TEST_RENDEZVOUS";
+/// A file that the party creates once it is running the test, so that a test name that matches no
+/// test (and so runs nothing, successfully) is caught.
+const MARKER_VAR: &str = "MPC_TEST_MARKER";
+
+/// Run `body` as each of `n` parties, in child processes running the test named `test`.
+///
+/// `test` is the test's full path (e.g., `tests::foo`), as the harness needs it for `--exact`.
+pub fn run_parties(test: &str, n: usize, body: impl FnOnce()) {
+    run_parties_with(test, n, |_| (), body)
+}
+
+/// Like [run_parties], but each party first runs `setup` with its id, before connecting.
+pub fn run_parties_with(test: &str, n: usize, setup: impl FnOnce(usize), body: impl FnOnce()) {
+    if run_as_party(setup, body) {
+        return;
+    }
+    let (hosts, rendezvous) = host_files(test, n);
+    let children: Vec<_> = (0..n)
+        .map(|i| party_command(test, i, &hosts, &rendezvous).spawn().unwrap())
+        .collect();
+    let ok = children
+        .into_iter()
+        .map(|mut c| c.wait().unwrap().success())
+        .fold(true, |a, b| a && b);
+    let ran = take_markers(&hosts, n);
+    remove_host_files(&hosts, &rendezvous);
+    check_ran(test, &ran);
+    assert!(ok, "some party failed");
+}
+
+/// How a party's process ended, in [run_parties_checked].
+#[derive(Clone, Debug)]
+pub struct Exit {
+    /// The exit code, or `None` if the process was killed.
+    pub code: Option<i32>,
+    /// Whether the party was still running at the deadline, and so was killed.
+    pub hung: bool,
+    /// What the party wrote to stderr (such as the message it panicked with).
+    pub stderr: String,
+}
+
+impl Exit {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Like [run_parties_with], but for runs that may fail (e.g., with [crate::faults] injected):
+/// rather than asserting that every party succeeds, call `check` with how each exited. A party
+/// still running after `deadline` is killed, and reported as [Exit::hung].
+///
+/// `check` runs in the test's own process, once all parties are done. The parties' stdout is
+/// discarded, and their stderr is kept for `check`.
+pub fn run_parties_checked(
+    test: &str,
+    n: usize,
+    deadline: Duration,
+    setup: impl FnOnce(usize),
+    body: impl FnOnce(),
+    check: impl FnOnce(Vec<Exit>),
+) {
+    if run_as_party(setup, body) {
+        return;
+    }
+    let (hosts, rendezvous) = host_files(test, n);
+    let start = Instant::now();
+    let mut children: Vec<_> = (0..n)
+        .map(|i| {
+            let mut child = party_command(test, i, &hosts, &rendezvous)
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+            // Read stderr as it comes, so that a full pipe cannot block the party.
+            let mut stderr = child.stderr.take().unwrap();
+            let reader = std::thread::spawn(move || {
+                let mut s = String::new();
+                let _ = stderr.read_to_string(&mut s);
+                s
+            });
+            (child, reader)
+        })
+        .collect();
+    let mut hung = vec![false; n];
+    while children
+        .iter_mut()
+        .any(|(c, _)| c.try_wait().unwrap().is_none())
+    {
+        if start.elapsed() > deadline {
+            for ((c, _), hung) in children.iter_mut().zip(&mut hung) {
+                if c.try_wait().unwrap().is_none() {
+                    *hung = true;
+                    let _ = c.kill();
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let exits = children
+        .into_iter()
+        .zip(hung)
+        .map(|((mut c, reader), hung)| Exit {
+            code: c.wait().unwrap().code(),
+            hung,
+            stderr: reader.join().unwrap(),
+        })
+        .collect();
+    let ran = take_markers(&hosts, n);
+    remove_host_files(&hosts, &rendezvous);
+    check_ran(test, &ran);
+    check(exits);
+}
+
+/// If this process is one of the parties of a test, run `setup` and `body` as that party, and
+/// return true.
+fn run_as_party(setup: impl FnOnce(usize), body: impl FnOnce()) -> bool {
+    let id = match std::env::var(PARTY_VAR) {
+        Ok(id) => id.parse().unwrap(),
+        Err(_) => return false,
+    };
+    let hosts = std::env::var(HOSTS_VAR).unwrap();
+    let rendezvous = std::env::var(RENDEZVOUS_VAR).unwrap();
+    std::fs::write(std::env::var(MARKER_VAR).unwrap(), "").unwrap();
+    setup(id);
+    MpcMultiNet::init_with_rendezvous(&hosts, id, &rendezvous);
+    body();
+    MpcMultiNet::deinit();
+    true
+}
+
+/// A hosts file for `n` parties of `test`, and the path of their rendezvous file.
+fn host_files(test: &str, n: usize) -> (PathBuf, PathBuf) {
+    let hosts = std::env::temp_dir().join(format!("mpc-test-{}-{}", test, std::process::id()));
+    let rendezvous = hosts.with_extension("rendezvous");
+    // Every party picks its own port.
+    std::fs::write(&hosts, vec!["127.0.0.1:0"; n].join("\n")).unwrap();
+    (hosts, rendezvous)
+}
+
+fn remove_host_files(hosts: &Path, rendezvous: &Path) {
+    std::fs::remove_file(hosts).unwrap();
+    // Absent if no party got as far as registering.
+    let _ = std::fs::remove_file(rendezvous);
+}
+
+fn marker(hosts: &Path, i: usize) -> PathBuf {
+    hosts.with_extension(format!("party{}", i))
+}
+
+/// Whether each of the `n` parties left its marker; removes them.
+fn take_markers(hosts: &Path, n: usize) -> Vec<bool> {
+    (0..n)
+        .map(|i| std::fs::remove_file(marker(hosts, i)).is_ok())
+        .collect()
+}
+
+fn check_ran(test: &str, ran: &[bool]) {
+    let missing: Vec<usize> = (0..ran.len()).filter(|&i| !ran[i]).collect();
+    assert!(
+        missing.is_empty(),
+        "parties {:?} ran no test named {}; is that the test's full path?",
+        missing,
+        test
+    );
+}
+
+/// The command that runs party `i` of `test`.
+fn party_command(test: &str, i: usize, hosts: &Path, rendezvous: &Path) -> Command {
+    let mut command = Command::new(std::env::current_exe().unwrap());
+    command
+        .args(&[test, "--exact", "--nocapture", "--test-threads=1"])
+        .env(PARTY_VAR, i.to_string())
+        .env(HOSTS_VAR, hosts)
+        .env(RENDEZVOUS_VAR, rendezvous)
+        .env(MARKER_VAR, marker(hosts, i));
+    command
+}
+
+/// The communication an operation used, at this party.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comm {
+    /// Broadcasts, sends to the king, replies from the king, and messages to or from a peer.
+    pub rounds: usize,
+    pub bytes_sent: usize,
+    pub bytes_recv: usize,
+}
+
+/// Run `f`, returning its result and the communication it used (per [MpcMultiNet::stats]).
+///
+/// Communication by other threads meanwhile is counted too. [Self-checks](crate::self_checks)
+/// are off meanwhile, so that the communication is the same in debug and release builds.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, Comm) {
+    let rounds = |s: &crate::Stats| s.broadcasts + s.to_king + s.from_king + s.peer_msgs;
+    let checks = crate::set_self_checks(false);
+    let before = MpcMultiNet::stats();
+    let t = f();
+    let after = MpcMultiNet::stats();
+    crate::set_self_checks(checks);
+    let comm = Comm {
+        rounds: rounds(&after) - rounds(&before),
+        bytes_sent: after.bytes_sent - before.bytes_sent,
+        bytes_recv: after.bytes_recv - before.bytes_recv,
+    };
+    (t, comm)
+}
+
+/// Evaluate a block, asserting bounds on the communication (a [Comm]) it used, and return its
+/// value.
+///
+/// ```ignore
+/// let x = assert_comm!(rounds <= 1, bytes_sent <= 64, { x.publicize() });
+/// ```
+#[macro_export]
+macro_rules! assert_comm {
+    ($($field:ident $op:tt $bound:expr),+ , $body:block) => {{
+        let (t, comm) = $crate::testing::measure(|| $body);
+        $(
+            assert!(
+                comm.$field $op $bound,
+                "{} was {}, but should be {} {} ({:?})",
+                stringify!($field),
+                comm.$field,
+                stringify!($op),
+                $bound,
+                comm
+            );
+        )+
+        t
+    }};
+}
@@ -0,0 +1,447 @@
+//! MPC-aware timers.
+//!
+//! Like `ark_std`'s `start_timer!`/`end_timer!` (which they also drive), but each section also
+//! records the bytes and communication rounds spent inside it, taken from the [`Stats`] of
+//! [`MpcMultiNet`]. Sections nest: a section started while another is open becomes its child, and
+//! repeated sections at the same place in the tree are aggregated. [`summary`] renders the tree.
+//!
+//! Each section also records its peak memory use; see [`memory`](crate::memory) for how. And it
+//! records how much each [`metrics`](crate::metrics) counter grew inside it, e.g., how many Beaver
+//! triples it consumed or values it opened. And it records the size of the message in each of its
+//! rounds, whose median, 95th percentile and maximum [`summary`] shows: a section whose time goes
+//! into many small rounds wants fewer rounds, and one that sends a few large ones wants smaller
+//! messages.
+//!
+//! The section stack is global, so timers should be started and ended on the protocol's main
+//! thread, in LIFO order.
+//!
+//! To measure a run repeatedly, record the sections after each repetition in [`Repetitions`],
+//! which reports each section's mean and standard deviation per repetition.
+use ark_std::perf_trace::TimerInfo;
+use ark_std::{end_timer, start_timer};
+use lazy_static::lazy_static;
+
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::histogram::SizeHistogram;
+use super::metrics::{self, Counts};
+use super::{memory, MpcMultiNet, MpcNet, Stats};
+
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    children: Vec<usize>,
+    calls: usize,
+    time: Duration,
+    bytes: usize,
+    rounds: usize,
+    /// Largest peak memory use over all calls.
+    mem: usize,
+    counters: Counts,
+    /// The size of the message in each round.
+    sizes: SizeHistogram,
+}
+
+#[derive(Debug, Default)]
+struct Tree {
+    nodes: Vec<Node>,
+    roots: Vec<usize>,
+    /// Sections currently open, innermost last.
+    open: Vec<usize>,
+}
+
+impl Tree {
+    fn enter(&mut self, name: String) -> usize {
+        let siblings = match self.open.last() {
+            Some(&p) => &self.nodes[p].children,
+            None => &self.roots,
+        };
+        let existing = siblings
+            .iter()
+            .cloned()
+            .find(|&i| self.nodes[i].name == name);
+        let i = existing.unwrap_or_else(|| {
+            let i = self.nodes.len();
+            self.nodes.push(Node {
+                name,
+                ..Node::default()
+            });
+            match self.open.last() {
+                Some(&p) => self.nodes[p].children.push(i),
+                None => self.roots.push(i),
+            }
+            i
+        });
+        self.open.push(i);
+        i
+    }
+    fn exit(
+        &mut self,
+        i: usize,
+        time: Duration,
+        bytes: usize,
+        rounds: usize,
+        mem: usize,
+        counters: Counts,
+        sizes: SizeHistogram,
+    ) {
+        let pos = self
+            .open
+            .iter()
+            .rposition(|&j| j == i)
+            .expect("ended an MPC timer that is not running");
+        assert_eq!(
+            pos + 1,
+            self.open.len(),
+            "MPC timer {} ended before its children",
+            self.nodes[i].name
+        );
+        self.open.pop();
+        let node = &mut self.nodes[i];
+        node.calls += 1;
+        node.time += time;
+        node.bytes += bytes;
+        node.rounds += rounds;
+        node.mem = node.mem.max(mem);
+        for (name, v) in counters {
+            *node.counters.entry(name).or_insert(0) += v;
+        }
+        node.sizes.merge(&sizes);
+    }
+    fn render(&self, i: usize, depth: usize, mem_kind: &str, out: &mut String) {
+        let n = &self.nodes[i];
+        let label = format!("{:indent$}{}", "", n.name, indent = 2 * depth);
+        let calls = if n.calls > 1 {
+            format!(" (x{})", n.calls)
+        } else {
+            String::new()
+        };
+        // Counters by their short names, e.g., `triples_consumed` for `mpc_triples_consumed_total`.
+        let counters: String = n
+            .counters
+            .iter()
+            .map(|(name, v)| {
+                let short = name.trim_start_matches("mpc_").trim_end_matches("_total");
+                format!(" {}={}", short, v)
+            })
+            .collect();
+        // Whether the rounds were small (waiting on round trips) or large (on bandwidth).
+        let sizes = if n.sizes.count() > 0 {
+            let (p50, p95) = (n.sizes.quantile(0.5), n.sizes.quantile(0.95));
+            format!(" msg_p50={}B msg_p95={}B msg_max={}B", p50, p95, n.sizes.max())
+        } else {
+            String::new()
+        };
+        writeln!(
+            out,
+            "{:<40} {:>12.3?} {:>12} B {:>8} rounds {:>10.1} MiB {}{}{}{}",
+            label,
+            n.time,
+            n.bytes,
+            n.rounds,
+            n.mem as f64 / (1 << 20) as f64,
+            mem_kind,
+            calls,
+            sizes,
+            counters
+        )
+        .unwrap();
+        for &c in &n.children {
+            self.render(c, depth + 1, mem_kind, out);
+        }
+    }
+}
+
+lazy_static! {
+    static ref TREE: Mutex<Tree> = Mutex::new(Tree::default());
+}
+
+fn bytes(s: &Stats) -> usize {
+    s.bytes_sent + s.bytes_recv
+}
+
+fn rounds(s: &Stats) -> usize {
+    s.broadcasts + s.to_king + s.from_king + s.peer_msgs
+}
+
+/// A running section. See [`start_mpc_timer`](crate::start_mpc_timer).
+#[must_use]
+pub struct MpcTimer {
+    node: usize,
+    start: Instant,
+    stats: Stats,
+    outer_peak: usize,
+    trace: TimerInfo,
+}
+
+impl MpcTimer {
+    pub fn start(name: impl Into<String>) -> Self {
+        let name = name.into();
+        let trace = start_timer!(|| name.clone());
+        let node = TREE.lock().unwrap().enter(name);
+        Self {
+            node,
+            stats: MpcMultiNet::stats(),
+            outer_peak: memory::begin_peak(),
+            start: Instant::now(),
+            trace,
+        }
+    }
+    pub fn end(self) {
+        let time = self.start.elapsed();
+        end_timer!(self.trace);
+        let stats = MpcMultiNet::stats();
+        let mem = memory::end_peak(self.outer_peak);
+        TREE.lock().unwrap().exit(
+            self.node,
+            time,
+            bytes(&stats).saturating_sub(bytes(&self.stats)),
+            rounds(&stats).saturating_sub(rounds(&self.stats)),
+            mem,
+            metrics::diff(&stats.counters, &self.stats.counters),
+            stats.round_sizes.since(&self.stats.round_sizes),
+        );
+    }
+}
+
+/// Render all sections recorded so far as a tree of (wall time, bytes, rounds, peak memory,
+/// message sizes).
+pub fn summary() -> String {
+    let tree = TREE.lock().unwrap();
+    let mut out = String::new();
+    // Peak heap use if allocations are counted, else the resident set size at exit.
+    let mem_kind = if memory::live().is_some() { "peak" } else { "rss" };
+    for &r in &tree.roots {
+        tree.render(r, 0, mem_kind, &mut out);
+    }
+    out
+}
+
+/// A finished section, for export.
+#[derive(Clone, Debug)]
+pub struct Phase {
+    /// Section names from the root, separated by `/`.
+    pub path: String,
+    pub calls: usize,
+    pub time: Duration,
+    pub bytes: usize,
+    pub rounds: usize,
+    /// How much each counter grew, over all calls.
+    pub counters: Counts,
+}
+
+/// All sections recorded so far, parents before their children.
+pub fn phases() -> Vec<Phase> {
+    fn walk(tree: &Tree, i: usize, prefix: &str, out: &mut Vec<Phase>) {
+        let n = &tree.nodes[i];
+        let path = if prefix.is_empty() {
+            n.name.clone()
+        } else {
+            format!("{}/{}", prefix, n.name)
+        };
+        out.push(Phase {
+            path: path.clone(),
+            calls: n.calls,
+            time: n.time,
+            bytes: n.bytes,
+            rounds: n.rounds,
+            counters: n.counters.clone(),
+        });
+        for &c in &n.children {
+            walk(tree, c, &path, out);
+        }
+    }
+    let tree = TREE.lock().unwrap();
+    let mut out = Vec::new();
+    for &r in &tree.roots {
+        walk(&tree, r, "", &mut out);
+    }
+    out
+}
+
+/// Sections recorded after each of several repetitions of a run, for per-repetition statistics.
+#[derive(Clone, Debug, Default)]
+pub struct Repetitions {
+    /// [phases] after each repetition: running totals, so each repetition is the difference.
+    snapshots: Vec<Vec<Phase>>,
+}
+
+/// A section's statistics over [Repetitions].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseStats {
+    pub path: String,
+    /// Mean and (population) standard deviation of the time per repetition.
+    pub mean: Duration,
+    pub stddev: Duration,
+    /// Mean bytes and rounds per repetition.
+    pub bytes: f64,
+    pub rounds: f64,
+}
+
+impl Repetitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the end of a repetition. The sections must not have been [reset] since the first.
+    pub fn record(&mut self) {
+        self.snapshots.push(phases());
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Each section's statistics, parents before their children; a section that some
+    /// repetition did not enter counts as taking nothing in it.
+    pub fn stats(&self) -> Vec<PhaseStats> {
+        let totals = |snapshot: Option<&Vec<Phase>>, path: &str| {
+            snapshot
+                .and_then(|s| s.iter().find(|p| p.path == path))
+                .map(|p| (p.time.as_secs_f64(), p.bytes as f64, p.rounds as f64))
+                .unwrap_or((0.0, 0.0, 0.0))
+        };
+        let reps = self.snapshots.len() as f64;
+        let last = match self.snapshots.last() {
+            Some(last) => last,
+            None => return Vec::new(),
+        };
+        last.iter()
+            .map(|phase| {
+                let per_rep: Vec<(f64, f64, f64)> = (0..self.snapshots.len())
+                    .map(|i| {
+                        let (t, b, r) = totals(Some(&self.snapshots[i]), &phase.path);
+                        let (t0, b0, r0) =
+                            totals(i.checked_sub(1).map(|j| &self.snapshots[j]), &phase.path);
+                        (t - t0, b - b0, r - r0)
+                    })
+                    .collect();
+                let mean = per_rep.iter().map(|p| p.0).sum::<f64>() / reps;
+                let var = per_rep.iter().map(|p| (p.0 - mean).powi(2)).sum::<f64>() / reps;
+                PhaseStats {
+                    path: phase.path.clone(),
+                    mean: Duration::from_secs_f64(mean),
+                    stddev: Duration::from_secs_f64(var.sqrt()),
+                    bytes: per_rep.iter().map(|p| p.1).sum::<f64>() / reps,
+                    rounds: per_rep.iter().map(|p| p.2).sum::<f64>() / reps,
+                }
+            })
+            .collect()
+    }
+
+    /// Render [Repetitions::stats] as a table, a section's depth shown by its indentation.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        for s in self.stats() {
+            let depth = s.path.matches('/').count();
+            let name = s.path.rsplit('/').next().unwrap();
+            let label = format!("{:indent$}{}", "", name, indent = 2 * depth);
+            writeln!(
+                out,
+                "{:<40} {:>12.3?} ± {:>10.3?} {:>14.0} B {:>10.1} rounds",
+                label, s.mean, s.stddev, s.bytes, s.rounds
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+/// The names of the open sections, outermost first, separated by `/`.
+pub fn current_path() -> String {
+    let tree = TREE.lock().unwrap();
+    let names: Vec<&str> = tree
+        .open
+        .iter()
+        .map(|&i| tree.nodes[i].name.as_str())
+        .collect();
+    names.join("/")
+}
+
+/// Forget all finished sections.
+pub fn reset() {
+    let mut tree = TREE.lock().unwrap();
+    assert!(tree.open.is_empty(), "reset while MPC timers are running");
+    *tree = Tree::default();
+}
+
+/// Start an MPC-aware timed section; it is also traced like `ark_std::start_timer!`.
+#[macro_export]
+macro_rules! start_mpc_timer {
+    ($msg:expr) => {
+        $crate::timer::MpcTimer::start($msg())
+    };
+}
+
+/// End a section started with [`start_mpc_timer`].
+#[macro_export]
+macro_rules! end_mpc_timer {
+    ($t:expr) => {
+        $t.end()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_sections_aggregate() {
+        let mut tree = Tree::default();
+        for _ in 0..2 {
+            let outer = tree.enter("outer".into());
+            let inner = tree.enter("inner".into());
+            let triples: Counts = vec![("triples", 4)].into_iter().collect();
+            let mut sizes = SizeHistogram::default();
+            sizes.record(10);
+            tree.exit(inner, Duration::from_millis(1), 10, 1, 5, triples, sizes);
+            let (t, sizes) = (Duration::from_millis(3), SizeHistogram::default());
+            tree.exit(outer, t, 30, 2, 7, Counts::new(), sizes);
+        }
+        assert_eq!(tree.roots.len(), 1);
+        let outer = &tree.nodes[tree.roots[0]];
+        assert_eq!((outer.calls, outer.bytes, outer.rounds, outer.mem), (2, 60, 4, 7));
+        assert_eq!(outer.children.len(), 1);
+        let inner = &tree.nodes[outer.children[0]];
+        assert_eq!((inner.calls, inner.time), (2, Duration::from_millis(2)));
+        assert_eq!((inner.sizes.count(), inner.sizes.max()), (2, 10));
+        assert_eq!(inner.counters["triples"], 8);
+    }
+
+    #[test]
+    fn repetitions_difference_running_totals() {
+        let phase = |path: &str, ms: u64, bytes: usize| Phase {
+            path: path.into(),
+            calls: 1,
+            time: Duration::from_millis(ms),
+            bytes,
+            rounds: bytes / 100,
+            counters: Counts::new(),
+        };
+        // Running totals after each of three repetitions: 10, 30 and 20 ms of "prove"; "fixup"
+        // only ran in the second.
+        let reps = Repetitions {
+            snapshots: vec![
+                vec![phase("prove", 10, 100)],
+                vec![phase("prove", 40, 200), phase("prove/fixup", 6, 0)],
+                vec![phase("prove", 60, 300), phase("prove/fixup", 6, 0)],
+            ],
+        };
+        let stats = reps.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].path, "prove");
+        assert_eq!(stats[0].mean, Duration::from_millis(20));
+        // sqrt(((10-20)^2 + (30-20)^2 + 0) / 3) ms
+        let sd = stats[0].stddev.as_secs_f64() * 1e3;
+        assert!((sd - (200.0f64 / 3.0).sqrt()).abs() < 1e-6);
+        assert_eq!((stats[0].bytes, stats[0].rounds), (100.0, 1.0));
+        assert_eq!(stats[1].mean, Duration::from_millis(2));
+        assert!(reps.summary().contains("  fixup"));
+    }
+}
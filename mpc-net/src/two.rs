@@ -114,6 +114,7 @@ impl FieldChannel {
         s.write_all(v).unwrap();
         s.set_nonblocking(true).unwrap();
         self.stats.bytes_sent += bytes.len() + v.len();
+        self.stats.round_sizes.record(v.len());
     }
 
     #[inline]
@@ -126,6 +127,7 @@ impl FieldChannel {
         s.read_exact(&mut bytes[..]).unwrap();
         s.set_nonblocking(true).unwrap();
         self.stats.bytes_recv += bytes.len() + len.len();
+        self.stats.round_sizes.record(bytes.len());
         bytes
     }
 
@@ -171,6 +173,7 @@ impl FieldChannel {
         self.stats.broadcasts += 1;
         self.stats.bytes_sent += n;
         self.stats.bytes_recv += n;
+        self.stats.round_sizes.record(n);
         end_timer!(timer);
         Ok(bytes_in)
     }
@@ -316,4 +319,54 @@ impl MpcNet for MpcTwoNet {
             ch.recv_vec()
         }
     }
+
+    #[inline]
+    fn send_bytes_to(party: usize, bytes: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let me = Self::party_id();
+        let mut ch = get_ch!();
+        ch.stats.to_king += 1;
+        if party == me {
+            let other = ch.recv_vec();
+            debug_assert_eq!(bytes.len(), other.len());
+            let mut all = vec![other, bytes.to_vec()];
+            if me == 0 {
+                all.reverse();
+            }
+            Some(all)
+        } else {
+            ch.send_slice(bytes);
+            None
+        }
+    }
+
+    #[inline]
+    fn recv_bytes_from(party: usize, bytes: Option<Vec<Vec<u8>>>) -> Vec<u8> {
+        let me = Self::party_id();
+        let mut ch = get_ch!();
+        ch.stats.from_king += 1;
+        if party == me {
+            let mut bytes = bytes.expect("the sender needs bytes");
+            assert_eq!(bytes.len(), 2);
+            ch.send_slice(&bytes[1 - me]);
+            bytes.swap_remove(me)
+        } else {
+            ch.recv_vec()
+        }
+    }
+
+    #[inline]
+    fn send_bytes_to_peer(party: usize, bytes: &[u8]) {
+        assert_ne!(party, Self::party_id(), "a party cannot message itself");
+        let mut ch = get_ch!();
+        ch.stats.peer_msgs += 1;
+        ch.send_slice(bytes);
+    }
+
+    #[inline]
+    fn recv_bytes_from_peer(party: usize) -> Vec<u8> {
+        assert_ne!(party, Self::party_id(), "a party cannot message itself");
+        let mut ch = get_ch!();
+        ch.stats.peer_msgs += 1;
+        ch.recv_vec()
+    }
 }
@@ -0,0 +1,34 @@
+//! Bounding the communication of operations.
+use mpc_net::{assert_comm, testing::run_parties, MpcMultiNet, MpcNet};
+
+#[test]
+fn broadcast_is_one_round() {
+    run_parties("broadcast_is_one_round", 3, || {
+        let all = assert_comm!(rounds == 1, bytes_sent == 2 * 4, bytes_recv == 2 * 4, {
+            MpcMultiNet::broadcast_bytes(&[0u8; 4])
+        });
+        assert_eq!(all.len(), 3);
+    });
+}
+
+#[test]
+fn reduce_is_logarithmic() {
+    run_parties("reduce_is_logarithmic", 5, || {
+        let me = MpcMultiNet::party_id() as u8;
+        // Concatenation is associative, and shows that the tree keeps the parties in order.
+        let all = assert_comm!(rounds <= 3 + 1, {
+            MpcMultiNet::reduce_bytes(&[me], |mut a, b| {
+                a.extend(b);
+                a
+            })
+        });
+        assert_eq!(all, vec![0, 1, 2, 3, 4]);
+    });
+}
+
+#[test]
+#[should_panic(expected = "ran no test named")]
+fn misnamed_test_fails() {
+    // The parties run the test named here, which is none: they must not pass for running nothing.
+    run_parties("no_such_test", 2, || {});
+}
@@ -0,0 +1,37 @@
+//! Compressing large messages, on the connections where both ends offer it.
+use mpc_net::{testing::run_parties_with, MpcMultiNet, MpcNet};
+
+/// A large, compressible message, different for each party.
+fn message(party: usize) -> Vec<u8> {
+    (0..1 << 16).map(|i| (i / 64 + party) as u8).collect()
+}
+
+#[test]
+fn compression_is_transparent() {
+    // Party 2 does not offer compression, so only the link between 0 and 1 uses it.
+    let setup = |id| MpcMultiNet::set_compression(id != 2);
+    run_parties_with("compression_is_transparent", 3, setup, || {
+        let me = MpcMultiNet::party_id();
+        let all = MpcMultiNet::broadcast_bytes(&message(me));
+        assert_eq!(all, (0..3).map(message).collect::<Vec<_>>());
+        let to_king = MpcMultiNet::send_bytes_to_king(&message(me));
+        let back = MpcMultiNet::recv_bytes_from_king(to_king.map(|msgs| {
+            assert_eq!(msgs, (0..3).map(message).collect::<Vec<_>>());
+            (0..3).map(|i| message(i + 3)).collect()
+        }));
+        assert_eq!(back, message(me + 3));
+        let lanes = MpcMultiNet::run_lanes(2, |lane| {
+            MpcMultiNet::broadcast_bytes(&message(me + lane))
+                == (0..3).map(|p| message(p + lane)).collect::<Vec<_>>()
+        });
+        assert_eq!(lanes, vec![true, true]);
+
+        let stats = MpcMultiNet::stats();
+        if me == 2 {
+            assert_eq!(stats.compressed_raw, 0);
+        } else {
+            assert!(stats.compressed_raw > 0);
+            assert!(stats.compressed_sent * 10 < stats.compressed_raw);
+        }
+    });
+}
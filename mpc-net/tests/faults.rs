@@ -0,0 +1,168 @@
+//! Injected faults: protocols abort, or carry on without a crashed party, but never hang.
+use mpc_net::faults::{self, Fault, CRASH_EXIT};
+use mpc_net::testing::{run_parties_checked, run_parties_with, Exit};
+use mpc_net::{end_mpc_timer, start_mpc_timer, MpcMultiNet, MpcNet};
+use std::time::Duration;
+
+const DEADLINE: Duration = Duration::from_secs(20);
+
+fn with_timeout(ms: u64) -> impl Fn(usize) {
+    move |_| MpcMultiNet::set_timeout(Some(Duration::from_millis(ms)))
+}
+
+/// Every party failed on its own, at least one of them with `message`.
+fn all_abort(exits: &[Exit], message: &str) {
+    assert!(exits.iter().all(|e| !e.hung && !e.success()), "{:?}", exits);
+    assert!(
+        exits.iter().any(|e| e.stderr.contains(message)),
+        "{:?}",
+        exits
+    );
+}
+
+#[test]
+fn dropped_message_times_out() {
+    let body = || {
+        MpcMultiNet::broadcast_bytes(&[1]);
+        if MpcMultiNet::party_id() == 1 {
+            faults::inject(0, Fault::Drop);
+        }
+        MpcMultiNet::broadcast_bytes(&[2]);
+        MpcMultiNet::broadcast_bytes(&[3]);
+    };
+    run_parties_checked(
+        "dropped_message_times_out",
+        2,
+        DEADLINE,
+        with_timeout(500),
+        body,
+        |exits| all_abort(&exits, "dropped out"),
+    );
+}
+
+#[test]
+fn delayed_message_arrives_in_time() {
+    run_parties_with(
+        "delayed_message_arrives_in_time",
+        2,
+        with_timeout(5_000),
+        || {
+            if MpcMultiNet::party_id() == 1 {
+                faults::inject(0, Fault::Delay(Duration::from_millis(200)));
+            }
+            let all = MpcMultiNet::broadcast_bytes(&[MpcMultiNet::party_id() as u8]);
+            assert_eq!(all, vec![vec![0], vec![1]]);
+        },
+    );
+}
+
+#[test]
+fn delayed_message_times_out() {
+    let body = || {
+        if MpcMultiNet::party_id() == 1 {
+            faults::inject(0, Fault::Delay(Duration::from_millis(2_000)));
+        }
+        MpcMultiNet::broadcast_bytes(&[1]);
+        MpcMultiNet::broadcast_bytes(&[2]);
+    };
+    run_parties_checked(
+        "delayed_message_times_out",
+        2,
+        DEADLINE,
+        with_timeout(200),
+        body,
+        |exits| all_abort(&exits, "dropped out"),
+    );
+}
+
+#[test]
+fn crash_is_survived_by_tolerant_rounds() {
+    let body = || {
+        MpcMultiNet::set_max_dropouts(1);
+        if MpcMultiNet::party_id() == 2 {
+            faults::inject(0, Fault::Crash);
+        }
+        let all = MpcMultiNet::broadcast_bytes_surviving(&[7]);
+        assert_eq!(all, vec![Some(vec![7]), Some(vec![7]), None]);
+        assert_eq!(MpcMultiNet::stats().dropouts, vec![2]);
+    };
+    let setup = |_| ();
+    run_parties_checked(
+        "crash_is_survived_by_tolerant_rounds",
+        3,
+        DEADLINE,
+        setup,
+        body,
+        |exits| {
+            assert!(exits[..2].iter().all(Exit::success), "{:?}", exits);
+            assert_eq!(exits[2].code, Some(CRASH_EXIT));
+        },
+    );
+}
+
+#[test]
+fn crash_aborts_other_rounds() {
+    let body = || {
+        if MpcMultiNet::party_id() == 2 {
+            faults::inject(0, Fault::Crash);
+        }
+        MpcMultiNet::broadcast_bytes(&[7]);
+    };
+    let setup = |_| ();
+    run_parties_checked(
+        "crash_aborts_other_rounds",
+        3,
+        DEADLINE,
+        setup,
+        body,
+        |exits| {
+            all_abort(&exits[..2], "dropped out");
+            assert_eq!(exits[2].code, Some(CRASH_EXIT));
+        },
+    );
+}
+
+#[test]
+fn stall_names_its_phase_and_round() {
+    let body = || {
+        MpcMultiNet::reset_stats();
+        MpcMultiNet::broadcast_bytes(&[1]);
+        if MpcMultiNet::party_id() == 1 {
+            faults::inject(0, Fault::Delay(Duration::from_millis(2_000)));
+        }
+        let t = start_mpc_timer!(|| "stalling");
+        MpcMultiNet::broadcast_bytes(&[2]);
+        end_mpc_timer!(t);
+        MpcMultiNet::broadcast_bytes(&[3]);
+    };
+    run_parties_checked(
+        "stall_names_its_phase_and_round",
+        2,
+        DEADLINE,
+        with_timeout(200),
+        body,
+        |exits| all_abort(&exits, "in round 2 of phase `stalling`"),
+    );
+}
+
+#[test]
+fn deadline_aborts_a_slow_protocol() {
+    let body = || {
+        MpcMultiNet::reset_stats();
+        MpcMultiNet::set_deadline(Some(Duration::from_millis(300)));
+        MpcMultiNet::broadcast_bytes(&[1]);
+        if MpcMultiNet::party_id() == 1 {
+            std::thread::sleep(Duration::from_millis(1_000));
+        }
+        MpcMultiNet::broadcast_bytes(&[2]);
+    };
+    let setup = |_| ();
+    run_parties_checked(
+        "deadline_aborts_a_slow_protocol",
+        2,
+        DEADLINE,
+        setup,
+        body,
+        |exits| all_abort(&exits, "deadline passed in round 2"),
+    );
+}
@@ -0,0 +1,38 @@
+//! Concurrent lanes, multiplexed over one set of connections.
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+
+#[test]
+fn lanes_do_not_mix() {
+    run_parties("lanes_do_not_mix", 3, || {
+        let me = MpcMultiNet::party_id() as u8;
+        // Lanes run different numbers of rounds, of different sizes, so their frames interleave.
+        let sums = MpcMultiNet::run_lanes(4, |lane| {
+            let lane = lane as u8;
+            let mut sum = 0u64;
+            for round in 0..(5 + 3 * lane) {
+                let msg = vec![me ^ lane ^ round; 1 + lane as usize * 100];
+                let all = MpcMultiNet::broadcast_bytes(&msg);
+                for (party, m) in all.iter().enumerate() {
+                    assert_eq!(m, &vec![party as u8 ^ lane ^ round; msg.len()]);
+                }
+                let to_king = MpcMultiNet::send_bytes_to_king(&[me, lane]);
+                let back = MpcMultiNet::recv_bytes_from_king(to_king.map(|msgs| {
+                    msgs.into_iter()
+                        .map(|m| {
+                            assert_eq!(m[1], lane);
+                            vec![m[0], lane, round]
+                        })
+                        .collect()
+                }));
+                assert_eq!(back, vec![me, lane, round]);
+                sum += all.iter().map(|m| m[0] as u64).sum::<u64>();
+            }
+            sum
+        });
+        assert_eq!(sums.len(), 4);
+        // The plain connections still work afterwards.
+        let all = MpcMultiNet::broadcast_bytes(&[me]);
+        assert_eq!(all, vec![vec![0], vec![1], vec![2]]);
+        assert!(MpcMultiNet::stats().broadcasts > 4 * 5);
+    });
+}
@@ -0,0 +1,22 @@
+//! Finding parties through a rendezvous file.
+use mpc_net::rendezvous::{publish, wait_for};
+use std::io::Write;
+
+#[test]
+fn partial_entries_are_not_read() {
+    let path = std::env::temp_dir().join(format!("mpc-rendezvous-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    publish(&path, 1, "127.0.0.1:4001".parse().unwrap());
+    // A registration caught half-written.
+    let mut f = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    f.write_all(b"2 127.0.0.1:40").unwrap();
+    let found = wait_for(&path, &[1]);
+    assert_eq!(found.len(), 1);
+    f.write_all(b"02\n").unwrap();
+    let found = wait_for(&path, &[1, 2]);
+    assert_eq!(found[&2], "127.0.0.1:4002".parse().unwrap());
+    std::fs::remove_file(&path).unwrap();
+}
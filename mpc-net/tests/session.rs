@@ -0,0 +1,12 @@
+//! Sessions: every party must derive the same id from the nonces exchanged while connecting.
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+
+#[test]
+fn parties_agree_on_the_session_id() {
+    run_parties("parties_agree_on_the_session_id", 3, || {
+        let id = MpcMultiNet::session_id();
+        assert_ne!(id, [0; 32]);
+        let all = MpcMultiNet::broadcast_bytes(&id);
+        assert!(all.iter().all(|theirs| theirs[..] == id[..]));
+    });
+}
@@ -0,0 +1,27 @@
+//! Streaming a message larger than a chunk from one party to the others.
+use mpc_net::stream::{Receiver, Sender, CHUNK};
+use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+use std::io::{Read, Write};
+
+#[test]
+fn stream_in_chunks() {
+    run_parties("stream_in_chunks", 3, || {
+        let message: Vec<u8> = (0..3 * CHUNK + 5).map(|i| (i % 251) as u8).collect();
+        let mut progress = Vec::new();
+        if MpcMultiNet::party_id() == 1 {
+            let mut sender = Sender::<MpcMultiNet, _>::new(|n| progress.push(n));
+            // In uneven writes, so that they straddle chunks.
+            for part in message.chunks(CHUNK / 3 + 1) {
+                sender.write_all(part).unwrap();
+            }
+            assert_eq!(sender.finish(), message.len());
+        } else {
+            let mut receiver = Receiver::<MpcMultiNet, _>::new(1, |n| progress.push(n));
+            let mut got = vec![0u8; message.len()];
+            receiver.read_exact(&mut got).unwrap();
+            assert_eq!(receiver.finish(), message.len());
+            assert!(got == message);
+        }
+        assert_eq!(progress, vec![CHUNK, 2 * CHUNK, 3 * CHUNK, 3 * CHUNK + 5]);
+    });
+}
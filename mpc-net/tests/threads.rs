@@ -0,0 +1,27 @@
+//! Configuring the threads that compute and that exchange messages.
+use mpc_net::{testing::run_parties_with, MpcMultiNet, MpcNet};
+
+#[test]
+fn one_net_thread_serves_every_peer() {
+    // With a single network thread, each round's I/O with the peers happens one at a time.
+    let setup = |_| {
+        // The global pool is already in use once the parties connect.
+        mpc_net::set_compute_threads(2);
+        MpcMultiNet::set_net_threads(Some(1));
+    };
+    run_parties_with("one_net_thread_serves_every_peer", 4, setup, || {
+        assert_eq!(rayon::current_num_threads(), 2);
+        let me = MpcMultiNet::party_id() as u8;
+        let all = MpcMultiNet::broadcast_bytes(&[me; 3]);
+        assert_eq!(all, (0..4).map(|i| vec![i; 3]).collect::<Vec<_>>());
+        let at_king = MpcMultiNet::send_bytes_to_king(&[me]);
+        if MpcMultiNet::am_king() {
+            assert_eq!(at_king, Some(vec![vec![0], vec![1], vec![2], vec![3]]));
+        }
+        let out = (0..4).map(|i| vec![i * 2]).collect();
+        let mine = MpcMultiNet::recv_bytes_from_king(if me == 0 { Some(out) } else { None });
+        assert_eq!(mine, vec![me * 2]);
+        MpcMultiNet::set_net_threads(None);
+        assert_eq!(MpcMultiNet::broadcast_bytes(&[me]).len(), 4);
+    });
+}
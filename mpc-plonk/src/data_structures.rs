@@ -56,6 +56,57 @@ pub struct ProductProof<C, O> {
     pub q_r_open: O,
 }
 
+/// Proof that some polynomial f sums to zero over a domain
+#[derive(Clone)]
+pub struct SumProof<C, O> {
+    /// t (partial sums) commitment
+    pub t_cmt: C,
+    /// t(w^{k-1}) opening
+    pub t_wk_open: O,
+    /// t(r) opening
+    pub t_r_open: O,
+    /// t(w*r) opening
+    pub t_wr_open: O,
+    /// f(w*r) opening
+    pub f_wr_open: O,
+}
+
+/// Check that each looked-up wire is in its table via the log-derivative identity
+/// sum_i Sel(i)/(b - F(i)) = sum_j M(j)/(b - T(j)) over the wires,
+/// where F = P + g*Tag and T = T + g*TTag merge the tables,
+/// and M counts how often each table entry is looked up.
+///
+/// H holds the summands; it sums to zero, and is well-formed:
+/// H(X)(b - F(X))(b - T(X)) - Sel(X)(b - T(X)) + M(X)(b - F(X)) = Q(X)*Z(X)
+/// where Z vanishes on the wires, and Q is existential
+#[derive(Clone)]
+pub struct LookupProof<C, O> {
+    /// M commitment
+    pub m_cmt: C,
+    /// H commitment
+    pub h_cmt: C,
+    /// proof that H sums to zero over the wire domain
+    pub h_sum_pf: SumProof<C, O>,
+    /// Q commitment
+    pub q_cmt: C,
+    /// P(x) opening
+    pub p_open: O,
+    /// M(x) opening
+    pub m_open: O,
+    /// H(x) opening
+    pub h_open: O,
+    /// Q(x) opening
+    pub q_open: O,
+    /// Sel(x) opening
+    pub sel_open: O,
+    /// Tag(x) opening
+    pub tag_open: O,
+    /// T(x) opening
+    pub t_open: O,
+    /// TTag(x) opening
+    pub t_tag_open: O,
+}
+
 /// Check that P(X) = P(W(X)) on the wires
 /// via P(X) - v(X) = Q(X)*Z(X)
 /// where Z vanishes on the public wires
@@ -89,6 +140,8 @@ pub struct Proof<F, C, O> {
     pub gates: GateProof<C, (F, O)>,
     /// Proof of gates
     pub public: PublicProof<C, (F, O)>,
+    /// Proof of lookups, if there are any
+    pub lookup: Option<LookupProof<C, (F, O)>>,
 }
 
 #[derive(Clone)]
@@ -97,6 +150,7 @@ pub struct ProverKey<F: Field, C: PCCommitment, PcCk> {
     pub w_cmt: LabeledCommitment<C>,
     pub s: LabeledPolynomial<F, DensePolynomial<F>>,
     pub s_cmt: LabeledCommitment<C>,
+    pub lookup: Option<LookupProverKey<F, C>>,
     pub pc_ck: PcCk,
 }
 
@@ -104,9 +158,31 @@ pub struct ProverKey<F: Field, C: PCCommitment, PcCk> {
 pub struct VerifierKey<C: PCCommitment, PcVk> {
     pub w_cmt: LabeledCommitment<C>,
     pub s_cmt: LabeledCommitment<C>,
+    pub lookup: Option<LookupVerifierKey<C>>,
     pub pc_vk: PcVk,
 }
 
+/// The lookup selector, tags, and tables, with their commitments
+#[derive(Clone)]
+pub struct LookupProverKey<F: Field, C: PCCommitment> {
+    pub sel: LabeledPolynomial<F, DensePolynomial<F>>,
+    pub sel_cmt: LabeledCommitment<C>,
+    pub tag: LabeledPolynomial<F, DensePolynomial<F>>,
+    pub tag_cmt: LabeledCommitment<C>,
+    pub t: LabeledPolynomial<F, DensePolynomial<F>>,
+    pub t_cmt: LabeledCommitment<C>,
+    pub t_tag: LabeledPolynomial<F, DensePolynomial<F>>,
+    pub t_tag_cmt: LabeledCommitment<C>,
+}
+
+#[derive(Clone)]
+pub struct LookupVerifierKey<C: PCCommitment> {
+    pub sel_cmt: LabeledCommitment<C>,
+    pub tag_cmt: LabeledCommitment<C>,
+    pub t_cmt: LabeledCommitment<C>,
+    pub t_tag_cmt: LabeledCommitment<C>,
+}
+
 impl<C: MpcWire, O: MpcWire> MpcWire for GateProof<C, O> {
     struct_mpc_wire_impl!(GateProof<C, O>;
         (C, q_cmt), (O, s_open), (O, q_open), (O, p_open), (O, p_w_open), (O, p_w2_open));
@@ -121,6 +197,17 @@ impl<C: MpcWire, O: MpcWire> MpcWire for ProductProof<C, O> {
         (C, q_cmt), (C, t_cmt), (O, t_wk_open), (O, t_r_open), (O, t_wr_open), (O, f_wr_open), (O, q_r_open));
 }
 
+impl<C: MpcWire, O: MpcWire> MpcWire for SumProof<C, O> {
+    struct_mpc_wire_impl!(SumProof<C, O>;
+        (C, t_cmt), (O, t_wk_open), (O, t_r_open), (O, t_wr_open), (O, f_wr_open));
+}
+
+impl<C: MpcWire, O: MpcWire> MpcWire for LookupProof<C, O> {
+    struct_mpc_wire_impl!(LookupProof<C, O>;
+        (C, m_cmt), (C, h_cmt), (SumProof<C, O>, h_sum_pf), (C, q_cmt), (O, p_open), (O, m_open),
+        (O, h_open), (O, q_open), (O, sel_open), (O, tag_open), (O, t_open), (O, t_tag_open));
+}
+
 impl<C: MpcWire, O: MpcWire> MpcWire for WiringProof<C, O> {
     struct_mpc_wire_impl!(WiringProof<C, O>;
         (C, l1_cmt), (ProductProof<C, O>, l1_prod_pf), (C, l2_q_cmt), (O, p_x_open), (O, w_x_open), (O, l1_x_open), (O, l2_q_x_open));
@@ -131,7 +218,8 @@ impl<F: MpcWire, C: MpcWire, O: MpcWire> MpcWire for Proof<F, C, O> {
         (C, p_cmt),
         (WiringProof<C, (F, O)>, wiring),
         (GateProof<C, (F, O)>, gates),
-        (PublicProof<C, (F, O)>, public)
+        (PublicProof<C, (F, O)>, public),
+        (Option<LookupProof<C, (F, O)>>, lookup)
     );
 }
 
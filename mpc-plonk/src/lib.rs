@@ -23,7 +23,7 @@ use ark_ff::{FftField, Field};
 use ark_poly_commit::{LabeledCommitment, LabeledPolynomial, PCRandomness, PolynomialCommitment};
 
 use ark_poly::{
-    domain::EvaluationDomain,
+    domain::{EvaluationDomain, GeneralEvaluationDomain},
     univariate::{DenseOrSparsePolynomial, DensePolynomial},
     Polynomial, UVPolynomial,
 };
@@ -63,11 +63,41 @@ pub fn setup<'r, F: FftField, PC: PolynomialCommitment<F, DensePolynomial<F>>>(
     assert_eq!(cs.len(), 1);
     assert_eq!(rs.len(), 1);
     let s_cmt = cs.pop().unwrap();
+    let commit_fixed = |label: &str, p: &DensePolynomial<F>| {
+        let p = LabeledPolynomial::new(label.into(), p.clone(), None, None);
+        let (mut cs, rs) = PC::commit(&ck, once(&p), None).unwrap();
+        assert_eq!(cs.len(), 1);
+        assert_eq!(rs.len(), 1);
+        (p, cs.pop().unwrap())
+    };
+    let lookup_pk = circ.lookups.as_ref().map(|l| {
+        let (sel, sel_cmt) = commit_fixed("lookup_sel", &l.sel);
+        let (tag, tag_cmt) = commit_fixed("lookup_tag", &l.tag);
+        let (t, t_cmt) = commit_fixed("lookup_t", &l.t);
+        let (t_tag, t_tag_cmt) = commit_fixed("lookup_t_tag", &l.t_tag);
+        LookupProverKey {
+            sel,
+            sel_cmt,
+            tag,
+            tag_cmt,
+            t,
+            t_cmt,
+            t_tag,
+            t_tag_cmt,
+        }
+    });
+    let lookup_vk = lookup_pk.as_ref().map(|l| LookupVerifierKey {
+        sel_cmt: l.sel_cmt.clone(),
+        tag_cmt: l.tag_cmt.clone(),
+        t_cmt: l.t_cmt.clone(),
+        t_tag_cmt: l.t_tag_cmt.clone(),
+    });
     (
         ProverKey {
             pc_ck: ck,
             s_cmt: s_cmt.clone(),
             w_cmt: w_cmt.clone(),
+            lookup: lookup_pk,
             s,
             w,
         },
@@ -75,6 +105,7 @@ pub fn setup<'r, F: FftField, PC: PolynomialCommitment<F, DensePolynomial<F>>>(
             pc_vk: vk,
             s_cmt,
             w_cmt,
+            lookup: lookup_vk,
         },
     )
 }
@@ -195,6 +226,141 @@ where
         }
     }
 
+    /// Prove that f sums to zero over the domain, using its partial sums t.
+    fn prove_zero_sum<D: EvaluationDomain<F>>(
+        &self,
+        f: &LabeledPolynomial<F, DensePolynomial<F>>,
+        f_cmt: &LabeledCommitment<PC::Commitment>,
+        f_rand: &PC::Randomness,
+        domain: D,
+    ) -> SumProof<PC::Commitment, (F, PC::Proof)> {
+        let timer = start_timer!(|| "prove_zero_sum");
+        let t_evals = {
+            let mut t = f.evaluate_over_domain_by_ref(domain);
+            for i in 1..t.evals.len() {
+                let prev = t.evals[i - 1];
+                t.evals[i] += prev;
+            }
+            t
+        };
+        // t(wX) - t(X) - f(wX) has degree less than the domain's size, and vanishes on it, so it
+        // is zero: unlike for products, there is no quotient.
+        let (t_cmt, t, t_rand) = self
+            .commit("sum_t", t_evals.interpolate(), None, None)
+            .unwrap();
+        let w = domain.element(1);
+        let k = domain.size();
+        let r = self.fs_rng.borrow_mut().gen::<F>();
+        let t_wr_open = self.eval(&t, &t_rand, &t_cmt, w * r).unwrap();
+        let t_r_open = self.eval(&t, &t_rand, &t_cmt, r).unwrap();
+        let t_wk_open = self
+            .eval(&t, &t_rand, &t_cmt, domain.element(k - 1))
+            .unwrap();
+        let f_wr_open = self.eval(&f, &f_rand, &f_cmt, w * r).unwrap();
+        end_timer!(timer);
+        SumProof {
+            t_cmt: t_cmt.commitment,
+            t_wk_open,
+            t_r_open,
+            t_wr_open,
+            f_wr_open,
+        }
+    }
+
+    /// Prove that each looked-up wire of p is in its table, given the lookup counts m.
+    fn prove_lookups<D: EvaluationDomain<F>>(
+        &self,
+        p: &LabeledPolynomial<F, DensePolynomial<F>>,
+        p_cmt: &LabeledCommitment<PC::Commitment>,
+        p_rand: &PC::Randomness,
+        m: &DensePolynomial<F>,
+        dom: D,
+    ) -> LookupProof<PC::Commitment, (F, PC::Proof)> {
+        let timer = start_timer!(|| "prove_lookups");
+        let key = self.pk.lookup.as_ref().expect("no lookups in the prover key");
+        let (m_cmt, m, m_rand) = self.commit("lookup_m", m.clone(), None, None).unwrap();
+        let g = self.fs_rng.borrow_mut().gen::<F>();
+        let b = self.fs_rng.borrow_mut().gen::<F>();
+        // b - F(X) and b - T(X)
+        let b_f = &(&(p.polynomial() + &(key.tag.polynomial() * &g)) * &-F::one()) + &b;
+        let b_t = &(&(key.t.polynomial() + &(key.t_tag.polynomial() * &g)) * &-F::one()) + &b;
+        let h_evals = {
+            let sel_evals = key.sel.evaluate_over_domain_by_ref(dom);
+            let m_evals = m.evaluate_over_domain_by_ref(dom);
+            let b_f_evals = b_f.evaluate_over_domain_by_ref(dom);
+            let b_t_evals = b_t.evaluate_over_domain_by_ref(dom);
+            &(&sel_evals / &b_f_evals) - &(&m_evals / &b_t_evals)
+        };
+        let (h_cmt, h, h_rand) = self
+            .commit("lookup_h", h_evals.interpolate(), None, None)
+            .unwrap();
+        let h_sum_pf = self.prove_zero_sum(&h, &h_cmt, &h_rand, dom);
+        let q_timer = start_timer!(|| "q");
+        let q = {
+            // The numerator has degree about 3|dom|, and Q about 2|dom|, so work over a coset of a
+            // larger domain, and cut Q down to its degree: every party's share then has the same
+            // number of coefficients.
+            let big = GeneralEvaluationDomain::<F>::new(3 * dom.size()).unwrap();
+            let coset_evals = |p: &DensePolynomial<F>| {
+                let mut e = p.coeffs.clone();
+                big.coset_fft_in_place(&mut e);
+                e
+            };
+            let h_v = coset_evals(&h);
+            let b_f_v = coset_evals(&b_f);
+            let b_t_v = coset_evals(&b_t);
+            let sel_v = coset_evals(&key.sel);
+            let m_v = coset_evals(&m);
+            // H(b - F)(b - T) - Sel(b - T) + M(b - F)
+            let h_b_f_v = big.mul_polynomials_in_evaluation_domain(&h_v, &b_f_v);
+            let mut d_v = big.mul_polynomials_in_evaluation_domain(&h_b_f_v, &b_t_v);
+            let sel_b_t_v = big.mul_polynomials_in_evaluation_domain(&sel_v, &b_t_v);
+            let m_b_f_v = big.mul_polynomials_in_evaluation_domain(&m_v, &b_f_v);
+            ark_std::cfg_iter_mut!(d_v)
+                .zip(sel_b_t_v)
+                .zip(m_b_f_v)
+                .for_each(|((d, a), b)| *d += b - a);
+            let mut z_inv_v: Vec<F> = {
+                let g = F::multiplicative_generator();
+                (0..big.size())
+                    .map(|i| dom.evaluate_vanishing_polynomial(g * big.element(i)))
+                    .collect()
+            };
+            ark_ff::batch_inversion(&mut z_inv_v);
+            let mut q_v = big.mul_polynomials_in_evaluation_domain(&d_v, &z_inv_v);
+            big.coset_ifft_in_place(&mut q_v);
+            q_v.truncate(2 * dom.size() - 2);
+            DensePolynomial::from_coefficients_vec(q_v)
+        };
+        end_timer!(q_timer);
+        let (q_cmt, q, q_rand) = self.commit("lookup_q", q, None, None).unwrap();
+        let x = self.fs_rng.borrow_mut().gen::<F>();
+        let empty = PC::Randomness::empty();
+        let p_open = self.eval(p, p_rand, p_cmt, x).unwrap();
+        let m_open = self.eval(&m, &m_rand, &m_cmt, x).unwrap();
+        let h_open = self.eval(&h, &h_rand, &h_cmt, x).unwrap();
+        let q_open = self.eval(&q, &q_rand, &q_cmt, x).unwrap();
+        let sel_open = self.eval(&key.sel, &empty, &key.sel_cmt, x).unwrap();
+        let tag_open = self.eval(&key.tag, &empty, &key.tag_cmt, x).unwrap();
+        let t_open = self.eval(&key.t, &empty, &key.t_cmt, x).unwrap();
+        let t_tag_open = self.eval(&key.t_tag, &empty, &key.t_tag_cmt, x).unwrap();
+        end_timer!(timer);
+        LookupProof {
+            m_cmt: m_cmt.commitment,
+            h_cmt: h_cmt.commitment,
+            h_sum_pf,
+            q_cmt: q_cmt.commitment,
+            p_open,
+            m_open,
+            h_open,
+            q_open,
+            sel_open,
+            tag_open,
+            t_open,
+            t_tag_open,
+        }
+    }
+
     /// Prove that p(X) = p(w(X)) on the domain.
     fn prove_wiring<D: EvaluationDomain<F>>(
         &self,
@@ -419,11 +585,16 @@ where
         let public = self.prove_public(&p, &p_cmt, &p_rand, circ);
         let gates = self.prove_gates(&p, &p_cmt, &p_rand, circ);
         let wiring = self.prove_wiring(&p, &p_cmt, &p_rand, circ.domains.wires);
+        let lookup = circ.lookups.as_ref().map(|l| {
+            let m = l.m.as_ref().expect("missing lookup counts");
+            self.prove_lookups(&p, &p_cmt, &p_rand, m, circ.domains.wires)
+        });
         Proof {
             p_cmt: p_cmt.commitment,
             wiring,
             gates,
             public,
+            lookup,
         }
     }
 }
@@ -472,6 +643,26 @@ where
         // Check total product is 1
         assert_eq!(t_wk, F::one());
     }
+    fn verify_zero_sum<D: EvaluationDomain<F>>(
+        &self,
+        f_cmt: &LabeledCommitment<PC::Commitment>,
+        pf: SumProof<PC::Commitment, (F, PC::Proof)>,
+        domain: D,
+    ) {
+        let k = domain.size();
+        let w = domain.element(1);
+        let t_cmt = self.recv_commit("sum_t", pf.t_cmt, None);
+        let r = self.fs_rng.borrow_mut().gen::<F>();
+        // Check commitments
+        let f_wr = self.check(f_cmt, w * r, &pf.f_wr_open);
+        let t_r = self.check(&t_cmt, r, &pf.t_r_open);
+        let t_wr = self.check(&t_cmt, w * r, &pf.t_wr_open);
+        let t_wk = self.check(&t_cmt, domain.element(k - 1), &pf.t_wk_open);
+        // Check partial sum
+        assert_eq!(t_wr - t_r, f_wr, "Partial sum failure");
+        // Check total sum is 0
+        assert_eq!(t_wk, F::zero());
+    }
     /// Receive a commitment
     ///
     /// Produces a (commitment, labeled_poly, randomness) triple.
@@ -521,6 +712,42 @@ where
         self.verify_public(&circ, &p, pf.public, public);
         self.verify_gates(&p, &circ, pf.gates);
         self.verify_wiring(&p, circ.domains.wires, pf.wiring);
+        match (&circ.lookups, pf.lookup) {
+            (Some(_), Some(lookup)) => self.verify_lookups(&p, circ.domains.wires, lookup),
+            (None, None) => {}
+            (Some(_), None) => panic!("Missing lookup proof"),
+            (None, Some(_)) => panic!("Unexpected lookup proof"),
+        }
+    }
+
+    fn verify_lookups<D: EvaluationDomain<F>>(
+        &self,
+        p_cmt: &LabeledCommitment<PC::Commitment>,
+        dom: D,
+        pf: LookupProof<PC::Commitment, (F, PC::Proof)>,
+    ) {
+        let key = self.vk.lookup.as_ref().expect("no lookups in the verifier key");
+        let m_cmt = self.recv_commit("lookup_m", pf.m_cmt, None);
+        let g = self.fs_rng.borrow_mut().gen::<F>();
+        let b = self.fs_rng.borrow_mut().gen::<F>();
+        let h_cmt = self.recv_commit("lookup_h", pf.h_cmt, None);
+        self.verify_zero_sum(&h_cmt, pf.h_sum_pf, dom);
+        let q_cmt = self.recv_commit("lookup_q", pf.q_cmt, None);
+        let x = self.fs_rng.borrow_mut().gen::<F>();
+        let p = self.check(p_cmt, x, &pf.p_open);
+        let m = self.check(&m_cmt, x, &pf.m_open);
+        let h = self.check(&h_cmt, x, &pf.h_open);
+        let q = self.check(&q_cmt, x, &pf.q_open);
+        let sel = self.check(&key.sel_cmt, x, &pf.sel_open);
+        let tag = self.check(&key.tag_cmt, x, &pf.tag_open);
+        let t = self.check(&key.t_cmt, x, &pf.t_open);
+        let t_tag = self.check(&key.t_tag_cmt, x, &pf.t_tag_open);
+        let b_f = b - (p + g * tag);
+        let b_t = b - (t + g * t_tag);
+        assert_eq!(
+            h * b_f * b_t - sel * b_t + m * b_f,
+            q * dom.evaluate_vanishing_polynomial(x)
+        );
     }
 
     fn verify_public(
@@ -637,6 +864,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use relations::{flat::*, structured::*};
 
     type E = ark_bls12_377::Bls12_377;
     type F = ark_bls12_377::Fr;
@@ -669,4 +897,62 @@ mod tests {
         let pf = Pl::prove(&pk, &circ, zk_rng);
         Pl::verify(&vk, &v_circ, pf, &public);
     }
+
+    fn prove_and_verify_lookups(c: PlonkCircuit<F>, out: F) {
+        let public: HashMap<String, F> = vec![("out".to_owned(), out)].into_iter().collect();
+        let circ = CircuitLayout::from_circuit(&c);
+        let v_circ = {
+            let mut t = circ.clone();
+            t.p = None;
+            t
+        };
+        let setup_rng = &mut ark_std::test_rng();
+        let zk_rng = &mut ark_std::test_rng();
+        let srs = Pl::universal_setup(circ.domains.gates.size(), setup_rng);
+        let (pk, vk) = Pl::circuit_setup(&srs, &v_circ);
+        let pf = Pl::prove(&pk, &circ, zk_rng);
+        assert!(pf.lookup.is_some());
+        Pl::verify(&vk, &v_circ, pf, &public);
+    }
+
+    #[test]
+    fn plonk_lookup_test() {
+        let mut c =
+            PlonkCircuit::<F>::new_range_checked_squaring_circuit(2, 8, Some(F::from(3u64)));
+        c.count_lookups();
+        prove_and_verify_lookups(c, F::from(81u64));
+    }
+
+    #[test]
+    fn plonk_two_tables_test() {
+        // a + b + a, with a in {1, 2, 3} and in {0, 1}, and b in {10, 20}
+        let mut c = PlonkCircuit::<F>::new(true);
+        let small = c.new_table(vec![F::from(1u64), F::from(2u64), F::from(3u64)]);
+        let tens = c.new_table(vec![F::from(10u64), F::from(20u64)]);
+        let bits = c.new_table(vec![F::from(0u64), F::from(1u64)]);
+        let a = c.new_var(|| F::from(1u64));
+        let b = c.new_var(|| F::from(20u64));
+        let s = c.new_sum(a, b);
+        let out = c.new_sum(a, s);
+        c.new_lookup(a, small);
+        c.new_lookup(a, bits);
+        c.new_lookup(b, tens);
+        c.pad_to_power_of_2();
+        c.publicize_var(out, "out".to_owned());
+        c.count_lookups();
+        prove_and_verify_lookups(c, F::from(22u64));
+    }
+
+    #[test]
+    #[should_panic(expected = "Partial sum failure")]
+    fn plonk_lookup_bad_counts() {
+        let mut c =
+            PlonkCircuit::<F>::new_range_checked_squaring_circuit(2, 8, Some(F::from(3u64)));
+        c.count_lookups();
+        // Claim that 3 is looked up as 4
+        let counts = &mut c.lookup_counts.as_mut().unwrap()[0];
+        counts[3] -= F::from(1u64);
+        counts[4] += F::from(1u64);
+        prove_and_verify_lookups(c, F::from(81u64));
+    }
 }
@@ -29,6 +29,26 @@ pub struct CircuitLayout<F: FftField> {
     pub p: Option<DensePolynomial<F>>,
     /// Domains over which the polynomials have meaning
     pub domains: Domains<F>,
+    /// Lookups, if there are any
+    pub lookups: Option<LookupLayout<F>>,
+}
+
+/// Lookups, laid out over the wire domain.
+///
+/// Table `i` gets tag `i + 1`; a wire looked up in it has that tag, and must equal some table entry
+/// with that tag. Tag zero is for table padding, which is never looked up.
+#[derive(Clone)]
+pub struct LookupLayout<F: FftField> {
+    /// How many times each wire is looked up
+    pub sel: DensePolynomial<F>,
+    /// The tag of the table that each wire is looked up in
+    pub tag: DensePolynomial<F>,
+    /// The table entries, all tables back to back
+    pub t: DensePolynomial<F>,
+    /// The tag of each table entry
+    pub t_tag: DensePolynomial<F>,
+    /// How many times each table entry is looked up
+    pub m: Option<DensePolynomial<F>>,
 }
 
 impl<F: FftField> CircuitLayout<F> {
@@ -109,6 +129,11 @@ impl<F: FftField> CircuitLayout<F> {
             }
             p_evals.interpolate()
         });
+        let lookups = if c.lookups.is_empty() {
+            None
+        } else {
+            Some(LookupLayout::from_circuit(c, &vars_to_indices, &domains))
+        };
         let w = wire_evals.interpolate();
             #[cfg(debug_assertions)]
             {
@@ -133,6 +158,7 @@ impl<F: FftField> CircuitLayout<F> {
                 })
                 .collect(),
             vars_to_indices,
+            lookups,
         }
     }
 
@@ -269,13 +295,118 @@ impl<F: FftField> CircuitLayout<F> {
     }
 
 
+    fn check_lookups(&self) {
+        if let (Some(p), Some(l)) = (&self.p, &self.lookups) {
+            let dom = self.domains.wires;
+            let p_evals = p.evaluate_over_domain_by_ref(dom).evals;
+            let sel_evals = l.sel.evaluate_over_domain_by_ref(dom).evals;
+            let tag_evals = l.tag.evaluate_over_domain_by_ref(dom).evals;
+            let t_evals = l.t.evaluate_over_domain_by_ref(dom).evals;
+            let t_tag_evals = l.t_tag.evaluate_over_domain_by_ref(dom).evals;
+            for i in 0..dom.size() {
+                if !sel_evals[i].is_zero() {
+                    assert!(
+                        (0..dom.size())
+                            .any(|j| t_evals[j] == p_evals[i] && t_tag_evals[j] == tag_evals[i]),
+                        "pin {} is {}, which is not in the table tagged {}",
+                        i,
+                        p_evals[i],
+                        tag_evals[i]
+                    );
+                }
+            }
+        }
+    }
+
     pub fn check(&self, public_wires: &HashMap<String, F>) {
         self.check_gates();
         self.check_wiring();
         self.check_inputs(public_wires);
+        self.check_lookups();
+    }
+}
+
+impl<F: FftField> LookupLayout<F> {
+    fn from_circuit(
+        c: &PlonkCircuit<F>,
+        vars_to_indices: &HashMap<u32, Vec<usize>>,
+        domains: &Domains<F>,
+    ) -> Self {
+        let n_wires = domains.wires.size();
+        assert!(
+            c.n_table_rows() <= n_wires,
+            "{} table rows do not fit in {} wires",
+            c.n_table_rows(),
+            n_wires
+        );
+        // Each lookup goes on a pin of its variable that is not yet looked up in another table.
+        let mut sel = vec![F::zero(); n_wires];
+        let mut tag = vec![F::zero(); n_wires];
+        for (v, table) in &c.lookups {
+            let table_tag = F::from(*table as u64 + 1);
+            let i = *vars_to_indices[v]
+                .iter()
+                .find(|i| tag[**i].is_zero() || tag[**i] == table_tag)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "Variable {} is looked up in more tables than it has pins",
+                        v
+                    )
+                });
+            sel[i] += F::one();
+            tag[i] = table_tag;
+        }
+        let t_tag: Vec<F> = c
+            .tables
+            .iter()
+            .enumerate()
+            .flat_map(|(i, t)| iter::repeat(F::from(i as u64 + 1)).take(t.len()))
+            .chain(iter::repeat(F::zero()))
+            .take(n_wires)
+            .collect();
+        let t: Vec<F> = c
+            .tables
+            .iter()
+            .flatten()
+            .cloned()
+            .chain(iter::repeat(F::zero()))
+            .take(n_wires)
+            .collect();
+        let m = c.values.as_ref().map(|_| {
+            let counts = c
+                .lookup_counts
+                .as_ref()
+                .expect("The lookup counts are part of the witness, but are missing");
+            assert_eq!(counts.len(), c.tables.len());
+            let m: Vec<F> = counts
+                .iter()
+                .zip(&c.tables)
+                .flat_map(|(m, t)| {
+                    assert_eq!(m.len(), t.len());
+                    m.iter().cloned()
+                })
+                .chain(iter::repeat(F::zero()))
+                .take(n_wires)
+                .collect();
+            interpolate_over(m, domains.wires)
+        });
+        LookupLayout {
+            sel: interpolate_over(sel, domains.wires),
+            tag: interpolate_over(tag, domains.wires),
+            t: interpolate_over(t, domains.wires),
+            t_tag: interpolate_over(t_tag, domains.wires),
+            m,
+        }
     }
 }
 
+fn interpolate_over<F: FftField>(
+    evals: Vec<F>,
+    domain: MixedRadixEvaluationDomain<F>,
+) -> DensePolynomial<F> {
+    Evaluations::from_vec_and_domain(evals, domain).interpolate()
+}
+
 /// We assume a power-of-two number of gates.
 /// We use a 2^r*3-sized domain for wires and a 2^r-sized domain for gates.
 #[derive(Clone, Debug)]
@@ -292,6 +423,10 @@ impl<F: FftField> Domains<F> {
             "We require the scalar field's multiplicative group to have a subgroup of order 3"
         );
         let n = c.n_gates();
+        assert!(
+            c.n_table_rows() <= 3 * n,
+            "The tables do not fit in the wires; pad the circuit"
+        );
         let gates = Radix2EvaluationDomain::new(n).expect("gate domain");
         let wires = MixedRadixEvaluationDomain::new(3 * n).expect("wire domain");
         assert!(3 * gates.size() == wires.size());
@@ -333,8 +468,7 @@ mod tests {
     fn circuit_polys() {
         for steps in &[1, 3] {
             let c = PlonkCircuit::<F>::new_squaring_circuit(*steps, None);
-            let d = Domains::from_circuit(&c);
-            let polys = CircuitLayout::from_circuit(&c, &d);
+            let polys = CircuitLayout::from_circuit(&c);
             polys.check_connection_degree(3);
         }
     }
@@ -345,10 +479,30 @@ mod tests {
             let c = PlonkCircuit::<F>::new_squaring_circuit(*steps, Some(start));
             let res = (0..*steps).fold(start, |a, _| a * a);
             let public: HashMap<String, F> = vec![("out".to_owned(), res)].into_iter().collect();
-            let d = Domains::from_circuit(&c);
-            let polys = CircuitLayout::from_circuit(&c, &d);
+            let polys = CircuitLayout::from_circuit(&c);
             polys.check_connection_degree(3);
             polys.check(&public);
         }
     }
+
+    #[test]
+    fn lookup_check() {
+        let start = F::from(3u64);
+        let mut c = PlonkCircuit::<F>::new_range_checked_squaring_circuit(2, 8, Some(start));
+        c.count_lookups();
+        let public: HashMap<String, F> = vec![("out".to_owned(), F::from(81u64))]
+            .into_iter()
+            .collect();
+        let polys = CircuitLayout::from_circuit(&c);
+        assert!(polys.lookups.is_some());
+        polys.check(&public);
+    }
+
+    #[test]
+    #[should_panic(expected = "not in table")]
+    fn lookup_out_of_range() {
+        // 3^(2^3) = 6561 does not fit in 8 bits
+        let start = F::from(3u64);
+        PlonkCircuit::<F>::new_range_checked_squaring_circuit(3, 8, Some(start)).count_lookups();
+    }
 }
@@ -7,6 +7,12 @@ pub struct PlonkCircuit<F: Field> {
     pub prods: Vec<(Var, Var, Var)>,
     pub sums: Vec<(Var, Var, Var)>,
     pub values: Option<Vec<F>>,
+    /// Public lookup tables
+    pub tables: Vec<Vec<F>>,
+    /// Lookups, as (variable, table) pairs: each variable's value must be in its table
+    pub lookups: Vec<(Var, usize)>,
+    /// For each table, how many times each of its entries is looked up. Part of the witness.
+    pub lookup_counts: Option<Vec<Vec<F>>>,
 }
 
 type Var = u32;
@@ -19,6 +25,9 @@ impl<F: Field> PlonkCircuit<F> {
             prods: Vec::new(),
             sums: Vec::new(),
             values: if values { Some(Vec::new()) } else { None },
+            tables: Vec::new(),
+            lookups: Vec::new(),
+            lookup_counts: None,
         }
     }
     pub fn new_var(&mut self, value: impl FnOnce() -> F) -> Var {
@@ -57,11 +66,53 @@ impl<F: Field> PlonkCircuit<F> {
         self.publicize_var(v, name);
         v
     }
+    /// Add a public table, returning its index.
+    pub fn new_table(&mut self, entries: Vec<F>) -> usize {
+        assert!(!entries.is_empty(), "Cannot look up into an empty table!");
+        self.tables.push(entries);
+        self.tables.len() - 1
+    }
+    /// Require that the value of `v` is an entry of `table`.
+    ///
+    /// `v` must be an input or output of some gate.
+    pub fn new_lookup(&mut self, v: Var, table: usize) {
+        assert!(table < self.tables.len(), "No table {}", table);
+        self.lookups.push((v, table));
+    }
+    /// Count, from the values, how many times each table entry is looked up, and set
+    /// `lookup_counts`.
+    ///
+    /// This compares values, so it is only for public ones: under MPC, the witness's owner should
+    /// count in the clear, and the counts be shared into `lookup_counts`.
+    pub fn count_lookups(&mut self) {
+        let values = self.values.as_ref().expect("Cannot count lookups without values");
+        let mut counts: Vec<Vec<F>> = self
+            .tables
+            .iter()
+            .map(|t| vec![F::zero(); t.len()])
+            .collect();
+        for (v, table) in &self.lookups {
+            let value = &values[*v as usize];
+            let j = self.tables[*table]
+                .iter()
+                .position(|e| e == value)
+                .unwrap_or_else(|| {
+                    panic!("Variable {} is {}, which is not in table {}", v, value, table)
+                });
+            counts[*table][j] += F::one();
+        }
+        self.lookup_counts = Some(counts);
+    }
+    /// The number of rows that the tables take up
+    pub fn n_table_rows(&self) -> usize {
+        self.tables.iter().map(|t| t.len()).sum()
+    }
     pub fn n_gates(&self) -> usize {
         self.prods.len() + self.sums.len()
     }
     pub fn pad_to_power_of_2(&mut self) {
-        let n = self.n_gates().next_power_of_two();
+        // There are three wires per gate, and the tables are laid out over the wires.
+        let n = std::cmp::max(self.n_gates(), (self.n_table_rows() + 2) / 3).next_power_of_two();
         assert!(self.n_vars > 0, "Cannot pad an empty circuit!");
         for _ in self.n_gates()..n {
             let v = self.n_vars - 1;
@@ -79,4 +130,22 @@ impl<F: Field> PlonkCircuit<F> {
         self_.publicize_var(v, "out".to_owned());
         self_
     }
+    /// A squaring circuit, whose intermediate values are all checked to be less than
+    /// `2^bits`, by lookups into a table of `0..2^bits`.
+    ///
+    /// The lookup counts are left to the caller (see [PlonkCircuit::count_lookups]).
+    pub fn new_range_checked_squaring_circuit(steps: usize, bits: u32, start: Option<F>) -> Self {
+        let mut self_ = PlonkCircuit::new(start.is_some());
+        let table = self_.new_table((0..1u64 << bits).map(F::from).collect());
+        let mut v = self_.new_var(|| start.unwrap());
+        for _ in 0..steps {
+            let next = self_.new_prod(v, v);
+            self_.new_lookup(v, table);
+            v = next;
+        }
+        self_.new_lookup(v, table);
+        self_.pad_to_power_of_2();
+        self_.publicize_var(v, "out".to_owned());
+        self_
+    }
 }
@@ -22,6 +22,19 @@ impl<C: Reveal, O: Reveal> Reveal for ProductProof<C, O> {
         (C, q_cmt), (C, t_cmt), (O, t_wk_open), (O, t_r_open), (O, t_wr_open), (O, f_wr_open), (O, q_r_open));
 }
 
+impl<C: Reveal, O: Reveal> Reveal for SumProof<C, O> {
+    type Base = SumProof<C::Base, O::Base>;
+    struct_reveal_impl!(SumProof<C, O>, SumProof;
+        (C, t_cmt), (O, t_wk_open), (O, t_r_open), (O, t_wr_open), (O, f_wr_open));
+}
+
+impl<C: Reveal, O: Reveal> Reveal for LookupProof<C, O> {
+    type Base = LookupProof<C::Base, O::Base>;
+    struct_reveal_impl!(LookupProof<C, O>, LookupProof;
+        (C, m_cmt), (C, h_cmt), (SumProof<C, O>, h_sum_pf), (C, q_cmt), (O, p_open), (O, m_open),
+        (O, h_open), (O, q_open), (O, sel_open), (O, tag_open), (O, t_open), (O, t_tag_open));
+}
+
 impl<C: Reveal, O: Reveal> Reveal for WiringProof<C, O> {
     type Base = WiringProof<C::Base, O::Base>;
     struct_reveal_impl!(WiringProof<C, O>, WiringProof;
@@ -34,7 +47,8 @@ impl<F: Reveal, C: Reveal, O: Reveal> Reveal for Proof<F, C, O> {
         (C, p_cmt),
         (WiringProof<C, (F, O)>, wiring),
         (GateProof<C, (F, O)>, gates),
-        (PublicProof<C, (F, O)>, public)
+        (PublicProof<C, (F, O)>, public),
+        (Option<LookupProof<C, (F, O)>>, lookup)
     );
 }
 
@@ -50,5 +64,16 @@ impl<E: PairingEngine, S: PairingShare<E>> Reveal
         marlin_pc::Commitment<E>,
         marlin_pc::CommitterKey<E>,
     >;
-    struct_reveal_simp_impl!(ProverKey; w, s, w_cmt, s_cmt, pc_ck);
+    struct_reveal_simp_impl!(ProverKey; w, s, w_cmt, s_cmt, lookup, pc_ck);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal
+    for LookupProverKey<
+        <MpcPairingEngine<E, S> as PairingEngine>::Fr,
+        marlin_pc::Commitment<MpcPairingEngine<E, S>>,
+    >
+{
+    type Base = LookupProverKey<<E as PairingEngine>::Fr, marlin_pc::Commitment<E>>;
+    struct_reveal_simp_impl!(LookupProverKey;
+        sel, sel_cmt, tag, tag_cmt, t, t_cmt, t_tag, t_tag_cmt);
 }
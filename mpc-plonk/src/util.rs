@@ -1,5 +1,5 @@
 ///! Extra algebra utils
-use ark_ff::{FftField, FromBytes, ToBytes, PubUniformRand};
+use ark_ff::{FftField, FromBytes, InversionQueue, ToBytes, PubUniformRand};
 use ark_poly::univariate::DensePolynomial;
 use ark_poly::UVPolynomial;
 use ark_std::marker::PhantomData;
@@ -17,17 +17,27 @@ pub fn shift<F: FftField>(mut f: DensePolynomial<F>, a: F) -> DensePolynomial<F>
     f
 }
 
+/// The polynomial of degree less than `points.len()` through `points`, whose abscissae must be
+/// distinct.
 pub fn interpolate<F: FftField>(points: &[(F, F)]) -> DensePolynomial<F> {
     let k = points.len();
-    let mut scaled_lagrange_basis: Vec<DensePolynomial<F>> = (0..k)
+    // The differences of abscissae are inverted together: over shares, in one batch.
+    let mut queue = InversionQueue::new();
+    let ranges: Vec<_> = (0..k)
         .map(|j| {
-            let mut basis = (0..k).filter(|i| *i != j).fold(
+            let xj = points[j].0;
+            queue.extend((0..k).filter(|m| *m != j).map(|m| xj - points[m].0))
+        })
+        .collect();
+    let inverses = queue.invert();
+    let mut scaled_lagrange_basis: Vec<DensePolynomial<F>> = (0..k)
+        .zip(ranges)
+        .map(|(j, range)| {
+            let mut basis = (0..k).filter(|i| *i != j).zip(&inverses[range]).fold(
                 DensePolynomial::from_coefficients_vec(vec![F::one()]),
-                |acc, m| {
+                |acc, (m, d)| {
                     let xm = points[m].0;
-                    let xj = points[j].0;
-                    let d = (xj - xm).inverse().unwrap();
-                    acc.naive_mul(&DensePolynomial::from_coefficients_vec(vec![-xm * d, d]))
+                    acc.naive_mul(&DensePolynomial::from_coefficients_vec(vec![-xm * d, *d]))
                 },
             );
             let yj = points[j].1;
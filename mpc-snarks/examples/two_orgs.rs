@@ -0,0 +1,330 @@
+//! Two organizations prove a joint statement about their private ledgers.
+//!
+//! Org A (party 0) and org B (party 1) each hold a CSV of `account,amount` rows. Together they
+//! prove, with collaborative Marlin, that their amounts are all below `2^AMOUNT_BITS` and add up to
+//! a published total, without either learning the other's rows. The king writes the proof to an
+//! [Envelope] and the statement to a file, which anyone can then check with `verify`.
+//!
+//! ```sh
+//! cargo build --release --example two_orgs
+//! for p in 0 1; do
+//!   csv=examples/data/org_$([ $p = 0 ] && echo a || echo b).csv
+//!   target/release/examples/two_orgs prove --hosts data/2 --party $p --csv $csv --out /tmp &
+//! done; wait
+//! target/release/examples/two_orgs verify --out /tmp
+//! ```
+//!
+//! With `--pool`, deriving the witness (the amounts' bits) draws its random bits and Beaver
+//! triples from a randomness pool (see the `pool` binary), accounted under the session
+//! `two-orgs`. The Marlin prover does not use the pool: it trims and bounds polynomials by their
+//! shared coefficients, which only works with the default (dummy) triples.
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::{Field, PrimeField};
+use ark_marlin::{IndexProverKey, IndexVerifierKey, Marlin, Proof};
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::marlin::marlin_pc::MarlinKZG10;
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::test_rng;
+use blake2::Blake2s;
+use mpc_algebra::{
+    channel::{self, MpcSerNet},
+    preprocessing, MpcField, MpcPairingEngine, Reveal, SpdzPairingShare,
+};
+use mpc_net::{handshake, MpcMultiNet, MpcNet};
+use mpc_snarks::envelope::{self, Envelope, Header};
+use mpc_snarks::inputs::PublicInputs;
+use structopt::StructOpt;
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// Amounts are integers in `[0, 2^AMOUNT_BITS)`.
+const AMOUNT_BITS: usize = 40;
+
+const SESSION: &str = "two-orgs";
+
+type E = Bls12_377;
+type S = SpdzPairingShare<E>;
+type MFr = MpcField<Fr, <S as mpc_algebra::PairingShare<E>>::FrShare>;
+type Pc<E> = MarlinKZG10<E, DensePolynomial<<E as ark_ec::PairingEngine>::Fr>>;
+type LocalMarlin = Marlin<Fr, Pc<E>, Blake2s>;
+type MpcMarlin = Marlin<MFr, Pc<MpcPairingEngine<E, S>>, Blake2s>;
+
+/// Public inputs are how many rows each organization has, and their total.
+///
+/// Each amount is the sum of its bits, which are boolean; the amounts add up to the total.
+#[derive(Clone)]
+struct LedgerCircuit<F: Field> {
+    rows: [usize; 2],
+    total: Option<F>,
+    amounts: Vec<Option<F>>,
+    bits: Vec<Vec<Option<F>>>,
+}
+
+impl<F: Field> LedgerCircuit<F> {
+    fn without_data(rows: [usize; 2]) -> Self {
+        let n = rows[0] + rows[1];
+        Self {
+            rows,
+            total: None,
+            amounts: vec![None; n],
+            bits: vec![vec![None; AMOUNT_BITS]; n],
+        }
+    }
+
+    /// `(constraints, variables, non-zero entries)`, for Marlin's universal setup.
+    fn marlin_bounds(rows: [usize; 2]) -> (usize, usize, usize) {
+        let size = (rows[0] + rows[1]) * (AMOUNT_BITS + 2) + 4;
+        (size, size, AMOUNT_BITS * size)
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for LedgerCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        for &rows in &self.rows {
+            cs.new_input_variable(|| Ok(ConstraintF::from(rows as u64)))?;
+        }
+        let total =
+            cs.new_input_variable(|| self.total.ok_or(SynthesisError::AssignmentMissing))?;
+        let mut sum = lc!();
+        for (amount, bits) in self.amounts.iter().zip(&self.bits) {
+            let amount =
+                cs.new_witness_variable(|| amount.ok_or(SynthesisError::AssignmentMissing))?;
+            let mut bit_sum = lc!();
+            let mut coeff = ConstraintF::one();
+            for b in bits {
+                let b = cs.new_witness_variable(|| b.ok_or(SynthesisError::AssignmentMissing))?;
+                cs.enforce_constraint(lc!() + b, lc!() + b, lc!() + b)?;
+                bit_sum = bit_sum + (coeff, b);
+                coeff.double_in_place();
+            }
+            cs.enforce_constraint(lc!() + amount, lc!() + Variable::One, bit_sum)?;
+            sum = sum + amount;
+        }
+        cs.enforce_constraint(sum, lc!() + Variable::One, lc!() + total)?;
+        Ok(())
+    }
+}
+
+fn statement<F: PrimeField>(rows: [usize; 2], total: u64) -> PublicInputs<F> {
+    PublicInputs::new()
+        .u64("rows from A", rows[0] as u64)
+        .u64("rows from B", rows[1] as u64)
+        .u64("total", total)
+}
+
+/// An element holding a `u64`.
+fn to_u64<F: PrimeField>(x: F) -> u64 {
+    x.into_repr().as_ref()[0]
+}
+
+/// The amounts in a CSV file with an `account,amount` header.
+fn load_amounts(path: &Path) -> Vec<u64> {
+    let csv = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Could not read {}: {}", path.display(), e));
+    csv.lines()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let amount = line.rsplit(',').next().unwrap().trim();
+            let amount: u64 = amount
+                .parse()
+                .unwrap_or_else(|e| panic!("Bad amount in {:?}: {}", line, e));
+            assert!(amount >> AMOUNT_BITS == 0, "Amount too large in {:?}", line);
+            amount
+        })
+        .collect()
+}
+
+/// The universal setup and index for `rows`.
+///
+/// The SRS comes from a fixed seed, so that both organizations and the verifier agree on it; a
+/// deployment would load a published one instead.
+fn index(rows: [usize; 2]) -> (IndexProverKey<Fr, Pc<E>>, IndexVerifierKey<Fr, Pc<E>>) {
+    let (n_constraints, n_vars, n_non_zero) = LedgerCircuit::<Fr>::marlin_bounds(rows);
+    let srs =
+        LocalMarlin::universal_setup(n_constraints, n_vars, n_non_zero, &mut test_rng()).unwrap();
+    LocalMarlin::index(&srs, LedgerCircuit::without_data(rows)).unwrap()
+}
+
+fn header(rows: [usize; 2], n_parties: usize, started_at: u64) -> Header {
+    Header {
+        curve: "bls12_377".to_owned(),
+        proof_system: "marlin-kzg".to_owned(),
+        circuit_hash: envelope::circuit_hash(LedgerCircuit::<Fr>::without_data(rows)),
+        n_parties,
+        started_at,
+        finished_at: envelope::unix_time(),
+    }
+}
+
+fn envelope_path(out: &Path) -> PathBuf {
+    out.join("proof.json")
+}
+
+fn statement_path(out: &Path) -> PathBuf {
+    out.join("statement.bin")
+}
+
+fn prove(hosts: &Path, party: usize, csv: &Path, out: &Path, pool: Option<&Path>) {
+    let mine = load_amounts(csv);
+    MpcMultiNet::init_from_file(hosts.to_str().unwrap(), party);
+    assert_eq!(
+        MpcMultiNet::n_parties(),
+        2,
+        "this example is for two organizations"
+    );
+    if let Err(e) = handshake::check_params::<MpcMultiNet>(&[("example", "two_orgs".to_owned())]) {
+        panic!("Parties disagree on parameters: {}", e);
+    }
+    if let Some(dir) = pool {
+        let pool = preprocessing::RandomnessPool::open(dir, party)
+            .unwrap_or_else(|e| panic!("Could not open pool {}: {}", dir.display(), e));
+        let session = preprocessing::PoolSession::new(pool, SESSION, 4096)
+            .unwrap_or_else(|e| panic!("Could not start pool session: {}", e));
+        preprocessing::install(session);
+    }
+    MFr::init_protocol();
+
+    // How many rows each organization has is public; what is in them is not.
+    let counts = MpcMultiNet::broadcast(&(mine.len() as u64));
+    let rows = [counts[0] as usize, counts[1] as usize];
+    println!("Rows: {} from A, {} from B", rows[0], rows[1]);
+
+    // Each amount is shared by its owner: the owner's share is the amount, the other's is zero.
+    let amounts: Vec<MFr> = (0..2)
+        .flat_map(|owner| {
+            let mine = &mine;
+            (0..rows[owner]).map(move |i| {
+                let amount = if owner == party { mine[i] } else { 0 };
+                MFr::from_add_shared(Fr::from(amount))
+            })
+        })
+        .collect();
+    let bits: Vec<Vec<MFr>> = amounts
+        .iter()
+        .map(|a| a.bit_decomposition(AMOUNT_BITS))
+        .collect();
+    let total = MFr::sum_and_open(amounts.clone());
+    println!("Total: {}", to_u64(total));
+    if let Some(session) = preprocessing::uninstall() {
+        println!("Pool usage ({}): {:?}", SESSION, session.used());
+    }
+
+    let (pk, vk) = index(rows);
+    let circ = LedgerCircuit {
+        rows,
+        // The total is public, but the prover needs the whole assignment shared.
+        total: Some(MFr::from_public(total).into_shared()),
+        amounts: amounts.into_iter().map(Some).collect(),
+        bits: bits
+            .into_iter()
+            .map(|b| b.into_iter().map(Some).collect())
+            .collect(),
+    };
+    let started_at = envelope::unix_time();
+    let proof: Proof<Fr, Pc<E>> = channel::without_cheating(|| {
+        MpcMarlin::prove(&IndexProverKey::from_public(pk), circ, &mut test_rng())
+            .unwrap()
+            .reveal()
+    });
+    let statement = statement(rows, to_u64(total));
+    assert!(LocalMarlin::verify(&vk, &statement.to_vec(), &proof, &mut test_rng()).unwrap());
+
+    if MpcMultiNet::am_king() {
+        let env = Envelope::seal(header(rows, 2, started_at), &proof, &statement.to_vec()).unwrap();
+        env.save(&envelope_path(out))
+            .unwrap_or_else(|e| panic!("Could not write {}: {}", out.display(), e));
+        let mut f = File::create(statement_path(out)).unwrap();
+        statement.serialize(&mut f).unwrap();
+        println!(
+            "Wrote {} and {}",
+            envelope_path(out).display(),
+            statement_path(out).display()
+        );
+    }
+    MFr::deinit_protocol();
+    MpcMultiNet::deinit();
+}
+
+fn verify(out: &Path) {
+    let f = File::open(statement_path(out)).unwrap();
+    let statement = PublicInputs::<Fr>::deserialize(f).unwrap();
+    let count = |i: usize| to_u64(statement.inputs()[i].values[0]) as usize;
+    let rows = [count(0), count(1)];
+
+    let env = Envelope::load(&envelope_path(out)).unwrap();
+    if let Err(e) = env.check(&header(rows, 0, 0)) {
+        panic!("Envelope is not for this statement: {}", e);
+    }
+    if let Err(e) = statement.check(&env.public_inputs::<Fr>().unwrap()) {
+        panic!("Proof is not for this statement: {}", e);
+    }
+    let (_, vk) = index(rows);
+    let proof = env.proof::<Proof<Fr, Pc<E>>>().unwrap();
+    assert!(
+        LocalMarlin::verify(&vk, &statement.to_vec(), &proof, &mut test_rng()).unwrap(),
+        "Invalid proof"
+    );
+    for input in statement.inputs() {
+        println!("{}: {}", input.name, to_u64(input.values[0]));
+    }
+    println!("Verified: proof by {} parties", env.header.n_parties);
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "two_orgs",
+    about = "Two organizations prove a joint ledger total"
+)]
+enum Opt {
+    /// Prove, as one of the organizations
+    Prove {
+        /// File with list of hosts
+        #[structopt(long, parse(from_os_str))]
+        hosts: PathBuf,
+
+        /// 0 for org A, 1 for org B
+        #[structopt(long)]
+        party: usize,
+
+        /// This organization's `account,amount` rows
+        #[structopt(long, parse(from_os_str))]
+        csv: PathBuf,
+
+        /// Directory to write the proof and statement to (by org A)
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+
+        /// Directory of a randomness pool to draw preprocessing material from
+        #[structopt(long, parse(from_os_str))]
+        pool: Option<PathBuf>,
+    },
+    /// Check a proof and statement written by `prove`
+    Verify {
+        /// Directory holding the proof and statement
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+    },
+}
+
+fn main() {
+    env_logger::init();
+    match Opt::from_args() {
+        Opt::Prove {
+            hosts,
+            party,
+            csv,
+            out,
+            pool,
+        } => prove(&hosts, party, &csv, &out, pool.as_deref()),
+        Opt::Verify { out } => verify(&out),
+    }
+}
@@ -0,0 +1,242 @@
+//! Which party's data each constraint touches, for audits of collaborative proofs.
+//!
+//! A circuit marks each witness variable that holds some party's data with its [Owner], by calling
+//! [tag] as it allocates it; tags only take effect while [audit] synthesizes the circuit, and cost
+//! nothing otherwise. The [AuditReport] then lists, per owner, its variables and the constraints
+//! they appear in, and which constraints combine data from more than one party. Variables that no
+//! one tagged (intermediate values, usually) are reported together, as untagged.
+use ark_ff::Field;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, SynthesisError, SynthesisMode,
+    Variable,
+};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+/// Whose data a variable holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Owner {
+    /// One party's input.
+    Party(usize),
+    /// Data that the parties computed or hold together, and no one of them owns.
+    Joint,
+}
+
+impl Display for Owner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Owner::Party(i) => write!(f, "party {}", i),
+            Owner::Joint => write!(f, "joint"),
+        }
+    }
+}
+
+thread_local! {
+    /// The tags of the circuit that [audit] is synthesizing on this thread, if any.
+    static TAGS: RefCell<Option<BTreeMap<usize, Owner>>> = RefCell::new(None);
+}
+
+/// Tag witness variable `var` as holding `owner`'s data, if an [audit] is running. Instance
+/// variables are public, and have no owner.
+///
+/// Panics if `var` is already tagged with another owner.
+pub fn tag(var: Variable, owner: Owner) {
+    let index = match var {
+        Variable::Witness(i) => i,
+        _ => return,
+    };
+    TAGS.with(|tags| {
+        if let Some(tags) = tags.borrow_mut().as_mut() {
+            if let Some(old) = tags.insert(index, owner) {
+                assert_eq!(
+                    old, owner,
+                    "witness {} is tagged as both {} and {}",
+                    index, old, owner
+                );
+            }
+        }
+    })
+}
+
+/// [tag] each of `vars`.
+pub fn tag_all(vars: &[Variable], owner: Owner) {
+    vars.iter().for_each(|v| tag(*v, owner))
+}
+
+/// How one owner's data (or the untagged variables) is used.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OwnerUse {
+    /// `None` for the untagged variables.
+    pub owner: Option<Owner>,
+    /// How many witness variables hold the data.
+    pub variables: usize,
+    /// The constraints in which any of them appear, in order.
+    pub constraints: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct AuditReport {
+    pub constraints: usize,
+    pub witness_variables: usize,
+    /// Per owner, in order, and then the untagged variables, if there are any.
+    pub owners: Vec<OwnerUse>,
+    /// The constraints in which more than one party's data appear.
+    pub mixed: Vec<usize>,
+}
+
+impl Display for AuditReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} constraints over {} witness variables",
+            self.constraints, self.witness_variables
+        )?;
+        for u in &self.owners {
+            let owner = u.owner.map_or("untagged".to_string(), |o| o.to_string());
+            writeln!(
+                f,
+                "  {:<12} {:>8} variables in {:>8} constraints",
+                owner,
+                u.variables,
+                u.constraints.len()
+            )?;
+        }
+        write!(
+            f,
+            "  {} constraints combine more than one party's data",
+            self.mixed.len()
+        )
+    }
+}
+
+/// Synthesize `circ` (without its assignment), collecting its tags, by witness index.
+pub(crate) fn synthesize_tagged<F: Field>(
+    circ: impl ConstraintSynthesizer<F>,
+) -> Result<(ConstraintMatrices<F>, BTreeMap<usize, Owner>), SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    TAGS.with(|tags| *tags.borrow_mut() = Some(BTreeMap::new()));
+    let synthesized = circ.generate_constraints(cs.clone());
+    let tags = TAGS.with(|tags| tags.borrow_mut().take().unwrap());
+    synthesized?;
+    cs.finalize();
+    Ok((cs.to_matrices().unwrap(), tags))
+}
+
+/// Synthesize `circ` (without its assignment, which it need not have) and report which owners'
+/// data its constraints touch.
+pub fn audit<F: Field>(circ: impl ConstraintSynthesizer<F>) -> Result<AuditReport, SynthesisError> {
+    let (matrices, tags) = synthesize_tagged(circ)?;
+
+    let n_instance = matrices.num_instance_variables;
+    // The owner of a witness column (`None` if untagged); instance columns have none.
+    let owner_of = |column: usize| {
+        column
+            .checked_sub(n_instance)
+            .map(|w| tags.get(&w).cloned())
+    };
+    let mut uses: BTreeMap<Option<Owner>, BTreeSet<usize>> = BTreeMap::new();
+    let mut mixed = Vec::new();
+    for i in 0..matrices.num_constraints {
+        let rows = [&matrices.a[i], &matrices.b[i], &matrices.c[i]];
+        let owners: BTreeSet<Option<Owner>> = rows
+            .iter()
+            .flat_map(|row| row.iter().filter_map(|(_, column)| owner_of(*column)))
+            .collect();
+        let parties = owners
+            .iter()
+            .filter(|o| matches!(o, Some(Owner::Party(_))))
+            .count();
+        if parties > 1 {
+            mixed.push(i);
+        }
+        for o in owners {
+            uses.entry(o).or_default().insert(i);
+        }
+    }
+
+    let mut variables: BTreeMap<Option<Owner>, usize> = BTreeMap::new();
+    for w in 0..matrices.num_witness_variables {
+        *variables.entry(tags.get(&w).cloned()).or_default() += 1;
+    }
+    // `None` sorts first; report the untagged variables last.
+    let mut owners: Vec<OwnerUse> = variables
+        .into_iter()
+        .map(|(owner, variables)| OwnerUse {
+            owner,
+            variables,
+            constraints: uses
+                .remove(&owner)
+                .unwrap_or_default()
+                .into_iter()
+                .collect(),
+        })
+        .collect();
+    let untagged = owners.iter().take_while(|u| u.owner.is_none()).count();
+    owners.rotate_left(untagged);
+    Ok(AuditReport {
+        constraints: matrices.num_constraints,
+        witness_variables: matrices.num_witness_variables,
+        owners,
+        mixed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::{lc, r1cs::ConstraintSystemRef};
+
+    /// Party 0's `x` and party 1's `y`, with `x * x = x2`, and `x * y = z` for a public `z`.
+    #[derive(Clone)]
+    struct Product;
+
+    impl ConstraintSynthesizer<Fr> for Product {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let z = cs.new_input_variable(|| Ok(Fr::from(8u8)))?;
+            let x = cs.new_witness_variable(|| Ok(Fr::from(2u8)))?;
+            let y = cs.new_witness_variable(|| Ok(Fr::from(4u8)))?;
+            let x2 = cs.new_witness_variable(|| Ok(Fr::from(4u8)))?;
+            tag(x, Owner::Party(0));
+            tag(y, Owner::Party(1));
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + x2)?;
+            cs.enforce_constraint(lc!() + x, lc!() + y, lc!() + z)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reports_owners_constraints() {
+        let report = audit(Product).unwrap();
+        assert_eq!((report.constraints, report.witness_variables), (2, 3));
+        let party = |i| Some(Owner::Party(i));
+        let summary: Vec<_> = report
+            .owners
+            .iter()
+            .map(|u| (u.owner, u.variables, u.constraints.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (party(0), 1, vec![0, 1]),
+                (party(1), 1, vec![1]),
+                (None, 1, vec![0])
+            ]
+        );
+        // Only `x * y = z` combines both parties' data. Tags do not follow data: `x2` is
+        // untagged, though it is computed from party 0's `x`.
+        assert_eq!(report.mixed, vec![1]);
+        assert!(report.to_string().contains("party 1"));
+    }
+
+    #[test]
+    fn tags_only_apply_in_an_audit() {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        Product.generate_constraints(cs).unwrap();
+        assert!(TAGS.with(|t| t.borrow().is_none()));
+    }
+}
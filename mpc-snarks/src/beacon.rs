@@ -0,0 +1,386 @@
+//! Public randomness from a drand beacon, which no party gets to choose.
+//!
+//! The parties need public coins that all of them agree on: e.g., to combine constraints when
+//! checking a witness, or to batch the checks of a shared verification. By default these come
+//! from a fixed seed, which is fine for benchmarks but lets whoever chose the witness predict
+//! them. Instead, the coins can come from a round of a drand network that is published after the
+//! inputs are fixed.
+//!
+//! A drand round is a BLS signature, by the network's threshold key, on the round number (and,
+//! for chained networks, on the previous round's signature); its randomness is the signature's
+//! SHA-256 hash. [Beacon::verify] checks both against the network's [ChainInfo]. Both are read
+//! from the JSON that the network's HTTP API serves, at `/info` and `/public/<round>`
+//! respectively. Only drand's default schemes are supported, with keys in G1 and signatures in
+//! G2 of BLS12-381.
+use ark_bls12_381::{Bls12_381, Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{BigInteger, PrimeField, SquareRootField};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+mod hash_to_curve;
+
+pub use hash_to_curve::hash_to_g2;
+
+/// The domain separation tag with which drand hashes messages to G2.
+pub const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+#[derive(Debug)]
+pub enum BeaconError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// A key, signature or randomness is malformed.
+    Format(String),
+    /// The network uses a scheme other than drand's default ones.
+    Unsupported(String),
+    /// The signature is not the network's, on this round.
+    BadSignature(u64),
+    /// The randomness is not the hash of the signature.
+    BadRandomness(u64),
+}
+
+impl Display for BeaconError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BeaconError::Io(e) => write!(f, "{}", e),
+            BeaconError::Json(e) => write!(f, "malformed beacon JSON: {}", e),
+            BeaconError::Format(e) => write!(f, "malformed beacon: {}", e),
+            BeaconError::Unsupported(s) => write!(f, "unsupported drand scheme {:?}", s),
+            BeaconError::BadSignature(r) => write!(f, "round {} is not signed by the network", r),
+            BeaconError::BadRandomness(r) => {
+                write!(f, "round {}'s randomness is not its signature's hash", r)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BeaconError {}
+
+impl From<std::io::Error> for BeaconError {
+    fn from(e: std::io::Error) -> Self {
+        BeaconError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for BeaconError {
+    fn from(e: serde_json::Error) -> Self {
+        BeaconError::Json(e)
+    }
+}
+
+/// What identifies a drand network: its public key, and whether its rounds are chained.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub public_key: G1Affine,
+    /// Whether each round's signature also covers the previous round's.
+    pub chained: bool,
+}
+
+#[derive(Deserialize)]
+struct ChainInfoJson {
+    public_key: String,
+    /// Absent for networks that predate unchained rounds.
+    #[serde(rename = "schemeID", default)]
+    scheme_id: Option<String>,
+}
+
+impl ChainInfo {
+    pub fn from_json(json: &str) -> Result<Self, BeaconError> {
+        let info: ChainInfoJson = serde_json::from_str(json)?;
+        let chained = match info.scheme_id.as_deref() {
+            None | Some("pedersen-bls-chained") => true,
+            Some("pedersen-bls-unchained") => false,
+            Some(s) => return Err(BeaconError::Unsupported(s.into())),
+        };
+        Ok(Self {
+            public_key: g1_from_compressed(&hex(&info.public_key)?)?,
+            chained,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BeaconError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// One round of a drand network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Beacon {
+    pub round: u64,
+    pub randomness: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// The previous round's signature, which chained networks sign too.
+    pub previous_signature: Option<Vec<u8>>,
+}
+
+#[derive(Deserialize)]
+struct BeaconJson {
+    round: u64,
+    randomness: String,
+    signature: String,
+    #[serde(default)]
+    previous_signature: Option<String>,
+}
+
+impl Beacon {
+    pub fn from_json(json: &str) -> Result<Self, BeaconError> {
+        let b: BeaconJson = serde_json::from_str(json)?;
+        Ok(Self {
+            round: b.round,
+            randomness: hex(&b.randomness)?,
+            signature: hex(&b.signature)?,
+            previous_signature: b.previous_signature.as_deref().map(hex).transpose()?,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, BeaconError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// What the network signed for this round.
+    pub fn message(&self, chained: bool) -> Vec<u8> {
+        let mut h = Sha256::new();
+        if chained {
+            h.update(self.previous_signature.as_deref().unwrap_or_default());
+        }
+        h.update(self.round.to_be_bytes());
+        h.finalize().to_vec()
+    }
+
+    /// Check that the network signed this round, and that its randomness follows.
+    pub fn verify(&self, info: &ChainInfo) -> Result<(), BeaconError> {
+        if info.chained && self.previous_signature.is_none() {
+            return Err(BeaconError::Format(format!(
+                "round {} of a chained network has no previous signature",
+                self.round
+            )));
+        }
+        if Sha256::digest(&self.signature)[..] != self.randomness[..] {
+            return Err(BeaconError::BadRandomness(self.round));
+        }
+        let signature = g2_from_compressed(&self.signature)?;
+        let hashed = hash_to_g2(&self.message(info.chained), DST);
+        let g1 = G1Affine::prime_subgroup_generator();
+        if Bls12_381::pairing(g1, signature) != Bls12_381::pairing(info.public_key, hashed) {
+            return Err(BeaconError::BadSignature(self.round));
+        }
+        Ok(())
+    }
+
+    /// A generator of public coins, seeded by this round's randomness and `label`; give each use
+    /// its own label, so that they draw independent coins.
+    pub fn rng(&self, label: &str) -> StdRng {
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(
+            &Sha256::new()
+                .chain(&self.randomness)
+                .chain(label.as_bytes())
+                .finalize(),
+        );
+        StdRng::from_seed(seed)
+    }
+}
+
+fn hex(s: &str) -> Result<Vec<u8>, BeaconError> {
+    crate::envelope::from_hex(s).map_err(|_| BeaconError::Format(format!("bad hex {:?}", s)))
+}
+
+/// Points are compressed as in the Zcash format: big-endian `x` (for G2, `c1` then `c0`), with
+/// flags in the top three bits: compressed, infinity, and whether `y` is the larger root.
+const COMPRESSED: u8 = 1 << 7;
+const INFINITY: u8 = 1 << 6;
+const LARGER: u8 = 1 << 5;
+
+fn g1_from_compressed(bytes: &[u8]) -> Result<G1Affine, BeaconError> {
+    let (flags, x) = split_flags(bytes, 48)?;
+    if flags & INFINITY != 0 {
+        return Err(BeaconError::Format("public key at infinity".into()));
+    }
+    let x = fq(&x)?;
+    let y = (x * x * x + Fq::from(4u64))
+        .sqrt()
+        .ok_or_else(|| BeaconError::Format("public key is not on the curve".into()))?;
+    let larger = y.into_repr() > (-y).into_repr();
+    let y = if larger == (flags & LARGER != 0) {
+        y
+    } else {
+        -y
+    };
+    let p = G1Affine::new(x, y, false);
+    if !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(BeaconError::Format("public key is not in G1".into()));
+    }
+    Ok(p)
+}
+
+fn g2_from_compressed(bytes: &[u8]) -> Result<G2Affine, BeaconError> {
+    let (flags, x) = split_flags(bytes, 96)?;
+    if flags & INFINITY != 0 {
+        return Err(BeaconError::Format("signature at infinity".into()));
+    }
+    let x = Fq2::new(fq(&x[48..])?, fq(&x[..48])?);
+    let y = (x * x * x + Fq2::new(Fq::from(4u64), Fq::from(4u64)))
+        .sqrt()
+        .ok_or_else(|| BeaconError::Format("signature is not on the curve".into()))?;
+    let key = |y: Fq2| (y.c1.into_repr(), y.c0.into_repr());
+    let larger = key(y) > key(-y);
+    let y = if larger == (flags & LARGER != 0) {
+        y
+    } else {
+        -y
+    };
+    let p = G2Affine::new(x, y, false);
+    if !p.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(BeaconError::Format("signature is not in G2".into()));
+    }
+    Ok(p)
+}
+
+/// The flags of a compressed point of `len` bytes, and its `x` without them.
+fn split_flags(bytes: &[u8], len: usize) -> Result<(u8, Vec<u8>), BeaconError> {
+    if bytes.len() != len {
+        return Err(BeaconError::Format(format!(
+            "expected a point of {} bytes, found {}",
+            len,
+            bytes.len()
+        )));
+    }
+    let flags = bytes[0] & (COMPRESSED | INFINITY | LARGER);
+    if flags & COMPRESSED == 0 {
+        return Err(BeaconError::Format("point is not compressed".into()));
+    }
+    let mut x = bytes.to_vec();
+    x[0] &= !flags;
+    Ok((flags, x))
+}
+
+/// The element of `Fq` with big-endian encoding `bytes`, which must be canonical.
+fn fq(bytes: &[u8]) -> Result<Fq, BeaconError> {
+    let x = Fq::from_be_bytes_mod_order(bytes);
+    if x.into_repr().to_bytes_be() != bytes {
+        return Err(BeaconError::Format("coordinate is not reduced".into()));
+    }
+    Ok(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    /// The Zcash encoding of `p`, the inverse of [g1_from_compressed] or [g2_from_compressed].
+    fn compress(coords: &[Fq], y_larger: bool) -> Vec<u8> {
+        let mut bytes: Vec<u8> = coords
+            .iter()
+            .flat_map(|c| c.into_repr().to_bytes_be())
+            .collect();
+        bytes[0] |= COMPRESSED | if y_larger { LARGER } else { 0 };
+        bytes
+    }
+
+    fn compress_g2(p: G2Affine) -> Vec<u8> {
+        let key = |y: Fq2| (y.c1.into_repr(), y.c0.into_repr());
+        compress(&[p.x.c1, p.x.c0], key(p.y) > key(-p.y))
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn decompresses_generators() {
+        let g1 = hex("97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb").unwrap();
+        assert_eq!(
+            g1_from_compressed(&g1).unwrap(),
+            G1Affine::prime_subgroup_generator()
+        );
+        let g2 = hex("93e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8").unwrap();
+        let g2 = g2_from_compressed(&g2).unwrap();
+        assert_eq!(g2, G2Affine::prime_subgroup_generator());
+        assert_eq!(g2_from_compressed(&compress_g2(-g2)).unwrap(), -g2);
+    }
+
+    /// A network with a known secret key, and its signed round `round`.
+    fn network(chained: bool, round: u64) -> (ChainInfo, Beacon) {
+        let rng = &mut test_rng();
+        let sk = ark_bls12_381::Fr::rand(rng);
+        let pk = G1Affine::prime_subgroup_generator().mul(sk).into_affine();
+        let y_larger = pk.y.into_repr() > (-pk.y).into_repr();
+        let info = ChainInfo::from_json(&format!(
+            r#"{{"public_key": "{}", "period": 30, "schemeID": "{}"}}"#,
+            to_hex(&compress(&[pk.x], y_larger)),
+            if chained {
+                "pedersen-bls-chained"
+            } else {
+                "pedersen-bls-unchained"
+            },
+        ))
+        .unwrap();
+        assert_eq!(info.public_key, pk);
+        let mut beacon = Beacon {
+            round,
+            randomness: Vec::new(),
+            signature: Vec::new(),
+            previous_signature: if chained { Some(vec![7; 96]) } else { None },
+        };
+        let signature = hash_to_g2(&beacon.message(chained), DST).mul(sk);
+        beacon.signature = compress_g2(signature.into_affine());
+        beacon.randomness = Sha256::digest(&beacon.signature).to_vec();
+        let json = format!(
+            r#"{{"round": {}, "randomness": "{}", "signature": "{}"{}}}"#,
+            round,
+            to_hex(&beacon.randomness),
+            to_hex(&beacon.signature),
+            match &beacon.previous_signature {
+                Some(s) => format!(r#", "previous_signature": "{}""#, to_hex(s)),
+                None => String::new(),
+            }
+        );
+        assert_eq!(Beacon::from_json(&json).unwrap(), beacon);
+        (info, beacon)
+    }
+
+    #[test]
+    fn verifies_signed_rounds() {
+        for &chained in &[true, false] {
+            let (info, beacon) = network(chained, 1234);
+            assert!(beacon.verify(&info).is_ok());
+
+            let mut wrong_round = beacon.clone();
+            wrong_round.round += 1;
+            assert!(matches!(
+                wrong_round.verify(&info),
+                Err(BeaconError::BadSignature(1235))
+            ));
+
+            let mut wrong_randomness = beacon.clone();
+            wrong_randomness.randomness[0] ^= 1;
+            assert!(matches!(
+                wrong_randomness.verify(&info),
+                Err(BeaconError::BadRandomness(1234))
+            ));
+
+            let (other, _) = network(!chained, 1234);
+            assert!(beacon.verify(&other).is_err());
+        }
+        let (info, mut beacon) = network(true, 1);
+        beacon.previous_signature.as_mut().unwrap()[0] ^= 1;
+        assert!(matches!(
+            beacon.verify(&info),
+            Err(BeaconError::BadSignature(1))
+        ));
+    }
+
+    #[test]
+    fn labels_separate_coins() {
+        let (_, beacon) = network(false, 5);
+        let draw = |label| Fq::rand(&mut beacon.rng(label));
+        assert_eq!(draw("a"), draw("a"));
+        assert_ne!(draw("a"), draw("b"));
+    }
+}
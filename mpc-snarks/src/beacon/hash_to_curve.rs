@@ -0,0 +1,208 @@
+//! Hashing to BLS12-381's G2, as in RFC 9380 (suite `BLS12381G2_XMD:SHA-256_SSWU_RO_`).
+//!
+//! Messages are expanded with SHA-256 into two elements of `Fq2`, each mapped to the curve by the
+//! simplified SWU map (onto a curve 3-isogenous to G2's, then across the isogeny), and their sum
+//! is multiplied by the effective cofactor. This is not constant-time; it only hashes public
+//! messages.
+use ark_bls12_381::{Fq, Fq2, G2Affine, G2Projective};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, Field, One, PrimeField, SquareRootField, Zero};
+use sha2::{Digest, Sha256};
+
+/// The effective cofactor, which clears G2's cofactor.
+const H_EFF: &str = "0bc69f08f2ee75b3584c6a0ea91b352888e2a8e9145ad7689986ff031508ffe1329c2f178731db\
+                     956d82bf015d1212b02ec0ec69d7477c1ae954cbc06689f6a359894c0adebbf6b4e8020005aaa95551";
+
+/// The hash of `msg` to G2, with domain separation tag `dst`.
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> G2Affine {
+    let uniform = expand_message_xmd(msg, dst, 256);
+    let u: Vec<Fq> = uniform
+        .chunks(64)
+        .map(Fq::from_be_bytes_mod_order)
+        .collect();
+    let q0 = map_to_curve(Fq2::new(u[0], u[1]));
+    let q1 = map_to_curve(Fq2::new(u[2], u[3]));
+    (q0 + q1).mul(limbs(H_EFF)).into_affine()
+}
+
+/// `expand_message_xmd` with SHA-256: `len` pseudorandom bytes from `msg`.
+fn expand_message_xmd(msg: &[u8], dst: &[u8], len: usize) -> Vec<u8> {
+    assert!(dst.len() < 256 && len <= 255 * 32);
+    let dst_prime: Vec<u8> = dst.iter().cloned().chain(Some(dst.len() as u8)).collect();
+    let b0 = Sha256::new()
+        .chain([0u8; 64])
+        .chain(msg)
+        .chain((len as u16).to_be_bytes())
+        .chain([0u8])
+        .chain(&dst_prime)
+        .finalize();
+    let mut b = Sha256::new()
+        .chain(b0)
+        .chain([1u8])
+        .chain(&dst_prime)
+        .finalize();
+    let mut out = b.to_vec();
+    for i in 2..=len.div_ceil(32) {
+        let xored: Vec<u8> = b0.iter().zip(&b).map(|(x, y)| x ^ y).collect();
+        b = Sha256::new()
+            .chain(&xored)
+            .chain([i as u8])
+            .chain(&dst_prime)
+            .finalize();
+        out.extend_from_slice(&b);
+    }
+    out.truncate(len);
+    out
+}
+
+/// The simplified SWU map, followed by the 3-isogeny to G2's curve.
+fn map_to_curve(u: Fq2) -> G2Projective {
+    let a = Fq2::new(Fq::zero(), Fq::from(240u64));
+    let b = Fq2::new(Fq::from(1012u64), Fq::from(1012u64));
+    let z = -Fq2::new(Fq::from(2u64), Fq::from(1u64));
+    let g = |x: Fq2| x * x * x + a * x + b;
+    let u2 = u.square();
+    let tv1 = (z.square() * u2.square() + z * u2)
+        .inverse()
+        .unwrap_or_else(Fq2::zero);
+    let x1 = if tv1.is_zero() {
+        b * (z * a).inverse().unwrap()
+    } else {
+        -b * a.inverse().unwrap() * (Fq2::one() + tv1)
+    };
+    let (x, mut y) = match g(x1).sqrt() {
+        Some(y) => (x1, y),
+        None => {
+            let x2 = z * u2 * x1;
+            (x2, g(x2).sqrt().unwrap())
+        }
+    };
+    if sgn0(u) != sgn0(y) {
+        y = -y;
+    }
+    iso_map(x, y).into_projective()
+}
+
+/// The "sign" of `x`: the parity of its first nonzero coordinate.
+fn sgn0(x: Fq2) -> bool {
+    let (c0, c1) = (x.c0.into_repr(), x.c1.into_repr());
+    c0.is_odd() || (c0.is_zero() && c1.is_odd())
+}
+
+/// The 3-isogeny from the SWU map's curve to G2's, with the constants of RFC 9380, E.3.
+fn iso_map(x: Fq2, y: Fq2) -> G2Affine {
+    let k = |c0: &str, c1: &str| Fq2::new(fq(c0), fq(c1));
+    let x_num = [
+        k(
+            "05c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97d6",
+            "05c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97d6",
+        ),
+        k(
+            "00",
+            "11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71a",
+        ),
+        k(
+            "11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71e",
+            "08ab05f8bdd54cde190937e76bc3e447cc27c3d6fbd7063fcd104635a790520c0a395554e5c6aaaa9354ffffffffe38d",
+        ),
+        k(
+            "171d6541fa38ccfaed6dea691f5fb614cb14b4e7f4e810aa22d6108f142b85757098e38d0f671c7188e2aaaaaaaa5ed1",
+            "00",
+        ),
+    ];
+    let x_den = [
+        k(
+            "00",
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa63",
+        ),
+        k(
+            "0c",
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa9f",
+        ),
+        Fq2::one(),
+    ];
+    let y_num = [
+        k(
+            "1530477c7ab4113b59a4c18b076d11930f7da5d4a07f649bf54439d87d27e500fc8c25ebf8c92f6812cfc71c71c6d706",
+            "1530477c7ab4113b59a4c18b076d11930f7da5d4a07f649bf54439d87d27e500fc8c25ebf8c92f6812cfc71c71c6d706",
+        ),
+        k(
+            "00",
+            "05c759507e8e333ebb5b7a9a47d7ed8532c52d39fd3a042a88b58423c50ae15d5c2638e343d9c71c6238aaaaaaaa97be",
+        ),
+        k(
+            "11560bf17baa99bc32126fced787c88f984f87adf7ae0c7f9a208c6b4f20a4181472aaa9cb8d555526a9ffffffffc71c",
+            "08ab05f8bdd54cde190937e76bc3e447cc27c3d6fbd7063fcd104635a790520c0a395554e5c6aaaa9354ffffffffe38f",
+        ),
+        k(
+            "124c9ad43b6cf79bfbf7043de3811ad0761b0f37a1e26286b0e977c69aa274524e79097a56dc4bd9e1b371c71c718b10",
+            "00",
+        ),
+    ];
+    let y_den = [
+        k(
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa8fb",
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa8fb",
+        ),
+        k(
+            "00",
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffa9d3",
+        ),
+        k(
+            "12",
+            "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaa99",
+        ),
+        Fq2::one(),
+    ];
+    let eval = |coeffs: &[Fq2]| coeffs.iter().rev().fold(Fq2::zero(), |acc, c| acc * x + c);
+    let x_out = eval(&x_num) * eval(&x_den).inverse().unwrap();
+    let y_out = y * eval(&y_num) * eval(&y_den).inverse().unwrap();
+    G2Affine::new(x_out, y_out, false)
+}
+
+fn fq(hex: &str) -> Fq {
+    Fq::from_be_bytes_mod_order(&bytes(hex))
+}
+
+/// The little-endian 64-bit limbs of the big-endian `hex`, for [ProjectiveCurve::mul].
+fn limbs(hex: &str) -> Vec<u64> {
+    let be = bytes(hex);
+    be.rchunks(8)
+        .map(|c| c.iter().fold(0u64, |acc, b| acc << 8 | *b as u64))
+        .collect()
+}
+
+fn bytes(hex: &str) -> Vec<u8> {
+    crate::envelope::from_hex(hex).expect("malformed constant")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_as_rfc_9380() {
+        // RFC 9380, K.1.
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        assert_eq!(
+            expand_message_xmd(b"", dst, 32),
+            bytes("68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235")
+        );
+    }
+
+    #[test]
+    fn hashes_as_rfc_9380() {
+        // RFC 9380, J.10.1, for the empty message.
+        let p = hash_to_g2(b"", b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_");
+        let x = Fq2::new(
+            fq("0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a"),
+            fq("05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d"),
+        );
+        let y = Fq2::new(
+            fq("0503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92"),
+            fq("12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d6"),
+        );
+        assert_eq!(p, G2Affine::new(x, y, false));
+        assert!(p.is_in_correct_subgroup_assuming_on_curve());
+    }
+}
@@ -0,0 +1,357 @@
+//! Proving a long sequential computation in chunks, each small enough for one setup.
+//!
+//! A computation that repeats a [Fragment] many times (a hash chain, a long run of squarings)
+//! can outgrow one Groth16 setup, or the FFT domain of its field. Here it is cut into chunks of
+//! `steps` repetitions each, all the same circuit, so one key (whose size depends only on
+//! `steps`) proves them all.
+//!
+//! The state between chunks stays hidden. The proof carries a Pedersen commitment to each state
+//! (the one before the first chunk, to the computation's inputs, included), and each chunk's
+//! proof is linked, as in [crate::groth::link], to the commitments to its input state and its
+//! output state: the chunk's first witness variables are those states, so consecutive chunks
+//! agree on the state between them without revealing it. The commitment to the final state is
+//! not blinded, and the verifier recomputes it from the output, which is public.
+//!
+//! The chunks' Groth16 equations are checked together, as one product of pairings (see
+//! [link::pairings_hold]), so checking `k` chunks takes `k + 2` Miller loops and one final
+//! exponentiation rather than `k` of each. That aggregates the checks, not the proofs: the proof
+//! still grows with the number of chunks, as each has its own Groth16 proof and link.
+//!
+//! In MPC, the chunks are proven one after another in the same session, so they draw from the
+//! same preprocessing as a single proof would; the parties open only the commitments and what
+//! each link opens anyway.
+use crate::compose::{Fragment, Repeat, Wire};
+use crate::groth::link::{self, CommitmentKey, LinkedProof, LinkedProvingKey, LinkedVerifyingKey};
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, UniformRand, Zero};
+use ark_groth16::{prepare_verifying_key, PreparedVerifyingKey};
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+        SynthesisMode, Variable,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::Rng;
+use blake2::{Blake2s, Digest};
+use mpc_algebra::{struct_reveal_simp_impl, MpcPairingEngine, PairingShare, Reveal};
+use mpc_trait::MpcWire;
+
+/// `steps` repetitions of a fragment, as one circuit; see the [module docs](self). Its first
+/// witness variables are its input state, then its output state; it has no public inputs.
+#[derive(Clone, Debug)]
+pub struct Chunk<A, F: Field> {
+    pub fragment: A,
+    pub steps: usize,
+    /// The state before and after the chunk; `None`s during setup.
+    pub inputs: Vec<Option<F>>,
+    pub outputs: Vec<Option<F>>,
+}
+
+impl<F: Field, A: Fragment<F>> ConstraintSynthesizer<F> for Chunk<A, F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|v| Wire::witness(&cs, v))
+            .collect::<Result<_, _>>()?;
+        let outputs = self
+            .outputs
+            .into_iter()
+            .map(|v| Wire::witness(&cs, v))
+            .collect::<Result<Vec<_>, _>>()?;
+        let steps = Repeat {
+            fragment: self.fragment,
+            times: self.steps,
+        };
+        let computed = steps.synthesize(&cs, inputs)?;
+        if computed.len() != outputs.len() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        for (out, claimed) in computed.iter().zip(&outputs) {
+            cs.enforce_constraint(lc!() + out.var, lc!() + Variable::One, lc!() + claimed.var)?;
+        }
+        Ok(())
+    }
+}
+
+/// The state after `steps` repetitions of `fragment` on `inputs`.
+fn run<F: Field, A: Fragment<F>>(
+    fragment: A,
+    steps: usize,
+    inputs: &[F],
+) -> Result<Vec<F>, SynthesisError> {
+    let cs = ConstraintSystem::new_ref();
+    cs.set_mode(SynthesisMode::Prove {
+        construct_matrices: false,
+    });
+    let inputs = inputs
+        .iter()
+        .map(|&v| Wire::witness(&cs, Some(v)))
+        .collect::<Result<_, _>>()?;
+    let steps = Repeat {
+        fragment,
+        times: steps,
+    };
+    steps
+        .synthesize(&cs, inputs)?
+        .into_iter()
+        .map(|w| w.value.ok_or(SynthesisError::AssignmentMissing))
+        .collect()
+}
+
+/// The proving key for every chunk, and the key the states are committed under.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChunkedKey<E: PairingEngine> {
+    pub pk: LinkedProvingKey<E>,
+    pub ck: CommitmentKey<E::G1Affine>,
+}
+
+impl<E: PairingEngine> ChunkedKey<E> {
+    /// Set up chunks of `steps` repetitions of `fragment`, whose state is `width` values.
+    pub fn setup<A: Fragment<E::Fr>, R: Rng>(
+        fragment: A,
+        steps: usize,
+        width: usize,
+        rng: &mut R,
+    ) -> Result<Self, SynthesisError> {
+        let chunk = Chunk {
+            fragment,
+            steps,
+            inputs: vec![None; width],
+            outputs: vec![None; width],
+        };
+        Ok(Self {
+            pk: link::setup(chunk, 2 * width, rng)?,
+            ck: CommitmentKey::setup(width, rng),
+        })
+    }
+
+    /// How many values the state is.
+    pub fn width(&self) -> usize {
+        self.pk.n_committed() / 2
+    }
+
+    pub fn verifying_key(&self) -> ChunkedVerifyingKey<E> {
+        ChunkedVerifyingKey {
+            pvk: prepare_verifying_key(&self.pk.link_vk.vk),
+            vk: self.pk.link_vk.clone(),
+            ck: self.ck.clone(),
+        }
+    }
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ChunkedKey<MpcPairingEngine<E, S>> {
+    type Base = ChunkedKey<E>;
+    struct_reveal_simp_impl!(ChunkedKey; pk, ck);
+}
+
+/// What checking a [ChunkedProof] takes.
+pub struct ChunkedVerifyingKey<E: PairingEngine> {
+    pub pvk: PreparedVerifyingKey<E>,
+    pub vk: LinkedVerifyingKey<E>,
+    pub ck: CommitmentKey<E::G1Affine>,
+}
+
+/// A commitment to the state before each chunk, a proof per chunk, and the final state.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ChunkedProof<E: PairingEngine> {
+    /// The first commits to the computation's inputs.
+    pub states: Vec<E::G1Affine>,
+    /// Each linked to the commitments to the state before and after its chunk.
+    pub proofs: Vec<LinkedProof<E>>,
+    pub output: Vec<E::Fr>,
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ChunkedProof<MpcPairingEngine<E, S>> {
+    type Base = ChunkedProof<E>;
+    struct_reveal_simp_impl!(ChunkedProof; states, proofs, output);
+}
+
+impl<E: PairingEngine> ChunkedProof<E> {
+    /// The outputs of the whole computation: the state after its last chunk.
+    pub fn output(&self) -> &[E::Fr] {
+        &self.output
+    }
+
+    /// The commitment to the computation's inputs, which a data owner may hold.
+    pub fn input_commitment(&self) -> Option<E::G1Affine> {
+        self.states.first().cloned()
+    }
+
+    /// Check that every chunk's proof holds, and that each is linked to the commitments to the
+    /// state before and after its chunk, the last of which commits to the output.
+    pub fn verify(&self, vk: &ChunkedVerifyingKey<E>) -> Result<bool, SynthesisError> {
+        let width = self.output.len();
+        if self.proofs.is_empty()
+            || self.proofs.len() != self.states.len()
+            || 2 * width != vk.vk.commit_bases.len()
+        {
+            return Ok(false);
+        }
+        let mut states = self.states.clone();
+        states.push(vk.ck.commit(&self.output, E::Fr::zero()).into_affine());
+        for (i, proof) in self.proofs.iter().enumerate() {
+            if !link::link_holds(&vk.vk, &vk.ck, &states[i..i + 2], &[width, width], proof)? {
+                return Ok(false);
+            }
+        }
+        let proofs: Vec<&LinkedProof<E>> = self.proofs.iter().collect();
+        let inputs = vec![&[][..]; proofs.len()];
+        link::pairings_hold(&vk.pvk, &proofs, &inputs, &self.weights())
+    }
+
+    /// The chunks' weights in the combined check: 128 bits each, of a hash of the whole proof.
+    fn weights(&self) -> Vec<E::Fr> {
+        let mut bytes = b"chunked groth16 batch".to_vec();
+        self.serialize(&mut bytes).unwrap();
+        (0..self.proofs.len() as u64)
+            .map(|i| {
+                let mut h = Blake2s::new();
+                h.update(&bytes);
+                h.update(i.to_le_bytes());
+                let mut w = [0u8; 16];
+                w.copy_from_slice(&h.finalize()[..16]);
+                E::Fr::from(u128::from_le_bytes(w))
+            })
+            .collect()
+    }
+}
+
+/// Prove `chunks` chunks of `key`'s size in turn, starting from `inputs`.
+///
+/// This works for any engine: over an MPC engine, the inputs are shared and so is the proof,
+/// until it is revealed (but for the commitments and links, which are opened as they are made).
+/// Each chunk is synthesized twice, once to compute its outputs and once to prove it, so a
+/// fragment that multiplies shares does so twice.
+pub fn prove<E, A, R>(
+    key: &ChunkedKey<E>,
+    fragment: A,
+    steps: usize,
+    chunks: usize,
+    inputs: Vec<E::Fr>,
+    rng: &mut R,
+) -> Result<ChunkedProof<E>, SynthesisError>
+where
+    E: PairingEngine,
+    A: Fragment<E::Fr>,
+    R: Rng,
+{
+    let commit = |state: &[E::Fr], blinding| {
+        let mut c = key.ck.commit(state, blinding).into_affine();
+        c.publicize();
+        c
+    };
+    let mut proof = ChunkedProof {
+        states: Vec::with_capacity(chunks),
+        proofs: Vec::with_capacity(chunks),
+        output: Vec::new(),
+    };
+    let mut state = inputs;
+    let mut blinding = E::Fr::rand(rng);
+    let mut com = commit(&state, blinding);
+    for i in 0..chunks {
+        let next = run(fragment.clone(), steps, &state)?;
+        // The final state is public, so its commitment is not blinded.
+        let next_blinding = if i + 1 == chunks {
+            E::Fr::zero()
+        } else {
+            E::Fr::rand(rng)
+        };
+        let next_com = commit(&next, next_blinding);
+        let chunk = Chunk {
+            fragment: fragment.clone(),
+            steps,
+            inputs: state.iter().cloned().map(Some).collect(),
+            outputs: next.iter().cloned().map(Some).collect(),
+        };
+        proof.proofs.push(link::create_random_proof_split(
+            chunk,
+            &key.pk,
+            &key.ck,
+            &[com, next_com],
+            &[&state, &next],
+            &[blinding, next_blinding],
+            rng,
+        )?);
+        proof.states.push(com);
+        state = next;
+        blinding = next_blinding;
+        com = next_com;
+    }
+    proof.output = state;
+    Ok(proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::Square;
+    use ark_bls12_377::{Bls12_377, Fr};
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::MpcPairingEngine as ME;
+    use mpc_net::testing::run_parties;
+
+    const STEPS: usize = 4;
+    const CHUNKS: usize = 3;
+
+    /// `x^(2^n)`.
+    fn squarings(x: Fr, n: usize) -> Fr {
+        (0..n).fold(x, |x, _| x.square())
+    }
+
+    #[test]
+    fn chunks_link_up() {
+        let rng = &mut test_rng();
+        let key = ChunkedKey::<Bls12_377>::setup(Square, STEPS, 1, rng).unwrap();
+        let vk = key.verifying_key();
+        let x = Fr::rand(rng);
+        let mut proof = prove(&key, Square, STEPS, CHUNKS, vec![x], rng).unwrap();
+        assert_eq!(proof.output(), &[squarings(x, STEPS * CHUNKS)][..]);
+        assert!(proof.verify(&vk).unwrap());
+
+        let mut bytes = Vec::new();
+        proof.serialize(&mut bytes).unwrap();
+        assert_eq!(ChunkedProof::deserialize(&bytes[..]).unwrap(), proof);
+        // The states between chunks are not in the proof.
+        for n in 1..CHUNKS {
+            let mid = squarings(x, STEPS * n);
+            assert!(!bytes
+                .windows(32)
+                .any(|w| w == &ark_ff::to_bytes![mid].unwrap()[..]));
+        }
+
+        // Nor can another state be slipped in between chunks, or another output claimed.
+        let honest = proof.clone();
+        proof.states[1] = key.ck.commit(&[Fr::from(1u8)], Fr::rand(rng)).into_affine();
+        assert!(!proof.verify(&vk).unwrap());
+        proof = honest.clone();
+        proof.output[0] += Fr::from(1u8);
+        assert!(!proof.verify(&vk).unwrap());
+        proof = honest.clone();
+        proof.proofs.swap(0, 1);
+        assert!(!proof.verify(&vk).unwrap());
+        proof = honest;
+        proof.proofs.pop();
+        proof.states.pop();
+        assert!(!proof.verify(&vk).unwrap());
+    }
+
+    #[test]
+    fn hbc_chunked() {
+        run_parties("chunked::tests::hbc_chunked", 2, || {
+            let rng = &mut test_rng();
+            let key = ChunkedKey::<Bls12_377>::setup(Square, STEPS, 1, rng).unwrap();
+            let vk = key.verifying_key();
+            let mpc_key = ChunkedKey::<ME<Bls12_377>>::from_public(key);
+            let x = Fr::rand(rng);
+            let shared = Reveal::king_share(x, rng);
+            let proof = prove(&mpc_key, Square, STEPS, CHUNKS, vec![shared], rng)
+                .unwrap()
+                .reveal();
+            assert_eq!(proof.output(), &[squarings(x, STEPS * CHUNKS)][..]);
+            assert!(proof.verify(&vk).unwrap());
+        });
+    }
+}
@@ -0,0 +1,237 @@
+//! Circom's field operations, over shares.
+//!
+//! A circuit compiled by circom comes with a witness calculator (WebAssembly, or C++) that reads
+//! the input signals and computes every other signal through a small runtime of field operations,
+//! `Fr_add`, `Fr_mul`, and so on. [FrOp] maps the runtime's operations onto any [Field], so that
+//! over an [MpcField](mpc_algebra::MpcField) they compute on shares (multiplying with Beaver
+//! triples), and [Signals] holds what they compute, for a circuit over the same constraints to
+//! take as its witness.
+//!
+//! Only operations that are affine, or multiplications, are supported. The others branch on a
+//! value (comparisons, `Fr_isTrue`), take it apart (bitwise operations, shifts, integer division,
+//! `Fr_toInt`) or invert it; over shares, each of these would need a protocol of its own, and
+//! [FrOp::from_name] refuses them, naming the operation.
+//!
+//! This does not run a compiled calculator: a calculator's operations are replayed through
+//! [Signals] by the caller, as the test below does. The WebAssembly calculator cannot be run over
+//! shares at all, since its field operations are compiled into the module rather than imported,
+//! and running the C++ one would take a build of it against an `fr.hpp` that calls back here.
+use ark_ff::Field;
+use std::fmt::{self, Display, Formatter};
+
+/// An operation of circom's field runtime that can be computed over shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrOp {
+    Copy,
+    Neg,
+    Add,
+    Sub,
+    Mul,
+    Square,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CircomError {
+    /// The operation (by its name in circom's runtime) cannot be computed over shares.
+    Unsupported(String),
+    /// Not an operation of circom's runtime.
+    Unknown(String),
+    /// The signal was read before it was computed or set.
+    Unassigned(usize),
+    /// There is no signal with this number.
+    NoSignal(usize),
+    /// The operation was given this many arguments.
+    Arity(FrOp, usize),
+}
+
+impl Display for CircomError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CircomError::Unsupported(op) => write!(
+                f,
+                "{} branches on, decomposes or inverts its argument, which over shares is not \
+                 supported",
+                op
+            ),
+            CircomError::Unknown(op) => write!(f, "{} is not an operation of circom's runtime", op),
+            CircomError::Unassigned(i) => write!(f, "signal {} is read before it is assigned", i),
+            CircomError::NoSignal(i) => write!(f, "there is no signal {}", i),
+            CircomError::Arity(op, n) => write!(f, "{:?} does not take {} arguments", op, n),
+        }
+    }
+}
+
+impl std::error::Error for CircomError {}
+
+/// Circom's runtime operations that are not affine or multiplications.
+const UNSUPPORTED: &[&str] = &[
+    "Fr_inv",
+    "Fr_div",
+    "Fr_idiv",
+    "Fr_mod",
+    "Fr_pow",
+    "Fr_eq",
+    "Fr_neq",
+    "Fr_lt",
+    "Fr_gt",
+    "Fr_leq",
+    "Fr_geq",
+    "Fr_land",
+    "Fr_lor",
+    "Fr_lnot",
+    "Fr_band",
+    "Fr_bor",
+    "Fr_bxor",
+    "Fr_bnot",
+    "Fr_shl",
+    "Fr_shr",
+    "Fr_isTrue",
+    "Fr_toInt",
+];
+
+impl FrOp {
+    /// The operation that circom's runtime calls `name` (e.g., `Fr_mul`).
+    pub fn from_name(name: &str) -> Result<Self, CircomError> {
+        Ok(match name {
+            "Fr_copy" => FrOp::Copy,
+            "Fr_neg" => FrOp::Neg,
+            "Fr_add" => FrOp::Add,
+            "Fr_sub" => FrOp::Sub,
+            "Fr_mul" => FrOp::Mul,
+            "Fr_square" => FrOp::Square,
+            _ if UNSUPPORTED.contains(&name) => {
+                return Err(CircomError::Unsupported(name.to_owned()))
+            }
+            _ => return Err(CircomError::Unknown(name.to_owned())),
+        })
+    }
+
+    pub fn arity(self) -> usize {
+        match self {
+            FrOp::Copy | FrOp::Neg | FrOp::Square => 1,
+            FrOp::Add | FrOp::Sub | FrOp::Mul => 2,
+        }
+    }
+
+    /// The operation's result on `args`; over shares, a multiplication takes one round.
+    pub fn apply<F: Field>(self, args: &[F]) -> Result<F, CircomError> {
+        if args.len() != self.arity() {
+            return Err(CircomError::Arity(self, args.len()));
+        }
+        Ok(match self {
+            FrOp::Copy => args[0],
+            FrOp::Neg => -args[0],
+            FrOp::Add => args[0] + args[1],
+            FrOp::Sub => args[0] - args[1],
+            FrOp::Mul => args[0] * args[1],
+            FrOp::Square => args[0].square(),
+        })
+    }
+}
+
+/// A calculator's signals, numbered as circom numbers them: signal 0 is the constant one, then
+/// the outputs, the inputs, and the intermediate signals.
+#[derive(Clone, Debug)]
+pub struct Signals<F: Field> {
+    values: Vec<Option<F>>,
+}
+
+impl<F: Field> Signals<F> {
+    /// `n` signals, of which only the constant one is assigned.
+    pub fn new(n: usize) -> Self {
+        let mut values = vec![None; n.max(1)];
+        values[0] = Some(F::one());
+        Self { values }
+    }
+
+    pub fn set(&mut self, i: usize, value: F) -> Result<(), CircomError> {
+        *self.values.get_mut(i).ok_or(CircomError::NoSignal(i))? = Some(value);
+        Ok(())
+    }
+
+    pub fn get(&self, i: usize) -> Result<F, CircomError> {
+        self.values
+            .get(i)
+            .ok_or(CircomError::NoSignal(i))?
+            .ok_or(CircomError::Unassigned(i))
+    }
+
+    /// Apply `op` to the signals `args`, and assign the result to the signal `out`.
+    pub fn compute(&mut self, op: FrOp, args: &[usize], out: usize) -> Result<(), CircomError> {
+        let args = args
+            .iter()
+            .map(|&i| self.get(i))
+            .collect::<Result<Vec<F>, _>>()?;
+        let value = op.apply(&args)?;
+        self.set(out, value)
+    }
+
+    /// Every signal, in order: the full assignment, as circom's `.wtns` holds it.
+    pub fn witness(&self) -> Result<Vec<F>, CircomError> {
+        (0..self.values.len()).map(|i| self.get(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use mpc_algebra::honest_but_curious::MpcField as MF;
+    use mpc_algebra::Reveal;
+    use mpc_net::testing::run_parties;
+    use mpc_net::{MpcMultiNet, MpcNet};
+
+    /// What a calculator for `out <== a * b + a * a - b` does, with signals numbered
+    /// `[one, out, a, b, ab]`.
+    fn calculate<F: Field>(a: F, b: F) -> Result<Vec<F>, CircomError> {
+        let mut s = Signals::new(5);
+        s.set(2, a)?;
+        s.set(3, b)?;
+        s.compute(FrOp::from_name("Fr_mul")?, &[2, 3], 4)?;
+        s.compute(FrOp::from_name("Fr_square")?, &[2], 1)?;
+        s.compute(FrOp::from_name("Fr_add")?, &[4, 1], 1)?;
+        s.compute(FrOp::from_name("Fr_sub")?, &[1, 3], 1)?;
+        s.witness()
+    }
+
+    #[test]
+    fn hbc_calculates_over_shares() {
+        run_parties("circom::tests::hbc_calculates_over_shares", 2, || {
+            let (a, b) = (Fr::from(3u8), Fr::from(5u8));
+            let local = calculate(a, b).unwrap();
+            assert_eq!(local[1], Fr::from(19u8));
+            // Party 0 holds all of `a`, as its share.
+            let a_share = if MpcMultiNet::party_id() == 0 {
+                a
+            } else {
+                Fr::from(0u8)
+            };
+            let shared = calculate(MF::from_add_shared(a_share), MF::from_public(b)).unwrap();
+            let shared: Vec<Fr> = shared.into_iter().map(|x| x.reveal()).collect();
+            assert_eq!(shared, local);
+        });
+
+        assert_eq!(
+            FrOp::from_name("Fr_lt"),
+            Err(CircomError::Unsupported("Fr_lt".to_owned()))
+        );
+        assert!(matches!(
+            FrOp::from_name("Fr_nop"),
+            Err(CircomError::Unknown(_))
+        ));
+        assert_eq!(
+            FrOp::Mul.apply(&[Fr::from(1u8)]),
+            Err(CircomError::Arity(FrOp::Mul, 1))
+        );
+        let mut s = Signals::<Fr>::new(3);
+        assert_eq!(
+            s.compute(FrOp::Neg, &[2], 1),
+            Err(CircomError::Unassigned(2))
+        );
+        assert_eq!(s.set(3, Fr::from(1u8)), Err(CircomError::NoSignal(3)));
+        assert_eq!(
+            s.compute(FrOp::Copy, &[0], 3),
+            Err(CircomError::NoSignal(3))
+        );
+    }
+}
@@ -0,0 +1,2643 @@
+//! The `proof` command line: standard and MPC proofs of a registry of computations.
+//!
+//! Each computation is a family of circuits (see [BenchCircuit]), registered by name as a
+//! [Plugin]. Besides the built-in ones, a downstream crate can add its own, by linking this crate
+//! into a binary of its own:
+//!
+//! ```ignore
+//! fn main() {
+//!     mpc_snarks::cli::main(vec![Plugin::new::<MyCircuit>("mycircuit")]);
+//! }
+//! ```
+//!
+//! which then takes `-c mycircuit`, with all of `proof`'s other options.
+#![allow(dead_code)]
+#![allow(unused_imports)]
+use ark_ec::PairingEngine;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_groth16;
+use ark_marlin::AHPForR1CS;
+use ark_serialize::CanonicalSerialize;
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+        SynthesisMode, Variable,
+    },
+};
+use ark_std::rand::Rng;
+use blake2::Blake2s;
+use clap::arg_enum;
+use log::{debug, warn};
+use mpc_algebra::com::WitnessCommitments;
+use mpc_algebra::security::{self, SecurityMode, Shortcut};
+use mpc_algebra::{channel, preprocessing, MpcField, MpcPairingEngine, PairingShare, Reveal};
+use mpc_net::{
+    end_mpc_timer, handshake, metrics, start_mpc_timer, timer, MpcMultiNet, MpcNet, MpcTwoNet,
+};
+use mpc_plonk::relations::structured::PlonkCircuit;
+use crate::beacon::{Beacon, ChainInfo};
+use crate::compose::{Fragment, Square, Step};
+use crate::dv::DvKey;
+use crate::envelope::{
+    self, Envelope, EnvelopeError, Header, KeyFile, PhaseRecord, Provenance, TestVector,
+};
+use crate::limits::{self, LimitError};
+use crate::outputs::RevealOutputs;
+use crate::phase::{Phase, Phases};
+use crate::srs;
+use crate::witness_cache::{WitnessCache, WitnessKey};
+use structopt::StructOpt;
+
+use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+mod auction;
+mod daemon;
+mod ecdsa;
+mod range;
+mod sha256;
+mod shuffle;
+
+const TIMED_SECTION_LABEL: &str = "timed section";
+
+/// The scalar field of the MPC version of `E`.
+pub type MpcFr<E, S> = <MpcPairingEngine<E, S> as PairingEngine>::Fr;
+
+/// Whether parties commit to their witness shares before proving.
+static COMMIT_WITNESS: AtomicBool = AtomicBool::new(false);
+
+/// If enabled, commit to this party's shares of `circ`'s witness, and exchange commitments.
+fn commit_witness<E: PairingEngine, S: PairingShare<E>, C>(circ: &C) -> Option<WitnessCommitments>
+where
+    C: ConstraintSynthesizer<MpcField<E::Fr, S::FrShare>> + Clone,
+{
+    if !COMMIT_WITNESS.load(Ordering::Relaxed) {
+        return None;
+    }
+    let timer = start_mpc_timer!(|| "commit to witness");
+    let cs = ConstraintSystem::new_ref();
+    cs.set_mode(SynthesisMode::Prove {
+        construct_matrices: false,
+    });
+    circ.clone().generate_constraints(cs.clone()).unwrap();
+    let cs = cs.borrow().unwrap();
+    let commitments = WitnessCommitments::exchange(&cs.witness_assignment);
+    end_mpc_timer!(timer);
+    Some(commitments)
+}
+
+/// Move on to phase `to`: a driver that enters phases out of order is wrong, so this panics.
+fn enter(phases: &mut Phases, to: Phase) {
+    if let Err(e) = phases.enter(to) {
+        panic!("{}", e);
+    }
+}
+
+/// Fixed-seed randomness, which MPC runs draw their inputs, setup, and coins from, so that every
+/// party draws the same. Secure mode refuses it where it is drawn.
+static FIXED_SEED: Shortcut = Shortcut::new("randomness from a fixed seed (test_rng)");
+
+/// The same randomness at every party, and in every run.
+fn test_rng() -> ark_std::rand::rngs::StdRng {
+    FIXED_SEED.take();
+    ark_std::test_rng()
+}
+
+/// Randomness for a computation that one process does alone, which no other party needs to
+/// repeat: the system's in secure mode, and otherwise the same as [test_rng]'s, so that runs
+/// repeat.
+fn local_rng() -> ark_std::rand::rngs::StdRng {
+    use ark_std::rand::SeedableRng;
+    if security::security_mode() == SecurityMode::Secure {
+        return ark_std::rand::rngs::StdRng::from_rng(rand::thread_rng()).unwrap();
+    }
+    test_rng()
+}
+
+/// Randomness of this party's own, which differs from every other party's: for what the parties
+/// contribute to jointly, like the hiding randomness of shared commitments (see
+/// [ark_poly_commit::kzg10::Randomness::joint_rand]). In secure mode, it is the system's; in
+/// benchmark mode, it is from a fixed seed, so that runs repeat.
+fn party_rng() -> ark_std::rand::rngs::StdRng {
+    use ark_std::rand::SeedableRng;
+    if security::security_mode() == SecurityMode::Secure {
+        return ark_std::rand::rngs::StdRng::from_rng(rand::thread_rng()).unwrap();
+    }
+    FIXED_SEED.take();
+    ark_std::rand::rngs::StdRng::seed_from_u64(MpcMultiNet::party_id() as u64)
+}
+
+lazy_static! {
+    /// A verified drand round to draw public coins from, rather than a fixed seed.
+    static ref BEACON: Mutex<Option<Beacon>> = Mutex::new(None);
+}
+
+/// The public coins for `label`: from the beacon if there is one, and otherwise from the same
+/// fixed seed at every party.
+fn public_rng(label: &str) -> ark_std::rand::rngs::StdRng {
+    match &*BEACON.lock().unwrap() {
+        Some(beacon) => beacon.rng(label),
+        None => test_rng(),
+    }
+}
+
+/// Whether the king reports whose data each constraint touches, before proving.
+static AUDIT: AtomicBool = AtomicBool::new(false);
+
+/// Whether the king reports which multiplications each party could compute alone.
+static LOCALITY: AtomicBool = AtomicBool::new(false);
+
+/// If enabled, print (at the king) which party's data each of `circ`'s constraints touches, as
+/// its witness variables are tagged (see [crate::audit]), and which constraints each party could
+/// compute alone (see [crate::locality]). Each synthesizes `circ` once more, but does not
+/// communicate.
+fn audit_witness<C: ConstraintSynthesizer<F> + Clone, F: Field>(circ: &C) {
+    if !MpcMultiNet::am_king() {
+        return;
+    }
+    if AUDIT.load(Ordering::Relaxed) {
+        let report = crate::audit::audit(circ.clone()).unwrap();
+        println!("Audit: {}", report);
+    }
+    if LOCALITY.load(Ordering::Relaxed) {
+        let report = crate::locality::locality(circ.clone()).unwrap();
+        println!("Locality: {}", report);
+    }
+}
+
+/// Whether parties check the witness against the constraints before proving.
+static CHECK_WITNESS: AtomicBool = AtomicBool::new(false);
+
+/// If enabled, check that the shared witness in `circ` satisfies it, aborting if not.
+fn check_witness<E: PairingEngine, S: PairingShare<E>, C>(circ: &C)
+where
+    C: ConstraintSynthesizer<MpcField<E::Fr, S::FrShare>> + Clone,
+{
+    if !CHECK_WITNESS.load(Ordering::Relaxed) {
+        return;
+    }
+    let timer = start_mpc_timer!(|| "check witness");
+    let coeff_rng = &mut public_rng("check witness");
+    let violated = crate::witness::first_violated(circ.clone(), || E::Fr::rand(coeff_rng));
+    end_mpc_timer!(timer);
+    if let Some(i) = violated {
+        panic!("Witness check failed: constraint {} is violated", i);
+    }
+    println!("Witness check passed");
+}
+
+/// How proof envelopes and key files name the curve and proof system, where to write envelopes
+/// (if at all), where to keep the key and the SRS (if anywhere), where to read a ceremony's key
+/// from (if anywhere), where to write test vectors (if at all), where to read the designated
+/// verifier's key (or our share of it) from, and, if envelopes are to record their provenance,
+/// the agreed parameters' digest and the parties.
+#[derive(Default)]
+struct EnvelopeSettings {
+    curve: String,
+    proof_system: String,
+    out: Option<PathBuf>,
+    key: Option<PathBuf>,
+    zkey: Option<PathBuf>,
+    srs: Option<PathBuf>,
+    test_vectors: Option<PathBuf>,
+    dv_key: Option<PathBuf>,
+    provenance: Option<(String, Vec<String>)>,
+}
+
+lazy_static! {
+    static ref ENVELOPE: Mutex<EnvelopeSettings> = Mutex::new(EnvelopeSettings::default());
+    /// What the inputs of the statement being proved are named (see [crate::witness_cache]), if
+    /// they are: its shared witness is only cached if so.
+    static ref WITNESS_INPUTS: Mutex<Option<String>> = Mutex::new(None);
+}
+
+/// The envelope header for a proof of `C`'s size-`n` circuit.
+fn envelope_header<E: PairingEngine, C: BenchCircuit>(
+    n: usize,
+    n_parties: usize,
+    started_at: u64,
+) -> Header {
+    let settings = ENVELOPE.lock().unwrap();
+    Header {
+        curve: settings.curve.clone(),
+        proof_system: settings.proof_system.clone(),
+        circuit_hash: envelope::circuit_hash(C::without_data::<E::Fr>(n)),
+        n_parties,
+        started_at,
+        finished_at: envelope::unix_time(),
+    }
+}
+
+/// If enabled, how this party has made the proof so far (see [Provenance]).
+fn provenance() -> Option<Provenance> {
+    let (params_digest, parties) = ENVELOPE.lock().unwrap().provenance.clone()?;
+    let stats = MpcMultiNet::stats();
+    let session_id = if MpcMultiNet::is_init() {
+        envelope::to_hex(&MpcMultiNet::session_id())
+    } else {
+        String::new()
+    };
+    let phases = timer::phases()
+        .into_iter()
+        .map(|p| PhaseRecord {
+            path: p.path,
+            millis: p.time.as_millis() as u64,
+            bytes: p.bytes,
+            rounds: p.rounds,
+        })
+        .collect();
+    Some(Provenance {
+        software: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        params_digest,
+        parties,
+        session_id,
+        phases,
+        bytes_sent: stats.bytes_sent,
+        bytes_recv: stats.bytes_recv,
+        rounds: stats.broadcasts + stats.to_king + stats.from_king + stats.peer_msgs,
+    })
+}
+
+/// `proof` and its `public_inputs` in an envelope, with its provenance if enabled.
+fn seal_envelope<E: PairingEngine, C: BenchCircuit, P: CanonicalSerialize>(
+    n: usize,
+    n_parties: usize,
+    started_at: u64,
+    proof: &P,
+    public_inputs: &[E::Fr],
+) -> Envelope {
+    let header = envelope_header::<E, C>(n, n_parties, started_at);
+    let env = Envelope::seal(header, proof, public_inputs).unwrap();
+    match provenance() {
+        Some(p) => env.with_provenance(p),
+        None => env,
+    }
+}
+
+/// If enabled, write `proof` and its `public_inputs` to an envelope, for the `verify` command.
+fn write_envelope<E: PairingEngine, C: BenchCircuit, P: CanonicalSerialize>(
+    n: usize,
+    n_parties: usize,
+    started_at: u64,
+    proof: &P,
+    public_inputs: &[E::Fr],
+) {
+    let out = match ENVELOPE.lock().unwrap().out.clone() {
+        Some(out) => out,
+        None => return,
+    };
+    seal_envelope::<E, C, _>(n, n_parties, started_at, proof, public_inputs)
+        .save(&out)
+        .unwrap_or_else(|e| panic!("Could not write {}: {}", out.display(), e));
+    println!("Wrote proof envelope {}", out.display());
+}
+
+/// If enabled, write `proof`, its `public_inputs` and the `vk` to check them with to a test
+/// vector.
+fn write_test_vector<E, C, V, P>(
+    n: usize,
+    n_parties: usize,
+    started_at: u64,
+    vk: &V,
+    proof: &P,
+    public_inputs: &[E::Fr],
+) where
+    E: PairingEngine,
+    C: BenchCircuit,
+    V: CanonicalSerialize,
+    P: CanonicalSerialize,
+{
+    let out = match ENVELOPE.lock().unwrap().test_vectors.clone() {
+        Some(out) => out,
+        None => return,
+    };
+    let env = seal_envelope::<E, C, _>(n, n_parties, started_at, proof, public_inputs);
+    TestVector::seal(env, vk)
+        .unwrap()
+        .save(&out)
+        .unwrap_or_else(|e| panic!("Could not write {}: {}", out.display(), e));
+    println!("Wrote test vector {}", out.display());
+}
+
+/// Check the proof in the envelope at `path`, which must be for `C`'s size-`n` circuit.
+fn verify_envelope<E: PairingEngine, B: SnarkBench, C: BenchCircuit>(n: usize, path: &Path) {
+    let checked = Envelope::load(path).and_then(|env| {
+        env.check(&envelope_header::<E, C>(n, 0, 0))?;
+        Ok((B::verify::<E, C>(n, &env)?, env))
+    });
+    match checked {
+        Ok((true, env)) => println!(
+            "Envelope verified: proof by {} parties, finished at {}",
+            env.header.n_parties, env.header.finished_at
+        ),
+        Ok((false, _)) => panic!("Invalid proof in {}", path.display()),
+        Err(e) => panic!("Bad envelope {}: {}", path.display(), e),
+    }
+}
+
+/// Remove the provenance block from the envelope or test vector at `path`, writing the result to
+/// `out`.
+fn strip_provenance(path: &Path, out: &Path) -> Result<(), EnvelopeError> {
+    // A test vector reads as an envelope too, so try it first, to keep its key.
+    let stripped = match TestVector::load(path) {
+        Ok(mut vector) => vector.envelope.strip_provenance().map(|_| vector.save(out)),
+        Err(_) => {
+            let mut env = Envelope::load(path)?;
+            env.strip_provenance().map(|_| env.save(out))
+        }
+    };
+    match stripped {
+        Some(saved) => saved,
+        None => {
+            if path != out {
+                std::fs::copy(path, out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Set up `C`'s size-`n` circuit for a designated verifier (see [crate::dv]): write the provers'
+/// proving key to the key file, and the verifier's secret key to `out`.
+fn set_up_dv<E: PairingEngine, C: BenchCircuit>(n: usize, out: &Path) {
+    let path = ENVELOPE.lock().unwrap().key.clone().unwrap();
+    // The trapdoor must be the verifier's alone, so not from a shared seed.
+    let rng = &mut rand::thread_rng();
+    let (pk, key) = crate::dv::setup::<E, _, _>(C::without_data::<E::Fr>(n), rng).unwrap();
+    KeyFile::seal(&envelope_header::<E, C>(n, 0, 0), &pk)
+        .unwrap()
+        .save(&path)
+        .unwrap_or_else(|e| panic!("Could not write {}: {}", path.display(), e));
+    println!("Wrote key file {}", path.display());
+    key.save(out)
+        .unwrap_or_else(|e| panic!("Could not write {}: {}", out.display(), e));
+    println!("Wrote DV key {}", out.display());
+}
+
+/// Print what, besides the proof itself, a verifier or arbiter may want to keep.
+fn report_metadata(witness_commitments: Option<WitnessCommitments>) {
+    if let Some(c) = witness_commitments {
+        println!("Proof metadata: witness commitments {}", c);
+        print!("Proof metadata: opening of our commitment ");
+        c.opening.iter().for_each(|b| print!("{:02x}", b));
+        println!();
+    }
+}
+
+trait SnarkBench {
+    fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str);
+    fn ark_local<E: PairingEngine, C: BenchCircuit>(_n: usize, _timer_label: &str) {
+        unimplemented!("ark benchmark for {}", std::any::type_name::<Self>())
+    }
+    fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(n: usize, timer_label: &str);
+    /// Like `mpc`, but set up once, and then prove `k` instances concurrently, one per lane (see
+    /// [MpcMultiNet::run_lanes]).
+    fn mpc_batch<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+        _n: usize,
+        _k: usize,
+        _timer_label: &str,
+    ) {
+        unimplemented!("batches for {}", std::any::type_name::<Self>())
+    }
+    /// Check the proof in `envelope`, against the same setup that `local` and `mpc` use.
+    fn verify<E: PairingEngine, C: BenchCircuit>(
+        _n: usize,
+        _envelope: &Envelope,
+    ) -> Result<bool, EnvelopeError> {
+        unimplemented!("envelopes for {}", std::any::type_name::<Self>())
+    }
+}
+
+/// A family of circuits, indexed by a size `n`, which the benchmarks prove.
+///
+/// To prove one from the command line, register it as a [Plugin].
+pub trait BenchCircuit {
+    type Circuit<F: PrimeField>: ConstraintSynthesizer<F> + Clone + 'static;
+
+    /// The circuit without an assignment, for setup.
+    fn without_data<F: PrimeField>(n: usize) -> Self::Circuit<F>;
+
+    /// A satisfying assignment, and the public inputs it induces.
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (Self::Circuit<F>, Vec<F>);
+
+    /// A satisfying assignment computed in MPC, and the (revealed) public inputs it induces.
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        rng: &mut R,
+    ) -> (Self::Circuit<MpcFr<E, S>>, Vec<E::Fr>);
+
+    /// Bounds on the number of constraints, variables, and non-zero matrix entries, for sizing
+    /// Marlin's universal setup.
+    fn marlin_bounds(n: usize) -> (usize, usize, usize);
+
+    /// The same relation as a Plonk circuit, if there is one. Its public variables, in order, take
+    /// the public inputs.
+    fn plonk<F: PrimeField>(_c: Self::Circuit<F>) -> Option<PlonkCircuit<F>> {
+        None
+    }
+
+    /// The same relation as `n` repetitions of one step on a state, if it is that, for proving
+    /// in chunks (see `--chunks`): the step, and the state before the first. The state after the
+    /// last is the public inputs.
+    fn steps<F: PrimeField>(_c: Self::Circuit<F>) -> Option<(Step<F>, Vec<Option<F>>)> {
+        None
+    }
+}
+
+mod squarings {
+    use super::*;
+    #[derive(Clone)]
+    pub struct RepeatedSquaringCircuit<F: Field> {
+        chain: Vec<Option<F>>,
+    }
+
+    impl<F: Field> RepeatedSquaringCircuit<F> {
+        fn without_data(squarings: usize) -> Self {
+            Self {
+                chain: vec![None; squarings + 1],
+            }
+        }
+        fn from_start(f: F, squarings: usize) -> Self {
+            let mut chain = vec![Some(f)];
+            for _ in 0..squarings {
+                let mut last = chain.last().unwrap().as_ref().unwrap().clone();
+                last.square_in_place();
+                chain.push(Some(last));
+            }
+            Self { chain }
+        }
+        fn from_chain(f: Vec<F>) -> Self {
+            Self {
+                chain: f.into_iter().map(Some).collect(),
+            }
+        }
+        fn squarings(&self) -> usize {
+            self.chain.len() - 1
+        }
+    }
+
+    /// Repeatedly square a secret; the result is public.
+    pub struct Squaring;
+
+    impl BenchCircuit for Squaring {
+        type Circuit<F: PrimeField> = RepeatedSquaringCircuit<F>;
+
+        fn without_data<F: PrimeField>(n: usize) -> RepeatedSquaringCircuit<F> {
+            RepeatedSquaringCircuit::without_data(n)
+        }
+
+        fn local<F: PrimeField, R: Rng>(
+            n: usize,
+            rng: &mut R,
+        ) -> (RepeatedSquaringCircuit<F>, Vec<F>) {
+            let circ = RepeatedSquaringCircuit::from_start(F::rand(rng), n);
+            let public_inputs = vec![circ.chain.last().unwrap().unwrap()];
+            (circ, public_inputs)
+        }
+
+        fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+            n: usize,
+            rng: &mut R,
+        ) -> (RepeatedSquaringCircuit<MpcFr<E, S>>, Vec<E::Fr>) {
+            let circ = mpc_squaring_circuit::<E::Fr, MpcFr<E, S>>(E::Fr::rand(rng), n);
+            let public_inputs = RevealOutputs::Instance.reveal(circ.clone()).unwrap();
+            (circ, public_inputs)
+        }
+
+        fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+            (n, n + 2, 3 * n)
+        }
+
+        fn plonk<F: PrimeField>(c: RepeatedSquaringCircuit<F>) -> Option<PlonkCircuit<F>> {
+            let n_gates = c.chain.len() as u32 - 1;
+            let n_vars = n_gates + 1;
+            let last_var = n_vars as u32 - 1;
+            let mut this = PlonkCircuit {
+                n_vars,
+                pub_vars: std::iter::once((last_var, "out".to_owned())).collect(),
+                prods: (0..(n_vars - 1)).map(|i| (i, i, i + 1)).collect(),
+                sums: Vec::new(),
+                values: c.chain.into_iter().collect(),
+                tables: Vec::new(),
+                lookups: Vec::new(),
+                lookup_counts: None,
+            };
+            this.pad_to_power_of_2();
+            Some(this)
+        }
+
+        fn steps<F: PrimeField>(
+            c: RepeatedSquaringCircuit<F>,
+        ) -> Option<(Step<F>, Vec<Option<F>>)> {
+            Some((Step(|cs, x| Square.synthesize(cs, x)), vec![c.chain[0]]))
+        }
+    }
+
+    fn mpc_squaring_circuit<Fr: Field, MFr: Field + Reveal<Base = Fr>>(
+        start: Fr,
+        squarings: usize,
+    ) -> RepeatedSquaringCircuit<MFr> {
+        let raw_chain: Vec<Fr> = std::iter::successors(Some(start), |a| Some(a.square()))
+            .take(squarings + 1)
+            .collect();
+        let rng = &mut test_rng();
+        let chain_shares = MFr::king_share_batch(raw_chain, rng);
+        RepeatedSquaringCircuit {
+            chain: chain_shares.into_iter().map(Some).collect(),
+        }
+    }
+
+    impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF>
+        for RepeatedSquaringCircuit<ConstraintF>
+    {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<ConstraintF>,
+        ) -> Result<(), SynthesisError> {
+            let mut vars: Vec<Variable> = self
+                .chain
+                .iter()
+                .take(self.squarings())
+                .map(|o| cs.new_witness_variable(|| o.ok_or(SynthesisError::AssignmentMissing)))
+                .collect::<Result<_, _>>()?;
+            vars.push(cs.new_input_variable(|| {
+                self.chain
+                    .last()
+                    .unwrap()
+                    .ok_or(SynthesisError::AssignmentMissing)
+            })?);
+
+            for i in 0..self.squarings() {
+                cs.enforce_constraint(lc!() + vars[i], lc!() + vars[i], lc!() + vars[i + 1])?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+mod benches {
+    use super::*;
+    use mpc_algebra::channel::MpcSerNet;
+    use std::any::{Any, TypeId};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    thread_local! {
+        /// Keys and indexes, by type and circuit size, once read or set up, for the runs after
+        /// the first (see `--reps` and `daemon`). Per thread, since Marlin's indexes are not
+        /// `Send`; runs all start on the main thread.
+        static CACHE: RefCell<HashMap<(TypeId, usize), Box<dyn Any>>> =
+            RefCell::new(HashMap::new());
+    }
+
+    thread_local! {
+        /// Shared witnesses, by statement, for `--witness-cache`.
+        static WITNESSES: RefCell<WitnessCache> = RefCell::new(WitnessCache::new(0));
+    }
+
+    /// Keep the shared witnesses of up to `capacity` statements.
+    pub fn keep_witnesses(capacity: usize) {
+        WITNESSES.with(|w| *w.borrow_mut() = WitnessCache::new(capacity));
+    }
+
+    /// `C`'s size-`n` circuit with its assignment computed in MPC, and its public inputs (see
+    /// [BenchCircuit::mpc]); or, if the statement's inputs are named, and every party has kept
+    /// its shared witness, that witness.
+    fn shared_witness<E, S, C, R>(n: usize, rng: &mut R) -> (C::Circuit<MpcFr<E, S>>, Vec<E::Fr>)
+    where
+        E: PairingEngine,
+        S: PairingShare<E>,
+        C: BenchCircuit,
+        R: Rng,
+    {
+        let inputs = WITNESS_INPUTS.lock().unwrap().clone();
+        let key = match inputs {
+            Some(inputs) if WITNESSES.with(|w| w.borrow().capacity() > 0) => {
+                let circuit = envelope::circuit_hash(C::without_data::<E::Fr>(n));
+                WitnessKey::new(&circuit, &inputs)
+            }
+            _ => return C::mpc::<E, S, _>(n, rng),
+        };
+        let kept = WITNESSES.with(|w| w.borrow_mut().get(&key));
+        if MpcMultiNet::broadcast(&kept.is_some()).into_iter().all(|k| k) {
+            println!("Reusing the shared witness {}", key);
+            return kept.unwrap();
+        }
+        let witness = C::mpc::<E, S, _>(n, rng);
+        WITNESSES.with(|w| w.borrow_mut().insert(key, witness.clone()));
+        witness
+    }
+
+    /// What `make` makes for size-`n` circuits: made by only the first run of a process to ask.
+    fn cached<T: Clone + 'static>(n: usize, make: impl FnOnce() -> T) -> T {
+        let key = (TypeId::of::<T>(), n);
+        let hit = CACHE.with(|c| c.borrow().get(&key).map(|v| v.downcast_ref::<T>().cloned()));
+        if let Some(v) = hit.flatten() {
+            return v;
+        }
+        let v = make();
+        CACHE.with(|c| c.borrow_mut().insert(key, Box::new(v.clone())));
+        v
+    }
+
+    pub mod groth {
+        use super::*;
+        use ark_groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+        use ark_groth16::{Proof, ProvingKey};
+        use crate::groth::prover::{
+            create_random_proof, create_random_proof_no_group_openings,
+            create_random_proof_with_reduction,
+        };
+        use crate::groth::r1cs_to_qap::CircomReduction;
+        use crate::groth::zkey::Zkey;
+        use crate::groth::se::{self, OtsKey, SeCircuit, SeProof};
+        use ark_ff::Zero;
+        use mpc_algebra::channel::MpcSerNet;
+        use crate::groth::shard::{self, ShardedProvingKey};
+        use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+        /// Whether the MPC prover gets a secret-shared proving key, rather than a public one.
+        pub static SHARED_PK: AtomicBool = AtomicBool::new(false);
+
+        /// Whether the MPC prover keeps all group elements shared until it reveals the proof.
+        pub static NO_GROUP_OPENINGS: AtomicBool = AtomicBool::new(false);
+
+        /// Whether each party keeps only its shard of the proving key (see [shard]).
+        pub static SHARD_PK: AtomicBool = AtomicBool::new(false);
+
+        /// Whether proofs are made simulation-extractable (see [se]).
+        pub static SE: AtomicBool = AtomicBool::new(false);
+
+        /// The only party to reveal the proof to, or [ALL_PARTIES].
+        pub static REVEAL_TO: AtomicUsize = AtomicUsize::new(ALL_PARTIES);
+        pub const ALL_PARTIES: usize = usize::MAX;
+
+        /// The proving key for `C`'s size-`n` circuit: read from the key file, if there is one,
+        /// and otherwise set up (and then written to it, by party 0 in MPC). A key file for
+        /// another circuit is refused.
+        /// Only the first run of a process reads or sets it up.
+        fn proving_key<E: PairingEngine, C: BenchCircuit, R: Rng>(
+            n: usize,
+            rng: &mut R,
+        ) -> ProvingKey<E> {
+            cached(n, || load_or_set_up_key::<E, C, R>(n, rng))
+        }
+
+        fn load_or_set_up_key<E: PairingEngine, C: BenchCircuit, R: Rng>(
+            n: usize,
+            rng: &mut R,
+        ) -> ProvingKey<E> {
+            if let Some(path) = circom_key() {
+                return read_zkey::<E, C>(n, &path);
+            }
+            let path = ENVELOPE.lock().unwrap().key.clone();
+            let header = envelope_header::<E, C>(n, 0, 0);
+            if let Some(path) = path.as_ref().filter(|p| p.exists()) {
+                return KeyFile::load(path)
+                    .and_then(|file| {
+                        file.check(&header)?;
+                        file.key()
+                    })
+                    .unwrap_or_else(|e| panic!("Bad key file {}: {}", path.display(), e));
+            }
+            let circ_no_data = C::without_data::<E::Fr>(n);
+            let params = if SE.load(Ordering::Relaxed) {
+                let circ_no_data = SeCircuit {
+                    inner: circ_no_data,
+                    tag: None,
+                };
+                generate_random_parameters::<E, _, _>(circ_no_data, rng)
+            } else {
+                generate_random_parameters::<E, _, _>(circ_no_data, rng)
+            }
+            .unwrap();
+            if let Some(path) = path {
+                if !MpcMultiNet::is_init() || MpcMultiNet::am_king() {
+                    KeyFile::seal(&header, &params)
+                        .unwrap()
+                        .save(&path)
+                        .unwrap_or_else(|e| panic!("Could not write {}: {}", path.display(), e));
+                    println!("Wrote key file {}", path.display());
+                }
+            }
+            params
+        }
+
+        /// The `.zkey` that the proving key is read from, if it is a circom ceremony's; proofs
+        /// must then be made with snarkjs's reduction (see [crate::groth::zkey]).
+        fn circom_key() -> Option<PathBuf> {
+            ENVELOPE.lock().unwrap().zkey.clone()
+        }
+
+        /// The proving key in the `.zkey` at `path`, which must be for `C`'s size-`n` circuit.
+        fn read_zkey<E: PairingEngine, C: BenchCircuit>(n: usize, path: &Path) -> ProvingKey<E> {
+            std::fs::File::open(path)
+                .map_err(Into::into)
+                .and_then(|file| Zkey::<E>::read(std::io::BufReader::new(file)))
+                .and_then(|zkey| {
+                    zkey.check_circuit(C::without_data::<E::Fr>(n))?;
+                    Ok(zkey.pk)
+                })
+                .unwrap_or_else(|e| panic!("Bad zkey {}: {}", path.display(), e))
+        }
+
+        /// The MPC prover's proving key, as chosen by [SHARED_PK] and [SHARD_PK].
+        enum MpcKey<E: PairingEngine, S: PairingShare<E>> {
+            Whole(ProvingKey<MpcPairingEngine<E, S>>),
+            Sharded(ShardedProvingKey<MpcPairingEngine<E, S>>),
+        }
+
+        fn mpc_key<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+            params: ProvingKey<E>,
+            rng: &mut R,
+        ) -> MpcKey<E, S> {
+            if SHARED_PK.load(Ordering::Relaxed) {
+                let share_timer = start_mpc_timer!(|| "share proving key");
+                let pk = Reveal::king_share(params, rng);
+                end_mpc_timer!(share_timer);
+                MpcKey::Whole(pk)
+            } else if SHARD_PK.load(Ordering::Relaxed) {
+                let (party, n_parties) = (MpcMultiNet::party_id(), MpcMultiNet::n_parties());
+                let shard = ShardedProvingKey::shard(&params, party, n_parties);
+                drop(params);
+                MpcKey::Sharded(Reveal::from_public(shard))
+            } else {
+                MpcKey::Whole(Reveal::from_public(params))
+            }
+        }
+
+        /// Prove, with the prover chosen by [NO_GROUP_OPENINGS] and the key's kind (and, for a
+        /// ceremony's key, with snarkjs's reduction).
+        fn prove<E: PairingEngine, S: PairingShare<E>, C, R: Rng>(
+            circ_data: C,
+            pk: &MpcKey<E, S>,
+            rng: &mut R,
+        ) -> Proof<MpcPairingEngine<E, S>>
+        where
+            C: ConstraintSynthesizer<MpcFr<E, S>>,
+        {
+            match pk {
+                MpcKey::Sharded(pk) => shard::create_random_proof(circ_data, pk, rng),
+                MpcKey::Whole(pk) if NO_GROUP_OPENINGS.load(Ordering::Relaxed) => {
+                    create_random_proof_no_group_openings::<MpcPairingEngine<E, S>, _, _>(
+                        circ_data, pk, rng,
+                    )
+                }
+                MpcKey::Whole(pk) if circom_key().is_some() => {
+                    create_random_proof_with_reduction::<_, _, _, CircomReduction>(circ_data, pk, rng)
+                }
+                MpcKey::Whole(pk) => {
+                    create_random_proof::<MpcPairingEngine<E, S>, _, _>(circ_data, pk, rng)
+                }
+            }
+            .unwrap()
+        }
+
+        /// With [SE], a fresh one-time signing key for the proof, from randomness of our own.
+        fn ots_key() -> Option<OtsKey> {
+            SE.load(Ordering::Relaxed)
+                .then(|| OtsKey::generate(&mut rand::thread_rng()))
+        }
+
+        pub struct Groth16Bench;
+
+        impl SnarkBench for Groth16Bench {
+            fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let rng = &mut local_rng();
+                let params = proving_key::<E, C, _>(n, rng);
+
+                let pvk = prepare_verifying_key::<E>(&params.vk);
+
+                let (circ_data, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                let started_at = envelope::unix_time();
+                let key = ots_key();
+                let timer = start_mpc_timer!(|| timer_label);
+                let proof = match &key {
+                    Some(key) => {
+                        let tag = Some(key.verifying_key().tag());
+                        let circ_data = SeCircuit {
+                            inner: circ_data,
+                            tag,
+                        };
+                        create_random_proof::<E, _, _>(circ_data, &params, rng)
+                    }
+                    None if circom_key().is_some() => {
+                        create_random_proof_with_reduction::<_, _, _, CircomReduction>(
+                            circ_data, &params, rng,
+                        )
+                    }
+                    None => create_random_proof::<E, _, _>(circ_data, &params, rng),
+                }
+                .unwrap();
+                end_mpc_timer!(timer);
+
+                match key {
+                    Some(key) => {
+                        let proof = se::seal(proof, key, &public_inputs);
+                        assert!(se::verify(&pvk, &proof, &public_inputs).unwrap());
+                        write_envelope::<E, C, _>(n, 1, started_at, &proof, &public_inputs);
+                    }
+                    None => {
+                        assert!(verify_proof(&pvk, &proof, &public_inputs).unwrap());
+                        write_envelope::<E, C, _>(n, 1, started_at, &proof, &public_inputs);
+                    }
+                }
+            }
+
+            fn ark_local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let rng = &mut local_rng();
+                let params = proving_key::<E, C, _>(n, rng);
+
+                let pvk = prepare_verifying_key::<E>(&params.vk);
+
+                let (circ_data, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                let timer = start_mpc_timer!(|| timer_label);
+                let proof =
+                    ark_groth16::create_random_proof::<E, _, _>(circ_data, &params, rng).unwrap();
+                end_mpc_timer!(timer);
+
+                assert!(verify_proof(&pvk, &proof, &public_inputs).unwrap());
+            }
+
+            fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                timer_label: &str,
+            ) {
+                let phases = &mut Phases::new();
+                enter(phases, Phase::Setup);
+                let rng = &mut test_rng();
+                let params = proving_key::<E, C, _>(n, rng);
+
+                let pvk = prepare_verifying_key::<E>(&params.vk);
+                let mpc_params = mpc_key::<E, S, _>(params, rng);
+
+                enter(phases, Phase::InputSharing);
+                let computation_timer = start_mpc_timer!(|| "do the mpc (cheat)");
+                let (circ_data, public_inputs) = shared_witness::<E, S, C, _>(n, rng);
+                end_mpc_timer!(computation_timer);
+                enter(phases, Phase::Preprocessing);
+                audit_witness(&circ_data);
+                check_witness::<E, S, _>(&circ_data);
+                let witness_commitments = commit_witness::<E, S, _>(&circ_data);
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let started_at = envelope::unix_time();
+                let recipient = REVEAL_TO.load(Ordering::Relaxed);
+                // The party that gets the proof signs it, so only it holds the signing key; the
+                // others learn the key's tag, which is a public input.
+                let signer = if recipient == ALL_PARTIES { 0 } else { recipient };
+                let key = ots_key().filter(|_| MpcMultiNet::party_id() == signer);
+                let tag = SE.load(Ordering::Relaxed).then(|| {
+                    // All send as much, as broadcasts need.
+                    let mine = key.as_ref().map_or(E::Fr::zero(), |k| k.verifying_key().tag());
+                    MpcMultiNet::broadcast(&mine)[signer]
+                });
+                let timer = start_mpc_timer!(|| timer_label);
+                let zk_rng = &mut party_rng();
+                let proof = channel::without_cheating(|| {
+                    enter(phases, Phase::Prove);
+                    let pf = match tag {
+                        Some(tag) => {
+                            let circ_data = SeCircuit {
+                                inner: circ_data,
+                                tag: Some(MpcFr::<E, S>::from_public(tag)),
+                            };
+                            prove(circ_data, &mpc_params, zk_rng)
+                        }
+                        None => prove(circ_data, &mpc_params, zk_rng),
+                    };
+                    enter(phases, Phase::Reveal);
+                    let reveal_timer = start_mpc_timer!(|| "reveal");
+                    let pf = if recipient == ALL_PARTIES {
+                        Some(pf.reveal())
+                    } else {
+                        pf.reveal_to(recipient)
+                    };
+                    end_mpc_timer!(reveal_timer);
+                    pf
+                });
+                end_mpc_timer!(timer);
+
+                report_metadata(witness_commitments);
+                let proof = match proof {
+                    Some(proof) => proof,
+                    None => {
+                        println!("Proof revealed to party {}", recipient);
+                        return;
+                    }
+                };
+                enter(phases, Phase::Verify);
+                if let Some(tag) = tag {
+                    let mut statement = public_inputs.clone();
+                    statement.push(tag);
+                    assert!(verify_proof(&pvk, &proof, &statement).unwrap());
+                    if let Some(key) = key {
+                        let proof = se::seal(proof, key, &public_inputs);
+                        assert!(se::verify(&pvk, &proof, &public_inputs).unwrap());
+                        let n_parties = MpcMultiNet::n_parties();
+                        write_envelope::<E, C, _>(n, n_parties, started_at, &proof, &public_inputs);
+                    }
+                    return;
+                }
+                assert!(verify_proof(&pvk, &proof, &public_inputs).unwrap());
+                // The recipient, if any, is the only one with the proof; otherwise, the king.
+                if recipient != ALL_PARTIES || MpcMultiNet::am_king() {
+                    let n_parties = MpcMultiNet::n_parties();
+                    write_envelope::<E, C, _>(n, n_parties, started_at, &proof, &public_inputs);
+                    write_test_vector::<E, C, _, _>(
+                        n,
+                        n_parties,
+                        started_at,
+                        &pvk.vk,
+                        &proof,
+                        &public_inputs,
+                    );
+                }
+            }
+
+            fn mpc_batch<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                k: usize,
+                timer_label: &str,
+            ) {
+                let rng = &mut test_rng();
+                let params = proving_key::<E, C, _>(n, rng);
+
+                let pvk = prepare_verifying_key::<E>(&params.vk);
+                let mpc_params = mpc_key::<E, S, _>(params, rng);
+
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let timer = start_mpc_timer!(|| timer_label);
+                // MPC timers are not per lane, so lanes only use plain ones.
+                let proofs = MpcMultiNet::run_lanes(k, |lane| {
+                    // The same per lane at every party, as `test_rng` is.
+                    let rng = &mut StdRng::seed_from_u64(lane as u64);
+                    let (circ_data, public_inputs) = C::mpc::<E, S, _>(n, rng);
+                    // And per lane and party, as `party_rng` is.
+                    let party = MpcMultiNet::party_id() as u64;
+                    let zk_rng = &mut StdRng::seed_from_u64((lane as u64) << 32 | party);
+                    let proof = channel::without_cheating(|| {
+                        prove(circ_data, &mpc_params, zk_rng).reveal()
+                    });
+                    (proof, public_inputs)
+                });
+                end_mpc_timer!(timer);
+
+                for (proof, public_inputs) in &proofs {
+                    assert!(verify_proof(&pvk, proof, public_inputs).unwrap());
+                }
+                println!("Verified a batch of {} proofs", k);
+            }
+
+            fn verify<E: PairingEngine, C: BenchCircuit>(
+                n: usize,
+                envelope: &Envelope,
+            ) -> Result<bool, EnvelopeError> {
+                let rng = &mut test_rng();
+                let params = proving_key::<E, C, _>(n, rng);
+                let pvk = prepare_verifying_key::<E>(&params.vk);
+                let public_inputs = envelope.public_inputs::<E::Fr>()?;
+                if SE.load(Ordering::Relaxed) {
+                    let proof = envelope.proof::<SeProof<E>>()?;
+                    return Ok(se::verify(&pvk, &proof, &public_inputs).unwrap());
+                }
+                let proof = envelope.proof::<ark_groth16::Proof<E>>()?;
+                Ok(verify_proof(&pvk, &proof, &public_inputs).unwrap())
+            }
+        }
+    }
+
+    pub mod chunked {
+        use super::*;
+        use crate::chunked::{self, ChunkedKey, ChunkedProof};
+
+        /// How many chunks to prove the computation in (see [crate::chunked]).
+        pub static CHUNKS: AtomicUsize = AtomicUsize::new(1);
+
+        /// `c` as its steps (see [BenchCircuit::steps]), and the number of steps per chunk.
+        fn steps<F: PrimeField, C: BenchCircuit>(
+            n: usize,
+            c: C::Circuit<F>,
+        ) -> (Step<F>, Vec<Option<F>>, usize) {
+            let (step, start) = C::steps(c).expect("the computation is not a repeated step");
+            (step, start, n / CHUNKS.load(Ordering::Relaxed))
+        }
+
+        /// The key for chunks of `C`'s size-`n` circuit. Only the first run of a process sets it
+        /// up.
+        fn chunked_key<E: PairingEngine, C: BenchCircuit, R: Rng>(
+            n: usize,
+            rng: &mut R,
+        ) -> ChunkedKey<E> {
+            cached(n, || {
+                let (step, start, per_chunk) = steps::<E::Fr, C>(n, C::without_data(n));
+                ChunkedKey::setup(step, per_chunk, start.len(), rng).unwrap()
+            })
+        }
+
+        pub struct ChunkedBench;
+
+        impl SnarkBench for ChunkedBench {
+            fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let rng = &mut local_rng();
+                let key = chunked_key::<E, C, _>(n, rng);
+                let vk = key.verifying_key();
+
+                let (circ_data, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                let (step, start, per_chunk) = steps::<E::Fr, C>(n, circ_data);
+                let start = start.into_iter().map(Option::unwrap).collect();
+                let chunks = CHUNKS.load(Ordering::Relaxed);
+                let started_at = envelope::unix_time();
+                let timer = start_mpc_timer!(|| timer_label);
+                let proof = chunked::prove(&key, step, per_chunk, chunks, start, rng).unwrap();
+                end_mpc_timer!(timer);
+
+                assert_eq!(proof.output(), &public_inputs[..]);
+                assert!(proof.verify(&vk).unwrap());
+                write_envelope::<E, C, _>(n, 1, started_at, &proof, &public_inputs);
+            }
+
+            fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                timer_label: &str,
+            ) {
+                let phases = &mut Phases::new();
+                enter(phases, Phase::Setup);
+                let rng = &mut test_rng();
+                let key = chunked_key::<E, C, _>(n, rng);
+                let vk = key.verifying_key();
+                let mpc_key: ChunkedKey<MpcPairingEngine<E, S>> = Reveal::from_public(key);
+
+                enter(phases, Phase::InputSharing);
+                let (circ_data, public_inputs) = shared_witness::<E, S, C, _>(n, rng);
+                enter(phases, Phase::Preprocessing);
+                audit_witness(&circ_data);
+                check_witness::<E, S, _>(&circ_data);
+                let witness_commitments = commit_witness::<E, S, _>(&circ_data);
+                let (step, start, per_chunk) = steps::<MpcFr<E, S>, C>(n, circ_data);
+                let start = start.into_iter().map(Option::unwrap).collect();
+                let chunks = CHUNKS.load(Ordering::Relaxed);
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let started_at = envelope::unix_time();
+                let timer = start_mpc_timer!(|| timer_label);
+                let zk_rng = &mut party_rng();
+                let proof = channel::without_cheating(|| {
+                    enter(phases, Phase::Prove);
+                    let pf = chunked::prove(&mpc_key, step, per_chunk, chunks, start, zk_rng);
+                    enter(phases, Phase::Reveal);
+                    pf.unwrap().reveal()
+                });
+                end_mpc_timer!(timer);
+
+                report_metadata(witness_commitments);
+                enter(phases, Phase::Verify);
+                assert_eq!(proof.output(), &public_inputs[..]);
+                assert!(proof.verify(&vk).unwrap());
+                if MpcMultiNet::am_king() {
+                    let n_parties = MpcMultiNet::n_parties();
+                    write_envelope::<E, C, _>(n, n_parties, started_at, &proof, &public_inputs);
+                }
+            }
+
+            fn verify<E: PairingEngine, C: BenchCircuit>(
+                n: usize,
+                envelope: &Envelope,
+            ) -> Result<bool, EnvelopeError> {
+                let key = chunked_key::<E, C, _>(n, &mut test_rng());
+                let public_inputs = envelope.public_inputs::<E::Fr>()?;
+                let proof = envelope.proof::<ChunkedProof<E>>()?;
+                Ok(proof.output() == &public_inputs[..]
+                    && proof.verify(&key.verifying_key()).unwrap())
+            }
+        }
+    }
+
+    pub mod marlin {
+        use super::*;
+        use ark_marlin::Marlin;
+        use ark_marlin::*;
+        use ark_poly::univariate::DensePolynomial;
+        use ark_poly_commit::ipa_pc::InnerProductArgPC;
+        use ark_poly_commit::marlin::marlin_pc::MarlinKZG10;
+        use ark_poly_commit::PolynomialCommitment;
+        use ark_serialize::CanonicalDeserialize;
+        use ark_ec::AffineCurve;
+        use digest::Digest;
+        use std::io;
+
+        /// Whether MPC parties verify the proof while it is still shared, before revealing it.
+        pub static SHARED_VERIFY: AtomicBool = AtomicBool::new(false);
+        /// Whether to print the size of each component of the proof.
+        pub static REPORT_PROOF: AtomicBool = AtomicBool::new(false);
+
+        /// Universal SRSs that can be kept in an SRS file (see [crate::srs]).
+        trait SrsFileScheme<F: PrimeField>: PolynomialCommitment<F, DensePolynomial<F>> {
+            /// The SRS in the file at `path`, sliced to `degree`.
+            fn load(path: &Path, degree: usize) -> io::Result<Self::UniversalParams>;
+            fn save(srs: &Self::UniversalParams, path: &Path) -> io::Result<()>;
+        }
+
+        impl<E: PairingEngine> SrsFileScheme<E::Fr> for KzgPC<E> {
+            fn load(path: &Path, degree: usize) -> io::Result<Self::UniversalParams> {
+                srs::SrsFile::open(path)?.slice(degree)
+            }
+
+            fn save(srs: &Self::UniversalParams, path: &Path) -> io::Result<()> {
+                srs::save(srs, path)
+            }
+        }
+
+        impl<G: AffineCurve, D: Digest> SrsFileScheme<G::ScalarField>
+            for InnerProductArgPC<G, D, DensePolynomial<G::ScalarField>>
+        {
+            fn load(_: &Path, _: usize) -> io::Result<Self::UniversalParams> {
+                unreachable!("--srs is only supported for --pc kzg")
+            }
+
+            fn save(_: &Self::UniversalParams, _: &Path) -> io::Result<()> {
+                unreachable!("--srs is only supported for --pc kzg")
+            }
+        }
+
+        /// The universal SRS for circuits within `bounds` (constraints, variables and non-zero
+        /// entries): sliced to their degree from the SRS file, if there is one, and otherwise set
+        /// up for them (and then written to it, by party 0 in MPC). Either way, the SRS has the
+        /// same degree, and so indexes to the same keys.
+        fn universal_srs<E, PC, R>(
+            bounds: (usize, usize, usize),
+            rng: &mut R,
+        ) -> PC::UniversalParams
+        where
+            E: PairingEngine,
+            PC: SrsFileScheme<E::Fr>,
+            R: Rng,
+        {
+            let (n_constraints, n_vars, n_non_zero) = bounds;
+            let degree =
+                AHPForR1CS::<E::Fr>::max_degree(n_constraints, n_vars, n_non_zero).unwrap();
+            let path = ENVELOPE.lock().unwrap().srs.clone();
+            if let Some(path) = path.as_ref().filter(|p| p.exists()) {
+                let timer = start_mpc_timer!(|| "load srs");
+                let srs = PC::load(path, degree)
+                    .unwrap_or_else(|e| panic!("Bad SRS file {}: {}", path.display(), e));
+                end_mpc_timer!(timer);
+                return srs;
+            }
+            let srs = PC::setup(degree, None, rng).unwrap();
+            if let Some(path) = path {
+                if !MpcMultiNet::is_init() || MpcMultiNet::am_king() {
+                    PC::save(&srs, &path)
+                        .unwrap_or_else(|e| panic!("Could not write {}: {}", path.display(), e));
+                    println!("Wrote SRS file {}", path.display());
+                }
+            }
+            srs
+        }
+
+        /// Print the size of each component of `proof`, if asked to. With `reference`, a local
+        /// proof from the same index, first check that `proof` has the same layout.
+        fn report_proof<E, PC>(proof: &Proof<E::Fr, PC>, reference: Option<&Proof<E::Fr, PC>>)
+        where
+            E: PairingEngine,
+            PC: PolynomialCommitment<E::Fr, DensePolynomial<E::Fr>>,
+        {
+            if !REPORT_PROOF.load(Ordering::Relaxed) {
+                return;
+            }
+            let sizes = proof.component_sizes();
+            if let Some(reference) = reference {
+                assert_eq!(
+                    sizes,
+                    reference.component_sizes(),
+                    "The proof's layout differs from a local proof's"
+                );
+            }
+            let mut bytes = Vec::new();
+            proof.serialize(&mut bytes).unwrap();
+            let mut again = Vec::new();
+            Proof::<E::Fr, PC>::deserialize(&bytes[..])
+                .expect("The proof does not deserialize")
+                .serialize(&mut again)
+                .unwrap();
+            assert!(bytes == again, "The proof does not serialize canonically");
+            for (component, size) in sizes {
+                println!("Proof component: {:<24} {:>8} B", component, size);
+            }
+            println!("Proof size: {} B", bytes.len());
+        }
+
+        type KzgPC<E> = MarlinKZG10<E, DensePolynomial<<E as PairingEngine>::Fr>>;
+        type IpaPC<E> = InnerProductArgPC<
+            <E as PairingEngine>::G1Affine,
+            Blake2s,
+            DensePolynomial<<E as PairingEngine>::Fr>,
+        >;
+
+        /// A Marlin benchmark over the polynomial commitment scheme `$pc<E>`, with Fiat-Shamir
+        /// hash `$fs`.
+        macro_rules! marlin_bench {
+            ($bench:ident, $pc:ident, $fs:ty) => {
+                pub struct $bench;
+
+                impl $bench {
+                    /// The index of `C`'s size-`n` circuit, from the universal SRS for it.
+                    /// Only the first run of a process sets up (or reads) the SRS and indexes.
+                    fn index<E: PairingEngine, C: BenchCircuit, R: Rng>(
+                        n: usize,
+                        rng: &mut R,
+                    ) -> (IndexProverKey<E::Fr, $pc<E>>, IndexVerifierKey<E::Fr, $pc<E>>) {
+                        cached(n, || {
+                            let srs = universal_srs::<E, $pc<E>, _>(C::marlin_bounds(n), rng);
+                            let circ_no_data = C::without_data::<E::Fr>(n);
+                            Marlin::<E::Fr, $pc<E>, $fs>::index(&srs, circ_no_data).unwrap()
+                        })
+                    }
+                }
+
+                impl SnarkBench for $bench {
+                    fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                        let rng = &mut local_rng();
+                        let (pk, vk) = Self::index::<E, C, _>(n, rng);
+
+                        let (circ_data, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                        let started_at = envelope::unix_time();
+                        let timer = start_mpc_timer!(|| timer_label);
+                        let zk_rng = &mut local_rng();
+                        let proof =
+                            Marlin::<E::Fr, $pc<E>, $fs>::prove(&pk, circ_data, zk_rng)
+                                .unwrap();
+                        end_mpc_timer!(timer);
+                        assert!(Marlin::<E::Fr, $pc<E>, $fs>::verify(
+                            &vk,
+                            &public_inputs,
+                            &proof,
+                            rng
+                        )
+                        .unwrap());
+                        report_proof::<E, _>(&proof, None);
+                        write_envelope::<E, C, _>(n, 1, started_at, &proof, &public_inputs);
+                    }
+
+                    fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                        n: usize,
+                        timer_label: &str,
+                    ) {
+                        let phases = &mut Phases::new();
+                        enter(phases, Phase::Setup);
+                        let rng = &mut test_rng();
+                        let (pk, vk) = Self::index::<E, C, _>(n, rng);
+                        let local_pk = match REPORT_PROOF.load(Ordering::Relaxed) {
+                            true => Some(pk.clone()),
+                            false => None,
+                        };
+                        let mpc_pk = IndexProverKey::from_public(pk);
+
+                        enter(phases, Phase::InputSharing);
+                        let computation_timer = start_mpc_timer!(|| "do the mpc (cheat)");
+                        let (circ_data, public_inputs) = shared_witness::<E, S, C, _>(n, rng);
+                        end_mpc_timer!(computation_timer);
+                        enter(phases, Phase::Preprocessing);
+                        audit_witness(&circ_data);
+                        check_witness::<E, S, _>(&circ_data);
+                        let witness_commitments = commit_witness::<E, S, _>(&circ_data);
+
+                        channel::barrier(timer_label);
+                        MpcMultiNet::reset_stats();
+                        let started_at = envelope::unix_time();
+                        let timer = start_mpc_timer!(|| timer_label);
+                        // Each party contributes to the hiding randomness, so it is no one's.
+                        let zk_rng = &mut party_rng();
+                        let proof = channel::without_cheating(|| {
+                            enter(phases, Phase::Prove);
+                            let proof = Marlin::<
+                                MpcFr<E, S>,
+                                $pc<MpcPairingEngine<E, S>>,
+                                $fs,
+                            >::prove(&mpc_pk, circ_data, zk_rng)
+                            .unwrap();
+                            if SHARED_VERIFY.load(Ordering::Relaxed) {
+                                // Catch a cheating co-prover before anything is revealed; only
+                                // whether the proof is valid is opened.
+                                let mpc_vk = IndexVerifierKey::from_public(vk.clone());
+                                let mpc_inputs: Vec<MpcFr<E, S>> = public_inputs
+                                    .iter()
+                                    .map(|x| MpcFr::<E, S>::from_public(*x))
+                                    .collect();
+                                let verify_timer = start_mpc_timer!(|| "shared verification");
+                                let coin_rng = &mut public_rng("shared verify");
+                                let valid = Marlin::<
+                                    MpcFr<E, S>,
+                                    $pc<MpcPairingEngine<E, S>>,
+                                    $fs,
+                                >::verify(&mpc_vk, &mpc_inputs, &proof, coin_rng)
+                                .unwrap();
+                                end_mpc_timer!(verify_timer);
+                                assert!(valid, "The shared proof does not verify");
+                            }
+                            enter(phases, Phase::Reveal);
+                            proof.reveal()
+                        });
+                        end_mpc_timer!(timer);
+                        enter(phases, Phase::Verify);
+                        assert!(Marlin::<E::Fr, $pc<E>, $fs>::verify(
+                            &vk,
+                            &public_inputs,
+                            &proof,
+                            rng
+                        )
+                        .unwrap());
+                        if let Some(pk) = local_pk {
+                            // The layout depends only on the index, not on the witness.
+                            let (circ_data, _) = C::local::<E::Fr, _>(n, &mut test_rng());
+                            let zk_rng = &mut test_rng();
+                            let reference =
+                                Marlin::<E::Fr, $pc<E>, $fs>::prove(&pk, circ_data, zk_rng)
+                                    .unwrap();
+                            report_proof::<E, _>(&proof, Some(&reference));
+                        }
+                        report_metadata(witness_commitments);
+                        if MpcMultiNet::am_king() {
+                            let n_parties = MpcMultiNet::n_parties();
+                            write_envelope::<E, C, _>(
+                                n,
+                                n_parties,
+                                started_at,
+                                &proof,
+                                &public_inputs,
+                            );
+                            write_test_vector::<E, C, _, _>(
+                                n,
+                                n_parties,
+                                started_at,
+                                &vk,
+                                &proof,
+                                &public_inputs,
+                            );
+                        }
+                    }
+
+                    fn verify<E: PairingEngine, C: BenchCircuit>(
+                        n: usize,
+                        envelope: &Envelope,
+                    ) -> Result<bool, EnvelopeError> {
+                        let rng = &mut test_rng();
+                        let (_, vk) = Self::index::<E, C, _>(n, rng);
+                        let proof = envelope.proof::<Proof<E::Fr, $pc<E>>>()?;
+                        let public_inputs = envelope.public_inputs::<E::Fr>()?;
+                        Ok(Marlin::<E::Fr, $pc<E>, $fs>::verify(&vk, &public_inputs, &proof, rng)
+                            .unwrap())
+                    }
+                }
+            };
+        }
+
+        // The prover, over shares, hashes the same (public) transcript as the verifier, so both
+        // use a sponge over the plain field.
+        type Poseidon<E> = PoseidonSponge<<E as PairingEngine>::Fr>;
+
+        marlin_bench!(MarlinBench, KzgPC, Blake2s);
+        marlin_bench!(IpaMarlinBench, IpaPC, Blake2s);
+        marlin_bench!(PoseidonMarlinBench, KzgPC, Poseidon<E>);
+        marlin_bench!(PoseidonIpaMarlinBench, IpaPC, Poseidon<E>);
+    }
+
+    pub mod plonk {
+        use super::*;
+        use ark_poly::univariate::DensePolynomial;
+        use ark_poly_commit::marlin::marlin_pc::MarlinKZG10;
+        use mpc_algebra::Reveal;
+        use mpc_plonk::relations::flat::CircuitLayout;
+        use mpc_plonk::*;
+        use std::collections::HashMap;
+
+        type MarlinPcPlonk<Fr, E> = mpc_plonk::Plonk<Fr, MarlinKZG10<E, DensePolynomial<Fr>>>;
+
+        fn plonk_circuit<F: PrimeField, C: BenchCircuit>(c: C::Circuit<F>) -> PlonkCircuit<F> {
+            C::plonk(c).unwrap_or_else(|| {
+                panic!("{} has no Plonk circuit", std::any::type_name::<C>())
+            })
+        }
+
+        /// Name the public inputs after the circuit's public variables.
+        fn named_inputs<F: Field, G: Field>(c: &PlonkCircuit<F>, inputs: Vec<G>) -> HashMap<String, G> {
+            let mut vars: Vec<_> = c.pub_vars.iter().collect();
+            vars.sort();
+            assert_eq!(vars.len(), inputs.len());
+            vars.into_iter()
+                .map(|(_, name)| name.clone())
+                .zip(inputs)
+                .collect()
+        }
+
+        pub struct PlonkBench;
+
+        impl SnarkBench for PlonkBench {
+            fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let rng = &mut local_rng();
+                let circ_no_data = plonk_circuit::<_, C>(C::without_data::<E::Fr>(n));
+                let circ_no_data = CircuitLayout::from_circuit(&circ_no_data);
+
+                let (circ_data, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                let plonk_circ_data = plonk_circuit::<_, C>(circ_data);
+                let public_inputs = named_inputs(&plonk_circ_data, public_inputs);
+                let plonk_circ_data = CircuitLayout::from_circuit(&plonk_circ_data);
+                let setup_rng = &mut local_rng();
+                let zk_rng = &mut local_rng();
+                let srs =
+                    MarlinPcPlonk::<E::Fr, E>::universal_setup(n.next_power_of_two(), setup_rng);
+                let (pk, vk) = MarlinPcPlonk::<E::Fr, E>::circuit_setup(&srs, &circ_no_data);
+                let timer = start_mpc_timer!(|| timer_label);
+                let pf = MarlinPcPlonk::<E::Fr, E>::prove(&pk, &plonk_circ_data, zk_rng);
+                end_mpc_timer!(timer);
+                MarlinPcPlonk::<E::Fr, E>::verify(&vk, &circ_no_data, pf, &public_inputs);
+            }
+
+            fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                timer_label: &str,
+            ) {
+                let phases = &mut Phases::new();
+                enter(phases, Phase::Setup);
+                let circ_no_data = plonk_circuit::<_, C>(C::without_data::<E::Fr>(n));
+                let circ_no_data = CircuitLayout::from_circuit(&circ_no_data);
+                let setup_rng = &mut test_rng();
+                let srs =
+                    MarlinPcPlonk::<E::Fr, E>::universal_setup(n.next_power_of_two(), setup_rng);
+                let (pk, vk) = MarlinPcPlonk::<E::Fr, E>::circuit_setup(&srs, &circ_no_data);
+                let mpc_pk = Reveal::from_public(pk);
+
+                enter(phases, Phase::InputSharing);
+                let rng = &mut test_rng();
+                let (circ_data, public_inputs) = shared_witness::<E, S, C, _>(n, rng);
+                enter(phases, Phase::Preprocessing);
+                audit_witness(&circ_data);
+                check_witness::<E, S, _>(&circ_data);
+                let witness_commitments = commit_witness::<E, S, _>(&circ_data);
+                let plonk_circ_data = plonk_circuit::<_, C>(circ_data);
+                let public_inputs = named_inputs(&plonk_circ_data, public_inputs);
+                let plonk_circ_data = CircuitLayout::from_circuit(&plonk_circ_data);
+                let zk_rng = &mut party_rng();
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let t = start_mpc_timer!(|| timer_label);
+                let pf = channel::without_cheating(|| {
+                    enter(phases, Phase::Prove);
+                    let pf = MarlinPcPlonk::<MpcFr<E, S>, MpcPairingEngine<E, S>>::prove(
+                        &mpc_pk,
+                        &plonk_circ_data,
+                        zk_rng,
+                    );
+
+                    enter(phases, Phase::Reveal);
+                    let reveal_timer = start_mpc_timer!(|| "reveal");
+                    let pf = pf.reveal();
+                    end_mpc_timer!(reveal_timer);
+                    pf
+                });
+                end_mpc_timer!(t);
+                enter(phases, Phase::Verify);
+                MarlinPcPlonk::<E::Fr, E>::verify(&vk, &circ_no_data, pf, &public_inputs);
+                report_metadata(witness_commitments);
+            }
+        }
+    }
+
+    /// Groth16 proofs for a designated verifier, who set up the proving key (see [crate::dv]).
+    pub mod dv {
+        use super::*;
+        use ark_groth16::{Proof, ProvingKey};
+        use crate::groth::prover::create_random_proof;
+
+        /// The proving key from the key file, which only the verifier may set up.
+        fn proving_key<E: PairingEngine, C: BenchCircuit>(n: usize) -> ProvingKey<E> {
+            let path = ENVELOPE.lock().unwrap().key.clone().unwrap();
+            let header = envelope_header::<E, C>(n, 0, 0);
+            cached(n, || {
+                KeyFile::load(&path)
+                    .and_then(|file| {
+                        file.check(&header)?;
+                        file.key()
+                    })
+                    .unwrap_or_else(|e| panic!("Bad key file {}: {}", path.display(), e))
+            })
+        }
+
+        pub struct DvBench;
+
+        impl SnarkBench for DvBench {
+            fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let pk = proving_key::<E, C>(n);
+                let rng = &mut local_rng();
+                let (circ, public_inputs) = C::local::<E::Fr, _>(n, rng);
+                let started_at = envelope::unix_time();
+                let timer = start_mpc_timer!(|| timer_label);
+                let proof = create_random_proof::<E, _, _>(circ, &pk, &mut party_rng()).unwrap();
+                end_mpc_timer!(timer);
+                write_envelope::<E, C, _>(n, 1, started_at, &proof, &public_inputs);
+            }
+
+            fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                timer_label: &str,
+            ) {
+                let pk: ProvingKey<MpcPairingEngine<E, S>> =
+                    Reveal::from_public(proving_key::<E, C>(n));
+                let rng = &mut test_rng();
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let started_at = envelope::unix_time();
+                let timer = start_mpc_timer!(|| timer_label);
+                let (circ, public_inputs) = shared_witness::<E, S, C, _>(n, rng);
+                let proof = channel::without_cheating(|| {
+                    let pf = create_random_proof::<MpcPairingEngine<E, S>, _, _>(
+                        circ,
+                        &pk,
+                        &mut party_rng(),
+                    )
+                    .unwrap();
+                    pf.reveal()
+                });
+                end_mpc_timer!(timer);
+                if MpcMultiNet::am_king() {
+                    let n_parties = MpcMultiNet::n_parties();
+                    write_envelope::<E, C, _>(n, n_parties, started_at, &proof, &public_inputs);
+                }
+            }
+
+            fn verify<E: PairingEngine, C: BenchCircuit>(
+                _n: usize,
+                envelope: &Envelope,
+            ) -> Result<bool, EnvelopeError> {
+                let proof = envelope.proof::<Proof<E>>()?;
+                let public_inputs = envelope.public_inputs::<E::Fr>()?;
+                let path = ENVELOPE.lock().unwrap().dv_key.clone().unwrap();
+                let key = DvKey::<E>::load(&path)
+                    .unwrap_or_else(|e| panic!("Could not read DV key {}: {}", path.display(), e));
+                Ok(key.verify(&proof, &public_inputs))
+            }
+        }
+    }
+
+    /// No proof: only the computation, to benchmark the MPC arithmetic itself.
+    pub mod none {
+        use super::*;
+        use ark_ff::Zero;
+
+        /// Synthesize `circ`, and combine its constraints, `<a, z> * <b, z> - <c, z>`, with
+        /// coefficients from `coeff`. This is zero if (and, for random coefficients, only if, with
+        /// high probability) `circ` is satisfied; in MPC it takes a single round.
+        pub(super) fn residual<F: Field>(
+            circ: impl ConstraintSynthesizer<F>,
+            coeff: impl FnMut() -> F,
+        ) -> F {
+            let cs = ConstraintSystem::new_ref();
+            circ.generate_constraints(cs.clone()).unwrap();
+            cs.finalize();
+            let cs = cs.borrow().unwrap();
+            let matrices = cs.to_matrices().unwrap();
+            let z: Vec<F> = cs
+                .instance_assignment
+                .iter()
+                .chain(&cs.witness_assignment)
+                .cloned()
+                .collect();
+            let az = F::sparse_mat_vec(&matrices.a, &z);
+            let bz = F::sparse_mat_vec(&matrices.b, &z);
+            let cz = F::sparse_mat_vec(&matrices.c, &z);
+            let r: Vec<F> = std::iter::repeat_with(coeff).take(az.len()).collect();
+            let r_az: Vec<F> = r.iter().zip(&az).map(|(r, a)| *r * a).collect();
+            F::dot(&r_az, &bz) - F::dot(&r, &cz)
+        }
+
+        pub struct NoProofBench;
+
+        impl SnarkBench for NoProofBench {
+            fn local<E: PairingEngine, C: BenchCircuit>(n: usize, timer_label: &str) {
+                let rng = &mut local_rng();
+                let coeff_rng = &mut local_rng();
+                let timer = start_mpc_timer!(|| timer_label);
+                let (circ, _) = C::local::<E::Fr, _>(n, rng);
+                let res = residual(circ, || E::Fr::rand(coeff_rng));
+                end_mpc_timer!(timer);
+                assert!(res.is_zero(), "The computation is wrong");
+            }
+
+            fn mpc<E: PairingEngine, S: PairingShare<E>, C: BenchCircuit>(
+                n: usize,
+                timer_label: &str,
+            ) {
+                let rng = &mut test_rng();
+                let coeff_rng = &mut public_rng("residual");
+                channel::barrier(timer_label);
+                MpcMultiNet::reset_stats();
+                let timer = start_mpc_timer!(|| timer_label);
+                let (circ, _) = shared_witness::<E, S, C, _>(n, rng);
+                let res = residual(circ, || MpcFr::<E, S>::public_rand(coeff_rng));
+                let res = res.reveal();
+                end_mpc_timer!(timer);
+                assert!(res.is_zero(), "The computation is wrong");
+            }
+        }
+    }
+}
+
+
+#[derive(Debug, StructOpt)]
+struct ShareInfo {
+    /// File with list of hosts
+    #[structopt(long, parse(from_os_str))]
+    hosts: PathBuf,
+
+    /// Rendezvous file through which parties with port 0 in the hosts file find each other
+    #[structopt(long, parse(from_os_str))]
+    rendezvous: Option<PathBuf>,
+
+    /// Listen here (e.g., 0.0.0.0:0 or [::]:0 for all interfaces, port 0 meaning our port in the
+    /// hosts file), rather than at our address in the hosts file, which peers connect to
+    #[structopt(long)]
+    bind: Option<std::net::SocketAddr>,
+
+    /// Compress large messages, to the peers that also do
+    #[structopt(long)]
+    compress: bool,
+
+    /// Which party are you? 0 or 1?
+    #[structopt(long, default_value = "0")]
+    party: u8,
+
+    /// Use spdz?
+    #[structopt(long)]
+    alg: MpcAlg,
+
+    /// Directory of a randomness pool to draw preprocessing material from
+    #[structopt(long, parse(from_os_str))]
+    pool: Option<PathBuf>,
+
+    /// Session name under which pool consumption is accounted
+    #[structopt(long, default_value = "proof")]
+    session: String,
+
+    /// How many records to reserve from the pool at a time
+    #[structopt(long, default_value = "4096")]
+    pool_chunk: usize,
+
+    /// Batch the consistency checks into one comparison at the end; this runs them in a release
+    /// build too
+    #[structopt(long)]
+    defer_checks: bool,
+
+    /// Run the self-checks (that the parties agree on what they open, and that products open
+    /// correctly) in a release build too; debug builds always run them
+    #[structopt(long)]
+    self_checks: bool,
+
+    /// Digest that the consistency checks compare: blake2s or sha3-256
+    #[structopt(long, default_value = "blake2s")]
+    check_digest: mpc_algebra::macros::CheckDigest,
+
+    /// Check values larger than this many bytes by their digest, rather than sending them
+    #[structopt(long, default_value = "64")]
+    raw_check_limit: usize,
+
+    /// Give the Groth16 prover a secret-shared proving key
+    #[structopt(long)]
+    shared_pk: bool,
+
+    /// Keep the Groth16 prover's group elements shared until the proof is revealed
+    #[structopt(long)]
+    no_group_openings: bool,
+
+    /// Keep only our shard of the Groth16 proving key, and stream it to the other parties for
+    /// each MSM: each party stores 1/n of the key, for sending its shard once per MSM
+    #[structopt(long)]
+    shard_pk: bool,
+
+    /// Exchange commitments to witness shares before proving, and report them with the proof
+    #[structopt(long)]
+    commit_witness: bool,
+
+    /// Before proving, check that the shared witness satisfies every constraint, and abort with
+    /// the first that it violates if not
+    #[structopt(long)]
+    check_witness: bool,
+
+    /// Before proving, print which party's data each constraint touches, as the computation tags
+    /// its witness (by party 0)
+    #[structopt(long)]
+    audit: bool,
+
+    /// Before proving, print which constraints take only one party's data, as the computation tags
+    /// its witness, and how many Beaver triples multiplying that data at its party would save (by
+    /// party 0)
+    #[structopt(long)]
+    locality: bool,
+
+    /// Keep the shared witnesses of up to this many statements in memory, to prove any of them
+    /// again without sharing its inputs or computing its witness (daemon requests that name their
+    /// inputs only)
+    #[structopt(long, default_value = "0")]
+    witness_cache: usize,
+
+    /// How many parties may drop out before the protocol aborts (gsz only)
+    #[structopt(long, default_value = "0")]
+    max_dropouts: usize,
+
+    /// Serve Prometheus metrics over HTTP at this address
+    #[structopt(long)]
+    metrics: Option<std::net::SocketAddr>,
+
+    /// Prove this many instances concurrently, after one setup (groth16 only)
+    #[structopt(long, default_value = "1")]
+    batch: usize,
+
+    /// Reveal the proof to this party only; the others just learn that it was (groth16 only)
+    #[structopt(long)]
+    reveal_to: Option<usize>,
+
+    /// Verify the proof while it is still shared, opening only whether it is valid (marlin with
+    /// kzg only)
+    #[structopt(long)]
+    shared_verify: bool,
+
+    /// Abort, naming the phase and round that stalled, if a peer sends nothing for this many
+    /// seconds
+    #[structopt(long)]
+    round_timeout: Option<f64>,
+
+    /// Abort, naming the phase and round that stalled, if the proof is not done this many
+    /// seconds after connecting
+    #[structopt(long)]
+    deadline: Option<f64>,
+
+    /// Exchange messages on this many threads of their own, rather than on the compute threads
+    #[structopt(long)]
+    net_threads: Option<usize>,
+
+    /// Draw public coins (e.g., for --check-witness and --shared-verify) from this drand round,
+    /// as served at `/public/<round>`, rather than from a fixed seed
+    #[structopt(long, parse(from_os_str), requires = "beacon-info")]
+    beacon: Option<PathBuf>,
+
+    /// The drand network's chain info, as served at `/info`, against which --beacon is verified
+    #[structopt(long, parse(from_os_str))]
+    beacon_info: Option<PathBuf>,
+}
+
+impl ShareInfo {
+    /// Connect, and check that all parties agree on `params` and on our own settings; returns
+    /// all the parameters agreed on.
+    fn setup<'a>(&self, mut params: Vec<(&'a str, String)>) -> Vec<(&'a str, String)> {
+        assert!(
+            self.max_dropouts == 0 || self.alg == MpcAlg::Gsz,
+            "--max-dropouts needs threshold shares (--alg gsz)"
+        );
+        assert!(
+            !(self.no_group_openings && self.shared_pk),
+            "--no-group-openings needs a public proving key (no --shared-pk)"
+        );
+        assert!(
+            !(self.shard_pk && (self.shared_pk || self.no_group_openings)),
+            "--shard-pk cannot be combined with --shared-pk or --no-group-openings"
+        );
+        // Lanes run in no particular order relative to each other, so they cannot share a stream
+        // of preprocessing or of deferred checks, which parties must consume in the same order.
+        assert!(
+            self.batch == 1
+                || !(self.pool.is_some()
+                    || self.defer_checks
+                    || self.commit_witness
+                    || self.check_witness),
+            "--batch cannot be combined with --pool, --defer-checks, --commit-witness or \
+             --check-witness"
+        );
+        assert!(
+            !(self.batch > 1 && self.reveal_to.is_some()),
+            "--reveal-to does not apply to batches"
+        );
+        if let Some(path) = &self.beacon {
+            let info_path = self.beacon_info.as_ref().unwrap();
+            let info = ChainInfo::load(info_path)
+                .unwrap_or_else(|e| panic!("Could not read {}: {}", info_path.display(), e));
+            let beacon = Beacon::load(path)
+                .and_then(|b| b.verify(&info).map(|()| b))
+                .unwrap_or_else(|e| panic!("Bad beacon {}: {}", path.display(), e));
+            println!("Public coins from drand round {}", beacon.round);
+            *BEACON.lock().unwrap() = Some(beacon);
+        }
+        // The share type fixes the message formats, so it is checked on connecting.
+        handshake::set_app_features(1 << self.alg as u16);
+        MpcMultiNet::set_compression(self.compress);
+        MpcMultiNet::set_net_threads(self.net_threads);
+        MpcMultiNet::set_timeout(self.round_timeout.map(Duration::from_secs_f64));
+        MpcMultiNet::set_bind_addr(self.bind);
+        let hosts = self.hosts.to_str().unwrap();
+        match &self.rendezvous {
+            Some(r) => {
+                MpcMultiNet::init_with_rendezvous(hosts, self.party as usize, r.to_str().unwrap())
+            }
+            None => MpcMultiNet::init_from_file(hosts, self.party as usize),
+        }
+        params.extend(vec![
+            ("alg", self.alg.to_string()),
+            ("shared pk", self.shared_pk.to_string()),
+            ("no group openings", self.no_group_openings.to_string()),
+            ("shard pk", self.shard_pk.to_string()),
+            ("commit witness", self.commit_witness.to_string()),
+            ("check witness", self.check_witness.to_string()),
+            ("self checks", (self.self_checks || mpc_net::self_checks()).to_string()),
+            ("defer checks", self.defer_checks.to_string()),
+            ("check digest", self.check_digest.to_string()),
+            ("raw check limit", self.raw_check_limit.to_string()),
+            ("max dropouts", self.max_dropouts.to_string()),
+            ("batch", self.batch.to_string()),
+            ("reveal to", format!("{:?}", self.reveal_to)),
+            ("shared verify", self.shared_verify.to_string()),
+            (
+                "beacon round",
+                format!("{:?}", BEACON.lock().unwrap().as_ref().map(|b| b.round)),
+            ),
+        ]);
+        if let Err(e) = handshake::check_params::<MpcMultiNet>(&params) {
+            panic!("Parties disagree on parameters: {}", e);
+        }
+        MpcMultiNet::set_max_dropouts(self.max_dropouts);
+        MpcMultiNet::set_deadline(self.deadline.map(Duration::from_secs_f64));
+        if let Some(addr) = self.metrics {
+            let addr = metrics::serve(addr)
+                .unwrap_or_else(|e| panic!("Could not serve metrics at {}: {}", addr, e));
+            println!("Serving metrics at http://{}/metrics", addr);
+        }
+        if let Some(dir) = &self.pool {
+            let pool = preprocessing::RandomnessPool::open(dir, self.party as usize)
+                .unwrap_or_else(|e| panic!("Could not open pool {}: {}", dir.display(), e));
+            let session = preprocessing::PoolSession::new(pool, &self.session, self.pool_chunk)
+                .unwrap_or_else(|e| panic!("Could not start pool session: {}", e));
+            preprocessing::install(session);
+        }
+        if self.self_checks {
+            mpc_net::set_self_checks(true);
+        }
+        mpc_algebra::macros::set_deferred_checks(self.defer_checks);
+        mpc_algebra::macros::set_check_digest(self.check_digest);
+        mpc_algebra::macros::set_raw_check_limit(self.raw_check_limit);
+        benches::groth::SHARED_PK.store(self.shared_pk, Ordering::Relaxed);
+        benches::groth::NO_GROUP_OPENINGS.store(self.no_group_openings, Ordering::Relaxed);
+        benches::groth::SHARD_PK.store(self.shard_pk, Ordering::Relaxed);
+        if let Some(party) = self.reveal_to {
+            assert!(party < MpcMultiNet::n_parties(), "--reveal-to {} is no party", party);
+            benches::groth::REVEAL_TO.store(party, Ordering::Relaxed);
+        }
+        COMMIT_WITNESS.store(self.commit_witness, Ordering::Relaxed);
+        CHECK_WITNESS.store(self.check_witness, Ordering::Relaxed);
+        AUDIT.store(self.audit, Ordering::Relaxed);
+        LOCALITY.store(self.locality, Ordering::Relaxed);
+        benches::keep_witnesses(self.witness_cache);
+        benches::marlin::SHARED_VERIFY.store(self.shared_verify, Ordering::Relaxed);
+        params
+    }
+    /// The parties' addresses, as the hosts file lists them.
+    fn parties(&self) -> Vec<String> {
+        std::fs::read_to_string(&self.hosts)
+            .unwrap_or_else(|e| panic!("Could not read {}: {}", self.hosts.display(), e))
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_owned)
+            .collect()
+    }
+    fn teardown(&self) {
+        let stats = MpcMultiNet::stats();
+        debug!("Stats: {:#?}", stats);
+        if !stats.dropouts.is_empty() {
+            println!("Degraded run: parties {:?} dropped out", stats.dropouts);
+        }
+        if self.defer_checks {
+            match mpc_algebra::macros::finish_deferred_checks() {
+                Ok(n) => println!("Deferred consistency checks passed ({} phases)", n),
+                Err(phase) => panic!("Consistency check failed in phase {}", phase),
+            }
+        }
+        if let Some(session) = preprocessing::uninstall() {
+            println!("Pool usage ({}): {:?}", self.session, session.used());
+        }
+        MpcMultiNet::deinit();
+    }
+    fn run<E: PairingEngine, B: SnarkBench, C: BenchCircuit>(
+        &self,
+        computation_size: usize,
+        timed_label: &str,
+    ) {
+        use mpc_algebra::share::{add, gsz20, spdz};
+        let (n, label) = (computation_size, timed_label);
+        match self.alg {
+            MpcAlg::Spdz => self.run_with::<E, spdz::SpdzPairingShare<E>, B, C>(n, label),
+            MpcAlg::Hbc => self.run_with::<E, add::AdditivePairingShare<E>, B, C>(n, label),
+            MpcAlg::Gsz => self.run_with::<E, gsz20::GszPairingShare<E>, B, C>(n, label),
+        }
+        metrics::inc(
+            "mpc_proofs_completed_total",
+            "Proofs produced and verified.",
+            self.batch as u64,
+        );
+    }
+    fn run_with<E: PairingEngine, S: PairingShare<E>, B: SnarkBench, C: BenchCircuit>(
+        &self,
+        computation_size: usize,
+        timed_label: &str,
+    ) {
+        if self.batch > 1 {
+            B::mpc_batch::<E, S, C>(computation_size, self.batch, timed_label)
+        } else {
+            B::mpc::<E, S, C>(computation_size, timed_label)
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum MpcAlg {
+        Spdz,
+        Hbc,
+        Gsz,
+    }
+}
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum ProofSystem {
+        Groth16,
+        Marlin,
+        Plonk,
+        Dv,
+        None,
+    }
+}
+
+arg_enum! {
+    #[allow(non_camel_case_types)]
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum Curve {
+        Bls12_377,
+        Cp6_782,
+        Bn254,
+    }
+}
+
+impl Curve {
+    /// The curve's name, as the literature writes it.
+    fn title(&self) -> &'static str {
+        match self {
+            Curve::Bls12_377 => "BLS12-377",
+            Curve::Cp6_782 => "CP6-782",
+            Curve::Bn254 => "BN254",
+        }
+    }
+}
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum PcScheme {
+        Kzg,
+        Ipa,
+    }
+}
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum FsHash {
+        Blake2s,
+        Poseidon,
+    }
+}
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum Security {
+        Secure,
+        Benchmark,
+    }
+}
+
+#[derive(Debug, StructOpt)]
+enum FieldOpt {
+    Mpc {
+        #[structopt(flatten)]
+        party_info: ShareInfo,
+    },
+    Local,
+    ArkLocal,
+    /// Check a proof envelope written with --envelope
+    Verify {
+        #[structopt(long, parse(from_os_str))]
+        envelope: PathBuf,
+    },
+    /// Remove the provenance block (see --provenance) from an envelope or a test vector, in place
+    /// or into --out
+    StripProvenance {
+        #[structopt(long, parse(from_os_str))]
+        envelope: PathBuf,
+
+        #[structopt(long, parse(from_os_str))]
+        out: Option<PathBuf>,
+    },
+    /// Set up as the designated verifier: write the provers' proving key to --key, and the
+    /// secret key to check their proofs with to --out (dv only)
+    DvKeys {
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+    },
+    /// Prove in MPC, and write the proof, its public inputs and the verifying key (in hex JSON)
+    /// for verifiers outside this repository to check (groth16 and marlin only)
+    GenTestVectors {
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+
+        #[structopt(flatten)]
+        party_info: ShareInfo,
+    },
+    /// Stay connected to the other parties, and prove in MPC what is requested on --socket (one
+    /// JSON request per line), with keys kept in memory between requests, until asked to shut
+    /// down
+    Daemon {
+        #[structopt(long, parse(from_os_str))]
+        socket: PathBuf,
+
+        #[structopt(flatten)]
+        party_info: ShareInfo,
+    },
+}
+
+impl FieldOpt {
+    /// The MPC settings, if this runs in MPC.
+    fn party_info(&self) -> Option<&ShareInfo> {
+        match self {
+            FieldOpt::Mpc { party_info }
+            | FieldOpt::GenTestVectors { party_info, .. }
+            | FieldOpt::Daemon { party_info, .. } => Some(party_info),
+            _ => None,
+        }
+    }
+    /// Set up, returning all the parameters that the parties agreed on (just `params`, locally).
+    fn setup<'a>(&self, params: Vec<(&'a str, String)>) -> Vec<(&'a str, String)> {
+        match self.party_info() {
+            Some(party_info) => party_info.setup(params),
+            None => params,
+        }
+    }
+    fn teardown(&self) {
+        if let Some(party_info) = self.party_info() {
+            party_info.teardown();
+        }
+        println!("Stats: {:#?}", MpcMultiNet::stats());
+        print!("Timers:\n{}", timer::summary());
+    }
+    fn run<E: PairingEngine, B: SnarkBench, C: BenchCircuit>(
+        &self,
+        computation_size: usize,
+        _b: B,
+        timed_label: &str,
+    ) {
+        match self {
+            FieldOpt::Mpc { party_info, .. }
+            | FieldOpt::GenTestVectors { party_info, .. }
+            | FieldOpt::Daemon { party_info, .. } => {
+                party_info.run::<E, B, C>(computation_size, timed_label)
+            }
+            FieldOpt::Local => B::local::<E, C>(computation_size, timed_label),
+            FieldOpt::ArkLocal => B::ark_local::<E, C>(computation_size, timed_label),
+            FieldOpt::Verify { envelope } => {
+                verify_envelope::<E, B, C>(computation_size, envelope)
+            }
+            FieldOpt::StripProvenance { envelope, out } => {
+                let out = out.as_ref().unwrap_or(envelope);
+                strip_provenance(envelope, out)
+                    .unwrap_or_else(|e| panic!("Could not strip {}: {}", envelope.display(), e));
+                println!("Wrote {} without provenance", out.display());
+            }
+            FieldOpt::DvKeys { out } => set_up_dv::<E, C>(computation_size, out),
+        }
+    }
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "proof", about = "Standard and MPC proofs")]
+struct Opt {
+    /// Computation to perform: one of those registered, e.g. squaring, rangeproof, auction,
+    /// secondpriceauction, sha256preimage, shuffle or ecdsasignature
+    #[structopt(short = "c")]
+    computation: String,
+
+    /// Proof system to use; with `dv`, prove to the designated verifier that set up the key in
+    /// --key (see `dv-keys`); with `none`, only run the computation (checking its constraints)
+    #[structopt(short = "p")]
+    proof_system: ProofSystem,
+
+    /// Computation to perform
+    #[structopt(long, default_value = "10")]
+    computation_size: usize,
+
+    /// Polynomial commitment scheme (Marlin only)
+    #[structopt(long, default_value = "kzg")]
+    pc: PcScheme,
+
+    /// Fiat-Shamir hash (Marlin only)
+    #[structopt(long, default_value = "blake2s")]
+    fs: FsHash,
+
+    /// Make proofs simulation-extractable, so that no one can re-randomize them or claim them
+    /// as their own: bind each to a fresh one-time signature key, and sign it (groth16 only)
+    #[structopt(long)]
+    se: bool,
+
+    /// Pairing-friendly curve to prove over
+    #[structopt(long, default_value = "bls12_377")]
+    curve: Curve,
+
+    /// Write the proof, with its public inputs and metadata, to this file (by party 0, in MPC)
+    #[structopt(long, parse(from_os_str))]
+    envelope: Option<PathBuf>,
+
+    /// Record in envelopes and test vectors how the proof was made: the software version, a digest
+    /// of the agreed parameters, the parties' addresses and session, and the time and
+    /// communication of each phase so far (as the party writing it saw them)
+    #[structopt(long)]
+    provenance: bool,
+
+    /// Read the proving key from this file, refusing it if it is for another circuit; if there is
+    /// no such file, set up and write it (by party 0, in MPC) (groth16); or read the proving key
+    /// that the designated verifier set up (dv)
+    #[structopt(long, parse(from_os_str))]
+    key: Option<PathBuf>,
+
+    /// Read the proving key from this snarkjs `.zkey`, as a circom ceremony made it, refusing it
+    /// if it is for another circuit; proofs then verify against the ceremony's verifying key
+    /// (groth16 only)
+    #[structopt(long, parse(from_os_str))]
+    zkey: Option<PathBuf>,
+
+    /// Prove the computation in this many chunks, each under one key the size of a chunk, with
+    /// the state between them committed to rather than revealed (groth16, for computations that
+    /// repeat one step, such as squaring)
+    #[structopt(long)]
+    chunks: Option<usize>,
+
+    /// Read the universal SRS from this file, only as far as the circuit's degree needs; if there
+    /// is no such file, set up and write one of that degree (by party 0, in MPC) (marlin with
+    /// --pc kzg only)
+    #[structopt(long, parse(from_os_str))]
+    srs: Option<PathBuf>,
+
+    /// Read the designated verifier's secret key from this file, to check proofs with (dv
+    /// `verify` only)
+    #[structopt(long, parse(from_os_str))]
+    dv_key: Option<PathBuf>,
+
+    /// Compute on this many threads, rather than one per core
+    #[structopt(long)]
+    threads: Option<usize>,
+
+    /// Print the size of each component of the proof, checking an MPC proof's layout against a
+    /// local proof's (marlin only)
+    #[structopt(long)]
+    report_proof: bool,
+
+    /// With `secure`, refuse the insecure shortcuts that benchmarks take (dummy triples, the SPDZ
+    /// MAC key, fixed-seed randomness), rather than warn of them
+    #[structopt(long, default_value = "benchmark")]
+    security: Security,
+
+    /// Runs to make and discard before the measured ones, warming caches, connections and keys
+    #[structopt(long, default_value = "0")]
+    warmup: usize,
+
+    /// Measured runs, over which each phase's mean and standard deviation are reported; the
+    /// connection and the keys are kept between them
+    #[structopt(long, default_value = "1")]
+    reps: usize,
+
+    #[structopt(subcommand)]
+    field: FieldOpt,
+}
+
+impl Opt {
+    /// Settings that all parties must share.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("computation", self.computation.to_lowercase()),
+            ("computation size", self.computation_size.to_string()),
+            ("proof system", self.proof_system.to_string()),
+            ("pc", self.pc.to_string()),
+            ("fs", self.fs.to_string()),
+            ("se", self.se.to_string()),
+            ("curve", self.curve.to_string()),
+            ("warmup", self.warmup.to_string()),
+            ("reps", self.reps.to_string()),
+            ("chunks", self.chunks.unwrap_or(1).to_string()),
+        ]
+    }
+    /// The proof system, as named in envelopes: with the options that change the proof.
+    fn proof_system_id(&self) -> String {
+        match self.proof_system {
+            ProofSystem::Marlin => format!("marlin-{}-{}", self.pc, self.fs),
+            ProofSystem::Groth16 if self.se => "groth16-se".to_owned(),
+            ProofSystem::Groth16 if self.chunks.is_some() => {
+                format!("groth16-chunked-{}", self.chunks.unwrap())
+            }
+            p => p.to_string(),
+        }
+        .to_lowercase()
+    }
+    /// Check that `C`'s size-`n` circuit fits the proof system's domains, and the SRS
+    /// file's degree, by its bounds ([BenchCircuit::marlin_bounds]) rather than by synthesizing
+    /// it. The bounds are upper bounds, so a circuit just under a limit may be refused.
+    fn check_limits<E: PairingEngine, C: BenchCircuit>(&self, n: usize) -> Result<(), LimitError> {
+        // Chunks are proven one at a time.
+        let (n_constraints, n_vars, n_non_zero) =
+            C::marlin_bounds(self.chunks.map_or(n, |k| n / k));
+        let curve = self.curve.title();
+        match self.proof_system {
+            // The QAP is over the constraints, and a constraint per public input.
+            ProofSystem::Groth16 | ProofSystem::Dv => {
+                limits::check_domain::<E::Fr>("groth16", curve, n_constraints + n_vars)
+            }
+            ProofSystem::Plonk => limits::check_domain::<E::Fr>("plonk", curve, n_constraints),
+            ProofSystem::Marlin => {
+                let matrix_dim = n_constraints.max(n_vars);
+                limits::check_domain::<E::Fr>("marlin", curve, matrix_dim)?;
+                limits::check_domain::<E::Fr>("marlin", curve, n_non_zero)?;
+                let path = match self.srs.as_ref().filter(|p| p.exists()) {
+                    Some(path) => path,
+                    None => return Ok(()),
+                };
+                let degree = srs::SrsFile::<E, _>::open(path)
+                    .unwrap_or_else(|e| panic!("Bad SRS file {}: {}", path.display(), e))
+                    .max_degree();
+                let needed =
+                    AHPForR1CS::<E::Fr>::max_degree(n_constraints, n_vars, n_non_zero).unwrap();
+                limits::check_srs(path.clone(), degree, needed)
+            }
+            ProofSystem::None => Ok(()),
+        }
+    }
+
+    fn run<E: PairingEngine, C: BenchCircuit>(self) {
+        if let Err(e) = self.check_limits::<E, C>(self.computation_size) {
+            eprintln!("error: --computation-size {}: {}", self.computation_size, e);
+            std::process::exit(2);
+        }
+        if let Some(k) = self.chunks {
+            if C::steps(C::without_data::<E::Fr>(self.computation_size)).is_none() {
+                eprintln!(
+                    "error: --chunks: {} is not a repeated step",
+                    self.computation
+                );
+                std::process::exit(2);
+            }
+            if self.computation_size % k != 0 {
+                eprintln!(
+                    "error: --chunks {}: does not divide --computation-size {}",
+                    k, self.computation_size
+                );
+                std::process::exit(2);
+            }
+        }
+        *ENVELOPE.lock().unwrap() = EnvelopeSettings {
+            curve: self.curve.to_string().to_lowercase(),
+            proof_system: self.proof_system_id(),
+            out: self.envelope.clone(),
+            key: self.key.clone(),
+            zkey: self.zkey.clone(),
+            srs: self.srs.clone(),
+            dv_key: self.dv_key.clone(),
+            test_vectors: match &self.field {
+                FieldOpt::GenTestVectors { out, .. } => Some(out.clone()),
+                _ => None,
+            },
+            provenance: None,
+        };
+        let mut params = self.params();
+        if self.field.party_info().is_some() {
+            // Parties that run different versions of the computation disagree on it.
+            let circuit = C::without_data::<E::Fr>(self.computation_size);
+            params.push(("circuit hash", envelope::circuit_hash(circuit)));
+        }
+        let params = self.field.setup(params);
+        if self.provenance {
+            let parties = self.field.party_info().map_or(Vec::new(), |p| p.parties());
+            ENVELOPE.lock().unwrap().provenance = Some((envelope::params_digest(&params), parties));
+        }
+        if let FieldOpt::Daemon { socket, .. } = &self.field {
+            self.serve::<E, C>(socket);
+            self.field.teardown();
+            return;
+        }
+        let mut reps = timer::Repetitions::new();
+        for i in 0..self.warmup + self.reps {
+            if i == self.warmup {
+                // Forget the warm-up runs' sections.
+                timer::reset();
+            }
+            self.prove::<E, C>(self.computation_size);
+            if i >= self.warmup {
+                reps.record();
+            }
+        }
+        if self.reps > 1 {
+            print!(
+                "Per run ({} runs after {} warm-up):\n{}",
+                self.reps,
+                self.warmup,
+                reps.summary()
+            );
+        }
+        self.field.teardown();
+    }
+
+    /// Make one proof of `C`'s size-`n` circuit, with the proof system and in the mode chosen.
+    fn prove<E: PairingEngine, C: BenchCircuit>(&self, n: usize) {
+        let (field, label) = (&self.field, TIMED_SECTION_LABEL);
+        match self.proof_system {
+            ProofSystem::Groth16 if self.chunks.is_some() => {
+                field.run::<E, _, C>(n, benches::chunked::ChunkedBench, label)
+            }
+            ProofSystem::Groth16 => field.run::<E, _, C>(n, benches::groth::Groth16Bench, label),
+            ProofSystem::Plonk => field.run::<E, _, C>(n, benches::plonk::PlonkBench, label),
+            ProofSystem::Dv => field.run::<E, _, C>(n, benches::dv::DvBench, label),
+            ProofSystem::None => field.run::<E, _, C>(n, benches::none::NoProofBench, label),
+            ProofSystem::Marlin => {
+                use benches::marlin::*;
+                match (self.pc, self.fs) {
+                    (PcScheme::Kzg, FsHash::Blake2s) => field.run::<E, _, C>(n, MarlinBench, label),
+                    (PcScheme::Ipa, FsHash::Blake2s) => {
+                        field.run::<E, _, C>(n, IpaMarlinBench, label)
+                    }
+                    (PcScheme::Kzg, FsHash::Poseidon) => {
+                        field.run::<E, _, C>(n, PoseidonMarlinBench, label)
+                    }
+                    (PcScheme::Ipa, FsHash::Poseidon) => {
+                        field.run::<E, _, C>(n, PoseidonIpaMarlinBench, label)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A computation that the command line can prove, under a name (case-insensitive).
+#[derive(Clone, Copy)]
+pub struct Plugin {
+    name: &'static str,
+    curves: &'static [Curve],
+    run: fn(Opt),
+}
+
+impl Plugin {
+    /// The circuits `C`, under `name`, on every curve.
+    pub fn new<C: BenchCircuit>(name: &'static str) -> Self {
+        Self {
+            name,
+            curves: &[Curve::Bls12_377, Curve::Cp6_782, Curve::Bn254],
+            run: run_computation::<C>,
+        }
+    }
+    /// Restrict these circuits to `curves`, e.g. because they need a particular scalar field.
+    pub fn only_on(self, curves: &'static [Curve]) -> Self {
+        Self { curves, ..self }
+    }
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+fn run_computation<C: BenchCircuit>(opt: Opt) {
+    match opt.curve {
+        Curve::Bls12_377 => opt.run::<ark_bls12_377::Bls12_377, C>(),
+        Curve::Cp6_782 => opt.run::<ark_cp6_782::CP6_782, C>(),
+        Curve::Bn254 => opt.run::<ark_bn254::Bn254, C>(),
+    }
+}
+
+/// The computations that `proof` comes with.
+pub fn builtin() -> Vec<Plugin> {
+    vec![
+        Plugin::new::<squarings::Squaring>("squaring"),
+        Plugin::new::<range::RangeProof<2>>("rangeproof"),
+        Plugin::new::<auction::FirstPriceAuction>("auction"),
+        Plugin::new::<auction::SecondPriceAuction>("secondpriceauction"),
+        Plugin::new::<sha256::Sha256Preimage>("sha256preimage"),
+        Plugin::new::<shuffle::Shuffle<2>>("shuffle"),
+        // Signatures are over ed_on_bls12_377, whose base field is only bls12_377's scalar field.
+        Plugin::new::<ecdsa::EcdsaSignature>("ecdsasignature").only_on(&[Curve::Bls12_377]),
+    ]
+}
+
+/// The plugin registered as `name`, if any.
+fn find<'a>(registry: &'a [Plugin], name: &str) -> Option<&'a Plugin> {
+    registry.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// Run the command line, with `plugins` registered besides the [builtin] computations.
+pub fn main(plugins: Vec<Plugin>) {
+    let mut registry = builtin();
+    for p in plugins {
+        if find(&registry, p.name).is_some() {
+            panic!("Computation {} is registered twice", p.name);
+        }
+        registry.push(p);
+    }
+    let opt = Opt::from_args();
+    let plugin = *find(&registry, &opt.computation).unwrap_or_else(|| {
+        let names: Vec<&str> = registry.iter().map(|p| p.name).collect();
+        panic!(
+            "Unknown computation {}; expected one of: {}",
+            opt.computation,
+            names.join(", ")
+        )
+    });
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    match opt.security {
+        Security::Secure => security::set_security_mode(SecurityMode::Secure),
+        Security::Benchmark => {
+            security::set_security_mode(SecurityMode::Benchmark);
+            warn!("==================================================================");
+            warn!("BENCHMARK MODE: insecure shortcuts are allowed; do not deploy this.");
+            warn!("Pass --security secure to refuse them.");
+            warn!("==================================================================");
+        }
+    }
+    if opt.pc != PcScheme::Kzg && opt.proof_system != ProofSystem::Marlin {
+        panic!("--pc {} is only supported for marlin", opt.pc);
+    }
+    if opt.fs != FsHash::Blake2s && opt.proof_system != ProofSystem::Marlin {
+        panic!("--fs {} is only supported for marlin", opt.fs);
+    }
+    if opt.curve == Curve::Cp6_782 && opt.proof_system == ProofSystem::Plonk {
+        // Our Plonk needs a subgroup of order 3 in the scalar field, which cp6_782's lacks.
+        panic!("--curve {} is not supported for plonk", opt.curve);
+    }
+    if !plugin.curves.contains(&opt.curve) {
+        panic!("--computation {} does not support --curve {}", plugin.name, opt.curve);
+    }
+    if let Some(party_info) = opt.field.party_info() {
+        if party_info.reveal_to.is_some() && opt.proof_system != ProofSystem::Groth16 {
+            panic!("--reveal-to is only supported for groth16");
+        }
+        if party_info.batch > 1 && opt.proof_system != ProofSystem::Groth16 {
+            panic!("--batch is only supported for groth16");
+        }
+        if party_info.shared_verify
+            && (opt.proof_system != ProofSystem::Marlin || opt.pc != PcScheme::Kzg)
+        {
+            panic!("--shared-verify is only supported for marlin with --pc kzg");
+        }
+    }
+    if opt.reps == 0 {
+        panic!("--reps must be at least 1");
+    }
+    if opt.report_proof && opt.proof_system != ProofSystem::Marlin {
+        panic!("--report-proof is only supported for marlin");
+    }
+    benches::marlin::REPORT_PROOF.store(opt.report_proof, Ordering::Relaxed);
+    if opt.se {
+        if opt.proof_system != ProofSystem::Groth16 {
+            panic!("--se is only supported for groth16");
+        }
+        match &opt.field {
+            FieldOpt::ArkLocal | FieldOpt::GenTestVectors { .. } => {
+                panic!("--se is not supported for ark-local or gen-test-vectors")
+            }
+            FieldOpt::Mpc { party_info } if party_info.batch > 1 => {
+                panic!("--se does not apply to batches")
+            }
+            _ => {}
+        }
+    }
+    benches::groth::SE.store(opt.se, Ordering::Relaxed);
+    if opt.envelope.is_some() && opt.proof_system == ProofSystem::Plonk {
+        panic!("--envelope is not supported for plonk, whose proofs are not serializable");
+    }
+    let writes_envelopes =
+        opt.envelope.is_some() || matches!(opt.field, FieldOpt::GenTestVectors { .. });
+    if opt.provenance && !writes_envelopes {
+        panic!("--provenance needs --envelope, or gen-test-vectors");
+    }
+    if opt.key.is_some() && !matches!(opt.proof_system, ProofSystem::Groth16 | ProofSystem::Dv) {
+        panic!("--key is only supported for groth16 and dv");
+    }
+    if opt.zkey.is_some() {
+        if opt.proof_system != ProofSystem::Groth16 {
+            panic!("--zkey is only supported for groth16");
+        }
+        if opt.key.is_some() || opt.se {
+            // The ceremony fixed the key, for the circuit without the signature key's tag.
+            panic!("--zkey cannot be combined with --key or --se");
+        }
+        if let FieldOpt::ArkLocal = opt.field {
+            panic!("--zkey is not supported for ark-local, whose prover is arkworks' own");
+        }
+        if let Some(party_info) = opt.field.party_info() {
+            if party_info.shard_pk || party_info.no_group_openings {
+                panic!("--zkey cannot be combined with --shard-pk or --no-group-openings");
+            }
+        }
+    }
+    if let Some(k) = opt.chunks {
+        if opt.proof_system != ProofSystem::Groth16 {
+            panic!("--chunks is only supported for groth16");
+        }
+        if k == 0 {
+            panic!("--chunks must be at least 1");
+        }
+        if opt.key.is_some() || opt.zkey.is_some() || opt.se {
+            panic!("--chunks cannot be combined with --key, --zkey or --se");
+        }
+        if matches!(opt.field, FieldOpt::ArkLocal | FieldOpt::GenTestVectors { .. }) {
+            panic!("--chunks is not supported for ark-local or gen-test-vectors");
+        }
+        if let Some(party_info) = opt.field.party_info() {
+            if party_info.reveal_to.is_some()
+                || party_info.batch > 1
+                || party_info.shared_pk
+                || party_info.shard_pk
+                || party_info.no_group_openings
+            {
+                panic!(
+                    "--chunks cannot be combined with --reveal-to, --batch, --shared-pk, \
+                     --shard-pk or --no-group-openings"
+                );
+            }
+        }
+    }
+    benches::chunked::CHUNKS.store(opt.chunks.unwrap_or(1), Ordering::Relaxed);
+    if opt.srs.is_some() && (opt.proof_system != ProofSystem::Marlin || opt.pc != PcScheme::Kzg) {
+        panic!("--srs is only supported for marlin with --pc kzg");
+    }
+    if let FieldOpt::GenTestVectors { party_info, .. } = &opt.field {
+        if opt.proof_system != ProofSystem::Groth16 && opt.proof_system != ProofSystem::Marlin {
+            panic!("gen-test-vectors is only supported for groth16 and marlin");
+        }
+        if party_info.reveal_to.is_some() || party_info.batch > 1 {
+            panic!("gen-test-vectors needs one proof, revealed to all parties");
+        }
+    }
+    if opt.envelope.is_some() && opt.proof_system == ProofSystem::None {
+        panic!("--envelope needs a proof system");
+    }
+    if opt.dv_key.is_some() && opt.proof_system != ProofSystem::Dv {
+        panic!("--dv-key is only supported for dv");
+    }
+    if matches!(opt.field, FieldOpt::DvKeys { .. }) && opt.proof_system != ProofSystem::Dv {
+        panic!("dv-keys is only supported for dv");
+    }
+    if opt.proof_system == ProofSystem::Dv {
+        if opt.key.is_none() {
+            panic!("-p dv needs --key, to write the proving key to or read it from");
+        }
+        if matches!(opt.field, FieldOpt::Verify { .. }) && opt.dv_key.is_none() {
+            panic!("-p dv needs --dv-key to verify");
+        }
+    }
+    if opt.proof_system == ProofSystem::Dv
+        && opt.envelope.is_none()
+        && matches!(opt.field, FieldOpt::Local | FieldOpt::Mpc { .. })
+    {
+        panic!("-p dv needs --envelope, to write the proof to");
+    }
+    if let FieldOpt::Daemon { party_info, .. } = &opt.field {
+        if opt.envelope.is_some() || opt.warmup > 0 || opt.reps > 1 {
+            panic!("daemon takes no --warmup, --reps or --envelope (each request names its own)");
+        }
+        if opt.proof_system == ProofSystem::Dv {
+            panic!("daemon is not supported for dv, whose proofs need an envelope each");
+        }
+        if party_info.deadline.is_some() || party_info.batch > 1 {
+            panic!("daemon is not supported with --deadline or --batch");
+        }
+    }
+    if let Some(n) = opt.threads {
+        mpc_net::set_compute_threads(n);
+    }
+    (plugin.run)(opt);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_computations_by_name() {
+        let registry = builtin();
+        for p in &registry {
+            assert_eq!(find(&registry, p.name).unwrap().name, p.name);
+        }
+        assert_eq!(find(&registry, "Sha256Preimage").unwrap().name, "sha256preimage");
+        assert!(find(&registry, "sha512preimage").is_none());
+    }
+}
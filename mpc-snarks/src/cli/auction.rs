@@ -0,0 +1,300 @@
+//! Sealed-bid auctions: prove that a published winner and price are correct for private bids.
+use ark_ec::PairingEngine;
+use ark_ff::{BigInteger, Field, PrimeField};
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    },
+};
+use ark_std::rand::Rng;
+use mpc_algebra::{PairingShare, Reveal};
+use mpc_net::{MpcMultiNet, MpcNet};
+
+use super::{BenchCircuit, MpcFr};
+use crate::audit::{self, Owner};
+use crate::inputs::PublicInputs;
+
+/// Bids are integers in `[0, 2^BID_BITS)`.
+pub const BID_BITS: usize = 32;
+
+/// Public inputs are the winner's index and the price.
+///
+/// The winner's bid is at least every other bid. The price is the winning bid in a first-price
+/// auction, and the highest losing bid in a second-price one.
+#[derive(Clone)]
+pub struct AuctionCircuit<F: Field> {
+    second_price: bool,
+    winner: Option<F>,
+    price: Option<F>,
+    bids: Vec<Option<F>>,
+    /// One-hot encoding of the winner.
+    selectors: Vec<Option<F>>,
+    /// `selectors[i] * bids[i]`.
+    selected: Vec<Option<F>>,
+    bid_bits: Vec<Vec<Option<F>>>,
+    /// Bits of `winning bid - bids[i]`.
+    gap_bits: Vec<Vec<Option<F>>>,
+    /// Second-price only: bits of `e_i = (1 - selectors[i]) * (price - bids[i])`.
+    price_gap_bits: Vec<Vec<Option<F>>>,
+    /// Second-price only: running products of `e_i + selectors[i]`, which end at zero iff some
+    /// loser bid the price.
+    products: Vec<Option<F>>,
+    /// Who input each bid, for [crate::audit]; empty if no one is to be named.
+    bid_owners: Vec<Owner>,
+}
+
+impl<F: Field> AuctionCircuit<F> {
+    fn without_data(bidders: usize, second_price: bool) -> Self {
+        let bits = vec![vec![None; BID_BITS]; bidders];
+        Self {
+            second_price,
+            winner: None,
+            price: None,
+            bids: vec![None; bidders],
+            selectors: vec![None; bidders],
+            selected: vec![None; bidders],
+            bid_bits: bits.clone(),
+            gap_bits: bits.clone(),
+            price_gap_bits: if second_price { bits } else { Vec::new() },
+            products: vec![None; if second_price { bidders } else { 0 }],
+            bid_owners: Vec::new(),
+        }
+    }
+
+    /// The assignment for a known outcome; `to_bits` gives the low [BID_BITS] bits of a value.
+    fn new(
+        bids: Vec<F>,
+        winner: usize,
+        price: F,
+        second_price: bool,
+        to_bits: impl Fn(F) -> Vec<F>,
+    ) -> Self {
+        let some = |v: Vec<F>| v.into_iter().map(Some).collect::<Vec<_>>();
+        let top = bids[winner];
+        let selectors: Vec<F> = (0..bids.len()).map(|i| F::from(i == winner)).collect();
+        let selected = selectors.iter().zip(&bids).map(|(s, b)| *s * b).collect();
+        let bid_bits = bids.iter().map(|b| some(to_bits(*b))).collect();
+        let gap_bits = bids.iter().map(|b| some(to_bits(top - b))).collect();
+        let (price_gap_bits, products) = if second_price {
+            let gaps: Vec<F> = selectors
+                .iter()
+                .zip(&bids)
+                .map(|(s, b)| (F::one() - s) * (price - b))
+                .collect();
+            let products = gaps
+                .iter()
+                .zip(&selectors)
+                .scan(F::one(), |acc, (e, s)| {
+                    *acc *= *e + s;
+                    Some(Some(*acc))
+                })
+                .collect();
+            let bits = gaps.into_iter().map(|e| some(to_bits(e))).collect();
+            (bits, products)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        Self {
+            second_price,
+            winner: Some(F::from(winner as u64)),
+            price: Some(price),
+            selectors: some(selectors),
+            bids: some(bids),
+            selected: some(selected),
+            bid_bits,
+            gap_bits,
+            price_gap_bits,
+            products,
+            bid_owners: Vec::new(),
+        }
+    }
+}
+
+fn public_inputs<F: Field>(winner: usize, price: F) -> Vec<F> {
+    PublicInputs::new()
+        .u64("winner", winner as u64)
+        .field("price", price)
+        .to_vec()
+}
+
+fn witness<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    vals: &[Option<F>],
+) -> Result<Vec<Variable>, SynthesisError> {
+    vals.iter()
+        .map(|v| cs.new_witness_variable(|| v.ok_or(SynthesisError::AssignmentMissing)))
+        .collect()
+}
+
+/// Allocate boolean `bits`, and enforce `a * b = sum 2^i bits_i`; returns that sum.
+fn enforce_bits<F: Field>(
+    cs: &ConstraintSystemRef<F>,
+    a: LinearCombination<F>,
+    b: LinearCombination<F>,
+    bits: &[Option<F>],
+) -> Result<LinearCombination<F>, SynthesisError> {
+    let mut sum = lc!();
+    let mut coeff = F::one();
+    for v in witness(cs, bits)? {
+        cs.enforce_constraint(lc!() + v, lc!() + v, lc!() + v)?;
+        sum = sum + (coeff, v);
+        coeff.double_in_place();
+    }
+    cs.enforce_constraint(a, b, sum.clone())?;
+    Ok(sum)
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for AuctionCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let winner =
+            cs.new_input_variable(|| self.winner.ok_or(SynthesisError::AssignmentMissing))?;
+        let price =
+            cs.new_input_variable(|| self.price.ok_or(SynthesisError::AssignmentMissing))?;
+        let bids = witness(&cs, &self.bids)?;
+        for (b, owner) in bids.iter().zip(&self.bid_owners) {
+            audit::tag(*b, *owner);
+        }
+        let selectors = witness(&cs, &self.selectors)?;
+        let selected = witness(&cs, &self.selected)?;
+
+        let mut n_selected = lc!();
+        let mut index = lc!();
+        let mut top = lc!();
+        let mut i_f = ConstraintF::zero();
+        for i in 0..bids.len() {
+            let (s, b) = (selectors[i], bids[i]);
+            cs.enforce_constraint(lc!() + s, lc!() + s, lc!() + s)?;
+            cs.enforce_constraint(lc!() + s, lc!() + b, lc!() + selected[i])?;
+            n_selected = n_selected + s;
+            index = index + (i_f, s);
+            top = top + selected[i];
+            i_f += ConstraintF::one();
+        }
+        cs.enforce_constraint(n_selected, lc!() + Variable::One, lc!() + Variable::One)?;
+        cs.enforce_constraint(index, lc!() + Variable::One, lc!() + winner)?;
+
+        for i in 0..bids.len() {
+            let b = bids[i];
+            enforce_bits(&cs, lc!() + b, lc!() + Variable::One, &self.bid_bits[i])?;
+            enforce_bits(
+                &cs,
+                top.clone() - b,
+                lc!() + Variable::One,
+                &self.gap_bits[i],
+            )?;
+        }
+
+        if self.second_price {
+            let products = witness(&cs, &self.products)?;
+            let mut prev = lc!() + Variable::One;
+            for i in 0..bids.len() {
+                let (s, b) = (selectors[i], bids[i]);
+                let not_s = lc!() + Variable::One - s;
+                let gap = lc!() + price - b;
+                let e = enforce_bits(&cs, not_s, gap, &self.price_gap_bits[i])?;
+                cs.enforce_constraint(prev, e + s, lc!() + products[i])?;
+                prev = lc!() + products[i];
+            }
+            cs.enforce_constraint(prev, lc!() + Variable::One, lc!())?;
+        } else {
+            cs.enforce_constraint(top, lc!() + Variable::One, lc!() + price)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An auction among `n` bidders; in MPC, bid `i` is input by party `i % n_parties`.
+pub struct Auction<const SECOND_PRICE: bool>;
+
+pub type FirstPriceAuction = Auction<false>;
+pub type SecondPriceAuction = Auction<true>;
+
+fn plain_bits<F: PrimeField>(x: F) -> Vec<F> {
+    let bits = x.into_repr().to_bits_le();
+    bits[..BID_BITS].iter().map(|b| F::from(*b)).collect()
+}
+
+impl<const SECOND_PRICE: bool> BenchCircuit for Auction<SECOND_PRICE> {
+    type Circuit<F: PrimeField> = AuctionCircuit<F>;
+
+    fn without_data<F: PrimeField>(n: usize) -> AuctionCircuit<F> {
+        AuctionCircuit::without_data(n, SECOND_PRICE)
+    }
+
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (AuctionCircuit<F>, Vec<F>) {
+        assert!(n >= 2, "an auction needs at least two bidders");
+        let bids: Vec<u32> = (0..n).map(|_| rng.gen()).collect();
+        let winner = (0..n).max_by_key(|&i| (bids[i], i)).unwrap();
+        let price = if SECOND_PRICE {
+            (0..n)
+                .filter(|&i| i != winner)
+                .map(|i| bids[i])
+                .max()
+                .unwrap()
+        } else {
+            bids[winner]
+        };
+        let bids = bids.into_iter().map(|b| F::from(b as u64)).collect();
+        let price = F::from(price as u64);
+        let circ = AuctionCircuit::new(bids, winner, price, SECOND_PRICE, plain_bits);
+        (circ, public_inputs(winner, price))
+    }
+
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        _rng: &mut R,
+    ) -> (AuctionCircuit<MpcFr<E, S>>, Vec<E::Fr>) {
+        assert!(n >= 2, "an auction needs at least two bidders");
+        let rng = &mut rand::thread_rng();
+        let bids: Vec<MpcFr<E, S>> = (0..n)
+            .map(|i| {
+                let mine = i % MpcMultiNet::n_parties() == MpcMultiNet::party_id();
+                let bid = if mine { rng.gen::<u32>() as u64 } else { 0 };
+                MpcFr::<E, S>::from_add_shared(E::Fr::from(bid))
+            })
+            .collect();
+
+        // Scan for the highest and second-highest bids over shares; later bids win ties.
+        let one = MpcFr::<E, S>::from(1u8);
+        let mut top = bids[0];
+        let mut second = MpcFr::<E, S>::from(0u8);
+        let mut winner = MpcFr::<E, S>::from(0u8);
+        for (i, &b) in bids.iter().enumerate().skip(1) {
+            let above_top = b.is_ge(top, BID_BITS);
+            let above_second = b.is_ge(second, BID_BITS);
+            let loser_second = above_second * b + (one - above_second) * second;
+            second = above_top * top + (one - above_top) * loser_second;
+            top = top + above_top * (b - top);
+            winner = winner + above_top * (MpcFr::<E, S>::from(i as u64) - winner);
+        }
+        let winner = winner.reveal().into_repr().as_ref()[0] as usize;
+        let price = if SECOND_PRICE { second } else { top }.reveal();
+
+        let mut circ = AuctionCircuit::new(
+            bids,
+            winner,
+            MpcFr::<E, S>::from_public(price),
+            SECOND_PRICE,
+            |x| x.bit_decomposition(BID_BITS),
+        );
+        // The outcome is known to everyone, but the prover needs the whole assignment shared.
+        let share = |v: &mut Option<MpcFr<E, S>>| *v = v.map(MpcFr::<E, S>::into_shared);
+        share(&mut circ.winner);
+        share(&mut circ.price);
+        circ.selectors.iter_mut().for_each(share);
+        circ.bid_owners = (0..n)
+            .map(|i| Owner::Party(i % MpcMultiNet::n_parties()))
+            .collect();
+        (circ, public_inputs(winner, price))
+    }
+
+    fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+        let size = n * (3 * BID_BITS + 8) + 4;
+        (size, size, 3 * size)
+    }
+}
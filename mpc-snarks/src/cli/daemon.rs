@@ -0,0 +1,234 @@
+//! `proof ... daemon`: a long-lived prover per party, which keeps its connection to the other
+//! parties, and its keys, across proofs.
+//!
+//! Each party starts a daemon with the same options (the computation, proof system, curve and MPC
+//! settings), and a Unix socket of its own. Once connected, each takes requests on its socket, one
+//! JSON object per line:
+//!
+//! ```text
+//! {"op": "prove", "id": "job-7", "circuit": "squaring", "size": 1024, "envelope": "job-7.json"}
+//! {"op": "prove", "id": "job-8", "circuit": "squaring", "size": 1024, "inputs": "9f2c..."}
+//! {"op": "shutdown"}
+//! ```
+//!
+//! and answers each with a line of its own, once the parties have served it together:
+//!
+//! ```text
+//! {"id": "job-7", "ok": true, "seconds": 1.93}
+//! {"id": "job-9", "ok": false, "error": "this daemon proves squaring, not auction"}
+//! ```
+//!
+//! A service in front of the parties must send each the same requests, in the same order. The
+//! parties check, in one round, that they agree on each request (but not on where to write the
+//! envelope, which only party 0 does), and all refuse one that they do not agree on, or that is
+//! not for the daemon's computation (`-c`) at a size that fits. The computations here draw their
+//! own inputs ([BenchCircuit::mpc]), so a request names only the circuit, its size, and where the
+//! proof goes. Proving keys and Marlin indexes are kept in memory by size, so only the first
+//! request for a size sets them up, or reads them from `--key` or `--srs` (which must then be for
+//! that size).
+//!
+//! A request may also name the statement's inputs (e.g., by a digest of their commitments), for
+//! the service to ask for the same statement to be proved again: with `--witness-cache`, a later
+//! request that names the same inputs, for the same circuit and size, reuses the shared witness
+//! (see [crate::witness_cache]).
+//!
+//! A refused request leaves the daemon serving. A proof that fails aborts it, as it aborts
+//! `proof`: the parties could no longer be sure of being at the same point in the protocol.
+//! A daemon that received its request first waits for the others to receive theirs, so
+//! `--round-timeout` also bounds how far apart the parties may receive the same request.
+use super::{BenchCircuit, Opt, ENVELOPE, WITNESS_INPUTS};
+use ark_ec::PairingEngine;
+use log::warn;
+use mpc_net::{handshake, timer, MpcMultiNet};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Request {
+    /// Prove the daemon's computation at size `size`, writing the proof to `envelope` (at party
+    /// 0), if given.
+    Prove {
+        id: String,
+        circuit: String,
+        size: usize,
+        #[serde(default)]
+        envelope: Option<PathBuf>,
+        /// What the statement's inputs are named, if they are.
+        #[serde(default)]
+        inputs: Option<String>,
+    },
+    /// Stop serving, and disconnect from the other parties.
+    Shutdown,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seconds: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// What the parties must agree on to serve `request`; a line that is no request has parameters
+/// of its own, so that every line takes part in the same round, and no party waits on another.
+fn params(request: &Result<Request, String>) -> Vec<(&'static str, String)> {
+    match request {
+        Ok(Request::Prove {
+            id,
+            circuit,
+            size,
+            inputs,
+            ..
+        }) => vec![
+            ("op", "prove".to_owned()),
+            ("id", id.clone()),
+            ("circuit", circuit.to_lowercase()),
+            ("size", size.to_string()),
+            ("inputs", format!("{:?}", inputs)),
+        ],
+        Ok(Request::Shutdown) => vec![("op", "shutdown".to_owned())],
+        Err(_) => vec![("op", "malformed".to_owned())],
+    }
+}
+
+impl Opt {
+    /// Serve requests on `socket` until one asks to shut down.
+    pub(super) fn serve<E: PairingEngine, C: BenchCircuit>(&self, socket: &Path) {
+        let listener = UnixListener::bind(socket)
+            .unwrap_or_else(|e| panic!("Could not listen on {}: {}", socket.display(), e));
+        println!("Serving requests on {}", socket.display());
+        'serve: for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!("Could not accept a connection: {}", e);
+                    continue;
+                }
+            };
+            let mut writer = stream.try_clone().unwrap();
+            for line in BufReader::new(stream).lines() {
+                let line = match line {
+                    Ok(line) if line.trim().is_empty() => continue,
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                let request = serde_json::from_str(&line).map_err(|e| e.to_string());
+                let shutdown = matches!(request, Ok(Request::Shutdown));
+                let response = self.handle::<E, C>(request);
+                let line = serde_json::to_string(&response).unwrap();
+                if let Err(e) = writeln!(writer, "{}", line) {
+                    warn!("Could not answer a request: {}", e);
+                }
+                if shutdown && response.ok {
+                    break 'serve;
+                }
+            }
+        }
+        if let Err(e) = std::fs::remove_file(socket) {
+            warn!("Could not remove {}: {}", socket.display(), e);
+        }
+    }
+
+    fn handle<E: PairingEngine, C: BenchCircuit>(
+        &self,
+        request: Result<Request, String>,
+    ) -> Response {
+        let agreed = handshake::check_params::<MpcMultiNet>(&params(&request));
+        let id = match &request {
+            Ok(Request::Prove { id, .. }) => Some(id.clone()),
+            _ => None,
+        };
+        let refuse = |error: String| Response {
+            id: id.clone(),
+            error: Some(error),
+            ..Response::default()
+        };
+        let request = match (request, agreed) {
+            (Err(e), _) => return refuse(format!("malformed request: {}", e)),
+            (Ok(_), Err(e)) => return refuse(format!("parties disagree on the request: {}", e)),
+            (Ok(request), Ok(())) => request,
+        };
+        let (id, circuit, size, envelope, inputs) = match request {
+            Request::Shutdown => {
+                return Response {
+                    ok: true,
+                    ..Response::default()
+                }
+            }
+            Request::Prove {
+                id,
+                circuit,
+                size,
+                envelope,
+                inputs,
+            } => (id, circuit, size, envelope, inputs),
+        };
+        if !circuit.eq_ignore_ascii_case(&self.computation) {
+            return refuse(format!(
+                "this daemon proves {}, not {}",
+                self.computation, circuit
+            ));
+        }
+        if let Err(e) = self.check_limits::<E, C>(size) {
+            return refuse(format!("size {}: {}", size, e));
+        }
+        ENVELOPE.lock().unwrap().out = envelope;
+        *WITNESS_INPUTS.lock().unwrap() = inputs;
+        timer::reset();
+        let started = Instant::now();
+        self.prove::<E, C>(size);
+        let seconds = started.elapsed().as_secs_f64();
+        print!("Request {}:\n{}", id, timer::summary());
+        Response {
+            id: Some(id),
+            ok: true,
+            seconds: Some(seconds),
+            error: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_requests() {
+        let line = r#"{"op": "prove", "id": "7", "circuit": "Squaring", "size": 16}"#;
+        let request = serde_json::from_str(line).map_err(|e: serde_json::Error| e.to_string());
+        assert_eq!(
+            request,
+            Ok(Request::Prove {
+                id: "7".to_owned(),
+                circuit: "Squaring".to_owned(),
+                size: 16,
+                envelope: None,
+                inputs: None,
+            })
+        );
+        assert_eq!(params(&request)[2], ("circuit", "squaring".to_owned()));
+        let shutdown = serde_json::from_str::<Request>(r#"{"op": "shutdown"}"#).unwrap();
+        assert_eq!(shutdown, Request::Shutdown);
+
+        let malformed = serde_json::from_str::<Request>(r#"{"op": "prove", "id": "8"}"#);
+        let malformed = malformed.map_err(|e| e.to_string());
+        assert_eq!(params(&malformed), vec![("op", "malformed".to_owned())]);
+
+        let refused = Response {
+            id: Some("8".to_owned()),
+            error: Some("no".to_owned()),
+            ..Response::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&refused).unwrap(),
+            r#"{"id":"8","ok":false,"error":"no"}"#
+        );
+    }
+}
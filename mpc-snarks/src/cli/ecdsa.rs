@@ -0,0 +1,622 @@
+//! ECDSA: prove knowledge of a signature on a public message, under a public key.
+//!
+//! Signatures are over the twisted Edwards curve whose base field is BLS12-377's scalar field
+//! (`ark_ed_on_bls12_377`), so point coordinates are native. Only scalars (modulo the curve order
+//! `n`) are foreign, and the circuit does no arithmetic modulo `n`: rather than computing
+//! `R = (z / s) G + (r / s) Q`, it takes `R` from the prover and checks `s R = z G + r Q`, with
+//! scalars as bits, and that `r = R.x mod n`, as `R.x = r + k n` with `r < n` and `k < 4`.
+//!
+//! In MPC, the signer (the king) deals its signatures to the custodians, so that none of them
+//! learns one; they compute the rest of the witness, the scalar multiplications, jointly.
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve, TEModelParameters};
+use ark_ed_on_bls12_377::{EdwardsAffine, EdwardsParameters, Fq, Fr as Scalar};
+use ark_ff::{BigInteger, Field, FpParameters, One, PrimeField, UniformRand, Zero};
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    },
+};
+use ark_std::rand::Rng;
+use mpc_algebra::{PairingShare, Reveal};
+use mpc_trait::MpcWire;
+use sha2::{Digest, Sha256};
+use std::ops::{Add, Mul, Sub};
+
+use super::{BenchCircuit, MpcFr};
+use crate::inputs::PublicInputs;
+use crate::outputs::RevealOutputs;
+
+/// Scalars are less than `2^SCALAR_BITS`.
+const SCALAR_BITS: usize = 251;
+
+/// Bits of the quotient `k` in `R.x = r + k n`.
+const QUOTIENT_BITS: usize = 2;
+
+/// Constraints (and witness variables, but for the inputs) per signature.
+const CONSTRAINTS_PER_SIGNATURE: usize = {
+    let add = 7;
+    let var_mul = SCALAR_BITS * (2 + add) + (SCALAR_BITS - 1) * add;
+    let fixed_mul = SCALAR_BITS * add;
+    let on_curve = 4;
+    let booleanity = 4 * SCALAR_BITS + QUOTIENT_BITS;
+    2 * var_mul + fixed_mul + add + 2 * on_curve + 5 + booleanity
+};
+
+/// A signature and what it is checked against, with scalars as bits (least significant first).
+#[derive(Clone)]
+struct Signature<T> {
+    /// The public key.
+    qx: T,
+    qy: T,
+    /// The message digest, reduced modulo `n`.
+    z: T,
+    rx: T,
+    ry: T,
+    r: Vec<T>,
+    s: Vec<T>,
+    z_bits: Vec<T>,
+    /// Bits of `k`, where `R.x = r + k n`.
+    k: Vec<T>,
+    /// Bits of `n - 1 - r`.
+    gap: Vec<T>,
+}
+
+impl<T> Signature<T> {
+    fn map<U>(self, mut f: impl FnMut(T) -> U) -> Signature<U> {
+        let mut bits = |v: Vec<T>| v.into_iter().map(&mut f).collect::<Vec<U>>();
+        let (r, s, z_bits, k, gap) = (
+            bits(self.r),
+            bits(self.s),
+            bits(self.z_bits),
+            bits(self.k),
+            bits(self.gap),
+        );
+        Signature {
+            qx: f(self.qx),
+            qy: f(self.qy),
+            z: f(self.z),
+            rx: f(self.rx),
+            ry: f(self.ry),
+            r,
+            s,
+            z_bits,
+            k,
+            gap,
+        }
+    }
+
+    fn to_vec(self) -> Vec<T> {
+        let mut v = vec![self.qx, self.qy, self.z, self.rx, self.ry];
+        for bits in vec![self.r, self.s, self.z_bits, self.k, self.gap] {
+            v.extend(bits);
+        }
+        v
+    }
+
+    fn from_vec(v: Vec<T>) -> Self {
+        let mut v = v.into_iter();
+        let mut next = |n: usize| (&mut v).take(n).collect::<Vec<T>>();
+        let mut fixed = next(5).into_iter();
+        let mut one = || fixed.next().unwrap();
+        let (qx, qy, z, rx, ry) = (one(), one(), one(), one(), one());
+        Signature {
+            qx,
+            qy,
+            z,
+            rx,
+            ry,
+            r: next(SCALAR_BITS),
+            s: next(SCALAR_BITS),
+            z_bits: next(SCALAR_BITS),
+            k: next(QUOTIENT_BITS),
+            gap: next(SCALAR_BITS),
+        }
+    }
+
+    fn without_data() -> Self
+    where
+        T: Clone + Default,
+    {
+        Self::from_vec(vec![T::default(); 5 + 4 * SCALAR_BITS + QUOTIENT_BITS])
+    }
+}
+
+fn bits(x: impl BigInteger, n: usize) -> Vec<bool> {
+    x.to_bits_le()[..n].to_vec()
+}
+
+/// `x` (an integer less than `F`'s characteristic) in `F`.
+fn embed<F: Field>(x: impl BigInteger) -> F {
+    let limb = F::from(1u128 << 64);
+    x.as_ref()
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, l| acc * limb + F::from(*l))
+}
+
+/// A signature by a fresh key on a random message.
+fn sign<R: Rng>(rng: &mut R) -> Signature<Fq> {
+    let g = EdwardsAffine::prime_subgroup_generator();
+    let d = Scalar::rand(rng);
+    let q = g.mul(d).into_affine();
+    let message: [u8; 32] = rng.gen();
+    let z = Scalar::from_le_bytes_mod_order(&Sha256::digest(&message));
+    let (big_r, r, s) = loop {
+        let k = Scalar::rand(rng);
+        let big_r = g.mul(k).into_affine();
+        let r = Scalar::from_le_bytes_mod_order(&big_r.x.into_repr().to_bytes_le());
+        let s = k.inverse().unwrap() * (z + r * d);
+        if !r.is_zero() && !s.is_zero() {
+            break (big_r, r, s);
+        }
+    };
+    let n: Fq = embed(<Scalar as PrimeField>::Params::MODULUS);
+    let r_fq: Fq = embed(r.into_repr());
+    let k = (big_r.x - r_fq) * n.inverse().unwrap();
+    let gap = n - Fq::one() - r_fq;
+    let to_fq = |v: Vec<bool>| v.into_iter().map(Fq::from).collect();
+    Signature {
+        qx: q.x,
+        qy: q.y,
+        z: embed(z.into_repr()),
+        rx: big_r.x,
+        ry: big_r.y,
+        r: to_fq(bits(r.into_repr(), SCALAR_BITS)),
+        s: to_fq(bits(s.into_repr(), SCALAR_BITS)),
+        z_bits: to_fq(bits(z.into_repr(), SCALAR_BITS)),
+        k: to_fq(bits(k.into_repr(), QUOTIENT_BITS)),
+        gap: to_fq(bits(gap.into_repr(), SCALAR_BITS)),
+    }
+}
+
+/// The curve's constants, in the circuit's field.
+struct Curve<F> {
+    a: F,
+    d: F,
+    /// `n`, the order of the generator.
+    n: F,
+    /// `2^i G`.
+    generator_powers: Vec<(F, F)>,
+}
+
+impl<F: Field> Curve<F> {
+    fn new() -> Self {
+        let mut p = EdwardsAffine::prime_subgroup_generator().into_projective();
+        let mut generator_powers = Vec::with_capacity(SCALAR_BITS);
+        for _ in 0..SCALAR_BITS {
+            let a = p.into_affine();
+            generator_powers.push((embed(a.x.into_repr()), embed(a.y.into_repr())));
+            p.double_in_place();
+        }
+        Self {
+            a: embed(EdwardsParameters::COEFF_A.into_repr()),
+            d: embed(EdwardsParameters::COEFF_D.into_repr()),
+            n: embed(<Scalar as PrimeField>::Params::MODULUS),
+            generator_powers,
+        }
+    }
+}
+
+/// Field arithmetic, over some kind of wire.
+trait Arith<F: Field> {
+    type Wire: Clone
+        + Add<Output = Self::Wire>
+        + Sub<Output = Self::Wire>
+        + Mul<F, Output = Self::Wire>;
+
+    fn constant(&self, c: F) -> Self::Wire;
+
+    /// Independent products, all at once.
+    fn products(
+        &mut self,
+        pairs: Vec<(Self::Wire, Self::Wire)>,
+    ) -> Result<Vec<Self::Wire>, SynthesisError>;
+
+    /// Independent quotients, all at once; denominators must be non-zero.
+    fn quotients(
+        &mut self,
+        pairs: Vec<(Self::Wire, Self::Wire)>,
+    ) -> Result<Vec<Self::Wire>, SynthesisError>;
+
+    fn enforce_equal(&mut self, a: Self::Wire, b: Self::Wire) -> Result<(), SynthesisError>;
+}
+
+/// Computes on values, recording every product and quotient.
+struct Eval<F> {
+    trace: Vec<F>,
+}
+
+impl<F: Field> Eval<F> {
+    /// Apply `op` to each pair, batching (so, one round for) the pairs of two shares in `batch`.
+    fn apply(
+        &mut self,
+        pairs: Vec<(F, F)>,
+        batch: fn(&mut [F], &[F]),
+        op: fn(F, F) -> F,
+    ) -> Vec<F> {
+        let both_shared = |(a, b): &(F, F)| a.is_shared() && b.is_shared();
+        let (mut xs, ys): (Vec<F>, Vec<F>) =
+            pairs.iter().filter(|p| both_shared(p)).cloned().unzip();
+        if !xs.is_empty() {
+            batch(&mut xs, &ys);
+        }
+        let mut xs = xs.into_iter();
+        let out: Vec<F> = pairs
+            .iter()
+            .map(|p| {
+                if both_shared(p) {
+                    xs.next().unwrap()
+                } else {
+                    op(p.0, p.1)
+                }
+            })
+            .collect();
+        self.trace.extend_from_slice(&out);
+        out
+    }
+}
+
+impl<F: Field> Arith<F> for Eval<F> {
+    type Wire = F;
+
+    fn constant(&self, c: F) -> F {
+        c
+    }
+
+    fn products(&mut self, pairs: Vec<(F, F)>) -> Result<Vec<F>, SynthesisError> {
+        Ok(self.apply(pairs, F::batch_product_in_place, |a, b| a * b))
+    }
+
+    fn quotients(&mut self, pairs: Vec<(F, F)>) -> Result<Vec<F>, SynthesisError> {
+        Ok(self.apply(pairs, F::batch_division_in_place, |a, b| a / b))
+    }
+
+    fn enforce_equal(&mut self, _a: F, _b: F) -> Result<(), SynthesisError> {
+        Ok(())
+    }
+}
+
+/// Allocates every product and quotient, assigned from an [Eval] trace if there is one.
+struct Synth<F: Field> {
+    cs: ConstraintSystemRef<F>,
+    trace: Option<std::vec::IntoIter<F>>,
+}
+
+impl<F: Field> Synth<F> {
+    fn next(&mut self) -> Result<Variable, SynthesisError> {
+        let value = self.trace.as_mut().and_then(|t| t.next());
+        self.cs
+            .new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing))
+    }
+}
+
+impl<F: Field> Arith<F> for Synth<F> {
+    type Wire = LinearCombination<F>;
+
+    fn constant(&self, c: F) -> LinearCombination<F> {
+        lc!() + (c, Variable::One)
+    }
+
+    fn products(
+        &mut self,
+        pairs: Vec<(LinearCombination<F>, LinearCombination<F>)>,
+    ) -> Result<Vec<LinearCombination<F>>, SynthesisError> {
+        let mut out = Vec::with_capacity(pairs.len());
+        for (a, b) in pairs {
+            let w = self.next()?;
+            self.cs.enforce_constraint(a, b, lc!() + w)?;
+            out.push(lc!() + w);
+        }
+        Ok(out)
+    }
+
+    fn quotients(
+        &mut self,
+        pairs: Vec<(LinearCombination<F>, LinearCombination<F>)>,
+    ) -> Result<Vec<LinearCombination<F>>, SynthesisError> {
+        let mut out = Vec::with_capacity(pairs.len());
+        for (a, b) in pairs {
+            let w = self.next()?;
+            self.cs.enforce_constraint(lc!() + w, b, a)?;
+            out.push(lc!() + w);
+        }
+        Ok(out)
+    }
+
+    fn enforce_equal(
+        &mut self,
+        a: LinearCombination<F>,
+        b: LinearCombination<F>,
+    ) -> Result<(), SynthesisError> {
+        self.cs.enforce_constraint(a, lc!() + Variable::One, b)
+    }
+}
+
+type Point<W> = (W, W);
+
+/// `sum_i 2^i bits_i`.
+fn pack<F: Field, A: Arith<F>>(g: &A, bits: &[A::Wire]) -> A::Wire {
+    let mut coeff = F::one();
+    let mut sum = g.constant(F::zero());
+    for b in bits {
+        sum = sum + b.clone() * coeff;
+        coeff.double_in_place();
+    }
+    sum
+}
+
+/// The complete twisted Edwards addition law: seven products and quotients.
+fn add<F: Field, A: Arith<F>>(
+    g: &mut A,
+    curve: &Curve<F>,
+    (x1, y1): &Point<A::Wire>,
+    (x2, y2): &Point<A::Wire>,
+) -> Result<Point<A::Wire>, SynthesisError> {
+    let p = g.products(vec![
+        (x1.clone(), y2.clone()),
+        (y1.clone(), x2.clone()),
+        (x1.clone(), x2.clone()),
+        (y1.clone(), y2.clone()),
+    ])?;
+    // x1 x2 y1 y2
+    let t = g.products(vec![(p[0].clone(), p[1].clone())])?.remove(0) * curve.d;
+    let one = g.constant(F::one());
+    let q = g.quotients(vec![
+        (p[0].clone() + p[1].clone(), one.clone() + t.clone()),
+        (p[3].clone() - p[2].clone() * curve.a, one - t),
+    ])?;
+    Ok((q[0].clone(), q[1].clone()))
+}
+
+/// `a x^2 + y^2 = 1 + d x^2 y^2`.
+fn enforce_on_curve<F: Field, A: Arith<F>>(
+    g: &mut A,
+    curve: &Curve<F>,
+    (x, y): &Point<A::Wire>,
+) -> Result<(), SynthesisError> {
+    let sq = g.products(vec![(x.clone(), x.clone()), (y.clone(), y.clone())])?;
+    let t = g.products(vec![(sq[0].clone(), sq[1].clone())])?.remove(0);
+    let one = g.constant(F::one());
+    g.enforce_equal(sq[0].clone() * curve.a + sq[1].clone(), one + t * curve.d)
+}
+
+/// `sum_i bits_i 2^i p`, by double-and-add.
+fn scalar_mul<F: Field, A: Arith<F>>(
+    g: &mut A,
+    curve: &Curve<F>,
+    bits: &[A::Wire],
+    p: &Point<A::Wire>,
+) -> Result<Point<A::Wire>, SynthesisError> {
+    let (zero, one) = (g.constant(F::zero()), g.constant(F::one()));
+    let mut acc = (zero, one.clone());
+    let mut power = p.clone();
+    for (i, b) in bits.iter().enumerate() {
+        // The identity is (0, 1).
+        let s = g.products(vec![
+            (b.clone(), power.0.clone()),
+            (b.clone(), power.1.clone() - one.clone()),
+        ])?;
+        acc = add(g, curve, &acc, &(s[0].clone(), s[1].clone() + one.clone()))?;
+        if i + 1 < bits.len() {
+            power = add(g, curve, &power, &power)?;
+        }
+    }
+    Ok(acc)
+}
+
+/// `sum_i bits_i 2^i G`; selecting a constant point is linear.
+fn mul_generator<F: Field, A: Arith<F>>(
+    g: &mut A,
+    curve: &Curve<F>,
+    bits: &[A::Wire],
+) -> Result<Point<A::Wire>, SynthesisError> {
+    let (zero, one) = (g.constant(F::zero()), g.constant(F::one()));
+    let mut acc = (zero, one.clone());
+    for (b, (x, y)) in bits.iter().zip(&curve.generator_powers) {
+        let selected = (b.clone() * *x, one.clone() + b.clone() * (*y - F::one()));
+        acc = add(g, curve, &acc, &selected)?;
+    }
+    Ok(acc)
+}
+
+/// Check a signature whose bits are known to be bits.
+fn verify<F: Field, A: Arith<F>>(
+    g: &mut A,
+    curve: &Curve<F>,
+    sig: &Signature<A::Wire>,
+) -> Result<(), SynthesisError> {
+    let r = pack(g, &sig.r);
+    g.enforce_equal(pack(g, &sig.z_bits), sig.z.clone())?;
+    g.enforce_equal(r.clone() + pack(g, &sig.k) * curve.n, sig.rx.clone())?;
+    let n_minus_one = g.constant(curve.n - F::one());
+    g.enforce_equal(pack(g, &sig.gap), n_minus_one - r)?;
+
+    let q = (sig.qx.clone(), sig.qy.clone());
+    let big_r = (sig.rx.clone(), sig.ry.clone());
+    enforce_on_curve(g, curve, &q)?;
+    enforce_on_curve(g, curve, &big_r)?;
+    let lhs = scalar_mul(g, curve, &sig.s, &big_r)?;
+    let zg = mul_generator(g, curve, &sig.z_bits)?;
+    let rq = scalar_mul(g, curve, &sig.r, &q)?;
+    let rhs = add(g, curve, &zg, &rq)?;
+    g.enforce_equal(lhs.0, rhs.0)?;
+    g.enforce_equal(lhs.1, rhs.1)
+}
+
+/// Public inputs are, per signature, the public key and the message digest.
+#[derive(Clone)]
+pub struct EcdsaCircuit<F: Field> {
+    signatures: Vec<Signature<Option<F>>>,
+    /// Every product and quotient, in order, from [Eval].
+    trace: Option<Vec<F>>,
+}
+
+impl<F: Field> EcdsaCircuit<F> {
+    fn new(signatures: Vec<Signature<F>>) -> Self {
+        let curve = Curve::new();
+        let mut eval = Eval { trace: Vec::new() };
+        for sig in &signatures {
+            verify(&mut eval, &curve, sig).unwrap();
+        }
+        Self {
+            signatures: signatures.into_iter().map(|s| s.map(Some)).collect(),
+            trace: Some(eval.trace),
+        }
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for EcdsaCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let curve = Curve::new();
+        let mut synth = Synth {
+            cs: cs.clone(),
+            trace: self.trace.map(Vec::into_iter),
+        };
+        for sig in self.signatures {
+            let var = |v: Option<ConstraintF>, input: bool| {
+                let f = || v.ok_or(SynthesisError::AssignmentMissing);
+                if input {
+                    cs.new_input_variable(f)
+                } else {
+                    cs.new_witness_variable(f)
+                }
+            };
+            let (qx, qy, z) = (var(sig.qx, true)?, var(sig.qy, true)?, var(sig.z, true)?);
+            let (rx, ry) = (var(sig.rx, false)?, var(sig.ry, false)?);
+            let bits = |v: &[Option<ConstraintF>]| -> Result<Vec<_>, SynthesisError> {
+                v.iter()
+                    .map(|b| {
+                        let b = var(*b, false)?;
+                        cs.enforce_constraint(lc!() + b, lc!() + b, lc!() + b)?;
+                        Ok(lc!() + b)
+                    })
+                    .collect()
+            };
+            let (r, s, z_bits, k, gap) = (
+                bits(&sig.r)?,
+                bits(&sig.s)?,
+                bits(&sig.z_bits)?,
+                bits(&sig.k)?,
+                bits(&sig.gap)?,
+            );
+            let sig = Signature {
+                qx: lc!() + qx,
+                qy: lc!() + qy,
+                z: lc!() + z,
+                rx: lc!() + rx,
+                ry: lc!() + ry,
+                r,
+                s,
+                z_bits,
+                k,
+                gap,
+            };
+            verify(&mut synth, &curve, &sig)?;
+        }
+        Ok(())
+    }
+}
+
+/// Knowledge of `n` signatures, each by its own key.
+pub struct EcdsaSignature;
+
+fn public_inputs<F: Field>(signatures: &[Signature<F>]) -> Vec<F> {
+    signatures
+        .iter()
+        .enumerate()
+        .fold(PublicInputs::new(), |p, (i, s)| {
+            p.field(&format!("public key {} x", i), s.qx)
+                .field(&format!("public key {} y", i), s.qy)
+                .field(&format!("digest {}", i), s.z)
+        })
+        .to_vec()
+}
+
+fn check_field<F: PrimeField>() {
+    assert_eq!(
+        F::characteristic(),
+        Fq::characteristic(),
+        "ECDSA signatures are over ed_on_bls12_377, so proofs must be over bls12_377"
+    );
+}
+
+impl BenchCircuit for EcdsaSignature {
+    type Circuit<F: PrimeField> = EcdsaCircuit<F>;
+
+    fn without_data<F: PrimeField>(n: usize) -> EcdsaCircuit<F> {
+        EcdsaCircuit {
+            signatures: vec![Signature::without_data(); n],
+            trace: None,
+        }
+    }
+
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (EcdsaCircuit<F>, Vec<F>) {
+        check_field::<F>();
+        let signatures: Vec<Signature<F>> = (0..n)
+            .map(|_| sign(rng).map(|x| embed(x.into_repr())))
+            .collect();
+        let inputs = public_inputs(&signatures);
+        (EcdsaCircuit::new(signatures), inputs)
+    }
+
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        _rng: &mut R,
+    ) -> (EcdsaCircuit<MpcFr<E, S>>, Vec<E::Fr>) {
+        check_field::<E::Fr>();
+        let rng = &mut rand::thread_rng();
+        // Only the king's signatures count.
+        let values: Vec<E::Fr> = (0..n)
+            .flat_map(|_| sign(rng).to_vec())
+            .map(|x| embed(x.into_repr()))
+            .collect();
+        let shares = MpcFr::<E, S>::king_share_batch(values, rng);
+        let per_signature = shares.len() / n.max(1);
+        let signatures: Vec<Signature<MpcFr<E, S>>> = shares
+            .chunks(per_signature)
+            .map(|c| Signature::from_vec(c.to_vec()))
+            .collect();
+        let mut circ = EcdsaCircuit::new(signatures);
+        // Products with public constants (e.g., the curve's) may be public, but the prover needs
+        // the whole assignment shared.
+        let trace = circ.trace.as_mut().unwrap();
+        trace.iter_mut().for_each(|v| *v = v.into_shared());
+        let inputs = RevealOutputs::Instance.reveal(circ.clone()).unwrap();
+        (circ, inputs)
+    }
+
+    fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+        let size = n * CONSTRAINTS_PER_SIGNATURE + 8;
+        (size, size, 4 * size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    fn satisfied(circ: EcdsaCircuit<Fr>) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        circ.generate_constraints(cs.clone()).unwrap();
+        assert_eq!(cs.num_constraints(), CONSTRAINTS_PER_SIGNATURE);
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn signatures_verify() {
+        let rng = &mut test_rng();
+        let sig = sign(rng).map(|x| embed::<Fr>(x.into_repr()));
+        assert!(satisfied(EcdsaCircuit::new(vec![sig.clone()])));
+
+        let mut forged = sig;
+        forged.s[0] = Fr::one() - forged.s[0];
+        assert!(!satisfied(EcdsaCircuit::new(vec![forged])));
+    }
+}
@@ -0,0 +1,208 @@
+//! Prove that a jointly-held value lies in `[0, 2^k)`.
+//!
+//! The value is the sum of contributions that their contributors committed to ahead of time (with
+//! [Poseidon commitments](crate::poseidon)), so the proof is about the committed value: the
+//! commitments are the public inputs.
+use ark_ec::PairingEngine;
+use ark_ff::{BigInteger, Field, PrimeField, UniformRand};
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+use ark_std::rand::Rng;
+use mpc_algebra::{PairingShare, Reveal};
+use mpc_net::{MpcMultiNet, MpcNet};
+
+use super::{BenchCircuit, MpcFr};
+use crate::audit::{self, Owner};
+use crate::inputs::PublicInputs;
+use crate::poseidon::{self, Opening};
+
+/// Contributions `x_j`, with an opening of each one's commitment, and bits `b_0..b_{k-1}` with
+/// each `b_i` boolean and `sum_j x_j = sum_i 2^i b_i`.
+#[derive(Clone)]
+pub struct RangeCircuit<F: Field> {
+    commitments: Vec<Option<F>>,
+    contributions: Vec<Option<Opening<F>>>,
+    /// Who made each contribution, for [crate::audit]; empty if no one is to be named.
+    contributors: Vec<Owner>,
+    bits: Vec<Option<F>>,
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for RangeCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let mut x = lc!();
+        for (j, (c, opening)) in self.commitments.iter().zip(&self.contributions).enumerate() {
+            let c = cs.new_input_variable(|| c.ok_or_else(missing))?;
+            let v =
+                cs.new_witness_variable(|| opening.as_ref().map(|o| o.value).ok_or_else(missing))?;
+            let owner = self.contributors.get(j).cloned();
+            if let Some(owner) = owner {
+                audit::tag(v, owner);
+            }
+            poseidon::enforce_opening(&cs, c, v, opening.as_ref(), owner)?;
+            x = x + v;
+        }
+        let bits: Vec<Variable> = self
+            .bits
+            .iter()
+            .map(|b| cs.new_witness_variable(|| b.ok_or_else(missing)))
+            .collect::<Result<_, _>>()?;
+
+        let mut sum = lc!();
+        let mut coeff = ConstraintF::one();
+        for &b in &bits {
+            cs.enforce_constraint(lc!() + b, lc!() + b, lc!() + b)?;
+            sum = sum + (coeff, b);
+            coeff.double_in_place();
+        }
+        cs.enforce_constraint(x, lc!() + Variable::One, sum)?;
+
+        Ok(())
+    }
+}
+
+fn public_inputs<F: Field>(commitments: &[F]) -> Vec<F> {
+    commitments
+        .iter()
+        .enumerate()
+        .fold(PublicInputs::new(), |p, (j, c)| {
+            p.field(&format!("commitment {}", j), *c)
+        })
+        .to_vec()
+}
+
+/// `0 <= x < 2^n` for the sum `x` of `CONTRIBUTIONS` committed contributions. In MPC,
+/// contribution `j` is by party `j % n_parties`.
+///
+/// `rangeproof` takes two contributions; an embedding binary can register a `RangeProof<N>` for
+/// `N` contributors.
+pub struct RangeProof<const CONTRIBUTIONS: usize>;
+
+impl<const CONTRIBUTIONS: usize> RangeProof<CONTRIBUTIONS> {
+    /// A contribution small enough that the sum of all of them stays below `2^n`, with a
+    /// commitment to it.
+    fn contribute<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (F, Opening<F>) {
+        let bits = n
+            .checked_sub(ceil_log2(CONTRIBUTIONS))
+            .expect("range too small for this many contributions");
+        let bits: Vec<bool> = (0..bits).map(|_| rng.gen()).collect();
+        let value = F::from_repr(F::BigInt::from_bits_le(&bits)).unwrap();
+        poseidon::commit(value, F::rand(rng))
+    }
+}
+
+impl<const CONTRIBUTIONS: usize> BenchCircuit for RangeProof<CONTRIBUTIONS> {
+    type Circuit<F: PrimeField> = RangeCircuit<F>;
+
+    fn without_data<F: PrimeField>(n: usize) -> RangeCircuit<F> {
+        RangeCircuit {
+            commitments: vec![None; CONTRIBUTIONS],
+            contributions: vec![None; CONTRIBUTIONS],
+            contributors: Vec::new(),
+            bits: vec![None; n],
+        }
+    }
+
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (RangeCircuit<F>, Vec<F>) {
+        let (commitments, openings): (Vec<F>, Vec<Opening<F>>) =
+            (0..CONTRIBUTIONS).map(|_| Self::contribute(n, rng)).unzip();
+        let value: F = openings.iter().map(|o| o.value).sum();
+        let bits = value.into_repr().to_bits_le();
+        let circ = RangeCircuit {
+            commitments: commitments.iter().cloned().map(Some).collect(),
+            contributions: openings.into_iter().map(Some).collect(),
+            contributors: Vec::new(),
+            bits: bits[..n].iter().map(|b| Some(F::from(*b))).collect(),
+        };
+        (circ, public_inputs(&commitments))
+    }
+
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        _rng: &mut R,
+    ) -> (RangeCircuit<MpcFr<E, S>>, Vec<E::Fr>) {
+        let parties = MpcMultiNet::n_parties();
+        let rng = &mut rand::thread_rng();
+        let mut commitments = Vec::new();
+        let mut contributions = Vec::new();
+        for j in 0..CONTRIBUTIONS {
+            // Everyone draws, to have an opening of the right shape; only the contributor's
+            // counts, and the others input zeros.
+            let ours = j % parties == MpcMultiNet::party_id();
+            let (c, opening) = Self::contribute::<E::Fr, _>(n, rng);
+            let share =
+                |x: E::Fr| MpcFr::<E, S>::from_add_shared(if ours { x } else { 0u8.into() });
+            // The contributor publishes its commitment.
+            commitments.push(share(c).reveal());
+            contributions.push(opening.map(share));
+        }
+        let value: MpcFr<E, S> = contributions.iter().map(|o| o.value).sum();
+        let circ = RangeCircuit {
+            commitments: commitments
+                .iter()
+                .map(|c| Some(MpcFr::<E, S>::from_public(*c)))
+                .collect(),
+            contributions: contributions.into_iter().map(Some).collect(),
+            contributors: (0..CONTRIBUTIONS)
+                .map(|j| Owner::Party(j % parties))
+                .collect(),
+            bits: value.bit_decomposition(n).into_iter().map(Some).collect(),
+        };
+        (circ, public_inputs(&commitments))
+    }
+
+    fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+        let (constraints, variables, non_zero) = poseidon::opening_bounds();
+        let k = CONTRIBUTIONS;
+        (
+            n + 1 + k * constraints,
+            n + 1 + k * (variables + 2),
+            3 * (n + k + 2) + k * non_zero,
+        )
+    }
+}
+
+fn ceil_log2(n: usize) -> usize {
+    (usize::BITS - (n - 1).leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    fn satisfied(circ: RangeCircuit<Fr>) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        circ.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn proves_the_committed_value_in_range() {
+        let rng = &mut test_rng();
+        let (circ, public) = RangeProof::<2>::local::<Fr, _>(8, rng);
+        assert_eq!(public.len(), 2);
+        assert!(satisfied(circ.clone()));
+
+        // A prover that swaps in a value of its own (in range, with the bits to match) proves
+        // its own commitment's statement, but not the published one's.
+        let (own, opening) = poseidon::commit(Fr::from(0u8), Fr::from(1u8));
+        let value = circ.contributions[1].as_ref().unwrap().value;
+        let mut forged = circ;
+        forged.contributions[0] = Some(opening);
+        forged.bits = value.into_repr().to_bits_le()[..8]
+            .iter()
+            .map(|b| Some(Fr::from(*b)))
+            .collect();
+        assert!(!satisfied(forged.clone()));
+        forged.commitments[0] = Some(own);
+        assert!(satisfied(forged));
+    }
+}
@@ -0,0 +1,522 @@
+//! SHA-256 preimages: prove knowledge of a message with a published digest.
+//!
+//! SHA-256 is all bit operations, so the circuit works over bits (least significant first within
+//! each word). Every `and` or `xor` of two bits is one multiplication: a constraint, and in MPC a
+//! product of shares. Rotations, shifts, constants and the padding are free rewiring. The same
+//! gadget code drives witness generation ([Eval], which multiplies each layer of independent gates
+//! in one batch, so one round) and synthesis ([Synth], which replays the gates' outputs as
+//! witnesses).
+use ark_ec::PairingEngine;
+use ark_ff::{Field, PrimeField};
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    },
+};
+use ark_std::rand::Rng;
+use mpc_algebra::{PairingShare, Reveal};
+use mpc_net::{MpcMultiNet, MpcNet};
+use mpc_trait::MpcWire;
+use std::ops::{Add, Sub};
+
+use super::{BenchCircuit, MpcFr};
+use crate::inputs::PublicInputs;
+use crate::outputs::RevealOutputs;
+
+/// The message length, in bytes, for `blocks` blocks: all of them but the padding (a `0x80` byte
+/// and the 64-bit length).
+pub fn message_len(blocks: usize) -> usize {
+    assert!(blocks >= 1, "a message takes at least one block");
+    64 * blocks - 9
+}
+
+const H0: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+#[derive(Clone, Copy)]
+enum Gate {
+    And,
+    Xor,
+}
+
+/// Two-input bit gates, over some kind of wire.
+trait Gates<F: Field> {
+    type Wire: Clone + Add<Output = Self::Wire> + Sub<Output = Self::Wire>;
+
+    fn constant(&self, bit: bool) -> Self::Wire;
+
+    /// Apply independent gates, all at once.
+    fn gates(
+        &mut self,
+        gates: Vec<(Gate, Self::Wire, Self::Wire)>,
+    ) -> Result<Vec<Self::Wire>, SynthesisError>;
+}
+
+/// Computes gates on values, recording every output.
+struct Eval<F> {
+    trace: Vec<F>,
+}
+
+impl<F: Field> Gates<F> for Eval<F> {
+    type Wire = F;
+
+    fn constant(&self, bit: bool) -> F {
+        F::from(bit)
+    }
+
+    fn gates(&mut self, gates: Vec<(Gate, F, F)>) -> Result<Vec<F>, SynthesisError> {
+        // Products of two shares take a round, in one batch; the others are local.
+        let both_shared = |(_, a, b): &(Gate, F, F)| a.is_shared() && b.is_shared();
+        let mut xs: Vec<F> = gates
+            .iter()
+            .filter(|g| both_shared(g))
+            .map(|g| g.1)
+            .collect();
+        let ys: Vec<F> = gates
+            .iter()
+            .filter(|g| both_shared(g))
+            .map(|g| g.2)
+            .collect();
+        if !xs.is_empty() {
+            F::batch_product_in_place(&mut xs, &ys);
+        }
+        let mut xs = xs.into_iter();
+        let products: Vec<F> = gates
+            .iter()
+            .map(|g| {
+                if both_shared(g) {
+                    xs.next().unwrap()
+                } else {
+                    g.1 * g.2
+                }
+            })
+            .collect();
+        let out: Vec<F> = gates
+            .iter()
+            .zip(products)
+            .map(|((gate, a, b), ab)| match gate {
+                Gate::And => ab,
+                Gate::Xor => *a + b - ab.double(),
+            })
+            .collect();
+        self.trace.extend_from_slice(&out);
+        Ok(out)
+    }
+}
+
+/// Allocates every gate's output, assigned from an [Eval] trace if there is one.
+struct Synth<F: Field> {
+    cs: ConstraintSystemRef<F>,
+    trace: Option<std::vec::IntoIter<F>>,
+}
+
+impl<F: Field> Gates<F> for Synth<F> {
+    type Wire = LinearCombination<F>;
+
+    fn constant(&self, bit: bool) -> LinearCombination<F> {
+        if bit {
+            lc!() + Variable::One
+        } else {
+            lc!()
+        }
+    }
+
+    fn gates(
+        &mut self,
+        gates: Vec<(Gate, LinearCombination<F>, LinearCombination<F>)>,
+    ) -> Result<Vec<LinearCombination<F>>, SynthesisError> {
+        let mut out = Vec::with_capacity(gates.len());
+        for (gate, a, b) in gates {
+            let value = self.trace.as_mut().and_then(|t| t.next());
+            let w = self
+                .cs
+                .new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing))?;
+            match gate {
+                Gate::And => self.cs.enforce_constraint(a, b, lc!() + w)?,
+                // a xor b = a + b - 2ab
+                Gate::Xor => {
+                    let two_a = a.clone() * F::from(2u8);
+                    self.cs.enforce_constraint(two_a, b.clone(), a + b - w)?
+                }
+            }
+            out.push(lc!() + w);
+        }
+        Ok(out)
+    }
+}
+
+type Word<W> = Vec<W>;
+
+fn constant_word<F: Field, G: Gates<F>>(g: &G, x: u32) -> Word<G::Wire> {
+    (0..32).map(|i| g.constant((x >> i) & 1 == 1)).collect()
+}
+
+fn rotr<W: Clone>(x: &[W], n: usize) -> Word<W> {
+    (0..32).map(|i| x[(i + n) % 32].clone()).collect()
+}
+
+fn shr<W: Clone>(x: &[W], n: usize, zero: W) -> Word<W> {
+    (0..32)
+        .map(|i| x.get(i + n).cloned().unwrap_or_else(|| zero.clone()))
+        .collect()
+}
+
+fn xor<F: Field, G: Gates<F>>(
+    g: &mut G,
+    a: &[G::Wire],
+    b: &[G::Wire],
+) -> Result<Word<G::Wire>, SynthesisError> {
+    g.gates(
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| (Gate::Xor, a.clone(), b.clone()))
+            .collect(),
+    )
+}
+
+/// `rotr(x, r0) ^ rotr(x, r1) ^ rotr(x, last)`, or `shr(x, last)` in the last term if `shift`.
+fn sigma<F: Field, G: Gates<F>>(
+    g: &mut G,
+    x: &[G::Wire],
+    (r0, r1, last): (usize, usize, usize),
+    shift: bool,
+) -> Result<Word<G::Wire>, SynthesisError> {
+    let t = xor(g, &rotr(x, r0), &rotr(x, r1))?;
+    let last = if shift {
+        shr(x, last, g.constant(false))
+    } else {
+        rotr(x, last)
+    };
+    xor(g, &t, &last)
+}
+
+/// `e ? f : g`, as `g + e (f - g)`.
+fn choose<F: Field, G: Gates<F>>(
+    g: &mut G,
+    e: &[G::Wire],
+    f: &[G::Wire],
+    h: &[G::Wire],
+) -> Result<Word<G::Wire>, SynthesisError> {
+    let gates = (0..32)
+        .map(|i| (Gate::And, e[i].clone(), f[i].clone() - h[i].clone()))
+        .collect();
+    let selected = g.gates(gates)?;
+    Ok(selected
+        .into_iter()
+        .zip(h)
+        .map(|(s, h)| s + h.clone())
+        .collect())
+}
+
+/// The majority of `a`, `b` and `c`, as `ab + c (a ^ b)`.
+fn majority<F: Field, G: Gates<F>>(
+    g: &mut G,
+    a: &[G::Wire],
+    b: &[G::Wire],
+    c: &[G::Wire],
+) -> Result<Word<G::Wire>, SynthesisError> {
+    let layer = (0..32)
+        .flat_map(|i| {
+            vec![
+                (Gate::And, a[i].clone(), b[i].clone()),
+                (Gate::Xor, a[i].clone(), b[i].clone()),
+            ]
+        })
+        .collect();
+    let half = g.gates(layer)?;
+    let gates = (0..32)
+        .map(|i| (Gate::And, c[i].clone(), half[2 * i + 1].clone()))
+        .collect();
+    let m = g.gates(gates)?;
+    Ok(m.into_iter()
+        .enumerate()
+        .map(|(i, m)| half[2 * i].clone() + m)
+        .collect())
+}
+
+/// `a + b mod 2^32`, by a ripple-carry adder: one layer of half adders, then one layer per bit.
+///
+/// The carry out of bit `i` is `ab + (a ^ b) c`, where at most one term is set, so a sum.
+fn add<F: Field, G: Gates<F>>(
+    g: &mut G,
+    a: &[G::Wire],
+    b: &[G::Wire],
+) -> Result<Word<G::Wire>, SynthesisError> {
+    let layer = (0..32)
+        .flat_map(|i| {
+            vec![
+                (Gate::Xor, a[i].clone(), b[i].clone()),
+                (Gate::And, a[i].clone(), b[i].clone()),
+            ]
+        })
+        .collect();
+    let half = g.gates(layer)?;
+    let mut sum = vec![half[0].clone()];
+    let mut carry = half[1].clone();
+    for i in 1..32 {
+        let t = half[2 * i].clone();
+        if i == 31 {
+            sum.extend(g.gates(vec![(Gate::Xor, t, carry)])?);
+            break;
+        }
+        let out = g.gates(vec![
+            (Gate::Xor, t.clone(), carry.clone()),
+            (Gate::And, t, carry),
+        ])?;
+        sum.push(out[0].clone());
+        carry = half[2 * i + 1].clone() + out[1].clone();
+    }
+    Ok(sum)
+}
+
+/// The digest of `message` (bits, least significant first within each byte), as eight words.
+fn sha256<F: Field, G: Gates<F>>(
+    g: &mut G,
+    message: &[G::Wire],
+) -> Result<Vec<Word<G::Wire>>, SynthesisError> {
+    assert_eq!(
+        message.len() % 512,
+        512 - 72,
+        "a message fills whole blocks but for the padding"
+    );
+    let mut bytes: Vec<Vec<G::Wire>> = message.chunks(8).map(|b| b.to_vec()).collect();
+    let padding = std::iter::once(0x80u8).chain((message.len() as u64).to_be_bytes().to_vec());
+    for b in padding {
+        bytes.push((0..8).map(|i| g.constant((b >> i) & 1 == 1)).collect());
+    }
+
+    let mut h: Vec<Word<G::Wire>> = H0.iter().map(|x| constant_word(g, *x)).collect();
+    for block in bytes.chunks(64) {
+        // Words are big-endian.
+        let mut w: Vec<Word<G::Wire>> = block
+            .chunks(4)
+            .map(|b| (0..32).map(|i| b[3 - i / 8][i % 8].clone()).collect())
+            .collect();
+        for t in 16..64 {
+            let s0 = sigma(g, &w[t - 15], (7, 18, 3), true)?;
+            let s1 = sigma(g, &w[t - 2], (17, 19, 10), true)?;
+            let x = add(g, &w[t - 16], &s0)?;
+            let x = add(g, &x, &w[t - 7])?;
+            w.push(add(g, &x, &s1)?);
+        }
+
+        // a, b, c, d, e, f, g, h
+        let mut v = h.clone();
+        for t in 0..64 {
+            let s1 = sigma(g, &v[4], (6, 11, 25), false)?;
+            let ch = choose(g, &v[4], &v[5], &v[6])?;
+            let k = constant_word(g, K[t]);
+            let mut t1 = add(g, &v[7], &s1)?;
+            for x in &[ch, k, w[t].clone()] {
+                t1 = add(g, &t1, x)?;
+            }
+            let s0 = sigma(g, &v[0], (2, 13, 22), false)?;
+            let maj = majority(g, &v[0], &v[1], &v[2])?;
+            let t2 = add(g, &s0, &maj)?;
+            let e = add(g, &v[3], &t1)?;
+            let a = add(g, &t1, &t2)?;
+            v.rotate_right(1);
+            v[0] = a;
+            v[4] = e;
+        }
+        h = h
+            .iter()
+            .zip(&v)
+            .map(|(h, v)| add(g, h, v))
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(h)
+}
+
+/// Public inputs are the eight words of the digest.
+///
+/// The message is a witness, one boolean variable per bit; every gate adds a witness variable and
+/// a constraint.
+#[derive(Clone)]
+pub struct Sha256Circuit<F: Field> {
+    /// Bits of the message, least significant first within each byte.
+    message: Vec<Option<F>>,
+    /// Every gate's output, in order, from [Eval].
+    trace: Option<Vec<F>>,
+    digest: Vec<Option<F>>,
+}
+
+impl<F: Field> Sha256Circuit<F> {
+    fn without_data(blocks: usize) -> Self {
+        Self {
+            message: vec![None; 8 * message_len(blocks)],
+            trace: None,
+            digest: vec![None; 8],
+        }
+    }
+
+    /// The assignment for a message, given as bits.
+    fn new(message: Vec<F>) -> Self {
+        let mut eval = Eval { trace: Vec::new() };
+        let words = sha256(&mut eval, &message).unwrap();
+        let digest = words
+            .iter()
+            .map(|w| Some(w.iter().rev().fold(F::zero(), |acc, b| acc.double() + b)))
+            .collect();
+        Self {
+            message: message.into_iter().map(Some).collect(),
+            trace: Some(eval.trace),
+            digest,
+        }
+    }
+}
+
+impl<ConstraintF: Field> ConstraintSynthesizer<ConstraintF> for Sha256Circuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let digest = self
+            .digest
+            .iter()
+            .map(|d| cs.new_input_variable(|| d.ok_or(SynthesisError::AssignmentMissing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut message = Vec::with_capacity(self.message.len());
+        for bit in &self.message {
+            let v = cs.new_witness_variable(|| bit.ok_or(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + v, lc!() + v, lc!() + v)?;
+            message.push(lc!() + v);
+        }
+        let mut synth = Synth {
+            cs: cs.clone(),
+            trace: self.trace.map(Vec::into_iter),
+        };
+        let words = sha256(&mut synth, &message)?;
+        for (word, d) in words.into_iter().zip(digest) {
+            let mut packed = lc!();
+            let mut coeff = ConstraintF::one();
+            for bit in word {
+                packed = packed + bit * coeff;
+                coeff.double_in_place();
+            }
+            cs.enforce_constraint(packed, lc!() + Variable::One, lc!() + d)?;
+        }
+        Ok(())
+    }
+}
+
+/// A message of `n` blocks with a given digest; in MPC, byte `i` is input by party
+/// `i % n_parties`.
+pub struct Sha256Preimage;
+
+fn public_inputs<F: Field>(words: &[u32]) -> Vec<F> {
+    words
+        .iter()
+        .enumerate()
+        .fold(PublicInputs::new(), |p, (i, w)| {
+            p.u64(&format!("digest word {}", i), *w as u64)
+        })
+        .to_vec()
+}
+
+fn words<F: PrimeField>(digest: &[F]) -> Vec<u32> {
+    digest
+        .iter()
+        .map(|d| d.into_repr().as_ref()[0] as u32)
+        .collect()
+}
+
+impl BenchCircuit for Sha256Preimage {
+    type Circuit<F: PrimeField> = Sha256Circuit<F>;
+
+    fn without_data<F: PrimeField>(n: usize) -> Sha256Circuit<F> {
+        Sha256Circuit::without_data(n)
+    }
+
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (Sha256Circuit<F>, Vec<F>) {
+        let message = (0..message_len(n))
+            .flat_map(|_| {
+                let byte: u8 = rng.gen();
+                (0..8).map(move |i| F::from((byte >> i) & 1 == 1))
+            })
+            .collect();
+        let circ = Sha256Circuit::new(message);
+        let digest: Vec<F> = circ.digest.iter().map(|d| d.unwrap()).collect();
+        (circ, public_inputs(&words(&digest)))
+    }
+
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        _rng: &mut R,
+    ) -> (Sha256Circuit<MpcFr<E, S>>, Vec<E::Fr>) {
+        let rng = &mut rand::thread_rng();
+        let message = (0..message_len(n))
+            .flat_map(|i| {
+                let mine = i % MpcMultiNet::n_parties() == MpcMultiNet::party_id();
+                let byte = if mine { rng.gen::<u8>() as u64 } else { 0 };
+                MpcFr::<E, S>::from_add_shared(E::Fr::from(byte)).bit_decomposition(8)
+            })
+            .collect();
+        let mut circ = Sha256Circuit::new(message);
+        // Gates on public values (e.g., the initial state) have public outputs, but the prover
+        // needs the whole assignment shared.
+        let trace = circ.trace.as_mut().unwrap();
+        trace.iter_mut().for_each(|v| *v = v.into_shared());
+        let digest = RevealOutputs::Instance.reveal(circ.clone()).unwrap();
+        (circ, digest)
+    }
+
+    fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+        let size = n * GATES_PER_BLOCK + 8 * message_len(n) + 16;
+        (size, size, 4 * size)
+    }
+}
+
+/// Gates (so, constraints and witness variables) per block.
+const GATES_PER_BLOCK: usize =
+    48 * (2 * 64 + 3 * ADDER_GATES) + 64 * (2 * 64 + 32 + 96 + 7 * ADDER_GATES) + 8 * ADDER_GATES;
+
+/// Gates in one 32-bit [add].
+const ADDER_GATES: usize = 64 + 2 * 31 - 1;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn matches_sha2() {
+        let rng = &mut test_rng();
+        let bytes: Vec<u8> = (0..message_len(2)).map(|_| rng.gen()).collect();
+        let bits = bytes
+            .iter()
+            .flat_map(|b| (0..8).map(move |i| Fr::from((b >> i) & 1 == 1)))
+            .collect();
+        let circ = Sha256Circuit::new(bits);
+        let digest: Vec<Fr> = circ.digest.iter().map(|d| d.unwrap()).collect();
+        let expected: Vec<u32> = Sha256::digest(&bytes)
+            .chunks(4)
+            .map(|w| u32::from_be_bytes([w[0], w[1], w[2], w[3]]))
+            .collect();
+        assert_eq!(words(&digest), expected);
+
+        let cs = ConstraintSystem::new_ref();
+        circ.generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(
+            cs.num_constraints(),
+            2 * GATES_PER_BLOCK + 8 * bytes.len() + 8
+        );
+    }
+}
@@ -0,0 +1,345 @@
+//! Shuffles: prove that a published list is a permutation of private inputs, as a mix does.
+//!
+//! Each input's owner commits to it ahead of time ([Poseidon commitments](crate::poseidon)), and
+//! the commitments are public inputs, so the list is a permutation of the committed inputs, not
+//! of any the provers pick.
+//!
+//! The circuit applies [Benes] networks to the inputs, one per shuffling pass, with the switch
+//! settings as witnesses: each setting `b` is a bit, and each switch's product `d = b * (y - x)`
+//! moves its wires to `(x + d, y - d)`, so whatever the settings, each network only permutes.
+//! The last network's output is the public list. In MPC, the witness is what
+//! [MpcField::shuffle_passes](mpc_algebra::MpcField::shuffle_passes) computes, each pass by its
+//! own party, and the provers reveal only the shuffled list: which input went where stays hidden
+//! unless every party that shuffled colludes.
+use ark_ec::PairingEngine;
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    },
+};
+use ark_std::rand::{seq::SliceRandom, Rng};
+use mpc_algebra::shuffle::{Benes, Pass};
+use mpc_algebra::{PairingShare, Reveal};
+use mpc_net::{MpcMultiNet, MpcNet};
+
+use super::{BenchCircuit, MpcFr};
+use crate::audit::{self, Owner};
+use crate::inputs::PublicInputs;
+use crate::poseidon::{self, Opening};
+
+/// A pass's switch settings, and their products, in the order of [Benes::route].
+#[derive(Clone)]
+struct PassWitness<F: Field> {
+    bits: Vec<Option<F>>,
+    products: Vec<Option<F>>,
+}
+
+/// Public inputs are the inputs' commitments, then the shuffled list.
+#[derive(Clone)]
+pub struct ShuffleCircuit<F: Field> {
+    network: Benes,
+    commitments: Vec<Option<F>>,
+    /// The openings of the commitments, whose values are the inputs.
+    inputs: Vec<Option<Opening<F>>>,
+    passes: Vec<PassWitness<F>>,
+    outputs: Vec<Option<F>>,
+    /// Who input each value, for [crate::audit]; empty if no one is to be named.
+    input_owners: Vec<Owner>,
+}
+
+impl<F: Field> ShuffleCircuit<F> {
+    fn without_data(len: usize, passes: usize) -> Self {
+        let network = Benes::new(len);
+        let switches = network.switches();
+        Self {
+            network,
+            commitments: vec![None; len],
+            inputs: vec![None; len],
+            passes: vec![
+                PassWitness {
+                    bits: vec![None; switches],
+                    products: vec![None; switches],
+                };
+                passes
+            ],
+            outputs: vec![None; len],
+            input_owners: Vec::new(),
+        }
+    }
+
+    /// The assignment for the inputs, committed to by `commitments` and opened by `inputs`,
+    /// shuffled by `passes`, in order.
+    fn new(
+        network: Benes,
+        commitments: Vec<F>,
+        inputs: Vec<Opening<F>>,
+        passes: Vec<Pass<F>>,
+    ) -> Self {
+        let some = |v: Vec<F>| v.into_iter().map(Some).collect::<Vec<_>>();
+        let outputs = passes.last().map_or_else(
+            || inputs.iter().map(|o| o.value).collect(),
+            |p| p.outputs.clone(),
+        );
+        Self {
+            network,
+            commitments: some(commitments),
+            inputs: inputs.into_iter().map(Some).collect(),
+            passes: passes
+                .into_iter()
+                .map(|p| PassWitness {
+                    bits: some(p.bits),
+                    products: some(p.products),
+                })
+                .collect(),
+            outputs: some(outputs),
+            input_owners: Vec::new(),
+        }
+    }
+}
+
+fn public_inputs<F: Field>(commitments: &[F], outputs: &[F]) -> Vec<F> {
+    let named = |p: PublicInputs<F>, name: &str, values: &[F]| {
+        values
+            .iter()
+            .enumerate()
+            .fold(p, |p, (i, x)| p.field(&format!("{} {}", name, i), *x))
+    };
+    let p = named(PublicInputs::new(), "commitment", commitments);
+    named(p, "output", outputs).to_vec()
+}
+
+impl<ConstraintF: PrimeField> ConstraintSynthesizer<ConstraintF> for ShuffleCircuit<ConstraintF> {
+    fn generate_constraints(
+        self,
+        cs: ConstraintSystemRef<ConstraintF>,
+    ) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let commitments = self
+            .commitments
+            .iter()
+            .map(|c| cs.new_input_variable(|| c.ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|v| cs.new_input_variable(|| v.ok_or_else(missing)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut wires: Vec<LinearCombination<ConstraintF>> = Vec::new();
+        for (i, (opening, c)) in self.inputs.iter().zip(commitments).enumerate() {
+            let x =
+                cs.new_witness_variable(|| opening.as_ref().map(|o| o.value).ok_or_else(missing))?;
+            let owner = self.input_owners.get(i).cloned();
+            if let Some(owner) = owner {
+                audit::tag(x, owner);
+            }
+            poseidon::enforce_opening(&cs, c, x, opening.as_ref(), owner)?;
+            wires.push(lc!() + x);
+        }
+        // The padding wires hold zero.
+        let width = 2 * self.network.layers().first().map_or(1, Vec::len);
+        wires.resize(width, lc!());
+
+        for pass in &self.passes {
+            let mut switch = 0;
+            for layer in self.network.layers() {
+                for &(a, b) in layer {
+                    let bit = cs.new_witness_variable(|| pass.bits[switch].ok_or_else(missing))?;
+                    let d =
+                        cs.new_witness_variable(|| pass.products[switch].ok_or_else(missing))?;
+                    cs.enforce_constraint(lc!() + bit, lc!() + bit, lc!() + bit)?;
+                    let gap = wires[b].clone() - &wires[a];
+                    cs.enforce_constraint(lc!() + bit, gap, lc!() + d)?;
+                    wires[a] = wires[a].clone() + d;
+                    wires[b] = wires[b].clone() - d;
+                    switch += 1;
+                }
+            }
+        }
+        for (w, y) in wires.into_iter().zip(outputs) {
+            cs.enforce_constraint(w, lc!() + Variable::One, lc!() + y)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A shuffle of `n` values through `PASSES` networks. In MPC, value `i` is input by party
+/// `i % n_parties`, and pass `p` is by party `p % n_parties`.
+///
+/// `shuffle` takes two passes, by parties 0 and 1; an embedding binary can register a
+/// `Shuffle<N>` for a pass by each of `N` parties.
+pub struct Shuffle<const PASSES: usize>;
+
+/// Values are integers below `2^32`, as votes or bids would be; each comes with a commitment to
+/// it.
+fn random_values<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (Vec<F>, Vec<Opening<F>>) {
+    (0..n)
+        .map(|_| poseidon::commit(F::from(rng.gen::<u32>() as u64), F::rand(rng)))
+        .unzip()
+}
+
+impl<const PASSES: usize> BenchCircuit for Shuffle<PASSES> {
+    type Circuit<F: PrimeField> = ShuffleCircuit<F>;
+
+    fn without_data<F: PrimeField>(n: usize) -> ShuffleCircuit<F> {
+        ShuffleCircuit::without_data(n, PASSES)
+    }
+
+    fn local<F: PrimeField, R: Rng>(n: usize, rng: &mut R) -> (ShuffleCircuit<F>, Vec<F>) {
+        let network = Benes::new(n);
+        let (commitments, inputs) = random_values(n, rng);
+        let mut list: Vec<F> = inputs.iter().map(|o| o.value).collect();
+        let mut passes = Vec::new();
+        for owner in 0..PASSES {
+            let mut perm: Vec<usize> = (0..n).collect();
+            perm.shuffle(rng);
+            let bits: Vec<F> = network.route(&perm).into_iter().map(F::from).collect();
+            let (outputs, products) = network.apply(&list, &bits);
+            list = outputs.clone();
+            passes.push(Pass {
+                owner,
+                bits,
+                products,
+                outputs,
+            });
+        }
+        let circ = ShuffleCircuit::new(network, commitments.clone(), inputs, passes);
+        let outputs: Vec<F> = circ.outputs.iter().map(|y| y.unwrap()).collect();
+        (circ, public_inputs(&commitments, &outputs))
+    }
+
+    fn mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+        n: usize,
+        _rng: &mut R,
+    ) -> (ShuffleCircuit<MpcFr<E, S>>, Vec<E::Fr>) {
+        let parties = MpcMultiNet::n_parties();
+        // Permutations must be private to each party, so not from the shared `_rng`.
+        let rng = &mut rand::thread_rng();
+        let (commitments, openings) = random_values::<E::Fr, _>(n, rng);
+        let (commitments, inputs): (Vec<E::Fr>, Vec<_>) = commitments
+            .into_iter()
+            .zip(openings)
+            .enumerate()
+            .map(|(i, (c, opening))| {
+                let ours = i % parties == MpcMultiNet::party_id();
+                let share = |x: E::Fr| {
+                    MpcFr::<E, S>::from_add_shared(if ours { x } else { E::Fr::from(0u8) })
+                };
+                // The owner publishes its commitment.
+                (share(c).reveal(), opening.map(share))
+            })
+            .unzip();
+        let values: Vec<MpcFr<E, S>> = inputs.iter().map(|o| o.value).collect();
+        let owners: Vec<usize> = (0..PASSES).map(|p| p % parties).collect();
+        let passes = MpcFr::<E, S>::shuffle_passes(&values, &owners, rng);
+        let public_commitments = commitments
+            .iter()
+            .map(|c| MpcFr::<E, S>::from_public(*c))
+            .collect();
+        let mut circ = ShuffleCircuit::new(Benes::new(n), public_commitments, inputs, passes);
+        let outputs: Vec<E::Fr> = circ.outputs.iter().map(|y| y.unwrap().reveal()).collect();
+        circ.input_owners = (0..n).map(|i| Owner::Party(i % parties)).collect();
+        (circ, public_inputs(&commitments, &outputs))
+    }
+
+    fn marlin_bounds(n: usize) -> (usize, usize, usize) {
+        let network = Benes::new(n);
+        let switches = PASSES * network.switches();
+        let size = 2 * switches + n + 1;
+        // A wire's linear combination gains a term at each layer.
+        let terms = PASSES * network.layers().len() + 1;
+        let (constraints, variables, non_zero) = poseidon::opening_bounds();
+        (
+            size + n * constraints,
+            size + n * (variables + 2),
+            size * (2 * terms + 4) + n * non_zero,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+
+    fn satisfied(circ: ShuffleCircuit<Fr>) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        circ.generate_constraints(cs.clone()).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn proves_only_permutations() {
+        let rng = &mut test_rng();
+        let n = 5;
+        let (circ, public) = Shuffle::<2>::local::<Fr, _>(n, rng);
+        let mut sorted = public[n..].to_vec();
+        sorted.sort();
+        let mut inputs: Vec<Fr> = circ
+            .inputs
+            .iter()
+            .map(|o| o.as_ref().unwrap().value)
+            .collect();
+        inputs.sort();
+        assert_eq!(sorted, inputs);
+        assert!(satisfied(circ));
+
+        // A switch set to 1/2 averages its wires: the products fit, but the setting is no bit.
+        let network = Benes::new(2);
+        let (commitments, inputs): (Vec<Fr>, Vec<Opening<Fr>>) = [2u8, 4]
+            .iter()
+            .map(|x| poseidon::commit(Fr::from(*x), Fr::rand(rng)))
+            .unzip();
+        let values: Vec<Fr> = inputs.iter().map(|o| o.value).collect();
+        let half = Fr::from(2u8).inverse().unwrap();
+        let (outputs, products) = network.apply(&values, &[half]);
+        assert_eq!(outputs, vec![Fr::from(3u8); 2]);
+        let pass = Pass {
+            owner: 0,
+            bits: vec![half],
+            products,
+            outputs,
+        };
+        let circ = ShuffleCircuit::new(network, commitments, inputs, vec![pass]);
+        assert!(!satisfied(circ));
+    }
+
+    #[test]
+    fn shuffles_only_the_committed_inputs() {
+        let rng = &mut test_rng();
+        let n = 4;
+        let (_, public) = Shuffle::<1>::local::<Fr, _>(n, rng);
+        let (commitments, outputs) = public.split_at(n);
+
+        // Claim that the outputs were the inputs, and that every switch stayed open.
+        let network = Benes::new(n);
+        let open = vec![Fr::from(0u8); network.switches()];
+        let (same, products) = network.apply(outputs, &open);
+        assert_eq!(same, outputs);
+        let pass = Pass {
+            owner: 0,
+            bits: open,
+            products,
+            outputs: same,
+        };
+        let (own_commitments, openings): (Vec<Fr>, Vec<Opening<Fr>>) = outputs
+            .iter()
+            .map(|y| poseidon::commit(*y, Fr::rand(rng)))
+            .unzip();
+        // That shuffles inputs of the prover's own...
+        let circ = ShuffleCircuit::new(
+            network.clone(),
+            own_commitments,
+            openings.clone(),
+            vec![pass.clone()],
+        );
+        assert!(satisfied(circ));
+        // ...but not the committed ones.
+        let circ = ShuffleCircuit::new(network, commitments.to_vec(), openings, vec![pass]);
+        assert!(!satisfied(circ));
+    }
+}
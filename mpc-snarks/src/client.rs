@@ -21,15 +21,13 @@ use mpc_algebra::malicious_majority as mm;
 use mpc_algebra::*;
 use mpc_trait::MpcWire;
 use mpc_net::{MpcNet, MpcMultiNet};
+use mpc_snarks::{groth, marlin};
 
 use clap::arg_enum;
 use merlin::Transcript;
 use structopt::StructOpt;
 
-mod groth;
-mod marlin;
 mod plonk;
-mod silly;
 
 arg_enum! {
     #[derive(PartialEq, Debug)]
@@ -49,6 +47,7 @@ arg_enum! {
         PairingDiv,
         Groth16,
         Marlin,
+        MarlinPoseidon,
         PolyEval,
         MarlinPc,
         MarlinPcBatch,
@@ -85,6 +84,10 @@ struct Opt {
     #[structopt(long, parse(from_os_str))]
     hosts: PathBuf,
 
+    /// Rendezvous file through which parties with port 0 in the hosts file find each other
+    #[structopt(long, parse(from_os_str))]
+    rendezvous: Option<PathBuf>,
+
     /// Which party are you? 0 or 1?
     #[structopt(long, default_value = "0")]
     party: u8,
@@ -123,6 +126,7 @@ impl Opt {
                 ComputationDomain::Pairing
             }
             Computation::Marlin
+            | Computation::MarlinPoseidon
             | Computation::Groth16
             | Computation::Plonk
             | Computation::Kzg
@@ -197,7 +201,11 @@ impl Computation {
                 vec![]
             }
             Computation::Marlin => {
-                marlin::mpc_test_prove_and_verify(1);
+                marlin::mpc_test_prove_and_verify::<blake2::Blake2s>(1);
+                vec![]
+            }
+            Computation::MarlinPoseidon => {
+                marlin::mpc_test_prove_and_verify::<ark_marlin::PoseidonSponge<Fr>>(1);
                 vec![]
             }
             Computation::Plonk => {
@@ -872,7 +880,11 @@ fn main() -> () {
         env_logger::init();
     }
     let domain = opt.domain();
-    MpcMultiNet::init_from_file(opt.hosts.to_str().unwrap(), opt.party as usize);
+    let hosts = opt.hosts.to_str().unwrap();
+    match &opt.rendezvous {
+        Some(r) => MpcMultiNet::init_with_rendezvous(hosts, opt.party as usize, r.to_str().unwrap()),
+        None => MpcMultiNet::init_from_file(hosts, opt.party as usize),
+    }
     debug!("Start");
     if opt.spdz {
         let inputs = opt
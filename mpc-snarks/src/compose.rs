@@ -0,0 +1,217 @@
+//! Combinators for building larger circuits out of existing pieces.
+//!
+//! A [Fragment] is a piece of circuit that consumes some wires and produces others. Fragments
+//! compose sequentially with [Then] and [Repeat], sharing wires between stages, and a fragment is
+//! turned into an ordinary [ConstraintSynthesizer] by [Closed]. Whole circuits are batched with
+//! [Parallel].
+//!
+//! Everything here is generic over the field, so it works just as well over an `MpcField`. Note
+//! that fragments compute their outputs during synthesis, so over shares, any multiplications
+//! they do happen (and communicate) inside the prover.
+use ark_ff::Field;
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable},
+};
+
+/// A variable, with its value if it is known.
+#[derive(Clone, Copy, Debug)]
+pub struct Wire<F: Field> {
+    pub var: Variable,
+    pub value: Option<F>,
+}
+
+impl<F: Field> Wire<F> {
+    /// Allocate a new witness.
+    pub fn witness(cs: &ConstraintSystemRef<F>, value: Option<F>) -> Result<Self, SynthesisError> {
+        let var = cs.new_witness_variable(|| value.ok_or(SynthesisError::AssignmentMissing))?;
+        Ok(Self { var, value })
+    }
+
+    /// Allocate a new public input.
+    pub fn input(cs: &ConstraintSystemRef<F>, value: Option<F>) -> Result<Self, SynthesisError> {
+        let var = cs.new_input_variable(|| value.ok_or(SynthesisError::AssignmentMissing))?;
+        Ok(Self { var, value })
+    }
+}
+
+/// A piece of circuit, which constrains output wires in terms of input wires.
+pub trait Fragment<F: Field>: Clone {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError>;
+}
+
+/// `A`, then `B` on `A`'s outputs.
+#[derive(Clone, Debug)]
+pub struct Then<A, B>(pub A, pub B);
+
+impl<F: Field, A: Fragment<F>, B: Fragment<F>> Fragment<F> for Then<A, B> {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError> {
+        let mid = self.0.synthesize(cs, inputs)?;
+        self.1.synthesize(cs, mid)
+    }
+}
+
+/// `A`, `times` times in sequence, each copy consuming the previous copy's outputs.
+#[derive(Clone, Debug)]
+pub struct Repeat<A> {
+    pub fragment: A,
+    pub times: usize,
+}
+
+impl<F: Field, A: Fragment<F>> Fragment<F> for Repeat<A> {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError> {
+        (0..self.times).try_fold(inputs, |wires, _| {
+            self.fragment.clone().synthesize(cs, wires)
+        })
+    }
+}
+
+/// What [Fragment::synthesize] does, as a function.
+pub type SynthesizeFn<F> =
+    fn(&ConstraintSystemRef<F>, Vec<Wire<F>>) -> Result<Vec<Wire<F>>, SynthesisError>;
+
+/// A fragment given by a function, for when which fragment is only known at run time.
+#[derive(Clone, Copy, Debug)]
+pub struct Step<F: Field>(pub SynthesizeFn<F>);
+
+impl<F: Field> Fragment<F> for Step<F> {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError> {
+        (self.0)(cs, inputs)
+    }
+}
+
+/// A circuit made from a fragment, whose inputs are witnesses and whose outputs are public.
+#[derive(Clone, Debug)]
+pub struct Closed<A, F: Field> {
+    pub fragment: A,
+    /// Values of the fragment's inputs; `None`s during setup.
+    pub inputs: Vec<Option<F>>,
+}
+
+impl<F: Field, A: Fragment<F>> ConstraintSynthesizer<F> for Closed<A, F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|v| Wire::witness(&cs, v))
+            .collect::<Result<_, _>>()?;
+        for out in self.fragment.synthesize(&cs, inputs)? {
+            let public = Wire::input(&cs, out.value)?;
+            cs.enforce_constraint(lc!() + out.var, lc!() + Variable::One, lc!() + public.var)?;
+        }
+        Ok(())
+    }
+}
+
+/// Independent instances of a circuit, in one constraint system.
+///
+/// Their public inputs are concatenated, in order.
+#[derive(Clone, Debug)]
+pub struct Parallel<C>(pub Vec<C>);
+
+impl<F: Field, C: ConstraintSynthesizer<F>> ConstraintSynthesizer<F> for Parallel<C> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        for c in self.0 {
+            c.generate_constraints(cs.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Squares each of its inputs.
+#[derive(Clone, Copy, Debug)]
+pub struct Square;
+
+impl<F: Field> Fragment<F> for Square {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError> {
+        inputs
+            .into_iter()
+            .map(|x| {
+                let y = Wire::witness(cs, x.value.map(|v| v.square()))?;
+                cs.enforce_constraint(lc!() + x.var, lc!() + x.var, lc!() + y.var)?;
+                Ok(y)
+            })
+            .collect()
+    }
+}
+
+/// Adds its inputs into one output.
+#[derive(Clone, Copy, Debug)]
+pub struct Sum;
+
+impl<F: Field> Fragment<F> for Sum {
+    fn synthesize(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: Vec<Wire<F>>,
+    ) -> Result<Vec<Wire<F>>, SynthesisError> {
+        let value = inputs.iter().map(|w| w.value).sum::<Option<F>>();
+        let out = Wire::witness(cs, value)?;
+        let sum = inputs.iter().fold(lc!(), |acc, w| acc + w.var);
+        cs.enforce_constraint(sum, lc!() + Variable::One, lc!() + out.var)?;
+        Ok(vec![out])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+
+    fn check<C: ConstraintSynthesizer<Fr>>(c: C) -> (bool, Vec<Fr>) {
+        let cs = ConstraintSystem::new_ref();
+        c.generate_constraints(cs.clone()).unwrap();
+        let inputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+        (cs.is_satisfied().unwrap(), inputs)
+    }
+
+    #[test]
+    fn sequential_shares_wires() {
+        // (a^4 + b^4)^2
+        let circ = Closed {
+            fragment: Then(
+                Repeat {
+                    fragment: Square,
+                    times: 2,
+                },
+                Then(Sum, Square),
+            ),
+            inputs: vec![Some(Fr::from(2u8)), Some(Fr::from(3u8))],
+        };
+        let (ok, outputs) = check(circ);
+        assert!(ok);
+        assert_eq!(outputs, vec![Fr::from(97u64 * 97)]);
+    }
+
+    #[test]
+    fn parallel_concatenates_inputs() {
+        let instance = |a: u8| Closed {
+            fragment: Square,
+            inputs: vec![Some(Fr::from(a))],
+        };
+        let (ok, outputs) = check(Parallel(vec![instance(2), instance(5), instance(7)]));
+        assert!(ok);
+        assert_eq!(outputs, vec![Fr::from(4u8), Fr::from(25u8), Fr::from(49u8)]);
+    }
+}
@@ -0,0 +1,509 @@
+//! Witness values from input parties that are not online while proving.
+//!
+//! An input party (say, a data owner) splits its values into additive shares, one per computing
+//! party, and encrypts each party's shares to that party's [PublicKey], ahead of time. The
+//! resulting [Contribution] can be handed over any way at all (a file, a bulletin board); later,
+//! each computing party decrypts its own shares and takes them as its shares of the witness (see
+//! [import]), with no round of interaction with the input party, or among the computing parties.
+//!
+//! The encryption is hashed ElGamal over `ed_on_bls12_377`: a fresh key `r`, published as `R = r
+//! G`, and per party `i` the Diffie-Hellman secret `r P_i`, hashed (with `R`, `P_i`, `i` and the
+//! contribution's label) into a ChaCha20 key and a Blake2s MAC key. A contribution whose
+//! ciphertext or label was altered fails its MAC. Shares are additive, so any shares at all are
+//! shares of some values: an input party can only choose its own inputs, as it could in person.
+//!
+//! Unless those inputs are also bound elsewhere: say the statement is about values that a
+//! regulator holds a commitment to. Then an input party may [commit](Contribution::seal_committed)
+//! to its values as it shares them, with a Pedersen commitment `C = sum_j v_j G_j + rho H`, and
+//! the computing parties can [check](import_checked), in one round, that the shares they were
+//! given add up to the committed values before proving anything with them. Each computes the
+//! same commitment of its own shares (of the values and of `rho`); these add up to `C` if and
+//! only if (barring a discrete logarithm between the generators, which are hashed to the curve)
+//! the shares add up to the committed values. This catches a malformed contribution from an input
+//! party that does not collude with a computing party; the commitments each party broadcasts are
+//! to uniformly random shares (those of `rho` among them), so they reveal nothing beyond `C`.
+use crate::envelope::{from_hex, to_hex};
+use ark_ec::msm::VariableBaseMSM;
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ed_on_bls12_377::{EdwardsAffine, Fr as Scalar};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::Rng;
+use blake2::crypto_mac::{Mac, NewMac};
+use blake2::{Blake2b, Blake2s, Digest};
+use mpc_algebra::channel::MpcSerNet;
+use mpc_algebra::{FieldShare, MpcField, Reveal};
+use mpc_net::MpcMultiNet;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// A computing party's key, to which input parties encrypt its shares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PublicKey(pub EdwardsAffine);
+
+/// A computing party's decryption key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SecretKey(Scalar);
+
+impl SecretKey {
+    pub fn generate<R: Rng>(rng: &mut R) -> Self {
+        Self(Scalar::rand(rng))
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(
+            EdwardsAffine::prime_subgroup_generator()
+                .mul(self.0)
+                .into_affine(),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum ContributionError {
+    /// The contribution has no ciphertext for this party.
+    NoShares(usize),
+    /// The ciphertext (or the label) was altered, or was not encrypted to this party's key.
+    Tampered(usize),
+    Encoding(SerializationError),
+    /// The contribution (by its label) has no commitment to check its shares against.
+    Uncommitted(String),
+    /// The contribution's shares do not add up to the values it commits to.
+    Inconsistent(String),
+    /// Another computing party could not decrypt its shares of the contribution.
+    PeerFailed(usize, String),
+}
+
+impl Display for ContributionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ContributionError::NoShares(p) => write!(f, "no shares for party {}", p),
+            ContributionError::Tampered(p) => {
+                write!(f, "shares for party {} fail authentication", p)
+            }
+            ContributionError::Encoding(e) => write!(f, "malformed contribution: {}", e),
+            ContributionError::Uncommitted(l) => {
+                write!(f, "contribution {:?} has no commitment", l)
+            }
+            ContributionError::Inconsistent(l) => {
+                write!(f, "shares of contribution {:?} do not match its commitment", l)
+            }
+            ContributionError::PeerFailed(p, l) => {
+                write!(f, "party {} could not open its shares of contribution {:?}", p, l)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ContributionError {}
+
+impl From<SerializationError> for ContributionError {
+    fn from(e: SerializationError) -> Self {
+        ContributionError::Encoding(e)
+    }
+}
+
+/// One input party's values, as shares encrypted to each computing party.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Contribution {
+    /// What the values are for (e.g., the input party's name), bound into the encryption.
+    pub label: String,
+    /// `R`, in hex.
+    pub ephemeral: String,
+    /// Per computing party, its encrypted shares and their MAC, in hex.
+    pub shares: Vec<String>,
+    /// The Pedersen commitment to the values, in hex, if the input party made one; the shares
+    /// are then of the values followed by the commitment's blinding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commitment: Option<String>,
+}
+
+/// The MAC's length, in bytes.
+const TAG_LEN: usize = 32;
+
+/// The ChaCha20 and MAC keys for party `party`, from the Diffie-Hellman secret `shared`.
+fn keys(
+    label: &str,
+    ephemeral: &EdwardsAffine,
+    pk: &PublicKey,
+    party: usize,
+    shared: &EdwardsAffine,
+) -> ([u8; 32], [u8; 32]) {
+    let derive = |purpose: &[u8]| {
+        let mut h = <Blake2s as Digest>::new();
+        Digest::update(&mut h, purpose);
+        for point in &[shared, ephemeral, &pk.0] {
+            let mut bytes = Vec::new();
+            point.serialize(&mut bytes).unwrap();
+            Digest::update(&mut h, &bytes);
+        }
+        Digest::update(&mut h, (party as u64).to_le_bytes());
+        Digest::update(&mut h, label.as_bytes());
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&Digest::finalize(h));
+        key
+    };
+    (derive(b"contribution cipher"), derive(b"contribution mac"))
+}
+
+fn mac(key: &[u8; 32], ciphertext: &[u8]) -> Blake2s {
+    let mut m = <Blake2s as NewMac>::new_varkey(key).unwrap();
+    Mac::update(&mut m, ciphertext);
+    m
+}
+
+/// XOR `bytes` with ChaCha20's keystream under `key` (a fresh key per message, so no nonce).
+fn apply_keystream(key: [u8; 32], bytes: &mut [u8]) {
+    let mut stream = vec![0u8; bytes.len()];
+    ChaCha20Rng::from_seed(key).fill_bytes(&mut stream);
+    bytes.iter_mut().zip(stream).for_each(|(b, s)| *b ^= s);
+}
+
+impl Contribution {
+    /// Share `values` among the parties with keys `pks`, and encrypt each party's shares to it.
+    pub fn seal<F: Field, R: Rng>(
+        label: &str,
+        values: &[F],
+        pks: &[PublicKey],
+        rng: &mut R,
+    ) -> Self {
+        assert!(!pks.is_empty(), "no computing parties");
+        let mut shares: Vec<Vec<F>> = (1..pks.len())
+            .map(|_| (0..values.len()).map(|_| F::rand(rng)).collect())
+            .collect();
+        let last = values
+            .iter()
+            .enumerate()
+            .map(|(j, v)| *v - shares.iter().map(|s| s[j]).sum::<F>())
+            .collect();
+        shares.push(last);
+
+        let r = Scalar::rand(rng);
+        let ephemeral = EdwardsAffine::prime_subgroup_generator()
+            .mul(r)
+            .into_affine();
+        let shares = shares
+            .iter()
+            .zip(pks)
+            .enumerate()
+            .map(|(party, (s, pk))| {
+                let shared = pk.0.mul(r).into_affine();
+                let (cipher_key, mac_key) = keys(label, &ephemeral, pk, party, &shared);
+                let mut bytes = Vec::new();
+                s.serialize(&mut bytes).unwrap();
+                apply_keystream(cipher_key, &mut bytes);
+                let tag = Mac::finalize(mac(&mac_key, &bytes)).into_bytes();
+                bytes.extend_from_slice(&tag);
+                to_hex(&bytes)
+            })
+            .collect();
+        let mut ephemeral_bytes = Vec::new();
+        ephemeral.serialize(&mut ephemeral_bytes).unwrap();
+        Self {
+            label: label.to_owned(),
+            ephemeral: to_hex(&ephemeral_bytes),
+            shares,
+            commitment: None,
+        }
+    }
+
+    /// Like [Contribution::seal], but also publish a Pedersen commitment in `G` to `values`, so
+    /// that computing parties can check their shares against it (see [import_checked]). Anyone
+    /// the input party gives the commitment's opening (`values` and the returned blinding) can
+    /// check it too.
+    pub fn seal_committed<G: ProjectiveCurve, R: Rng>(
+        label: &str,
+        values: &[G::ScalarField],
+        pks: &[PublicKey],
+        rng: &mut R,
+    ) -> (Self, G::ScalarField) {
+        let blinding = G::ScalarField::rand(rng);
+        let mut opening = values.to_vec();
+        opening.push(blinding);
+        let mut c = Self::seal(label, &opening, pks, rng);
+        let mut bytes = Vec::new();
+        pedersen::<G>(&opening).into_affine().serialize(&mut bytes).unwrap();
+        c.commitment = Some(to_hex(&bytes));
+        (c, blinding)
+    }
+
+    /// The commitment to the values, if the input party made one.
+    pub fn commitment<G: ProjectiveCurve>(&self) -> Result<Option<G::Affine>, ContributionError> {
+        match &self.commitment {
+            Some(hex) => Ok(Some(G::Affine::deserialize(&from_hex(hex)?[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Decrypt `party`'s shares, with its secret key `sk`.
+    pub fn open<F: Field>(
+        &self,
+        party: usize,
+        sk: &SecretKey,
+    ) -> Result<Vec<F>, ContributionError> {
+        let mut shares = self.open_with_blinding(party, sk)?;
+        if self.commitment.is_some() {
+            shares.pop();
+        }
+        Ok(shares)
+    }
+
+    /// `party`'s shares, followed by its share of the commitment's blinding if there is one.
+    fn open_with_blinding<F: Field>(
+        &self,
+        party: usize,
+        sk: &SecretKey,
+    ) -> Result<Vec<F>, ContributionError> {
+        let ciphertext = self
+            .shares
+            .get(party)
+            .ok_or(ContributionError::NoShares(party))?;
+        let mut bytes = from_hex(ciphertext)?;
+        if bytes.len() < TAG_LEN {
+            return Err(ContributionError::Tampered(party));
+        }
+        let tag = bytes.split_off(bytes.len() - TAG_LEN);
+        let ephemeral = EdwardsAffine::deserialize(&from_hex(&self.ephemeral)?[..])?;
+        let shared = ephemeral.mul(sk.0).into_affine();
+        let (cipher_key, mac_key) = keys(&self.label, &ephemeral, &sk.public_key(), party, &shared);
+        mac(&mac_key, &bytes)
+            .verify(&tag)
+            .map_err(|_| ContributionError::Tampered(party))?;
+        apply_keystream(cipher_key, &mut bytes);
+        Ok(Vec::<F>::deserialize(&bytes[..])?)
+    }
+}
+
+/// This party's shares of the values in `contributions`, in order, as decrypted with `sk`.
+///
+/// Every computing party must import the same contributions, in the same order.
+pub fn import<F: Field, S: FieldShare<F>>(
+    contributions: &[Contribution],
+    party: usize,
+    sk: &SecretKey,
+) -> Result<Vec<MpcField<F, S>>, ContributionError> {
+    let mut values = Vec::new();
+    for c in contributions {
+        values.extend(c.open::<F>(party, sk)?);
+    }
+    Ok(values.into_iter().map(MpcField::from_add_shared).collect())
+}
+
+/// The `i`th Pedersen generator of `G`: hashed to the curve by try-and-increment, so that no one
+/// knows a discrete logarithm between any two.
+fn generator<G: ProjectiveCurve>(i: usize) -> G::Affine {
+    let len = G::Affine::zero().serialized_size();
+    (0u64..)
+        .find_map(|attempt| {
+            let mut bytes = Vec::new();
+            for block in 0..len.div_ceil(64) {
+                let mut h = <Blake2b as Digest>::new();
+                Digest::update(&mut h, b"contribution pedersen generator");
+                Digest::update(&mut h, (i as u64).to_le_bytes());
+                Digest::update(&mut h, attempt.to_le_bytes());
+                Digest::update(&mut h, (block as u64).to_le_bytes());
+                bytes.extend_from_slice(&Digest::finalize(h));
+            }
+            let p = G::Affine::from_random_bytes(&bytes[..len])?.mul_by_cofactor_to_projective();
+            if p.is_zero() {
+                None
+            } else {
+                Some(p.into_affine())
+            }
+        })
+        .unwrap()
+}
+
+/// The commitment `sum_j x_j G_j` to `x` (whose last entry is the blinding).
+fn pedersen<G: ProjectiveCurve>(x: &[G::ScalarField]) -> G {
+    let bases: Vec<G::Affine> = (0..x.len()).map(generator::<G>).collect();
+    let scalars: Vec<_> = x.iter().map(|s| s.into_repr()).collect();
+    VariableBaseMSM::multi_scalar_mul(&bases, &scalars)
+}
+
+/// Like [import], but first check, with the other computing parties, that every contribution's
+/// shares add up to the values it commits to (in `G`; see [Contribution::seal_committed]).
+///
+/// Every computing party must call this together, with the same contributions in the same order:
+/// it takes one broadcast among them. Either all of them get their shares, or all of them fail,
+/// on the first contribution that is uncommitted, that some party cannot decrypt, or whose shares
+/// do not match its commitment.
+pub fn import_checked<G: ProjectiveCurve, S: FieldShare<G::ScalarField>>(
+    contributions: &[Contribution],
+    party: usize,
+    sk: &SecretKey,
+) -> Result<Vec<MpcField<G::ScalarField, S>>, ContributionError> {
+    let mut commitments = Vec::new();
+    for c in contributions {
+        commitments.push(
+            c.commitment::<G>()?
+                .ok_or_else(|| ContributionError::Uncommitted(c.label.clone()))?,
+        );
+    }
+    let opened: Vec<_> = contributions
+        .iter()
+        .map(|c| c.open_with_blinding::<G::ScalarField>(party, sk))
+        .collect();
+    // A party that cannot decrypt still takes part in the broadcast, with no commitment, so that
+    // the others do not wait on it. Each also sends how many shares it has: they are only shares
+    // of the same values if all have as many.
+    let mine: Vec<Option<(u64, G::Affine)>> = opened
+        .iter()
+        .map(|o| {
+            let x = o.as_ref().ok()?;
+            Some((x.len() as u64, pedersen::<G>(x).into_affine()))
+        })
+        .collect();
+    let all = MpcMultiNet::broadcast(&mine);
+
+    let mut values = Vec::new();
+    for (j, (c, o)) in contributions.iter().zip(opened).enumerate() {
+        let shares = o?;
+        let inconsistent = || ContributionError::Inconsistent(c.label.clone());
+        let mut sum = G::zero();
+        for (p, theirs) in all.iter().enumerate() {
+            match theirs.get(j).copied().flatten() {
+                Some((len, ci)) if len == shares.len() as u64 => sum.add_assign_mixed(&ci),
+                Some(_) => return Err(inconsistent()),
+                None => return Err(ContributionError::PeerFailed(p, c.label.clone())),
+            }
+        }
+        if sum.into_affine() != commitments[j] {
+            return Err(inconsistent());
+        }
+        let (_blinding, shares) = shares.split_last().ok_or_else(inconsistent)?;
+        values.extend_from_slice(shares);
+    }
+    Ok(values.into_iter().map(MpcField::from_add_shared).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groth::prover::create_random_proof;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr, G1Projective};
+    use ark_groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use mpc_algebra::honest_but_curious::{MpcField as MFr, MpcPairingEngine as ME};
+    use mpc_algebra::AdditiveFieldShare;
+    use mpc_net::testing::run_parties;
+    use mpc_net::{MpcMultiNet, MpcNet};
+
+    /// The computing parties' keys, the same in every process.
+    fn secret_keys(n: usize) -> Vec<SecretKey> {
+        let rng = &mut StdRng::seed_from_u64(1);
+        (0..n).map(|_| SecretKey::generate(rng)).collect()
+    }
+
+    #[test]
+    fn parties_recover_the_values() {
+        let rng = &mut StdRng::seed_from_u64(2);
+        let sks = secret_keys(3);
+        let pks: Vec<PublicKey> = sks.iter().map(SecretKey::public_key).collect();
+        let values: Vec<Fr> = (0..5).map(|_| Fr::rand(rng)).collect();
+        let c = Contribution::seal("alice", &values, &pks, rng);
+        let c: Contribution = serde_json::from_str(&serde_json::to_string(&c).unwrap()).unwrap();
+        let shares: Vec<Vec<Fr>> = (0..3).map(|i| c.open(i, &sks[i]).unwrap()).collect();
+        let sums: Vec<Fr> = (0..5).map(|j| shares.iter().map(|s| s[j]).sum()).collect();
+        assert_eq!(sums, values);
+
+        // Another party's key, a relabeling, or a flipped bit is caught.
+        assert!(matches!(
+            c.open::<Fr>(0, &sks[1]),
+            Err(ContributionError::Tampered(0))
+        ));
+        let mut relabeled = c.clone();
+        relabeled.label = "mallory".to_owned();
+        assert!(relabeled.open::<Fr>(0, &sks[0]).is_err());
+        let mut flipped = c.clone();
+        flipped.shares[2].replace_range(0..1, if &c.shares[2][..1] == "0" { "1" } else { "0" });
+        assert!(flipped.open::<Fr>(2, &sks[2]).is_err());
+        assert!(matches!(
+            c.open::<Fr>(3, &sks[0]),
+            Err(ContributionError::NoShares(3))
+        ));
+
+        // Contributions from before commitments still parse, as uncommitted.
+        let old = r#"{"label":"alice","ephemeral":"00","shares":[]}"#;
+        assert_eq!(serde_json::from_str::<Contribution>(old).unwrap().commitment, None);
+    }
+
+    #[test]
+    fn hbc_checked_contributions() {
+        let rng = &mut StdRng::seed_from_u64(5);
+        let pks: Vec<PublicKey> = secret_keys(2).iter().map(SecretKey::public_key).collect();
+        let values = [Fr::from(2u8), Fr::from(3u8)];
+        let (good, blinding) =
+            Contribution::seal_committed::<G1Projective, _>("a", &values, &pks, rng);
+        let mut opening = values.to_vec();
+        opening.push(blinding);
+        assert_eq!(
+            good.commitment::<G1Projective>().unwrap(),
+            Some(pedersen::<G1Projective>(&opening).into_affine())
+        );
+        // Shares of other values, under the commitment to `values`.
+        let (mut bad, _) =
+            Contribution::seal_committed::<G1Projective, _>("b", &[Fr::from(4u8)], &pks, rng);
+        bad.commitment = good.commitment.clone();
+        let uncommitted = Contribution::seal("c", &values, &pks, rng);
+        run_parties("contribution::tests::hbc_checked_contributions", 2, || {
+            let party = MpcMultiNet::party_id();
+            let sk = &secret_keys(2)[party];
+            let check = |cs: &[Contribution]| {
+                import_checked::<G1Projective, AdditiveFieldShare<Fr>>(cs, party, sk)
+            };
+            let shares = check(std::slice::from_ref(&good)).unwrap();
+            let opened: Vec<Fr> = shares.into_iter().map(|s| s.reveal()).collect();
+            assert_eq!(opened, values);
+            assert!(matches!(
+                check(&[good.clone(), bad.clone()]),
+                Err(ContributionError::Inconsistent(l)) if l == "b"
+            ));
+            assert!(matches!(
+                check(std::slice::from_ref(&uncommitted)),
+                Err(ContributionError::Uncommitted(l)) if l == "c"
+            ));
+        });
+    }
+
+    #[test]
+    fn hbc_prove_from_contributions() {
+        // Made ahead of time, by two input parties that are not among the provers.
+        let rng = &mut StdRng::seed_from_u64(3);
+        let pks: Vec<PublicKey> = secret_keys(2).iter().map(SecretKey::public_key).collect();
+        let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+        let contributions = vec![
+            Contribution::seal("a", &[a], &pks, rng),
+            Contribution::seal("b", &[b], &pks, rng),
+        ];
+        run_parties(
+            "contribution::tests::hbc_prove_from_contributions",
+            2,
+            || {
+                let rng = &mut StdRng::seed_from_u64(4);
+                let party = MpcMultiNet::party_id();
+                let sk = &secret_keys(2)[party];
+                let witness: Vec<MFr<Fr>> =
+                    import::<Fr, AdditiveFieldShare<Fr>>(&contributions, party, sk).unwrap();
+                let params = generate_random_parameters::<Bls12_377, _, _>(
+                    MySillyCircuit { a: None, b: None },
+                    rng,
+                )
+                .unwrap();
+                let pvk = prepare_verifying_key(&params.vk);
+                let mpc_params = Reveal::from_public(params);
+                let circ = MySillyCircuit {
+                    a: Some(witness[0]),
+                    b: Some(witness[1]),
+                };
+                let proof = create_random_proof::<ME<Bls12_377>, _, _>(circ, &mpc_params, rng)
+                    .unwrap()
+                    .reveal();
+                assert!(verify_proof(&pvk, &proof, &[a * b]).unwrap());
+            },
+        );
+    }
+}
@@ -0,0 +1,198 @@
+//! Estimates of what a circuit costs to evaluate in MPC, broken down by gadget.
+//!
+//! In MPC, a constraint `a * b = c` costs communication only when both `a` and `b` depend on the
+//! (shared) witness: computing `c` is then a multiplication of shares, which opens two masked
+//! values. If either side is public (only involves instance variables), it is a local scaling.
+//!
+//! A [CostProfiler] wraps a constraint system in setup mode, so circuits can be profiled without
+//! an assignment. Synthesize into [CostProfiler::cs], naming the parts of interest with
+//! [CostProfiler::gadget], then read off a [CostReport].
+use ark_ff::Field;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef,
+    SynthesisError, SynthesisMode,
+};
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+
+/// The MPC cost of some constraints.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub constraints: usize,
+    /// Shared-by-shared multiplications.
+    pub mults: usize,
+    /// Field elements opened (two per multiplication).
+    pub openings: usize,
+}
+
+impl Cost {
+    fn of(m: &ConstraintMatrices<impl Field>, constraints: std::ops::Range<usize>) -> Self {
+        let shared = |row: &[(_, usize)]| row.iter().any(|(_, v)| *v >= m.num_instance_variables);
+        let mults = constraints
+            .clone()
+            .filter(|&i| shared(&m.a[i]) && shared(&m.b[i]))
+            .count();
+        Self {
+            constraints: constraints.len(),
+            mults,
+            openings: 2 * mults,
+        }
+    }
+}
+
+/// Costs of a whole circuit, and of each named gadget within it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CostReport {
+    pub total: Cost,
+    /// Gadgets, by `/`-separated path, in the order they were entered.
+    pub gadgets: Vec<(String, Cost)>,
+}
+
+impl Display for CostReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<40} {:>12} {:>12} {:>12}",
+            "gadget", "constraints", "mults", "openings"
+        )?;
+        let rows = self.gadgets.iter().map(|(n, c)| (n.as_str(), c));
+        for (name, c) in rows.chain(std::iter::once(("(total)", &self.total))) {
+            writeln!(
+                f,
+                "{:<40} {:>12} {:>12} {:>12}",
+                name, c.constraints, c.mults, c.openings
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A constraint system that records which constraints each gadget enforces.
+pub struct CostProfiler<F: Field> {
+    cs: ConstraintSystemRef<F>,
+    /// Open gadget names.
+    path: RefCell<Vec<String>>,
+    /// Each gadget's path, and the range of constraints it enforced.
+    gadgets: RefCell<Vec<(String, std::ops::Range<usize>)>>,
+}
+
+impl<F: Field> CostProfiler<F> {
+    pub fn new() -> Self {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        Self {
+            cs,
+            path: RefCell::new(Vec::new()),
+            gadgets: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The constraint system to synthesize into.
+    pub fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    /// Run `f`, attributing the constraints it enforces to a gadget called `name`.
+    ///
+    /// Gadgets may nest; a gadget's cost includes those of the gadgets within it.
+    pub fn gadget<T>(
+        &self,
+        name: &str,
+        f: impl FnOnce(ConstraintSystemRef<F>) -> Result<T, SynthesisError>,
+    ) -> Result<T, SynthesisError> {
+        self.path.borrow_mut().push(name.to_owned());
+        let path = self.path.borrow().join("/");
+        let slot = self.gadgets.borrow().len();
+        self.gadgets.borrow_mut().push((path, 0..0));
+        let start = self.cs.num_constraints();
+        let r = f(self.cs.clone());
+        self.gadgets.borrow_mut()[slot].1 = start..self.cs.num_constraints();
+        self.path.borrow_mut().pop();
+        r
+    }
+
+    /// Profile a whole circuit, as one gadget.
+    pub fn profile<C: ConstraintSynthesizer<F>>(
+        name: &str,
+        circuit: C,
+    ) -> Result<CostReport, SynthesisError> {
+        let p = Self::new();
+        p.gadget(name, |cs| circuit.generate_constraints(cs))?;
+        Ok(p.report())
+    }
+
+    pub fn report(self) -> CostReport {
+        self.cs.finalize();
+        let m = self.cs.to_matrices().unwrap();
+        CostReport {
+            total: Cost::of(&m, 0..m.num_constraints),
+            gadgets: self
+                .gadgets
+                .into_inner()
+                .into_iter()
+                .map(|(name, range)| (name, Cost::of(&m, range)))
+                .collect(),
+        }
+    }
+}
+
+impl<F: Field> Default for CostProfiler<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compose::{Closed, Fragment, Square, Sum, Wire};
+    use ark_bls12_377::Fr;
+
+    #[test]
+    fn counts_shared_products_per_gadget() {
+        let p = CostProfiler::<Fr>::new();
+        let cs = p.cs();
+        let x = Wire::witness(&cs, None).unwrap();
+        let pub_x = Wire::input(&cs, None).unwrap();
+        let squares = p
+            .gadget("squares", |cs| Square.synthesize(&cs, vec![x, pub_x]))
+            .unwrap();
+        p.gadget("sum", |cs| Sum.synthesize(&cs, squares)).unwrap();
+        let report = p.report();
+        // Squaring a witness needs a multiplication; squaring an input, or summing, does not.
+        assert_eq!(
+            report.gadgets,
+            vec![
+                (
+                    "squares".to_owned(),
+                    Cost {
+                        constraints: 2,
+                        mults: 1,
+                        openings: 2
+                    }
+                ),
+                (
+                    "sum".to_owned(),
+                    Cost {
+                        constraints: 1,
+                        mults: 0,
+                        openings: 0
+                    }
+                ),
+            ]
+        );
+        assert_eq!(report.total.mults, 1);
+    }
+
+    #[test]
+    fn whole_circuit() {
+        let circ = Closed::<_, Fr> {
+            fragment: Square,
+            inputs: vec![None; 3],
+        };
+        let report = CostProfiler::profile("squares", circ).unwrap();
+        // Three squarings, and three constraints exposing their outputs.
+        assert_eq!(report.total.constraints, 6);
+        assert_eq!(report.total.mults, 3);
+    }
+}
@@ -0,0 +1,161 @@
+//! Designated-verifier proofs: Groth16 proofs that only the verifier who set up the key can check.
+//!
+//! When only one known party needs convincing, it can run the setup itself ([setup]) and keep the
+//! trapdoor, `(alpha, beta, gamma, delta)`. The provers get a proving key without the verifying
+//! key's `gamma` parts, which the pairing check needs, and prove as for Groth16; so a proof is
+//! sound exactly as a Groth16 proof is, whatever the provers do. The verifier checks it with the
+//! trapdoor ([DvKey::verify]): `e(A, B) = e(alpha * beta * G + gamma * I + delta * C, H)` for the
+//! public inputs' combination `I`, which takes two pairings, rather than a pairing per term.
+//!
+//! A proof convinces no one else: with the trapdoor, the verifier can make a proof of any
+//! statement ([DvKey::simulate]), so a proof it shows proves nothing. A key may be used for any
+//! number of proofs, as long as the trapdoor stays secret.
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField, UniformRand, Zero};
+use ark_groth16::{Proof, ProvingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use ark_std::rand::Rng;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The designated verifier's secret key: the setup's trapdoor, its generators, and the bases of
+/// the public inputs.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct DvKey<E: PairingEngine> {
+    pub alpha: E::Fr,
+    pub beta: E::Fr,
+    pub gamma: E::Fr,
+    pub delta: E::Fr,
+    pub g1: E::G1Affine,
+    pub g2: E::G2Affine,
+    /// `(beta * u_i + alpha * v_i + w_i) / gamma * G`, for the constant one and each input.
+    pub gamma_abc_g1: Vec<E::G1Affine>,
+}
+
+/// Set up `circuit` for a designated verifier: the provers' proving key, and the verifier's key.
+pub fn setup<E, C, R>(circuit: C, rng: &mut R) -> Result<(ProvingKey<E>, DvKey<E>), SynthesisError>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+    R: Rng,
+{
+    let nonzero = |rng: &mut R| loop {
+        let x = E::Fr::rand(rng);
+        if !x.is_zero() {
+            return x;
+        }
+    };
+    let (alpha, beta, gamma, delta) = (nonzero(rng), nonzero(rng), nonzero(rng), nonzero(rng));
+    let mut pk =
+        ark_groth16::generate_parameters::<E, C, R>(circuit, alpha, beta, gamma, delta, rng)?;
+    let key = DvKey {
+        alpha,
+        beta,
+        gamma,
+        delta,
+        // The setup draws its own generators.
+        g1: pk
+            .vk
+            .alpha_g1
+            .mul(alpha.inverse().unwrap().into_repr())
+            .into_affine(),
+        g2: pk
+            .vk
+            .beta_g2
+            .mul(beta.inverse().unwrap().into_repr())
+            .into_affine(),
+        gamma_abc_g1: std::mem::take(&mut pk.vk.gamma_abc_g1),
+    };
+    pk.vk.gamma_g2 = E::G2Affine::zero();
+    Ok((pk, key))
+}
+
+impl<E: PairingEngine> DvKey<E> {
+    /// `gamma * I`, for the combination `I` of the public inputs' bases; `None` if there are not
+    /// as many inputs as bases.
+    fn inputs_term(&self, public_inputs: &[E::Fr]) -> Option<E::G1Projective> {
+        if public_inputs.len() + 1 != self.gamma_abc_g1.len() {
+            return None;
+        }
+        let mut acc = self.gamma_abc_g1[0].into_projective();
+        for (x, base) in public_inputs.iter().zip(&self.gamma_abc_g1[1..]) {
+            acc += &base.mul(x.into_repr());
+        }
+        Some(acc.mul(self.gamma.into_repr()))
+    }
+
+    /// Whether `proof` proves the statement with `public_inputs`.
+    pub fn verify(&self, proof: &Proof<E>, public_inputs: &[E::Fr]) -> bool {
+        let inputs = match self.inputs_term(public_inputs) {
+            Some(inputs) => inputs,
+            None => return false,
+        };
+        let rhs = self.g1.mul((self.alpha * self.beta).into_repr())
+            + inputs
+            + proof.c.mul(self.delta.into_repr());
+        E::product_of_pairings(&[
+            (proof.a.into(), proof.b.into()),
+            ((-rhs).into_affine().into(), self.g2.into()),
+        ])
+        .is_one()
+    }
+
+    /// A proof of the statement with `public_inputs`, which this key accepts, made without a
+    /// witness.
+    pub fn simulate<R: Rng>(&self, public_inputs: &[E::Fr], rng: &mut R) -> Option<Proof<E>> {
+        let inputs = self.inputs_term(public_inputs)?;
+        let (a, b) = (E::Fr::rand(rng), E::Fr::rand(rng));
+        let c = (self.g1.mul((a * b - self.alpha * self.beta).into_repr()) - inputs)
+            .mul(self.delta.inverse()?.into_repr());
+        Some(Proof {
+            a: self.g1.mul(a.into_repr()).into_affine(),
+            b: self.g2.mul(b.into_repr()).into_affine(),
+            c: c.into_affine(),
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.serialize(&mut writer)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::deserialize(&mut reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr};
+    use ark_groth16::create_random_proof;
+
+    #[test]
+    fn only_the_verifier_checks_proofs() {
+        let rng = &mut ark_std::test_rng();
+        let circ = |a: Option<Fr>, b: Option<Fr>| MySillyCircuit { a, b };
+        let (pk, key) = setup::<Bls12_377, _, _>(circ(None, None), rng).unwrap();
+        // The provers' key cannot check proofs.
+        assert!(pk.vk.gamma_abc_g1.is_empty());
+        assert!(pk.vk.gamma_g2.is_zero());
+
+        let (a, b) = (Fr::from(3u8), Fr::from(5u8));
+        let proof = create_random_proof(circ(Some(a), Some(b)), &pk, rng).unwrap();
+        assert!(key.verify(&proof, &[a * b]));
+        assert!(!key.verify(&proof, &[a]));
+        assert!(!key.verify(&proof, &[]));
+
+        // But the verifier can make proofs of false statements, so its proofs convince no one.
+        let fake = key.simulate(&[a], rng).unwrap();
+        assert!(key.verify(&fake, &[a]));
+
+        let mut bytes = Vec::new();
+        key.serialize(&mut bytes).unwrap();
+        assert_eq!(DvKey::deserialize(&bytes[..]).unwrap(), key);
+    }
+}
@@ -0,0 +1,512 @@
+//! A self-describing proof artifact.
+//!
+//! An [Envelope] carries a proof together with what another organization needs to check it: the
+//! public inputs, the curve and proof system, a hash of the circuit, how many parties produced
+//! it, and when. The metadata is JSON (via serde), so that any tool can read it; the proof and the
+//! inputs are [CanonicalSerialize] bytes, hex-encoded within it.
+//!
+//! A [KeyFile] carries a key in the same way, under the same curve, proof system and circuit
+//! hash, so that a key made for another version of a circuit is refused before proving with it.
+//!
+//! An envelope may also carry a [Provenance] block, recording how the proof was made: the
+//! software, a digest of the parameters the parties agreed on, who the parties were, and what
+//! proving took in time and communication. Nothing checks it, and
+//! [Envelope::strip_provenance] removes it for artifacts published without it.
+//!
+//! A [TestVector] is an envelope together with the verifying key, so that a verifier outside this
+//! repository (e.g., in another language) can check an MPC-produced proof with nothing else.
+use ark_ff::Field;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisMode};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use blake2::{Blake2s, Digest};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The envelope format this code writes, and the only one it reads.
+pub const ENVELOPE_VERSION: u32 = 1;
+
+/// What a proof is about, and where it comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Header {
+    pub curve: String,
+    /// The proof system, with any options that change the proof (e.g., Marlin's PC scheme).
+    pub proof_system: String,
+    /// See [circuit_hash].
+    pub circuit_hash: String,
+    pub n_parties: usize,
+    /// When proving started and finished, in seconds since the Unix epoch.
+    pub started_at: u64,
+    pub finished_at: u64,
+}
+
+/// A proof, its public inputs, and their [Header].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    #[serde(flatten)]
+    pub header: Header,
+    /// The serialized proof, in hex.
+    pub proof: String,
+    /// The serialized public inputs (a vector of field elements), in hex.
+    pub public_inputs: String,
+    /// Absent from envelopes written without it, or stripped of it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// How a proof was made, as the party that wrote its envelope saw it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance {
+    /// The crate and version that made the proof, e.g. `mpc-snarks 0.1.0`.
+    pub software: String,
+    /// See [params_digest].
+    pub params_digest: String,
+    /// The parties' addresses, in party order, from the hosts file (empty for a local proof).
+    pub parties: Vec<String>,
+    /// The MPC session's id, in hex (empty for a local proof).
+    pub session_id: String,
+    /// Each timed section so far, parents before their children.
+    pub phases: Vec<PhaseRecord>,
+    /// What this party sent and received, in all, and in how many rounds.
+    pub bytes_sent: usize,
+    pub bytes_recv: usize,
+    pub rounds: usize,
+}
+
+/// A timed section of proving, its path being the section names from the root, separated by
+/// `/`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PhaseRecord {
+    pub path: String,
+    pub millis: u64,
+    pub bytes: usize,
+    pub rounds: usize,
+}
+
+#[derive(Debug)]
+pub enum EnvelopeError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The proof or the inputs do not deserialize.
+    Encoding(SerializationError),
+    /// The envelope has another format version.
+    Version(u32),
+    /// The envelope is for another curve, proof system or circuit.
+    Mismatch {
+        field: &'static str,
+        expected: String,
+        found: String,
+    },
+}
+
+impl Display for EnvelopeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::Io(e) => write!(f, "{}", e),
+            EnvelopeError::Json(e) => write!(f, "malformed envelope: {}", e),
+            EnvelopeError::Encoding(e) => write!(f, "malformed proof or inputs: {}", e),
+            EnvelopeError::Version(v) => write!(
+                f,
+                "envelope version {} (only version {} is supported)",
+                v, ENVELOPE_VERSION
+            ),
+            EnvelopeError::Mismatch {
+                field,
+                expected,
+                found,
+            } => write!(f, "{} is {:?}, expected {:?}", field, found, expected),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
+impl From<std::io::Error> for EnvelopeError {
+    fn from(e: std::io::Error) -> Self {
+        EnvelopeError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for EnvelopeError {
+    fn from(e: serde_json::Error) -> Self {
+        EnvelopeError::Json(e)
+    }
+}
+
+impl From<SerializationError> for EnvelopeError {
+    fn from(e: SerializationError) -> Self {
+        EnvelopeError::Encoding(e)
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(s: &str) -> Result<Vec<u8>, SerializationError> {
+    if !s.len().is_multiple_of(2) {
+        return Err(SerializationError::InvalidData);
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| SerializationError::InvalidData))
+        .collect()
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// A digest (in hex) of the constraint matrices of `circuit`, which needs no assignment.
+///
+/// Parties that agree on it prove and verify the same relation.
+pub fn circuit_hash<F: Field, C: ConstraintSynthesizer<F>>(circuit: C) -> String {
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    circuit.generate_constraints(cs.clone()).unwrap();
+    cs.finalize();
+    let m = cs.to_matrices().unwrap();
+    let mut h = Blake2s::new();
+    for n in &[m.num_instance_variables, m.num_witness_variables, m.num_constraints] {
+        h.update((*n as u64).to_le_bytes());
+    }
+    let mut bytes = Vec::new();
+    for matrix in &[m.a, m.b, m.c] {
+        for row in matrix {
+            h.update((row.len() as u64).to_le_bytes());
+            for (coeff, var) in row {
+                bytes.clear();
+                coeff.serialize(&mut bytes).unwrap();
+                h.update(&bytes);
+                h.update((*var as u64).to_le_bytes());
+            }
+        }
+    }
+    to_hex(&h.finalize())
+}
+
+/// A digest (in hex) of the parameters that parties agree on before proving, by name and value,
+/// in order.
+pub fn params_digest(params: &[(&str, String)]) -> String {
+    let mut h = Blake2s::new();
+    for (name, value) in params {
+        for s in &[*name, &value[..]] {
+            h.update((s.len() as u64).to_le_bytes());
+            h.update(s.as_bytes());
+        }
+    }
+    to_hex(&h.finalize())
+}
+
+/// Check a curve, proof system and circuit hash against `expected`'s.
+fn check_fields(
+    expected: &Header,
+    curve: &str,
+    proof_system: &str,
+    circuit_hash: &str,
+) -> Result<(), EnvelopeError> {
+    let fields = [
+        ("curve", &expected.curve[..], curve),
+        ("proof system", &expected.proof_system[..], proof_system),
+        ("circuit hash", &expected.circuit_hash[..], circuit_hash),
+    ];
+    match fields.iter().find(|(_, e, f)| e != f) {
+        Some((field, e, f)) => Err(EnvelopeError::Mismatch {
+            field,
+            expected: e.to_string(),
+            found: f.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+impl Envelope {
+    pub fn seal<P: CanonicalSerialize, F: CanonicalSerialize>(
+        header: Header,
+        proof: &P,
+        public_inputs: &[F],
+    ) -> Result<Self, SerializationError> {
+        let mut proof_bytes = Vec::new();
+        proof.serialize(&mut proof_bytes)?;
+        let mut input_bytes = Vec::new();
+        public_inputs.serialize(&mut input_bytes)?;
+        Ok(Self {
+            version: ENVELOPE_VERSION,
+            header,
+            proof: to_hex(&proof_bytes),
+            public_inputs: to_hex(&input_bytes),
+            provenance: None,
+        })
+    }
+
+    pub fn with_provenance(self, provenance: Provenance) -> Self {
+        Self {
+            provenance: Some(provenance),
+            ..self
+        }
+    }
+
+    /// Remove the [Provenance] block, if any; the proof checks just as well without it.
+    pub fn strip_provenance(&mut self) -> Option<Provenance> {
+        self.provenance.take()
+    }
+
+    /// Check that this envelope is for the same curve, proof system and circuit as `expected`.
+    pub fn check(&self, expected: &Header) -> Result<(), EnvelopeError> {
+        if self.version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::Version(self.version));
+        }
+        check_fields(
+            expected,
+            &self.header.curve,
+            &self.header.proof_system,
+            &self.header.circuit_hash,
+        )
+    }
+
+    pub fn proof<P: CanonicalDeserialize>(&self) -> Result<P, EnvelopeError> {
+        Ok(P::deserialize(&from_hex(&self.proof)?[..])?)
+    }
+
+    pub fn public_inputs<F: CanonicalDeserialize>(&self) -> Result<Vec<F>, EnvelopeError> {
+        Ok(Vec::<F>::deserialize(&from_hex(&self.public_inputs)?[..])?)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Parse an envelope, of any version (see [Envelope::check]).
+    pub fn from_json(json: &str) -> Result<Self, EnvelopeError> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), EnvelopeError> {
+        Ok(std::fs::write(path, self.to_json())?)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, EnvelopeError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// A key (e.g., a Groth16 proving key), and the circuit it was set up for.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub curve: String,
+    pub proof_system: String,
+    /// See [circuit_hash], computed at setup.
+    pub circuit_hash: String,
+    /// The serialized key, in hex.
+    pub key: String,
+}
+
+impl KeyFile {
+    /// A key set up for the circuit that `header` describes (its other fields are not kept).
+    pub fn seal<K: CanonicalSerialize>(
+        header: &Header,
+        key: &K,
+    ) -> Result<Self, SerializationError> {
+        let mut bytes = Vec::new();
+        key.serialize(&mut bytes)?;
+        Ok(Self {
+            version: ENVELOPE_VERSION,
+            curve: header.curve.clone(),
+            proof_system: header.proof_system.clone(),
+            circuit_hash: header.circuit_hash.clone(),
+            key: to_hex(&bytes),
+        })
+    }
+
+    /// Check that this key is for the same curve, proof system and circuit as `expected`.
+    pub fn check(&self, expected: &Header) -> Result<(), EnvelopeError> {
+        if self.version != ENVELOPE_VERSION {
+            return Err(EnvelopeError::Version(self.version));
+        }
+        check_fields(
+            expected,
+            &self.curve,
+            &self.proof_system,
+            &self.circuit_hash,
+        )
+    }
+
+    pub fn key<K: CanonicalDeserialize>(&self) -> Result<K, EnvelopeError> {
+        Ok(K::deserialize(&from_hex(&self.key)?[..])?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), EnvelopeError> {
+        Ok(std::fs::write(
+            path,
+            serde_json::to_string_pretty(self).unwrap(),
+        )?)
+    }
+
+    /// Read a key file, of any version (see [KeyFile::check]).
+    pub fn load(path: &Path) -> Result<Self, EnvelopeError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+/// An [Envelope] and the verifying key to check it with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TestVector {
+    #[serde(flatten)]
+    pub envelope: Envelope,
+    /// The serialized verifying key, in hex.
+    pub vk: String,
+}
+
+impl TestVector {
+    pub fn seal<V: CanonicalSerialize>(
+        envelope: Envelope,
+        vk: &V,
+    ) -> Result<Self, SerializationError> {
+        let mut bytes = Vec::new();
+        vk.serialize(&mut bytes)?;
+        Ok(Self {
+            envelope,
+            vk: to_hex(&bytes),
+        })
+    }
+
+    pub fn vk<V: CanonicalDeserialize>(&self) -> Result<V, EnvelopeError> {
+        Ok(V::deserialize(&from_hex(&self.vk)?[..])?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), EnvelopeError> {
+        Ok(std::fs::write(
+            path,
+            serde_json::to_string_pretty(self).unwrap(),
+        )?)
+    }
+
+    /// Read a test vector, of any version (see [Envelope::check]).
+    pub fn load(path: &Path) -> Result<Self, EnvelopeError> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+
+    fn header(curve: &str) -> Header {
+        Header {
+            curve: curve.to_owned(),
+            proof_system: "groth16".to_owned(),
+            circuit_hash: "00".to_owned(),
+            n_parties: 2,
+            started_at: 1,
+            finished_at: 2,
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let inputs = vec![Fr::from(3u64), Fr::from(4u64)];
+        let env = Envelope::seal(header("bls12_377"), &vec![7u8, 8], &inputs).unwrap();
+        let read = Envelope::from_json(&env.to_json()).unwrap();
+        assert_eq!(read, env);
+        assert_eq!(read.proof::<Vec<u8>>().unwrap(), vec![7, 8]);
+        assert_eq!(read.public_inputs::<Fr>().unwrap(), inputs);
+        assert!(read.check(&header("bls12_377")).is_ok());
+    }
+
+    #[test]
+    fn test_vectors_carry_the_vk() {
+        let env = Envelope::seal(header("bls12_377"), &vec![7u8], &[Fr::from(3u64)]).unwrap();
+        let vector = TestVector::seal(env.clone(), &vec![5u8, 6]).unwrap();
+        let json = serde_json::to_string(&vector).unwrap();
+        // The envelope's fields are at the top level, so an envelope reader can read it too.
+        assert_eq!(Envelope::from_json(&json).unwrap(), env);
+        let read: TestVector = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, vector);
+        assert_eq!(read.vk::<Vec<u8>>().unwrap(), vec![5, 6]);
+    }
+
+    #[test]
+    fn provenance_is_optional() {
+        let env = Envelope::seal(header("bls12_377"), &vec![7u8], &[Fr::from(3u64)]).unwrap();
+        // Envelopes without it are read, and written, as before.
+        assert!(!env.to_json().contains("provenance"));
+        let params = vec![("curve", "bls12_377".to_owned()), ("reps", "1".to_owned())];
+        let provenance = Provenance {
+            software: "mpc-snarks 0.1.0".to_owned(),
+            params_digest: params_digest(&params),
+            parties: vec!["127.0.0.1:8000".to_owned(), "127.0.0.1:8001".to_owned()],
+            session_id: "00".to_owned(),
+            phases: vec![PhaseRecord {
+                path: "prove".to_owned(),
+                millis: 12,
+                bytes: 3400,
+                rounds: 56,
+            }],
+            bytes_sent: 1700,
+            bytes_recv: 1700,
+            rounds: 56,
+        };
+        let mut with = env.clone().with_provenance(provenance.clone());
+        let mut read = Envelope::from_json(&with.to_json()).unwrap();
+        assert_eq!(read, with);
+        assert!(read.check(&header("bls12_377")).is_ok());
+        assert_eq!(read.strip_provenance(), Some(provenance));
+        assert_eq!(read, env);
+        with.strip_provenance();
+        assert_eq!(with.to_json(), env.to_json());
+
+        // The digest is of names and values, not of their concatenation.
+        let shifted = vec![("curv", "ebls12_377".to_owned()), ("reps", "1".to_owned())];
+        assert_ne!(params_digest(&params), params_digest(&shifted));
+    }
+
+    #[test]
+    fn mismatches_are_rejected() {
+        let mut env = Envelope::seal(header("bls12_377"), &0u8, &Vec::<Fr>::new()).unwrap();
+        match env.check(&header("cp6_782")) {
+            Err(EnvelopeError::Mismatch { field, .. }) => assert_eq!(field, "curve"),
+            r => panic!("unexpected {:?}", r),
+        }
+        env.version += 1;
+        assert!(matches!(
+            env.check(&header("bls12_377")),
+            Err(EnvelopeError::Version(_))
+        ));
+    }
+
+    #[test]
+    fn key_files_name_their_circuit() {
+        use crate::silly::MySillyCircuit;
+        let silly = || MySillyCircuit::<Fr> { a: None, b: None };
+        let hash = circuit_hash(silly());
+        assert_eq!(hash, circuit_hash(silly()));
+        let squaring = crate::compose::Closed {
+            fragment: crate::compose::Square,
+            inputs: vec![None],
+        };
+        assert_ne!(hash, circuit_hash::<Fr, _>(squaring));
+
+        let mut expected = header("bls12_377");
+        expected.circuit_hash = hash;
+        let path = std::env::temp_dir().join(format!("key_file_{}.json", std::process::id()));
+        KeyFile::seal(&expected, &vec![1u8, 2, 3])
+            .unwrap()
+            .save(&path)
+            .unwrap();
+        let read = KeyFile::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(read.check(&expected).is_ok());
+        assert_eq!(read.key::<Vec<u8>>().unwrap(), vec![1, 2, 3]);
+        match read.check(&header("bls12_377")) {
+            Err(EnvelopeError::Mismatch { field, .. }) => assert_eq!(field, "circuit hash"),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,383 @@
+//! Circuits written as arithmetic expressions over named inputs.
+//!
+//! An [Expr] is built from [Expr::input]s and [Expr::constant]s with `+`, `-`, `*`, `/` and
+//! [Expr::select]. An [ExprCircuit] synthesizes some expressions as its public outputs: it
+//! evaluates each one as it goes, assigning the witness, and emits the matching constraints. So
+//! over an `MpcField`, the same circuit computes the witness from shared inputs (multiplying,
+//! dividing and selecting on shares, inside the prover), without a hand-written
+//! `generate_constraints`.
+//!
+//! Additions, subtractions and multiplications by constants are free; every other operation
+//! costs one witness and one constraint, and a selection one more, that its condition is a bit.
+use crate::compose::Wire;
+use ark_ff::Field;
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystemRef, LinearCombination, SynthesisError, Variable,
+    },
+};
+use std::collections::BTreeMap;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// An arithmetic expression over named inputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr<F: Field> {
+    Input(String),
+    Constant(F),
+    Add(Box<Expr<F>>, Box<Expr<F>>),
+    Sub(Box<Expr<F>>, Box<Expr<F>>),
+    Mul(Box<Expr<F>>, Box<Expr<F>>),
+    /// Constrained as `quotient * divisor = dividend`, which a zero divisor only satisfies with
+    /// a zero dividend, and then with any quotient.
+    Div(Box<Expr<F>>, Box<Expr<F>>),
+    /// `then` if the condition is one, `otherwise` if it is zero; it must be one or the other.
+    Select {
+        cond: Box<Expr<F>>,
+        then: Box<Expr<F>>,
+        otherwise: Box<Expr<F>>,
+    },
+}
+
+impl<F: Field> Expr<F> {
+    pub fn input(name: &str) -> Self {
+        Expr::Input(name.to_owned())
+    }
+
+    pub fn constant(x: F) -> Self {
+        Expr::Constant(x)
+    }
+
+    pub fn select(cond: Self, then: Self, otherwise: Self) -> Self {
+        Expr::Select {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            otherwise: Box::new(otherwise),
+        }
+    }
+
+    /// The names of the inputs that this expression reads.
+    pub fn inputs(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.visit_inputs(&mut names);
+        names
+    }
+
+    fn visit_inputs<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expr::Input(name) => names.push(name),
+            Expr::Constant(_) => {}
+            Expr::Add(a, b) | Expr::Sub(a, b) | Expr::Mul(a, b) | Expr::Div(a, b) => {
+                a.visit_inputs(names);
+                b.visit_inputs(names);
+            }
+            Expr::Select {
+                cond,
+                then,
+                otherwise,
+            } => {
+                cond.visit_inputs(names);
+                then.visit_inputs(names);
+                otherwise.visit_inputs(names);
+            }
+        }
+    }
+
+    /// Constrain this expression, over the input wires `inputs`.
+    fn synthesize(
+        &self,
+        cs: &ConstraintSystemRef<F>,
+        inputs: &BTreeMap<String, Wire<F>>,
+    ) -> Result<Term<F>, SynthesisError> {
+        Ok(match self {
+            Expr::Input(name) => {
+                let w = inputs[name];
+                Term {
+                    lc: lc!() + w.var,
+                    value: w.value,
+                    constant: None,
+                }
+            }
+            Expr::Constant(x) => Term {
+                lc: lc!() + (*x, Variable::One),
+                value: Some(*x),
+                constant: Some(*x),
+            },
+            Expr::Add(a, b) => {
+                let (a, b) = (a.synthesize(cs, inputs)?, b.synthesize(cs, inputs)?);
+                Term {
+                    lc: a.lc + b.lc,
+                    value: zip_with(a.value, b.value, |a, b| a + b),
+                    constant: zip_with(a.constant, b.constant, |a, b| a + b),
+                }
+            }
+            Expr::Sub(a, b) => {
+                let (a, b) = (a.synthesize(cs, inputs)?, b.synthesize(cs, inputs)?);
+                Term {
+                    lc: a.lc - b.lc,
+                    value: zip_with(a.value, b.value, |a, b| a - b),
+                    constant: zip_with(a.constant, b.constant, |a, b| a - b),
+                }
+            }
+            Expr::Mul(a, b) => {
+                let (a, b) = (a.synthesize(cs, inputs)?, b.synthesize(cs, inputs)?);
+                match (a.constant, b.constant) {
+                    (Some(x), _) => b.scale(x),
+                    (_, Some(y)) => a.scale(y),
+                    (None, None) => {
+                        let out = Wire::witness(cs, zip_with(a.value, b.value, |a, b| a * b))?;
+                        cs.enforce_constraint(a.lc, b.lc, lc!() + out.var)?;
+                        Term::wire(out)
+                    }
+                }
+            }
+            Expr::Div(a, b) => {
+                let (a, b) = (a.synthesize(cs, inputs)?, b.synthesize(cs, inputs)?);
+                let quotient = match (a.value, b.value) {
+                    (Some(a), Some(b)) => {
+                        Some(a * b.inverse().ok_or(SynthesisError::DivisionByZero)?)
+                    }
+                    _ => None,
+                };
+                let out = Wire::witness(cs, quotient)?;
+                cs.enforce_constraint(lc!() + out.var, b.lc, a.lc)?;
+                Term::wire(out)
+            }
+            Expr::Select {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let c = cond.synthesize(cs, inputs)?;
+                let (t, o) = (
+                    then.synthesize(cs, inputs)?,
+                    otherwise.synthesize(cs, inputs)?,
+                );
+                // c * (1 - c) = 0, and c * (t - o) = out - o.
+                cs.enforce_constraint(c.lc.clone(), lc!() + Variable::One - c.lc.clone(), lc!())?;
+                let value = match (c.value, t.value, o.value) {
+                    (Some(c), Some(t), Some(o)) => Some(o + c * (t - o)),
+                    _ => None,
+                };
+                let out = Wire::witness(cs, value)?;
+                cs.enforce_constraint(c.lc, t.lc - o.lc.clone(), lc!() + out.var - o.lc)?;
+                Term::wire(out)
+            }
+        })
+    }
+}
+
+/// A synthesized expression: a linear combination of wires, with its value if it is known, and
+/// also if it is a constant.
+struct Term<F: Field> {
+    lc: LinearCombination<F>,
+    value: Option<F>,
+    constant: Option<F>,
+}
+
+impl<F: Field> Term<F> {
+    fn wire(w: Wire<F>) -> Self {
+        Self {
+            lc: lc!() + w.var,
+            value: w.value,
+            constant: None,
+        }
+    }
+
+    fn scale(self, x: F) -> Self {
+        Self {
+            lc: self.lc * x,
+            value: self.value.map(|v| v * x),
+            constant: self.constant.map(|c| c * x),
+        }
+    }
+}
+
+fn zip_with<F: Field>(a: Option<F>, b: Option<F>, f: impl FnOnce(F, F) -> F) -> Option<F> {
+    Some(f(a?, b?))
+}
+
+impl<F: Field> Add for Expr<F> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Expr::Add(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F: Field> Sub for Expr<F> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Expr::Sub(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F: Field> Mul for Expr<F> {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Expr::Mul(Box::new(self), Box::new(other))
+    }
+}
+
+impl<F: Field> Div for Expr<F> {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        Expr::Div(Box::new(self), Box::new(other))
+    }
+}
+
+/// A circuit whose witnesses are named inputs, and whose public inputs are the values of some
+/// expressions over them, in order.
+#[derive(Clone, Debug)]
+pub struct ExprCircuit<F: Field> {
+    inputs: Vec<(String, Option<F>)>,
+    outputs: Vec<Expr<F>>,
+}
+
+impl<F: Field> ExprCircuit<F> {
+    pub fn new() -> Self {
+        Self {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Add a witness input, with its value; `None` during setup.
+    ///
+    /// Panics if the name is taken.
+    pub fn input(mut self, name: &str, value: Option<F>) -> Self {
+        assert!(
+            self.inputs.iter().all(|(n, _)| n != name),
+            "input {:?} is declared twice",
+            name
+        );
+        self.inputs.push((name.to_owned(), value));
+        self
+    }
+
+    /// Add an output, whose value becomes the next public input.
+    ///
+    /// Panics if the expression reads an input that has not been declared.
+    pub fn output(mut self, expr: Expr<F>) -> Self {
+        for name in expr.inputs() {
+            assert!(
+                self.inputs.iter().any(|(n, _)| n == name),
+                "unknown input {:?}",
+                name
+            );
+        }
+        self.outputs.push(expr);
+        self
+    }
+}
+
+impl<F: Field> Default for ExprCircuit<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for ExprCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|(name, v)| Ok((name, Wire::witness(&cs, v)?)))
+            .collect::<Result<BTreeMap<_, _>, SynthesisError>>()?;
+        for expr in &self.outputs {
+            let out = expr.synthesize(&cs, &inputs)?;
+            let public = Wire::input(&cs, out.value)?;
+            cs.enforce_constraint(out.lc, lc!() + Variable::One, lc!() + public.var)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outputs::RevealOutputs;
+    use crate::witness::first_violated;
+    use ark_ff::UniformRand;
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::MpcField as MF;
+    use mpc_algebra::Reveal;
+    use mpc_net::testing::run_parties;
+
+    type Fr = ark_bls12_377::Fr;
+
+    /// `(x * y + 3) / z` and `select(b, x, y) - 2 * x`, over inputs of type `F`.
+    fn circuit<F: Field>(vals: Option<[F; 4]>) -> ExprCircuit<F> {
+        let x = || Expr::input("x");
+        let y = || Expr::input("y");
+        let c = |n: u8| Expr::constant(F::from(n));
+        ExprCircuit::new()
+            .input("x", vals.map(|v| v[0]))
+            .input("y", vals.map(|v| v[1]))
+            .input("z", vals.map(|v| v[2]))
+            .input("b", vals.map(|v| v[3]))
+            .output((x() * y() + c(3)) / Expr::input("z"))
+            .output(Expr::select(Expr::input("b"), x(), y()) - c(2) * x())
+    }
+
+    fn synthesize(circ: ExprCircuit<Fr>) -> (bool, Vec<Fr>, usize) {
+        let cs = ConstraintSystem::new_ref();
+        circ.generate_constraints(cs.clone()).unwrap();
+        let outputs = cs.borrow().unwrap().instance_assignment[1..].to_vec();
+        (cs.is_satisfied().unwrap(), outputs, cs.num_constraints())
+    }
+
+    fn fr(n: u64) -> Fr {
+        Fr::from(n)
+    }
+
+    #[test]
+    fn evaluates_and_constrains() {
+        let (ok, outputs, n) = synthesize(circuit(Some([fr(4), fr(5), fr(23), fr(1)])));
+        assert!(ok);
+        assert_eq!(outputs, vec![fr(1), -fr(4)]);
+        // A product, a quotient, a selection (two), and an equality per output.
+        assert_eq!(n, 6);
+        let (ok, outputs, _) = synthesize(circuit(Some([fr(4), fr(5), fr(23), fr(0)])));
+        assert!(ok);
+        assert_eq!(outputs[1], -fr(3));
+    }
+
+    #[test]
+    fn selector_must_be_a_bit() {
+        let (ok, _, _) = synthesize(circuit(Some([fr(4), fr(5), fr(23), fr(2)])));
+        assert!(!ok);
+    }
+
+    #[test]
+    fn division_by_zero_fails() {
+        let cs = ConstraintSystem::new_ref();
+        let circ = circuit(Some([fr(4), fr(5), fr(0), fr(1)]));
+        assert_eq!(
+            circ.generate_constraints(cs),
+            Err(SynthesisError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown input \"w\"")]
+    fn unknown_inputs_panic() {
+        ExprCircuit::<Fr>::new()
+            .input("x", None)
+            .output(Expr::input("x") * Expr::input("w"));
+    }
+
+    #[test]
+    fn hbc_shared_witness() {
+        run_parties("expr::tests::hbc_shared_witness", 2, || {
+            let rng = &mut test_rng();
+            let vals = [Fr::rand(rng), Fr::rand(rng), Fr::rand(rng), fr(1)];
+            let shared = MF::<Fr>::king_share_batch(vals.to_vec(), rng);
+            let circ = || circuit(Some([shared[0], shared[1], shared[2], shared[3]]));
+            let coeff_rng = &mut test_rng();
+            assert_eq!(first_violated(circ(), || Fr::rand(coeff_rng)), None);
+            let (_, expected, _) = synthesize(circuit(Some(vals)));
+            assert_eq!(RevealOutputs::Instance.reveal(circ()).unwrap(), expected);
+        });
+    }
+}
@@ -4,9 +4,14 @@ use ark_groth16::{generate_random_parameters, prepare_verifying_key, verify_proo
 use ark_std::{test_rng, UniformRand};
 use mpc_algebra::*;
 use mpc_algebra::Reveal;
+use crate::outputs::RevealOutputs;
 
 pub mod prover;
 pub mod r1cs_to_qap;
+pub mod link;
+pub mod se;
+pub mod shard;
+pub mod zkey;
 
 pub fn mpc_test_prove_and_verify<E: PairingEngine, S: PairingShare<E>>(n_iters: usize) {
     let rng = &mut test_rng();
@@ -20,23 +25,141 @@ pub fn mpc_test_prove_and_verify<E: PairingEngine, S: PairingShare<E>>(n_iters:
     for _ in 0..n_iters {
         let a = MpcField::<E::Fr, S::FrShare>::rand(rng);
         let b = MpcField::<E::Fr, S::FrShare>::rand(rng);
-        let mut c = a;
-        c *= &b;
-
-        let mpc_proof = prover::create_random_proof::<MpcPairingEngine<E, S>, _, _>(
-            MySillyCircuit {
-                a: Some(a),
-                b: Some(b),
-            },
-            &mpc_params,
-            rng,
-        )
-        .unwrap();
+        let circ = MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let pub_c = RevealOutputs::Instance.reveal(circ.clone()).unwrap();
+
+        let mpc_proof =
+            prover::create_random_proof::<MpcPairingEngine<E, S>, _, _>(circ, &mpc_params, rng)
+                .unwrap();
         let proof = mpc_proof.reveal();
         let pub_a = a.reveal();
-        let pub_c = c.reveal();
 
-        assert!(verify_proof(&pvk, &proof, &[pub_c]).unwrap());
+        assert!(verify_proof(&pvk, &proof, &pub_c).unwrap());
         assert!(!verify_proof(&pvk, &proof, &[pub_a]).unwrap());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Bls12_377;
+    use ark_groth16::Proof;
+    use ark_relations::r1cs::Result as R1CSResult;
+    use mpc_algebra::share::group::GROUP_OPENINGS_METRIC;
+    use mpc_net::{assert_comm, metrics, testing::run_parties};
+
+    type Pk<S> = ProvingKey<MpcPairingEngine<Bls12_377, S>>;
+    type Fr<S> = MpcField<<Bls12_377 as PairingEngine>::Fr, <S as PairingShare<Bls12_377>>::FrShare>;
+    type Prove<S> =
+        fn(MySillyCircuit<Fr<S>>, &Pk<S>) -> R1CSResult<Proof<MpcPairingEngine<Bls12_377, S>>>;
+
+    /// Prove (and check) `MySillyCircuit` with `prove`, returning how many group elements it
+    /// opened, including the proof's.
+    fn group_openings<S: PairingShare<Bls12_377>>(prove: Prove<S>) -> u64 {
+        let rng = &mut test_rng();
+        let params = generate_random_parameters::<Bls12_377, _, _>(
+            MySillyCircuit { a: None, b: None },
+            rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key::<Bls12_377>(&params.vk);
+        let mpc_params = ProvingKey::from_public(params);
+        let a = Fr::<S>::rand(rng);
+        let b = Fr::<S>::rand(rng);
+        let before = metrics::get(GROUP_OPENINGS_METRIC);
+        let proof = prove(MySillyCircuit { a: Some(a), b: Some(b) }, &mpc_params)
+            .unwrap()
+            .reveal();
+        let opened = metrics::get(GROUP_OPENINGS_METRIC) - before;
+        assert!(verify_proof(&pvk, &proof, &[(a * b).reveal()]).unwrap());
+        opened
+    }
+
+    fn check_openings<S: PairingShare<Bls12_377>>() {
+        let no_openings = group_openings::<S>(|c, pk| {
+            prover::create_random_proof_no_group_openings(c, pk, &mut test_rng())
+        });
+        // Only A, B and C.
+        assert_eq!(no_openings, 3);
+        let default = group_openings::<S>(|c, pk| {
+            prover::create_random_proof(c, pk, &mut test_rng())
+        });
+        assert!(default > 3);
+    }
+
+    #[test]
+    fn spdz_opens_only_the_proof() {
+        run_parties("groth::tests::spdz_opens_only_the_proof", 2, || {
+            check_openings::<mpc_algebra::share::spdz::SpdzPairingShare<Bls12_377>>()
+        });
+    }
+
+    #[test]
+    fn hbc_opens_only_the_proof() {
+        run_parties("groth::tests::hbc_opens_only_the_proof", 2, || {
+            check_openings::<mpc_algebra::share::add::AdditivePairingShare<Bls12_377>>()
+        });
+    }
+
+    /// Prove (and open) `MySillyCircuit` with `prove`, checking that it takes exactly `rounds`
+    /// rounds and `bytes` bytes each way. A change in either is a change in the protocol.
+    fn check_proving_comm<S: PairingShare<Bls12_377>>(
+        prove: Prove<S>,
+        rounds: usize,
+        bytes: usize,
+    ) {
+        let rng = &mut test_rng();
+        let params = generate_random_parameters::<Bls12_377, _, _>(
+            MySillyCircuit { a: None, b: None },
+            rng,
+        )
+        .unwrap();
+        let pvk = prepare_verifying_key::<Bls12_377>(&params.vk);
+        let mpc_params: Pk<S> = ProvingKey::from_public(params);
+        let a = Fr::<S>::rand(rng);
+        let b = Fr::<S>::rand(rng);
+        let circuit = MySillyCircuit { a: Some(a), b: Some(b) };
+        let proof = assert_comm!(rounds == rounds, bytes_sent == bytes, bytes_recv == bytes, {
+            prove(circuit, &mpc_params).unwrap().reveal()
+        });
+        assert!(verify_proof(&pvk, &proof, &[(a * b).reveal()]).unwrap());
+    }
+
+    #[test]
+    fn spdz_proving_comm() {
+        run_parties("groth::tests::spdz_proving_comm", 2, || {
+            check_proving_comm::<mpc_algebra::share::spdz::SpdzPairingShare<Bls12_377>>(
+                |c, pk| prover::create_random_proof(c, pk, &mut test_rng()),
+                39,
+                2864,
+            )
+        });
+    }
+
+    #[test]
+    fn hbc_proving_comm() {
+        run_parties("groth::tests::hbc_proving_comm", 2, || {
+            check_proving_comm::<mpc_algebra::share::add::AdditivePairingShare<Bls12_377>>(
+                |c, pk| prover::create_random_proof(c, pk, &mut test_rng()),
+                13,
+                1008,
+            )
+        });
+    }
+
+    #[test]
+    fn hbc_no_group_openings_comm() {
+        run_parties("groth::tests::hbc_no_group_openings_comm", 2, || {
+            // One batch of products (`r` and `s` times each shared witness element, and `rs`),
+            // not a round per product.
+            check_proving_comm::<mpc_algebra::share::add::AdditivePairingShare<Bls12_377>>(
+                |c, pk| prover::create_random_proof_no_group_openings(c, pk, &mut test_rng()),
+                9,
+                1216,
+            )
+        });
+    }
+}
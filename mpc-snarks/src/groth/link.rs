@@ -0,0 +1,524 @@
+//! Commit-and-prove: Groth16 proofs about values committed to ahead of time.
+//!
+//! A data owner publishes a Pedersen commitment `com = sum_i w_i P_i + rho H` to some values `w`
+//! (with a [CommitmentKey]), and later hands shares of `w` and `rho` to the computing parties.
+//! Their collaborative proof then shows, besides the circuit's statement, that the circuit's first
+//! witness variables are the committed `w`: so anyone holding `com` knows which data the proof is
+//! about, without seeing it.
+//!
+//! This follows LegoGroth16 (LegoSNARK, [CFQ19](https://eprint.iacr.org/2019/142), appendix H).
+//! At setup, the L-query bases of the committed variables are moved from under `delta` to under
+//! `gamma` (the [LinkedVerifyingKey::commit_bases]), and the proof carries a commitment `D` to those
+//! variables with them, blinded by `nu (eta / gamma)`, which the verifier adds to its input
+//! accumulator. A sigma protocol, made non-interactive with Fiat-Shamir, then shows that `D` and
+//! `com` open to the same values ([LinkProof]). The values may also be split among several
+//! commitments ([create_random_proof_split]), e.g., to a step's input and to its output.
+//!
+//! Under MPC, everything but the opening of `D`, of the sigma protocol's first message, and of its
+//! responses is linear in the shares, and those openings are public in the proof anyway.
+use super::prover::create_proof_with_key;
+use super::r1cs_to_qap::R1CStoQAP;
+use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PrimeField, UniformRand, Zero};
+use ark_groth16::{generate_parameters, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, Result as R1CSResult, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::Rng;
+use blake2::{Blake2s, Digest};
+use mpc_algebra::{struct_reveal_simp_impl, MpcPairingEngine, PairingShare, Reveal};
+use mpc_trait::MpcWire;
+
+/// A Pedersen commitment key: a base per value, and one for the blinding.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct CommitmentKey<G: AffineCurve> {
+    pub bases: Vec<G>,
+    pub blinding: G,
+}
+
+impl<G: AffineCurve> CommitmentKey<G> {
+    /// A key for up to `n` values, from `rng`.
+    ///
+    /// Nobody may know discrete logarithms between the bases, so `rng` must be public coins (such
+    /// as a seed everyone knows), not the committer's.
+    pub fn setup<R: Rng>(n: usize, rng: &mut R) -> Self {
+        let mut points: Vec<G::Projective> = (0..=n).map(|_| G::Projective::rand(rng)).collect();
+        let blinding = points.pop().unwrap().into_affine();
+        Self {
+            bases: G::Projective::batch_normalization_into_affine(&points),
+            blinding,
+        }
+    }
+
+    /// The commitment to `values`, blinded by `blinding`.
+    pub fn commit(&self, values: &[G::ScalarField], blinding: G::ScalarField) -> G::Projective {
+        assert!(
+            values.len() <= self.bases.len(),
+            "too many values to commit to"
+        );
+        msm(&self.bases[..values.len()], values, self.blinding, blinding)
+    }
+}
+
+impl<G: AffineCurve + Reveal> Reveal for CommitmentKey<G>
+where
+    G::Base: AffineCurve,
+{
+    type Base = CommitmentKey<G::Base>;
+    struct_reveal_simp_impl!(CommitmentKey; bases, blinding);
+}
+
+/// What a verifier needs, besides the Groth16 verifying key, to check a [LinkedProof].
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkedVerifyingKey<E: PairingEngine> {
+    pub vk: VerifyingKey<E>,
+    /// The committed variables' L-query bases, under `gamma` rather than `delta`.
+    pub commit_bases: Vec<E::G1Affine>,
+    /// `eta / gamma` times the generator, which blinds `D`.
+    pub eta_gamma_g1: E::G1Affine,
+}
+
+/// A Groth16 proving key whose circuit's first [LinkedProvingKey::n_committed] witness variables
+/// are committed to.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkedProvingKey<E: PairingEngine> {
+    /// The proving key, with the committed variables' L-query bases zeroed: they are in `D`.
+    pub pk: ProvingKey<E>,
+    pub link_vk: LinkedVerifyingKey<E>,
+    /// `eta / delta` times the generator, which takes `D`'s blinding back out of C.
+    pub eta_delta_g1: E::G1Affine,
+}
+
+impl<E: PairingEngine> LinkedProvingKey<E> {
+    pub fn n_committed(&self) -> usize {
+        self.link_vk.commit_bases.len()
+    }
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for LinkedVerifyingKey<MpcPairingEngine<E, S>> {
+    type Base = LinkedVerifyingKey<E>;
+    struct_reveal_simp_impl!(LinkedVerifyingKey; vk, commit_bases, eta_gamma_g1);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for LinkedProvingKey<MpcPairingEngine<E, S>> {
+    type Base = LinkedProvingKey<E>;
+    struct_reveal_simp_impl!(LinkedProvingKey; pk, link_vk, eta_delta_g1);
+}
+
+/// A sigma protocol's transcript, showing that `D` (under the [LinkedVerifyingKey]) and the
+/// commitments (under the [CommitmentKey]) open to the same values.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkProof<E: PairingEngine> {
+    /// Commitments to the masks: under `D`'s key, and under the commitment key, one per commitment.
+    pub t_d: E::G1Affine,
+    pub t_coms: Vec<E::G1Affine>,
+    /// The masked values, and the masked blindings of `D` and of each commitment.
+    pub z: Vec<E::Fr>,
+    pub z_d: E::Fr,
+    pub z_coms: Vec<E::Fr>,
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for LinkProof<MpcPairingEngine<E, S>> {
+    type Base = LinkProof<E>;
+    struct_reveal_simp_impl!(LinkProof; t_d, t_coms, z, z_d, z_coms);
+}
+
+/// A Groth16 proof, the commitment `D` to its committed variables, and the link from `D` to the
+/// data's commitment.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct LinkedProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    pub d: E::G1Affine,
+    pub link: LinkProof<E>,
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for LinkedProof<MpcPairingEngine<E, S>> {
+    type Base = LinkedProof<E>;
+    struct_reveal_simp_impl!(LinkedProof; proof, d, link);
+}
+
+/// Make a proving key for `circuit`, whose first `n_committed` witness variables are committed to.
+pub fn setup<E, C, R>(
+    circuit: C,
+    n_committed: usize,
+    rng: &mut R,
+) -> R1CSResult<LinkedProvingKey<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+    R: Rng,
+{
+    let (alpha, beta, gamma, delta, eta) = (
+        E::Fr::rand(rng),
+        E::Fr::rand(rng),
+        E::Fr::rand(rng),
+        E::Fr::rand(rng),
+        E::Fr::rand(rng),
+    );
+    let mut pk = generate_parameters::<E, C, R>(circuit, alpha, beta, gamma, delta, rng)?;
+    if n_committed > pk.l_query.len() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let delta_inv = delta.inverse().unwrap();
+    let gamma_inv = gamma.inverse().unwrap();
+    // L_i = (beta u_i + alpha v_i + w_i) / delta; the committed variables' go under gamma.
+    let to_gamma = delta * gamma_inv;
+    let commit_bases: Vec<E::G1Projective> = pk.l_query[..n_committed]
+        .iter()
+        .map(|l| l.scalar_mul(to_gamma))
+        .collect();
+    for l in &mut pk.l_query[..n_committed] {
+        *l = E::G1Affine::zero();
+    }
+    // delta_g1 is delta times the generator (which the generator picks at random).
+    let eta_gamma_g1 = pk
+        .delta_g1
+        .scalar_mul(eta * delta_inv * gamma_inv)
+        .into_affine();
+    let eta_delta_g1 = pk
+        .delta_g1
+        .scalar_mul(eta * delta_inv * delta_inv)
+        .into_affine();
+    Ok(LinkedProvingKey {
+        link_vk: LinkedVerifyingKey {
+            vk: pk.vk.clone(),
+            commit_bases: E::G1Projective::batch_normalization_into_affine(&commit_bases),
+            eta_gamma_g1,
+        },
+        pk,
+        eta_delta_g1,
+    })
+}
+
+/// Prove `circuit`, whose first witness variables are `committed`, the values that `com` commits
+/// to under `ck` with blinding `opening`.
+///
+/// Under MPC, the parties open `D` and the [LinkProof] as they go; the rest of the proof stays
+/// shared until the caller reveals it.
+pub fn create_random_proof<E, C, R>(
+    circuit: C,
+    pk: &LinkedProvingKey<E>,
+    ck: &CommitmentKey<E::G1Affine>,
+    com: E::G1Affine,
+    committed: &[E::Fr],
+    opening: E::Fr,
+    rng: &mut R,
+) -> R1CSResult<LinkedProof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+    R: Rng,
+{
+    create_random_proof_split(circuit, pk, ck, &[com], &[committed], &[opening], rng)
+}
+
+/// Like [create_random_proof], but the committed variables are split among several commitments:
+/// `coms[j]` commits under `ck` (from its first base) to the `parts[j]`, with blinding
+/// `openings[j]`, and the parts in order are the circuit's first witness variables.
+pub fn create_random_proof_split<E, C, R>(
+    circuit: C,
+    pk: &LinkedProvingKey<E>,
+    ck: &CommitmentKey<E::G1Affine>,
+    coms: &[E::G1Affine],
+    parts: &[&[E::Fr]],
+    openings: &[E::Fr],
+    rng: &mut R,
+) -> R1CSResult<LinkedProof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+    R: Rng,
+{
+    assert!(
+        coms.len() == parts.len() && coms.len() == openings.len(),
+        "need a part and an opening per commitment"
+    );
+    let committed: Vec<E::Fr> = parts.iter().flat_map(|p| p.iter().cloned()).collect();
+    assert_eq!(
+        committed.len(),
+        pk.n_committed(),
+        "wrong number of committed values"
+    );
+    let vk = &pk.link_vk;
+    let (r, s) = (E::Fr::rand(rng), E::Fr::rand(rng));
+    let mut proof = create_proof_with_key::<E, C, R1CStoQAP, _>(circuit, &pk.pk, r, s)?;
+
+    let nu = E::Fr::rand(rng);
+    let mut d = msm(&vk.commit_bases, &committed, vk.eta_gamma_g1, nu).into_affine();
+    let mut c = proof.c.into_projective();
+    c -= &msm(&[], &[], pk.eta_delta_g1, nu);
+    proof.c = c.into_affine();
+    d.publicize();
+
+    let masks: Vec<E::Fr> = committed.iter().map(|_| E::Fr::rand(rng)).collect();
+    let mask_d = E::Fr::rand(rng);
+    let mask_coms: Vec<E::Fr> = coms.iter().map(|_| E::Fr::rand(rng)).collect();
+    let mut t_d = msm(&vk.commit_bases, &masks, vk.eta_gamma_g1, mask_d).into_affine();
+    t_d.publicize();
+    let mut start = 0;
+    let t_coms: Vec<E::G1Affine> = parts
+        .iter()
+        .zip(&mask_coms)
+        .map(|(part, &mask)| {
+            let mut t = ck
+                .commit(&masks[start..start + part.len()], mask)
+                .into_affine();
+            start += part.len();
+            t.publicize();
+            t
+        })
+        .collect();
+
+    let e = challenge::<E>(coms, d, t_d, &t_coms);
+    let respond = |mask: E::Fr, value: E::Fr| {
+        let mut z = mask + e * value;
+        z.publicize();
+        z
+    };
+    let link = LinkProof {
+        t_d,
+        t_coms,
+        z: masks
+            .iter()
+            .zip(&committed)
+            .map(|(&m, &w)| respond(m, w))
+            .collect(),
+        z_d: respond(mask_d, nu),
+        z_coms: mask_coms
+            .iter()
+            .zip(openings)
+            .map(|(&m, &rho)| respond(m, rho))
+            .collect(),
+    };
+    Ok(LinkedProof { proof, d, link })
+}
+
+/// Check `proof` of the circuit with `public_inputs`, about the values committed to in `com`.
+pub fn verify_proof<E: PairingEngine>(
+    pvk: &PreparedVerifyingKey<E>,
+    vk: &LinkedVerifyingKey<E>,
+    ck: &CommitmentKey<E::G1Affine>,
+    com: E::G1Affine,
+    public_inputs: &[E::Fr],
+    proof: &LinkedProof<E>,
+) -> R1CSResult<bool> {
+    let n = vk.commit_bases.len();
+    verify_proof_split(pvk, vk, ck, &[com], &[n], public_inputs, proof)
+}
+
+/// Check `proof` of the circuit with `public_inputs`, about the values committed to in `coms`, as
+/// split in [create_random_proof_split]: `coms[j]` commits to the next `lens[j]` of them.
+pub fn verify_proof_split<E: PairingEngine>(
+    pvk: &PreparedVerifyingKey<E>,
+    vk: &LinkedVerifyingKey<E>,
+    ck: &CommitmentKey<E::G1Affine>,
+    coms: &[E::G1Affine],
+    lens: &[usize],
+    public_inputs: &[E::Fr],
+    proof: &LinkedProof<E>,
+) -> R1CSResult<bool> {
+    Ok(link_holds(vk, ck, coms, lens, proof)?
+        && pairings_hold(pvk, &[proof], &[public_inputs], &[E::Fr::one()])?)
+}
+
+/// Whether `proof`'s [LinkProof] shows that its `D` opens to the values that `coms` commit to,
+/// split as in [verify_proof_split].
+pub fn link_holds<E: PairingEngine>(
+    vk: &LinkedVerifyingKey<E>,
+    ck: &CommitmentKey<E::G1Affine>,
+    coms: &[E::G1Affine],
+    lens: &[usize],
+    proof: &LinkedProof<E>,
+) -> R1CSResult<bool> {
+    if coms.len() != lens.len()
+        || lens.iter().sum::<usize>() != vk.commit_bases.len()
+        || lens.iter().any(|&l| l > ck.bases.len())
+    {
+        return Err(SynthesisError::MalformedVerifyingKey);
+    }
+    let LinkProof {
+        t_d,
+        t_coms,
+        z,
+        z_d,
+        z_coms,
+    } = &proof.link;
+    if z.len() != vk.commit_bases.len() || t_coms.len() != coms.len() || z_coms.len() != coms.len()
+    {
+        return Ok(false);
+    }
+    let e = challenge::<E>(coms, proof.d, *t_d, t_coms);
+    let d_opens = msm(&vk.commit_bases, z, vk.eta_gamma_g1, *z_d)
+        == proof.d.scalar_mul(e) + t_d.into_projective();
+    let mut start = 0;
+    let coms_open = coms.iter().zip(lens).enumerate().all(|(j, (com, &len))| {
+        let z = &z[start..start + len];
+        start += len;
+        ck.commit(z, z_coms[j]) == com.scalar_mul(e) + t_coms[j].into_projective()
+    });
+    Ok(d_opens && coms_open)
+}
+
+/// Whether the Groth16 equations of `proofs` hold, each with its public inputs.
+///
+/// They are checked together, as one product of pairings: of the equations' combination with
+/// `weights`, which the prover must not be able to predict (a hash of the proofs will do). With
+/// one proof and a weight of one, that is just its equation.
+pub fn pairings_hold<E: PairingEngine>(
+    pvk: &PreparedVerifyingKey<E>,
+    proofs: &[&LinkedProof<E>],
+    public_inputs: &[&[E::Fr]],
+    weights: &[E::Fr],
+) -> R1CSResult<bool> {
+    let gamma_abc = &pvk.vk.gamma_abc_g1;
+    assert!(
+        proofs.len() == public_inputs.len() && proofs.len() == weights.len(),
+        "need public inputs and a weight per proof"
+    );
+    let mut g_ic = E::G1Projective::zero();
+    let mut c = E::G1Projective::zero();
+    let mut weight = E::Fr::zero();
+    let mut pairs = Vec::with_capacity(proofs.len() + 2);
+    for ((proof, inputs), &w) in proofs.iter().zip(public_inputs).zip(weights) {
+        if inputs.len() + 1 != gamma_abc.len() {
+            return Err(SynthesisError::MalformedVerifyingKey);
+        }
+        let mut ic = msm(&gamma_abc[1..], inputs, gamma_abc[0], E::Fr::one());
+        ic.add_assign_mixed(&proof.d);
+        ic *= w;
+        g_ic += &ic;
+        c += &proof.proof.c.scalar_mul(w);
+        pairs.push((
+            proof.proof.a.scalar_mul(w).into_affine().into(),
+            proof.proof.b.into(),
+        ));
+        weight += &w;
+    }
+    pairs.push((g_ic.into_affine().into(), pvk.gamma_g2_neg_pc.clone()));
+    pairs.push((c.into_affine().into(), pvk.delta_g2_neg_pc.clone()));
+    Ok(E::product_of_pairings(&pairs) == pvk.alpha_g1_beta_g2.pow(weight.into_repr()))
+}
+
+/// `sum_i scalars_i bases_i + blinding_scalar blinding`.
+fn msm<G: AffineCurve>(
+    bases: &[G],
+    scalars: &[G::ScalarField],
+    blinding: G,
+    blinding_scalar: G::ScalarField,
+) -> G::Projective {
+    let mut acc = G::multi_scalar_mul(&[blinding], &[blinding_scalar]);
+    if !scalars.is_empty() {
+        acc += &G::multi_scalar_mul(bases, scalars);
+    }
+    acc
+}
+
+/// The sigma protocol's challenge: 128 bits of a hash of its statement (the commitments and `D`)
+/// and first message.
+fn challenge<E: PairingEngine>(
+    coms: &[E::G1Affine],
+    d: E::G1Affine,
+    t_d: E::G1Affine,
+    t_coms: &[E::G1Affine],
+) -> E::Fr {
+    let mut bytes = b"groth16 commit-and-prove link".to_vec();
+    for p in coms.iter().chain(&[d, t_d]).chain(t_coms) {
+        p.serialize(&mut bytes).unwrap();
+    }
+    let mut e = [0u8; 16];
+    e.copy_from_slice(&Blake2s::digest(&bytes)[..16]);
+    E::Fr::from(u128::from_le_bytes(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr, G1Affine};
+    use ark_groth16::prepare_verifying_key;
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::{MpcField, MpcG1Affine, MpcPairingEngine as ME};
+    use mpc_net::{testing::run_parties, MpcMultiNet, MpcNet};
+
+    type Keys = (LinkedProvingKey<Bls12_377>, CommitmentKey<G1Affine>);
+
+    /// Keys for `MySillyCircuit`, committing to its `a`.
+    fn keys() -> Keys {
+        let rng = &mut test_rng();
+        let pk = setup(MySillyCircuit { a: None, b: None }, 1, rng).unwrap();
+        (pk, CommitmentKey::setup(4, rng))
+    }
+
+    fn verify(keys: &Keys, com: G1Affine, c: Fr, proof: &LinkedProof<Bls12_377>) -> bool {
+        let pvk = prepare_verifying_key(&keys.0.link_vk.vk);
+        verify_proof(&pvk, &keys.0.link_vk, &keys.1, com, &[c], proof).unwrap()
+    }
+
+    #[test]
+    fn proves_about_the_committed_values() {
+        let rng = &mut test_rng();
+        let keys = keys();
+        let (a, b, rho) = (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+        let com = keys.1.commit(&[a], rho).into_affine();
+        let circuit = MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let proof =
+            create_random_proof(circuit.clone(), &keys.0, &keys.1, com, &[a], rho, rng).unwrap();
+        assert!(verify(&keys, com, a * b, &proof));
+        assert!(!verify(&keys, com, a * b + Fr::one(), &proof));
+
+        // The same proof says nothing about other data.
+        let other = keys.1.commit(&[a + Fr::one()], rho).into_affine();
+        assert!(!verify(&keys, other, a * b, &proof));
+        // Nor can a prover claim its witness is other data: D is checked by the pairing too.
+        let lie = a + Fr::one();
+        let lying_com = keys.1.commit(&[lie], rho).into_affine();
+        let lying =
+            create_random_proof(circuit, &keys.0, &keys.1, lying_com, &[lie], rho, rng).unwrap();
+        assert!(!verify(&keys, lying_com, a * b, &lying));
+    }
+
+    #[test]
+    fn hbc_proves_about_the_committed_values() {
+        run_parties(
+            "groth::link::tests::hbc_proves_about_the_committed_values",
+            2,
+            || {
+                let rng = &mut test_rng();
+                let keys = keys();
+                // The owner commits, then hands the parties shares of the value and blinding.
+                let (a, rho) = (Fr::rand(rng), Fr::rand(rng));
+                let com = keys.1.commit(&[a], rho).into_affine();
+                let (a_sh, rho_sh) = if MpcMultiNet::am_king() {
+                    (MpcField::from_add_shared(a), MpcField::from_add_shared(rho))
+                } else {
+                    (
+                        MpcField::from_add_shared(Fr::zero()),
+                        MpcField::from_add_shared(Fr::zero()),
+                    )
+                };
+                let b = MpcField::<Fr>::rand(rng);
+                let c = (a_sh * b).reveal();
+                let pk: LinkedProvingKey<ME<Bls12_377>> = Reveal::from_public(keys.0.clone());
+                let ck: CommitmentKey<MpcG1Affine<Bls12_377>> = Reveal::from_public(keys.1.clone());
+                let circuit = MySillyCircuit {
+                    a: Some(a_sh),
+                    b: Some(b),
+                };
+                let proof = create_random_proof(
+                    circuit,
+                    &pk,
+                    &ck,
+                    Reveal::from_public(com),
+                    &[a_sh],
+                    rho_sh,
+                    rng,
+                )
+                .unwrap()
+                .reveal();
+                assert!(verify(&keys, com, c, &proof));
+            },
+        );
+    }
+}
@@ -1,15 +1,17 @@
 #![allow(dead_code)]
 use ark_ec::{AffineCurve, PairingEngine, ProjectiveCurve};
-use ark_ff::{Field, UniformRand, Zero};
-use super::r1cs_to_qap::R1CStoQAP;
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use super::r1cs_to_qap::{R1CStoQAP, Reduction};
 use ark_groth16::{Proof, ProvingKey, VerifyingKey};
 use ark_poly::GeneralEvaluationDomain;
 use ark_relations::r1cs::{
-    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, Result as R1CSResult,
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, OptimizationGoal,
+    Result as R1CSResult,
 };
 use ark_std::rand::Rng;
 use ark_std::{end_timer, start_timer, vec::Vec};
 use log::debug;
+use mpc_trait::MpcWire;
 
 // Changelog:
 // 1. Specialized to Bls12_377 (our MPC lifting machinery cannot be written fully generically b/c
@@ -45,6 +47,25 @@ where
     create_proof::<E, C>(circuit, pk, r, s)
 }
 
+/// Like [create_random_proof], but for a proving key made for the reduction `Q`, such as one
+/// from a circom ceremony (see [super::zkey]).
+#[inline]
+pub fn create_random_proof_with_reduction<E, C, R, Q>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    rng: &mut R,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
+    R: Rng,
+    Q: Reduction,
+{
+    let r = <E as PairingEngine>::Fr::rand(rng);
+    let s = <E as PairingEngine>::Fr::rand(rng);
+    create_proof_with_reduction::<E, C, Q>(circuit, pk, r, s)
+}
+
 /// Create a Groth16 proof that is *not* zero-knowledge.
 #[inline]
 pub fn create_proof_no_zk<E, C>(circuit: C, pk: &ProvingKey<E>) -> R1CSResult<Proof<E>>
@@ -74,41 +95,120 @@ where
     //E::Fr: BatchProd,
     C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
 {
-    debug!("r: {}", r);
-    debug!("s: {}", s);
-    type D<F> = GeneralEvaluationDomain<F>;
+    create_proof_with_reduction::<E, C, R1CStoQAP>(circuit, pk, r, s)
+}
 
-    let prover_time = start_timer!(|| "Groth16::Prover");
-    let cs = ConstraintSystem::new_ref();
+/// Like [create_proof], but for a proving key made for the reduction `Q`.
+pub fn create_proof_with_reduction<E, C, Q>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    r: <E as PairingEngine>::Fr,
+    s: <E as PairingEngine>::Fr,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
+    Q: Reduction,
+{
+    create_proof_with_key::<E, C, Q, _>(circuit, pk, r, s)
+}
 
-    // Set the optimization goal
-    cs.set_optimization_goal(OptimizationGoal::Constraints);
+/// A proving key's queries in G1, each of which the prover takes one MSM over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum G1Query {
+    A,
+    BG1,
+    H,
+    L,
+}
 
-    // Synthesize the circuit.
-    let synthesis_time = start_timer!(|| "Constraint synthesis");
-    circuit.generate_constraints(cs.clone())?;
-    //debug_assert!(cs.is_satisfied().unwrap());
-    end_timer!(synthesis_time);
+/// What the prover needs of a proving key: its fixed elements, and MSMs over its queries.
+///
+/// A [ProvingKey] holds its queries whole; a [super::shard::ShardedProvingKey] holds a slice of
+/// each, and takes MSMs over them together with the other parties.
+pub trait ProverKey<E: PairingEngine> {
+    fn vk(&self) -> &VerifyingKey<E>;
+    fn beta_g1(&self) -> E::G1Affine;
+    fn delta_g1(&self) -> E::G1Affine;
+    /// The first base of `query` (the constant's, in the A and B queries).
+    fn g1_head(&self, query: G1Query) -> E::G1Affine;
+    /// The MSM of `query`'s bases from index `from` on with `scalars`.
+    fn g1_msm(&self, query: G1Query, from: usize, scalars: &[E::Fr]) -> E::G1Projective;
+    /// The first base of the B query in G2.
+    fn g2_head(&self) -> E::G2Affine;
+    /// The MSM of the G2 B query's bases from index `from` on with `scalars`.
+    fn g2_msm(&self, from: usize, scalars: &[E::Fr]) -> E::G2Projective;
+}
 
-    let lc_time = start_timer!(|| "Inlining LCs");
-    cs.finalize();
-    end_timer!(lc_time);
+impl<E: PairingEngine> ProverKey<E> for ProvingKey<E> {
+    fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+    fn beta_g1(&self) -> E::G1Affine {
+        self.beta_g1
+    }
+    fn delta_g1(&self) -> E::G1Affine {
+        self.delta_g1
+    }
+    fn g1_head(&self, query: G1Query) -> E::G1Affine {
+        g1_query(self, query)[0]
+    }
+    fn g1_msm(&self, query: G1Query, from: usize, scalars: &[E::Fr]) -> E::G1Projective {
+        let bases = &g1_query(self, query)[from..];
+        let t = start_timer!(|| format!("MSM size {} {}", bases.len(), scalars.len()));
+        let acc = <E::G1Affine as AffineCurve>::multi_scalar_mul(bases, scalars);
+        end_timer!(t);
+        acc
+    }
+    fn g2_head(&self) -> E::G2Affine {
+        self.b_g2_query[0]
+    }
+    fn g2_msm(&self, from: usize, scalars: &[E::Fr]) -> E::G2Projective {
+        let bases = &self.b_g2_query[from..];
+        let t = start_timer!(|| format!("MSM size {} {}", bases.len(), scalars.len()));
+        let acc = <E::G2Affine as AffineCurve>::multi_scalar_mul(bases, scalars);
+        end_timer!(t);
+        acc
+    }
+}
 
-    let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
-    let h = R1CStoQAP::witness_map::<<E as PairingEngine>::Fr, D<<E as PairingEngine>::Fr>>(
-        cs.clone(),
-    )?;
-    end_timer!(witness_map_time);
+fn g1_query<E: PairingEngine>(pk: &ProvingKey<E>, query: G1Query) -> &[E::G1Affine] {
+    match query {
+        G1Query::A => &pk.a_query,
+        G1Query::BG1 => &pk.b_g1_query,
+        G1Query::H => &pk.h_query,
+        G1Query::L => &pk.l_query,
+    }
+}
+
+/// Like [create_proof_with_reduction], for any [ProverKey].
+pub fn create_proof_with_key<E, C, Q, K>(
+    circuit: C,
+    pk: &K,
+    r: <E as PairingEngine>::Fr,
+    s: <E as PairingEngine>::Fr,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
+    Q: Reduction,
+    K: ProverKey<E>,
+{
+    debug!("r: {}", r);
+    debug!("s: {}", s);
+
+    let prover_time = start_timer!(|| "Groth16::Prover");
+    let (cs, h) = synthesize::<<E as PairingEngine>::Fr, C, Q>(circuit)?;
     let prover_crypto_time = start_timer!(|| "crypto");
     let c_acc_time = start_timer!(|| "Compute C");
-    let h_acc = <<E as PairingEngine>::G1Affine as AffineCurve>::multi_scalar_mul(&pk.h_query, &h);
+    let h_acc = pk.g1_msm(G1Query::H, 0, &h);
     debug!("h_acc: {}", h_acc);
     // Compute C
     let prover = cs.borrow().unwrap();
-    let l_aux_acc = <<E as PairingEngine>::G1Affine as AffineCurve>::multi_scalar_mul(&pk.l_query, &prover.witness_assignment);
+    let l_aux_acc = pk.g1_msm(G1Query::L, 0, &prover.witness_assignment);
 
     let r_s_delta_g1 = pk
-        .delta_g1
+        .delta_g1()
         .into_projective()
         .scalar_mul(&r)
         .scalar_mul(&s);
@@ -122,14 +222,19 @@ where
 
     // Compute A
     let a_acc_time = start_timer!(|| "Compute A");
-    let r_g1 = pk.delta_g1.scalar_mul(r);
+    let r_g1 = pk.delta_g1().scalar_mul(r);
     debug!("r_g1: {}", r_g1);
     // debug!("Assignment:");
     // for (i, a) in assignment.iter().enumerate() {
     //     debug!("  a[{}]: {}", i, a);
     // }
 
-    let g_a = calculate_coeff(r_g1, &pk.a_query, pk.vk.alpha_g1, &assignment);
+    let g_a = coeff(
+        r_g1,
+        pk.g1_head(G1Query::A),
+        pk.g1_msm(G1Query::A, 1, &assignment),
+        pk.vk().alpha_g1,
+    );
     debug!("g_a: {}", g_a);
 
     let s_g_a = g_a.scalar_mul(&s);
@@ -139,8 +244,13 @@ where
     // Compute B in G1 if needed
 //    let g1_b = if !r.is_zero() {
         let b_g1_acc_time = start_timer!(|| "Compute B in G1");
-        let s_g1 = pk.delta_g1.scalar_mul(s);
-        let g1_b = calculate_coeff(s_g1, &pk.b_g1_query, pk.beta_g1, &assignment);
+        let s_g1 = pk.delta_g1().scalar_mul(s);
+        let g1_b = coeff(
+            s_g1,
+            pk.g1_head(G1Query::BG1),
+            pk.g1_msm(G1Query::BG1, 1, &assignment),
+            pk.beta_g1(),
+        );
 
         end_timer!(b_g1_acc_time);
 //
@@ -151,8 +261,8 @@ where
 
     // Compute B in G2
     let b_g2_acc_time = start_timer!(|| "Compute B in G2");
-    let s_g2 = pk.vk.delta_g2.scalar_mul(s);
-    let g2_b = calculate_coeff(s_g2, &pk.b_g2_query, pk.vk.beta_g2, &assignment);
+    let s_g2 = pk.vk().delta_g2.scalar_mul(s);
+    let g2_b = coeff(s_g2, pk.g2_head(), pk.g2_msm(1, &assignment), pk.vk().beta_g2);
     let r_g1_b = g1_b.scalar_mul(&r);
     debug!("r_g1_b: {}", r_g1_b);
     drop(assignment);
@@ -177,6 +287,179 @@ where
     })
 }
 
+/// Synthesize `circuit`, and compute its QAP witness `h` with the reduction `Q`.
+fn synthesize<F, C, Q>(circuit: C) -> R1CSResult<(ConstraintSystemRef<F>, Vec<F>)>
+where
+    F: PrimeField,
+    C: ConstraintSynthesizer<F>,
+    Q: Reduction,
+{
+    type D<F> = GeneralEvaluationDomain<F>;
+    let cs = ConstraintSystem::new_ref();
+
+    // Set the optimization goal
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+
+    // Synthesize the circuit.
+    let synthesis_time = start_timer!(|| "Constraint synthesis");
+    circuit.generate_constraints(cs.clone())?;
+    //debug_assert!(cs.is_satisfied().unwrap());
+    end_timer!(synthesis_time);
+
+    let lc_time = start_timer!(|| "Inlining LCs");
+    cs.finalize();
+    end_timer!(lc_time);
+
+    let witness_map_time = start_timer!(|| "R1CS to QAP witness map");
+    let h = Q::witness_map::<F, D<F>>(cs.clone())?;
+    end_timer!(witness_map_time);
+    Ok((cs, h))
+}
+
+/// Like [create_random_proof], but with [create_proof_no_group_openings].
+#[inline]
+pub fn create_random_proof_no_group_openings<E, C, R>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    rng: &mut R,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
+    R: Rng,
+{
+    let t = start_timer!(|| "zk sampling");
+    let r = <E as PairingEngine>::Fr::rand(rng);
+    let s = <E as PairingEngine>::Fr::rand(rng);
+    end_timer!(t);
+
+    create_proof_no_group_openings::<E, C>(circuit, pk, r, s)
+}
+
+/// Create a Groth16 proof using randomness `r` and `s`, without opening any group element.
+///
+/// Under MPC, [create_proof] multiplies shared group elements (A, and B in G1) by the shared `r`
+/// and `s`, and each such product opens a masked group element; if the masks are biased, these
+/// openings leak the witness. Here, `r` and `s` are multiplied into the assignment instead, as
+/// field elements, so that every group element is an MSM of public bases: A, B and C stay shared
+/// until the caller reveals the proof. This costs one batch of field products (two per
+/// shared assignment element), and needs a public proving key.
+pub fn create_proof_no_group_openings<E, C>(
+    circuit: C,
+    pk: &ProvingKey<E>,
+    r: <E as PairingEngine>::Fr,
+    s: <E as PairingEngine>::Fr,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<<E as PairingEngine>::Fr>,
+{
+    assert!(
+        !pk.delta_g1.is_shared(),
+        "Proving without group openings needs a public proving key"
+    );
+    let prover_time = start_timer!(|| "Groth16::Prover (no group openings)");
+    let (cs, h) = synthesize::<<E as PairingEngine>::Fr, C, R1CStoQAP>(circuit)?;
+    let prover = cs.borrow().unwrap();
+    let n_public = prover.instance_assignment.len() - 1;
+    let assignment: Vec<<E as PairingEngine>::Fr> = prover.instance_assignment[1..]
+        .iter()
+        .chain(prover.witness_assignment.iter())
+        .cloned()
+        .collect();
+    drop(prover);
+    drop(cs);
+
+    let products_time = start_timer!(|| "Randomize assignment");
+    let (s_assignment, r_assignment, rs) = randomize_assignment(&assignment, r, s);
+    end_timer!(products_time);
+
+    let prover_crypto_time = start_timer!(|| "crypto");
+    // A = alpha + sum_i a_i A_i + r delta
+    let a_time = start_timer!(|| "Compute A");
+    let g_a = calculate_coeff(pk.delta_g1.scalar_mul(r), &pk.a_query, pk.vk.alpha_g1, &assignment);
+    end_timer!(a_time);
+
+    // B = beta + sum_i a_i B_i + s delta
+    let b_time = start_timer!(|| "Compute B");
+    let g2_b = calculate_coeff(
+        pk.vk.delta_g2.scalar_mul(s),
+        &pk.b_g2_query,
+        pk.vk.beta_g2,
+        &assignment,
+    );
+    end_timer!(b_time);
+
+    // C = sA + rB - rs delta + L + H
+    //   = s (alpha + sum_i a_i A_i) + r (beta + sum_i a_i B_i) + rs delta + L + H
+    let c_time = start_timer!(|| "Compute C");
+    let mut g_c = scaled_coeff(s, &pk.a_query, pk.vk.alpha_g1, &s_assignment);
+    g_c += &scaled_coeff(r, &pk.b_g1_query, pk.beta_g1, &r_assignment);
+    g_c += &pk.delta_g1.scalar_mul(rs);
+    g_c += &<<E as PairingEngine>::G1Affine as AffineCurve>::multi_scalar_mul(
+        &pk.l_query,
+        &assignment[n_public..],
+    );
+    g_c += &<<E as PairingEngine>::G1Affine as AffineCurve>::multi_scalar_mul(&pk.h_query, &h);
+    end_timer!(c_time);
+    end_timer!(prover_crypto_time);
+
+    end_timer!(prover_time);
+
+    Ok(Proof {
+        a: g_a.into_affine(),
+        b: g2_b.into_affine(),
+        c: g_c.into_affine(),
+    })
+}
+
+/// [calculate_coeff] scaled by `x`, given the assignment already scaled by `x`.
+fn scaled_coeff<G: AffineCurve>(
+    x: G::ScalarField,
+    query: &[G],
+    vk_param: G,
+    scaled_assignment: &[G::ScalarField],
+) -> G::Projective {
+    let mut res = query[0].into_projective();
+    res.add_assign_mixed(&vk_param);
+    res *= x;
+    res += &G::multi_scalar_mul(&query[1..], scaled_assignment);
+    res
+}
+
+/// `s a_i` and `r a_i` for each element `a_i` of `assignment`, and `rs`.
+///
+/// Shared products are computed in one batch; products with a public factor are local.
+fn randomize_assignment<F: Field>(assignment: &[F], r: F, s: F) -> (Vec<F>, Vec<F>, F) {
+    let shared: Vec<usize> = (0..assignment.len())
+        .filter(|&i| assignment[i].is_shared())
+        .collect();
+    let mut lhs: Vec<F> = shared.iter().map(|&i| assignment[i]).collect();
+    lhs.extend(shared.iter().map(|&i| assignment[i]));
+    lhs.push(r);
+    let mut rhs = vec![s; shared.len()];
+    rhs.extend(vec![r; shared.len()]);
+    rhs.push(s);
+    F::batch_product_in_place(&mut lhs, &rhs);
+
+    let rs = lhs.pop().unwrap();
+    let (s_shared, r_shared) = lhs.split_at(shared.len());
+    let scaled = |x: F, products: &[F]| {
+        let mut products = products.iter();
+        assignment
+            .iter()
+            .map(|a| {
+                if a.is_shared() {
+                    *products.next().unwrap()
+                } else {
+                    x * a
+                }
+            })
+            .collect::<Vec<F>>()
+    };
+    (scaled(s, s_shared), scaled(r, r_shared), rs)
+}
+
 /// Given a Groth16 proof, returns a fresh proof of the same statement. For a proof π of a
 /// statement S, the output of the non-deterministic procedure `rerandomize_proof(π)` is
 /// statistically indistinguishable from a fresh honest proof of S. For more info, see theorem 3 of
@@ -219,12 +502,21 @@ fn calculate_coeff<G: AffineCurve>(
     vk_param: G,
     assignment: &[G::ScalarField],
 ) -> G::Projective where {
-    let el = query[0];
     let t = start_timer!(|| format!("MSM size {} {}", query.len() - 1, assignment.len()));
     let acc = G::multi_scalar_mul(&query[1..], assignment);
     end_timer!(t);
+    coeff(initial, query[0], acc, vk_param)
+}
+
+/// `initial + head + acc + vk_param`: a proof element, from its query's head and MSM.
+fn coeff<G: AffineCurve>(
+    initial: G::Projective,
+    head: G,
+    acc: G::Projective,
+    vk_param: G,
+) -> G::Projective {
     let mut res = initial;
-    res.add_assign_mixed(&el);
+    res.add_assign_mixed(&head);
     res += &acc;
     res.add_assign_mixed(&vk_param);
 
@@ -1,3 +1,4 @@
+#![allow(dead_code)]
 use ark_ff::{One, PrimeField, Zero};
 use ark_poly::EvaluationDomain;
 use ark_std::{cfg_iter, cfg_iter_mut, vec, start_timer, end_timer};
@@ -40,47 +41,25 @@ where
     return res;
 }
 
+/// A reduction from R1CS to QAP, as seen by the prover: how it computes the coefficients `h`
+/// that multiply the proving key's `h_query`. This must be the reduction that the key was made
+/// for.
+pub trait Reduction {
+    fn witness_map<F: PrimeField, D: EvaluationDomain<F>>(
+        prover: ConstraintSystemRef<F>,
+    ) -> R1CSResult<Vec<F>>;
+}
+
+/// The reduction of libsnark (and arkworks' generator): `h` is the coefficients of
+/// `(a * b - c) / z`, where `z` vanishes on the domain.
 pub struct R1CStoQAP;
 
-impl R1CStoQAP {
+impl Reduction for R1CStoQAP {
     #[inline]
-    pub fn witness_map<F: PrimeField, D: EvaluationDomain<F>>(
+    fn witness_map<F: PrimeField, D: EvaluationDomain<F>>(
         prover: ConstraintSystemRef<F>,
     ) -> R1CSResult<Vec<F>> {
-        let matrices = prover.to_matrices().unwrap();
-        let zero = F::zero();
-        let num_inputs = prover.num_instance_variables();
-        let num_constraints = prover.num_constraints();
-        let cs = prover.borrow().unwrap();
-        let prover = cs.deref();
-
-        let full_assignment = [
-            prover.instance_assignment.as_slice(),
-            prover.witness_assignment.as_slice(),
-        ]
-        .concat();
-
-        let domain =
-            D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
-        let domain_size = domain.size();
-
-        let mut a = vec![zero; domain_size];
-        let mut b = vec![zero; domain_size];
-
-        cfg_iter_mut!(a[..num_constraints])
-            .zip(cfg_iter_mut!(b[..num_constraints]))
-            .zip(cfg_iter!(&matrices.a))
-            .zip(cfg_iter!(&matrices.b))
-            .for_each(|(((a, b), at_i), bt_i)| {
-                *a = evaluate_constraint(&at_i, &full_assignment);
-                *b = evaluate_constraint(&bt_i, &full_assignment);
-            });
-
-        {
-            let start = num_constraints;
-            let end = start + num_inputs;
-            a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
-        }
+        let (domain, mut a, mut b, mut c) = evaluate_constraints::<F, D>(prover)?;
 
         domain.ifft_in_place(&mut a);
         domain.ifft_in_place(&mut b);
@@ -92,13 +71,6 @@ impl R1CStoQAP {
         F::batch_product_in_place(&mut ab, &b);
         end_timer!(batch_product_timer);
 
-        let mut c = vec![zero; domain_size];
-        cfg_iter_mut!(c[..prover.num_constraints])
-            .enumerate()
-            .for_each(|(i, c)| {
-                *c = evaluate_constraint(&matrices.c[i], &full_assignment);
-            });
-
         domain.ifft_in_place(&mut c);
         domain.coset_fft_in_place(&mut c);
 
@@ -112,3 +84,88 @@ impl R1CStoQAP {
         Ok(ab)
     }
 }
+
+/// The reduction of circom and snarkjs: `h` is the evaluations of `a * b - c` on the odd powers
+/// of a root of unity of twice the domain's size. There, `z` is the constant -2, so snarkjs folds
+/// the division by it into `h_query`.
+pub struct CircomReduction;
+
+impl Reduction for CircomReduction {
+    #[inline]
+    fn witness_map<F: PrimeField, D: EvaluationDomain<F>>(
+        prover: ConstraintSystemRef<F>,
+    ) -> R1CSResult<Vec<F>> {
+        let (domain, mut a, mut b, mut c) = evaluate_constraints::<F, D>(prover)?;
+        let root = D::new(2 * domain.size())
+            .ok_or(SynthesisError::PolynomialDegreeTooLarge)?
+            .element(1);
+
+        for evals in [&mut a, &mut b, &mut c].iter_mut() {
+            domain.ifft_in_place(evals);
+            D::distribute_powers(evals, root);
+            domain.fft_in_place(evals);
+        }
+
+        let batch_product_timer = start_timer!(|| "batch product");
+        F::batch_product_in_place(&mut a, &b);
+        end_timer!(batch_product_timer);
+
+        for (ab_i, c_i) in a.iter_mut().zip(c) {
+            *ab_i -= &c_i;
+        }
+
+        Ok(a)
+    }
+}
+
+/// The evaluations of `a`, `b` and `c` on the QAP's domain (which is also returned): the
+/// constraints' inner products with the assignment, and then a row in `a` for each input.
+fn evaluate_constraints<F: PrimeField, D: EvaluationDomain<F>>(
+    prover: ConstraintSystemRef<F>,
+) -> R1CSResult<(D, Vec<F>, Vec<F>, Vec<F>)> {
+    let matrices = prover.to_matrices().unwrap();
+    let zero = F::zero();
+    let num_inputs = prover.num_instance_variables();
+    let num_constraints = prover.num_constraints();
+    let cs = prover.borrow().unwrap();
+    let prover = cs.deref();
+
+    let full_assignment = [
+        prover.instance_assignment.as_slice(),
+        prover.witness_assignment.as_slice(),
+    ]
+    .concat();
+
+    let domain =
+        D::new(num_constraints + num_inputs).ok_or(SynthesisError::PolynomialDegreeTooLarge)?;
+    let domain_size = domain.size();
+
+    // The constraint inner products accumulate in `F::Acc`, so that (for shared values) the
+    // public terms are added in once per row rather than once per term.
+    let mut a = vec![zero; domain_size];
+    let mut b = vec![zero; domain_size];
+
+    cfg_iter_mut!(a[..num_constraints])
+        .zip(cfg_iter_mut!(b[..num_constraints]))
+        .zip(cfg_iter!(&matrices.a))
+        .zip(cfg_iter!(&matrices.b))
+        .for_each(|(((a, b), at_i), bt_i)| {
+            *a = evaluate_constraint::<_, _, F::Acc>(&at_i, &full_assignment).into();
+            *b = evaluate_constraint::<_, _, F::Acc>(&bt_i, &full_assignment).into();
+        });
+
+    {
+        let start = num_constraints;
+        let end = start + num_inputs;
+        a[start..end].clone_from_slice(&full_assignment[..num_inputs]);
+    }
+
+    let mut c = vec![zero; domain_size];
+    cfg_iter_mut!(c[..num_constraints])
+        .enumerate()
+        .for_each(|(i, c)| {
+            *c = evaluate_constraint::<_, _, F::Acc>(&matrices.c[i], &full_assignment).into();
+        });
+
+    Ok((domain, a, b, c))
+}
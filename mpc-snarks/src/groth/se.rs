@@ -0,0 +1,227 @@
+//! Simulation-extractable Groth16, by binding each proof to a one-time signature key.
+//!
+//! Groth16 proofs can be re-randomized: anyone who sees one can make another, different proof of
+//! the same statement. Where a proof is meant to be someone's, e.g. a bid that one of the provers
+//! might copy and resubmit as its own, it must not be malleable. The standard transformation
+//! makes it so: the prover draws a fresh key of a one-time signature scheme, proves the statement
+//! extended with a digest of the verifying key (as one more public input, [SeCircuit]), and signs
+//! the proof and the statement with the key ([seal]). A verifier checks the signature, and the
+//! proof against the digest of the key that the signature is under ([verify]). Changing the proof
+//! invalidates the signature; signing it under another key needs a proof for another digest,
+//! which Groth16's (weak) simulation-extractability rules out without a witness.
+//!
+//! The signature scheme is Lamport's, over Blake2s: it needs no more than a hash, and keys are
+//! cheap to draw (if 16 KiB each). In MPC, one party (the one that receives the proof) holds the
+//! signing key; it can sign any proof, but no one outside the provers can.
+use ark_ec::PairingEngine;
+use ark_ff::{Field, PrimeField};
+use ark_groth16::{PreparedVerifyingKey, Proof};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::{CryptoRng, Rng};
+use blake2::{Blake2s, Digest};
+
+/// Bits in the digest that a signature signs.
+const BITS: usize = 256;
+
+fn hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut h = Blake2s::new();
+    for p in parts {
+        h.update(p);
+    }
+    h.finalize().into()
+}
+
+/// The digest of `msg` that is signed, bit by bit.
+fn message_bits(msg: &[u8]) -> impl Iterator<Item = bool> {
+    let digest = hash(&[b"mpc-snarks se message", msg]);
+    (0..BITS).map(move |i| digest[i / 8] >> (i % 8) & 1 == 1)
+}
+
+/// A one-time signing key: two secrets per bit of the digest, of which a signature reveals one.
+pub struct OtsKey {
+    secrets: Vec<[u8; 32]>,
+}
+
+/// The hashes of an [OtsKey]'s secrets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OtsVk {
+    hashes: Vec<[u8; 32]>,
+}
+
+impl OtsKey {
+    /// A fresh key, which must be drawn from randomness that no one else knows.
+    pub fn generate<R: Rng + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            secrets: (0..2 * BITS).map(|_| rng.gen()).collect(),
+        }
+    }
+
+    pub fn verifying_key(&self) -> OtsVk {
+        OtsVk {
+            hashes: self.secrets.iter().map(|s| hash(&[s])).collect(),
+        }
+    }
+
+    /// Sign `msg`. This reveals half the key, so it is consumed: a second signature would reveal
+    /// enough of the rest to forge others.
+    pub fn sign(self, msg: &[u8]) -> Vec<u8> {
+        message_bits(msg)
+            .enumerate()
+            .flat_map(|(i, bit)| self.secrets[2 * i + bit as usize])
+            .collect()
+    }
+}
+
+impl OtsVk {
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 2 * BITS * 32 {
+            return None;
+        }
+        let hashes = bytes
+            .chunks(32)
+            .map(|c| {
+                let mut h = [0; 32];
+                h.copy_from_slice(c);
+                h
+            })
+            .collect();
+        Some(Self { hashes })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.hashes.concat()
+    }
+
+    /// Whether `signature` is this key's on `msg`.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> bool {
+        signature.len() == BITS * 32
+            && message_bits(msg)
+                .zip(signature.chunks(32))
+                .enumerate()
+                .all(|(i, (bit, secret))| hash(&[secret]) == self.hashes[2 * i + bit as usize])
+    }
+
+    /// The digest of this key that proofs are bound to, as the last public input.
+    pub fn tag<F: PrimeField>(&self) -> F {
+        F::from_le_bytes_mod_order(&hash(&[b"mpc-snarks se vk", &self.to_bytes()]))
+    }
+}
+
+/// A circuit, with a one-time verifying key's [tag](OtsVk::tag) as one more public input, after
+/// its own. The tag is in no constraint: Groth16 binds each public input to the proof anyway.
+#[derive(Clone)]
+pub struct SeCircuit<C, F> {
+    pub inner: C,
+    pub tag: Option<F>,
+}
+
+impl<F: Field, C: ConstraintSynthesizer<F>> ConstraintSynthesizer<F> for SeCircuit<C, F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.inner.generate_constraints(cs.clone())?;
+        let tag = self.tag;
+        cs.new_input_variable(|| tag.ok_or(SynthesisError::AssignmentMissing))?;
+        Ok(())
+    }
+}
+
+/// A Groth16 proof of the statement extended with the tag of `ots_vk`, signed under it.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct SeProof<E: PairingEngine> {
+    pub proof: Proof<E>,
+    /// See [OtsVk::to_bytes].
+    pub ots_vk: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// What the signature signs: the proof, and the public inputs (without the tag).
+fn signed<E: PairingEngine>(proof: &Proof<E>, public_inputs: &[E::Fr]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize(&mut bytes).unwrap();
+    public_inputs.serialize(&mut bytes).unwrap();
+    bytes
+}
+
+/// The public inputs that `proof` proves, with the tag of `vk`.
+pub fn statement<F: PrimeField>(public_inputs: &[F], vk: &OtsVk) -> Vec<F> {
+    let mut inputs = public_inputs.to_vec();
+    inputs.push(vk.tag());
+    inputs
+}
+
+/// Sign `proof`, of `public_inputs` extended with `key`'s tag, with `key`.
+pub fn seal<E: PairingEngine>(proof: Proof<E>, key: OtsKey, public_inputs: &[E::Fr]) -> SeProof<E> {
+    let msg = signed(&proof, public_inputs);
+    let ots_vk = key.verifying_key().to_bytes();
+    SeProof {
+        proof,
+        ots_vk,
+        signature: key.sign(&msg),
+    }
+}
+
+/// Whether `proof` is signed, and proves `public_inputs` extended with its key's tag.
+pub fn verify<E: PairingEngine>(
+    pvk: &PreparedVerifyingKey<E>,
+    proof: &SeProof<E>,
+    public_inputs: &[E::Fr],
+) -> Result<bool, SynthesisError> {
+    let vk = match OtsVk::from_bytes(&proof.ots_vk) {
+        Some(vk) => vk,
+        None => return Ok(false),
+    };
+    if !vk.verify(&signed(&proof.proof, public_inputs), &proof.signature) {
+        return Ok(false);
+    }
+    ark_groth16::verify_proof(pvk, &proof.proof, &statement(public_inputs, &vk))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_groth16::{create_random_proof, generate_random_parameters, prepare_verifying_key};
+    use ark_std::test_rng;
+
+    #[test]
+    fn signed_proofs_are_not_malleable() {
+        let rng = &mut test_rng();
+        let circ = |a: Option<Fr>, b: Option<Fr>, tag| SeCircuit {
+            inner: MySillyCircuit { a, b },
+            tag,
+        };
+        let params =
+            generate_random_parameters::<Bls12_377, _, _>(circ(None, None, None), rng).unwrap();
+        let pvk = prepare_verifying_key(&params.vk);
+
+        let (a, b) = (Fr::from(3u8), Fr::from(5u8));
+        let public_inputs = vec![a * b];
+        let key = OtsKey::generate(rng);
+        let vk = key.verifying_key();
+        let proof =
+            create_random_proof(circ(Some(a), Some(b), Some(vk.tag())), &params, rng).unwrap();
+        let sealed = seal(proof, key, &public_inputs);
+        assert!(verify(&pvk, &sealed, &public_inputs).unwrap());
+        assert!(!verify(&pvk, &sealed, &[a]).unwrap());
+
+        // A re-randomized (or otherwise changed) proof is no longer signed.
+        let mut mauled = sealed.clone();
+        mauled.proof.a = mauled.proof.a.into_projective().double().into_affine();
+        assert!(!verify(&pvk, &mauled, &public_inputs).unwrap());
+        // Nor is the proof under a fresh key, whose tag it does not prove.
+        let other = OtsKey::generate(rng);
+        let msg = signed(&sealed.proof, &public_inputs);
+        let resigned = SeProof {
+            proof: sealed.proof.clone(),
+            ots_vk: other.verifying_key().to_bytes(),
+            signature: other.sign(&msg),
+        };
+        assert!(!verify(&pvk, &resigned, &public_inputs).unwrap());
+
+        let mut bytes = Vec::new();
+        sealed.serialize(&mut bytes).unwrap();
+        assert_eq!(SeProof::deserialize(&bytes[..]).unwrap(), sealed);
+    }
+}
@@ -0,0 +1,268 @@
+//! Groth16 proving keys split across the parties, each storing only its own slice.
+//!
+//! A proving key is almost all bases of MSMs (its queries), and an MSM splits along its bases:
+//! the sum of MSMs over consecutive slices is the MSM over the whole. A [ShardedProvingKey] holds
+//! one party's slice of every query (party `i` of `n` holds the `i`-th of `n` equal slices), so
+//! each party stores `1/n` of the key.
+//!
+//! The scalars are shared, so every party must still multiply its shares by every base. For each
+//! slice, its owner streams the slice's bases to the others [CHUNK] at a time; every party takes
+//! the MSM of each chunk with its shares, and adds it into its share of the whole MSM (a
+//! group-share addition, which is local). So no party holds more than its slice and one chunk of
+//! the others' at once, at the cost of sending its slice once per MSM (five per proof).
+use super::prover::{create_proof_with_key, G1Query, ProverKey};
+use super::r1cs_to_qap::R1CStoQAP;
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{UniformRand, Zero};
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::{ConstraintSynthesizer, Result as R1CSResult};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError, Write};
+use ark_std::rand::Rng;
+use mpc_algebra::{struct_reveal_simp_impl, MpcPairingEngine, PairingShare, Reveal};
+use mpc_net::{MpcMultiNet, MpcNet};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// How many bases a slice's owner sends at a time.
+pub const CHUNK: usize = 1 << 14;
+
+/// The `party`-th of `n_parties` slices of `0..len`.
+fn slice(len: usize, party: usize, n_parties: usize) -> Range<usize> {
+    len * party / n_parties..len * (party + 1) / n_parties
+}
+
+/// One party's slice of a query, and the query's first base, which every party keeps.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct QueryShard<G: AffineCurve> {
+    /// The whole query's length.
+    pub len: usize,
+    pub head: G,
+    /// Where this party's slice starts in the query.
+    pub start: usize,
+    pub bases: Vec<G>,
+}
+
+impl<G: AffineCurve> QueryShard<G> {
+    fn new(query: &[G], party: usize, n_parties: usize) -> Self {
+        let range = slice(query.len(), party, n_parties);
+        Self {
+            len: query.len(),
+            head: query[0],
+            start: range.start,
+            bases: query[range].to_vec(),
+        }
+    }
+
+    /// This party's share of the MSM of the query's bases from `from` on with `scalars`.
+    ///
+    /// Every party must call this at the same point, with the same `from` and as many scalars.
+    fn msm(&self, from: usize, scalars: &[G::ScalarField]) -> G::Projective {
+        self.msm_in_chunks(from, scalars, CHUNK)
+    }
+
+    fn msm_in_chunks(
+        &self,
+        from: usize,
+        scalars: &[G::ScalarField],
+        chunk: usize,
+    ) -> G::Projective {
+        let (me, n_parties) = (MpcMultiNet::party_id(), MpcMultiNet::n_parties());
+        let end = self.len.min(from + scalars.len());
+        let mut acc = G::Projective::zero();
+        for owner in 0..n_parties {
+            let owned = slice(self.len, owner, n_parties);
+            let (lo, hi) = (owned.start.max(from), owned.end.min(end));
+            for start in (lo..hi).step_by(chunk) {
+                let stop = hi.min(start + chunk);
+                let bases = if owner == me {
+                    let mine = &self.bases[start - self.start..stop - self.start];
+                    let mut bytes = Vec::new();
+                    mine.serialize(&mut bytes).unwrap();
+                    MpcMultiNet::recv_bytes_from(owner, Some(vec![bytes; n_parties]));
+                    Cow::Borrowed(mine)
+                } else {
+                    let bytes = MpcMultiNet::recv_bytes_from(owner, None);
+                    Cow::Owned(Vec::<G>::deserialize(&bytes[..]).unwrap())
+                };
+                acc += &G::multi_scalar_mul(&bases, &scalars[start - from..stop - from]);
+            }
+        }
+        acc
+    }
+}
+
+impl<G: AffineCurve + Reveal> Reveal for QueryShard<G>
+where
+    G::Base: AffineCurve,
+{
+    type Base = QueryShard<G::Base>;
+    struct_reveal_simp_impl!(QueryShard; len, head, start, bases);
+}
+
+/// One party's share of a Groth16 [ProvingKey]: its slice of each query, and the rest whole.
+#[derive(Clone, Debug, PartialEq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct ShardedProvingKey<E: PairingEngine> {
+    pub vk: VerifyingKey<E>,
+    pub beta_g1: E::G1Affine,
+    pub delta_g1: E::G1Affine,
+    pub a_query: QueryShard<E::G1Affine>,
+    pub b_g1_query: QueryShard<E::G1Affine>,
+    pub b_g2_query: QueryShard<E::G2Affine>,
+    pub h_query: QueryShard<E::G1Affine>,
+    pub l_query: QueryShard<E::G1Affine>,
+    pub party: usize,
+    pub n_parties: usize,
+}
+
+impl<E: PairingEngine> ShardedProvingKey<E> {
+    /// Party `party`'s shard of `pk`, among `n_parties`.
+    pub fn shard(pk: &ProvingKey<E>, party: usize, n_parties: usize) -> Self {
+        assert!(party < n_parties, "party {} of {}", party, n_parties);
+        Self {
+            vk: pk.vk.clone(),
+            beta_g1: pk.beta_g1,
+            delta_g1: pk.delta_g1,
+            a_query: QueryShard::new(&pk.a_query, party, n_parties),
+            b_g1_query: QueryShard::new(&pk.b_g1_query, party, n_parties),
+            b_g2_query: QueryShard::new(&pk.b_g2_query, party, n_parties),
+            h_query: QueryShard::new(&pk.h_query, party, n_parties),
+            l_query: QueryShard::new(&pk.l_query, party, n_parties),
+            party,
+            n_parties,
+        }
+    }
+
+    fn g1_query(&self, query: G1Query) -> &QueryShard<E::G1Affine> {
+        match query {
+            G1Query::A => &self.a_query,
+            G1Query::BG1 => &self.b_g1_query,
+            G1Query::H => &self.h_query,
+            G1Query::L => &self.l_query,
+        }
+    }
+
+    fn check_parties(&self) {
+        assert_eq!(
+            (self.party, self.n_parties),
+            (MpcMultiNet::party_id(), MpcMultiNet::n_parties()),
+            "this shard is another party's"
+        );
+    }
+}
+
+impl<E: PairingEngine> ProverKey<E> for ShardedProvingKey<E> {
+    fn vk(&self) -> &VerifyingKey<E> {
+        &self.vk
+    }
+    fn beta_g1(&self) -> E::G1Affine {
+        self.beta_g1
+    }
+    fn delta_g1(&self) -> E::G1Affine {
+        self.delta_g1
+    }
+    fn g1_head(&self, query: G1Query) -> E::G1Affine {
+        self.g1_query(query).head
+    }
+    fn g1_msm(&self, query: G1Query, from: usize, scalars: &[E::Fr]) -> E::G1Projective {
+        self.check_parties();
+        self.g1_query(query).msm(from, scalars)
+    }
+    fn g2_head(&self) -> E::G2Affine {
+        self.b_g2_query.head
+    }
+    fn g2_msm(&self, from: usize, scalars: &[E::Fr]) -> E::G2Projective {
+        self.check_parties();
+        self.b_g2_query.msm(from, scalars)
+    }
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ShardedProvingKey<MpcPairingEngine<E, S>> {
+    type Base = ShardedProvingKey<E>;
+    struct_reveal_simp_impl!(ShardedProvingKey; vk, beta_g1, delta_g1, a_query, b_g1_query,
+        b_g2_query, h_query, l_query, party, n_parties);
+}
+
+/// Like [super::prover::create_random_proof], with a sharded proving key.
+pub fn create_random_proof<E, C, R>(
+    circuit: C,
+    pk: &ShardedProvingKey<E>,
+    rng: &mut R,
+) -> R1CSResult<Proof<E>>
+where
+    E: PairingEngine,
+    C: ConstraintSynthesizer<E::Fr>,
+    R: Rng,
+{
+    let r = E::Fr::rand(rng);
+    let s = E::Fr::rand(rng);
+    create_proof_with_key::<E, C, R1CStoQAP, _>(circuit, pk, r, s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr, G1Affine, G1Projective};
+    use ark_ec::ProjectiveCurve;
+    use ark_groth16::{generate_random_parameters, prepare_verifying_key, verify_proof};
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::{MpcField, MpcG1Affine, MpcPairingEngine as ME};
+    use mpc_net::testing::run_parties;
+
+    #[test]
+    fn slices_cover_the_query() {
+        for &(len, n) in &[(0, 2), (1, 2), (7, 2), (7, 3), (100, 4)] {
+            let slices: Vec<_> = (0..n).map(|i| slice(len, i, n)).collect();
+            assert_eq!(slices[0].start, 0);
+            assert_eq!(slices[n - 1].end, len);
+            assert!(slices.windows(2).all(|w| w[0].end == w[1].start));
+        }
+    }
+
+    #[test]
+    fn hbc_msm_across_chunks() {
+        run_parties("groth::shard::tests::hbc_msm_across_chunks", 2, || {
+            let rng = &mut test_rng();
+            let len = 23;
+            let query = G1Projective::batch_normalization_into_affine(
+                &(0..len)
+                    .map(|_| G1Projective::rand(rng))
+                    .collect::<Vec<_>>(),
+            );
+            let scalars: Vec<MpcField<Fr>> = (1..len).map(|_| MpcField::rand(rng)).collect();
+            let public: Vec<Fr> = scalars.iter().map(|s| s.reveal()).collect();
+            let expected = G1Affine::multi_scalar_mul(&query[1..], &public);
+            let shard = QueryShard::new(&query, MpcMultiNet::party_id(), 2);
+            let shard: QueryShard<MpcG1Affine<Bls12_377>> = Reveal::from_public(shard);
+            assert_eq!(shard.msm_in_chunks(1, &scalars, 4).reveal(), expected);
+        });
+    }
+
+    #[test]
+    fn hbc_sharded_proof() {
+        run_parties("groth::shard::tests::hbc_sharded_proof", 3, || {
+            let rng = &mut test_rng();
+            let params = generate_random_parameters::<Bls12_377, _, _>(
+                MySillyCircuit { a: None, b: None },
+                rng,
+            )
+            .unwrap();
+            let pvk = prepare_verifying_key(&params.vk);
+            let shard = ShardedProvingKey::shard(
+                &params,
+                MpcMultiNet::party_id(),
+                MpcMultiNet::n_parties(),
+            );
+            assert!(shard.h_query.bases.len() < params.h_query.len());
+            let shard: ShardedProvingKey<ME<Bls12_377>> = Reveal::from_public(shard);
+            let (a, b) = (MpcField::<Fr>::rand(rng), MpcField::<Fr>::rand(rng));
+            let c = (a * b).reveal();
+            let circ = MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            let proof = create_random_proof(circ, &shard, rng).unwrap().reveal();
+            assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+        });
+    }
+}
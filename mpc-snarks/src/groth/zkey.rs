@@ -0,0 +1,624 @@
+//! Groth16 proving keys from snarkjs `.zkey` files.
+//!
+//! A `.zkey` is the output of a circom ceremony: a Powers-of-Tau (phase 1, e.g. the perpetual
+//! one, in a `.ptau`) specialized to one circuit and contributed to (phase 2) with `snarkjs
+//! zkey`. Reading one gives a [ProvingKey] whose verifying key is the one the ceremony's
+//! participants trust, so proofs made collaboratively from it verify against their setup.
+//!
+//! Two things differ from keys made by arkworks' generator:
+//! * snarkjs reduces R1CS to QAP its own way, so proofs must be made with
+//!   [super::r1cs_to_qap::CircomReduction] (see
+//!   [super::prover::create_random_proof_with_reduction]).
+//! * The key fixes the circuit, variable for variable. [Zkey::check_circuit] compares the
+//!   constraints that the key records with an arkworks circuit's, before proving with it.
+use ark_ec::{AffineCurve, PairingEngine};
+use ark_ff::{BigInteger, Field, FpParameters, One, PrimeField};
+use ark_groth16::{ProvingKey, VerifyingKey};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, OptimizationGoal, SynthesisMode,
+};
+use ark_serialize::{CanonicalDeserialize, SWFlags, SerializationError};
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::io::Read;
+
+/// A sparse matrix: for each row, its `(coefficient, column)` entries.
+pub type Matrix<F> = Vec<Vec<(F, usize)>>;
+
+/// The sections of a Groth16 `.zkey` (all others, e.g. the contributions, are skipped).
+const HEADER: u32 = 1;
+const GROTH_HEADER: u32 = 2;
+const IC: u32 = 3;
+const COEFFS: u32 = 4;
+const POINTS_A: u32 = 5;
+const POINTS_B1: u32 = 6;
+const POINTS_B2: u32 = 7;
+const POINTS_C: u32 = 8;
+const POINTS_H: u32 = 9;
+
+/// The protocol number of Groth16, in the header section.
+const GROTH16: u32 = 1;
+
+#[derive(Debug)]
+pub enum ZkeyError {
+    Io(std::io::Error),
+    /// A field element or a curve point that is not one.
+    Encoding(SerializationError),
+    /// Not a Groth16 `.zkey` for this curve.
+    Format(String),
+    /// The key is not for the circuit it was checked against.
+    Mismatch(String),
+}
+
+impl Display for ZkeyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ZkeyError::Io(e) => write!(f, "{}", e),
+            ZkeyError::Encoding(e) => write!(f, "malformed field element or point: {}", e),
+            ZkeyError::Format(e) => write!(f, "malformed zkey: {}", e),
+            ZkeyError::Mismatch(e) => write!(f, "zkey is for another circuit: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZkeyError {}
+
+impl From<std::io::Error> for ZkeyError {
+    fn from(e: std::io::Error) -> Self {
+        ZkeyError::Io(e)
+    }
+}
+
+impl From<SerializationError> for ZkeyError {
+    fn from(e: SerializationError) -> Self {
+        ZkeyError::Encoding(e)
+    }
+}
+
+/// A Groth16 key from a circom ceremony.
+pub struct Zkey<E: PairingEngine> {
+    pub pk: ProvingKey<E>,
+    /// The number of public inputs, not counting the constant one.
+    pub n_public: usize,
+    pub domain_size: usize,
+    /// The circuit's A and B matrices, including a row in A for each input (as in arkworks'
+    /// reduction). The key does not record C.
+    pub a: Matrix<E::Fr>,
+    pub b: Matrix<E::Fr>,
+}
+
+impl<E: PairingEngine> Zkey<E> {
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ZkeyError> {
+        let mut sections = read_sections(&mut reader)?;
+        let mut section = |id: u32| {
+            sections
+                .remove(&id)
+                .ok_or_else(|| ZkeyError::Format(format!("no section {}", id)))
+        };
+
+        let header = section(HEADER)?;
+        let protocol = read_u32(&mut &header[..])?;
+        if protocol != GROTH16 {
+            return Err(ZkeyError::Format(format!(
+                "protocol {} is not Groth16",
+                protocol
+            )));
+        }
+
+        let header = section(GROTH_HEADER)?;
+        let r = &mut &header[..];
+        read_modulus::<E::Fq>(r, "base")?;
+        read_modulus::<E::Fr>(r, "scalar")?;
+        let n_vars = read_u32(r)? as usize;
+        let n_public = read_u32(r)? as usize;
+        let domain_size = read_u32(r)? as usize;
+        let alpha_g1 = read_g1::<E>(r)?;
+        let beta_g1 = read_g1::<E>(r)?;
+        let beta_g2 = read_g2::<E>(r)?;
+        let gamma_g2 = read_g2::<E>(r)?;
+        let delta_g1 = read_g1::<E>(r)?;
+        let delta_g2 = read_g2::<E>(r)?;
+
+        let points = |bytes: Vec<u8>, n: usize| -> Result<Vec<E::G1Affine>, ZkeyError> {
+            let r = &mut &bytes[..];
+            (0..n).map(|_| read_g1::<E>(r)).collect()
+        };
+        let gamma_abc_g1 = points(section(IC)?, n_public + 1)?;
+        let a_query = points(section(POINTS_A)?, n_vars)?;
+        let b_g1_query = points(section(POINTS_B1)?, n_vars)?;
+        let b_g2_query = {
+            let bytes = section(POINTS_B2)?;
+            let r = &mut &bytes[..];
+            (0..n_vars)
+                .map(|_| read_g2::<E>(r))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let l_query = points(section(POINTS_C)?, n_vars - n_public - 1)?;
+        let h_query = points(section(POINTS_H)?, domain_size)?;
+
+        let coeffs = section(COEFFS)?;
+        let r = &mut &coeffs[..];
+        let (mut a, mut b) = (Matrix::new(), Matrix::new());
+        for _ in 0..read_u32(r)? {
+            let matrix = match read_u32(r)? {
+                0 => &mut a,
+                1 => &mut b,
+                m => return Err(ZkeyError::Format(format!("coefficient of matrix {}", m))),
+            };
+            let row = read_u32(r)? as usize;
+            let column = read_u32(r)? as usize;
+            // Coefficients carry one more factor of R than the points' coordinates.
+            let coeff = read_montgomery::<E::Fr>(r)? * r_inverse::<E::Fr>();
+            if matrix.len() <= row {
+                matrix.resize(row + 1, Vec::new());
+            }
+            matrix[row].push((coeff, column));
+        }
+
+        Ok(Zkey {
+            pk: ProvingKey {
+                vk: VerifyingKey {
+                    alpha_g1,
+                    beta_g2,
+                    gamma_g2,
+                    delta_g2,
+                    gamma_abc_g1,
+                },
+                beta_g1,
+                delta_g1,
+                a_query,
+                b_g1_query,
+                b_g2_query,
+                h_query,
+                l_query,
+            },
+            n_public,
+            domain_size,
+            a,
+            b,
+        })
+    }
+
+    /// Check that this key is for `circuit`: that it has the same inputs and variables, and the
+    /// same A and B matrices, as synthesized by the MPC prover.
+    pub fn check_circuit<C: ConstraintSynthesizer<E::Fr>>(
+        &self,
+        circuit: C,
+    ) -> Result<(), ZkeyError> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit
+            .generate_constraints(cs.clone())
+            .map_err(|e| ZkeyError::Mismatch(format!("cannot synthesize circuit: {}", e)))?;
+        cs.finalize();
+        let matrices = cs.to_matrices().unwrap();
+
+        let mismatch = |what: &str, ours: usize, theirs: usize| {
+            Err(ZkeyError::Mismatch(format!(
+                "circuit has {} {}, but the key {}",
+                ours, what, theirs
+            )))
+        };
+        if matrices.num_instance_variables != self.n_public + 1 {
+            return mismatch("inputs", matrices.num_instance_variables - 1, self.n_public);
+        }
+        let n_vars = matrices.num_instance_variables + matrices.num_witness_variables;
+        if n_vars != self.pk.a_query.len() {
+            return mismatch("variables", n_vars, self.pk.a_query.len());
+        }
+        let domain_size =
+            GeneralEvaluationDomain::<E::Fr>::new(matrices.num_constraints + self.n_public + 1)
+                .map_or(0, |d| d.size());
+        if domain_size != self.domain_size {
+            return mismatch("a domain of size", domain_size, self.domain_size);
+        }
+
+        let mut a = matrices.a;
+        a.extend((0..=self.n_public).map(|i| vec![(E::Fr::one(), i)]));
+        for (name, ours, theirs) in [("A", a, &self.a), ("B", matrices.b, &self.b)].iter() {
+            let rows = ours.len().max(theirs.len());
+            let row =
+                |m: &Matrix<E::Fr>, i: usize| normalize(m.get(i).cloned().unwrap_or_default());
+            if let Some(i) = (0..rows).find(|&i| row(ours, i) != row(theirs, i)) {
+                return Err(ZkeyError::Mismatch(format!(
+                    "row {} of {} differs",
+                    i, name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sort a row by column, summing repeated columns and dropping zeros.
+fn normalize<F: Field>(mut row: Vec<(F, usize)>) -> Vec<(F, usize)> {
+    row.sort_by_key(|(_, column)| *column);
+    let mut out: Vec<(F, usize)> = Vec::new();
+    for (coeff, column) in row {
+        match out.last_mut() {
+            Some((c, last)) if *last == column => *c += coeff,
+            _ => out.push((coeff, column)),
+        }
+    }
+    out.retain(|(c, _)| !c.is_zero());
+    out
+}
+
+/// Read the file's sections, by id: after the magic, the version, and the number of sections,
+/// each is an id, a length, and that many bytes.
+fn read_sections<R: Read>(reader: &mut R) -> Result<HashMap<u32, Vec<u8>>, ZkeyError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"zkey" {
+        return Err(ZkeyError::Format("not a zkey file".into()));
+    }
+    let version = read_u32(reader)?;
+    if version != 1 {
+        return Err(ZkeyError::Format(format!("version {} is not 1", version)));
+    }
+    let mut sections = HashMap::new();
+    for _ in 0..read_u32(reader)? {
+        let id = read_u32(reader)?;
+        let mut len = [0u8; 8];
+        reader.read_exact(&mut len)?;
+        let mut bytes = vec![0u8; u64::from_le_bytes(len) as usize];
+        reader.read_exact(&mut bytes)?;
+        sections.insert(id, bytes);
+    }
+    Ok(sections)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, ZkeyError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Read a field's size and modulus, and check that they are `F`'s.
+fn read_modulus<F: PrimeField>(reader: &mut &[u8], which: &str) -> Result<(), ZkeyError> {
+    let size = read_u32(reader)? as usize;
+    let mut modulus = vec![0u8; size];
+    reader.read_exact(&mut modulus)?;
+    if modulus != <F::Params as FpParameters>::MODULUS.to_bytes_le() {
+        return Err(ZkeyError::Format(format!(
+            "the {} field is not this curve's",
+            which
+        )));
+    }
+    Ok(())
+}
+
+/// `R^-1`, where `R` is the Montgomery factor of `F`.
+fn r_inverse<F: PrimeField>() -> F {
+    F::from_repr(<F::Params as FpParameters>::R)
+        .unwrap()
+        .inverse()
+        .unwrap()
+}
+
+/// Read a field element in Montgomery form (little-endian, `x * R`), as snarkjs writes them.
+fn read_montgomery<F: PrimeField>(reader: &mut &[u8]) -> Result<F, ZkeyError> {
+    let repr = F::BigInt::deserialize(reader)?;
+    let x = F::from_repr(repr).ok_or(SerializationError::InvalidData)?;
+    Ok(x * r_inverse::<F>())
+}
+
+/// Read a point with `d` base field elements per coordinate (all zero for the identity), and
+/// check that it is on the curve and in the right subgroup.
+fn read_point<G: AffineCurve, Q: PrimeField>(reader: &mut &[u8], d: usize) -> Result<G, ZkeyError> {
+    let coords = (0..2 * d)
+        .map(|_| read_montgomery::<Q>(reader))
+        .collect::<Result<Vec<_>, _>>()?;
+    if coords.iter().all(|c| c.is_zero()) {
+        return Ok(G::zero());
+    }
+    let (last, rest) = coords.split_last().unwrap();
+    let mut bytes = Vec::new();
+    for c in rest {
+        c.serialize(&mut bytes)?;
+    }
+    last.serialize_with_flags(&mut bytes, SWFlags::default())?;
+    let p = G::deserialize_uncompressed(&bytes[..])?;
+    // The uncompressed encoding is not checked to be on the curve; recovering the point from
+    // its x-coordinate is.
+    let mut compressed = Vec::new();
+    p.serialize(&mut compressed)?;
+    if G::deserialize(&compressed[..])? != p {
+        return Err(SerializationError::InvalidData.into());
+    }
+    Ok(p)
+}
+
+fn read_g1<E: PairingEngine>(reader: &mut &[u8]) -> Result<E::G1Affine, ZkeyError> {
+    read_point::<E::G1Affine, E::Fq>(reader, 1)
+}
+
+fn read_g2<E: PairingEngine>(reader: &mut &[u8]) -> Result<E::G2Affine, ZkeyError> {
+    read_point::<E::G2Affine, E::Fq>(reader, E::Fqe::extension_degree() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::prover::{create_random_proof, create_random_proof_with_reduction};
+    use super::super::r1cs_to_qap::CircomReduction;
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr};
+    use ark_ff::UniformRand;
+    use ark_groth16::{prepare_verifying_key, verify_proof};
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSystemRef, SynthesisError},
+    };
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{rand::Rng, test_rng};
+    use mpc_algebra::share::add::AdditivePairingShare;
+    use mpc_algebra::{MpcField, MpcPairingEngine, Reveal};
+    use mpc_net::testing::run_parties;
+
+    type D<F> = GeneralEvaluationDomain<F>;
+
+    /// Make a key for `circuit` as snarkjs does: its `h_query` has the Lagrange polynomials
+    /// of the odd points of the doubled domain.
+    fn circom_setup<E: PairingEngine, C: ConstraintSynthesizer<E::Fr>, R: Rng>(
+        circuit: C,
+        rng: &mut R,
+    ) -> Zkey<E> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_optimization_goal(OptimizationGoal::Constraints);
+        cs.set_mode(SynthesisMode::Setup);
+        circuit.generate_constraints(cs.clone()).unwrap();
+        cs.finalize();
+        let n_public = cs.num_instance_variables() - 1;
+        let mut rand = || E::Fr::rand(rng);
+        let (tau, alpha, beta, gamma, delta) = (rand(), rand(), rand(), rand(), rand());
+        let (a, b, c, _, _, domain_size) =
+            ark_groth16::r1cs_to_qap::R1CStoQAP::instance_map_with_evaluation::<E::Fr, D<E::Fr>>(
+                cs.clone(),
+                &tau,
+            )
+            .unwrap();
+        let lagrange = D::<E::Fr>::new(2 * domain_size)
+            .unwrap()
+            .evaluate_all_lagrange_coefficients(tau);
+
+        let g1 = |x: E::Fr| {
+            E::G1Affine::prime_subgroup_generator()
+                .mul(x.into_repr())
+                .into()
+        };
+        let g2 = |x: E::Fr| {
+            E::G2Affine::prime_subgroup_generator()
+                .mul(x.into_repr())
+                .into()
+        };
+        let (gamma_inv, delta_inv) = (gamma.inverse().unwrap(), delta.inverse().unwrap());
+        let abc: Vec<E::Fr> = (0..a.len())
+            .map(|i| beta * a[i] + alpha * b[i] + c[i])
+            .collect();
+        let matrices = cs.to_matrices().unwrap();
+        let mut a_matrix = matrices.a;
+        a_matrix.extend((0..=n_public).map(|i| vec![(E::Fr::one(), i)]));
+        Zkey {
+            pk: ProvingKey {
+                vk: VerifyingKey {
+                    alpha_g1: g1(alpha),
+                    beta_g2: g2(beta),
+                    gamma_g2: g2(gamma),
+                    delta_g2: g2(delta),
+                    gamma_abc_g1: abc[..=n_public]
+                        .iter()
+                        .map(|x| g1(*x * gamma_inv))
+                        .collect(),
+                },
+                beta_g1: g1(beta),
+                delta_g1: g1(delta),
+                a_query: a.iter().map(|x| g1(*x)).collect(),
+                b_g1_query: b.iter().map(|x| g1(*x)).collect(),
+                b_g2_query: b.iter().map(|x| g2(*x)).collect(),
+                h_query: (0..domain_size)
+                    .map(|i| g1(lagrange[2 * i + 1] * delta_inv))
+                    .collect(),
+                l_query: abc[n_public + 1..]
+                    .iter()
+                    .map(|x| g1(*x * delta_inv))
+                    .collect(),
+            },
+            n_public,
+            domain_size,
+            a: a_matrix,
+            b: matrices.b,
+        }
+    }
+
+    fn write_u32(out: &mut Vec<u8>, x: usize) {
+        out.extend_from_slice(&(x as u32).to_le_bytes());
+    }
+
+    fn write_montgomery<F: PrimeField>(out: &mut Vec<u8>, x: F) {
+        let r = F::from_repr(<F::Params as FpParameters>::R).unwrap();
+        (x * r).into_repr().serialize(out).unwrap();
+    }
+
+    fn write_point<G: AffineCurve, Q: PrimeField>(out: &mut Vec<u8>, p: &G, d: usize) {
+        let coords = if p.is_zero() {
+            vec![Q::zero(); 2 * d]
+        } else {
+            let mut bytes = Vec::new();
+            p.serialize_uncompressed(&mut bytes).unwrap();
+            let r = &mut &bytes[..];
+            let mut coords: Vec<Q> = (1..2 * d)
+                .map(|_| Q::deserialize(&mut *r).unwrap())
+                .collect();
+            coords.push(Q::deserialize_with_flags::<_, SWFlags>(r).unwrap().0);
+            coords
+        };
+        for c in coords {
+            write_montgomery(out, c);
+        }
+    }
+
+    /// Write `zkey` as snarkjs would, with no contributions.
+    fn write<E: PairingEngine>(zkey: &Zkey<E>) -> Vec<u8> {
+        let pk = &zkey.pk;
+        let d2 = E::Fqe::extension_degree() as usize;
+        let g1s = |points: &[E::G1Affine]| {
+            let mut out = Vec::new();
+            points
+                .iter()
+                .for_each(|p| write_point::<_, E::Fq>(&mut out, p, 1));
+            out
+        };
+
+        let mut groth_header = Vec::new();
+        for modulus in [
+            <E::Fq as PrimeField>::Params::MODULUS.to_bytes_le(),
+            <E::Fr as PrimeField>::Params::MODULUS.to_bytes_le(),
+        ]
+        .iter()
+        {
+            write_u32(&mut groth_header, modulus.len());
+            groth_header.extend_from_slice(modulus);
+        }
+        write_u32(&mut groth_header, pk.a_query.len());
+        write_u32(&mut groth_header, zkey.n_public);
+        write_u32(&mut groth_header, zkey.domain_size);
+        groth_header.extend(g1s(&[pk.vk.alpha_g1, pk.beta_g1]));
+        write_point::<_, E::Fq>(&mut groth_header, &pk.vk.beta_g2, d2);
+        write_point::<_, E::Fq>(&mut groth_header, &pk.vk.gamma_g2, d2);
+        groth_header.extend(g1s(&[pk.delta_g1]));
+        write_point::<_, E::Fq>(&mut groth_header, &pk.vk.delta_g2, d2);
+
+        let mut coeffs = Vec::new();
+        let entries: Vec<_> = [&zkey.a, &zkey.b]
+            .iter()
+            .enumerate()
+            .flat_map(|(m, matrix)| {
+                matrix.iter().enumerate().flat_map(move |(row, entries)| {
+                    entries
+                        .iter()
+                        .map(move |(coeff, column)| (m, row, *column, *coeff))
+                })
+            })
+            .collect();
+        write_u32(&mut coeffs, entries.len());
+        for (m, row, column, coeff) in entries {
+            write_u32(&mut coeffs, m);
+            write_u32(&mut coeffs, row);
+            write_u32(&mut coeffs, column);
+            let r = E::Fr::from_repr(<E::Fr as PrimeField>::Params::R).unwrap();
+            write_montgomery(&mut coeffs, coeff * r);
+        }
+
+        let mut b2 = Vec::new();
+        for p in &pk.b_g2_query {
+            write_point::<_, E::Fq>(&mut b2, p, d2);
+        }
+
+        let mut header = Vec::new();
+        write_u32(&mut header, GROTH16 as usize);
+        let sections = vec![
+            (HEADER, header),
+            (GROTH_HEADER, groth_header),
+            (IC, g1s(&pk.vk.gamma_abc_g1)),
+            (COEFFS, coeffs),
+            (POINTS_A, g1s(&pk.a_query)),
+            (POINTS_B1, g1s(&pk.b_g1_query)),
+            (POINTS_B2, b2),
+            (POINTS_C, g1s(&pk.l_query)),
+            (POINTS_H, g1s(&pk.h_query)),
+        ];
+        let mut out = b"zkey".to_vec();
+        write_u32(&mut out, 1);
+        write_u32(&mut out, sections.len());
+        for (id, bytes) in sections {
+            write_u32(&mut out, id as usize);
+            out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            out.extend(bytes);
+        }
+        out
+    }
+
+    /// `x * x = y`, with `y` public.
+    struct Square;
+
+    impl<F: Field> ConstraintSynthesizer<F> for Square {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let x = cs.new_witness_variable(|| Err(SynthesisError::AssignmentMissing))?;
+            let y = cs.new_input_variable(|| Err(SynthesisError::AssignmentMissing))?;
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + y)
+        }
+    }
+
+    #[test]
+    fn read_checks_keys() {
+        let rng = &mut test_rng();
+        let zkey = circom_setup::<Bls12_377, _, _>(MySillyCircuit::<Fr> { a: None, b: None }, rng);
+        let read = Zkey::<Bls12_377>::read(&write(&zkey)[..]).unwrap();
+        assert_eq!(read.pk, zkey.pk);
+        assert!(read.a == zkey.a && read.b == zkey.b);
+
+        read.check_circuit(MySillyCircuit::<Fr> { a: None, b: None })
+            .unwrap();
+        assert!(matches!(
+            read.check_circuit(Square),
+            Err(ZkeyError::Mismatch(_))
+        ));
+
+        let mut off_curve = Vec::new();
+        write_montgomery(&mut off_curve, <Bls12_377 as PairingEngine>::Fq::one());
+        write_montgomery(&mut off_curve, <Bls12_377 as PairingEngine>::Fq::one());
+        assert!(matches!(
+            read_g1::<Bls12_377>(&mut &off_curve[..]),
+            Err(ZkeyError::Encoding(_))
+        ));
+    }
+
+    #[test]
+    fn hbc_proves_with_circom_key() {
+        run_parties("groth::zkey::tests::hbc_proves_with_circom_key", 2, || {
+            type E = MpcPairingEngine<Bls12_377, AdditivePairingShare<Bls12_377>>;
+            let rng = &mut test_rng();
+            let zkey =
+                circom_setup::<Bls12_377, _, _>(MySillyCircuit::<Fr> { a: None, b: None }, rng);
+            let zkey = Zkey::<Bls12_377>::read(&write(&zkey)[..]).unwrap();
+            let pvk = prepare_verifying_key(&zkey.pk.vk);
+            let pk = ProvingKey::<E>::from_public(zkey.pk);
+            let a = MpcField::rand(rng);
+            let b = MpcField::rand(rng);
+            let c = (a * b).reveal();
+            let circuit = MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+
+            let proof = create_random_proof_with_reduction::<E, _, _, CircomReduction>(
+                circuit.clone(),
+                &pk,
+                rng,
+            )
+            .unwrap()
+            .reveal();
+            assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+            // The key's `h_query` is for circom's reduction, not arkworks'.
+            let proof = create_random_proof::<E, _, _>(circuit, &pk, rng)
+                .unwrap()
+                .reveal();
+            assert!(!verify_proof(&pvk, &proof, &[c]).unwrap());
+        });
+    }
+
+    /// Write the BN254 key for `MySillyCircuit` that `tests/zkey.rs` reads.
+    #[test]
+    #[ignore]
+    fn write_bn254_vector() {
+        let rng = &mut test_rng();
+        let circuit = MySillyCircuit::<ark_bn254::Fr> { a: None, b: None };
+        let zkey = circom_setup::<ark_bn254::Bn254, _, _>(circuit, rng);
+        let path =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors/silly_bn254.zkey");
+        std::fs::write(path, write(&zkey)).unwrap();
+    }
+}
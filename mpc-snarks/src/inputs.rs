@@ -0,0 +1,253 @@
+//! Public inputs, built from application data.
+//!
+//! A proof only verifies if the prover and verifier encode the statement into exactly the same
+//! field elements. [PublicInputs] gives each input a name and a kind, so that both sides can build
+//! their vectors with the same code, ship them around serialized, and, when verification fails,
+//! find which input they disagree on with [PublicInputs::diff] or [PublicInputs::check].
+use ark_ff::{Field, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+
+/// How an input was encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputKind {
+    Field,
+    U64,
+    Bool,
+    /// Bytes (e.g., a hash or commitment), packed little-endian into as few elements as fit.
+    Bytes,
+}
+
+impl CanonicalSerialize for InputKind {
+    fn serialize<W: Write>(&self, writer: W) -> Result<(), SerializationError> {
+        (*self as u8).serialize(writer)
+    }
+    fn serialized_size(&self) -> usize {
+        1
+    }
+}
+
+impl CanonicalDeserialize for InputKind {
+    fn deserialize<R: Read>(reader: R) -> Result<Self, SerializationError> {
+        match u8::deserialize(reader)? {
+            0 => Ok(InputKind::Field),
+            1 => Ok(InputKind::U64),
+            2 => Ok(InputKind::Bool),
+            3 => Ok(InputKind::Bytes),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// A named input, and the field elements encoding it.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Input<F: Field> {
+    pub name: String,
+    pub kind: InputKind,
+    pub values: Vec<F>,
+}
+
+/// A circuit's public inputs, in the order it allocates them.
+#[derive(Clone, Debug, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+pub struct PublicInputs<F: Field> {
+    inputs: Vec<Input<F>>,
+}
+
+/// Where two encodings of the public inputs first differ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputMismatch {
+    /// The input, if it is named on our side.
+    pub name: Option<String>,
+    /// The position of the first differing field element.
+    pub index: usize,
+    pub reason: String,
+}
+
+impl Display for InputMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "public input {:?} (element {})", name, self.index)?,
+            None => write!(f, "public input element {}", self.index)?,
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+impl std::error::Error for InputMismatch {}
+
+impl<F: Field> PublicInputs<F> {
+    pub fn new() -> Self {
+        Self { inputs: Vec::new() }
+    }
+
+    fn push(mut self, name: &str, kind: InputKind, values: Vec<F>) -> Self {
+        self.inputs.push(Input {
+            name: name.to_owned(),
+            kind,
+            values,
+        });
+        self
+    }
+
+    pub fn field(self, name: &str, x: F) -> Self {
+        self.push(name, InputKind::Field, vec![x])
+    }
+
+    pub fn u64(self, name: &str, x: u64) -> Self {
+        self.push(name, InputKind::U64, vec![F::from(x)])
+    }
+
+    pub fn bool(self, name: &str, x: bool) -> Self {
+        self.push(name, InputKind::Bool, vec![F::from(x)])
+    }
+
+    pub fn inputs(&self) -> &[Input<F>] {
+        &self.inputs
+    }
+
+    /// The number of field elements.
+    pub fn len(&self) -> usize {
+        self.inputs.iter().map(|i| i.values.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The field elements, as the verifier takes them.
+    pub fn to_vec(&self) -> Vec<F> {
+        self.inputs
+            .iter()
+            .flat_map(|i| i.values.iter().cloned())
+            .collect()
+    }
+
+    /// The input holding the `index`-th field element.
+    fn locate(&self, index: usize) -> Option<&Input<F>> {
+        let mut start = 0;
+        for i in &self.inputs {
+            start += i.values.len();
+            if index < start {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// Check a raw input vector (e.g., the one a proof was made for) against these inputs.
+    pub fn check(&self, values: &[F]) -> Result<(), InputMismatch> {
+        let ours = self.to_vec();
+        let n = ours.len().max(values.len());
+        let index = match (0..n).find(|&i| ours.get(i) != values.get(i)) {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let name = self.locate(index).map(|i| i.name.clone());
+        let counts = format!("got {} elements, expected {}", values.len(), ours.len());
+        let reason = match (ours.get(index), values.get(index)) {
+            (Some(a), Some(b)) => format!("expected {}, got {}", a, b),
+            (Some(_), None) => format!("missing ({})", counts),
+            (None, _) => format!("unexpected ({})", counts),
+        };
+        Err(InputMismatch {
+            name,
+            index,
+            reason,
+        })
+    }
+
+    /// Compare with another party's inputs, naming the first that differs.
+    pub fn diff(&self, other: &Self) -> Result<(), InputMismatch> {
+        let mut index = 0;
+        for (a, b) in self.inputs.iter().zip(&other.inputs) {
+            let reason = if a.name != b.name {
+                Some(format!("named {:?} on the other side", b.name))
+            } else if a.kind != b.kind {
+                Some(format!(
+                    "encoded as {:?}, but as {:?} on the other side",
+                    a.kind, b.kind
+                ))
+            } else if a.values != b.values {
+                Some("values differ".to_owned())
+            } else {
+                None
+            };
+            if let Some(reason) = reason {
+                return Err(InputMismatch {
+                    name: Some(a.name.clone()),
+                    index,
+                    reason,
+                });
+            }
+            index += a.values.len();
+        }
+        let (n, m) = (self.inputs.len(), other.inputs.len());
+        if n != m {
+            return Err(InputMismatch {
+                name: self.inputs.get(m).map(|i| i.name.clone()),
+                index,
+                reason: format!("{} inputs here, but {} on the other side", n, m),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<F: PrimeField> PublicInputs<F> {
+    /// Bytes per element when packing bytes, so that every chunk is below the modulus.
+    pub fn bytes_per_element() -> usize {
+        (F::size_in_bits() - 1) / 8
+    }
+
+    /// Add bytes (e.g., a digest), packed little-endian, [PublicInputs::bytes_per_element] bytes
+    /// to an element.
+    pub fn bytes(self, name: &str, bytes: &[u8]) -> Self {
+        let values = bytes
+            .chunks(Self::bytes_per_element())
+            .map(F::from_le_bytes_mod_order)
+            .collect();
+        self.push(name, InputKind::Bytes, values)
+    }
+}
+
+impl<F: Field> Default for PublicInputs<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+
+    fn example(price: u64) -> PublicInputs<Fr> {
+        PublicInputs::new()
+            .u64("winner", 3)
+            .u64("price", price)
+            .bytes("bids commitment", &[7u8; 32])
+            .bool("second price", true)
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let inputs = example(10);
+        // The 32 bytes take two elements.
+        assert_eq!(inputs.len(), 5);
+        let mut bytes = Vec::new();
+        inputs.serialize(&mut bytes).unwrap();
+        assert_eq!(PublicInputs::<Fr>::deserialize(&bytes[..]).unwrap(), inputs);
+    }
+
+    #[test]
+    fn mismatches_are_named() {
+        let (ours, theirs) = (example(10), example(11));
+        assert_eq!(ours.diff(&ours), Ok(()));
+        assert_eq!(ours.diff(&theirs).unwrap_err().name.as_deref(), Some("price"));
+        let err = ours.check(&theirs.to_vec()).unwrap_err();
+        assert_eq!((err.name.as_deref(), err.index), (Some("price"), 1));
+        let err = ours.check(&ours.to_vec()[..3]).unwrap_err();
+        assert_eq!(err.name.as_deref(), Some("bids commitment"));
+    }
+}
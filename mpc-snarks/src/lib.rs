@@ -1,3 +1,28 @@
+pub mod audit;
+pub mod beacon;
+pub mod chunked;
+pub mod circom;
+pub mod cli;
+pub mod compose;
+pub mod contribution;
+pub mod cost;
+pub mod dv;
+pub mod envelope;
+pub mod expr;
+pub mod groth;
+pub mod inputs;
+pub mod limits;
+pub mod locality;
+pub mod marlin;
+pub mod microbench;
+pub mod outputs;
+pub mod phase;
+pub mod poseidon;
+pub mod silly;
+pub mod srs;
+pub mod witness;
+pub mod witness_cache;
+
 #[cfg(test)]
 mod tests {
     #[test]
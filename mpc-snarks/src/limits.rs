@@ -0,0 +1,127 @@
+//! Whether a computation fits the proof system and curve it is to be proven with.
+//!
+//! A proof system interpolates over FFT domains of a power-of-two size (or a small multiple of
+//! one), and a field only has those up to its two-adicity: BLS12-377's scalar field has
+//! multiplicative subgroups of order up to `2^47`, or `3 * 2^47` with its subgroup of order 3. A
+//! computation that needs a larger domain fails deep inside `ark-poly`, typically after its
+//! witness has been computed; checking its size against these limits first turns that into a
+//! [LimitError] that says which limit it exceeds.
+use ark_ff::{FftField, FftParameters};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LimitError {
+    /// `system` needs a domain of `size` elements, and the field of `curve` has none that large.
+    Domain {
+        system: &'static str,
+        size: usize,
+        curve: &'static str,
+        two_adicity: u32,
+        /// The order of the field's small subgroup, which multiplies the largest domain, or 1.
+        small_subgroup: u64,
+    },
+    /// The SRS in the file at `path` has degree `degree`, below the `needed` one.
+    Srs {
+        path: PathBuf,
+        degree: usize,
+        needed: usize,
+    },
+}
+
+impl Display for LimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::Domain {
+                system,
+                size,
+                curve,
+                two_adicity,
+                small_subgroup,
+            } => {
+                write!(
+                    f,
+                    "size 2^{} exceeds the {} two-adic domain (at most ",
+                    log2_ceil(*size),
+                    curve
+                )?;
+                if *small_subgroup > 1 {
+                    write!(f, "{} * ", small_subgroup)?;
+                }
+                write!(f, "2^{}), which {} needs", two_adicity, system)
+            }
+            LimitError::Srs {
+                path,
+                degree,
+                needed,
+            } => write!(
+                f,
+                "the SRS in {} has degree {}, below the {} that this computation needs",
+                path.display(),
+                degree,
+                needed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+fn log2_ceil(n: usize) -> u32 {
+    n.next_power_of_two().trailing_zeros()
+}
+
+/// Check that `F` has an FFT domain of at least `size` elements, as `system` needs, over `curve`.
+pub fn check_domain<F: FftField>(
+    system: &'static str,
+    curve: &'static str,
+    size: usize,
+) -> Result<(), LimitError> {
+    match GeneralEvaluationDomain::<F>::compute_size_of_domain(size) {
+        Some(_) => Ok(()),
+        None => Err(LimitError::Domain {
+            system,
+            size,
+            curve,
+            two_adicity: F::FftParams::TWO_ADICITY,
+            small_subgroup: match F::FftParams::SMALL_SUBGROUP_BASE {
+                Some(b) => (b as u64).pow(F::FftParams::SMALL_SUBGROUP_BASE_ADICITY.unwrap()),
+                None => 1,
+            },
+        }),
+    }
+}
+
+/// Check that the SRS in the file at `path`, of degree `degree`, reaches the `needed` one.
+pub fn check_srs(path: PathBuf, degree: usize, needed: usize) -> Result<(), LimitError> {
+    if degree < needed {
+        return Err(LimitError::Srs {
+            path,
+            degree,
+            needed,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+
+    #[test]
+    fn domains_up_to_the_two_adicity() {
+        // Fr's largest domain is `3 * 2^47`, with its subgroup of order 3.
+        assert_eq!(check_domain::<Fr>("groth16", "BLS12-377", 3 << 47), Ok(()));
+        assert_eq!(check_domain::<Fr>("groth16", "BLS12-377", 3), Ok(()));
+        let e = check_domain::<Fr>("groth16", "BLS12-377", (3 << 47) + 1).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "size 2^49 exceeds the BLS12-377 two-adic domain (at most 3 * 2^47), which groth16 \
+             needs"
+        );
+        assert!(check_srs("srs.bin".into(), 100, 101).is_err());
+        assert_eq!(check_srs("srs.bin".into(), 100, 100), Ok(()));
+    }
+}
@@ -0,0 +1,234 @@
+//! Which multiplications a single party could compute alone, from the ownership tags of
+//! [crate::audit].
+//!
+//! A constraint `a * b = c` whose inputs `a` and `b` involve only one party's data (and public
+//! values) is a multiplication that party can compute on its own values, in the clear, and then
+//! share: it needs no Beaver triple, where a multiplication of shares needs one. Its output is then
+//! that party's data too, so ownership spreads through a circuit from the tagged inputs: an
+//! untagged witness variable that is the only one in `c` takes the owner of `a` and `b`. (It does
+//! not spread backwards, from `c` to `a` or `b`.)
+//!
+//! [locality] partitions the constraints into a block per party, of those whose inputs are its
+//! data alone, and a joint block of the others. A party's block involves only its own data and
+//! public values, so it can be computed by that party alone, before any joint multiplication:
+//! [LocalityReport::schedule] orders the constraints so, keeping the circuit's order within each
+//! block. [LocalityReport::triples_saved] counts the multiplications in the party blocks.
+//!
+//! These are hints: the savings are realized only by witness computations that multiply each
+//! party's data at that party, before sharing the products. Proving itself (e.g., the quotient
+//! of a QAP) multiplies polynomials over all of the witness, and is unaffected.
+use ark_ff::Field;
+use ark_relations::r1cs::{ConstraintSynthesizer, SynthesisError};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+use crate::audit::{self, Owner};
+
+/// The constraints whose inputs are one party's data.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PartyBlock {
+    pub party: usize,
+    /// In their order in the circuit.
+    pub constraints: Vec<usize>,
+    /// How many of them multiply two of the party's values, rather than scale one.
+    pub multiplications: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct LocalityReport {
+    pub constraints: usize,
+    /// Per party, in order, for the parties with any.
+    pub parties: Vec<PartyBlock>,
+    /// The other constraints, in order.
+    pub joint: Vec<usize>,
+    /// How many of those multiply shared values, each taking a Beaver triple.
+    pub joint_multiplications: usize,
+    /// Untagged witness variables that were found to be a single party's data.
+    pub derived: usize,
+}
+
+impl LocalityReport {
+    /// Beaver triples that multiplying each party's data at that party would save.
+    pub fn triples_saved(&self) -> usize {
+        self.parties.iter().map(|b| b.multiplications).sum()
+    }
+
+    /// The constraints, with each party's block ahead of the joint block.
+    pub fn schedule(&self) -> Vec<usize> {
+        self.parties
+            .iter()
+            .flat_map(|b| b.constraints.iter().copied())
+            .chain(self.joint.iter().copied())
+            .collect()
+    }
+}
+
+impl Display for LocalityReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} constraints, {} witness variables found to be one party's",
+            self.constraints, self.derived
+        )?;
+        for b in &self.parties {
+            writeln!(
+                f,
+                "  {:<12} {:>8} constraints, {:>8} multiplications",
+                Owner::Party(b.party).to_string(),
+                b.constraints.len(),
+                b.multiplications
+            )?;
+        }
+        writeln!(
+            f,
+            "  {:<12} {:>8} constraints, {:>8} multiplications",
+            "joint",
+            self.joint.len(),
+            self.joint_multiplications
+        )?;
+        let total = self.triples_saved() + self.joint_multiplications;
+        write!(
+            f,
+            "  local multiplications would save {} of {} triples",
+            self.triples_saved(),
+            total
+        )
+    }
+}
+
+/// Synthesize `circ` (without its assignment, which it need not have) and partition its
+/// constraints by the party whose data their inputs are.
+pub fn locality<F: Field>(
+    circ: impl ConstraintSynthesizer<F>,
+) -> Result<LocalityReport, SynthesisError> {
+    let (m, mut owners) = audit::synthesize_tagged(circ)?;
+    let n_instance = m.num_instance_variables;
+    let witnesses = |row: &[(F, usize)]| -> Vec<usize> {
+        row.iter()
+            .filter_map(|(_, column)| column.checked_sub(n_instance))
+            .collect()
+    };
+    let tagged = owners.len();
+
+    // The party whose data constraint `i`'s inputs are, if there is one such party.
+    let party_of = |i: usize, owners: &BTreeMap<usize, Owner>| {
+        let inputs: BTreeSet<Option<Owner>> = witnesses(&m.a[i])
+            .into_iter()
+            .chain(witnesses(&m.b[i]))
+            .map(|w| owners.get(&w).copied())
+            .collect();
+        match inputs.into_iter().collect::<Vec<_>>()[..] {
+            [Some(Owner::Party(p))] => Some(p),
+            _ => None,
+        }
+    };
+    // Spread ownership to outputs until nothing changes: constraints need not come in the order
+    // that their values are computed.
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..m.num_constraints {
+            let party = match party_of(i, &owners) {
+                Some(p) => p,
+                None => continue,
+            };
+            if let [w] = witnesses(&m.c[i])[..] {
+                if !owners.contains_key(&w) {
+                    owners.insert(w, Owner::Party(party));
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let multiplies = |i: usize| !witnesses(&m.a[i]).is_empty() && !witnesses(&m.b[i]).is_empty();
+    let mut blocks: BTreeMap<usize, PartyBlock> = BTreeMap::new();
+    let (mut joint, mut joint_multiplications) = (Vec::new(), 0);
+    for i in 0..m.num_constraints {
+        match party_of(i, &owners) {
+            Some(party) => {
+                let block = blocks.entry(party).or_insert_with(|| PartyBlock {
+                    party,
+                    constraints: Vec::new(),
+                    multiplications: 0,
+                });
+                block.constraints.push(i);
+                block.multiplications += multiplies(i) as usize;
+            }
+            None => {
+                joint.push(i);
+                joint_multiplications += multiplies(i) as usize;
+            }
+        }
+    }
+    Ok(LocalityReport {
+        constraints: m.num_constraints,
+        parties: blocks.into_iter().map(|(_, b)| b).collect(),
+        joint,
+        joint_multiplications,
+        derived: owners.len() - tagged,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSystemRef, Variable},
+    };
+
+    /// Party 0's `x` and party 1's `y`, with (in this order) `x2 * x = x3`, `x * x = x2`,
+    /// `y * y = y2`, `x3 * y2 = z` for a public `z`, and `x2 * 1 = 4`.
+    struct Cubes;
+
+    impl ConstraintSynthesizer<Fr> for Cubes {
+        fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+            let z = cs.new_input_variable(|| Ok(Fr::from(128u8)))?;
+            let x = cs.new_witness_variable(|| Ok(Fr::from(2u8)))?;
+            let y = cs.new_witness_variable(|| Ok(Fr::from(4u8)))?;
+            let x2 = cs.new_witness_variable(|| Ok(Fr::from(4u8)))?;
+            let x3 = cs.new_witness_variable(|| Ok(Fr::from(8u8)))?;
+            let y2 = cs.new_witness_variable(|| Ok(Fr::from(16u8)))?;
+            audit::tag(x, Owner::Party(0));
+            audit::tag(y, Owner::Party(1));
+            cs.enforce_constraint(lc!() + x2, lc!() + x, lc!() + x3)?;
+            cs.enforce_constraint(lc!() + x, lc!() + x, lc!() + x2)?;
+            cs.enforce_constraint(lc!() + y, lc!() + y, lc!() + y2)?;
+            cs.enforce_constraint(lc!() + x3, lc!() + y2, lc!() + z)?;
+            let four = lc!() + (Fr::from(4u8), Variable::One);
+            cs.enforce_constraint(lc!() + x2, lc!() + Variable::One, four)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn partitions_by_party() {
+        let report = locality(Cubes).unwrap();
+        assert_eq!(report.constraints, 5);
+        // `x2`, `x3` and `y2`, though `x3` only on a second pass.
+        assert_eq!(report.derived, 3);
+        assert_eq!(
+            report.parties,
+            vec![
+                PartyBlock {
+                    party: 0,
+                    constraints: vec![0, 1, 4],
+                    multiplications: 2,
+                },
+                PartyBlock {
+                    party: 1,
+                    constraints: vec![2],
+                    multiplications: 1,
+                },
+            ]
+        );
+        assert_eq!(report.joint, vec![3]);
+        assert_eq!(report.joint_multiplications, 1);
+        assert_eq!(report.triples_saved(), 3);
+        assert_eq!(report.schedule(), vec![0, 1, 4, 2, 3]);
+        assert!(report.to_string().contains("save 3 of 4 triples"));
+    }
+}
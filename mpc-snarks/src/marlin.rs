@@ -4,8 +4,10 @@ use ark_poly::univariate::DensePolynomial;
 use ark_poly_commit::marlin_pc::MarlinKZG10;
 use ark_std::{end_timer, start_timer, test_rng};
 use blake2::Blake2s;
+use ark_marlin::rng::FiatShamirHash;
 use mpc_algebra::honest_but_curious::*;
 use mpc_algebra::Reveal;
+use crate::outputs::RevealOutputs;
 
 fn prover_message_publicize(
     p: ProverMsg<MpcField<ark_bls12_377::Fr>>,
@@ -80,15 +82,16 @@ type ME = MpcPairingEngine<ark_bls12_377::Bls12_377>;
 type MFr = MpcField<Fr>;
 type MpcMarlinKZG10 = MarlinKZG10<ME, DensePolynomial<MFr>>;
 type LocalMarlinKZG10 = MarlinKZG10<E, DensePolynomial<Fr>>;
-type LocalMarlin = Marlin<Fr, LocalMarlinKZG10, Blake2s>;
-type MpcMarlin = Marlin<MFr, MpcMarlinKZG10, Blake2s>;
+/// Fiat-Shamir hashes public data, so the local and MPC provers must use the same `D`.
+type LocalMarlin<D = Blake2s> = Marlin<Fr, LocalMarlinKZG10, D>;
+type MpcMarlin<D = Blake2s> = Marlin<MFr, MpcMarlinKZG10, D>;
 
-pub fn mpc_test_prove_and_verify(n_iters: usize) {
+pub fn mpc_test_prove_and_verify<D: FiatShamirHash>(n_iters: usize) {
     let rng = &mut test_rng();
 
-    let srs = LocalMarlin::universal_setup(100, 50, 100, rng).unwrap();
+    let srs = LocalMarlin::<D>::universal_setup(100, 50, 100, rng).unwrap();
     let empty_circuit: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
-    let (index_pk, index_vk) = LocalMarlin::index(&srs, empty_circuit.clone()).unwrap();
+    let (index_pk, index_vk) = LocalMarlin::<D>::index(&srs, empty_circuit.clone()).unwrap();
     let mpc_index_pk = IndexProverKey::from_public(index_pk);
 
     for _ in 0..n_iters {
@@ -98,16 +101,233 @@ pub fn mpc_test_prove_and_verify(n_iters: usize) {
             a: Some(a),
             b: Some(b),
         };
-        let mut c = a;
-        c *= &b;
-        let inputs = vec![c.reveal()];
-        println!("{}\n{}\n{}", a, b, c);
-        let mpc_proof = MpcMarlin::prove(&mpc_index_pk, circ, rng).unwrap();
+        let inputs = RevealOutputs::Instance.reveal(circ.clone()).unwrap();
+        println!("{}\n{}\n{:?}", a, b, inputs);
+        let mpc_proof = MpcMarlin::<D>::prove(&mpc_index_pk, circ, rng).unwrap();
         let proof = pf_publicize(mpc_proof);
         let public_a = a.reveal();
-        let is_valid = LocalMarlin::verify(&index_vk, &inputs, &proof, rng).unwrap();
+        let is_valid = LocalMarlin::<D>::verify(&index_vk, &inputs, &proof, rng).unwrap();
         assert!(is_valid);
-        let is_valid = LocalMarlin::verify(&index_vk, &[public_a], &proof, rng).unwrap();
+        let is_valid = LocalMarlin::<D>::verify(&index_vk, &[public_a], &proof, rng).unwrap();
         assert!(!is_valid);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_serialize::CanonicalSerialize;
+    use mpc_net::{assert_comm, testing::run_parties};
+    use mpc_trait::MpcWire;
+
+    fn check_shared_verification() {
+        let rng = &mut test_rng();
+        let srs = LocalMarlin::<Blake2s>::universal_setup(100, 50, 100, rng).unwrap();
+        let empty_circuit: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
+        let (index_pk, index_vk) = LocalMarlin::<Blake2s>::index(&srs, empty_circuit).unwrap();
+        let mpc_index_pk = IndexProverKey::from_public(index_pk);
+        let mpc_index_vk = IndexVerifierKey::from_public(index_vk);
+
+        let a = MFr::king_share(Fr::from(2u8), rng);
+        let b = MFr::king_share(Fr::from(3u8), rng);
+        let circ = MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let inputs = vec![MFr::from_public(Fr::from(6u8))];
+        let mut proof = MpcMarlin::<Blake2s>::prove(&mpc_index_pk, circ, rng).unwrap();
+        assert!(proof.pc_proof.proof.iter().any(|p| p.w.is_shared()));
+        let verify = |proof: &Proof<MFr, MpcMarlinKZG10>| {
+            MpcMarlin::<Blake2s>::verify(&mpc_index_vk, &inputs, proof, &mut test_rng()).unwrap()
+        };
+        assert!(verify(&proof));
+
+        let w = &mut proof.pc_proof.proof[0].w;
+        *w = w.into_projective().double().into();
+        assert!(!verify(&proof));
+    }
+
+    #[test]
+    fn hbc_shared_verification() {
+        run_parties(
+            "marlin::tests::hbc_shared_verification",
+            2,
+            check_shared_verification,
+        );
+    }
+
+    #[test]
+    fn hbc_joint_hiding() {
+        use ark_ff::UniformRand;
+        use ark_poly::UVPolynomial;
+        use ark_poly_commit::kzg10::{self, KZG10};
+        use ark_poly_commit::marlin_pc::CommitterKey;
+        use ark_poly_commit::{LabeledPolynomial, PCRandomness, PolynomialCommitment};
+        use ark_std::rand::{rngs::StdRng, SeedableRng};
+        use mpc_net::{MpcMultiNet, MpcNet};
+
+        run_parties("marlin::tests::hbc_joint_hiding", 2, || {
+            let rng = &mut test_rng();
+            let hiding_bound = 1;
+            let pp = LocalMarlinKZG10::setup(16, None, rng).unwrap();
+            let (ck, _) = LocalMarlinKZG10::trim(&pp, 16, hiding_bound, None).unwrap();
+            let mpc_ck: CommitterKey<ME> = Reveal::from_public(ck.clone());
+            let coeffs: Vec<Fr> = (0..10).map(|_| Fr::rand(rng)).collect();
+            let p = DensePolynomial::from_coefficients_vec(MFr::king_share_batch(coeffs, rng));
+            let local_p = DensePolynomial::from_coefficients_vec(
+                p.coeffs.iter().map(|c| c.reveal()).collect(),
+            );
+
+            // What each party would sample locally; the joint randomness is their sum.
+            let party_rng = |i: usize| StdRng::seed_from_u64(i as u64);
+            let expected = (0..MpcMultiNet::n_parties())
+                .map(|i| kzg10::Randomness::rand(hiding_bound, false, None, &mut party_rng(i)))
+                .fold(kzg10::Randomness::<Fr, DensePolynomial<Fr>>::empty(), |a, b| a + &b);
+            let ours = party_rng(MpcMultiNet::party_id());
+            let joint = kzg10::Randomness::joint_rand(hiding_bound, &mut ours.clone());
+            assert_eq!(joint.clone().reveal().blinding_polynomial, expected.blinding_polynomial);
+
+            // A commitment hidden by it is exactly the local one with that randomness.
+            let local = KZG10::commit_with_randomness(&ck.powers(), &local_p, &expected).unwrap();
+            let shared = KZG10::commit_with_randomness(&mpc_ck.powers(), &p, &joint).unwrap();
+            assert_eq!(shared.reveal(), local);
+
+            // And so is one that MarlinKZG10 hides, given the same per-party `rng`.
+            let labeled = LabeledPolynomial::new("p".into(), p, None, Some(hiding_bound));
+            let (comms, rands) =
+                MpcMarlinKZG10::commit(&mpc_ck, &[labeled], Some(&mut ours.clone())).unwrap();
+            assert_eq!(
+                rands[0].clone().reveal().rand.blinding_polynomial,
+                expected.blinding_polynomial
+            );
+            assert_eq!(comms[0].commitment().clone().reveal().comm, local);
+        });
+    }
+
+    #[test]
+    fn hbc_degree_claims() {
+        run_parties("marlin::tests::hbc_degree_claims", 2, || {
+            let rng = &mut test_rng();
+            let srs = LocalMarlin::<Blake2s>::universal_setup(100, 50, 100, rng).unwrap();
+            let empty_circuit: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
+            let (index_pk, _) = LocalMarlin::<Blake2s>::index(&srs, empty_circuit).unwrap();
+            let mpc_index_pk = IndexProverKey::from_public(index_pk);
+            let circ = MySillyCircuit {
+                a: Some(MFr::king_share(Fr::from(2u8), rng)),
+                b: Some(MFr::king_share(Fr::from(3u8), rng)),
+            };
+            ahp::degrees::track();
+            MpcMarlin::<Blake2s>::prove(&mpc_index_pk, circ, rng).unwrap();
+            // The oracles that depend on the witness or the mask: all in the first two rounds but
+            // t, and none in the third.
+            assert_eq!(ahp::degrees::check_tracked(), Ok(6));
+        });
+    }
+
+    #[test]
+    fn hbc_proving_comm() {
+        run_parties("marlin::tests::hbc_proving_comm", 2, || {
+            let rng = &mut test_rng();
+            let srs = LocalMarlin::<Blake2s>::universal_setup(100, 50, 100, rng).unwrap();
+            let empty_circuit: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
+            let (index_pk, index_vk) = LocalMarlin::<Blake2s>::index(&srs, empty_circuit).unwrap();
+            let mpc_index_pk = IndexProverKey::from_public(index_pk);
+            let a = MFr::king_share(Fr::from(2u8), rng);
+            let b = MFr::king_share(Fr::from(3u8), rng);
+            let circ = MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            };
+            // Exact, so that any change to the protocol's communication is noticed.
+            let proof = assert_comm!(rounds == 24, bytes_sent == 2960, bytes_recv == 2960, {
+                pf_publicize(MpcMarlin::<Blake2s>::prove(&mpc_index_pk, circ, rng).unwrap())
+            });
+            let inputs = [Fr::from(6u8)];
+            assert!(LocalMarlin::<Blake2s>::verify(&index_vk, &inputs, &proof, rng).unwrap());
+        });
+    }
+
+    #[test]
+    fn hbc_proof_layout() {
+        run_parties("marlin::tests::hbc_proof_layout", 2, || {
+            let rng = &mut test_rng();
+            let srs = LocalMarlin::<Blake2s>::universal_setup(100, 50, 100, rng).unwrap();
+            let empty_circuit: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
+            let (index_pk, _) = LocalMarlin::<Blake2s>::index(&srs, empty_circuit).unwrap();
+            let local_circ = MySillyCircuit {
+                a: Some(Fr::from(2u8)),
+                b: Some(Fr::from(3u8)),
+            };
+            let local = LocalMarlin::<Blake2s>::prove(&index_pk, local_circ, rng).unwrap();
+            let mpc_index_pk = IndexProverKey::from_public(index_pk);
+            let circ = MySillyCircuit {
+                a: Some(MFr::king_share(Fr::from(2u8), rng)),
+                b: Some(MFr::king_share(Fr::from(3u8), rng)),
+            };
+            let proof =
+                pf_publicize(MpcMarlin::<Blake2s>::prove(&mpc_index_pk, circ, rng).unwrap());
+            assert_eq!(proof.component_sizes(), local.component_sizes());
+            assert_eq!(proof.serialized_size(), local.serialized_size());
+        });
+    }
+
+    #[test]
+    fn hbc_shared_index() {
+        use ark_relations::lc;
+        use ark_relations::r1cs::{
+            ConstraintSynthesizer, ConstraintSystemRef, SynthesisError, Variable,
+        };
+
+        /// `a * k = c`, for a public `c`, with `k` part of the circuit.
+        #[derive(Clone)]
+        struct Scaled<F: ark_ff::Field> {
+            k: F,
+            a: Option<F>,
+        }
+        impl<F: ark_ff::Field> ConstraintSynthesizer<F> for Scaled<F> {
+            fn generate_constraints(
+                self,
+                cs: ConstraintSystemRef<F>,
+            ) -> Result<(), SynthesisError> {
+                let (k, a) = (self.k, self.a.ok_or(SynthesisError::AssignmentMissing));
+                let c = cs.new_input_variable(|| Ok(a? * k))?;
+                let a = cs.new_witness_variable(|| a)?;
+                // Repeated, for domains large enough for Marlin.
+                for _ in 0..4 {
+                    cs.enforce_constraint(lc!() + a, lc!() + (k, Variable::One), lc!() + c)?;
+                }
+                Ok(())
+            }
+        }
+        run_parties("marlin::tests::hbc_shared_index", 2, || {
+            let rng = &mut test_rng();
+            let srs = LocalMarlin::<Blake2s>::universal_setup(100, 50, 100, rng).unwrap();
+            let local = Scaled {
+                k: Fr::from(5u8),
+                a: None,
+            };
+            let (_, local_vk) = LocalMarlin::<Blake2s>::index(&srs, local).unwrap();
+            let mpc_srs = Reveal::from_public(srs);
+            let k = MFr::king_share(Fr::from(5u8), rng);
+            let (pk, vk) =
+                MpcMarlin::<Blake2s>::index_shared(&mpc_srs, Scaled { k, a: None }).unwrap();
+            let bytes = |vk: &IndexVerifierKey<Fr, LocalMarlinKZG10>| {
+                let mut bytes = Vec::new();
+                vk.serialize(&mut bytes).unwrap();
+                bytes
+            };
+            assert_eq!(bytes(&vk), bytes(&local_vk));
+
+            let a = MFr::king_share(Fr::from(3u8), rng);
+            let proof = MpcMarlin::<Blake2s>::prove(&pk, Scaled { k, a: Some(a) }, rng).unwrap();
+            let proof = pf_publicize(proof);
+            let verify = |c: u8| {
+                LocalMarlin::<Blake2s>::verify(&vk, &[Fr::from(c)], &proof, &mut test_rng())
+                    .unwrap()
+            };
+            assert!(verify(15));
+            assert!(!verify(16));
+        });
+    }
+}
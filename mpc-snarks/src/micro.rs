@@ -0,0 +1,4 @@
+//! Microbenchmarks of the algebra layer; see [mpc_snarks::microbench].
+fn main() {
+    mpc_snarks::microbench::main();
+}
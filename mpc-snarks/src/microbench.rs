@@ -0,0 +1,312 @@
+//! The `micro` command line: microbenchmarks of the algebra layer.
+//!
+//! Each [Op] is one batched operation on `size` field (or group) elements, run `reps` times, on
+//! the scalar field of a chosen curve, locally or over one of the MPC share types. For each, a
+//! [Sample] reports the mean latency, and the communication (rounds and bytes, as this party saw
+//! them) of one run, so that a change to the algebra layer can be measured op by op, away from the
+//! proof systems built on it.
+use crate::cli::{Curve, MpcAlg};
+use ark_ec::{PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, One, PubUniformRand, UniformRand};
+use ark_poly::{EvaluationDomain, GeneralEvaluationDomain};
+use ark_std::rand::Rng;
+use clap::arg_enum;
+use mpc_algebra::{FieldShare, MpcField, MpcPairingEngine, PairingShare};
+use mpc_net::{testing::measure, MpcMultiNet, MpcNet};
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+arg_enum! {
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub enum Op {
+        Mul,
+        Inv,
+        Open,
+        Fft,
+        Msm,
+    }
+}
+
+impl Op {
+    fn all() -> Vec<Op> {
+        vec![Op::Mul, Op::Inv, Op::Open, Op::Fft, Op::Msm]
+    }
+}
+
+/// One op's measurements: the mean over its runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub op: Op,
+    pub size: usize,
+    pub reps: usize,
+    pub latency: Duration,
+    pub rounds: usize,
+    pub bytes_sent: usize,
+    pub bytes_recv: usize,
+}
+
+impl Display for Sample {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<5} {:>8} {:>12.3} {:>7} {:>12} {:>12}",
+            self.op.to_string().to_lowercase(),
+            self.size,
+            self.latency.as_secs_f64() * 1e3,
+            self.rounds,
+            self.bytes_sent,
+            self.bytes_recv
+        )
+    }
+}
+
+impl Sample {
+    pub const HEADER: &'static str =
+        "op        size   latency/ms  rounds   bytes sent   bytes recv";
+}
+
+/// Run `op` on `size` elements `reps` times over `E`, opening shared values with `open`.
+///
+/// Over an MPC engine, the inputs are random shares and the MSM's bases are public; locally,
+/// `open` does nothing. Setting up the inputs is neither timed nor counted.
+pub fn bench<E: PairingEngine, R: Rng>(
+    op: Op,
+    size: usize,
+    reps: usize,
+    open: impl Fn(Vec<E::Fr>) -> Vec<E::Fr>,
+    rng: &mut R,
+) -> Sample {
+    assert!(size > 0 && reps > 0, "nothing to measure");
+    let scalars = |rng: &mut R| (0..size).map(|_| E::Fr::rand(rng)).collect::<Vec<_>>();
+    let mut sample = Sample {
+        op,
+        size,
+        reps,
+        latency: Duration::default(),
+        rounds: 0,
+        bytes_sent: 0,
+        bytes_recv: 0,
+    };
+    for _ in 0..reps {
+        let (a, b) = (scalars(rng), scalars(rng));
+        let bases = match op {
+            Op::Msm => E::G1Projective::batch_normalization_into_affine(
+                &(0..size)
+                    .map(|_| E::G1Projective::pub_rand(rng))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => Vec::new(),
+        };
+        let start = Instant::now();
+        let (_, comm) = measure(|| match op {
+            Op::Mul => {
+                let mut a = a;
+                E::Fr::batch_product_in_place(&mut a, &b);
+            }
+            Op::Inv => {
+                let mut ones = vec![E::Fr::one(); size];
+                E::Fr::batch_division_in_place(&mut ones, &b);
+            }
+            Op::Open => {
+                open(a);
+            }
+            Op::Fft => {
+                let domain = GeneralEvaluationDomain::<E::Fr>::new(size).unwrap();
+                let mut a = a;
+                domain.fft_in_place(&mut a);
+            }
+            Op::Msm => {
+                <E::G1Affine as ark_ec::AffineCurve>::multi_scalar_mul(&bases, &a);
+            }
+        });
+        sample.latency += start.elapsed();
+        sample.rounds = comm.rounds;
+        sample.bytes_sent = comm.bytes_sent;
+        sample.bytes_recv = comm.bytes_recv;
+    }
+    sample.latency /= reps as u32;
+    sample
+}
+
+/// [bench] over the MPC version of `E` with shares `S`.
+pub fn bench_mpc<E: PairingEngine, S: PairingShare<E>, R: Rng>(
+    op: Op,
+    size: usize,
+    reps: usize,
+    rng: &mut R,
+) -> Sample {
+    let open = |xs: Vec<MpcField<E::Fr, S::FrShare>>| {
+        let shares = xs.into_iter().map(|x| match x {
+            MpcField::Shared(s) => s,
+            MpcField::Public(_) => unreachable!("inputs are shared"),
+        });
+        S::FrShare::batch_open(shares)
+            .into_iter()
+            .map(MpcField::Public)
+            .collect()
+    };
+    bench::<MpcPairingEngine<E, S>, R>(op, size, reps, open, rng)
+}
+
+#[derive(Debug, StructOpt)]
+enum Mode {
+    Mpc {
+        /// File with list of hosts
+        #[structopt(long, parse(from_os_str))]
+        hosts: PathBuf,
+
+        /// Rendezvous file through which parties with port 0 in the hosts file find each other
+        #[structopt(long, parse(from_os_str))]
+        rendezvous: Option<PathBuf>,
+
+        /// Which party are you?
+        #[structopt(long, default_value = "0")]
+        party: u8,
+
+        /// Share type
+        #[structopt(long)]
+        alg: MpcAlg,
+    },
+    Local,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "micro", about = "Microbenchmarks of the algebra layer")]
+struct Opt {
+    /// Operation to measure (mul, inv, open, fft or msm); repeat to measure several, or omit to
+    /// measure them all
+    #[structopt(long = "op", number_of_values = 1)]
+    ops: Vec<Op>,
+
+    /// Elements per operation
+    #[structopt(long, default_value = "1024")]
+    size: usize,
+
+    /// Runs of each operation, over which its latency is averaged
+    #[structopt(long, default_value = "3")]
+    reps: usize,
+
+    /// Curve over whose scalar field (and first group) to compute
+    #[structopt(long, default_value = "bls12_377")]
+    curve: Curve,
+
+    #[structopt(subcommand)]
+    mode: Mode,
+}
+
+impl Opt {
+    fn run<E: PairingEngine>(&self) -> Vec<Sample> {
+        use mpc_algebra::share::{add, gsz20, spdz};
+        let ops = if self.ops.is_empty() {
+            Op::all()
+        } else {
+            self.ops.clone()
+        };
+        let rng = &mut ark_std::test_rng();
+        let (size, reps) = (self.size, self.reps);
+        ops.into_iter()
+            .map(|op| match &self.mode {
+                Mode::Local => bench::<E, _>(op, size, reps, |x| x, rng),
+                Mode::Mpc { alg, .. } => {
+                    // Start each op at every party at once, so that none waits on another's
+                    // setup inside the measurement.
+                    mpc_algebra::channel::barrier(&op.to_string());
+                    match alg {
+                        MpcAlg::Hbc => {
+                            bench_mpc::<E, add::AdditivePairingShare<E>, _>(op, size, reps, rng)
+                        }
+                        MpcAlg::Spdz => {
+                            bench_mpc::<E, spdz::SpdzPairingShare<E>, _>(op, size, reps, rng)
+                        }
+                        MpcAlg::Gsz => {
+                            bench_mpc::<E, gsz20::GszPairingShare<E>, _>(op, size, reps, rng)
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run the `micro` command line.
+pub fn main() {
+    let opt = Opt::from_args();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    if let Mode::Mpc {
+        hosts,
+        rendezvous,
+        party,
+        alg,
+    } = &opt.mode
+    {
+        // The share type fixes the message formats, so it is checked on connecting.
+        mpc_net::handshake::set_app_features(1 << *alg as u16);
+        let hosts = hosts.to_str().unwrap();
+        match rendezvous {
+            Some(r) => {
+                MpcMultiNet::init_with_rendezvous(hosts, *party as usize, r.to_str().unwrap())
+            }
+            None => MpcMultiNet::init_from_file(hosts, *party as usize),
+        }
+        let params = vec![
+            ("ops", format!("{:?}", opt.ops)),
+            ("size", opt.size.to_string()),
+            ("reps", opt.reps.to_string()),
+            ("curve", opt.curve.to_string()),
+        ];
+        if let Err(e) = mpc_net::handshake::check_params::<MpcMultiNet>(&params) {
+            panic!("Parties disagree on parameters: {}", e);
+        }
+    }
+    let samples = match opt.curve {
+        Curve::Bls12_377 => opt.run::<ark_bls12_377::Bls12_377>(),
+        Curve::Cp6_782 => opt.run::<ark_cp6_782::CP6_782>(),
+        Curve::Bn254 => opt.run::<ark_bn254::Bn254>(),
+    };
+    println!("{}", Sample::HEADER);
+    for s in &samples {
+        println!("{}", s);
+    }
+    if let Mode::Mpc { .. } = opt.mode {
+        MpcMultiNet::deinit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Bls12_377;
+    use mpc_algebra::share::spdz::SpdzPairingShare;
+    use mpc_net::testing::run_parties;
+
+    #[test]
+    fn local_ops_are_free() {
+        let rng = &mut ark_std::test_rng();
+        for op in Op::all() {
+            let s = bench::<Bls12_377, _>(op, 16, 2, |x| x, rng);
+            assert_eq!((s.op, s.size, s.reps), (op, 16, 2));
+            assert_eq!((s.rounds, s.bytes_sent), (0, 0));
+        }
+    }
+
+    #[test]
+    fn spdz_ops() {
+        run_parties("microbench::tests::spdz_ops", 2, || {
+            let rng = &mut ark_std::test_rng();
+            let mut rounds = |op, size| {
+                bench_mpc::<Bls12_377, SpdzPairingShare<Bls12_377>, _>(op, size, 1, rng).rounds
+            };
+            // Linear maps of shares are local; the other ops are batched, so their rounds do not
+            // grow with the batch.
+            assert_eq!(rounds(Op::Fft, 16), 0);
+            assert_eq!(rounds(Op::Msm, 16), 0);
+            for op in vec![Op::Mul, Op::Inv, Op::Open] {
+                let r = rounds(op, 16);
+                assert!(r > 0);
+                assert_eq!(rounds(op, 64), r);
+            }
+        });
+    }
+}
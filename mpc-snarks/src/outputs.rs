@@ -0,0 +1,134 @@
+//! Revealing a circuit's outputs, and nothing else of its assignment.
+//!
+//! The verifier needs the public inputs of a proof, which in MPC are shared like the rest of the
+//! assignment. Picking them out by hand (recomputing the circuit's outputs and revealing each) is
+//! easy to get wrong: reveal one value too many, and a secret is out. Instead, [RevealOutputs]
+//! names the wires to reveal, synthesizes the circuit, and opens exactly those wires, together.
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, SynthesisError, SynthesisMode, Variable,
+};
+use mpc_algebra::{FieldShare, MpcField};
+
+/// Which wires of a circuit to reveal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RevealOutputs {
+    /// The public inputs: every instance variable but the constant one, in order.
+    Instance,
+    /// Just these wires, in this order.
+    Wires(Vec<Variable>),
+}
+
+impl RevealOutputs {
+    /// Synthesize `circ`, and reveal its output wires; its other wires stay shared.
+    ///
+    /// Fails if synthesis does, or if some output wire is not in the circuit.
+    pub fn reveal<F: PrimeField, S: FieldShare<F>>(
+        &self,
+        circ: impl ConstraintSynthesizer<MpcField<F, S>>,
+    ) -> Result<Vec<F>, SynthesisError> {
+        let cs = ConstraintSystem::new_ref();
+        cs.set_mode(SynthesisMode::Prove {
+            construct_matrices: false,
+        });
+        circ.generate_constraints(cs.clone())?;
+        let cs = cs.borrow().unwrap();
+        let outputs: Vec<MpcField<F, S>> = match self {
+            RevealOutputs::Instance => cs.instance_assignment[1..].to_vec(),
+            RevealOutputs::Wires(wires) => wires
+                .iter()
+                .map(|w| {
+                    cs.assigned_value(*w)
+                        .ok_or(SynthesisError::AssignmentMissing)
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        Ok(open(outputs))
+    }
+}
+
+/// Open all of `xs`, with the shared ones opened in one batch.
+fn open<F: PrimeField, S: FieldShare<F>>(xs: Vec<MpcField<F, S>>) -> Vec<F> {
+    let shared: Vec<S> = xs
+        .iter()
+        .filter_map(|x| match x {
+            MpcField::Shared(s) => Some(*s),
+            MpcField::Public(_) => None,
+        })
+        .collect();
+    let mut opened = S::batch_open(shared).into_iter();
+    xs.into_iter()
+        .map(|x| match x {
+            MpcField::Shared(_) => opened.next().unwrap(),
+            MpcField::Public(x) => x,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_relations::{lc, r1cs::ConstraintSystemRef};
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::MpcField as MF;
+    use mpc_algebra::share::field::FIELD_OPENINGS_METRIC;
+    use mpc_algebra::Reveal;
+    use mpc_net::{metrics, testing::run_parties};
+    use mpc_trait::MpcWire;
+
+    type Fr = ark_bls12_377::Fr;
+
+    /// Proves knowledge of `a` and `b` with `a * b = c` and `a + b = d`, for public `c` and `d`.
+    struct ProductAndSum {
+        a: MF<Fr>,
+        b: MF<Fr>,
+        c: MF<Fr>,
+    }
+
+    impl ConstraintSynthesizer<MF<Fr>> for ProductAndSum {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<MF<Fr>>,
+        ) -> Result<(), SynthesisError> {
+            let a = cs.new_witness_variable(|| Ok(self.a))?;
+            let b = cs.new_witness_variable(|| Ok(self.b))?;
+            let c = cs.new_input_variable(|| Ok(self.c))?;
+            let d = cs.new_input_variable(|| Ok(self.a + self.b))?;
+            cs.enforce_constraint(lc!() + a, lc!() + b, lc!() + c)?;
+            cs.enforce_constraint(lc!() + a + b, lc!() + Variable::One, lc!() + d)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn hbc_reveal_outputs() {
+        run_parties("outputs::tests::hbc_reveal_outputs", 2, || {
+            let rng = &mut test_rng();
+            let abc = MF::<Fr>::king_share_batch(
+                vec![3u8, 5, 15].into_iter().map(Fr::from).collect(),
+                rng,
+            );
+            let circ = || ProductAndSum {
+                a: abc[0],
+                b: abc[1],
+                c: abc[2],
+            };
+
+            let opened = metrics::get(FIELD_OPENINGS_METRIC);
+            let outputs = RevealOutputs::Instance.reveal(circ()).unwrap();
+            assert_eq!(outputs, vec![Fr::from(15u8), Fr::from(8u8)]);
+            // Only the outputs, and not `a` or `b`.
+            assert_eq!(metrics::get(FIELD_OPENINGS_METRIC) - opened, 2);
+
+            let sum = RevealOutputs::Wires(vec![Variable::Instance(2), Variable::One]);
+            assert_eq!(
+                sum.reveal(circ()).unwrap(),
+                vec![Fr::from(8u8), Fr::from(1u8)]
+            );
+            let missing = RevealOutputs::Wires(vec![Variable::Witness(2)]);
+            assert!(missing.reveal(circ()).is_err());
+            // The inputs are still shared.
+            assert!(abc.iter().all(|x| x.is_shared()));
+        });
+    }
+}
@@ -0,0 +1,161 @@
+//! The phases of an MPC proof, which every party goes through in order, once each.
+//!
+//! Parties exchange messages in each phase, and a party in one phase reads the messages of
+//! another's as its own: revealing a proof before the preprocessing is done, or proving twice in
+//! one session, garbles the channel rather than failing. [Phases] tracks which phase a party is
+//! in, and turns such a mistake into a [PhaseError] before the party sends anything.
+use std::fmt::{self, Display, Formatter};
+
+/// A phase of an MPC proof, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Deriving the keys, and lifting the proving key into the MPC.
+    Setup,
+    /// Computing the shared witness, and opening the public inputs.
+    InputSharing,
+    /// Checking and committing to the witness, and meeting the others to start proving.
+    Preprocessing,
+    Prove,
+    /// Opening the proof, to everyone or to one party.
+    Reveal,
+    /// Checking the opened proof.
+    Verify,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::Setup,
+        Phase::InputSharing,
+        Phase::Preprocessing,
+        Phase::Prove,
+        Phase::Reveal,
+        Phase::Verify,
+    ];
+
+    /// The phase that comes before this one, if any.
+    pub fn prev(self) -> Option<Phase> {
+        let i = Self::ALL.iter().position(|&p| p == self).unwrap();
+        i.checked_sub(1).map(|i| Self::ALL[i])
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Phase::Setup => "setup",
+            Phase::InputSharing => "input sharing",
+            Phase::Preprocessing => "preprocessing",
+            Phase::Prove => "proving",
+            Phase::Reveal => "reveal",
+            Phase::Verify => "verification",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PhaseError {
+    /// A phase was entered early: from `from` (`None` before any), which is not the one before it.
+    Skipped { from: Option<Phase>, to: Phase },
+    /// A phase was entered again, from `from`: it has already run in this session.
+    Repeated { from: Phase, to: Phase },
+}
+
+impl Display for PhaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseError::Skipped { from: None, to } => {
+                write!(f, "cannot start with {}, before {}", to, Phase::ALL[0])
+            }
+            PhaseError::Skipped { from: Some(from), to } => write!(
+                f,
+                "cannot go from {} to {}, before {}",
+                from,
+                to,
+                to.prev().unwrap()
+            ),
+            PhaseError::Repeated { from, to } => {
+                write!(f, "cannot go from {} back to {}, which has run", from, to)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PhaseError {}
+
+/// Where one party is in a session's phases.
+#[derive(Clone, Debug, Default)]
+pub struct Phases {
+    current: Option<Phase>,
+}
+
+impl Phases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The phase this party is in, or `None` before the first.
+    pub fn current(&self) -> Option<Phase> {
+        self.current
+    }
+
+    /// Move on to `to`, which must be the phase after the current one.
+    pub fn enter(&mut self, to: Phase) -> Result<(), PhaseError> {
+        match self.current {
+            Some(from) if to <= from => Err(PhaseError::Repeated { from, to }),
+            from if to.prev() != from => Err(PhaseError::Skipped { from, to }),
+            _ => {
+                self.current = Some(to);
+                Ok(())
+            }
+        }
+    }
+
+    /// [Self::enter] `to`, and then run `f` in it.
+    pub fn run<T>(&mut self, to: Phase, f: impl FnOnce() -> T) -> Result<T, PhaseError> {
+        self.enter(to)?;
+        Ok(f())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phases_run_in_order() {
+        let mut phases = Phases::new();
+        for &p in &Phase::ALL {
+            assert_eq!(phases.run(p, || p), Ok(p));
+            assert_eq!(phases.current(), Some(p));
+        }
+    }
+
+    #[test]
+    fn illegal_transitions() {
+        let mut phases = Phases::new();
+        let to = Phase::Prove;
+        assert_eq!(phases.enter(to), Err(PhaseError::Skipped { from: None, to }));
+        phases.enter(Phase::Setup).unwrap();
+        phases.enter(Phase::InputSharing).unwrap();
+        // Revealing before the preprocessing.
+        let (from, to) = (Some(Phase::InputSharing), Phase::Reveal);
+        assert_eq!(phases.enter(to), Err(PhaseError::Skipped { from, to }));
+        assert_eq!(phases.current(), Some(Phase::InputSharing));
+        phases.enter(Phase::Preprocessing).unwrap();
+        phases.enter(Phase::Prove).unwrap();
+        // Proving twice in one session; nothing runs.
+        let (from, to) = (Phase::Prove, Phase::Prove);
+        let mut ran = false;
+        assert_eq!(phases.run(to, || ran = true), Err(PhaseError::Repeated { from, to }));
+        assert!(!ran);
+        assert_eq!(
+            PhaseError::Skipped {
+                from: Some(Phase::InputSharing),
+                to: Phase::Reveal
+            }
+            .to_string(),
+            "cannot go from input sharing to reveal, before proving"
+        );
+    }
+}
@@ -69,3 +69,48 @@ pub fn mpc_test_prove_and_verify(n_iters: usize) {
     end_timer!(t);
     LocalPlonk::verify(&vk, &v_circ, pf, &public);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mpc_net::testing::run_parties;
+    use relations::{flat::*, structured::*};
+
+    #[test]
+    fn hbc_lookups() {
+        run_parties("plonk::tests::hbc_lookups", 2, || {
+            let rng = &mut test_rng();
+            let start = F::from(3u64);
+            let public: HashMap<String, F> = vec![("out".to_owned(), F::from(81u64))]
+                .into_iter()
+                .collect();
+
+            let v_circ = CircuitLayout::from_circuit(
+                &PlonkCircuit::<F>::new_range_checked_squaring_circuit(2, 8, None),
+            );
+            let srs = LocalPlonk::universal_setup(v_circ.domains.gates.size as usize, rng);
+            let (pk, vk) = LocalPlonk::circuit_setup(&srs, &v_circ);
+
+            // The king knows the witness, so it counts the lookups, and shares the counts.
+            let mut c = PlonkCircuit::<F>::new_range_checked_squaring_circuit(2, 8, Some(start));
+            c.count_lookups();
+            let counts: Vec<Vec<MF>> = c
+                .lookup_counts
+                .unwrap()
+                .into_iter()
+                .map(|counts| MF::king_share_batch(counts, rng))
+                .collect();
+            let mut c = PlonkCircuit::<MF>::new_range_checked_squaring_circuit(
+                2,
+                8,
+                Some(MF::king_share(start, rng)),
+            );
+            c.lookup_counts = Some(counts);
+            let circ = CircuitLayout::from_circuit(&c);
+
+            let mpc_pk = ProverKey::from_public(pk);
+            let mpc_pf = MpcPlonk::prove(&mpc_pk, &circ, &mut test_rng());
+            LocalPlonk::verify(&vk, &v_circ, mpc_pf.reveal(), &public);
+        });
+    }
+}
@@ -0,0 +1,72 @@
+//! Deal and inspect randomness pools (see `mpc_algebra::preprocessing`).
+use mpc_algebra::preprocessing::{Material, RandomnessPool};
+use structopt::StructOpt;
+
+use std::path::PathBuf;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pool", about = "Circuit-agnostic preprocessing pools")]
+enum Opt {
+    /// Deal a fresh pool (trusted dealer; for benchmarking only)
+    Deal {
+        /// Pool directory
+        #[structopt(long, parse(from_os_str))]
+        dir: PathBuf,
+
+        /// Number of parties
+        #[structopt(long, default_value = "2")]
+        parties: usize,
+
+        /// Records of each kind of material
+        #[structopt(long)]
+        size: usize,
+
+        /// Give all parties but the last a seed to expand their shares from, instead of the shares
+        /// (this compresses what the dealer ships, and is still a trusted dealer)
+        #[structopt(long)]
+        compressed: bool,
+    },
+    /// Print remaining material and per-session consumption
+    Usage {
+        /// Pool directory
+        #[structopt(long, parse(from_os_str))]
+        dir: PathBuf,
+
+        /// Whose copy of the pool to inspect
+        #[structopt(long, default_value = "0")]
+        party: usize,
+    },
+}
+
+fn main() -> std::io::Result<()> {
+    env_logger::init();
+    match Opt::from_args() {
+        Opt::Deal {
+            dir,
+            parties,
+            size,
+            compressed,
+        } => {
+            let rng = &mut rand::thread_rng();
+            if compressed {
+                RandomnessPool::deal_compressed::<ark_bls12_377::Fr, _>(&dir, parties, size, rng)?;
+            } else {
+                RandomnessPool::deal::<ark_bls12_377::Fr, _>(&dir, parties, size, rng)?;
+            }
+        }
+        Opt::Usage { dir, party } => {
+            let pool = RandomnessPool::open(&dir, party)?;
+            println!("Field: {}", pool.field_name()?);
+            for m in &Material::ALL {
+                println!("Remaining {}: {}", m, pool.remaining(*m)?);
+            }
+            for (session, used) in pool.usage()? {
+                println!("Session {}:", session);
+                for (m, n) in used {
+                    println!("  {}: {}", m, n);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Poseidon commitments: a party publishes a commitment to a private value ahead of time, and a
+//! circuit then shows that the value it computes on is the committed one.
+//!
+//! The commitment to `x` with blinding `r` is circomlib's `poseidon([x, r])`: the first element of
+//! the [Poseidon permutation](PoseidonParameters) of `[0, x, r]`. It hides `x` (for a random `r`)
+//! and binds the committer to it. In a circuit, the commitment is a public input, and `x`, `r` and
+//! the S-boxes' intermediate powers are witnesses, with a constraint per multiplication. The
+//! committer knows all of them in the clear, so in MPC it inputs the powers as it does `x`, and
+//! the parties multiply no shares to open a commitment.
+use ark_ff::{Field, PrimeField};
+use ark_marlin::sponge::PoseidonParameters;
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+        SynthesisMode, Variable,
+    },
+};
+
+use crate::audit::{self, Owner};
+
+/// A commitment's opening: the value, the blinding, and every multiplication's result in the
+/// permutation, in the order [enforce_opening] allocates them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Opening<F: Field> {
+    pub value: F,
+    pub blinding: F,
+    pub trace: Vec<F>,
+}
+
+impl<F: Field> Opening<F> {
+    /// The opening with `f` applied to each of its elements, e.g., to share them.
+    pub fn map<G: Field>(&self, f: impl Fn(F) -> G) -> Opening<G> {
+        Opening {
+            value: f(self.value),
+            blinding: f(self.blinding),
+            trace: self.trace.iter().map(|x| f(*x)).collect(),
+        }
+    }
+}
+
+/// The commitment to `value` with `blinding`, and its opening.
+pub fn commit<F: PrimeField>(value: F, blinding: F) -> (F, Opening<F>) {
+    let params = PoseidonParameters::<F>::new();
+    let mut trace = Vec::new();
+    let mut state = [F::zero(), value, blinding];
+    for r in 0..rounds(&params) {
+        for (s, c) in state.iter_mut().zip(round_constants(&params, r)) {
+            *s += c;
+        }
+        for s in state.iter_mut().take(s_boxes(&params, r)) {
+            let x = *s;
+            for by_x in power_steps(params.alpha) {
+                *s = if by_x { *s * x } else { s.square() };
+                trace.push(*s);
+            }
+        }
+        let old = state;
+        for (s, row) in state.iter_mut().zip(&params.mds) {
+            *s = row.iter().zip(&old).map(|(m, x)| *m * x).sum();
+        }
+    }
+    let opening = Opening {
+        value,
+        blinding,
+        trace,
+    };
+    (state[0], opening)
+}
+
+/// Enforce, in `cs`, that `commitment` opens to `value`: allocate the opening's blinding and
+/// trace (in setup, placeholders for them) as witnesses, tagged as `owner`'s.
+///
+/// `opening`, if any, must open `commitment` to `value`'s assignment.
+pub fn enforce_opening<F: PrimeField>(
+    cs: &ConstraintSystemRef<F>,
+    commitment: Variable,
+    value: Variable,
+    opening: Option<&Opening<F>>,
+    owner: Option<Owner>,
+) -> Result<(), SynthesisError> {
+    let params = PoseidonParameters::<F>::new();
+    let witness = |v: Option<F>| {
+        let var = cs.new_witness_variable(|| v.ok_or(SynthesisError::AssignmentMissing))?;
+        if let Some(owner) = owner {
+            audit::tag(var, owner);
+        }
+        Ok(var)
+    };
+    let blinding = witness(opening.map(|o| o.blinding))?;
+    let mut trace = opening.map(|o| o.trace.iter().cloned());
+
+    let mut state = [lc!(), lc!() + value, lc!() + blinding];
+    for r in 0..rounds(&params) {
+        for (s, c) in state.iter_mut().zip(round_constants(&params, r)) {
+            *s = s.clone() + (*c, Variable::One);
+        }
+        for s in state.iter_mut().take(s_boxes(&params, r)) {
+            let x = s.clone();
+            for by_x in power_steps(params.alpha) {
+                let product = witness(trace.as_mut().and_then(Iterator::next))?;
+                let factor = if by_x { x.clone() } else { s.clone() };
+                cs.enforce_constraint(s.clone(), factor, lc!() + product)?;
+                *s = lc!() + product;
+            }
+        }
+        let old = state.clone();
+        for (s, row) in state.iter_mut().zip(&params.mds) {
+            *s = row
+                .iter()
+                .zip(&old)
+                .fold(lc!(), |acc, (m, x)| acc + (*m, x));
+        }
+    }
+    let [out, _, _] = state;
+    cs.enforce_constraint(out, lc!() + Variable::One, lc!() + commitment)
+}
+
+/// The constraints, witness variables, and non-zero entries per matrix that opening a commitment
+/// takes, at most, over the fields that the CLI proves over; for sizing Marlin's setup.
+pub fn opening_bounds() -> (usize, usize, usize) {
+    let bounds = |(c, v, nz): (usize, usize, usize), other: (usize, usize, usize)| {
+        (c.max(other.0), v.max(other.1), nz.max(other.2))
+    };
+    bounds(
+        opening_size::<ark_bls12_377::Fr>(),
+        opening_size::<ark_bls12_377::Fq>(),
+    )
+}
+
+fn opening_size<F: PrimeField>() -> (usize, usize, usize) {
+    struct Opens;
+    impl<F: PrimeField> ConstraintSynthesizer<F> for Opens {
+        fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+            let commitment = cs.new_input_variable(|| Ok(F::zero()))?;
+            let value = cs.new_witness_variable(|| Ok(F::zero()))?;
+            enforce_opening(&cs, commitment, value, None, None)
+        }
+    }
+    let cs = ConstraintSystem::<F>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    Opens.generate_constraints(cs.clone()).unwrap();
+    cs.finalize();
+    let matrices = cs.to_matrices().unwrap();
+    (
+        matrices.num_constraints,
+        matrices.num_witness_variables,
+        matrices
+            .a_num_non_zero
+            .max(matrices.b_num_non_zero)
+            .max(matrices.c_num_non_zero),
+    )
+}
+
+fn rounds<F: PrimeField>(params: &PoseidonParameters<F>) -> usize {
+    params.full_rounds + params.partial_rounds
+}
+
+/// How many state elements round `r` applies S-boxes to: all in the full rounds, which come
+/// half before the partial rounds and half after, and the first in the partial rounds.
+fn s_boxes<F: PrimeField>(params: &PoseidonParameters<F>, r: usize) -> usize {
+    let half = params.full_rounds / 2;
+    if r < half || r >= half + params.partial_rounds {
+        3
+    } else {
+        1
+    }
+}
+
+fn round_constants<F: PrimeField>(params: &PoseidonParameters<F>, r: usize) -> &[F] {
+    &params.round_constants[3 * r..3 * (r + 1)]
+}
+
+/// The multiplications that raise `x` to `alpha` by square-and-multiply: `true` for a
+/// multiplication by `x`, `false` for a squaring.
+fn power_steps(alpha: u64) -> Vec<bool> {
+    let bits = 64 - alpha.leading_zeros();
+    (0..bits - 1)
+        .rev()
+        .flat_map(|i| {
+            if (alpha >> i) & 1 == 1 {
+                vec![false, true]
+            } else {
+                vec![false]
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_377::Fr;
+    use ark_ff::UniformRand;
+
+    fn opens(commitment: Fr, value: Fr, opening: &Opening<Fr>) -> bool {
+        let cs = ConstraintSystem::new_ref();
+        let c = cs.new_input_variable(|| Ok(commitment)).unwrap();
+        let x = cs.new_witness_variable(|| Ok(value)).unwrap();
+        enforce_opening(&cs, c, x, Some(opening), None).unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn opens_only_to_the_committed_value() {
+        let rng = &mut ark_std::test_rng();
+        let (x, r) = (Fr::rand(rng), Fr::rand(rng));
+        let (com, opening) = commit(x, r);
+        let mut state = [Fr::from(0u8), x, r];
+        PoseidonParameters::new().permute(&mut state);
+        assert_eq!(com, state[0]);
+
+        assert!(opens(com, x, &opening));
+        assert!(!opens(com, x + Fr::from(1u8), &opening));
+        assert!(!opens(com + Fr::from(1u8), x, &opening));
+        // Another blinding opens another commitment.
+        let (other, other_opening) = commit(x, r + Fr::from(1u8));
+        assert_ne!(com, other);
+        assert!(!opens(com, x, &other_opening));
+    }
+}
@@ -0,0 +1,196 @@
+//! Universal SRS files, read only as far as a circuit needs.
+//!
+//! A Marlin SRS set up for large circuits is mostly powers of tau that a smaller circuit never
+//! uses, and reading one whole to index a small circuit takes as much memory as the largest
+//! circuit would. An SRS file is the SRS's canonical (compressed) serialization; [SrsFile::slice]
+//! reads just the powers up to a degree, seeking past the rest, and returns them as an SRS of that
+//! degree, which Marlin indexes like any other (with
+//! [AHPForR1CS::max_degree](ark_marlin::AHPForR1CS::max_degree) as the degree for a circuit).
+//!
+//! A sliced SRS is an SRS in its own right, with the same tau. But Marlin's keys depend on the
+//! SRS's degree, as well as on its powers, so everyone who indexes a circuit must slice the file
+//! (or set it up) to the same degree; slicing to the circuit's own degree makes sure of that.
+use ark_ec::PairingEngine;
+use ark_ff::Zero;
+use ark_poly_commit::kzg10::UniversalParams;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom};
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Writes `srs` to a new file at `path`, for [SrsFile].
+pub fn save<E: PairingEngine>(srs: &UniversalParams<E>, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    srs.serialize(&mut writer).map_err(invalid)
+}
+
+/// An SRS file (or any other reader over one), open for slicing.
+pub struct SrsFile<E, R> {
+    reader: R,
+    /// The number of powers of `g` in the file, which the reader is just after.
+    powers: usize,
+    _engine: PhantomData<E>,
+}
+
+impl<E: PairingEngine> SrsFile<E, BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<E: PairingEngine, R: Read + Seek> SrsFile<E, R> {
+    /// Reads the header of the SRS in `reader`.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let powers = u64::deserialize(&mut reader).map_err(invalid)? as usize;
+        if powers == 0 {
+            return Err(invalid("an SRS with no powers"));
+        }
+        Ok(Self {
+            reader,
+            powers,
+            _engine: PhantomData,
+        })
+    }
+
+    /// The degree of the SRS in the file: the highest degree it can be sliced to.
+    pub fn max_degree(&self) -> usize {
+        self.powers - 1
+    }
+
+    /// The SRS of degree `degree` in the file: its powers up to `degree`, and no further.
+    ///
+    /// Reads only those, and the file's few other elements; the file is used up.
+    pub fn slice(mut self, degree: usize) -> io::Result<UniversalParams<E>> {
+        if degree > self.max_degree() {
+            return Err(invalid(format!(
+                "an SRS of degree {} cannot be sliced to degree {}",
+                self.max_degree(),
+                degree
+            )));
+        }
+        let g1 = E::G1Affine::zero().serialized_size() as i64;
+        let reader = &mut self.reader;
+        let powers_of_g = (0..=degree)
+            .map(|_| E::G1Affine::deserialize(&mut *reader))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?;
+        reader.seek(SeekFrom::Current((self.powers - 1 - degree) as i64 * g1))?;
+        // One more power of `gamma * g` than of `g`, as the setup makes them, for hiding.
+        let powers_of_gamma_g = read_prefix(reader, degree + 1, 8 + g1)?;
+        let h = E::G2Affine::deserialize(&mut *reader).map_err(invalid)?;
+        let beta_h = E::G2Affine::deserialize(&mut *reader).map_err(invalid)?;
+        let g2 = E::G2Affine::zero().serialized_size() as i64;
+        let neg_powers_of_h = read_prefix(reader, degree, 8 + g2)?;
+        Ok(UniversalParams {
+            powers_of_g,
+            powers_of_gamma_g,
+            prepared_h: h.into(),
+            prepared_beta_h: beta_h.into(),
+            h,
+            beta_h,
+            neg_powers_of_h,
+        })
+    }
+}
+
+/// Reads the entries of a serialized map whose keys are at most `last`, seeking past the rest, of
+/// `entry` bytes each. Maps serialize in key order.
+fn read_prefix<R: Read + Seek, V: CanonicalDeserialize>(
+    reader: &mut R,
+    last: usize,
+    entry: i64,
+) -> io::Result<BTreeMap<usize, V>> {
+    let len = u64::deserialize(&mut *reader).map_err(invalid)?;
+    let mut map = BTreeMap::new();
+    for read in 0..len {
+        let key = u64::deserialize(&mut *reader).map_err(invalid)? as usize;
+        if key > last {
+            reader.seek(SeekFrom::Current((len - read) as i64 * entry - 8))?;
+            break;
+        }
+        map.insert(key, V::deserialize(&mut *reader).map_err(invalid)?);
+    }
+    Ok(map)
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::silly::MySillyCircuit;
+    use ark_bls12_377::{Bls12_377, Fr};
+    use ark_marlin::{AHPForR1CS, Marlin};
+    use ark_poly::univariate::DensePolynomial;
+    use ark_poly_commit::marlin_pc::MarlinKZG10;
+    use blake2::Blake2s;
+    use std::cell::Cell;
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    type LocalMarlin = Marlin<Fr, MarlinKZG10<Bls12_377, DensePolynomial<Fr>>, Blake2s>;
+
+    /// A reader that counts the bytes read through it.
+    struct Counting {
+        inner: Cursor<Vec<u8>>,
+        read: Rc<Cell<usize>>,
+    }
+
+    impl Read for Counting {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read.set(self.read.get() + n);
+            Ok(n)
+        }
+    }
+
+    impl Seek for Counting {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn slices_index_and_prove() {
+        let rng = &mut ark_std::test_rng();
+        let srs = LocalMarlin::universal_setup(100, 50, 100, rng).unwrap();
+        let mut bytes = Vec::new();
+        srs.serialize(&mut bytes).unwrap();
+        let read = Rc::new(Cell::new(0));
+        let file = || {
+            let inner = Cursor::new(bytes.clone());
+            let read = read.clone();
+            SrsFile::<Bls12_377, _>::new(Counting { inner, read }).unwrap()
+        };
+        assert_eq!(file().max_degree(), srs.powers_of_g.len() - 1);
+        let whole = file().slice(file().max_degree()).unwrap();
+        assert_eq!(whole.powers_of_g, srs.powers_of_g);
+        assert_eq!(whole.powers_of_gamma_g, srs.powers_of_gamma_g);
+        assert!(file().slice(file().max_degree() + 1).is_err());
+
+        let empty: MySillyCircuit<Fr> = MySillyCircuit { a: None, b: None };
+        let degree = AHPForR1CS::index(empty.clone()).unwrap().max_degree();
+        read.set(0);
+        let slice = file().slice(degree).unwrap();
+        assert!(
+            read.get() * 4 < bytes.len(),
+            "{} of {} bytes",
+            read.get(),
+            bytes.len()
+        );
+        assert_eq!(slice.powers_of_g, srs.powers_of_g[..=degree].to_vec());
+        assert_eq!(slice.powers_of_gamma_g.len(), degree + 2);
+
+        let (pk, vk) = LocalMarlin::index(&slice, empty).unwrap();
+        let circ = MySillyCircuit {
+            a: Some(Fr::from(2u8)),
+            b: Some(Fr::from(3u8)),
+        };
+        let proof = LocalMarlin::prove(&pk, circ, rng).unwrap();
+        assert!(LocalMarlin::verify(&vk, &[Fr::from(6u8)], &proof, rng).unwrap());
+    }
+}
@@ -0,0 +1,151 @@
+//! Checking a secret-shared witness against its constraints, before proving with it.
+//!
+//! A prover given a witness that does not satisfy its circuit still runs to completion, and only
+//! the verifier notices. [first_violated] finds out up front, at the cost of one round of
+//! multiplications and one opening: it opens a random combination of all constraints' residuals,
+//! which is zero (with high probability) only if they all are. If it is not, it bisects, opening
+//! combinations of ever smaller prefixes, to find the first violated constraint.
+use ark_ff::{Field, PrimeField};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+use mpc_algebra::{FieldShare, MpcField, Reveal};
+use mpc_trait::MpcWire;
+
+/// Synthesize `circ`, and return the index of its first violated constraint, if any.
+///
+/// The residuals, `<a, z> * <b, z> - <c, z>`, are combined with public coefficients from
+/// `coeff`; only these combinations are opened. They need only be unpredictable to whoever
+/// chose the witness, so all parties must draw the same ones.
+pub fn first_violated<F: PrimeField, S: FieldShare<F>>(
+    circ: impl ConstraintSynthesizer<MpcField<F, S>>,
+    coeff: impl FnMut() -> F,
+) -> Option<usize> {
+    let cs = ConstraintSystem::new_ref();
+    circ.generate_constraints(cs.clone()).unwrap();
+    cs.finalize();
+    let cs = cs.borrow().unwrap();
+    let matrices = cs.to_matrices().unwrap();
+    let z: Vec<MpcField<F, S>> = cs
+        .instance_assignment
+        .iter()
+        .chain(&cs.witness_assignment)
+        .cloned()
+        .collect();
+    let az = MpcField::sparse_mat_vec(&matrices.a, &z);
+    let bz = MpcField::sparse_mat_vec(&matrices.b, &z);
+    let cz = MpcField::sparse_mat_vec(&matrices.c, &z);
+    let residuals: Vec<MpcField<F, S>> = products(az, &bz)
+        .into_iter()
+        .zip(cz)
+        .map(|(ab, c)| ab - c)
+        .collect();
+    let r: Vec<F> = std::iter::repeat_with(coeff)
+        .take(residuals.len())
+        .collect();
+    let combine = |range: std::ops::Range<usize>| -> F {
+        residuals[range.clone()]
+            .iter()
+            .zip(&r[range])
+            .map(|(x, r)| *x * MpcField::from_public(*r))
+            .sum::<MpcField<F, S>>()
+            .reveal()
+    };
+    if combine(0..residuals.len()).is_zero() {
+        return None;
+    }
+    // The first violation is in [lo, hi).
+    let (mut lo, mut hi) = (0, residuals.len());
+    while hi - lo > 1 {
+        let mid = (lo + hi) / 2;
+        if combine(lo..mid).is_zero() {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(lo)
+}
+
+/// `xs[i] * ys[i]`, with all products of two shared values taken together, in one round.
+fn products<F: PrimeField, S: FieldShare<F>>(
+    mut xs: Vec<MpcField<F, S>>,
+    ys: &[MpcField<F, S>],
+) -> Vec<MpcField<F, S>> {
+    let shared: Vec<usize> = (0..xs.len())
+        .filter(|&i| xs[i].is_shared() && ys[i].is_shared())
+        .collect();
+    let mut sxs: Vec<_> = shared.iter().map(|&i| xs[i]).collect();
+    let sys: Vec<_> = shared.iter().map(|&i| ys[i]).collect();
+    if !sxs.is_empty() {
+        MpcField::batch_product_in_place(&mut sxs, &sys);
+    }
+    for (x, y) in xs.iter_mut().zip(ys) {
+        if !(x.is_shared() && y.is_shared()) {
+            *x *= *y;
+        }
+    }
+    for (i, p) in shared.into_iter().zip(sxs) {
+        xs[i] = p;
+    }
+    xs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+    use ark_relations::{
+        lc,
+        r1cs::{ConstraintSystemRef, SynthesisError},
+    };
+    use ark_std::test_rng;
+    use mpc_algebra::honest_but_curious::MpcField as MF;
+    use mpc_net::testing::run_parties;
+
+    type Fr = ark_bls12_377::Fr;
+
+    /// Each link of the chain is the square of the one before.
+    struct Chain(Vec<MF<Fr>>);
+
+    impl ConstraintSynthesizer<MF<Fr>> for Chain {
+        fn generate_constraints(
+            self,
+            cs: ConstraintSystemRef<MF<Fr>>,
+        ) -> Result<(), SynthesisError> {
+            let vars = self
+                .0
+                .iter()
+                .map(|x| cs.new_witness_variable(|| Ok(*x)))
+                .collect::<Result<Vec<_>, _>>()?;
+            for w in vars.windows(2) {
+                cs.enforce_constraint(lc!() + w[0], lc!() + w[0], lc!() + w[1])?;
+            }
+            Ok(())
+        }
+    }
+
+    /// Check a chain of 9 squarings, with the `bad`th (if any) off by one.
+    fn check(bad: Option<usize>) {
+        let rng = &mut test_rng();
+        let mut chain = vec![Fr::from(3u8)];
+        for i in 0..9 {
+            let mut y = chain[i].square();
+            if bad == Some(i) {
+                y += Fr::from(1u8);
+            }
+            chain.push(y);
+        }
+        let chain = Chain(MF::king_share_batch(chain, rng));
+        let coeff_rng = &mut test_rng();
+        assert_eq!(first_violated(chain, || Fr::rand(coeff_rng)), bad);
+    }
+
+    #[test]
+    fn hbc_first_violated() {
+        run_parties("witness::tests::hbc_first_violated", 2, || {
+            check(None);
+            check(Some(0));
+            check(Some(5));
+            check(Some(8));
+        });
+    }
+}
@@ -0,0 +1,119 @@
+//! Shared witnesses, kept to prove the same statement again.
+//!
+//! Sharing the inputs and computing the witness over shares is much of an MPC proof's cost, and
+//! a statement is sometimes proved more than once: say, a verifier failed to receive or check the
+//! proof, and asks for it again. A [WitnessCache] keeps the shared witnesses of the last few
+//! statements, under a [WitnessKey]: a digest of the circuit (its
+//! [hash](crate::envelope::circuit_hash)) and of its inputs, as the parties name them, e.g. by a
+//! digest of the commitments that the input parties published. The parties must agree on the key;
+//! a witness found under it is only reused if every party has it, since one that computes the
+//! witness again takes part in rounds that the others would skip.
+//!
+//! Each party keeps only its own shares, so nothing is revealed by keeping them; but reusing them
+//! is only sound if the inputs the key names are the inputs they are shares of, which the key does
+//! not check.
+use blake2::{Blake2s, Digest};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
+
+/// What a shared witness is kept under: a digest of the circuit and of its inputs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WitnessKey([u8; 32]);
+
+impl WitnessKey {
+    /// The key for the circuit with hash `circuit_hash`, on the inputs named by `inputs`.
+    pub fn new(circuit_hash: &str, inputs: &str) -> Self {
+        let mut h = Blake2s::new();
+        h.update(b"mpc-snarks witness");
+        for s in &[circuit_hash, inputs] {
+            h.update(&(s.len() as u64).to_le_bytes());
+            h.update(s.as_bytes());
+        }
+        Self(h.finalize().into())
+    }
+}
+
+impl Display for WitnessKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::envelope::to_hex(&self.0))
+    }
+}
+
+/// Up to `capacity` witnesses (of any types), the least recently used evicted first.
+pub struct WitnessCache {
+    capacity: usize,
+    /// The most recently used last.
+    entries: VecDeque<(WitnessKey, Box<dyn Any>)>,
+}
+
+impl WitnessCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// A copy of the witness kept under `key`, if there is one, of type `T`.
+    pub fn get<T: Clone + 'static>(&mut self, key: &WitnessKey) -> Option<T> {
+        let i = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(i).unwrap();
+        let value = entry.1.downcast_ref::<T>().cloned();
+        self.entries.push_back(entry);
+        value
+    }
+
+    /// Keep `value` under `key`, replacing what was kept under it, and evicting the least
+    /// recently used witness if there are too many.
+    pub fn insert<T: 'static>(&mut self, key: WitnessKey, value: T) {
+        self.entries.retain(|(k, _)| *k != key);
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, Box::new(value)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let key = |inputs| WitnessKey::new("00", inputs);
+        assert_ne!(key("a"), WitnessKey::new("00a", ""));
+        let mut cache = WitnessCache::new(2);
+        cache.insert(key("a"), vec![1u8]);
+        cache.insert(key("b"), vec![2u8]);
+        assert_eq!(cache.get::<Vec<u8>>(&key("a")), Some(vec![1]));
+        // Of another type, it is not found, but still kept.
+        assert_eq!(cache.get::<u8>(&key("a")), None);
+        cache.insert(key("c"), vec![3u8]);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get::<Vec<u8>>(&key("b")), None);
+        assert_eq!(cache.get::<Vec<u8>>(&key("a")), Some(vec![1]));
+        cache.insert(key("a"), vec![4u8]);
+        assert_eq!(cache.get::<Vec<u8>>(&key("a")), Some(vec![4]));
+        assert_eq!(cache.len(), 2);
+
+        let mut off = WitnessCache::new(0);
+        off.insert(key("a"), 1u8);
+        assert!(off.is_empty());
+    }
+}
@@ -0,0 +1,352 @@
+//! Small canonical circuits, proved in MPC under every share type and with both proof systems:
+//! the correctness gate that a share backend must pass.
+//!
+//! Each circuit's witness is computed by the same code over plain and shared field elements, so
+//! the shared computation exercises the backend's additions, multiplications, inversions and bit
+//! decompositions. The provers reveal the outputs and the proof, which must verify under the local
+//! verifying key, on exactly the outputs that the local computation gives.
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::{BigInteger, Field, One, PrimeField, UniformRand};
+use ark_marlin::Marlin;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::marlin_pc::MarlinKZG10;
+use ark_relations::{
+    lc,
+    r1cs::{
+        ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, LinearCombination,
+        SynthesisError, Variable,
+    },
+};
+use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+use blake2::Blake2s;
+use mpc_algebra::{
+    share::gsz20::GszPairingShare, AdditivePairingShare, MpcField, MpcPairingEngine, PairingShare,
+    Reveal, SpdzPairingShare,
+};
+use mpc_net::testing::run_parties;
+use mpc_snarks::groth::prover::create_random_proof;
+
+type E = Bls12_377;
+type MpcE<S> = MpcPairingEngine<E, S>;
+type MFr<S> = MpcField<Fr, <S as PairingShare<E>>::FrShare>;
+type LocalMarlin = Marlin<Fr, MarlinKZG10<E, DensePolynomial<Fr>>, Blake2s>;
+type MpcMarlin<S> = Marlin<MFr<S>, MarlinKZG10<MpcE<S>, DensePolynomial<MFr<S>>>, Blake2s>;
+
+/// Leaves of the trees, and links of the inverse chain.
+const N: usize = 8;
+/// Selections in the select circuit.
+const SELECTS: usize = 4;
+/// Bits of the decomposed value.
+const BITS: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    /// Sums of pairs, up to the total.
+    AdditionTree,
+    /// Products of pairs, up to the product of all.
+    MultiplicationTree,
+    /// `x_{i+1} = 1 / (x_i + 1)`.
+    InverseChain,
+    /// `b ? x : y`, as `y + b * (x - y)`.
+    ConditionalSelect,
+    /// A value's bits, and (as the output) how many are set.
+    BitDecomposition,
+}
+
+const KINDS: [Kind; 5] = [
+    Kind::AdditionTree,
+    Kind::MultiplicationTree,
+    Kind::InverseChain,
+    Kind::ConditionalSelect,
+    Kind::BitDecomposition,
+];
+
+impl Kind {
+    /// Private inputs for this circuit.
+    fn inputs<R: Rng>(self, rng: &mut R) -> Vec<Fr> {
+        match self {
+            Kind::AdditionTree | Kind::MultiplicationTree => {
+                (0..N).map(|_| Fr::rand(rng)).collect()
+            }
+            Kind::InverseChain => vec![Fr::rand(rng)],
+            Kind::ConditionalSelect => (0..SELECTS)
+                .flat_map(|i| vec![Fr::rand(rng), Fr::rand(rng), Fr::from((i % 2) as u8)])
+                .collect(),
+            Kind::BitDecomposition => vec![Fr::from(rng.gen::<u16>())],
+        }
+    }
+
+    /// How many witness variables the circuit has.
+    fn values(self) -> usize {
+        match self {
+            Kind::AdditionTree | Kind::MultiplicationTree => 2 * N - 1,
+            Kind::InverseChain => N + 1,
+            Kind::ConditionalSelect => 5 * SELECTS,
+            Kind::BitDecomposition => BITS + 2,
+        }
+    }
+
+    /// The witness variables that are revealed, as the public inputs.
+    fn outputs(self) -> Vec<usize> {
+        match self {
+            Kind::AdditionTree | Kind::MultiplicationTree => vec![2 * N - 2],
+            Kind::InverseChain => vec![N],
+            Kind::ConditionalSelect => (0..SELECTS).map(|i| 5 * i + 4).collect(),
+            Kind::BitDecomposition => vec![BITS + 1],
+        }
+    }
+
+    /// The witness, from the inputs; `bits` decomposes a value into its `BITS` low bits.
+    fn assign<F: Field>(self, inputs: &[F], bits: impl Fn(F) -> Vec<F>) -> Vec<F> {
+        let mut v = inputs.to_vec();
+        match self {
+            Kind::AdditionTree => {
+                for k in 0..N - 1 {
+                    v.push(v[2 * k] + v[2 * k + 1]);
+                }
+            }
+            Kind::MultiplicationTree => {
+                for k in 0..N - 1 {
+                    v.push(v[2 * k] * v[2 * k + 1]);
+                }
+            }
+            Kind::InverseChain => {
+                for i in 0..N {
+                    v.push((v[i] + F::one()).inverse().unwrap());
+                }
+            }
+            Kind::ConditionalSelect => {
+                v.clear();
+                for s in inputs.chunks(3) {
+                    let (x, y, b) = (s[0], s[1], s[2]);
+                    let d = b * (x - y);
+                    v.extend_from_slice(&[x, y, b, d, y + d]);
+                }
+            }
+            Kind::BitDecomposition => {
+                let bits = bits(v[0]);
+                let set = bits.iter().fold(F::zero(), |acc, b| acc + b);
+                v.extend(bits);
+                v.push(set);
+            }
+        }
+        assert_eq!(v.len(), self.values());
+        v
+    }
+
+    fn constrain<F: Field>(
+        self,
+        cs: &ConstraintSystemRef<F>,
+        v: &[Variable],
+    ) -> Result<(), SynthesisError> {
+        let one = || lc!() + Variable::One;
+        match self {
+            Kind::AdditionTree => {
+                for k in 0..N - 1 {
+                    cs.enforce_constraint(
+                        lc!() + v[2 * k] + v[2 * k + 1],
+                        one(),
+                        lc!() + v[N + k],
+                    )?;
+                }
+            }
+            Kind::MultiplicationTree => {
+                for k in 0..N - 1 {
+                    cs.enforce_constraint(
+                        lc!() + v[2 * k],
+                        lc!() + v[2 * k + 1],
+                        lc!() + v[N + k],
+                    )?;
+                }
+            }
+            Kind::InverseChain => {
+                for i in 0..N {
+                    cs.enforce_constraint(lc!() + v[i + 1], lc!() + v[i] + Variable::One, one())?;
+                }
+            }
+            Kind::ConditionalSelect => {
+                for s in v.chunks(5) {
+                    let (x, y, b, d, z) = (s[0], s[1], s[2], s[3], s[4]);
+                    cs.enforce_constraint(lc!() + b, lc!() + b, lc!() + b)?;
+                    cs.enforce_constraint(lc!() + b, lc!() + x - y, lc!() + d)?;
+                    cs.enforce_constraint(lc!() + y + d, one(), lc!() + z)?;
+                }
+            }
+            Kind::BitDecomposition => {
+                let bits = &v[1..=BITS];
+                let mut sum = LinearCombination::zero();
+                let mut coeff = F::one();
+                for b in bits {
+                    cs.enforce_constraint(lc!() + *b, lc!() + *b, lc!() + *b)?;
+                    sum = sum + (coeff, *b);
+                    coeff.double_in_place();
+                }
+                cs.enforce_constraint(sum, one(), lc!() + v[0])?;
+                let set = bits.iter().fold(lc!(), |acc, b| acc + *b);
+                cs.enforce_constraint(set, one(), lc!() + v[BITS + 1])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct Canonical<F: Field> {
+    kind: Kind,
+    values: Vec<Option<F>>,
+}
+
+impl<F: Field> Canonical<F> {
+    fn without_data(kind: Kind) -> Self {
+        Self {
+            kind,
+            values: vec![None; kind.values()],
+        }
+    }
+
+    fn new(kind: Kind, inputs: &[F], bits: impl Fn(F) -> Vec<F>) -> Self {
+        Self {
+            kind,
+            values: kind.assign(inputs, bits).into_iter().map(Some).collect(),
+        }
+    }
+
+    fn outputs(&self) -> Vec<F> {
+        let outputs = self.kind.outputs().into_iter();
+        outputs.map(|i| self.values[i].unwrap()).collect()
+    }
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for Canonical<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let missing = || SynthesisError::AssignmentMissing;
+        let mut vars = Vec::new();
+        for v in &self.values {
+            vars.push(cs.new_witness_variable(|| v.ok_or_else(missing))?);
+        }
+        for i in self.kind.outputs() {
+            let v = self.values[i];
+            let y = cs.new_input_variable(|| v.ok_or_else(missing))?;
+            cs.enforce_constraint(lc!() + vars[i], lc!() + Variable::One, lc!() + y)?;
+        }
+        self.kind.constrain(&cs, &vars)
+    }
+}
+
+fn local_bits(x: Fr) -> Vec<Fr> {
+    let bits = x.into_repr().to_bits_le();
+    bits[..BITS].iter().map(|b| Fr::from(*b)).collect()
+}
+
+/// Each circuit without data (for setup), with its local witness, and with its witness computed
+/// on inputs shared by the king.
+fn circuits<S: PairingShare<E>>(
+    rng: &mut StdRng,
+) -> Vec<(Canonical<Fr>, Canonical<Fr>, Canonical<MFr<S>>)> {
+    KINDS
+        .iter()
+        .map(|&kind| {
+            let inputs = kind.inputs(rng);
+            let local = Canonical::new(kind, &inputs, local_bits);
+            let cs = ConstraintSystem::new_ref();
+            local.clone().generate_constraints(cs.clone()).unwrap();
+            assert!(cs.is_satisfied().unwrap(), "{:?}", kind);
+            let shared = MFr::<S>::king_share_batch(inputs, rng);
+            let shared = Canonical::new(kind, &shared, |x| x.bit_decomposition(BITS));
+            (Canonical::without_data(kind), local, shared)
+        })
+        .collect()
+}
+
+/// The outputs, revealed, which must be the local ones.
+fn revealed<S: PairingShare<E>>(local: &Canonical<Fr>, shared: &Canonical<MFr<S>>) -> Vec<Fr> {
+    let outputs = shared.outputs().reveal();
+    assert_eq!(outputs, local.outputs(), "{:?}", local.kind);
+    outputs
+}
+
+fn check_groth16<S: PairingShare<E>>() {
+    let rng = &mut StdRng::seed_from_u64(0);
+    for (setup, local, shared) in circuits::<S>(rng) {
+        let pk = ark_groth16::generate_random_parameters::<E, _, _>(setup, rng).unwrap();
+        let pvk = ark_groth16::prepare_verifying_key(&pk.vk);
+        let mpc_pk: ark_groth16::ProvingKey<MpcE<S>> = Reveal::from_public(pk);
+        let outputs = revealed::<S>(&local, &shared);
+        let proof = create_random_proof(shared, &mpc_pk, rng).unwrap().reveal();
+        assert!(ark_groth16::verify_proof(&pvk, &proof, &outputs).unwrap());
+        let mut wrong = outputs;
+        wrong[0] += Fr::one();
+        assert!(!ark_groth16::verify_proof(&pvk, &proof, &wrong).unwrap());
+    }
+}
+
+fn check_marlin<S: PairingShare<E>>() {
+    let rng = &mut StdRng::seed_from_u64(0);
+    let srs = LocalMarlin::universal_setup(100, 100, 100, rng).unwrap();
+    for (setup, local, shared) in circuits::<S>(rng) {
+        let (pk, vk) = LocalMarlin::index(&srs, setup).unwrap();
+        let mpc_pk = Reveal::from_public(pk);
+        let outputs = revealed::<S>(&local, &shared);
+        let proof = MpcMarlin::<S>::prove(&mpc_pk, shared, rng)
+            .unwrap()
+            .reveal();
+        assert!(LocalMarlin::verify(&vk, &outputs, &proof, rng).unwrap());
+        let mut wrong = outputs;
+        wrong[0] += Fr::one();
+        assert!(!LocalMarlin::verify(&vk, &wrong, &proof, rng).unwrap());
+    }
+}
+
+#[test]
+fn hbc_groth16_canonical() {
+    run_parties(
+        "hbc_groth16_canonical",
+        3,
+        check_groth16::<AdditivePairingShare<E>>,
+    );
+}
+
+#[test]
+fn spdz_groth16_canonical() {
+    run_parties(
+        "spdz_groth16_canonical",
+        2,
+        check_groth16::<SpdzPairingShare<E>>,
+    );
+}
+
+#[test]
+fn gsz_groth16_canonical() {
+    run_parties(
+        "gsz_groth16_canonical",
+        3,
+        check_groth16::<GszPairingShare<E>>,
+    );
+}
+
+#[test]
+fn hbc_marlin_canonical() {
+    run_parties(
+        "hbc_marlin_canonical",
+        3,
+        check_marlin::<AdditivePairingShare<E>>,
+    );
+}
+
+#[test]
+fn spdz_marlin_canonical() {
+    run_parties(
+        "spdz_marlin_canonical",
+        2,
+        check_marlin::<SpdzPairingShare<E>>,
+    );
+}
+
+#[test]
+fn gsz_marlin_canonical() {
+    run_parties(
+        "gsz_marlin_canonical",
+        3,
+        check_marlin::<GszPairingShare<E>>,
+    );
+}
@@ -0,0 +1,77 @@
+//! Parties that drop out partway through a proof, under GSZ20 with four parties (so that any three
+//! can carry on).
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_groth16::{generate_random_parameters, prepare_verifying_key, verify_proof, ProvingKey};
+use ark_std::UniformRand;
+use mpc_algebra::{
+    share::gsz20::GszPairingShare, MpcField, MpcPairingEngine, PairingShare, Reveal,
+};
+use mpc_net::faults::{self, Fault, CRASH_EXIT};
+use mpc_net::testing::{run_parties_checked, Exit};
+use mpc_net::{MpcMultiNet, MpcNet};
+use mpc_snarks::{groth::prover::create_random_proof, silly::MySillyCircuit};
+use std::time::Duration;
+
+type E = Bls12_377;
+type S = GszPairingShare<E>;
+type MFr = MpcField<Fr, <S as PairingShare<E>>::FrShare>;
+
+/// Each party sends the king five messages while proving; `crasher` crashes instead of sending
+/// its third.
+fn prove_with_crash(crasher: usize) {
+    MpcMultiNet::set_max_dropouts(1);
+    let rng = &mut ark_std::test_rng();
+    let params =
+        generate_random_parameters::<E, _, _>(MySillyCircuit { a: None, b: None }, rng).unwrap();
+    let pvk = prepare_verifying_key(&params.vk);
+    let pk: ProvingKey<MpcPairingEngine<E, S>> = ProvingKey::from_public(params);
+    let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+    let circ = MySillyCircuit {
+        a: Some(MFr::king_share(a, rng)),
+        b: Some(MFr::king_share(b, rng)),
+    };
+    if MpcMultiNet::party_id() == crasher {
+        faults::inject(2, Fault::Crash);
+    }
+    let proof = create_random_proof(circ, &pk, rng).unwrap().reveal();
+    assert!(verify_proof(&pvk, &proof, &[a * b]).unwrap());
+    assert_eq!(MpcMultiNet::stats().dropouts, vec![crasher]);
+}
+
+#[test]
+fn gsz_proof_survives_dropout() {
+    run_parties_checked(
+        "gsz_proof_survives_dropout",
+        4,
+        Duration::from_secs(60),
+        |_| (),
+        || prove_with_crash(3),
+        |exits: Vec<Exit>| {
+            assert_eq!(exits[3].code, Some(CRASH_EXIT), "{:?}", exits);
+            assert!(exits[..3].iter().all(Exit::success), "{:?}", exits);
+        },
+    );
+}
+
+/// The king cannot drop out: the others would need another round to agree on whether it answered
+/// them all. They abort, naming it, rather than wait.
+#[test]
+fn gsz_proof_aborts_without_king() {
+    run_parties_checked(
+        "gsz_proof_aborts_without_king",
+        4,
+        Duration::from_secs(60),
+        |_| (),
+        || prove_with_crash(0),
+        |exits: Vec<Exit>| {
+            assert_eq!(exits[0].code, Some(CRASH_EXIT), "{:?}", exits);
+            for e in &exits[1..] {
+                assert!(
+                    !e.hung && e.stderr.contains("party 0 dropped out"),
+                    "{:?}",
+                    exits
+                );
+            }
+        },
+    );
+}
@@ -0,0 +1,253 @@
+//! MPC proofs, byte for byte against local ones: with all randomness fixed, the lifted provers
+//! must compute exactly the function that the local provers do.
+//!
+//! The provers' randomness is shared, each party's draws being its additive shares of it. So
+//! that it is the local prover's randomness, every party draws shares from a seeded rng, and the
+//! king draws whatever makes them sum to the local prover's draws. (A share of zero would not do:
+//! a party whose share of a polynomial is zero would disagree with the others on its degree.)
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_ff::{Field, UniformRand};
+use ark_marlin::Marlin;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::marlin_pc::MarlinKZG10;
+use ark_relations::{
+    lc,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{rngs::StdRng, Error, RngCore, SeedableRng};
+use blake2::Blake2s;
+use mpc_algebra::{
+    AdditivePairingShare, MpcField, MpcPairingEngine, PairingShare, Reveal, SpdzPairingShare,
+};
+use mpc_net::testing::run_parties;
+use mpc_net::{MpcMultiNet, MpcNet};
+use mpc_snarks::groth::prover::create_random_proof;
+use mpc_snarks::silly::MySillyCircuit;
+use std::collections::VecDeque;
+
+type E = Bls12_377;
+type MpcE<S> = MpcPairingEngine<E, S>;
+type MFr<S> = MpcField<Fr, <S as PairingShare<E>>::FrShare>;
+type LocalMarlin = Marlin<Fr, MarlinKZG10<E, DensePolynomial<Fr>>, Blake2s>;
+type MpcMarlin<S> = Marlin<MFr<S>, MarlinKZG10<MpcE<S>, DensePolynomial<MFr<S>>>, Blake2s>;
+
+const SEED: u64 = 7;
+
+fn party_rng(party: usize) -> StdRng {
+    StdRng::seed_from_u64(SEED + 1 + party as u64)
+}
+
+/// A prover's rng, whose draws are this party's shares of the local prover's draws.
+///
+/// The provers draw only field elements, each of which is sampled from the limbs of its
+/// (Montgomery) representation, in which addition is linear. So the king samples the local draw
+/// and every other party's, and returns the limbs of the difference.
+enum ProverRng {
+    King {
+        local: StdRng,
+        others: Vec<StdRng>,
+        limbs: VecDeque<u64>,
+    },
+    Other(StdRng),
+}
+
+impl ProverRng {
+    fn new() -> Self {
+        if MpcMultiNet::am_king() {
+            ProverRng::King {
+                local: StdRng::seed_from_u64(SEED),
+                others: (1..MpcMultiNet::n_parties()).map(party_rng).collect(),
+                limbs: VecDeque::new(),
+            }
+        } else {
+            ProverRng::Other(party_rng(MpcMultiNet::party_id()))
+        }
+    }
+}
+
+impl RngCore for ProverRng {
+    fn next_u32(&mut self) -> u32 {
+        unreachable!("the provers draw only field elements")
+    }
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            ProverRng::King {
+                local,
+                others,
+                limbs,
+            } => {
+                if limbs.is_empty() {
+                    let mut share = Fr::rand(local);
+                    for r in others {
+                        share -= Fr::rand(r);
+                    }
+                    limbs.extend(share.0 .0.iter().copied());
+                }
+                limbs.pop_front().unwrap()
+            }
+            ProverRng::Other(r) => r.next_u64(),
+        }
+    }
+    fn fill_bytes(&mut self, _: &mut [u8]) {
+        unreachable!("the provers draw only field elements")
+    }
+    fn try_fill_bytes(&mut self, _: &mut [u8]) -> Result<(), Error> {
+        unreachable!("the provers draw only field elements")
+    }
+}
+
+/// `n` squarings of a secret, the last of which is public.
+#[derive(Clone)]
+struct Squarings<F: Field> {
+    chain: Vec<Option<F>>,
+}
+
+impl<F: Field> Squarings<F> {
+    fn without_data(n: usize) -> Self {
+        Self {
+            chain: vec![None; n + 1],
+        }
+    }
+    fn new(x: F, n: usize) -> Self {
+        Self {
+            chain: std::iter::successors(Some(x), |x| Some(x.square()))
+                .take(n + 1)
+                .map(Some)
+                .collect(),
+        }
+    }
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for Squarings<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let n = self.chain.len() - 1;
+        let mut vars = Vec::new();
+        for (i, x) in self.chain.into_iter().enumerate() {
+            let x = || x.ok_or(SynthesisError::AssignmentMissing);
+            vars.push(if i == n {
+                cs.new_input_variable(x)?
+            } else {
+                cs.new_witness_variable(x)?
+            });
+        }
+        for i in 0..n {
+            cs.enforce_constraint(lc!() + vars[i], lc!() + vars[i], lc!() + vars[i + 1])?;
+        }
+        Ok(())
+    }
+}
+
+fn bytes(x: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut out = Vec::new();
+    x.serialize(&mut out).unwrap();
+    out
+}
+
+/// Each circuit without data (for setup), with a witness, and with it shared by the king.
+fn circuits<S: PairingShare<E>>(
+    rng: &mut StdRng,
+) -> Vec<(Circuit<Fr>, Circuit<Fr>, Circuit<MFr<S>>)> {
+    let (a, b, x) = (Fr::rand(rng), Fr::rand(rng), Fr::rand(rng));
+    let shared = MFr::<S>::king_share_batch(vec![a, b, x], rng);
+    vec![
+        (
+            Circuit::Silly(MySillyCircuit { a: None, b: None }),
+            Circuit::Silly(MySillyCircuit {
+                a: Some(a),
+                b: Some(b),
+            }),
+            Circuit::Silly(MySillyCircuit {
+                a: Some(shared[0]),
+                b: Some(shared[1]),
+            }),
+        ),
+        (
+            Circuit::Squarings(Squarings::without_data(20)),
+            Circuit::Squarings(Squarings::new(x, 20)),
+            Circuit::Squarings(mpc_squarings::<S>(x, shared[2], 20)),
+        ),
+    ]
+}
+
+/// The squarings of shared `x` (which is `local`), shared; the last revealed, as the output.
+fn mpc_squarings<S: PairingShare<E>>(local: Fr, x: MFr<S>, n: usize) -> Squarings<MFr<S>> {
+    let mut circ = Squarings::new(x, n);
+    circ.chain[n] = Some(MFr::<S>::from_public(local.pow(&[1u64 << n])));
+    circ
+}
+
+#[derive(Clone)]
+enum Circuit<F: Field> {
+    Silly(MySillyCircuit<F>),
+    Squarings(Squarings<F>),
+}
+
+impl<F: Field> ConstraintSynthesizer<F> for Circuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        match self {
+            Circuit::Silly(c) => c.generate_constraints(cs),
+            Circuit::Squarings(c) => c.generate_constraints(cs),
+        }
+    }
+}
+
+fn check_groth16<S: PairingShare<E>>() {
+    let rng = &mut StdRng::seed_from_u64(0);
+    for (setup, local, shared) in circuits::<S>(rng) {
+        let pk = ark_groth16::generate_random_parameters::<E, _, _>(setup, rng).unwrap();
+        let expected =
+            ark_groth16::create_random_proof(local, &pk, &mut StdRng::seed_from_u64(SEED)).unwrap();
+        let mpc_pk: ark_groth16::ProvingKey<MpcE<S>> = Reveal::from_public(pk);
+        let proof = create_random_proof(shared, &mpc_pk, &mut ProverRng::new())
+            .unwrap()
+            .reveal();
+        assert_eq!(bytes(&proof), bytes(&expected));
+    }
+}
+
+fn check_marlin<S: PairingShare<E>>() {
+    let rng = &mut StdRng::seed_from_u64(0);
+    let srs = LocalMarlin::universal_setup(100, 50, 100, rng).unwrap();
+    for (setup, local, shared) in circuits::<S>(rng) {
+        let (pk, _) = LocalMarlin::index(&srs, setup).unwrap();
+        let expected = LocalMarlin::prove(&pk, local, &mut StdRng::seed_from_u64(SEED)).unwrap();
+        let mpc_pk = Reveal::from_public(pk);
+        let proof = MpcMarlin::<S>::prove(&mpc_pk, shared, &mut ProverRng::new())
+            .unwrap()
+            .reveal();
+        assert_eq!(bytes(&proof), bytes(&expected));
+    }
+}
+
+#[test]
+fn hbc_groth16_bytes() {
+    run_parties(
+        "hbc_groth16_bytes",
+        3,
+        check_groth16::<AdditivePairingShare<E>>,
+    );
+}
+
+#[test]
+fn spdz_groth16_bytes() {
+    run_parties(
+        "spdz_groth16_bytes",
+        2,
+        check_groth16::<SpdzPairingShare<E>>,
+    );
+}
+
+#[test]
+fn hbc_marlin_bytes() {
+    run_parties(
+        "hbc_marlin_bytes",
+        3,
+        check_marlin::<AdditivePairingShare<E>>,
+    );
+}
+
+#[test]
+fn spdz_marlin_bytes() {
+    run_parties("spdz_marlin_bytes", 2, check_marlin::<SpdzPairingShare<E>>);
+}
@@ -0,0 +1,59 @@
+//! Proofs made in MPC, checked by the plain (non-MPC) verifiers, from nothing but their test
+//! vectors: what a verifier outside this repository would be given.
+//!
+//! The vectors in `tests/vectors` are for the `squaring` circuit of size 4, proven by two parties
+//! with additive shares; to make them again, run both parties of
+//!
+//! ```text
+//! proof -c squaring -p <groth16|marlin> --computation-size 4 gen-test-vectors \
+//!     --out tests/vectors/<groth16|marlin>_bls12_377.json --hosts data/2 --party <i> --alg hbc
+//! ```
+use ark_bls12_377::{Bls12_377, Fr};
+use ark_marlin::{IndexVerifierKey, Marlin, Proof};
+use ark_poly::univariate::DensePolynomial;
+use ark_poly_commit::marlin_pc::MarlinKZG10;
+use ark_std::test_rng;
+use blake2::Blake2s;
+use mpc_snarks::envelope::TestVector;
+use std::path::Path;
+
+type KzgPC = MarlinKZG10<Bls12_377, DensePolynomial<Fr>>;
+
+fn load(name: &str) -> (TestVector, Vec<Fr>) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/vectors")
+        .join(name);
+    let vector = TestVector::load(&path).unwrap();
+    assert_eq!(vector.envelope.header.curve, "bls12_377");
+    let inputs = vector.envelope.public_inputs::<Fr>().unwrap();
+    (vector, inputs)
+}
+
+#[test]
+fn groth16_vector_verifies() {
+    let (vector, mut inputs) = load("groth16_bls12_377.json");
+    assert_eq!(vector.envelope.header.proof_system, "groth16");
+    let vk = vector.vk::<ark_groth16::VerifyingKey<Bls12_377>>().unwrap();
+    let pvk = ark_groth16::prepare_verifying_key(&vk);
+    let proof = vector
+        .envelope
+        .proof::<ark_groth16::Proof<Bls12_377>>()
+        .unwrap();
+    assert!(ark_groth16::verify_proof(&pvk, &proof, &inputs).unwrap());
+
+    inputs[0] += Fr::from(1u8);
+    assert!(!ark_groth16::verify_proof(&pvk, &proof, &inputs).unwrap());
+}
+
+#[test]
+fn marlin_vector_verifies() {
+    let (vector, mut inputs) = load("marlin_bls12_377.json");
+    assert_eq!(vector.envelope.header.proof_system, "marlin-kzg-blake2s");
+    let vk = vector.vk::<IndexVerifierKey<Fr, KzgPC>>().unwrap();
+    let proof = vector.envelope.proof::<Proof<Fr, KzgPC>>().unwrap();
+    let rng = &mut test_rng();
+    assert!(Marlin::<Fr, KzgPC, Blake2s>::verify(&vk, &inputs, &proof, rng).unwrap());
+
+    inputs[0] += Fr::from(1u8);
+    assert!(!Marlin::<Fr, KzgPC, Blake2s>::verify(&vk, &inputs, &proof, rng).unwrap());
+}
@@ -0,0 +1,16 @@
+pragma circom 2.0.0;
+
+// `MySillyCircuit`: a * b = c, six times, with c public.
+template Silly() {
+    signal input a;
+    signal input b;
+    signal output c;
+    c <== a * b;
+    a * b === c;
+    a * b === c;
+    a * b === c;
+    a * b === c;
+    a * b === c;
+}
+
+component main = Silly();
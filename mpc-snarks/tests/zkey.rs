@@ -0,0 +1,56 @@
+//! A circom ceremony's Groth16 key, read from its `.zkey`, proves in MPC.
+//!
+//! `tests/vectors/silly_bn254.zkey` is a key for `MySillyCircuit` over BN254 (snarkjs's `bn128`),
+//! in snarkjs's layout. It was written by `groth::zkey::tests::write_bn254_vector` (run with
+//! `--ignored`) rather than by snarkjs; `silly.circom` is the same circuit, for which snarkjs
+//! makes a key of its own with
+//!
+//! ```text
+//! circom silly.circom --r1cs --O0
+//! snarkjs powersoftau new bn128 4 pot.ptau
+//! snarkjs powersoftau prepare phase2 pot.ptau pot_final.ptau
+//! snarkjs groth16 setup silly.r1cs pot_final.ptau silly_bn254.zkey
+//! ```
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{prepare_verifying_key, verify_proof, ProvingKey};
+use ark_std::{test_rng, UniformRand};
+use mpc_algebra::{AdditivePairingShare, MpcField, MpcPairingEngine, Reveal};
+use mpc_net::testing::run_parties;
+use mpc_snarks::groth::prover::create_random_proof_with_reduction;
+use mpc_snarks::groth::r1cs_to_qap::CircomReduction;
+use mpc_snarks::groth::zkey::Zkey;
+use mpc_snarks::silly::MySillyCircuit;
+use std::path::Path;
+
+type E = MpcPairingEngine<Bn254, AdditivePairingShare<Bn254>>;
+
+fn load() -> Zkey<Bn254> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors/silly_bn254.zkey");
+    let zkey = Zkey::read(&std::fs::read(path).unwrap()[..]).unwrap();
+    zkey.check_circuit(MySillyCircuit::<Fr> { a: None, b: None })
+        .unwrap();
+    zkey
+}
+
+#[test]
+fn hbc_proves_with_bn254_zkey() {
+    run_parties("hbc_proves_with_bn254_zkey", 2, || {
+        let zkey = load();
+        assert_eq!((zkey.n_public, zkey.domain_size), (1, 8));
+        let pvk = prepare_verifying_key(&zkey.pk.vk);
+        let pk = ProvingKey::<E>::from_public(zkey.pk);
+        let rng = &mut test_rng();
+        let (a, b) = (MpcField::rand(rng), MpcField::rand(rng));
+        let c = (a * b).reveal();
+        let circuit = MySillyCircuit {
+            a: Some(a),
+            b: Some(b),
+        };
+        let proof =
+            create_random_proof_with_reduction::<E, _, _, CircomReduction>(circuit, &pk, rng)
+                .unwrap()
+                .reveal();
+        assert!(verify_proof(&pvk, &proof, &[c]).unwrap());
+        assert!(!verify_proof(&pvk, &proof, &[c + Fr::from(1u8)]).unwrap());
+    });
+}
@@ -223,7 +223,10 @@ pub struct Proof<G: AffineCurve> {
 
 impl<G: AffineCurve> PCProof for Proof<G> {
     fn size_in_bytes(&self) -> usize {
-        ark_ff::to_bytes![self].unwrap().len()
+        // Computed from the proof's shape, since under MPC it may not be revealed yet.
+        let g = ark_ff::to_bytes![G::zero()].unwrap().len();
+        let f = ark_ff::to_bytes![G::ScalarField::zero()].unwrap().len();
+        (self.l_vec.len() + self.r_vec.len() + 2) * g + 2 * f
     }
 }
 
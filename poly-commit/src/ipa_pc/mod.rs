@@ -3,8 +3,9 @@ use crate::{BatchLCProof, Error, Evaluations, QuerySet, UVPolynomial};
 use crate::{LabeledCommitment, LabeledPolynomial, LinearCombination};
 use crate::{PCCommitterKey, PCRandomness, PCUniversalParams, PolynomialCommitment};
 
-use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
-use ark_ff::{to_bytes, Field, One, PrimeField, UniformRand, Zero};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{to_bytes, Field, One, UniformRand, Zero};
+use mpc_trait::MpcWire;
 use ark_std::rand::RngCore;
 use ark_std::{convert::TryInto, format, marker::PhantomData, vec};
 
@@ -47,11 +48,28 @@ impl<G: AffineCurve, D: Digest, P: UVPolynomial<G::ScalarField>> InnerProductArg
         hiding_generator: Option<G>,
         randomizer: Option<G::ScalarField>,
     ) -> G::Projective {
-        let scalars_bigint = ark_std::cfg_iter!(scalars)
-            .map(|s| s.into_repr())
-            .collect::<Vec<_>>();
-
-        let mut comm = VariableBaseMSM::multi_scalar_mul(comm_key, &scalars_bigint);
+        let comm_key = &comm_key[..ark_std::cmp::min(comm_key.len(), scalars.len())];
+        let scalars = &scalars[..comm_key.len()];
+
+        // Over MPC, the scalars may mix shared and public values (e.g., zero padding), but an MSM
+        // must be over one kind only, so the two kinds are committed separately.
+        let mut comm = if scalars.iter().any(|s| s.is_shared()) {
+            let (shared, public): (Vec<_>, Vec<_>) = comm_key
+                .iter()
+                .zip(scalars)
+                .partition(|(_, s)| s.is_shared());
+            let mut comm = G::Projective::zero();
+            for part in [shared, public].iter() {
+                if !part.is_empty() {
+                    let (bases, scalars): (Vec<G>, Vec<G::ScalarField>) =
+                        part.iter().map(|(b, s)| (**b, **s)).unzip();
+                    comm += &G::multi_scalar_mul(&bases, &scalars);
+                }
+            }
+            comm
+        } else {
+            G::multi_scalar_mul(comm_key, scalars)
+        };
 
         if randomizer.is_some() {
             assert!(hiding_generator.is_some());
@@ -72,7 +90,10 @@ impl<G: AffineCurve, D: Digest, P: UVPolynomial<G::ScalarField>> InnerProductArg
             i += 1;
         }
 
-        challenge.unwrap()
+        // Over MPC, `from_random_bytes` yields a (trivially) shared element; challenges are public.
+        let mut challenge = challenge.unwrap();
+        challenge.publicize();
+        challenge
     }
 
     #[inline]
@@ -539,7 +560,14 @@ where
 
         end_timer!(combine_time);
 
-        let combined_v = combined_polynomial.evaluate(point);
+        // Under MPC the combined polynomial may mix shared and public coefficients, which
+        // `Polynomial::evaluate` rejects, so evaluate it by hand. The evaluation, like everything
+        // hashed into the random oracle below, is part of the proof, so it is revealed at once.
+        let mut combined_v = combined_polynomial
+            .coeffs()
+            .iter()
+            .rfold(G::ScalarField::zero(), |acc, c| acc * point + c);
+        combined_v.publicize();
 
         // Pad the coefficients to the appropriate vector size
         let d = ck.supported_degree();
@@ -568,6 +596,7 @@ where
                 combined_commitment_proj,
                 hiding_commitment_proj,
             ]);
+            batch.publicize();
             hiding_commitment = Some(batch.pop().unwrap());
             combined_commitment = batch.pop().unwrap();
 
@@ -598,6 +627,7 @@ where
             start_timer!(|| format!("Generating proof for degree {} combined polynomial", d + 1));
 
         combined_commitment = combined_commitment_proj.into_affine();
+        combined_commitment.publicize();
 
         // ith challenge
         let mut round_challenge = Self::compute_random_oracle_challenge(
@@ -650,7 +680,8 @@ where
             let r = Self::cm_commit(key_r, coeffs_l, None, None)
                 + &h_prime.scalar_mul(Self::inner_product(coeffs_l, z_r));
 
-            let lr = G::Projective::batch_normalization_into_affine(&[l, r]);
+            let mut lr = G::Projective::batch_normalization_into_affine(&[l, r]);
+            lr.publicize();
             l_vec.push(lr[0]);
             r_vec.push(lr[1]);
 
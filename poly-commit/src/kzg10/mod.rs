@@ -144,12 +144,40 @@ where
         hiding_bound: Option<usize>,
         rng: Option<&mut dyn RngCore>,
     ) -> Result<(Commitment<E>, Randomness<E::Fr, P>), Error> {
+        let mut randomness = Randomness::<E::Fr, P>::empty();
+        if let Some(hiding_degree) = hiding_bound {
+            let mut rng = rng.ok_or(Error::MissingRng)?;
+            let sample_random_poly_time = start_timer!(|| format!(
+                "Sampling a random polynomial of degree {}",
+                hiding_degree
+            ));
+
+            randomness = Randomness::rand(hiding_degree, false, None, &mut rng);
+            end_timer!(sample_random_poly_time);
+        }
+        let commitment = Self::commit_with_randomness(powers, polynomial, &randomness)?;
+        Ok((commitment, randomness))
+    }
+
+    /// Outputs a commitment to `polynomial`, hidden by `randomness` (e.g., randomness to which
+    /// several parties contributed).
+    pub fn commit_with_randomness(
+        powers: &Powers<E>,
+        polynomial: &P,
+        randomness: &Randomness<E::Fr, P>,
+    ) -> Result<Commitment<E>, Error> {
         Self::check_degree_is_too_large(polynomial.degree(), powers.size())?;
+        if randomness.is_hiding() {
+            Self::check_hiding_bound(
+                randomness.blinding_polynomial.degree(),
+                powers.powers_of_gamma_g.len(),
+            )?;
+        }
 
         let commit_time = start_timer!(|| format!(
-            "Committing to polynomial of degree {} with hiding_bound: {:?}",
+            "Committing to polynomial of degree {} with hiding polynomial of degree {}",
             polynomial.degree(),
-            hiding_bound,
+            randomness.blinding_polynomial.degree(),
         ));
 
         let num_leading_zeros = 0;
@@ -162,22 +190,6 @@ where
         );
         end_timer!(msm_time);
 
-        let mut randomness = Randomness::<E::Fr, P>::empty();
-        if let Some(hiding_degree) = hiding_bound {
-            let mut rng = rng.ok_or(Error::MissingRng)?;
-            let sample_random_poly_time = start_timer!(|| format!(
-                "Sampling a random polynomial of degree {}",
-                hiding_degree
-            ));
-
-            randomness = Randomness::rand(hiding_degree, false, None, &mut rng);
-            Self::check_hiding_bound(
-                randomness.blinding_polynomial.degree(),
-                powers.powers_of_gamma_g.len(),
-            )?;
-            end_timer!(sample_random_poly_time);
-        }
-
         let msm_time = start_timer!(|| "MSM to compute commitment to random poly");
         let random_commitment = <E::G1Affine as AffineCurve>::multi_scalar_mul(
             &powers.powers_of_gamma_g,
@@ -189,7 +201,7 @@ where
         commitment.add_assign_mixed(&random_commitment);
 
         end_timer!(commit_time);
-        Ok((Commitment(commitment.into()), randomness))
+        Ok(Commitment(commitment.into()))
     }
 
     /// Compute witness polynomial.
@@ -304,13 +316,16 @@ where
         if let Some(random_v) = proof.random_v {
             inner -= &vk.gamma_g.scalar_mul(random_v);
         }
-        let lhs = E::pairing(inner, vk.h);
-
-        let inner = vk.beta_h.into_projective() - &vk.h.scalar_mul(point);
-        let rhs = E::pairing(proof.w, inner);
+        // e(inner, h) == e(w, beta_h - point * h), as a product of pairings.
+        let beta_h_minus_point: E::G2Affine =
+            (vk.beta_h.into_projective() - &vk.h.scalar_mul(point)).into();
+        let result = E::product_of_pairings_is_one(&[
+            (inner.into(), vk.prepared_h.clone()),
+            (-proof.w, beta_h_minus_point.into()),
+        ]);
 
-        end_timer!(check_time, || format!("Result: {}", lhs == rhs));
-        Ok(lhs == rhs)
+        end_timer!(check_time, || format!("Result: {}", result));
+        Ok(result)
     }
 
     /// Check that each `proof_i` in `proofs` is a valid proof of evaluation for
@@ -360,11 +375,10 @@ where
         end_timer!(to_affine_time);
 
         let pairing_time = start_timer!(|| "Performing product of pairings");
-        let result = E::product_of_pairings(&[
-            (total_w.into(), vk.prepared_beta_h.clone()),
-            (total_c.into(), vk.prepared_h.clone()),
-        ])
-        .is_one();
+        let result = E::product_of_pairings_is_one(&[
+            (total_w, vk.prepared_beta_h.clone()),
+            (total_c, vk.prepared_h.clone()),
+        ]);
         end_timer!(pairing_time);
         end_timer!(check_time, || format!("Result: {}", result));
         Ok(result)
@@ -5,9 +5,14 @@ use ark_poly::univariate::DensePolynomial;
 use mpc_trait::{struct_mpc_wire_simp_impl, MpcWire};
 use mpc_algebra::*;
 
+use ark_poly::UVPolynomial;
+use ark_std::rand::RngCore;
+use std::marker::PhantomData;
 use std::rc::Rc;
 
-use crate::{kzg10, marlin_pc, BatchLCProof, LabeledCommitment, LabeledPolynomial, PCCommitment};
+use crate::{ipa_pc, kzg10, marlin_pc, BatchLCProof, LabeledCommitment, LabeledPolynomial, PCCommitment, PCRandomness};
+use digest::Digest;
+use ipa_pc::InnerProductArgPC;
 use marlin_pc::*;
 
 impl<E: PairingEngine, S: PairingShare<E>> Reveal for Commitment<MpcPairingEngine<E, S>> {
@@ -22,6 +27,31 @@ impl<E: PrimeField, S: FieldShare<E>> Reveal
     struct_reveal_simp_impl!(kzg10::Randomness; blinding_polynomial, _field);
 }
 
+impl<F: PrimeField, S: FieldShare<F>>
+    kzg10::Randomness<MpcField<F, S>, DensePolynomial<MpcField<F, S>>>
+{
+    /// Hiding randomness that every party contributes to: each samples a random polynomial from
+    /// its own `rng`, as [PCRandomness::rand] would locally, and the blinding polynomial is the
+    /// (shared) sum of them all. It is uniform as long as one party's `rng` is, so commitments
+    /// that it hides are distributed as local ones are.
+    ///
+    /// Over MPC fields, [PCRandomness::rand] samples exactly this; but if the parties pass it
+    /// the same `rng`, the sum is no secret.
+    pub fn joint_rand<R: RngCore>(hiding_bound: usize, rng: &mut R) -> Self {
+        let ours = kzg10::Randomness::<F, DensePolynomial<F>>::rand(hiding_bound, false, None, rng);
+        kzg10::Randomness {
+            blinding_polynomial: DensePolynomial::from_coefficients_vec(
+                ours.blinding_polynomial
+                    .coeffs
+                    .into_iter()
+                    .map(MpcField::from_add_shared)
+                    .collect(),
+            ),
+            _field: PhantomData,
+        }
+    }
+}
+
 impl<E: PrimeField, S: FieldShare<E>> Reveal
     for Randomness<MpcField<E, S>, DensePolynomial<MpcField<E, S>>>
 {
@@ -189,3 +219,42 @@ impl<C: PairingEngine> MpcWire for Commitment<C> {
 impl<C: PairingEngine> MpcWire for kzg10::Commitment<C> {
     struct_mpc_wire_simp_impl!(kzg10::Commitment; 0);
 }
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ipa_pc::Commitment<MpcG1Affine<E, S>> {
+    type Base = ipa_pc::Commitment<E::G1Affine>;
+    struct_reveal_simp_impl!(ipa_pc::Commitment; comm, shifted_comm);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ipa_pc::Randomness<MpcG1Affine<E, S>> {
+    type Base = ipa_pc::Randomness<E::G1Affine>;
+    struct_reveal_simp_impl!(ipa_pc::Randomness; rand, shifted_rand);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ipa_pc::Proof<MpcG1Affine<E, S>> {
+    type Base = ipa_pc::Proof<E::G1Affine>;
+    struct_reveal_simp_impl!(ipa_pc::Proof; l_vec, r_vec, final_comm_key, c, hiding_comm, rand);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>> Reveal for ipa_pc::CommitterKey<MpcG1Affine<E, S>> {
+    type Base = ipa_pc::CommitterKey<E::G1Affine>;
+    struct_reveal_simp_impl!(ipa_pc::CommitterKey; comm_key, h, s, max_degree);
+}
+
+impl<E: PairingEngine, S: PairingShare<E>, D: Digest> Reveal
+    for BatchLCProof<
+        <MpcPairingEngine<E, S> as PairingEngine>::Fr,
+        DensePolynomial<<MpcPairingEngine<E, S> as PairingEngine>::Fr>,
+        InnerProductArgPC<
+            MpcG1Affine<E, S>,
+            D,
+            DensePolynomial<<MpcPairingEngine<E, S> as PairingEngine>::Fr>,
+        >,
+    >
+{
+    type Base = BatchLCProof<
+        <E as PairingEngine>::Fr,
+        DensePolynomial<<E as PairingEngine>::Fr>,
+        InnerProductArgPC<E::G1Affine, D, DensePolynomial<<E as PairingEngine>::Fr>>,
+    >;
+    struct_reveal_simp_impl!(BatchLCProof; proof, evals);
+}